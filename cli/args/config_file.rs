@@ -17,6 +17,7 @@ use deno_core::serde_json;
 use deno_core::serde_json::json;
 use deno_core::serde_json::Value;
 use deno_core::ModuleSpecifier;
+use deno_runtime::deno_wsi::event_loop::WsiControlFlow;
 use indexmap::IndexMap;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
@@ -461,6 +462,15 @@ pub struct BenchConfig {
   pub files: FilesConfig,
 }
 
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct WsiConfig {
+  pub enabled: bool,
+  pub control_flow: WsiControlFlow,
+  pub gpu_backend: Option<String>,
+  pub window: Option<Value>,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(untagged)]
 pub enum LockConfig {
@@ -481,6 +491,7 @@ pub struct ConfigFileJson {
   pub test: Option<Value>,
   pub bench: Option<Value>,
   pub lock: Option<Value>,
+  pub wsi: Option<Value>,
 }
 
 #[derive(Clone, Debug)]
@@ -836,6 +847,16 @@ impl ConfigFile {
       Ok(None)
     }
   }
+
+  pub fn to_wsi_config(&self) -> Result<Option<WsiConfig>, AnyError> {
+    if let Some(config) = self.json.wsi.clone() {
+      let wsi_config: WsiConfig = serde_json::from_value(config)
+        .context("Failed to parse \"wsi\" configuration")?;
+      Ok(Some(wsi_config))
+    } else {
+      Ok(None)
+    }
+  }
 }
 
 /// Represents the "default" type library that should be used when type
@@ -1135,6 +1156,35 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_parse_config_wsi() {
+    let config_text = r#"{
+      "wsi": {
+        "enabled": true,
+        "controlFlow": "poll",
+        "gpuBackend": "vulkan",
+        "window": {
+          "title": "Hello"
+        }
+      }
+    }"#;
+    let config_specifier =
+      ModuleSpecifier::parse("file:///deno/deno.json").unwrap();
+    let config_file = ConfigFile::new(config_text, &config_specifier).unwrap();
+    let wsi_config = config_file
+      .to_wsi_config()
+      .expect("error parsing wsi object")
+      .expect("wsi object should be defined");
+    assert!(wsi_config.enabled);
+    assert_eq!(wsi_config.control_flow, WsiControlFlow::Poll);
+    assert_eq!(wsi_config.gpu_backend, Some("vulkan".to_string()));
+    assert_eq!(wsi_config.window, Some(json!({ "title": "Hello" })));
+
+    let config_text = r#"{ "wsi": { "enable": true } }"#;
+    let config_file = ConfigFile::new(config_text, &config_specifier).unwrap();
+    assert!(config_file.to_wsi_config().is_err());
+  }
+
   #[test]
   fn test_parse_config_with_empty_file() {
     let config_text = "";
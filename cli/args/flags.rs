@@ -2,6 +2,7 @@
 // Copyright 2023 Jo Bates. All rights reserved. MIT license.
 
 use clap::Arg;
+use clap::ArgGroup;
 use clap::ArgMatches;
 use clap::ColorChoice;
 use clap::Command;
@@ -84,6 +85,10 @@ pub struct CompileFlags {
   pub output: Option<PathBuf>,
   pub args: Vec<String>,
   pub target: Option<String>,
+  pub icon: Option<PathBuf>,
+  pub product_name: Option<String>,
+  pub product_version: Option<String>,
+  pub url_schemes: Vec<String>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -323,6 +328,9 @@ pub struct Flags {
   pub allow_run: Option<Vec<String>>,
   pub allow_sys: Option<Vec<String>>,
   pub allow_write: Option<Vec<PathBuf>>,
+  pub allow_wsi: bool,
+  pub allow_wsi_devices: bool,
+  pub allow_wsi_global_input: bool,
   pub ca_stores: Option<Vec<String>>,
   pub ca_data: Option<CaData>,
   pub cache_blocklist: Vec<String>,
@@ -335,6 +343,7 @@ pub struct Flags {
   pub node_modules_dir: Option<bool>,
   pub coverage_dir: Option<String>,
   pub enable_testing_features: bool,
+  pub gpu_backend: Option<String>,
   pub ignore: Vec<PathBuf>,
   pub import_map_path: Option<String>,
   pub inspect_brk: Option<SocketAddr>,
@@ -357,6 +366,14 @@ pub struct Flags {
   pub watch: Option<Vec<PathBuf>>,
   pub no_clear_screen: bool,
   pub wsi: bool,
+  pub wsi_app_id: Option<String>,
+  pub wsi_assets: Option<PathBuf>,
+  pub wsi_backend: Option<String>,
+  pub wsi_crash_reports: bool,
+  pub wsi_headless: bool,
+  pub wsi_measure_latency: bool,
+  pub wsi_resilient: bool,
+  pub wsi_trace: bool,
 }
 
 fn join_paths(allowlist: &[PathBuf], d: &str) -> String {
@@ -473,6 +490,18 @@ impl Flags {
       args.push("--allow-hrtime".to_string());
     }
 
+    if self.allow_wsi {
+      args.push("--allow-wsi".to_string());
+    }
+
+    if self.allow_wsi_devices {
+      args.push("--allow-wsi-devices".to_string());
+    }
+
+    if self.allow_wsi_global_input {
+      args.push("--allow-wsi-global-input".to_string());
+    }
+
     args
   }
 
@@ -566,6 +595,9 @@ impl Flags {
       || self.allow_run.is_some()
       || self.allow_sys.is_some()
       || self.allow_write.is_some()
+      || self.allow_wsi
+      || self.allow_wsi_devices
+      || self.allow_wsi_global_input
   }
 
   pub fn has_permission_in_argv(&self) -> bool {
@@ -579,6 +611,9 @@ impl Flags {
         || arg.starts_with("--allow-run")
         || arg.starts_with("--allow-sys")
         || arg.starts_with("--allow-write")
+        || arg == "--allow-wsi"
+        || arg == "--allow-wsi-devices"
+        || arg == "--allow-wsi-global-input"
     })
   }
 }
@@ -602,6 +637,8 @@ static ENV_VARIABLES_HELP: &str = r#"ENVIRONMENT VARIABLES:
                          available
     DENO_V8_FLAGS        Set V8 command line options
     DENO_WEBGPU_TRACE    Directory to use for wgpu traces
+    DENOG_WSI_BACKEND    Window system to use on Linux and the BSDs.
+                         Possible values: "wayland", "x11".
     DENO_JOBS            Number of parallel workers used for the --parallel
                          flag with the test subcommand. Defaults to number
                          of available CPUs.
@@ -697,6 +734,9 @@ fn handle_repl_flags(flags: &mut Flags, repl_flags: ReplFlags) {
     flags.allow_write = Some(vec![]);
     flags.allow_ffi = Some(vec![]);
     flags.allow_hrtime = true;
+    flags.allow_wsi = true;
+    flags.allow_wsi_devices = true;
+    flags.allow_wsi_global_input = true;
   }
   flags.subcommand = DenoSubcommand::Repl(repl_flags);
 }
@@ -759,7 +799,7 @@ fn clap_root(version: &str) -> Command {
 }
 
 fn bench_subcommand<'a>() -> Command<'a> {
-  runtime_args(Command::new("bench"), false, true, false)
+  runtime_args(Command::new("bench"), true, true, false)
     .trailing_var_arg(true)
     .arg(
       Arg::new("json")
@@ -897,6 +937,8 @@ fn compile_subcommand<'a>() -> Command<'a> {
   runtime_args(Command::new("compile"), false, true, false)
     .trailing_var_arg(true)
     .arg(script_arg().required(true))
+    .arg(wsi_app_id_arg())
+    .arg(wsi_assets_arg())
     .arg(
       Arg::new("output")
         .long("output")
@@ -917,6 +959,55 @@ fn compile_subcommand<'a>() -> Command<'a> {
           "aarch64-apple-darwin",
         ]),
     )
+    .arg(
+      Arg::new("icon")
+        .long("icon")
+        .help("Application icon (a square PNG image)")
+        .takes_value(true)
+        .value_hint(ValueHint::FilePath),
+    )
+    .arg(
+      Arg::new("product-name")
+        .long("product-name")
+        .help("Application name shown by the operating system")
+        .takes_value(true),
+    )
+    .arg(
+      Arg::new("product-version")
+        .long("product-version")
+        .help("Application version, e.g. 1.2.3")
+        .takes_value(true)
+        .validator(|val: &str| {
+          let parts = Vec::from_iter(val.split('.'));
+          if parts.len() <= 4 && parts.iter().all(|p| p.parse::<u16>().is_ok())
+          {
+            Ok(())
+          } else {
+            Err(
+              "Product version should be up to four dot-separated numbers"
+                .to_string(),
+            )
+          }
+        }),
+    )
+    .arg(
+      Arg::new("url-scheme")
+        .long("url-scheme")
+        .help("URL scheme the application opens, e.g. myapp")
+        .value_name("SCHEME")
+        .takes_value(true)
+        .multiple_occurrences(true)
+        .validator(|val: &str| {
+          let mut chars = val.chars();
+          if chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+          {
+            Ok(())
+          } else {
+            Err(format!("'{val}' is not a valid URL scheme"))
+          }
+        }),
+    )
     .about("UNSTABLE: Compile the script into a self contained executable")
     .long_about(
       "UNSTABLE: Compiles the given script into a self contained executable.
@@ -938,6 +1029,15 @@ Cross-compiling to different target architectures is supported using the \
 `--target` flag. On the first invocation with deno will download proper \
 binary and cache it in $DENO_DIR. The aarch64-apple-darwin target is not \
 supported in canary.
+
+The '--icon', '--product-name' and '--product-version' flags make the \
+executable look like a native app. On Windows, they replace its icon and \
+version information. When targeting macOS with an output path ending in \
+'.app', an application bundle is emitted instead of a bare executable.
+
+The '--url-scheme' flag, which can be repeated, declares the URL schemes that \
+a macOS application bundle opens. Other platforms register URL schemes at \
+runtime with 'Deno.wsi.registerUrlScheme'.
 ",
     )
 }
@@ -1559,7 +1659,7 @@ Ignore linting a file by adding an ignore comment at the top of the file:
 }
 
 fn repl_subcommand<'a>() -> Command<'a> {
-  runtime_args(Command::new("repl"), false, true, true)
+  runtime_args(Command::new("repl"), true, true, true)
     .about("Read Eval Print Loop")
     .arg(
       Arg::new("eval-file")
@@ -1589,6 +1689,8 @@ fn run_subcommand<'a>() -> Command<'a> {
         .conflicts_with("inspect-brk"),
     )
     .arg(no_clear_screen_arg())
+    .arg(wsi_app_id_arg())
+    .arg(wsi_assets_arg())
     .trailing_var_arg(true)
     .arg(script_arg().required(true))
     .about("Run a JavaScript or TypeScript program")
@@ -1648,7 +1750,7 @@ fn task_subcommand<'a>() -> Command<'a> {
 }
 
 fn test_subcommand<'a>() -> Command<'a> {
-  runtime_args(Command::new("test"), false, true, true)
+  runtime_args(Command::new("test"), true, true, true)
     .trailing_var_arg(true)
     .arg(
       Arg::new("ignore")
@@ -1910,13 +2012,95 @@ fn compile_args_without_check_args(app: Command) -> Command {
 }
 
 fn wsi_args(app: Command) -> Command {
-  app.arg(
-    Arg::new("wsi")
-      .long("wsi")
-      .requires("unstable")
-      .conflicts_with("watch")
-      .help("UNSTABLE: Enable window system integration"),
-  )
+  app
+    // Flags that work with real or virtual windows require this group.
+    .group(ArgGroup::new("wsi-mode").args(&["wsi", "wsi-headless"]))
+    .arg(
+      Arg::new("wsi")
+        .long("wsi")
+        .requires("unstable")
+        .help("UNSTABLE: Enable window system integration"),
+    )
+    .arg(
+      Arg::new("wsi-headless")
+        .long("wsi-headless")
+        .requires("unstable")
+        .conflicts_with("wsi")
+        .help("UNSTABLE: Enable window system integration with virtual windows")
+        .long_help(
+          "UNSTABLE: Enable window system integration with virtual windows.
+No real windows are created and resize and redraw events are synthesized.
+GPU surfaces render to offscreen textures. Useful for smoke-testing windowed
+programs without a display server.",
+        ),
+    )
+    .arg(
+      Arg::new("wsi-backend")
+        .long("wsi-backend")
+        .requires("wsi")
+        .takes_value(true)
+        .require_equals(true)
+        .possible_values(["wayland", "x11"])
+        .help("UNSTABLE: Use the given window system on Linux and the BSDs")
+        .long_help(
+          "UNSTABLE: Use the given window system on Linux and the BSDs.
+Overrides the DENOG_WSI_BACKEND environment variable. By default Wayland is
+used when a compositor is running and X11 otherwise. Useful for falling back
+to XWayland when a Wayland compositor misbehaves.",
+        ),
+    )
+    .arg(
+      Arg::new("wsi-crash-reports")
+        .long("wsi-crash-reports")
+        .requires("wsi")
+        .help("UNSTABLE: Write minidumps of native crashes to DENO_DIR")
+        .long_help(
+          "UNSTABLE: Write minidumps of native crashes to DENO_DIR.
+A second process is started alongside the program to write a minidump when
+it crashes, e.g. in a GPU driver or the window system. The minidumps go to the
+crash_reports directory of DENO_DIR.",
+        ),
+    )
+    .arg(
+      Arg::new("wsi-resilient")
+        .long("wsi-resilient")
+        .requires("wsi")
+        .help(
+          "UNSTABLE: Report recoverable WSI errors as events instead of exiting",
+        )
+        .long_help(
+          "UNSTABLE: Report recoverable WSI errors as events instead of exiting.
+Failed window operations are delivered as \"recoverable-error\" events and
+lost or outdated GPU surfaces are reconfigured automatically. Intended for
+unattended (kiosk) deployments.",
+        ),
+    )
+    .arg(
+      Arg::new("wsi-measure-latency")
+        .long("wsi-measure-latency")
+        .requires("wsi-mode")
+        .help("UNSTABLE: Measure input latency for Deno.wsi.metrics()")
+        .long_help(
+          "UNSTABLE: Measure input latency for Deno.wsi.metrics().
+Input events are timestamped when the window system delivers them, when
+Deno.wsi.nextEvent returns them and when their window next presents a GPU
+surface. Percentile latencies are reported by Deno.wsi.metrics(). Virtual
+windows get no input, so with --wsi-headless there's nothing to measure.",
+        ),
+    )
+    .arg(
+      Arg::new("wsi-trace")
+        .long("wsi-trace")
+        .requires("wsi-mode")
+        .help("UNSTABLE: Log WSI events and requests to stderr as JSON")
+        .long_help(
+          "UNSTABLE: Log WSI events and requests to stderr as JSON.
+Every event the event loop delivers and every request it executes is written
+as a JSON object per line, with how long it waited and took, to debug a
+program that stalls waiting on the event loop. Deno.wsi.setTraceEnabled()
+turns it on and off at runtime.",
+        ),
+    )
 }
 
 fn permission_args(app: Command) -> Command {
@@ -2008,6 +2192,21 @@ fn permission_args(app: Command) -> Command {
         .long("allow-hrtime")
         .help("Allow high resolution time measurement"),
     )
+    .arg(
+      Arg::new("allow-wsi")
+        .long("allow-wsi")
+        .help("Allow creating windows and other window system access"),
+    )
+    .arg(
+      Arg::new("allow-wsi-devices")
+        .long("allow-wsi-devices")
+        .help("Allow receiving input device events regardless of focus"),
+    )
+    .arg(
+      Arg::new("allow-wsi-global-input")
+        .long("allow-wsi-global-input")
+        .help("Allow receiving the desktop's keyboard and mouse input"),
+    )
     .arg(
       Arg::new("allow-all")
         .short('A')
@@ -2048,6 +2247,7 @@ fn runtime_args(
     .arg(v8_flags_arg())
     .arg(seed_arg())
     .arg(enable_testing_features_arg())
+    .arg(gpu_backend_arg())
 }
 
 fn inspect_args(app: Command) -> Command {
@@ -2206,6 +2406,21 @@ fn seed_arg<'a>() -> Arg<'a> {
     })
 }
 
+fn gpu_backend_arg<'a>() -> Arg<'a> {
+  Arg::new("gpu-backend")
+    .long("gpu-backend")
+    .requires("unstable")
+    .takes_value(true)
+    .require_equals(true)
+    .possible_values(["vulkan", "metal", "dx12", "gl"])
+    .help("UNSTABLE: Use the given WebGPU backend")
+    .long_help(
+      "UNSTABLE: Use the given WebGPU backend.
+Overrides the DENO_WEBGPU_BACKEND environment variable. Useful for working
+around driver bugs and for testing across backends.",
+    )
+}
+
 fn watch_arg<'a>(takes_files: bool) -> Arg<'a> {
   let arg = Arg::new("watch")
     .long("watch")
@@ -2239,6 +2454,36 @@ fn no_clear_screen_arg<'a>() -> Arg<'a> {
     .help("Do not clear terminal screen when under watch mode")
 }
 
+fn wsi_app_id_arg<'a>() -> Arg<'a> {
+  Arg::new("wsi-app-id")
+    .long("wsi-app-id")
+    .requires("unstable")
+    .takes_value(true)
+    .value_name("ID")
+    .help("UNSTABLE: Application ID of the program's windows on Linux")
+    .long_help(
+      "UNSTABLE: Application ID of the program's windows on Linux.
+Sets the Wayland app_id and the X11 WM_CLASS of every window, so desktops can
+match them to the program's .desktop file for their icon and taskbar grouping.
+The ID should be the name of the .desktop file without the extension.",
+    )
+}
+
+fn wsi_assets_arg<'a>() -> Arg<'a> {
+  Arg::new("wsi-assets")
+    .long("wsi-assets")
+    .requires("unstable")
+    .takes_value(true)
+    .value_name("DIR")
+    .help("UNSTABLE: Directory of assets to serve from Deno.wsi.assets")
+    .long_help(
+      "UNSTABLE: Directory of assets to serve from Deno.wsi.assets.
+With 'denog compile', the files are embedded in the executable, so it can be
+shipped as a single file.",
+    )
+    .value_hint(ValueHint::DirPath)
+}
+
 fn no_check_arg<'a>() -> Arg<'a> {
   Arg::new("no-check")
     .takes_value(true)
@@ -2381,7 +2626,7 @@ fn unsafely_ignore_certificate_errors_arg<'a>() -> Arg<'a> {
 fn bench_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   flags.type_check_mode = TypeCheckMode::Local;
 
-  runtime_args_parse(flags, matches, false, true, false);
+  runtime_args_parse(flags, matches, true, true, false);
 
   // NOTE: `deno bench` always uses `--no-prompt`, tests shouldn't ever do
   // interactive prompts, unless done by user code
@@ -2476,6 +2721,8 @@ fn check_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
 fn compile_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   flags.type_check_mode = TypeCheckMode::Local;
   runtime_args_parse(flags, matches, false, true, false);
+  wsi_app_id_arg_parse(flags, matches);
+  wsi_assets_arg_parse(flags, matches);
 
   let mut script: Vec<String> = matches
     .values_of("script_arg")
@@ -2487,12 +2734,23 @@ fn compile_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   let source_file = script[0].to_string();
   let output = matches.value_of("output").map(PathBuf::from);
   let target = matches.value_of("target").map(String::from);
+  let icon = matches.value_of("icon").map(PathBuf::from);
+  let product_name = matches.value_of("product-name").map(String::from);
+  let product_version = matches.value_of("product-version").map(String::from);
+  let url_schemes = match matches.values_of("url-scheme") {
+    Some(f) => f.map(String::from).collect(),
+    None => vec![],
+  };
 
   flags.subcommand = DenoSubcommand::Compile(CompileFlags {
     source_file,
     output,
     args,
     target,
+    icon,
+    product_name,
+    product_version,
+    url_schemes,
   });
 }
 
@@ -2595,6 +2853,9 @@ fn eval_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   flags.allow_write = Some(vec![]);
   flags.allow_ffi = Some(vec![]);
   flags.allow_hrtime = true;
+  flags.allow_wsi = true;
+  flags.allow_wsi_devices = true;
+  flags.allow_wsi_global_input = true;
   // TODO(@satyarohith): remove this flag in 2.0.
   let as_typescript = matches.is_present("ts");
   let ext = if as_typescript {
@@ -2776,7 +3037,7 @@ fn lint_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
 }
 
 fn repl_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
-  runtime_args_parse(flags, matches, false, true, true);
+  runtime_args_parse(flags, matches, true, true, true);
   unsafely_ignore_certificate_errors_parse(flags, matches);
 
   let eval_files: Option<Vec<String>> = matches
@@ -2809,6 +3070,8 @@ fn run_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   }
 
   watch_arg_parse(flags, matches, true);
+  wsi_app_id_arg_parse(flags, matches);
+  wsi_assets_arg_parse(flags, matches);
   flags.subcommand = DenoSubcommand::Run(RunFlags { script });
 }
 
@@ -2876,7 +3139,7 @@ fn task_parse(
 
 fn test_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   flags.type_check_mode = TypeCheckMode::Local;
-  runtime_args_parse(flags, matches, false, true, true);
+  runtime_args_parse(flags, matches, true, true, true);
   // NOTE: `deno test` always uses `--no-prompt`, tests shouldn't ever do
   // interactive prompts, unless done by user code
   flags.no_prompt = true;
@@ -3042,6 +3305,30 @@ fn wsi_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   if matches.is_present("wsi") {
     flags.wsi = true;
   }
+  flags.wsi_backend = matches.value_of("wsi-backend").map(String::from);
+  if matches.is_present("wsi-crash-reports") {
+    flags.wsi_crash_reports = true;
+  }
+  if matches.is_present("wsi-headless") {
+    flags.wsi_headless = true;
+  }
+  if matches.is_present("wsi-measure-latency") {
+    flags.wsi_measure_latency = true;
+  }
+  if matches.is_present("wsi-resilient") {
+    flags.wsi_resilient = true;
+  }
+  if matches.is_present("wsi-trace") {
+    flags.wsi_trace = true;
+  }
+}
+
+fn wsi_app_id_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  flags.wsi_app_id = matches.value_of("wsi-app-id").map(String::from);
+}
+
+fn wsi_assets_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  flags.wsi_assets = matches.value_of("wsi-assets").map(PathBuf::from);
 }
 
 fn permission_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
@@ -3098,6 +3385,18 @@ fn permission_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   if matches.is_present("allow-hrtime") {
     flags.allow_hrtime = true;
   }
+
+  if matches.is_present("allow-wsi") {
+    flags.allow_wsi = true;
+  }
+
+  if matches.is_present("allow-wsi-devices") {
+    flags.allow_wsi_devices = true;
+  }
+
+  if matches.is_present("allow-wsi-global-input") {
+    flags.allow_wsi_global_input = true;
+  }
   if matches.is_present("allow-all") {
     flags.allow_all = true;
     flags.allow_read = Some(vec![]);
@@ -3108,6 +3407,9 @@ fn permission_args_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     flags.allow_sys = Some(vec![]);
     flags.allow_ffi = Some(vec![]);
     flags.allow_hrtime = true;
+    flags.allow_wsi = true;
+    flags.allow_wsi_devices = true;
+    flags.allow_wsi_global_input = true;
   }
   if matches.is_present("no-prompt") {
     flags.no_prompt = true;
@@ -3146,6 +3448,7 @@ fn runtime_args_parse(
   v8_flags_arg_parse(flags, matches);
   seed_arg_parse(flags, matches);
   enable_testing_features_arg_parse(flags, matches);
+  gpu_backend_arg_parse(flags, matches);
 }
 
 fn inspect_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
@@ -3225,6 +3528,10 @@ fn location_arg_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     .map(|href| Url::parse(href).unwrap());
 }
 
+fn gpu_backend_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
+  flags.gpu_backend = matches.value_of("gpu-backend").map(String::from);
+}
+
 fn v8_flags_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
   if let Some(v8_flags) = matches.values_of("v8-flags") {
     flags.v8_flags = v8_flags.map(String::from).collect();
@@ -3424,104 +3731,505 @@ mod tests {
         ..Flags::default()
       }
     );
-  }
+  }
+
+  #[test]
+  fn version() {
+    let r = flags_from_vec(svec!["deno", "--version"]);
+    assert_eq!(r.unwrap_err().kind(), clap::ErrorKind::DisplayVersion);
+    let r = flags_from_vec(svec!["deno", "-V"]);
+    assert_eq!(r.unwrap_err().kind(), clap::ErrorKind::DisplayVersion);
+  }
+
+  #[test]
+  fn run_reload() {
+    let r = flags_from_vec(svec!["deno", "run", "-r", "script.ts"]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        reload: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_watch() {
+    let r = flags_from_vec(svec!["deno", "run", "--watch", "script.ts"]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        watch: Some(vec![]),
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_watch_with_external() {
+    let r =
+      flags_from_vec(svec!["deno", "run", "--watch=file1,file2", "script.ts"]);
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        watch: Some(vec![PathBuf::from("file1"), PathBuf::from("file2")]),
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_watch_with_no_clear_screen() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--watch",
+      "--no-clear-screen",
+      "script.ts"
+    ]);
+
+    let flags = r.unwrap();
+    assert_eq!(
+      flags,
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        watch: Some(vec![]),
+        no_clear_screen: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_reload_allow_write() {
+    let r =
+      flags_from_vec(svec!["deno", "run", "-r", "--allow-write", "script.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        reload: true,
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        allow_write: Some(vec![]),
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_wsi() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi",
+      "--wsi-resilient",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        unstable: true,
+        wsi: true,
+        wsi_resilient: true,
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec(svec!["deno", "run", "--wsi-resilient", "script.ts"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn run_wsi_measure_latency() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi",
+      "--wsi-measure-latency",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        unstable: true,
+        wsi: true,
+        wsi_measure_latency: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi-headless",
+      "--wsi-measure-latency",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        unstable: true,
+        wsi_headless: true,
+        wsi_measure_latency: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi-measure-latency",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn run_wsi_watch() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi",
+      "--watch",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        unstable: true,
+        wsi: true,
+        watch: Some(vec![]),
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn run_wsi_backend() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi",
+      "--wsi-backend=x11",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        unstable: true,
+        wsi: true,
+        wsi_backend: Some("x11".to_string()),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi",
+      "--wsi-backend=win32",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi-backend=wayland",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn run_wsi_crash_reports() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi",
+      "--wsi-crash-reports",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        unstable: true,
+        wsi: true,
+        wsi_crash_reports: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi-crash-reports",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn run_wsi_trace() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi",
+      "--wsi-trace",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        unstable: true,
+        wsi: true,
+        wsi_trace: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi-headless",
+      "--wsi-trace",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "script.ts".to_string(),
+        }),
+        unstable: true,
+        wsi_headless: true,
+        wsi_trace: true,
+        ..Flags::default()
+      }
+    );
 
-  #[test]
-  fn version() {
-    let r = flags_from_vec(svec!["deno", "--version"]);
-    assert_eq!(r.unwrap_err().kind(), clap::ErrorKind::DisplayVersion);
-    let r = flags_from_vec(svec!["deno", "-V"]);
-    assert_eq!(r.unwrap_err().kind(), clap::ErrorKind::DisplayVersion);
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi-trace",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
   }
 
   #[test]
-  fn run_reload() {
-    let r = flags_from_vec(svec!["deno", "run", "-r", "script.ts"]);
-    let flags = r.unwrap();
+  fn run_gpu_backend() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--gpu-backend=vulkan",
+      "script.ts"
+    ]);
     assert_eq!(
-      flags,
+      r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run(RunFlags {
           script: "script.ts".to_string(),
         }),
-        reload: true,
+        unstable: true,
+        gpu_backend: Some("vulkan".to_string()),
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--gpu-backend=webgl",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
   }
 
   #[test]
-  fn run_watch() {
-    let r = flags_from_vec(svec!["deno", "run", "--watch", "script.ts"]);
-    let flags = r.unwrap();
+  fn run_wsi_app_id() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi-app-id",
+      "org.example.Game",
+      "script.ts"
+    ]);
     assert_eq!(
-      flags,
+      r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run(RunFlags {
           script: "script.ts".to_string(),
         }),
-        watch: Some(vec![]),
+        unstable: true,
+        wsi_app_id: Some("org.example.Game".to_string()),
         ..Flags::default()
       }
     );
-  }
 
-  #[test]
-  fn run_watch_with_external() {
-    let r =
-      flags_from_vec(svec!["deno", "run", "--watch=file1,file2", "script.ts"]);
-    let flags = r.unwrap();
+    let r = flags_from_vec(svec![
+      "deno",
+      "compile",
+      "--unstable",
+      "--wsi-app-id",
+      "org.example.Game",
+      "script.ts"
+    ]);
     assert_eq!(
-      flags,
+      r.unwrap(),
       Flags {
-        subcommand: DenoSubcommand::Run(RunFlags {
-          script: "script.ts".to_string(),
+        subcommand: DenoSubcommand::Compile(CompileFlags {
+          source_file: "script.ts".to_string(),
+          output: None,
+          args: vec![],
+          target: None,
+          icon: None,
+          product_name: None,
+          product_version: None,
+          url_schemes: vec![],
         }),
-        watch: Some(vec![PathBuf::from("file1"), PathBuf::from("file2")]),
+        type_check_mode: TypeCheckMode::Local,
+        unstable: true,
+        wsi_app_id: Some("org.example.Game".to_string()),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn run_watch_with_no_clear_screen() {
+  fn run_wsi_assets() {
     let r = flags_from_vec(svec![
       "deno",
       "run",
-      "--watch",
-      "--no-clear-screen",
+      "--unstable",
+      "--wsi-assets",
+      "assets",
       "script.ts"
     ]);
-
-    let flags = r.unwrap();
     assert_eq!(
-      flags,
+      r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Run(RunFlags {
           script: "script.ts".to_string(),
         }),
-        watch: Some(vec![]),
-        no_clear_screen: true,
+        unstable: true,
+        wsi_assets: Some(PathBuf::from("assets")),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "compile",
+      "--unstable",
+      "--wsi-assets",
+      "assets",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Compile(CompileFlags {
+          source_file: "script.ts".to_string(),
+          output: None,
+          args: vec![],
+          target: None,
+          icon: None,
+          product_name: None,
+          product_version: None,
+          url_schemes: vec![],
+        }),
+        type_check_mode: TypeCheckMode::Local,
+        unstable: true,
+        wsi_assets: Some(PathBuf::from("assets")),
         ..Flags::default()
       }
     );
   }
 
   #[test]
-  fn run_reload_allow_write() {
-    let r =
-      flags_from_vec(svec!["deno", "run", "-r", "--allow-write", "script.ts"]);
+  fn run_wsi_headless() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi-headless",
+      "script.ts"
+    ]);
     assert_eq!(
       r.unwrap(),
       Flags {
-        reload: true,
         subcommand: DenoSubcommand::Run(RunFlags {
           script: "script.ts".to_string(),
         }),
-        allow_write: Some(vec![]),
+        unstable: true,
+        wsi_headless: true,
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--wsi",
+      "--wsi-headless",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -3615,6 +4323,9 @@ mod tests {
         allow_write: Some(vec![]),
         allow_ffi: Some(vec![]),
         allow_hrtime: true,
+        allow_wsi: true,
+        allow_wsi_devices: true,
+        allow_wsi_global_input: true,
         ..Flags::default()
       }
     );
@@ -3650,6 +4361,57 @@ mod tests {
     );
   }
 
+  #[test]
+  fn allow_wsi() {
+    let r = flags_from_vec(svec!["deno", "run", "--allow-wsi", "gist.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "gist.ts".to_string(),
+        }),
+        allow_wsi: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_wsi_devices() {
+    let r =
+      flags_from_vec(svec!["deno", "run", "--allow-wsi-devices", "gist.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "gist.ts".to_string(),
+        }),
+        allow_wsi_devices: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn allow_wsi_global_input() {
+    let r = flags_from_vec(svec![
+      "deno",
+      "run",
+      "--allow-wsi-global-input",
+      "gist.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run(RunFlags {
+          script: "gist.ts".to_string(),
+        }),
+        allow_wsi_global_input: true,
+        ..Flags::default()
+      }
+    );
+  }
+
   #[test]
   fn double_hyphen() {
     // notice that flags passed after double dash will not
@@ -4363,6 +5125,9 @@ mod tests {
         allow_write: Some(vec![]),
         allow_ffi: Some(vec![]),
         allow_hrtime: true,
+        allow_wsi: true,
+        allow_wsi_devices: true,
+        allow_wsi_global_input: true,
         ..Flags::default()
       }
     );
@@ -4387,6 +5152,9 @@ mod tests {
         allow_write: Some(vec![]),
         allow_ffi: Some(vec![]),
         allow_hrtime: true,
+        allow_wsi: true,
+        allow_wsi_devices: true,
+        allow_wsi_global_input: true,
         ..Flags::default()
       }
     );
@@ -4412,6 +5180,9 @@ mod tests {
         allow_write: Some(vec![]),
         allow_ffi: Some(vec![]),
         allow_hrtime: true,
+        allow_wsi: true,
+        allow_wsi_devices: true,
+        allow_wsi_global_input: true,
         ..Flags::default()
       }
     );
@@ -4450,6 +5221,9 @@ mod tests {
         allow_write: Some(vec![]),
         allow_ffi: Some(vec![]),
         allow_hrtime: true,
+        allow_wsi: true,
+        allow_wsi_devices: true,
+        allow_wsi_global_input: true,
         ..Flags::default()
       }
     );
@@ -4481,6 +5255,9 @@ mod tests {
         allow_write: Some(vec![]),
         allow_ffi: Some(vec![]),
         allow_hrtime: true,
+        allow_wsi: true,
+        allow_wsi_devices: true,
+        allow_wsi_global_input: true,
         ..Flags::default()
       }
     );
@@ -4506,6 +5283,9 @@ mod tests {
         allow_write: Some(vec![]),
         allow_ffi: Some(vec![]),
         allow_hrtime: true,
+        allow_wsi: true,
+        allow_wsi_devices: true,
+        allow_wsi_global_input: true,
         ..Flags::default()
       }
     );
@@ -4545,12 +5325,37 @@ mod tests {
         allow_write: Some(vec![]),
         allow_ffi: Some(vec![]),
         allow_hrtime: true,
+        allow_wsi: true,
+        allow_wsi_devices: true,
+        allow_wsi_global_input: true,
         unsafely_ignore_certificate_errors: Some(vec![]),
         ..Flags::default()
       }
     );
   }
 
+  #[test]
+  fn repl_wsi() {
+    let r = flags_from_vec(svec!["deno", "repl", "--unstable", "--wsi"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Repl(ReplFlags {
+          eval_files: None,
+          eval: None,
+          is_default_command: false,
+        }),
+        unstable: true,
+        wsi: true,
+        type_check_mode: TypeCheckMode::None,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec(svec!["deno", "repl", "--wsi"]);
+    assert!(r.is_err());
+  }
+
   #[test]
   fn repl_with_eval_flag() {
     #[rustfmt::skip]
@@ -5847,6 +6652,35 @@ mod tests {
     assert!(r.is_err());
   }
 
+  #[test]
+  fn test_wsi() {
+    let r = flags_from_vec(svec!["deno", "test", "--unstable", "--wsi"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test(TestFlags {
+          no_run: false,
+          doc: false,
+          fail_fast: None,
+          filter: None,
+          allow_none: false,
+          shuffle: None,
+          files: FileFlags {
+            include: vec![],
+            ignore: vec![],
+          },
+          concurrent_jobs: None,
+          trace_ops: false,
+        }),
+        unstable: true,
+        wsi: true,
+        type_check_mode: TypeCheckMode::Local,
+        no_prompt: true,
+        ..Flags::default()
+      }
+    );
+  }
+
   #[test]
   fn test_with_fail_fast() {
     let r = flags_from_vec(svec!["deno", "test", "--fail-fast=3"]);
@@ -6257,6 +7091,10 @@ mod tests {
           output: None,
           args: vec![],
           target: None,
+          icon: None,
+          product_name: None,
+          product_version: None,
+          url_schemes: vec![],
         }),
         type_check_mode: TypeCheckMode::Local,
         ..Flags::default()
@@ -6276,6 +7114,10 @@ mod tests {
           output: Some(PathBuf::from("colors")),
           args: svec!["foo", "bar"],
           target: None,
+          icon: None,
+          product_name: None,
+          product_version: None,
+          url_schemes: vec![],
         }),
         import_map_path: Some("import_map.json".to_string()),
         no_remote: true,
@@ -6297,6 +7139,37 @@ mod tests {
     );
   }
 
+  #[test]
+  fn compile_with_packaging_flags() {
+    #[rustfmt::skip]
+    let r = flags_from_vec(svec!["deno", "compile", "--icon", "icon.png", "--product-name", "Colors", "--product-version", "1.2.3", "--url-scheme", "colors", "--url-scheme", "x-colors", "--output", "colors.app", "https://deno.land/std/examples/colors.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Compile(CompileFlags {
+          source_file: "https://deno.land/std/examples/colors.ts".to_string(),
+          output: Some(PathBuf::from("colors.app")),
+          args: vec![],
+          target: None,
+          icon: Some(PathBuf::from("icon.png")),
+          product_name: Some("Colors".to_string()),
+          product_version: Some("1.2.3".to_string()),
+          url_schemes: svec!["colors", "x-colors"],
+        }),
+        type_check_mode: TypeCheckMode::Local,
+        ..Flags::default()
+      }
+    );
+
+    #[rustfmt::skip]
+    let r = flags_from_vec(svec!["deno", "compile", "--product-version", "1.2.3-beta", "https://deno.land/std/examples/colors.ts"]);
+    assert!(r.is_err());
+
+    #[rustfmt::skip]
+    let r = flags_from_vec(svec!["deno", "compile", "--url-scheme", "my_app", "https://deno.land/std/examples/colors.ts"]);
+    assert!(r.is_err());
+  }
+
   #[test]
   fn coverage() {
     let r = flags_from_vec(svec!["deno", "coverage", "foo.json"]);
@@ -6691,6 +7564,29 @@ mod tests {
     );
   }
 
+  #[test]
+  fn bench_wsi() {
+    let r = flags_from_vec(svec!["deno", "bench", "--unstable", "--wsi"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Bench(BenchFlags {
+          filter: None,
+          json: false,
+          files: FileFlags {
+            include: vec![],
+            ignore: vec![],
+          },
+        }),
+        unstable: true,
+        wsi: true,
+        type_check_mode: TypeCheckMode::Local,
+        no_prompt: true,
+        ..Flags::default()
+      }
+    );
+  }
+
   #[test]
   fn bench_watch() {
     let r = flags_from_vec(svec!["deno", "bench", "--watch"]);
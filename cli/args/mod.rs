@@ -27,6 +27,7 @@ pub use config_file::TsConfig;
 pub use config_file::TsConfigForEmit;
 pub use config_file::TsConfigType;
 pub use config_file::TsTypeLib;
+pub use config_file::WsiConfig;
 pub use flags::*;
 pub use lockfile::Lockfile;
 pub use lockfile::LockfileError;
@@ -47,6 +48,10 @@ use deno_runtime::deno_tls::rustls::RootCertStore;
 use deno_runtime::deno_tls::rustls_native_certs::load_native_certs;
 use deno_runtime::deno_tls::rustls_pemfile;
 use deno_runtime::deno_tls::webpki_roots;
+use deno_runtime::deno_wsi;
+use deno_runtime::deno_wsi::assets::WsiAssets;
+use deno_runtime::deno_wsi::event_loop::WsiUnixBackend;
+use deno_runtime::deno_wsi::WsiOptions;
 use deno_runtime::inspector_server::InspectorServer;
 use deno_runtime::permissions::PermissionsOptions;
 use once_cell::sync::Lazy;
@@ -1000,6 +1005,9 @@ impl CliOptions {
       allow_run: self.flags.allow_run.clone(),
       allow_sys: self.flags.allow_sys.clone(),
       allow_write: self.flags.allow_write.clone(),
+      allow_wsi: self.flags.allow_wsi,
+      allow_wsi_devices: self.flags.allow_wsi_devices,
+      allow_wsi_global_input: self.flags.allow_wsi_global_input,
       prompt: !self.no_prompt(),
     }
   }
@@ -1046,6 +1054,32 @@ impl CliOptions {
     &self.flags.v8_flags
   }
 
+  pub fn gpu_backend(&self) -> Option<String> {
+    self.flags.gpu_backend.clone()
+  }
+
+  pub fn wsi_options(&self) -> Result<WsiOptions, AnyError> {
+    let wsi_config = match &self.maybe_config_file {
+      Some(config_file) => config_file.to_wsi_config()?.unwrap_or_default(),
+      None => WsiConfig::default(),
+    };
+    Ok(WsiOptions {
+      headless: self.flags.wsi_headless,
+      measure_latency: self.flags.wsi_measure_latency,
+      resilient: self.flags.wsi_resilient,
+      trace: self.flags.wsi_trace,
+      hot_reload: self.flags.watch.is_some(),
+      app_id: self.flags.wsi_app_id.clone(),
+      window_defaults: wsi_config.window,
+      assets: self
+        .flags
+        .wsi_assets
+        .as_ref()
+        .map(|dir| WsiAssets::Directory(self.initial_cwd.join(dir))),
+      ..Default::default()
+    })
+  }
+
   pub fn watch_paths(&self) -> &Option<Vec<PathBuf>> {
     &self.flags.watch
   }
@@ -1160,6 +1194,62 @@ fn resolve_files(
   result
 }
 
+/// Applies the "wsi" section of the configuration file to the flags and returns
+/// it. Unlike the rest of the configuration, this has to be resolved before the
+/// runtime starts, because WSI takes over the main thread for its event loop.
+pub fn resolve_wsi_config(flags: &mut Flags) -> Result<WsiConfig, AnyError> {
+  // Windowed tests and benches fall back to virtual windows where there's no
+  // display server to create real ones on.
+  if matches!(
+    flags.subcommand,
+    DenoSubcommand::Test(_) | DenoSubcommand::Bench(_)
+  ) && flags.wsi
+    && !deno_wsi::testing::display_available()
+  {
+    flags.wsi = false;
+    flags.wsi_headless = true;
+  }
+  if !matches!(flags.subcommand, DenoSubcommand::Run(_)) {
+    return Ok(WsiConfig::default());
+  }
+  let cwd = env::current_dir()?;
+  let wsi_config = match ConfigFile::discover(flags, &cwd)? {
+    Some(config_file) => config_file.to_wsi_config()?.unwrap_or_default(),
+    None => return Ok(WsiConfig::default()),
+  };
+  if wsi_config.enabled && !flags.wsi && !flags.wsi_headless {
+    if !flags.unstable {
+      bail!(
+        "Enabling WSI in the configuration file requires the --unstable flag."
+      );
+    }
+    flags.wsi = true;
+  }
+  // The --gpu-backend flag and the DENO_WEBGPU_BACKEND environment variable
+  // take precedence over the config file.
+  if flags.gpu_backend.is_none() && env::var_os("DENO_WEBGPU_BACKEND").is_none()
+  {
+    flags.gpu_backend = wsi_config.gpu_backend.clone();
+  }
+  Ok(wsi_config)
+}
+
+/// Resolves the window system that WSI should use on Linux and the BSDs. The
+/// --wsi-backend flag takes precedence over the DENOG_WSI_BACKEND environment
+/// variable, and without either winit picks one.
+pub fn resolve_wsi_backend(
+  flags: &Flags,
+) -> Result<Option<WsiUnixBackend>, AnyError> {
+  let backend = match &flags.wsi_backend {
+    Some(backend) => backend.clone(),
+    None => match env::var("DENOG_WSI_BACKEND") {
+      Ok(backend) if !backend.is_empty() => backend,
+      _ => return Ok(None),
+    },
+  };
+  Ok(Some(backend.parse()?))
+}
+
 /// Resolves the no_prompt value based on the cli flags and environment.
 pub fn resolve_no_prompt(flags: &Flags) -> bool {
   flags.no_prompt || has_flag_env_var("DENO_NO_PROMPT")
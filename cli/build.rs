@@ -324,7 +324,7 @@ fn create_cli_snapshot(snapshot_path: PathBuf) {
     deno_websocket::init_ops::<PermissionsContainer>("".to_owned(), None, None),
     deno_webstorage::init_ops(None),
     deno_crypto::init_ops(None),
-    deno_webgpu::init_ops(false),
+    deno_webgpu::init_ops::<PermissionsContainer>(false, None),
     deno_broadcast_channel::init_ops(
       deno_broadcast_channel::InMemoryBroadcastChannel::default(),
       false, // No --unstable.
@@ -341,7 +341,7 @@ fn create_cli_snapshot(snapshot_path: PathBuf) {
     deno_napi::init_ops::<PermissionsContainer>(),
     deno_http::init_ops(),
     deno_flash::init_ops::<PermissionsContainer>(false), // No --unstable
-    deno_wsi::init_ops(None),
+    deno_wsi::init_ops::<PermissionsContainer>(None, Default::default()),
   ];
 
   let mut esm_files = include_js_files!(
@@ -483,6 +483,7 @@ fn main() {
   {
     let mut res = winres::WindowsResource::new();
     res.set_icon("deno.ico");
+    res.set_manifest_file("denog.manifest");
     res.set_language(winapi::um::winnt::MAKELANGID(
       winapi::um::winnt::LANG_ENGLISH,
       winapi::um::winnt::SUBLANG_ENGLISH_US,
@@ -98,6 +98,11 @@ impl DenoDir {
     self.root.join("location_data")
   }
 
+  /// Folder where native crashes of WSI programs are written as minidumps.
+  pub fn crash_reports_folder_path(&self) -> PathBuf {
+    self.root.join("crash_reports")
+  }
+
   /// File used for the upgrade checker.
   pub fn upgrade_check_file_path(&self) -> PathBuf {
     self.root.join("latest.txt")
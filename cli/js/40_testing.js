@@ -7,6 +7,10 @@ import { setExitHandler } from "ext:runtime/30_os.js";
 import { Console } from "ext:deno_console/02_console.js";
 import { serializePermissions } from "ext:runtime/10_permissions.js";
 import { assert } from "ext:deno_web/00_infra.js";
+import {
+  destroyWindowsOpenedSince,
+  openWindows,
+} from "ext:deno_wsi/01_wsi.js";
 const primordials = globalThis.__bootstrap.primordials;
 const {
   ArrayFrom,
@@ -441,6 +445,21 @@ function assertExit(fn, isTest) {
   };
 }
 
+// Wrap test function so that the windows it leaves open are destroyed when
+// it's done, before the sanitizers look for leaked resources like their GPU
+// surfaces.
+function destroyLeftoverWindows(fn) {
+  /** @param desc {TestDescription | TestStepDescription} */
+  return async function windowCleanup(desc) {
+    const before = openWindows();
+    try {
+      await fn(desc);
+    } finally {
+      destroyWindowsOpenedSince(before);
+    }
+  };
+}
+
 function assertTestStepScopes(fn) {
   /** @param desc {TestDescription | TestStepDescription} */
   return async function testStepSanitizer(desc) {
@@ -600,6 +619,7 @@ function withPermissions(fn, permissions) {
  *   only: boolean.
  *   sanitizeExit: boolean,
  *   permissions: PermissionOptions,
+ *   frames: boolean,
  * }} BenchDescription
  */
 
@@ -704,7 +724,10 @@ function test(
 
   // Delete this prop in case the user passed it. It's used to detect steps.
   delete testDesc.parent;
-  testDesc.fn = wrapTestFnWithSanitizers(testDesc.fn, testDesc);
+  testDesc.fn = wrapTestFnWithSanitizers(
+    destroyLeftoverWindows(testDesc.fn),
+    testDesc,
+  );
   if (testDesc.permissions) {
     testDesc.fn = withPermissions(
       testDesc.fn,
@@ -748,6 +771,7 @@ function bench(
     only: false,
     sanitizeExit: true,
     permissions: null,
+    frames: false,
   };
 
   if (typeof nameOrFnOrOptions === "string") {
@@ -992,6 +1016,89 @@ async function benchMeasure(timeBudget, desc) {
   return benchStats(n, wavg > lowPrecisionThresholdInNs, avg, min, max, all);
 }
 
+// Frames that a frame bench renders before its frames are measured, e.g.
+// while pipelines are compiled, and how many it's measured for at least.
+const FRAME_BENCH_WARMUP_FRAMES = 10;
+const FRAME_BENCH_MIN_FRAMES = 10;
+
+/** @param all {number[]} */
+function measurementStats(all) {
+  ArrayPrototypeSort(all, compareMeasurements);
+  let sum = 0;
+  for (const measurement of new SafeArrayIterator(all)) {
+    sum += measurement;
+  }
+  return benchStats(all.length, true, sum, all[0], all[all.length - 1], all);
+}
+
+/**
+ * Measure a bench with `frames: true`. Its function is called once with a
+ * `BenchFrames` context and renders frames in a loop of its own, marking where
+ * each ends, until the context has measured enough of them.
+ * @param {number} timeBudget
+ * @param {BenchDescription} desc
+ */
+async function benchFrames(timeBudget, desc) {
+  const frameTimes = [];
+  const gpuTimes = [];
+  let warmupFrames = FRAME_BENCH_WARMUP_FRAMES;
+  let budget = timeBudget * 1e6;
+  let frameStart = null;
+
+  await desc.fn({
+    [SymbolToStringTag]: "BenchFrames",
+    /**
+     * Marks the end of a frame and the start of the next one. Returns `false`
+     * once enough frames were measured.
+     * @returns {boolean}
+     */
+    frame() {
+      const now = benchNow();
+      if (frameStart !== null) {
+        const frameTime = now - frameStart;
+        if (warmupFrames > 0) {
+          warmupFrames--;
+        } else {
+          ArrayPrototypePush(frameTimes, frameTime);
+          budget -= frameTime;
+        }
+      }
+      frameStart = now;
+      return warmupFrames > 0 || budget > 0 ||
+        frameTimes.length < FRAME_BENCH_MIN_FRAMES;
+    },
+    /**
+     * Records how long the GPU took for a frame, as the top-level scopes of a
+     * `GPUProfiler`. The results usually arrive a few frames late, so they're
+     * measured separately from the frame times.
+     * @param {GPUProfilerScopeResult[]} results
+     */
+    addGpuTimings(results) {
+      if (warmupFrames > 0) {
+        return;
+      }
+      let milliseconds = 0;
+      for (const result of new SafeArrayIterator(results)) {
+        if (result.depth === 0) {
+          milliseconds += result.milliseconds;
+        }
+      }
+      ArrayPrototypePush(gpuTimes, milliseconds * 1e6);
+    },
+  });
+
+  if (frameTimes.length === 0) {
+    throw new Error(
+      "The frame bench didn't render any frames after warming up. Call frames.frame() after each frame until it returns false.",
+    );
+  }
+  const stats = measurementStats(frameTimes);
+  if (gpuTimes.length > 0) {
+    stats.gpu = measurementStats(gpuTimes);
+  }
+  return stats;
+}
+
 async function runBench(desc) {
   let token = null;
 
@@ -1010,7 +1117,9 @@ async function runBench(desc) {
     }
 
     const benchTimeInMs = 500;
-    const stats = await benchMeasure(benchTimeInMs, desc);
+    const stats = desc.frames
+      ? await benchFrames(benchTimeInMs, desc)
+      : await benchMeasure(benchTimeInMs, desc);
 
     return { ok: stats };
   } catch (error) {
@@ -1134,12 +1243,20 @@ async function runBenchmarks() {
     (a, b) => groups.indexOf(a.group) - groups.indexOf(b.group),
   );
 
+  const names = ArrayPrototypeMap(filtered, (desc) => desc.name);
+  // Frame benches can report GPU times on a line of their own.
+  for (const desc of filtered) {
+    if (desc.frames) {
+      ArrayPrototypePush(names, `${desc.name} (gpu)`);
+    }
+  }
+
   ops.op_dispatch_bench_event({
     plan: {
       origin,
       total: filtered.length,
       usedOnly: only.length > 0,
-      names: ArrayPrototypeMap(filtered, (desc) => desc.name),
+      names,
     },
   });
 
@@ -27,8 +27,11 @@ mod version;
 mod worker;
 
 use crate::args::flags_from_vec;
+use crate::args::resolve_wsi_backend;
+use crate::args::resolve_wsi_config;
 use crate::args::DenoSubcommand;
 use crate::args::Flags;
+use crate::cache::DenoDir;
 use crate::proc_state::ProcState;
 use crate::resolver::CliGraphResolver;
 use crate::util::display;
@@ -57,10 +60,19 @@ async fn run_subcommand(
       let cli_options = CliOptions::from_flags(flags)?;
       let bench_options = cli_options.resolve_bench_options(bench_flags)?;
       if cli_options.watch_paths().is_some() {
-        tools::bench::run_benchmarks_with_watch(cli_options, bench_options)
-          .await?;
+        tools::bench::run_benchmarks_with_watch(
+          cli_options,
+          bench_options,
+          wsi_event_loop_proxy,
+        )
+        .await?;
       } else {
-        tools::bench::run_benchmarks(cli_options, bench_options).await?;
+        tools::bench::run_benchmarks(
+          cli_options,
+          bench_options,
+          wsi_event_loop_proxy,
+        )
+        .await?;
       }
       Ok(0)
     }
@@ -131,7 +143,7 @@ async fn run_subcommand(
       Ok(0)
     }
     DenoSubcommand::Repl(repl_flags) => {
-      tools::repl::run(flags, repl_flags).await
+      tools::repl::run(flags, repl_flags, wsi_event_loop_proxy).await
     }
     DenoSubcommand::Run(run_flags) => {
       if run_flags.is_stdin() {
@@ -158,9 +170,15 @@ async fn run_subcommand(
       let test_options = cli_options.resolve_test_options(test_flags)?;
 
       if cli_options.watch_paths().is_some() {
-        tools::test::run_tests_with_watch(cli_options, test_options).await?;
+        tools::test::run_tests_with_watch(
+          cli_options,
+          test_options,
+          wsi_event_loop_proxy,
+        )
+        .await?;
       } else {
-        tools::test::run_tests(cli_options, test_options).await?;
+        tools::test::run_tests(cli_options, test_options, wsi_event_loop_proxy)
+          .await?;
       }
 
       Ok(0)
@@ -233,7 +251,19 @@ fn unwrap_or_exit<T>(result: Result<T, AnyError>) -> T {
   }
 }
 
+// Minidumps of native crashes go next to the cache, in `DENO_DIR`.
+fn install_wsi_crash_handler(flags: &Flags) -> Result<(), AnyError> {
+  let maybe_custom_root = flags
+    .cache_path
+    .clone()
+    .or_else(|| env::var("DENO_DIR").map(String::into).ok());
+  let deno_dir = DenoDir::new(maybe_custom_root)?;
+  deno_wsi::crash::install_crash_handler(deno_dir.crash_reports_folder_path())
+}
+
 pub fn main() {
+  // This process may have been spawned to write minidumps for another one.
+  deno_wsi::crash::run_crash_server_if_requested();
   setup_panic_hook();
 
   util::unix::raise_fd_limit();
@@ -260,7 +290,7 @@ pub fn main() {
   // TODO(bartlomieju): doesn't handle exit code set by the runtime properly
   unwrap_or_exit(standalone_res);
 
-  let flags = match flags_from_vec(args) {
+  let mut flags = match flags_from_vec(args) {
     Ok(flags) => flags,
     Err(err @ clap::Error { .. })
       if err.kind() == clap::ErrorKind::DisplayHelp
@@ -276,12 +306,25 @@ pub fn main() {
 
   util::logger::init(flags.log_level);
 
+  let wsi_config = unwrap_or_exit(resolve_wsi_config(&mut flags));
   if flags.wsi {
+    // Crash reports are a convenience, so programs run without them if the
+    // crash server can't be started.
+    if flags.wsi_crash_reports {
+      if let Err(err) = install_wsi_crash_handler(&flags) {
+        log::warn!("Crash reporting is unavailable: {err}");
+      }
+    }
+    let wsi_backend = unwrap_or_exit(resolve_wsi_backend(&flags));
     drop(local);
-    deno_wsi::event_loop::hijack_main_and_spawn_proxy(|wsi_event_loop_proxy| {
-      let local = tokio::task::LocalSet::new();
-      run(rt, local, flags, Some(wsi_event_loop_proxy));
-    });
+    deno_wsi::event_loop::hijack_main_and_spawn_proxy(
+      wsi_config.control_flow,
+      wsi_backend,
+      |wsi_event_loop_proxy| {
+        let local = tokio::task::LocalSet::new();
+        run(rt, local, flags, Some(wsi_event_loop_proxy));
+      },
+    );
   } else {
     run(rt, local, flags, None);
   }
@@ -292,9 +335,12 @@ pub fn main() {
     flags: Flags,
     wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
   ) -> ! {
-    let exit_code = unwrap_or_exit(
-      local.block_on(&rt, run_subcommand(flags, wsi_event_loop_proxy)),
-    );
+    let result =
+      local.block_on(&rt, run_subcommand(flags, wsi_event_loop_proxy));
+    // Don't leave the cursor grabbed or a window fullscreen if the program
+    // died with an uncaught exception.
+    deno_wsi::cleanup::restore_desktop();
+    let exit_code = unwrap_or_exit(result);
 
     drop(local);
     drop(rt);
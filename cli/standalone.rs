@@ -29,6 +29,8 @@ use deno_core::ResolutionKind;
 use deno_graph::source::Resolver;
 use deno_runtime::deno_broadcast_channel::InMemoryBroadcastChannel;
 use deno_runtime::deno_web::BlobStore;
+use deno_runtime::deno_wsi::assets::WsiAssets;
+use deno_runtime::deno_wsi::WsiOptions;
 use deno_runtime::fmt_errors::format_js_error;
 use deno_runtime::permissions::Permissions;
 use deno_runtime::permissions::PermissionsContainer;
@@ -38,6 +40,7 @@ use deno_runtime::worker::WorkerOptions;
 use deno_runtime::BootstrapOptions;
 use import_map::parse_from_json;
 use log::Level;
+use std::collections::BTreeMap;
 use std::env::current_exe;
 use std::io::SeekFrom;
 use std::pin::Pin;
@@ -56,8 +59,12 @@ pub struct Metadata {
   pub ca_stores: Option<Vec<String>>,
   pub ca_data: Option<Vec<u8>>,
   pub unsafely_ignore_certificate_errors: Option<Vec<String>>,
+  pub gpu_backend: Option<String>,
+  pub wsi_app_id: Option<String>,
   pub maybe_import_map: Option<(Url, String)>,
   pub entrypoint: ModuleSpecifier,
+  // Offsets and lengths of the embedded WSI assets in the executable.
+  pub assets: BTreeMap<String, (u64, u64)>,
 }
 
 pub const MAGIC_TRAILER: &[u8; 8] = b"d3n0l4nd";
@@ -216,6 +223,9 @@ fn metadata_to_flags(metadata: &Metadata) -> Flags {
     allow_read: permissions.allow_read,
     allow_run: permissions.allow_run,
     allow_write: permissions.allow_write,
+    allow_wsi: permissions.allow_wsi,
+    allow_wsi_devices: permissions.allow_wsi_devices,
+    allow_wsi_global_input: permissions.allow_wsi_global_input,
     v8_flags: metadata.v8_flags.clone(),
     log_level: metadata.log_level,
     ca_stores: metadata.ca_stores.clone(),
@@ -263,6 +273,14 @@ pub async fn run(
   v8_set_flags(construct_v8_flags(&metadata.v8_flags, vec![]));
 
   let root_cert_store = ps.root_cert_store.clone();
+  let wsi_assets = if metadata.assets.is_empty() {
+    None
+  } else {
+    Some(WsiAssets::Embedded {
+      file: current_exe()?,
+      entries: Arc::new(metadata.assets),
+    })
+  };
 
   let options = WorkerOptions {
     bootstrap: BootstrapOptions {
@@ -284,6 +302,7 @@ pub async fn run(
       unstable: metadata.unstable,
       user_agent: version::get_user_agent(),
       inspect: ps.options.is_inspecting(),
+      gpu_backend: metadata.gpu_backend,
     },
     extensions: ops::cli_exts(ps),
     startup_snapshot: Some(crate::js::deno_isolate_init()),
@@ -310,6 +329,11 @@ pub async fn run(
     compiled_wasm_module_store: None,
     stdio: Default::default(),
     wsi_event_loop_proxy: None,
+    wsi_options: WsiOptions {
+      app_id: metadata.wsi_app_id,
+      assets: wsi_assets,
+      ..Default::default()
+    },
   };
   let mut worker = MainWorker::bootstrap_from_options(
     main_module.clone(),
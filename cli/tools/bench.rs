@@ -22,10 +22,13 @@ use deno_core::error::generic_error;
 use deno_core::error::AnyError;
 use deno_core::error::JsError;
 use deno_core::futures::future;
+use deno_core::futures::future::LocalBoxFuture;
 use deno_core::futures::stream;
 use deno_core::futures::FutureExt;
 use deno_core::futures::StreamExt;
 use deno_core::ModuleSpecifier;
+use deno_runtime::deno_wsi;
+use deno_runtime::deno_wsi::event_loop::WsiEventLoopProxy;
 use deno_runtime::permissions::Permissions;
 use deno_runtime::permissions::PermissionsContainer;
 use deno_runtime::tokio_util::run_local;
@@ -37,9 +40,11 @@ use std::cell::RefCell;
 use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::Arc;
 use tokio::sync::mpsc::unbounded_channel;
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinError;
 
 #[derive(Debug, Clone)]
 struct BenchSpecifierOptions {
@@ -100,6 +105,9 @@ pub struct BenchStats {
   pub p99: f64,
   pub p995: f64,
   pub p999: f64,
+  /// How long the GPU took per frame, for frame benches that record it.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub gpu: Option<Box<BenchStats>>,
 }
 
 impl BenchReport {
@@ -347,6 +355,24 @@ impl BenchReporter for ConsoleReporter {
           )
         );
 
+        if let Some(gpu) = &stats.gpu {
+          println!(
+            "{}",
+            mitata::reporter::benchmark(
+              &format!("{} (gpu)", desc.name),
+              &mitata::reporter::BenchmarkStats {
+                avg: gpu.avg,
+                min: gpu.min,
+                max: gpu.max,
+                p75: gpu.p75,
+                p99: gpu.p99,
+                p995: gpu.p995,
+              },
+              options
+            )
+          );
+        }
+
         self.group_measurements.push((desc, stats.clone()));
       }
 
@@ -429,25 +455,34 @@ async fn check_specifiers(
   Ok(())
 }
 
-/// Run a single specifier as an executable bench module.
+/// Run a single specifier as an executable bench module. With an event loop
+/// proxy, the windows that it left open are destroyed afterwards, so that they
+/// don't leak into the next one.
 async fn bench_specifier(
   ps: ProcState,
   permissions: Permissions,
   specifier: ModuleSpecifier,
   channel: UnboundedSender<BenchEvent>,
   options: BenchSpecifierOptions,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
 ) -> Result<(), AnyError> {
   let filter = options.filter;
   let mut worker = create_main_worker_for_test_or_bench(
     &ps,
     specifier,
     PermissionsContainer::new(permissions),
+    wsi_event_loop_proxy.clone(),
     vec![ops::bench::init(channel, filter)],
     Default::default(),
   )
   .await?;
 
-  worker.run_bench_specifier().await
+  let result = worker.run_bench_specifier().await;
+  drop(worker);
+  if let Some(proxy) = &wsi_event_loop_proxy {
+    deno_wsi::testing::destroy_windows(proxy);
+  }
+  result
 }
 
 /// Test a collection of specifiers with test modes concurrently.
@@ -456,6 +491,7 @@ async fn bench_specifiers(
   permissions: &Permissions,
   specifiers: Vec<ModuleSpecifier>,
   options: BenchSpecifierOptions,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
 ) -> Result<(), AnyError> {
   let log_level = ps.options.log_level();
 
@@ -470,16 +506,33 @@ async fn bench_specifiers(
     let sender = sender.clone();
     let options = option_for_handles.clone();
 
-    tokio::task::spawn_blocking(move || {
-      let future = bench_specifier(ps, permissions, specifier, sender, options);
-
-      run_local(future)
-    })
+    // The event loop proxy stays on this thread, which owns it.
+    let join_handle: LocalBoxFuture<'static, Result<_, JoinError>> =
+      match wsi_event_loop_proxy.clone() {
+        Some(proxy) => bench_specifier(
+          ps,
+          permissions,
+          specifier,
+          sender,
+          options,
+          Some(proxy),
+        )
+        .map(Ok)
+        .boxed_local(),
+        None => tokio::task::spawn_blocking(move || {
+          let future =
+            bench_specifier(ps, permissions, specifier, sender, options, None);
+
+          run_local(future)
+        })
+        .boxed_local(),
+      };
+    join_handle
   });
 
   let join_stream = stream::iter(join_handles)
     .buffer_unordered(1)
-    .collect::<Vec<Result<Result<(), AnyError>, tokio::task::JoinError>>>();
+    .collect::<Vec<Result<Result<(), AnyError>, JoinError>>>();
 
   let handler = {
     tokio::task::spawn(async move {
@@ -574,6 +627,7 @@ fn is_supported_bench_path(path: &Path) -> bool {
 pub async fn run_benchmarks(
   cli_options: CliOptions,
   bench_options: BenchOptions,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
 ) -> Result<(), AnyError> {
   let ps = ProcState::from_options(Arc::new(cli_options)).await?;
   // Various bench files should not share the same permissions in terms of
@@ -599,6 +653,7 @@ pub async fn run_benchmarks(
       filter: TestFilter::from_flag(&bench_options.filter),
       json: bench_options.json,
     },
+    wsi_event_loop_proxy,
   )
   .await?;
 
@@ -609,6 +664,7 @@ pub async fn run_benchmarks(
 pub async fn run_benchmarks_with_watch(
   cli_options: CliOptions,
   bench_options: BenchOptions,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
 ) -> Result<(), AnyError> {
   let ps = ProcState::from_options(Arc::new(cli_options)).await?;
   // Various bench files should not share the same permissions in terms of
@@ -731,6 +787,7 @@ pub async fn run_benchmarks_with_watch(
   let operation = |modules_to_reload: Vec<ModuleSpecifier>| {
     let permissions = &permissions;
     let bench_options = &bench_options;
+    let wsi_event_loop_proxy = wsi_event_loop_proxy.clone();
     ps.borrow_mut().reset_for_file_watcher();
     let ps = ps.borrow().clone();
 
@@ -751,6 +808,7 @@ pub async fn run_benchmarks_with_watch(
           filter: TestFilter::from_flag(&bench_options.filter),
           json: bench_options.json,
         },
+        wsi_event_loop_proxy,
       )
       .await?;
 
@@ -8,9 +8,11 @@ use crate::proc_state::ProcState;
 use crate::worker::create_main_worker;
 use deno_core::error::AnyError;
 use deno_core::resolve_path;
+use deno_runtime::deno_wsi::event_loop::WsiEventLoopProxy;
 use deno_runtime::permissions::Permissions;
 use deno_runtime::permissions::PermissionsContainer;
 use rustyline::error::ReadlineError;
+use std::rc::Rc;
 
 mod cdp;
 mod channel;
@@ -81,7 +83,11 @@ async fn read_eval_file(
   Ok((*file.source).to_string())
 }
 
-pub async fn run(flags: Flags, repl_flags: ReplFlags) -> Result<i32, AnyError> {
+pub async fn run(
+  flags: Flags,
+  repl_flags: ReplFlags,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
+) -> Result<i32, AnyError> {
   let ps = ProcState::build(flags).await?;
   let main_module =
     resolve_path("./$deno$repl.ts", ps.options.initial_cwd()).unwrap();
@@ -91,7 +97,7 @@ pub async fn run(flags: Flags, repl_flags: ReplFlags) -> Result<i32, AnyError> {
     PermissionsContainer::new(Permissions::from_options(
       &ps.options.permissions_options(),
     )?),
-    None,
+    wsi_event_loop_proxy,
   )
   .await?;
   worker.setup_repl().await?;
@@ -41,7 +41,7 @@ To grant permissions, set them before the script argument. For example:
   }
 
   if flags.watch.is_some() {
-    return run_with_watch(flags, run_flags.script).await;
+    return run_with_watch(flags, run_flags.script, wsi_event_loop_proxy).await;
   }
 
   // TODO(bartlomieju): actually I think it will also fail if there's an import
@@ -111,7 +111,11 @@ pub async fn run_from_stdin(
 
 // TODO(bartlomieju): this function is not handling `exit_code` set by the runtime
 // code properly.
-async fn run_with_watch(flags: Flags, script: String) -> Result<i32, AnyError> {
+async fn run_with_watch(
+  flags: Flags,
+  script: String,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
+) -> Result<i32, AnyError> {
   let flags = Arc::new(flags);
   let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
   let mut ps =
@@ -121,12 +125,14 @@ async fn run_with_watch(flags: Flags, script: String) -> Result<i32, AnyError> {
   let operation = |main_module: ModuleSpecifier| {
     ps.reset_for_file_watcher();
     let ps = ps.clone();
+    let wsi_event_loop_proxy = wsi_event_loop_proxy.clone();
     Ok(async move {
       let permissions = PermissionsContainer::new(Permissions::from_options(
         &ps.options.permissions_options(),
       )?);
       let worker =
-        create_main_worker(&ps, main_module, permissions, None).await?;
+        create_main_worker(&ps, main_module, permissions, wsi_event_loop_proxy)
+          .await?;
       worker.run_for_watcher().await?;
 
       Ok(())
@@ -0,0 +1,123 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+//! Lays out a macOS application bundle around a compiled executable.
+
+use super::write_standalone_binary;
+use super::AppIcon;
+use super::AppInfo;
+use deno_core::anyhow::bail;
+use deno_core::error::AnyError;
+use std::path::Path;
+
+/// Writes `<bundle>/Contents/MacOS/<executable>` along with the bundle's
+/// Info.plist and, if there is one, its icon.
+pub async fn write_app_bundle(
+  bundle: &Path,
+  final_bin: Vec<u8>,
+  app: &AppInfo,
+) -> Result<(), AnyError> {
+  let contents = bundle.join("Contents");
+  let executable = bundle.file_stem().unwrap().to_string_lossy().to_string();
+  let icon_file = format!("{executable}.icns");
+  let icns = app.icon.as_ref().map(build_icns).transpose()?;
+
+  write_standalone_binary(contents.join("MacOS").join(&executable), final_bin)
+    .await?;
+  let info_plist =
+    build_info_plist(app, &executable, icns.as_ref().map(|_| &*icon_file));
+  tokio::fs::write(contents.join("Info.plist"), info_plist).await?;
+  if let Some(icns) = icns {
+    let resources = contents.join("Resources");
+    tokio::fs::create_dir_all(&resources).await?;
+    tokio::fs::write(resources.join(icon_file), icns).await?;
+  }
+  Ok(())
+}
+
+fn build_info_plist(
+  app: &AppInfo,
+  executable: &str,
+  icon_file: Option<&str>,
+) -> String {
+  let mut entries = vec![
+    ("CFBundleDisplayName", escape(&app.name)),
+    ("CFBundleExecutable", escape(executable)),
+    ("CFBundleInfoDictionaryVersion", "6.0".to_string()),
+    ("CFBundleName", escape(&app.name)),
+    ("CFBundlePackageType", "APPL".to_string()),
+  ];
+  if let Some(icon_file) = icon_file {
+    entries.push(("CFBundleIconFile", escape(icon_file)));
+  }
+  if let Some(version) = &app.version {
+    entries.push(("CFBundleShortVersionString", escape(version)));
+    entries.push(("CFBundleVersion", escape(version)));
+  }
+
+  let mut plist = String::from(concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" ",
+    "\"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+    "<plist version=\"1.0\">\n",
+    "<dict>\n",
+  ));
+  for (key, value) in entries {
+    plist
+      .push_str(&format!("  <key>{key}</key>\n  <string>{value}</string>\n"));
+  }
+  if !app.url_schemes.is_empty() {
+    // macOS opens URLs with these schemes with the app, delivering them as
+    // Apple Events.
+    plist.push_str("  <key>CFBundleURLTypes</key>\n  <array>\n");
+    for scheme in &app.url_schemes {
+      plist.push_str(&format!(
+        concat!(
+          "    <dict>\n",
+          "      <key>CFBundleURLName</key>\n",
+          "      <string>{scheme}</string>\n",
+          "      <key>CFBundleURLSchemes</key>\n",
+          "      <array>\n",
+          "        <string>{scheme}</string>\n",
+          "      </array>\n",
+          "    </dict>\n",
+        ),
+        scheme = escape(scheme),
+      ));
+    }
+    plist.push_str("  </array>\n");
+  }
+  // Render at the display's native resolution instead of being upscaled.
+  plist.push_str("  <key>NSHighResolutionCapable</key>\n  <true/>\n");
+  plist.push_str("</dict>\n</plist>\n");
+  plist
+}
+
+// An icns file with a single PNG-encoded image.
+fn build_icns(icon: &AppIcon) -> Result<Vec<u8>, AnyError> {
+  let kind = match icon.size {
+    16 => b"icp4",
+    32 => b"icp5",
+    64 => b"icp6",
+    128 => b"ic07",
+    256 => b"ic08",
+    512 => b"ic09",
+    1024 => b"ic10",
+    _ => bail!(
+      "macOS icons must be 16, 32, 64, 128, 256, 512 or 1024 pixels square."
+    ),
+  };
+  let image_len = 8 + icon.png.len() as u32;
+  let mut icns = Vec::with_capacity(8 + image_len as usize);
+  icns.extend_from_slice(b"icns");
+  icns.extend_from_slice(&(8 + image_len).to_be_bytes());
+  icns.extend_from_slice(kind);
+  icns.extend_from_slice(&image_len.to_be_bytes());
+  icns.extend_from_slice(&icon.png);
+  Ok(icns)
+}
+
+fn escape(s: &str) -> String {
+  s.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+}
@@ -22,6 +22,7 @@ use deno_core::resolve_url_or_path;
 use deno_core::serde_json;
 use deno_graph::ModuleSpecifier;
 use deno_runtime::colors;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::fs::File;
@@ -35,6 +36,73 @@ use std::sync::Arc;
 
 use super::installer::infer_name_from_url;
 
+mod macos;
+mod windows;
+
+/// Packaging metadata that makes a compiled program look like a native app.
+struct AppInfo {
+  name: String,
+  file_name: String,
+  version: Option<String>,
+  icon: Option<AppIcon>,
+  // URL schemes the app opens, e.g. "myapp" for "myapp://...".
+  url_schemes: Vec<String>,
+}
+
+struct AppIcon {
+  png: Vec<u8>,
+  // The width and height in pixels.
+  size: u32,
+}
+
+impl AppInfo {
+  fn new(
+    compile_flags: &CompileFlags,
+    output_path: &Path,
+  ) -> Result<Self, AnyError> {
+    let file_name = output_path.file_name().unwrap().to_string_lossy();
+    let name = match &compile_flags.product_name {
+      Some(name) => name.clone(),
+      None => output_path
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .to_string(),
+    };
+    let icon = match &compile_flags.icon {
+      Some(path) => Some(AppIcon::load(path)?),
+      None => None,
+    };
+    Ok(Self {
+      name,
+      file_name: file_name.to_string(),
+      version: compile_flags.product_version.clone(),
+      icon,
+      url_schemes: compile_flags.url_schemes.clone(),
+    })
+  }
+}
+
+impl AppIcon {
+  fn load(path: &Path) -> Result<Self, AnyError> {
+    let png =
+      fs::read(path).with_context(|| format!("Reading: {}", path.display()))?;
+    let (width, height) = {
+      let reader = png::Decoder::new(png.as_slice())
+        .read_info()
+        .with_context(|| format!("{} is not a PNG image", path.display()))?;
+      (reader.info().width, reader.info().height)
+    };
+    if width != height {
+      bail!(
+        "The icon must be square, but {} is {width}x{height} pixels.",
+        path.display()
+      );
+    }
+    Ok(Self { png, size: width })
+  }
+}
+
 pub async fn compile(
   flags: Flags,
   compile_flags: CompileFlags,
@@ -49,6 +117,17 @@ pub async fn compile(
     ps.options.initial_cwd(),
   )
   .await?;
+  let is_app_bundle = is_macos_target(&compile_flags.target)
+    && output_path.extension().unwrap_or_default() == "app";
+  let app = if is_app_bundle
+    || compile_flags.icon.is_some()
+    || compile_flags.product_name.is_some()
+    || compile_flags.product_version.is_some()
+  {
+    Some(AppInfo::new(&compile_flags, &output_path)?)
+  } else {
+    None
+  };
 
   let graph = Arc::try_unwrap(
     create_graph_and_maybe_check(module_specifier.clone(), &ps).await?,
@@ -68,9 +147,14 @@ pub async fn compile(
   );
 
   // Select base binary based on target
-  let original_binary =
+  let mut original_binary =
     get_base_binary(&ps.http_client, deno_dir, compile_flags.target.clone())
       .await?;
+  if let Some(app) = &app {
+    if is_windows_target(&compile_flags.target) {
+      windows::set_resources(&mut original_binary, app)?;
+    }
+  }
 
   let final_bin = create_standalone_binary(
     original_binary,
@@ -83,7 +167,12 @@ pub async fn compile(
 
   log::info!("{} {}", colors::green("Emit"), output_path.display());
 
-  write_standalone_binary(output_path, final_bin).await?;
+  match &app {
+    Some(app) if is_app_bundle => {
+      macos::write_app_bundle(&output_path, final_bin, app).await?
+    }
+    _ => write_standalone_binary(output_path, final_bin).await?,
+  }
   Ok(())
 }
 
@@ -179,6 +268,21 @@ async fn create_standalone_binary(
     .resolve_import_map(&ps.file_fetcher)
     .await?
     .map(|import_map| (import_map.base_url().clone(), import_map.to_json()));
+
+  // Assets are stored between the eszip archive and the metadata.
+  let eszip_pos = original_bin.len();
+  let assets_pos = eszip_pos + eszip_archive.len();
+  let mut assets_data = Vec::new();
+  let mut assets = BTreeMap::new();
+  if let Some(dir_assets) = ps.options.wsi_options()?.assets {
+    for path in dir_assets.list().context("Failed to list the WSI assets")? {
+      let data = dir_assets.read(&path)?;
+      let pos = (assets_pos + assets_data.len()) as u64;
+      assets.insert(path, (pos, data.len() as u64));
+      assets_data.extend(data);
+    }
+  }
+
   let metadata = Metadata {
     argv: compile_flags.args.clone(),
     unstable: ps.options.unstable(),
@@ -191,24 +295,31 @@ async fn create_standalone_binary(
       .unsafely_ignore_certificate_errors()
       .clone(),
     log_level: ps.options.log_level(),
+    gpu_backend: ps.options.gpu_backend(),
+    wsi_app_id: ps.options.wsi_options()?.app_id,
     ca_stores: ps.options.ca_stores().clone(),
     ca_data,
     entrypoint,
     maybe_import_map,
+    assets,
   };
   let mut metadata = serde_json::to_string(&metadata)?.as_bytes().to_vec();
 
-  let eszip_pos = original_bin.len();
-  let metadata_pos = eszip_pos + eszip_archive.len();
+  let metadata_pos = assets_pos + assets_data.len();
   let mut trailer = MAGIC_TRAILER.to_vec();
   trailer.write_all(&eszip_pos.to_be_bytes())?;
   trailer.write_all(&metadata_pos.to_be_bytes())?;
 
   let mut final_bin = Vec::with_capacity(
-    original_bin.len() + eszip_archive.len() + trailer.len(),
+    original_bin.len()
+      + eszip_archive.len()
+      + assets_data.len()
+      + metadata.len()
+      + trailer.len(),
   );
   final_bin.append(&mut original_bin);
   final_bin.append(&mut eszip_archive);
+  final_bin.append(&mut assets_data);
   final_bin.append(&mut metadata);
   final_bin.append(&mut trailer);
 
@@ -325,11 +436,9 @@ fn get_os_specific_filepath(
   output: PathBuf,
   target: &Option<String>,
 ) -> PathBuf {
-  let is_windows = match target {
-    Some(target) => target.contains("windows"),
-    None => cfg!(windows),
-  };
-  if is_windows && output.extension().unwrap_or_default() != "exe" {
+  if is_windows_target(target)
+    && output.extension().unwrap_or_default() != "exe"
+  {
     if let Some(ext) = output.extension() {
       // keep version in my-exe-0.1.0 -> my-exe-0.1.0.exe
       output.with_extension(format!("{}.exe", ext.to_string_lossy()))
@@ -341,6 +450,20 @@ fn get_os_specific_filepath(
   }
 }
 
+fn is_windows_target(target: &Option<String>) -> bool {
+  match target {
+    Some(target) => target.contains("windows"),
+    None => cfg!(windows),
+  }
+}
+
+fn is_macos_target(target: &Option<String>) -> bool {
+  match target {
+    Some(target) => target.contains("apple-darwin"),
+    None => cfg!(target_os = "macos"),
+  }
+}
+
 #[cfg(test)]
 mod test {
   pub use super::*;
@@ -353,6 +476,10 @@ mod test {
         output: Some(PathBuf::from("./file")),
         args: Vec::new(),
         target: Some("x86_64-unknown-linux-gnu".to_string()),
+        icon: None,
+        product_name: None,
+        product_version: None,
+        url_schemes: Vec::new(),
       },
       &std::env::current_dir().unwrap(),
     )
@@ -373,6 +500,10 @@ mod test {
         output: Some(PathBuf::from("./file")),
         args: Vec::new(),
         target: Some("x86_64-pc-windows-msvc".to_string()),
+        icon: None,
+        product_name: None,
+        product_version: None,
+        url_schemes: Vec::new(),
       },
       &std::env::current_dir().unwrap(),
     )
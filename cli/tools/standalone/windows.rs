@@ -0,0 +1,425 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+//! Replaces the resources of a Windows executable, so that it carries the
+//! application's icon and version information instead of Denog's.
+
+use super::AppInfo;
+use deno_core::anyhow::bail;
+use deno_core::anyhow::Context;
+use deno_core::error::AnyError;
+
+// Declares per-monitor DPI awareness, so windows aren't bitmap-stretched.
+const MANIFEST: &[u8] = include_bytes!("../../denog.manifest");
+
+const RT_ICON: u32 = 3;
+const RT_GROUP_ICON: u32 = 14;
+const RT_VERSION: u32 = 16;
+const RT_MANIFEST: u32 = 24;
+const LANG_EN_US: u32 = 0x0409;
+const CODE_PAGE_UNICODE: u16 = 0x04b0;
+
+const DIRECTORY_ENTRY_RESOURCE: usize = 2;
+const DIRECTORY_ENTRY_SECURITY: usize = 4;
+const SECTION_HEADER_SIZE: usize = 40;
+const SCN_CNT_INITIALIZED_DATA: u32 = 0x0000_0040;
+const SCN_MEM_READ: u32 = 0x4000_0000;
+const RESOURCE_SUBDIRECTORY: u32 = 0x8000_0000;
+
+struct Resource {
+  kind: u32,
+  id: u32,
+  data: Vec<u8>,
+}
+
+/// Points the resource directory of a 64-bit Windows executable at a new
+/// section holding the application's icon, version information and manifest.
+/// The original resources are left in place but are no longer referenced.
+pub fn set_resources(bin: &mut Vec<u8>, app: &AppInfo) -> Result<(), AnyError> {
+  let pe = read_u32(bin, 0x3c)? as usize;
+  if bin.get(pe..pe + 4) != Some(b"PE\0\0") {
+    bail!("The base binary is not a Windows executable.");
+  }
+  let coff = pe + 4;
+  let num_sections = read_u16(bin, coff + 2)? as usize;
+  let optional_header_size = read_u16(bin, coff + 16)? as usize;
+  let optional_header = coff + 20;
+  if read_u16(bin, optional_header)? != 0x20b {
+    bail!("The base binary is not a 64-bit Windows executable.");
+  }
+  let section_alignment = read_u32(bin, optional_header + 32)? as usize;
+  let file_alignment = read_u32(bin, optional_header + 36)? as usize;
+  let headers_size = read_u32(bin, optional_header + 60)? as usize;
+  if read_u32(bin, optional_header + 108)? as usize <= DIRECTORY_ENTRY_SECURITY
+  {
+    bail!("The base binary has no resource directory.");
+  }
+  let data_directories = optional_header + 112;
+  let section_table = optional_header + optional_header_size;
+
+  let new_section = section_table + num_sections * SECTION_HEADER_SIZE;
+  let new_section_end = new_section + SECTION_HEADER_SIZE;
+  if new_section_end > headers_size
+    || bin[new_section..new_section_end].iter().any(|b| *b != 0)
+  {
+    bail!("The base binary has no room for another section header.");
+  }
+
+  // Any signature is invalidated by the changes, so it's removed.
+  let security = data_directories + DIRECTORY_ENTRY_SECURITY * 8;
+  let signature_offset = read_u32(bin, security)? as usize;
+  let signature_size = read_u32(bin, security + 4)? as usize;
+  if signature_size != 0 {
+    if signature_offset + signature_size >= bin.len() {
+      bin.truncate(signature_offset);
+    }
+    write_u32(bin, security, 0);
+    write_u32(bin, security + 4, 0);
+  }
+
+  let mut image_end = 0;
+  for i in 0..num_sections {
+    let section = section_table + i * SECTION_HEADER_SIZE;
+    let virtual_size = read_u32(bin, section + 8)? as usize;
+    let virtual_address = read_u32(bin, section + 12)? as usize;
+    image_end = image_end.max(virtual_address + virtual_size);
+  }
+  let rva = align(image_end, section_alignment);
+  let data = build_resource_section(&app_resources(app)?, rva as u32);
+  let raw_data_offset = align(bin.len(), file_alignment);
+  let raw_data_size = align(data.len(), file_alignment);
+  bin.resize(raw_data_offset, 0);
+  bin.extend_from_slice(&data);
+  bin.resize(raw_data_offset + raw_data_size, 0);
+
+  bin[new_section..new_section + 8].copy_from_slice(b".rsrc2\0\0");
+  write_u32(bin, new_section + 8, data.len() as u32);
+  write_u32(bin, new_section + 12, rva as u32);
+  write_u32(bin, new_section + 16, raw_data_size as u32);
+  write_u32(bin, new_section + 20, raw_data_offset as u32);
+  write_u32(
+    bin,
+    new_section + 36,
+    SCN_CNT_INITIALIZED_DATA | SCN_MEM_READ,
+  );
+  write_u16(bin, coff + 2, (num_sections + 1) as u16);
+
+  let initialized_data_size = read_u32(bin, optional_header + 8)?;
+  write_u32(
+    bin,
+    optional_header + 8,
+    initialized_data_size + raw_data_size as u32,
+  );
+  write_u32(
+    bin,
+    optional_header + 56,
+    align(rva + data.len(), section_alignment) as u32,
+  );
+  // The checksum is only verified for drivers and system DLLs.
+  write_u32(bin, optional_header + 64, 0);
+  let resource = data_directories + DIRECTORY_ENTRY_RESOURCE * 8;
+  write_u32(bin, resource, rva as u32);
+  write_u32(bin, resource + 4, data.len() as u32);
+  Ok(())
+}
+
+// Sorted by kind and then id, as the resource directory requires.
+fn app_resources(app: &AppInfo) -> Result<Vec<Resource>, AnyError> {
+  let mut resources = Vec::new();
+  if let Some(icon) = &app.icon {
+    if icon.size > 256 {
+      bail!("Windows icons can't be larger than 256x256 pixels.");
+    }
+    resources.push(Resource {
+      kind: RT_ICON,
+      id: 1,
+      data: icon.png.clone(),
+    });
+    // A 256 pixel icon has a width and height of 0 in the group entry.
+    let size = icon.size as u8;
+    let mut group = Vec::new();
+    push_u16s(&mut group, &[0, 1, 1]);
+    group.extend_from_slice(&[size, size, 0, 0]);
+    push_u16s(&mut group, &[1, 32]);
+    group.extend_from_slice(&(icon.png.len() as u32).to_le_bytes());
+    push_u16s(&mut group, &[1]);
+    resources.push(Resource {
+      kind: RT_GROUP_ICON,
+      id: 1,
+      data: group,
+    });
+  }
+  resources.push(Resource {
+    kind: RT_VERSION,
+    id: 1,
+    data: build_version_info(app),
+  });
+  resources.push(Resource {
+    kind: RT_MANIFEST,
+    id: 1,
+    data: MANIFEST.to_vec(),
+  });
+  Ok(resources)
+}
+
+// Lays out a three level (kind, id, language) resource directory followed by
+// the data entries and the data they point to.
+fn build_resource_section(resources: &[Resource], rva: u32) -> Vec<u8> {
+  let directory_size = |entries: usize| 16 + 8 * entries;
+  let mut kinds = Vec::from_iter(resources.iter().map(|r| r.kind));
+  kinds.dedup();
+  let count_of_kind =
+    |kind: u32| resources.iter().filter(|r| r.kind == kind).count();
+
+  let id_directories = directory_size(kinds.len());
+  let language_directories = id_directories
+    + kinds
+      .iter()
+      .map(|kind| directory_size(count_of_kind(*kind)))
+      .sum::<usize>();
+  let data_entries = language_directories + resources.len() * directory_size(1);
+  let data_start = align(data_entries + resources.len() * 16, 8);
+
+  let mut out = Vec::new();
+  push_directory(&mut out, kinds.len());
+  let mut offset = id_directories;
+  for kind in &kinds {
+    push_u32s(&mut out, &[*kind, RESOURCE_SUBDIRECTORY | offset as u32]);
+    offset += directory_size(count_of_kind(*kind));
+  }
+  let mut index = 0;
+  for kind in &kinds {
+    push_directory(&mut out, count_of_kind(*kind));
+    for resource in resources.iter().filter(|r| r.kind == *kind) {
+      let offset = language_directories + index * directory_size(1);
+      push_u32s(
+        &mut out,
+        &[resource.id, RESOURCE_SUBDIRECTORY | offset as u32],
+      );
+      index += 1;
+    }
+  }
+  for index in 0..resources.len() {
+    push_directory(&mut out, 1);
+    push_u32s(&mut out, &[LANG_EN_US, (data_entries + index * 16) as u32]);
+  }
+  let mut offset = data_start;
+  for resource in resources {
+    let size = resource.data.len() as u32;
+    push_u32s(&mut out, &[rva + offset as u32, size, 0, 0]);
+    offset = align(offset + resource.data.len(), 8);
+  }
+  for resource in resources {
+    out.resize(align(out.len(), 8), 0);
+    out.extend_from_slice(&resource.data);
+  }
+  out
+}
+
+fn push_directory(out: &mut Vec<u8>, id_entries: usize) {
+  push_u32s(out, &[0, 0, 0]);
+  push_u16s(out, &[0, id_entries as u16]);
+}
+
+enum VersionValue<'a> {
+  Binary(&'a [u8]),
+  Text(&'a str),
+}
+
+// Builds a VS_VERSIONINFO structure.
+fn build_version_info(app: &AppInfo) -> Vec<u8> {
+  let version = app
+    .version
+    .as_deref()
+    .map(parse_version)
+    .unwrap_or_default();
+  let ms = (version[0] as u32) << 16 | version[1] as u32;
+  let ls = (version[2] as u32) << 16 | version[3] as u32;
+  let mut fixed_file_info = Vec::new();
+  push_u32s(
+    &mut fixed_file_info,
+    &[
+      0xfeef04bd, // signature
+      0x0001_0000,
+      ms,
+      ls,
+      ms,
+      ls,
+      0x3f,        // file flags mask
+      0,           // file flags
+      0x0004_0004, // VOS_NT_WINDOWS32
+      1,           // VFT_APP
+      0,
+      0,
+      0,
+    ],
+  );
+
+  let mut strings = vec![
+    ("FileDescription", app.name.as_str()),
+    ("OriginalFilename", app.file_name.as_str()),
+    ("ProductName", app.name.as_str()),
+  ];
+  if let Some(version) = &app.version {
+    strings.push(("FileVersion", version));
+    strings.push(("ProductVersion", version));
+  }
+  let mut translation = Vec::new();
+  push_u16s(&mut translation, &[LANG_EN_US as u16, CODE_PAGE_UNICODE]);
+
+  let mut out = Vec::new();
+  push_version_node(
+    &mut out,
+    "VS_VERSION_INFO",
+    Some(VersionValue::Binary(&fixed_file_info)),
+    |out| {
+      push_version_node(out, "StringFileInfo", None, |out| {
+        let key = format!("{:04x}{:04x}", LANG_EN_US, CODE_PAGE_UNICODE);
+        push_version_node(out, &key, None, |out| {
+          for (key, value) in &strings {
+            push_version_node(
+              out,
+              key,
+              Some(VersionValue::Text(value)),
+              |_| {},
+            );
+          }
+        });
+      });
+      push_version_node(out, "VarFileInfo", None, |out| {
+        push_version_node(
+          out,
+          "Translation",
+          Some(VersionValue::Binary(&translation)),
+          |_| {},
+        );
+      });
+    },
+  );
+  out
+}
+
+fn push_version_node(
+  out: &mut Vec<u8>,
+  key: &str,
+  value: Option<VersionValue>,
+  push_children: impl FnOnce(&mut Vec<u8>),
+) {
+  out.resize(align(out.len(), 4), 0);
+  let start = out.len();
+  let (value_length, value_type) = match &value {
+    None => (0, 1),
+    Some(VersionValue::Binary(bytes)) => (bytes.len(), 0),
+    // Text lengths are in UTF-16 code units, including the terminator.
+    Some(VersionValue::Text(text)) => (text.encode_utf16().count() + 1, 1),
+  };
+  push_u16s(out, &[0, value_length as u16, value_type]);
+  push_utf16(out, key);
+  out.resize(align(out.len(), 4), 0);
+  match value {
+    None => {}
+    Some(VersionValue::Binary(bytes)) => out.extend_from_slice(bytes),
+    Some(VersionValue::Text(text)) => push_utf16(out, text),
+  }
+  push_children(out);
+  let length = (out.len() - start) as u16;
+  out[start..start + 2].copy_from_slice(&length.to_le_bytes());
+}
+
+// Missing or invalid components of a version like "1.2.3" are treated as 0.
+fn parse_version(version: &str) -> [u16; 4] {
+  let mut parts = [0; 4];
+  for (part, s) in parts.iter_mut().zip(version.split('.')) {
+    *part = s.parse().unwrap_or(0);
+  }
+  parts
+}
+
+fn push_utf16(out: &mut Vec<u8>, s: &str) {
+  for unit in s.encode_utf16().chain([0]) {
+    out.extend_from_slice(&unit.to_le_bytes());
+  }
+}
+
+fn push_u16s(out: &mut Vec<u8>, values: &[u16]) {
+  for value in values {
+    out.extend_from_slice(&value.to_le_bytes());
+  }
+}
+
+fn push_u32s(out: &mut Vec<u8>, values: &[u32]) {
+  for value in values {
+    out.extend_from_slice(&value.to_le_bytes());
+  }
+}
+
+fn read_u16(bin: &[u8], offset: usize) -> Result<u16, AnyError> {
+  let bytes = bin
+    .get(offset..offset + 2)
+    .context("The base binary is truncated.")?;
+  Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(bin: &[u8], offset: usize) -> Result<u32, AnyError> {
+  let bytes = bin
+    .get(offset..offset + 4)
+    .context("The base binary is truncated.")?;
+  Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn write_u16(bin: &mut [u8], offset: usize, value: u16) {
+  bin[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_u32(bin: &mut [u8], offset: usize, value: u32) {
+  bin[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn align(value: usize, alignment: usize) -> usize {
+  (value + alignment - 1) / alignment * alignment
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_parse_version() {
+    assert_eq!(parse_version("1.2.3"), [1, 2, 3, 0]);
+    assert_eq!(parse_version("1.2.3.4"), [1, 2, 3, 4]);
+    assert_eq!(parse_version("2"), [2, 0, 0, 0]);
+  }
+
+  #[test]
+  fn test_build_resource_section() {
+    let resources = [
+      Resource {
+        kind: RT_VERSION,
+        id: 1,
+        data: vec![1, 2, 3],
+      },
+      Resource {
+        kind: RT_MANIFEST,
+        id: 1,
+        data: vec![4, 5],
+      },
+    ];
+    let section = build_resource_section(&resources, 0x1000);
+    let lookup = |kind: u32| {
+      let mut offset = 0;
+      for id in [kind, 1, LANG_EN_US] {
+        let entries = read_u16(&section, offset + 14).unwrap() as usize;
+        let entry = (0..entries)
+          .map(|i| offset + 16 + i * 8)
+          .find(|entry| read_u32(&section, *entry).unwrap() == id)
+          .unwrap();
+        offset = (read_u32(&section, entry + 4).unwrap()
+          & !RESOURCE_SUBDIRECTORY) as usize;
+      }
+      let rva = read_u32(&section, offset).unwrap() as usize;
+      let size = read_u32(&section, offset + 4).unwrap() as usize;
+      section[rva - 0x1000..rva - 0x1000 + size].to_vec()
+    };
+    assert_eq!(lookup(RT_VERSION), [1, 2, 3]);
+    assert_eq!(lookup(RT_MANIFEST), [4, 5]);
+  }
+}
@@ -25,6 +25,7 @@ use deno_core::error::generic_error;
 use deno_core::error::AnyError;
 use deno_core::error::JsError;
 use deno_core::futures::future;
+use deno_core::futures::future::LocalBoxFuture;
 use deno_core::futures::stream;
 use deno_core::futures::FutureExt;
 use deno_core::futures::StreamExt;
@@ -33,6 +34,8 @@ use deno_core::url::Url;
 use deno_core::ModuleSpecifier;
 use deno_runtime::deno_io::Stdio;
 use deno_runtime::deno_io::StdioPipe;
+use deno_runtime::deno_wsi;
+use deno_runtime::deno_wsi::event_loop::WsiEventLoopProxy;
 use deno_runtime::fmt_errors::format_js_error;
 use deno_runtime::permissions::Permissions;
 use deno_runtime::permissions::PermissionsContainer;
@@ -53,12 +56,14 @@ use std::io::Write;
 use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
 use tokio::sync::mpsc::unbounded_channel;
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinError;
 
 /// The test mode is used to determine how a specifier is to be tested.
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -708,6 +713,7 @@ pub fn format_test_error(js_error: &JsError) -> String {
 
 /// Test a single specifier as documentation containing test programs, an executable test module or
 /// both.
+#[allow(clippy::too_many_arguments)]
 async fn test_specifier(
   ps: &ProcState,
   permissions: Permissions,
@@ -716,6 +722,7 @@ async fn test_specifier(
   sender: TestEventSender,
   fail_fast_tracker: FailFastTracker,
   options: TestSpecifierOptions,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
 ) -> Result<(), AnyError> {
   let stdout = StdioPipe::File(sender.stdout());
   let stderr = StdioPipe::File(sender.stderr());
@@ -723,6 +730,7 @@ async fn test_specifier(
     ps,
     specifier,
     PermissionsContainer::new(permissions),
+    wsi_event_loop_proxy,
     vec![ops::testing::init(
       sender,
       fail_fast_tracker,
@@ -991,12 +999,59 @@ pub async fn check_specifiers(
   Ok(())
 }
 
+/// Test a single specifier and report an uncaught error as a test event. With
+/// an event loop proxy, the windows that the specifier left open are destroyed
+/// afterwards, so that they don't leak into the next one.
+#[allow(clippy::too_many_arguments)]
+async fn test_file(
+  ps: ProcState,
+  permissions: Permissions,
+  specifier: ModuleSpecifier,
+  mode: TestMode,
+  mut sender: TestEventSender,
+  fail_fast_tracker: FailFastTracker,
+  options: TestSpecifierOptions,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
+) -> Result<(), AnyError> {
+  if fail_fast_tracker.should_stop() {
+    return Ok(());
+  }
+
+  let origin = specifier.to_string();
+  let file_result = test_specifier(
+    &ps,
+    permissions,
+    specifier,
+    mode,
+    sender.clone(),
+    fail_fast_tracker,
+    options,
+    wsi_event_loop_proxy.clone(),
+  )
+  .await;
+  if let Some(proxy) = &wsi_event_loop_proxy {
+    deno_wsi::testing::destroy_windows(proxy);
+  }
+  if let Err(error) = file_result {
+    if error.is::<JsError>() {
+      sender.send(TestEvent::UncaughtError(
+        origin,
+        Box::new(error.downcast::<JsError>().unwrap()),
+      ))?;
+    } else {
+      return Err(error);
+    }
+  }
+  Ok(())
+}
+
 /// Test a collection of specifiers with test modes concurrently.
 async fn test_specifiers(
   ps: &ProcState,
   permissions: &Permissions,
   specifiers_with_mode: Vec<(ModuleSpecifier, TestMode)>,
   options: TestSpecifierOptions,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
 ) -> Result<(), AnyError> {
   let log_level = ps.options.log_level();
   let specifiers_with_mode = if let Some(seed) = ps.options.shuffle_tests() {
@@ -1011,7 +1066,13 @@ async fn test_specifiers(
 
   let (sender, mut receiver) = unbounded_channel::<TestEvent>();
   let sender = TestEventSender::new(sender);
-  let concurrent_jobs = options.concurrent_jobs;
+  // There's only one event loop, so specifiers that share it run one at a
+  // time on this thread, which owns the proxy.
+  let concurrent_jobs = if wsi_event_loop_proxy.is_some() {
+    NonZeroUsize::new(1).unwrap()
+  } else {
+    options.concurrent_jobs
+  };
 
   let join_handles =
     specifiers_with_mode
@@ -1019,42 +1080,44 @@ async fn test_specifiers(
       .map(move |(specifier, mode)| {
         let ps = ps.clone();
         let permissions = permissions.clone();
-        let mut sender = sender.clone();
+        let sender = sender.clone();
         let options = options.clone();
         let fail_fast_tracker = FailFastTracker::new(options.fail_fast);
 
-        tokio::task::spawn_blocking(move || {
-          if fail_fast_tracker.should_stop() {
-            return Ok(());
-          }
-
-          let origin = specifier.to_string();
-          let file_result = run_local(test_specifier(
-            &ps,
-            permissions,
-            specifier,
-            mode,
-            sender.clone(),
-            fail_fast_tracker,
-            options,
-          ));
-          if let Err(error) = file_result {
-            if error.is::<JsError>() {
-              sender.send(TestEvent::UncaughtError(
-                origin,
-                Box::new(error.downcast::<JsError>().unwrap()),
-              ))?;
-            } else {
-              return Err(error);
-            }
-          }
-          Ok(())
-        })
+        let join_handle: LocalBoxFuture<'static, Result<_, JoinError>> =
+          match wsi_event_loop_proxy.clone() {
+            Some(proxy) => test_file(
+              ps,
+              permissions,
+              specifier,
+              mode,
+              sender,
+              fail_fast_tracker,
+              options,
+              Some(proxy),
+            )
+            .map(Ok)
+            .boxed_local(),
+            None => tokio::task::spawn_blocking(move || {
+              run_local(test_file(
+                ps,
+                permissions,
+                specifier,
+                mode,
+                sender,
+                fail_fast_tracker,
+                options,
+                None,
+              ))
+            })
+            .boxed_local(),
+          };
+        join_handle
       });
 
   let join_stream = stream::iter(join_handles)
     .buffer_unordered(concurrent_jobs.get())
-    .collect::<Vec<Result<Result<(), AnyError>, tokio::task::JoinError>>>();
+    .collect::<Vec<Result<Result<(), AnyError>, JoinError>>>();
 
   let mut reporter = Box::new(PrettyTestReporter::new(
     concurrent_jobs.get() > 1,
@@ -1307,6 +1370,7 @@ async fn fetch_specifiers_with_test_mode(
 pub async fn run_tests(
   cli_options: CliOptions,
   test_options: TestOptions,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
 ) -> Result<(), AnyError> {
   let ps = ProcState::from_options(Arc::new(cli_options)).await?;
   // Various test files should not share the same permissions in terms of
@@ -1342,6 +1406,7 @@ pub async fn run_tests(
       fail_fast: test_options.fail_fast,
       filter: TestFilter::from_flag(&test_options.filter),
     },
+    wsi_event_loop_proxy,
   )
   .await?;
 
@@ -1351,6 +1416,7 @@ pub async fn run_tests(
 pub async fn run_tests_with_watch(
   cli_options: CliOptions,
   test_options: TestOptions,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
 ) -> Result<(), AnyError> {
   let ps = ProcState::from_options(Arc::new(cli_options)).await?;
   // Various test files should not share the same permissions in terms of
@@ -1476,6 +1542,7 @@ pub async fn run_tests_with_watch(
   let operation = |modules_to_reload: Vec<ModuleSpecifier>| {
     let permissions = &permissions;
     let test_options = &test_options;
+    let wsi_event_loop_proxy = wsi_event_loop_proxy.clone();
     ps.borrow_mut().reset_for_file_watcher();
     let ps = ps.borrow().clone();
 
@@ -1506,6 +1573,7 @@ pub async fn run_tests_with_watch(
           fail_fast: test_options.fail_fast,
           filter: TestFilter::from_flag(&test_options.filter),
         },
+        wsi_event_loop_proxy,
       )
       .await?;
 
@@ -621,6 +621,39 @@ declare namespace Deno {
      * @default {false}
      */
     write?: "inherit" | boolean | Array<string | URL>;
+
+    /** Specifies if the `wsi` permission should be requested or revoked.
+     * If set to `"inherit"`, the current `wsi` permission will be inherited.
+     * If set to `true`, the global `wsi` permission will be requested.
+     * If set to `false`, the global `wsi` permission will be revoked.
+     *
+     * @default {false}
+     */
+    wsi?: "inherit" | boolean;
+
+    /** Specifies if the `wsi-devices` permission should be requested or
+     * revoked.
+     * If set to `"inherit"`, the current `wsi-devices` permission will be
+     * inherited.
+     * If set to `true`, the global `wsi-devices` permission will be requested.
+     * If set to `false`, the global `wsi-devices` permission will be revoked.
+     *
+     * @default {false}
+     */
+    "wsi-devices"?: "inherit" | boolean;
+
+    /** Specifies if the `wsi-global-input` permission should be requested or
+     * revoked.
+     * If set to `"inherit"`, the current `wsi-global-input` permission will be
+     * inherited.
+     * If set to `true`, the global `wsi-global-input` permission will be
+     * requested.
+     * If set to `false`, the global `wsi-global-input` permission will be
+     * revoked.
+     *
+     * @default {false}
+     */
+    "wsi-global-input"?: "inherit" | boolean;
   }
 
   /**
@@ -978,8 +1011,9 @@ declare namespace Deno {
    * @category Testing
    */
   export interface BenchDefinition {
-    /** The test function which will be benchmarked. */
-    fn: () => void | Promise<void>;
+    /** The test function which will be benchmarked. Only a frame bench is
+     * passed a {@linkcode BenchFrames} context. */
+    fn: (frames: BenchFrames) => void | Promise<void>;
     /** The name of the test, which will be used in displaying the results. */
     name: string;
     /** If truthy, the benchmark test will be ignored/skipped. */
@@ -1011,6 +1045,45 @@ declare namespace Deno {
      * @default {"inherit"}
      */
     permissions?: PermissionOptions;
+    /** **UNSTABLE**: New API, yet to be vetted.
+     *
+     * Measure frames instead of calls. The function is called once and renders
+     * frames in a loop of its own, calling `frames.frame()` after each one
+     * until it returns `false`. Frame times are reported like call times, and
+     * GPU times recorded with `frames.addGpuTimings()` on a line of their own.
+     *
+     * @default {false} */
+    frames?: boolean;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   *
+   * The context that a frame bench measures its frames with.
+   *
+   * ```ts
+   * Deno.bench({ name: "draw", frames: true }, async (frames) => {
+   *   while (frames.frame()) {
+   *     const encoder = device.createCommandEncoder();
+   *     profiler.beginScope(encoder, "draw");
+   *     draw(encoder);
+   *     profiler.endScope(encoder);
+   *     profiler.resolve(encoder);
+   *     device.queue.submit([encoder.finish()]);
+   *     frames.addGpuTimings(await profiler.getResults());
+   *   }
+   * });
+   * ```
+   *
+   * @category Testing
+   */
+  export interface BenchFrames {
+    /** Marks the end of a frame and the start of the next one. The first
+     * frames warm up and aren't measured. Returns `false` once enough frames
+     * were measured. */
+    frame(): boolean;
+    /** Records how long the GPU took for a frame, as the top-level scopes of a
+     * `GPUProfiler`. */
+    addGpuTimings(results: GPUProfilerScopeResult[]): void;
   }
 
   /**
@@ -1139,7 +1212,7 @@ declare namespace Deno {
   export function bench(
     name: string,
     options: Omit<BenchDefinition, "fn" | "name">,
-    fn: () => void | Promise<void>,
+    fn: (frames: BenchFrames) => void | Promise<void>,
   ): void;
 
   /**
@@ -1173,7 +1246,7 @@ declare namespace Deno {
    */
   export function bench(
     options: Omit<BenchDefinition, "fn">,
-    fn: () => void | Promise<void>,
+    fn: (frames: BenchFrames) => void | Promise<void>,
   ): void;
 
   /**
@@ -4282,7 +4355,10 @@ declare namespace Deno {
     | "env"
     | "sys"
     | "ffi"
-    | "hrtime";
+    | "hrtime"
+    | "wsi"
+    | "wsi-devices"
+    | "wsi-global-input";
 
   /** The current status of the permission:
    *
@@ -4415,6 +4491,35 @@ declare namespace Deno {
     name: "hrtime";
   }
 
+  /** The permission descriptor for the `allow-wsi` permission, which controls
+   * if the runtime code can create windows and otherwise interact with the
+   * window system via `Deno.wsi`.
+   *
+   * @category Permissions */
+  export interface WsiPermissionDescriptor {
+    name: "wsi";
+  }
+
+  /** The permission descriptor for the `allow-wsi-devices` permission, which
+   * controls if the runtime code can receive device events, which report input
+   * from devices like keyboards and mice regardless of which window (if any)
+   * has focus. This is effectively the ability to log keystrokes.
+   *
+   * @category Permissions */
+  export interface WsiDevicesPermissionDescriptor {
+    name: "wsi-devices";
+  }
+
+  /** The permission descriptor for the `allow-wsi-global-input` permission,
+   * which controls if the runtime code can receive the keyboard and mouse
+   * input of the whole desktop, even without a window. This is effectively
+   * the ability to log keystrokes and watch the mouse in other applications.
+   *
+   * @category Permissions */
+  export interface WsiGlobalInputPermissionDescriptor {
+    name: "wsi-global-input";
+  }
+
   /** Permission descriptors which define a permission and can be queried,
    * requested, or revoked.
    *
@@ -4431,7 +4536,10 @@ declare namespace Deno {
     | EnvPermissionDescriptor
     | SysPermissionDescriptor
     | FfiPermissionDescriptor
-    | HrtimePermissionDescriptor;
+    | HrtimePermissionDescriptor
+    | WsiPermissionDescriptor
+    | WsiDevicesPermissionDescriptor
+    | WsiGlobalInputPermissionDescriptor;
 
   /** The interface which defines what event types are supported by
    * {@linkcode PermissionStatus} instances.
@@ -9,20 +9,118 @@ declare namespace Deno {
 
   export const wsi: WSI;
   export class WSI {
-    nextEvent(): Promise<WSIEvent>;
+    nextEvent(
+      options: WSINextEventOptions & { timeoutMs: number },
+    ): Promise<WSIEvent | null>;
+    nextEvent(options?: WSINextEventOptions): Promise<WSIEvent>;
     setDeviceEventFilter(filter: WSIDeviceEventFilter): void;
+    setDeviceMotionInterval(milliseconds: number): void;
+    getDeviceInfo(deviceId: number): WSIDeviceInfo | null;
+    setGlobalInputEnabled(enabled?: boolean): boolean;
+    setClickOptions(options?: WSIClickOptions): void;
+    capabilities(): WSICapabilities;
+    getMonitors(): WSIMonitor[];
+    getSystemTheme(): WSIWindowTheme | null;
+    getCursorPosition(): [number, number] | null;
+    getKeyLabel(scanCode: number): string | null;
+    getKeyScanCode(label: string): number | null;
+    getModifiers(): WSIModifierKeys;
+    setAppMenu(items: (WSIAppMenuItem | null)[]): void;
+    setDockBadge(label: string | null): void;
+    setDockIcon(image: BufferSource | null): void;
+    requestDockAttention(type?: WSIUserAttentionType): void;
+    registerUrlScheme(scheme: string): void;
+    onBeforeQuit(
+      callback: () => boolean | void | Promise<boolean | void>,
+    ): void;
+    setCrashMetadata(metadata: Record<string, string>): boolean;
     createWindow(options?: WSICreateWindowOptions): WSIWindow;
+    windows(): WSIWindowInfo[];
+    clearSavedSession(): void;
+    metrics(): WSIMetrics;
+    setTraceEnabled(enabled?: boolean): void;
+    requestFrame(window: WSIWindow): Promise<void>;
+    readonly assets: WSIAssets;
+    watchShader(
+      device: GPUDevice,
+      path: string,
+      options?: WSIWatchShaderOptions,
+    ): WSIShaderWatch;
   }
 
+  // An item of the macOS application menu. `key` selects it together with
+  // Command.
+  export interface WSIAppMenuItem {
+    id: string;
+    key?: string;
+    title: string;
+  }
+
+  // Files from the --wsi-assets directory, which `denog compile` embeds in the
+  // executable. Paths are relative and separated by forward slashes.
+  export class WSIAssets {
+    list(): string[];
+    readFile(path: string): Promise<Uint8Array>;
+    readTextFile(path: string): Promise<string>;
+  }
+
+  export interface WSIWatchShaderOptions {
+    label?: string;
+  }
+
+  // A WGSL file that is compiled for a device again whenever it changes.
+  export class WSIShaderWatch {
+    // The module of the last compilation without errors, if any.
+    readonly module: GPUShaderModule | null;
+    // The messages of the last compilation.
+    readonly messages: GPUCompilationMessage[];
+    close(): void;
+  }
+
+  // The window system that windows are created on, or "headless" for the
+  // virtual windows of --wsi-headless.
+  export type WSIBackend =
+    | "win32"
+    | "cocoa"
+    | "x11"
+    | "wayland"
+    | "headless";
+
   // https://docs.rs/winit/0.28.1/winit/event/enum.ElementState.html
   export type WSIButtonState =
     | "pressed"
     | "released";
 
   // https://docs.rs/winit/0.28.1/winit/window/struct.WindowBuilder.html
+  // How far apart in time (in milliseconds) and space (in physical pixels)
+  // mouse button presses can be to count as one double or triple click.
+  // Options that aren't given follow the OS settings.
+  export interface WSIClickOptions {
+    interval?: number;
+    distance?: number;
+  }
+
+  // Which optional features the window system supports.
+  export type WSICapabilities = {
+    backend: WSIBackend;
+    // The modes that WSIWindow.setCursorGrabMode() accepts.
+    cursorGrabModes: WSICursorGrabMode[];
+    transparency: boolean;
+    // Whether WSIWindow.beginDragResize() is supported.
+    dragResize: boolean;
+    ime: boolean;
+    // Whether WSIWindow.setContentProtected() has an effect.
+    contentProtection: boolean;
+    // Whether protected windows get "window-capture-detected" events.
+    captureDetection: boolean;
+  };
+
   export interface WSICreateWindowOptions {
     active?: boolean;
+    appId?: string;
     contentProtected?: boolean;
+    coordinateSpace?: WSICoordinateSpace;
+    corners?: WSIWindowCorners;
     decorated?: boolean;
     enabledButtons?: WSIWindowButtons;
     fullscreen?: boolean;
@@ -32,19 +130,32 @@ declare namespace Deno {
     maxInnerSize?: [number, number];
     level?: WSIWindowLevel;
     maximized?: boolean;
+    name?: string;
+    parent?: WSIWindow;
     resizable?: boolean;
     resizeIncrements?: [number, number];
+    shadow?: boolean;
+    skipTaskbar?: boolean;
+    tag?: unknown;
     theme?: WSIWindowTheme;
     title?: string;
+    titleBarStyle?: WSITitleBarStyle;
     transparent?: boolean;
     visible?: boolean;
   }
 
+  // Whether a window's positions and sizes are in physical pixels, or in logical
+  // pixels that are divided by the window's scale factor.
+  export type WSICoordinateSpace =
+    | "physical"
+    | "logical";
+
   // https://docs.rs/winit/0.28.1/winit/window/enum.CursorGrabMode.html
   export type WSICursorGrabMode =
     | "none"
     | "confined"
-    | "locked";
+    | "locked"
+    | "locked-or-confined";
 
   // https://docs.rs/winit/0.28.1/winit/window/enum.CursorIcon.html
   export type WSICursorIcon =
@@ -90,8 +201,57 @@ declare namespace Deno {
     | "unfocused"
     | "never";
 
+  // What the OS tells about an input device.
+  export interface WSIDeviceInfo {
+    name: string | null;
+    type: WSIDeviceType;
+    vendorId: number | null;
+    productId: number | null;
+  }
+
+  export type WSIDeviceType =
+    | "mouse"
+    | "keyboard"
+    | "touchpad"
+    | "other";
+
+  // What can be changed about the monitor that a window is on.
+  export type WSIDisplayControl =
+    | "brightness"
+    | "gamma";
+
+  // What `startDrag` drags out of a window.
+  export interface WSIDragData {
+    paths?: string[];
+    text?: string;
+  }
+
+  // The MIME types of data that windows accept besides files.
+  export type WSIDropType =
+    | "image/png"
+    | "text/plain"
+    | "text/uri-list";
+
   // https://docs.rs/winit/0.28.1/winit/event/enum.Event.html
   export type WSIEvent =
+    | {
+      // None of the app's windows can be seen anymore, because each is
+      // minimized, hidden or covered.
+      type: "app-did-enter-background";
+    }
+    | {
+      // One of the app's windows can be seen again.
+      type: "app-did-enter-foreground";
+    }
+    | {
+      // The system became low on memory.
+      type: "app-memory-warning";
+    }
+    | {
+      // An item of the menu set by `Deno.wsi.setAppMenu` was chosen.
+      type: "app-menu-item";
+      id: string;
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.Event.html#variant.Resumed
       type: "app-resumed";
@@ -100,6 +260,10 @@ declare namespace Deno {
       // https://docs.rs/winit/0.28.1/winit/event/enum.Event.html#variant.Suspended
       type: "app-suspended";
     }
+    | {
+      // The app is about to exit, after agreeing to quit. It's the last event.
+      type: "app-will-terminate";
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.CloseRequested
       type: "close-requested";
@@ -124,6 +288,27 @@ declare namespace Deno {
       deviceId: number;
       position: [number, number];
     }
+    | {
+      // Data of a type set by `setDropTypes` was dropped. Text types are
+      // strings and images are PNG-encoded bytes.
+      type: "data-dropped";
+      window: WSIWindow;
+      mimeType: WSIDropType;
+      data: string | Uint8Array;
+      position: [number, number] | null;
+    }
+    | {
+      // Data of a type set by `setDropTypes` was dragged over the window.
+      type: "data-hovered";
+      window: WSIWindow;
+      mimeType: WSIDropType;
+      position: [number, number] | null;
+    }
+    | {
+      // The data dragged over the window left it or couldn't be read.
+      type: "data-left";
+      window: WSIWindow;
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.DeviceEvent.html#variant.Added
       type: "device-added";
@@ -173,18 +358,57 @@ declare namespace Deno {
       type: "file-dropped";
       window: WSIWindow;
       path: string;
+      position: [number, number] | null;
     }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.HoveredFile
       type: "file-hovered";
       window: WSIWindow;
       path: string;
+      position: [number, number] | null;
     }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.HoveredFileCancelled
       type: "file-left";
       window: WSIWindow;
     }
+    | {
+      // All the files dropped at once, after their "file-dropped" events.
+      type: "files-dropped";
+      window: WSIWindow;
+      paths: string[];
+      position: [number, number] | null;
+    }
+    | {
+      // The cursor moved anywhere on the desktop, while global input is
+      // enabled by `Deno.wsi.setGlobalInputEnabled`. Global input positions
+      // are relative to the desktop.
+      type: "global-cursor-moved";
+      position: [number, number];
+    }
+    | {
+      // A key was pressed or released in any application, while global input
+      // is enabled.
+      type: "global-key";
+      scanCode: number;
+      code?: string;
+      state: WSIButtonState;
+    }
+    | {
+      // A mouse button was pressed or released anywhere on the desktop, while
+      // global input is enabled.
+      type: "global-mouse-button";
+      button: WSIMouseButton;
+      state: WSIButtonState;
+      position: [number, number];
+    }
+    | {
+      // The mouse wheel was turned anywhere on the desktop, while global
+      // input is enabled.
+      type: "global-mouse-scroll";
+      delta: WSIScrollDelta;
+      position: [number, number];
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.Ime.html#variant.Commit
       type: "ime-commit";
@@ -229,7 +453,19 @@ declare namespace Deno {
       deviceId: number;
       scanCode: number;
       keyCode?: WSIKeyCode;
+      // The physical key, named like `KeyboardEvent.code`, e.g. "KeyQ" for
+      // the key labeled A on AZERTY keyboards.
+      code?: string;
+      // What the key means in the keyboard layout, like `KeyboardEvent.key`,
+      // e.g. "a", "é", "Enter" or "Dead".
+      key: string;
+      // The text the key types, if it's pressed and not part of a shortcut.
+      text?: string;
       state: WSIButtonState;
+      // The modifier keys held when the key was pressed or released.
+      modifiers: WSIModifierKeys;
+      // Whether the key is held and the press repeats automatically.
+      repeat: boolean;
       isSynthetic: boolean;
     }
     | {
@@ -246,6 +482,21 @@ declare namespace Deno {
       // https://docs.rs/winit/0.28.1/winit/event/enum.Event.html#variant.MainEventsCleared
       type: "main-events-cleared";
     }
+    | {
+      // A monitor's size, position, work area, scale factor, refresh rate or
+      // primary status changed.
+      type: "monitor-changed";
+      monitor: WSIMonitor;
+    }
+    | {
+      type: "monitor-connected";
+      monitor: WSIMonitor;
+    }
+    | {
+      // The monitor as it was last seen.
+      type: "monitor-disconnected";
+      monitor: WSIMonitor;
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.ModifiersChanged
       type: "modifiers-changed";
@@ -259,6 +510,9 @@ declare namespace Deno {
       deviceId: number;
       button: WSIMouseButton;
       state: WSIButtonState;
+      // 2 for the second press of a double click, 3 for a triple click, and so
+      // on. Releases have the count of the press they end.
+      clickCount: number;
     }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.DeviceEvent.html#variant.MouseMotion
@@ -278,6 +532,58 @@ declare namespace Deno {
       // https://docs.rs/winit/0.28.1/winit/event/enum.Event.html#variant.NewEvents
       type: "new-events";
     }
+    | {
+      // A URL with a scheme registered by `Deno.wsi.registerUrlScheme` was
+      // opened.
+      type: "open-url";
+      url: string;
+    }
+    | {
+      // A pen touched the window. Pens are reported with these events instead
+      // of "input-touch" events on Windows, and with them after mouse events
+      // on macOS. `pressure` is from 0 to 1 and `tilt` is in degrees, from -90
+      // to 90, like in DOM pointer events. `eraser` is never set on macOS.
+      type: "pen-down";
+      window: WSIWindow;
+      deviceId: number;
+      position: [number, number];
+      pressure: number;
+      tilt: [number, number];
+      inContact: boolean;
+      barrelButton: boolean;
+      eraser: boolean;
+    }
+    | {
+      // A pen moved over or on the window.
+      type: "pen-moved";
+      window: WSIWindow;
+      deviceId: number;
+      position: [number, number];
+      pressure: number;
+      tilt: [number, number];
+      inContact: boolean;
+      barrelButton: boolean;
+      eraser: boolean;
+    }
+    | {
+      // A pen was lifted from the window.
+      type: "pen-up";
+      window: WSIWindow;
+      deviceId: number;
+      position: [number, number];
+      pressure: number;
+      tilt: [number, number];
+      inContact: boolean;
+      barrelButton: boolean;
+      eraser: boolean;
+    }
+    | {
+      // Only delivered when running with --wsi-resilient.
+      type: "recoverable-error";
+      window: WSIWindow;
+      operation: string;
+      message: string;
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.Event.html#variant.RedrawEventsCleared
       type: "redraw-events-cleared";
@@ -293,12 +599,52 @@ declare namespace Deno {
       window: WSIWindow;
       scaleFactor: number;
     }
+    | {
+      // Delivered first if a previous run of this program saved its layout.
+      type: "session-restore-available";
+      windows: WSISavedWindow[];
+    }
+    | {
+      // A file watched with `Deno.wsi.watchShader()` changed and was compiled
+      // again. `module` is null if the code has errors, which are in
+      // `messages`.
+      type: "shader-reloaded";
+      watch: WSIShaderWatch;
+      module: GPUShaderModule | null;
+      messages: GPUCompilationMessage[];
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.SmartMagnify
       type: "smart-magnify";
       window: WSIWindow;
       deviceId: number;
     }
+    | {
+      // Where the soft keyboard will cover the window's client area, as
+      // [x, y, width, height] in physical pixels, or null once it hides or if
+      // it doesn't cover the window.
+      type: "soft-keyboard-changed";
+      window: WSIWindow;
+      rect: [number, number, number, number] | null;
+    }
+    | {
+      // The theme chosen for the whole system changed. Only sent once
+      // `Deno.wsi.getSystemTheme()` has been called.
+      type: "system-theme-changed";
+      theme: WSIWindowTheme;
+    }
+    | {
+      // The device that the window's GPUSurface was configured with was lost.
+      // Configure the surface with a new device to keep rendering.
+      type: "surface-device-lost";
+      window: WSIWindow;
+    }
+    | {
+      // The window's GPUSurface was outdated or lost and has been reconfigured
+      // with its last configuration.
+      type: "surface-outdated";
+      window: WSIWindow;
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.TouchpadMagnify
       type: "touchpad-magnify";
@@ -323,12 +669,36 @@ declare namespace Deno {
       delta: number;
       touchPhase: WSITouchPhase;
     }
+    | {
+      // Sent by a native extension that registered this kind of user event.
+      type: "user-event";
+      kind: string;
+      payload: unknown;
+    }
+    | {
+      // The screen started being recorded or shared while the window's
+      // content is protected. Only sent on macOS.
+      type: "window-capture-detected";
+      window: WSIWindow;
+    }
+    | {
+      // The color profile of the window's monitor changed, or the window
+      // moved to a monitor with another one.
+      type: "window-color-profile-changed";
+      window: WSIWindow;
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.Focused
       type: "window-focus";
       window: WSIWindow;
       hasFocus: boolean;
     }
+    | {
+      // The window moved to another monitor, or off every monitor.
+      type: "window-monitor-changed";
+      window: WSIWindow;
+      monitor: WSIMonitor | null;
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.Moved
       type: "window-moved";
@@ -341,12 +711,33 @@ declare namespace Deno {
       window: WSIWindow;
       isOccluded: boolean;
     }
+    | {
+      // The orientation of the window's monitor changed, or the window moved
+      // to a monitor with another one.
+      type: "window-orientation-changed";
+      window: WSIWindow;
+      orientation: WSIOrientation;
+    }
+    | {
+      // The refresh rate of the window's monitor changed, in millihertz, or
+      // the window moved to a monitor with another one.
+      type: "window-refresh-rate-changed";
+      window: WSIWindow;
+      refreshRate: number | null;
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.Resized
       type: "window-resized";
       window: WSIWindow;
       innerSize: [number, number];
     }
+    | {
+      // The window's safe area insets changed, as [top, right, bottom, left]
+      // in physical pixels.
+      type: "window-safe-area-changed";
+      window: WSIWindow;
+      insets: [number, number, number, number];
+    }
     | {
       // https://docs.rs/winit/0.28.1/winit/event/enum.WindowEvent.html#variant.ThemeChanged
       type: "window-theme-changed";
@@ -354,6 +745,23 @@ declare namespace Deno {
       theme: WSIWindowTheme;
     };
 
+  export interface WSINextEventOptions {
+    // Aborting rejects the call with the signal's reason. The event it was
+    // waiting for goes to the next call instead.
+    signal?: AbortSignal;
+    // Resolve with null if no event arrives within this many milliseconds.
+    timeoutMs?: number;
+  }
+
+  export interface WSIGPUSurfaceOptions {
+    // Reconfigure the surface with its last configuration and retry when
+    // getCurrentTexture() finds it outdated or lost.
+    autoReconfigure?: boolean;
+    // Reconfigure the surface to the window's inner size whenever the window
+    // is resized, before the next getCurrentTexture().
+    autoResize?: boolean;
+  }
+
   // https://docs.rs/winit/0.28.1/winit/window/enum.ImePurpose.html
   export type WSIIMEPurpose =
     | "normal"
@@ -527,6 +935,25 @@ declare namespace Deno {
     | "cut";
 
   // https://docs.rs/winit/0.28.1/winit/event/struct.ModifiersState.html
+  // Latencies in milliseconds over the most recent input events.
+  export type WSILatencyPercentiles = {
+    count: number;
+    p50: number;
+    p90: number;
+    p99: number;
+    max: number;
+  };
+
+  export type WSIMetrics = {
+    // Only measured when running with --wsi-measure-latency.
+    inputLatency: {
+      // From delivery by the window system to Deno.wsi.nextEvent returning.
+      toReceipt: WSILatencyPercentiles;
+      // From delivery by the window system to the window's next present.
+      toPresent: WSILatencyPercentiles;
+    } | null;
+  };
+
   export type WSIModifierKeys = number;
   export class WSIModifierKey {
     static SHIFT: 0o0004;
@@ -535,6 +962,21 @@ declare namespace Deno {
     static GUI: 0o4000;
   }
 
+  // https://docs.rs/winit/0.28.1/winit/monitor/struct.MonitorHandle.html
+  export type WSIMonitor = {
+    name: string | null;
+    primary: boolean;
+    position: [number, number];
+    size: [number, number];
+    // The part of the monitor not covered by taskbars, docks or menu bars, as
+    // [x, y, width, height]. Only known on Windows; elsewhere it's the whole
+    // monitor.
+    workArea: [number, number, number, number];
+    scaleFactor: number;
+    // In millihertz.
+    refreshRate: number | null;
+  };
+
   // https://docs.rs/winit/0.28.1/winit/event/enum.MouseButton.html
   export type WSIMouseButton =
     | "left"
@@ -548,6 +990,30 @@ declare namespace Deno {
     y: number;
   };
 
+  // How far the monitor is rotated clockwise, in degrees, and whether that
+  // makes it portrait or landscape.
+  export type WSIOrientation = {
+    type: WSIOrientationType;
+    angle: number;
+  };
+
+  // https://w3c.github.io/screen-orientation/#dom-orientationlocktype
+  export type WSIOrientationLockType =
+    | "any"
+    | "landscape"
+    | "landscape-primary"
+    | "landscape-secondary"
+    | "portrait"
+    | "portrait-primary"
+    | "portrait-secondary";
+
+  // https://w3c.github.io/screen-orientation/#dom-orientationtype
+  export type WSIOrientationType =
+    | "landscape-primary"
+    | "landscape-secondary"
+    | "portrait-primary"
+    | "portrait-secondary";
+
   // https://docs.rs/winit/0.28.1/winit/window/enum.ResizeDirection.html
   export type WSIResizeDirection =
     | "east"
@@ -559,6 +1025,14 @@ declare namespace Deno {
     | "southwest"
     | "west";
 
+  export type WSISavedWindow = {
+    name: string;
+    position: [number, number] | null;
+    innerSize: [number, number];
+    maximized: boolean;
+    fullscreen: boolean;
+  };
+
   // https://docs.rs/winit/0.28.1/winit/event/enum.MouseScrollDelta.html
   export type WSIScrollDelta = {
     type: "line-delta" | "pixel-delta";
@@ -586,6 +1060,14 @@ declare namespace Deno {
     | "ended"
     | "cancelled";
 
+  // https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-itaskbarlist3-setprogressstate
+  export type WSITaskbarProgressState =
+    | "none"
+    | "indeterminate"
+    | "normal"
+    | "error"
+    | "paused";
+
   // https://docs.rs/winit/0.28.1/winit/window/enum.UserAttentionType.html
   export type WSIUserAttentionType =
     | "critical"
@@ -593,30 +1075,57 @@ declare namespace Deno {
 
   // https://docs.rs/winit/0.28.1/winit/window/struct.Window.html
   export class WSIWindow {
+    getSupportedBackdrops(): WSIWindowBackdrop[];
+    setBackdrop(backdrop: WSIWindowBackdrop): boolean;
+    isContentProtected(): boolean | null;
     setContentProtected(contentProtected?: boolean): void;
+    setCornerPreference(corners: WSIWindowCorners): void;
+    setTitleBarStyle(style: WSITitleBarStyle): void;
+    getCoordinateSpace(): WSICoordinateSpace;
+    setCoordinateSpace(space: WSICoordinateSpace): void;
+    setCursorConfinementRect(
+      rect: [number, number, number, number] | null,
+    ): void;
     setCursorGrabMode(mode: WSICursorGrabMode): void;
     setCursorHitTestEnabled(enabled?: boolean): void;
+    setCursorHitTestRegion(
+      rects: [number, number, number, number][] | null,
+    ): void;
     setCursorIcon(icon: WSICursorIcon): void;
     setCursorPosition(position: [number, number]): void;
     setCursorPosition(x: number, y: number): void;
+    getCursorPosition(): [number, number] | null;
+    clientToDesktop(position: [number, number]): [number, number];
+    desktopToClient(position: [number, number]): [number, number];
     setCursorVisible(visible?: boolean): void;
     isDecorated(): boolean;
     setDecorated(decorated?: boolean): void;
-    getEnabledButtons(): WSIWindowButtons;
+    setDiagnosticsOverlay(enabled?: boolean): void;
+    isEnabled(): boolean;
+    setEnabled(enabled?: boolean): void;
+    // A bitfield of `WSIWindowButton` constants.
+    getEnabledButtons(): number;
+    getEnabledButtonsDict(): Required<WSIWindowButtonsDict>;
     setEnabledButtons(buttons: WSIWindowButtons): void;
     hasFocus(): boolean;
     takeFocus(): void;
     isFullscreen(): boolean;
     setFullscreen(fullscreen?: boolean): void;
-    getGPUSurface(): GPUSurface;
+    getGPUSurface(options?: WSIGPUSurfaceOptions): GPUSurface;
     setIMEAllowed(allowed?: boolean): void;
     setIMEPosition(position: [number, number]): void;
     setIMEPosition(x: number, y: number): void;
     setIMEPurpose(purpose: WSIIMEPurpose): void;
+    resetDeadKeys(): void;
+    showSoftKeyboard(): boolean;
+    hideSoftKeyboard(): boolean;
+    getSoftKeyboardRect(): [number, number, number, number] | null;
+    setSystemShortcutsInhibited(inhibited?: boolean): boolean;
     getInnerPosition(): [number, number];
     getOuterPosition(): [number, number];
     setOuterPosition(position: [number, number]): void;
     setOuterPosition(x: number, y: number): void;
+    center(): void;
     getInnerSize(): [number, number];
     getOuterSize(): [number, number];
     setInnerSize(size: [number, number]): void;
@@ -632,10 +1141,27 @@ declare namespace Deno {
     setMaximized(maximized?: boolean): void;
     isResizable(): boolean;
     setResizable(resizable?: boolean): void;
+    setShadow(shadow?: boolean): void;
+    setShape(shape: WSIWindowShape | null): boolean;
     getResizeIncrements(): [number, number] | null;
     setResizeIncrements(size: [number, number] | null): void;
     setResizeIncrements(width: number, height: number): void;
+    setAspectRatio(ratio: [number, number] | null): void;
+    setAspectRatio(width: number, height: number): void;
+    fitInnerSize(size: [number, number]): void;
+    fitInnerSize(width: number, height: number): void;
     getScaleFactor(): number;
+    getRefreshRate(): number | null;
+    getColorProfile(): Uint8Array | null;
+    getSupportedDisplayControls(): WSIDisplayControl[];
+    getBrightness(): number | null;
+    setBrightness(brightness: number): boolean;
+    setGamma(gamma: number): boolean;
+    setSkipTaskbar(skip?: boolean): void;
+    getTag(): unknown;
+    setTaskbarProgress(state: WSITaskbarProgressState, value?: number): void;
+    setTaskbarOverlay(icon: BufferSource | null, description?: string): void;
+    setBadge(count: number | null): boolean;
     getTheme(): WSIWindowTheme | null;
     setTheme(theme: WSIWindowTheme | null): void;
     getTitle(): string;
@@ -643,26 +1169,90 @@ declare namespace Deno {
     setTransparent(transparent?: boolean): void;
     isVisible(): boolean | null;
     setVisible(visible?: boolean): void;
+    getVisibleRect(): [number, number, number, number] | null;
+    getVisibleFraction(): number | null;
+    getSafeAreaInsets(): [number, number, number, number];
+    getOrientation(): WSIOrientation | null;
+    lockOrientation(lock: WSIOrientationLockType): boolean;
+    unlockOrientation(): void;
     beginDragMove(): void;
     beginDragResize(direction: WSIResizeDirection): void;
+    setDropTypes(types: WSIDropType[]): void;
+    startDrag(data: WSIDragData, image?: BufferSource | null): boolean;
     requestRedraw(): void;
     requestUserAttention(type: WSIUserAttentionType | null): void;
+    captureContent(): Uint8Array | null;
     destroy(): void;
   }
 
+  // The part of a window that's shown and takes clicks: the union of
+  // `[x, y, width, height]` rectangles, each with rounded corners.
+  export interface WSIWindowShape {
+    rects: [number, number, number, number][];
+    cornerRadius?: number;
+  }
+
+  // Which title bar buttons are enabled. Buttons that are left out are enabled.
+  export interface WSIWindowButtonsDict {
+    close?: boolean;
+    minimize?: boolean;
+    maximize?: boolean;
+  }
+
+  // A bitfield of `WSIWindowButton` constants is still accepted, but the names
+  // are clearer.
   // https://docs.rs/winit/0.28.1/winit/window/struct.WindowButtons.html
-  export type WSIWindowButtons = number;
+  export type WSIWindowButtons = WSIWindowButtonsDict | number;
   export class WSIWindowButton {
     static CLOSE: 0b001;
     static MINIMIZE: 0b010;
     static MAXIMIZE: 0b100;
   }
 
+  // What the window system draws behind the transparent parts of a window:
+  // "blur" needs KWin on X11, "acrylic" and "mica" need Windows 11.
+  export type WSIWindowBackdrop =
+    | "none"
+    | "blur"
+    | "acrylic"
+    | "mica";
+
+  // https://learn.microsoft.com/en-us/windows/win32/api/dwmapi/ne-dwmapi-dwm_window_corner_preference
+  export type WSIWindowCorners =
+    | "default"
+    | "round"
+    | "round-small"
+    | "square";
+
+  // Colors are `[r, g, b]`. Left out, they're the system's default, and
+  // `darkMode` is left to the window's theme.
+  // https://learn.microsoft.com/en-us/windows/win32/api/dwmapi/ne-dwmapi-dwmwindowattribute
+  export interface WSITitleBarStyle {
+    darkMode?: boolean;
+    captionColor?: [number, number, number];
+    borderColor?: [number, number, number];
+    textColor?: [number, number, number];
+  }
+
+  // What `Deno.wsi.windows()` reports about a live window. `visible` is `null`
+  // where the window system can't tell, as on Wayland.
+  export type WSIWindowInfo = {
+    window: WSIWindow;
+    title: string;
+    focused: boolean;
+    visible: boolean | null;
+    monitor: WSIMonitor | null;
+    tag: unknown;
+  };
+
   // https://docs.rs/winit/0.28.1/winit/window/enum.WindowLevel.html
+  // "always-on-top-of-fullscreen" also stays above other apps' fullscreen
+  // windows on macOS, and is the same as "always-on-top" elsewhere.
   export type WSIWindowLevel =
     | "always-on-bottom"
     | "normal"
-    | "always-on-top";
+    | "always-on-top"
+    | "always-on-top-of-fullscreen";
 
   // https://docs.rs/winit/0.28.1/winit/window/enum.Theme.html
   export type WSIWindowTheme =
@@ -46,6 +46,7 @@ declare class GPUSupportedLimits {
   maxComputeWorkgroupSizeY?: number;
   maxComputeWorkgroupSizeZ?: number;
   maxComputeWorkgroupsPerDimension?: number;
+  maxPushConstantSize?: number;
 }
 
 /** @category WebGPU */
@@ -82,6 +83,71 @@ declare class GPU {
   requestAdapter(
     options?: GPURequestAdapterOptions,
   ): Promise<GPUAdapter | null>;
+  startTrace(directory: string): void;
+  stopTrace(): void;
+  isRenderDocAttached(): boolean;
+  triggerRenderDocCapture(frames?: number): void;
+  startRenderDocCapture(): void;
+  endRenderDocCapture(): boolean;
+  validateWGSL(code: string): GPUCompilationMessage[];
+  translateSPIRVToWGSL(spirv: BufferSource): GPUShaderTranslation;
+  translateGLSLToWGSL(
+    code: string,
+    stage: "vertex" | "fragment" | "compute",
+  ): GPUShaderTranslation;
+  reflectWGSL(code: string): GPUShaderReflection;
+  createSurfaceFromRawHandles(handles: GPURawSurfaceHandles): GPUSurface;
+}
+
+/** @category WebGPU */
+declare type GPURawWindowSystem =
+  | "win32"
+  | "xlib"
+  | "xcb"
+  | "wayland"
+  | "appkit";
+
+/** Raw handles of a window created outside of Denog. Pointers are
+ * `Deno.PointerObject`s obtained through FFI.
+ *
+ * | system      | window                | display                      |
+ * | ----------- | --------------------- | ---------------------------- |
+ * | `"win32"`   | `HWND`                | `HINSTANCE` (optional)       |
+ * | `"xlib"`    | `Window` id           | `Display*`                   |
+ * | `"xcb"`     | `xcb_window_t` id     | `xcb_connection_t*`          |
+ * | `"wayland"` | `wl_surface*`         | `wl_display*`                |
+ * | `"appkit"`  | `NSView*`             | `NSWindow*` (optional)       |
+ *
+ * @category WebGPU
+ */
+declare interface GPURawSurfaceHandles {
+  system: GPURawWindowSystem;
+  window: object | number | bigint;
+  display?: object | null;
+}
+
+/** @category WebGPU */
+declare interface GPUShaderTranslation {
+  code: string | null;
+  messages: GPUCompilationMessage[];
+}
+
+/** @category WebGPU */
+declare interface GPUShaderReflection {
+  bindGroups: GPUReflectedBindGroup[];
+  messages: GPUCompilationMessage[];
+}
+
+/** @category WebGPU */
+declare interface GPUReflectedBindGroup {
+  group: number;
+  entries: GPUReflectedBinding[];
+}
+
+/** @category WebGPU */
+declare interface GPUReflectedBinding extends GPUBindGroupLayoutEntry {
+  name: string | null;
+  count: number | null;
 }
 
 /** @category WebGPU */
@@ -127,6 +193,7 @@ declare type GPUFeatureName =
   | "sampled-texture-array-dynamic-indexing"
   | "sampled-texture-array-non-uniform-indexing"
   | "unsized-binding-array"
+  | "partially-bound-binding-array"
   | "multi-draw-indirect"
   | "multi-draw-indirect-count"
   | "push-constants"
@@ -183,6 +250,8 @@ declare class GPUDevice extends EventTarget implements GPUObjectBase {
   ): GPURenderBundleEncoder;
 
   createQuerySet(descriptor: GPUQuerySetDescriptor): GPUQuerySet;
+
+  createProfiler(descriptor?: GPUProfilerDescriptor): GPUProfiler;
 }
 
 /** @category WebGPU */
@@ -466,6 +535,7 @@ declare interface GPUBindGroupLayoutEntry {
   sampler?: GPUSamplerBindingLayout;
   texture?: GPUTextureBindingLayout;
   storageTexture?: GPUStorageTextureBindingLayout;
+  count?: number;
 }
 
 /** @category WebGPU */
@@ -539,7 +609,10 @@ declare interface GPUBindGroupDescriptor extends GPUObjectDescriptorBase {
 declare type GPUBindingResource =
   | GPUSampler
   | GPUTextureView
-  | GPUBufferBinding;
+  | GPUBufferBinding
+  | GPUSampler[]
+  | GPUTextureView[]
+  | GPUBufferBinding[];
 
 /** @category WebGPU */
 declare interface GPUBindGroupEntry {
@@ -562,6 +635,14 @@ declare class GPUPipelineLayout implements GPUObjectBase {
 /** @category WebGPU */
 declare interface GPUPipelineLayoutDescriptor extends GPUObjectDescriptorBase {
   bindGroupLayouts: GPUBindGroupLayout[];
+  pushConstantRanges?: GPUPushConstantRange[];
+}
+
+/** @category WebGPU */
+declare interface GPUPushConstantRange {
+  stages: GPUShaderStageFlags;
+  start: number;
+  end: number;
 }
 
 /** @category WebGPU */
@@ -573,6 +654,8 @@ declare interface GPUCompilationMessage {
   readonly type: GPUCompilationMessageType;
   readonly lineNum: number;
   readonly linePos: number;
+  readonly offset: number;
+  readonly length: number;
 }
 
 /** @category WebGPU */
@@ -956,6 +1039,7 @@ declare class GPUComputePassEncoder
     dynamicOffsetsDataStart: number,
     dynamicOffsetsDataLength: number,
   ): undefined;
+  setPushConstants(offset: number, data: BufferSource): undefined;
   pushDebugGroup(groupLabel: string): undefined;
   popDebugGroup(): undefined;
   insertDebugMarker(markerLabel: string): undefined;
@@ -1034,6 +1118,11 @@ declare class GPURenderPassEncoder
     dynamicOffsetsDataStart: number,
     dynamicOffsetsDataLength: number,
   ): undefined;
+  setPushConstants(
+    stages: GPUShaderStageFlags,
+    offset: number,
+    data: BufferSource,
+  ): undefined;
   pushDebugGroup(groupLabel: string): undefined;
   popDebugGroup(): undefined;
   insertDebugMarker(markerLabel: string): undefined;
@@ -1068,6 +1157,30 @@ declare class GPURenderPassEncoder
     indirectBuffer: GPUBuffer,
     indirectOffset: number,
   ): undefined;
+  multiDrawIndirect(
+    indirectBuffer: GPUBuffer,
+    indirectOffset: number,
+    count: number,
+  ): undefined;
+  multiDrawIndexedIndirect(
+    indirectBuffer: GPUBuffer,
+    indirectOffset: number,
+    count: number,
+  ): undefined;
+  multiDrawIndirectCount(
+    indirectBuffer: GPUBuffer,
+    indirectOffset: number,
+    countBuffer: GPUBuffer,
+    countBufferOffset: number,
+    maxCount: number,
+  ): undefined;
+  multiDrawIndexedIndirectCount(
+    indirectBuffer: GPUBuffer,
+    indirectOffset: number,
+    countBuffer: GPUBuffer,
+    countBufferOffset: number,
+    maxCount: number,
+  ): undefined;
 
   setViewport(
     x: number,
@@ -1192,6 +1305,11 @@ declare class GPURenderBundleEncoder
     size?: number,
   ): undefined;
   setPipeline(pipeline: GPURenderPipeline): undefined;
+  setPushConstants(
+    stages: GPUShaderStageFlags,
+    offset: number,
+    data: BufferSource,
+  ): undefined;
   setVertexBuffer(
     slot: number,
     buffer: GPUBuffer,
@@ -1237,6 +1355,14 @@ declare class GPUQueue implements GPUObjectBase {
     dataLayout: GPUImageDataLayout,
     size: GPUExtent3D,
   ): undefined;
+
+  /** Copies a region of a texture into a new `ArrayBuffer`, waiting for
+   * previously submitted work to finish. Rows of texel blocks are tightly
+   * packed, so no `bytesPerRow` padding has to be stripped. */
+  readTexture(
+    source: GPUImageCopyTexture,
+    size: GPUExtent3D,
+  ): ArrayBuffer;
 }
 
 /** @category WebGPU */
@@ -1328,8 +1454,14 @@ declare type GPUExtent3D = number[] | GPUExtent3DDict;
 /** @category WebGPU */
 declare class GPUSurface {
   getCapabilities(adapter: GPUAdapter): GPUSurfaceCapabilities;
+  getPreferredConfiguration(
+    adapter: GPUAdapter,
+  ): GPUSurfacePreferredConfiguration;
   configure(device: GPUDevice, config: GPUSurfaceConfiguration): void;
   getCurrentTexture(): GPUSurfaceTexture;
+  getPresentStatistics(): GPUSurfacePresentStatistics | null;
+  startFrameCapture(options: GPUSurfaceFrameCaptureOptions): void;
+  stopFrameCapture(): void;
 }
 
 /** @category WebGPU */
@@ -1339,6 +1471,13 @@ declare interface GPUSurfaceCapabilities {
   alphaModes: GPUSurfaceAlphaMode[];
 }
 
+/** @category WebGPU */
+declare interface GPUSurfacePreferredConfiguration {
+  format: GPUTextureFormat;
+  presentMode: GPUSurfacePresentMode;
+  alphaMode: GPUSurfaceAlphaMode;
+}
+
 /** @category WebGPU */
 declare type GPUSurfacePresentMode =
   | "auto-vsync"
@@ -1366,8 +1505,58 @@ declare interface GPUSurfaceConfiguration {
   viewFormats?: GPUTextureFormat[];
 }
 
+/** @category WebGPU */
+declare interface GPUSurfacePresentStatistics {
+  presentCount: number;
+  presentDuration: number;
+  interval: number | null;
+  missedVsyncs: number | null;
+}
+
+/** @category WebGPU */
+declare interface GPUSurfaceFrameCaptureOptions {
+  directory: string;
+  interval?: number;
+}
+
 /** @category WebGPU */
 declare class GPUSurfaceTexture extends GPUTexture {
   readonly isSuboptimal: boolean;
-  present(): void;
+  present(options?: GPUSurfacePresentOptions): void;
+}
+
+/** @category WebGPU */
+declare interface GPUSurfacePresentOptions {
+  damage?: GPUSurfaceDamageRect[];
+}
+
+/** @category WebGPU */
+declare interface GPUSurfaceDamageRect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+/** @category WebGPU */
+declare interface GPUProfilerDescriptor {
+  maxScopes?: number;
+}
+
+/** @category WebGPU */
+declare class GPUProfiler {
+  static requiredFeatures(adapter: GPUAdapter): GPUFeatureName[];
+  readonly enabled: boolean;
+  beginScope(encoder: GPUCommandEncoder, label: string): void;
+  endScope(encoder: GPUCommandEncoder): void;
+  resolve(encoder: GPUCommandEncoder): void;
+  getResults(): Promise<GPUProfilerScopeResult[]>;
+  destroy(): void;
+}
+
+/** @category WebGPU */
+declare interface GPUProfilerScopeResult {
+  label: string;
+  depth: number;
+  milliseconds: number;
 }
@@ -19,6 +19,8 @@ use deno_graph::npm::NpmPackageReqReference;
 use deno_runtime::colors;
 use deno_runtime::deno_node;
 use deno_runtime::deno_wsi::event_loop::WsiEventLoopProxy;
+use deno_runtime::deno_wsi::hot_reload;
+use deno_runtime::deno_wsi::WsiOptions;
 use deno_runtime::fmt_errors::format_js_error;
 use deno_runtime::ops::worker_host::CreateWebWorkerCb;
 use deno_runtime::ops::worker_host::WorkerEventCb;
@@ -170,6 +172,9 @@ impl CliMainWorker {
             .worker
             .dispatch_unload_event(&located_script_name!());
         }
+        // Hand windows, GPU surfaces and devices to the next run with --wsi.
+        let op_state = self.inner.worker.js_runtime.op_state();
+        hot_reload::preserve(&mut op_state.borrow_mut());
       }
     }
 
@@ -423,6 +428,7 @@ pub async fn create_main_worker_for_test_or_bench(
   ps: &ProcState,
   main_module: ModuleSpecifier,
   permissions: PermissionsContainer,
+  wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
   custom_extensions: Vec<Extension>,
   stdio: deno_runtime::deno_io::Stdio,
 ) -> Result<CliMainWorker, AnyError> {
@@ -430,7 +436,7 @@ pub async fn create_main_worker_for_test_or_bench(
     ps,
     main_module,
     permissions,
-    None,
+    wsi_event_loop_proxy,
     custom_extensions,
     stdio,
     true,
@@ -503,6 +509,12 @@ async fn create_main_worker_internal(
       .join("deno_cache")
       .join(checksum::gen(&[key.as_bytes()]))
   });
+  let wsi_options = WsiOptions {
+    session_file: origin_storage_dir
+      .as_ref()
+      .map(|dir| dir.join("wsi_session.json")),
+    ..ps.options.wsi_options()?
+  };
 
   let mut extensions = ops::cli_exts(ps.clone());
   extensions.append(&mut custom_extensions);
@@ -528,6 +540,7 @@ async fn create_main_worker_internal(
       unstable: ps.options.unstable(),
       user_agent: version::get_user_agent(),
       inspect: ps.options.is_inspecting(),
+      gpu_backend: ps.options.gpu_backend(),
     },
     extensions,
     startup_snapshot: Some(crate::js::deno_isolate_init()),
@@ -556,6 +569,7 @@ async fn create_main_worker_internal(
     compiled_wasm_module_store: Some(ps.compiled_wasm_module_store.clone()),
     stdio,
     wsi_event_loop_proxy,
+    wsi_options,
   };
 
   let mut worker = MainWorker::bootstrap_from_options(
@@ -697,6 +711,7 @@ fn create_web_worker_callback(
         unstable: ps.options.unstable(),
         user_agent: version::get_user_agent(),
         inspect: ps.options.is_inspecting(),
+        gpu_backend: ps.options.gpu_backend(),
       },
       extensions,
       startup_snapshot: Some(crate::js::deno_isolate_init()),
@@ -763,6 +778,7 @@ mod tests {
         unstable: false,
         user_agent: "x".to_string(),
         inspect: false,
+        gpu_backend: None,
       },
       extensions: vec![],
       startup_snapshot: Some(crate::js::deno_isolate_init()),
@@ -788,6 +804,7 @@ mod tests {
       compiled_wasm_module_store: None,
       stdio: Default::default(),
       wsi_event_loop_proxy: None,
+      wsi_options: Default::default(),
     };
 
     MainWorker::bootstrap_from_options(main_module, permissions, options)
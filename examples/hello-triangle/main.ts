@@ -40,7 +40,8 @@ const layout = device.createPipelineLayout({
 });
 
 // Choose GPUTextureFormat.
-const format = surface.getCapabilities(adapter).formats[0];
+const preferred = surface.getPreferredConfiguration(adapter);
+const { format } = preferred;
 
 // Create GPURenderPipeline.
 const pipeline = device.createRenderPipeline({
@@ -60,7 +61,7 @@ const pipeline = device.createRenderPipeline({
 
 // Configure surface.
 const config = {
-  format,
+  ...preferred,
   size: window.getInnerSize(),
 };
 surface.configure(device, config);
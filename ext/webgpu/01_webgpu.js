@@ -14,15 +14,16 @@ import * as webidl from "ext:deno_webidl/00_webidl.js";
 import { EventTarget } from "ext:deno_web/02_event.js";
 import DOMException from "ext:deno_web/01_dom_exception.js";
 const {
-  ArrayBuffer,
   ArrayBufferIsView,
   ArrayIsArray,
   ArrayPrototypeFilter,
   ArrayPrototypeMap,
   ArrayPrototypePop,
   ArrayPrototypePush,
+  BigUint64Array,
   Error,
   MathMax,
+  Number,
   ObjectDefineProperty,
   ObjectPrototypeIsPrototypeOf,
   Promise,
@@ -79,6 +80,20 @@ const _configuration = Symbol("[[configuration]]");
 const _currentTexture = Symbol("[[currentTexture]]");
 const _surface = Symbol("[[surface]]");
 const _isSuboptimal = Symbol("[[isSuboptimal]]");
+const _offscreen = Symbol("[[offscreen]]");
+const _onReconfigured = Symbol("[[onReconfigured]]");
+const _getOffscreenSize = Symbol("[[getOffscreenSize]]");
+const _presentStatistics = Symbol("[[presentStatistics]]");
+const _profiledDevice = Symbol("[[profiledDevice]]");
+const _querySet = Symbol("[[querySet]]");
+const _resolveBuffer = Symbol("[[resolveBuffer]]");
+const _readBuffer = Symbol("[[readBuffer]]");
+const _maxScopes = Symbol("[[maxScopes]]");
+const _scopes = Symbol("[[scopes]]");
+const _openScopes = Symbol("[[openScopes]]");
+const _resolved = Symbol("[[resolved]]");
+const _code = Symbol("[[code]]");
+const _onDeviceLost = Symbol("[[onDeviceLost]]");
 
 /**
  * @param {any} self
@@ -186,6 +201,16 @@ function normalizeGPUColor(data) {
   }
 }
 
+/**
+ * @param {BufferSource} data
+ * @returns {Uint8Array} the bytes of `data`, without copying
+ */
+function bufferSourceBytes(data) {
+  return ArrayBufferIsView(data)
+    ? new Uint8Array(data.buffer, data.byteOffset, data.byteLength)
+    : new Uint8Array(data);
+}
+
 const illegalConstructorKey = Symbol("illegalConstructorKey");
 class GPUError extends Error {
   constructor(key = null) {
@@ -276,6 +301,160 @@ class GPU {
     }
   }
 
+  /**
+   * Devices requested from now on record their API calls to the given
+   * directory, which wgpu's player can replay.
+   * @param {string} directory
+   */
+  startTrace(directory) {
+    webidl.assertBranded(this, GPUPrototype);
+    const prefix = "Failed to execute 'startTrace' on 'GPU'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    directory = webidl.converters["DOMString"](directory, {
+      prefix,
+      context: "Argument 1",
+    });
+    ops.op_webgpu_start_trace(directory);
+  }
+
+  stopTrace() {
+    webidl.assertBranded(this, GPUPrototype);
+    ops.op_webgpu_stop_trace();
+  }
+
+  /**
+   * Whether the program was launched from RenderDoc.
+   * @returns {boolean}
+   */
+  isRenderDocAttached() {
+    webidl.assertBranded(this, GPUPrototype);
+    return ops.op_webgpu_renderdoc_is_attached();
+  }
+
+  /**
+   * Captures the next frames presented to the active window with RenderDoc.
+   * @param {number} frames
+   */
+  triggerRenderDocCapture(frames = 1) {
+    webidl.assertBranded(this, GPUPrototype);
+    const prefix = "Failed to execute 'triggerRenderDocCapture' on 'GPU'";
+    frames = webidl.converters["unsigned long"](frames, {
+      prefix,
+      context: "Argument 1",
+    });
+    ops.op_webgpu_renderdoc_trigger_capture(frames);
+  }
+
+  /**
+   * Starts capturing all GPU work with RenderDoc until endRenderDocCapture().
+   */
+  startRenderDocCapture() {
+    webidl.assertBranded(this, GPUPrototype);
+    ops.op_webgpu_renderdoc_start_capture();
+  }
+
+  /**
+   * @returns {boolean} whether a capture was saved
+   */
+  endRenderDocCapture() {
+    webidl.assertBranded(this, GPUPrototype);
+    return ops.op_webgpu_renderdoc_end_capture();
+  }
+
+  /**
+   * Parses and validates WGSL without a device.
+   * @param {string} code
+   * @returns {GPUCompilationMessage[]}
+   */
+  validateWGSL(code) {
+    webidl.assertBranded(this, GPUPrototype);
+    const prefix = "Failed to execute 'validateWGSL' on 'GPU'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    code = webidl.converters["USVString"](code, {
+      prefix,
+      context: "Argument 1",
+    });
+    return ops.op_webgpu_shader_validate_wgsl(code);
+  }
+
+  /**
+   * @param {BufferSource} spirv
+   * @returns {GPUShaderTranslation}
+   */
+  translateSPIRVToWGSL(spirv) {
+    webidl.assertBranded(this, GPUPrototype);
+    const prefix = "Failed to execute 'translateSPIRVToWGSL' on 'GPU'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    spirv = webidl.converters["BufferSource"](spirv, {
+      prefix,
+      context: "Argument 1",
+    });
+    return ops.op_webgpu_shader_spirv_to_wgsl(bufferSourceBytes(spirv));
+  }
+
+  /**
+   * @param {string} code
+   * @param {"vertex" | "fragment" | "compute"} stage
+   * @returns {GPUShaderTranslation}
+   */
+  translateGLSLToWGSL(code, stage) {
+    webidl.assertBranded(this, GPUPrototype);
+    const prefix = "Failed to execute 'translateGLSLToWGSL' on 'GPU'";
+    webidl.requiredArguments(arguments.length, 2, { prefix });
+    code = webidl.converters["USVString"](code, {
+      prefix,
+      context: "Argument 1",
+    });
+    stage = webidl.converters["DOMString"](stage, {
+      prefix,
+      context: "Argument 2",
+    });
+    return ops.op_webgpu_shader_glsl_to_wgsl(code, stage);
+  }
+
+  /**
+   * Describes the bind group layouts that a WGSL module's resources need.
+   * The entries can be passed to GPUDevice.createBindGroupLayout.
+   * @param {string} code
+   * @returns {GPUShaderReflection}
+   */
+  reflectWGSL(code) {
+    webidl.assertBranded(this, GPUPrototype);
+    const prefix = "Failed to execute 'reflectWGSL' on 'GPU'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    code = webidl.converters["USVString"](code, {
+      prefix,
+      context: "Argument 1",
+    });
+    return ops.op_webgpu_shader_reflect_wgsl(code);
+  }
+
+  /**
+   * Creates a surface for a window that Denog didn't create, e.g. one owned
+   * by a plugin host, from raw handles obtained through FFI.
+   * @param {GPURawSurfaceHandles} handles
+   * @returns {GPUSurface}
+   */
+  createSurfaceFromRawHandles(handles) {
+    webidl.assertBranded(this, GPUPrototype);
+    const prefix =
+      "Failed to execute 'createSurfaceFromRawHandles' on 'GPU'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    handles = webidl.converters.GPURawSurfaceHandles(handles, {
+      prefix,
+      context: "Argument 1",
+    });
+    // X11 windows are identified by an XID instead of a pointer.
+    const isX11 = handles.system === "xlib" || handles.system === "xcb";
+    const rid = ops.op_webgpu_create_surface_from_raw_handles(
+      handles.system,
+      isX11 ? null : handles.window,
+      isX11 ? Number(handles.window) : 0,
+      handles.display,
+    );
+    return createGPUSurface(rid);
+  }
+
   [SymbolFor("Deno.privateCustomInspect")](inspect) {
     return `${this.constructor.name} ${inspect({})}`;
   }
@@ -501,6 +680,7 @@ function createGPUSupportedLimits(limits) {
  * @property {number} maxComputeWorkgroupSizeY
  * @property {number} maxComputeWorkgroupSizeZ
  * @property {number} maxComputeWorkgroupsPerDimension
+ * @property {number} maxPushConstantSize
  */
 
 class GPUSupportedLimits {
@@ -622,6 +802,10 @@ class GPUSupportedLimits {
     webidl.assertBranded(this, GPUSupportedLimitsPrototype);
     return this[_limits].maxComputeWorkgroupsPerDimension;
   }
+  get maxPushConstantSize() {
+    webidl.assertBranded(this, GPUSupportedLimitsPrototype);
+    return this[_limits].maxPushConstantSize;
+  }
 
   [SymbolFor("Deno.privateCustomInspect")](inspect) {
     return `${this.constructor.name} ${inspect(this[_limits])}`;
@@ -942,14 +1126,12 @@ class GPUDevice extends EventTarget {
     let options;
     if (descriptor.mappedAtCreation) {
       options = {
-        mapping: new ArrayBuffer(descriptor.size),
         mappingRange: [0, descriptor.size],
         mappedRanges: [],
         state: "mapped at creation",
       };
     } else {
       options = {
-        mapping: null,
         mappedRanges: null,
         mappingRange: null,
         state: "unmapped",
@@ -1096,6 +1278,7 @@ class GPUDevice extends EventTarget {
       device.rid,
       descriptor.label,
       bindGroupLayouts,
+      descriptor.pushConstantRanges ?? [],
     );
     device.pushError(err);
 
@@ -1133,7 +1316,54 @@ class GPUDevice extends EventTarget {
     const entries = ArrayPrototypeMap(descriptor.entries, (entry, i) => {
       const context = `entry ${i + 1}`;
       const resource = entry.resource;
-      if (ObjectPrototypeIsPrototypeOf(GPUSamplerPrototype, resource)) {
+      if (ArrayIsArray(resource)) {
+        // A binding array, which is native-only.
+        let kind;
+        const elements = ArrayPrototypeMap(resource, (element, j) => {
+          const elementContext = `${context} element ${j + 1}`;
+          let elementKind;
+          let rid;
+          // The resource that belongs to a device.
+          let owner;
+          if (ObjectPrototypeIsPrototypeOf(GPUSamplerPrototype, element)) {
+            elementKind = "GPUSamplerArray";
+            rid = assertResource(element, { prefix, context: elementContext });
+            owner = element;
+          } else if (
+            ObjectPrototypeIsPrototypeOf(GPUTextureViewPrototype, element)
+          ) {
+            elementKind = "GPUTextureViewArray";
+            rid = assertResource(element, { prefix, context: elementContext });
+            owner = element[_texture];
+            assertResource(owner, { prefix, context: elementContext });
+          } else {
+            elementKind = "GPUBufferBindingArray";
+            owner = element.buffer;
+            rid = assertResource(owner, { prefix, context: elementContext });
+          }
+          if (kind !== undefined && kind !== elementKind) {
+            throw new TypeError(
+              `${prefix}: ${context} must not mix resource types.`,
+            );
+          }
+          kind = elementKind;
+          assertDeviceMatch(device, owner, {
+            prefix,
+            resourceContext: elementContext,
+            selfContext: "this",
+          });
+          return {
+            resource: rid,
+            offset: element.offset,
+            size: element.size,
+          };
+        });
+        return {
+          binding: entry.binding,
+          kind: kind ?? "GPUTextureViewArray",
+          elements,
+        };
+      } else if (ObjectPrototypeIsPrototypeOf(GPUSamplerPrototype, resource)) {
         const rid = assertResource(resource, {
           prefix,
           context,
@@ -1226,6 +1456,7 @@ class GPUDevice extends EventTarget {
       descriptor.label,
       device,
       rid,
+      descriptor.code,
     );
     device.trackResource(shaderModule);
     return shaderModule;
@@ -1461,6 +1692,21 @@ class GPUDevice extends EventTarget {
     return querySet;
   }
 
+  /**
+   * @param {GPUProfilerDescriptor} descriptor
+   * @returns {GPUProfiler}
+   */
+  createProfiler(descriptor = {}) {
+    webidl.assertBranded(this, GPUDevicePrototype);
+    const prefix = "Failed to execute 'createProfiler' on 'GPUDevice'";
+    descriptor = webidl.converters.GPUProfilerDescriptor(descriptor, {
+      prefix,
+      context: "Argument 1",
+    });
+    assertDevice(this, { prefix, context: "this" });
+    return createGPUProfiler(this, descriptor.maxScopes);
+  }
+
   get lost() {
     webidl.assertBranded(this, GPUDevicePrototype);
     const device = this[_device];
@@ -1637,7 +1883,7 @@ class GPUQueue {
       bufferOffset,
       dataOffset,
       size,
-      new Uint8Array(ArrayBufferIsView(data) ? data.buffer : data),
+      data,
     );
     device.pushError(err);
   }
@@ -1690,11 +1936,55 @@ class GPUQueue {
       },
       dataLayout,
       normalizeGPUExtent3D(size),
-      new Uint8Array(ArrayBufferIsView(data) ? data.buffer : data),
+      data,
     );
     device.pushError(err);
   }
 
+  /**
+   * Copies a region of a texture into a new ArrayBuffer, waiting for all
+   * previously submitted work to finish. Rows of texel blocks are tightly
+   * packed, without the 256 byte row alignment of texture-to-buffer copies.
+   * @param {GPUImageCopyTexture} source
+   * @param {GPUExtent3D} size
+   * @returns {ArrayBuffer}
+   */
+  readTexture(source, size) {
+    webidl.assertBranded(this, GPUQueuePrototype);
+    const prefix = "Failed to execute 'readTexture' on 'GPUQueue'";
+    webidl.requiredArguments(arguments.length, 2, { prefix });
+    source = webidl.converters.GPUImageCopyTexture(source, {
+      prefix,
+      context: "Argument 1",
+    });
+    size = webidl.converters.GPUExtent3D(size, {
+      prefix,
+      context: "Argument 2",
+    });
+    const device = assertDevice(this, { prefix, context: "this" });
+    const textureRid = assertResource(source.texture, {
+      prefix,
+      context: "texture",
+    });
+    assertDeviceMatch(device, source.texture, {
+      prefix,
+      selfContext: "this",
+      resourceContext: "texture",
+    });
+    const data = ops.op_webgpu_queue_read_texture(
+      device.rid,
+      {
+        texture: textureRid,
+        mipLevel: source.mipLevel,
+        origin: source.origin ? normalizeGPUOrigin3D(source.origin) : undefined,
+        aspect: source.aspect,
+      },
+      source.texture[_format],
+      normalizeGPUExtent3D(size),
+    );
+    return data.buffer;
+  }
+
   copyImageBitmapToTexture(_source, _destination, _copySize) {
     throw new Error("Not yet implemented");
   }
@@ -1712,7 +2002,6 @@ const GPUQueuePrototype = GPUQueue.prototype;
 
 /**
  * @typedef CreateGPUBufferOptions
- * @property {ArrayBuffer | null} mapping
  * @property {number[] | null} mappingRange
  * @property {[ArrayBuffer, number, number][] | null} mappedRanges
  * @property {"mapped" | "mapped at creation" | "mapped pending" | "unmapped" | "destroy" } state
@@ -1765,7 +2054,10 @@ class GPUBuffer {
       while (mappedRanges.length > 0) {
         const mappedRange = ArrayPrototypePop(mappedRanges);
         if (mappedRange !== undefined) {
-          core.close(mappedRange[1]);
+          ops.op_webgpu_buffer_detach_mapped_range(
+            mappedRange[1],
+            mappedRange[0],
+          );
         }
       }
     }
@@ -1947,12 +2239,13 @@ class GPUBuffer {
       }
     }
 
-    const buffer = new ArrayBuffer(rangeSize);
-    const { rid } = ops.op_webgpu_buffer_get_mapped_range(
+    // The returned ArrayBuffer points straight at the mapped memory and is
+    // detached again on unmap, so no copies are made in either direction. It
+    // can't be transferred.
+    const { rid, buffer } = ops.op_webgpu_buffer_get_mapped_range(
       bufferRid,
       offset,
       size,
-      new Uint8Array(buffer),
     );
 
     ArrayPrototypePush(mappedRanges, [buffer, rid, offset]);
@@ -1980,21 +2273,8 @@ class GPUBuffer {
     } else if (
       this[_state] === "mapped" || this[_state] === "mapped at creation"
     ) {
-      /** @type {boolean} */
-      let write = false;
-      if (this[_state] === "mapped at creation") {
-        write = true;
-      } else if (this[_state] === "mapped") {
-        const mapMode = this[_mapMode];
-        if (mapMode === undefined) {
-          throw new DOMException(
-            `${prefix}: invalid state.`,
-            "OperationError",
-          );
-        }
-        if ((mapMode & 0x0002) === 0x0002) {
-          write = true;
-        }
+      if (this[_state] === "mapped" && this[_mapMode] === undefined) {
+        throw new DOMException(`${prefix}: invalid state.`, "OperationError");
       }
 
       const mappedRanges = this[_mappedRanges];
@@ -2006,7 +2286,7 @@ class GPUBuffer {
         const { err } = ops.op_webgpu_buffer_unmap(
           bufferRid,
           mappedRid,
-          ...new SafeArrayIterator(write ? [new Uint8Array(buffer)] : []),
+          buffer,
         );
         device.pushError(err);
         if (err) return;
@@ -2480,12 +2760,13 @@ GPUObjectBaseMixin("GPUBindGroup", GPUBindGroup);
  * @param {number} rid
  * @returns {GPUShaderModule}
  */
-function createGPUShaderModule(label, device, rid) {
+function createGPUShaderModule(label, device, rid, code) {
   /** @type {GPUShaderModule} */
   const bindGroup = webidl.createBranded(GPUShaderModule);
   bindGroup[_label] = label;
   bindGroup[_device] = device;
   bindGroup[_rid] = rid;
+  bindGroup[_code] = code;
   return bindGroup;
 }
 class GPUShaderModule {
@@ -2493,6 +2774,8 @@ class GPUShaderModule {
   [_device];
   /** @type {number | undefined} */
   [_rid];
+  /** @type {string} */
+  [_code];
 
   [_cleanup]() {
     const rid = this[_rid];
@@ -2507,8 +2790,13 @@ class GPUShaderModule {
     webidl.illegalConstructor();
   }
 
+  /**
+   * @returns {Promise<GPUCompilationInfo>}
+   */
   compilationInfo() {
-    throw new Error("Not yet implemented");
+    webidl.assertBranded(this, GPUShaderModulePrototype);
+    const messages = ops.op_webgpu_shader_validate_wgsl(this[_code]);
+    return PromiseResolve({ messages });
   }
 
   [SymbolFor("Deno.privateCustomInspect")](inspect) {
@@ -2520,6 +2808,7 @@ class GPUShaderModule {
   }
 }
 GPUObjectBaseMixin("GPUShaderModule", GPUShaderModule);
+const GPUShaderModulePrototype = GPUShaderModule.prototype;
 
 class GPUShaderStage {
   constructor() {
@@ -3817,6 +4106,45 @@ class GPURenderPassEncoder {
     );
   }
 
+  /**
+   * @param {number} stages
+   * @param {number} offset
+   * @param {BufferSource} data
+   */
+  setPushConstants(stages, offset, data) {
+    webidl.assertBranded(this, GPURenderPassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'setPushConstants' on 'GPURenderPassEncoder'";
+    webidl.requiredArguments(arguments.length, 3, { prefix });
+    stages = webidl.converters.GPUShaderStageFlags(stages, {
+      prefix,
+      context: "Argument 1",
+    });
+    offset = webidl.converters.GPUSize32(offset, {
+      prefix,
+      context: "Argument 2",
+    });
+    data = webidl.converters.BufferSource(data, {
+      prefix,
+      context: "Argument 3",
+    });
+    assertDevice(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    assertResource(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    const renderPassRid = assertResource(this, { prefix, context: "this" });
+    ops.op_webgpu_render_pass_set_push_constants(
+      renderPassRid,
+      stages,
+      offset,
+      bufferSourceBytes(data),
+    );
+  }
+
   /**
    * @param {string} groupLabel
    */
@@ -4207,63 +4535,28 @@ class GPURenderPassEncoder {
     );
   }
 
-  [SymbolFor("Deno.privateCustomInspect")](inspect) {
-    return `${this.constructor.name} ${
-      inspect({
-        label: this.label,
-      })
-    }`;
-  }
-}
-GPUObjectBaseMixin("GPURenderPassEncoder", GPURenderPassEncoder);
-const GPURenderPassEncoderPrototype = GPURenderPassEncoder.prototype;
-
-/**
- * @param {string | null} label
- * @param {GPUCommandEncoder} encoder
- * @param {number} rid
- * @returns {GPUComputePassEncoder}
- */
-function createGPUComputePassEncoder(label, encoder, rid) {
-  /** @type {GPUComputePassEncoder} */
-  const computePassEncoder = webidl.createBranded(GPUComputePassEncoder);
-  computePassEncoder[_label] = label;
-  computePassEncoder[_encoder] = encoder;
-  computePassEncoder[_rid] = rid;
-  return computePassEncoder;
-}
-
-class GPUComputePassEncoder {
-  /** @type {GPUCommandEncoder} */
-  [_encoder];
-
-  /** @type {number | undefined} */
-  [_rid];
-
-  [_cleanup]() {
-    const rid = this[_rid];
-    if (rid !== undefined) {
-      core.close(rid);
-      /** @type {number | undefined} */
-      this[_rid] = undefined;
-    }
-  }
-
-  constructor() {
-    webidl.illegalConstructor();
-  }
-
   /**
-   * @param {GPUComputePipeline} pipeline
+   * @param {GPUBuffer} indirectBuffer
+   * @param {number} indirectOffset
+   * @param {number} count
    */
-  setPipeline(pipeline) {
-    webidl.assertBranded(this, GPUComputePassEncoderPrototype);
-    const prefix = "Failed to execute 'setPipeline' on 'GPUComputePassEncoder'";
-    webidl.requiredArguments(arguments.length, 1, { prefix });
-    pipeline = webidl.converters.GPUComputePipeline(pipeline, {
+  multiDrawIndirect(indirectBuffer, indirectOffset, count) {
+    webidl.assertBranded(this, GPURenderPassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'multiDrawIndirect' on 'GPURenderPassEncoder'";
+    webidl.requiredArguments(arguments.length, 3, { prefix });
+    indirectBuffer = webidl.converters.GPUBuffer(indirectBuffer, {
       prefix,
       context: "Argument 1",
     });
+    indirectOffset = webidl.converters.GPUSize64(indirectOffset, {
+      prefix,
+      context: "Argument 2",
+    });
+    count = webidl.converters.GPUSize32(count, {
+      prefix,
+      context: "Argument 3",
+    });
     const device = assertDevice(this[_encoder], {
       prefix,
       context: "encoder referenced by this",
@@ -4272,17 +4565,298 @@ class GPUComputePassEncoder {
       prefix,
       context: "encoder referenced by this",
     });
-    const computePassRid = assertResource(this, { prefix, context: "this" });
-    const pipelineRid = assertResource(pipeline, {
+    const renderPassRid = assertResource(this, { prefix, context: "this" });
+    const indirectBufferRid = assertResource(indirectBuffer, {
       prefix,
       context: "Argument 1",
     });
-    assertDeviceMatch(device, pipeline, {
+    assertDeviceMatch(device, indirectBuffer, {
       prefix,
       resourceContext: "Argument 1",
       selfContext: "this",
     });
-    ops.op_webgpu_compute_pass_set_pipeline(computePassRid, pipelineRid);
+    ops.op_webgpu_render_pass_multi_draw_indirect(
+      renderPassRid,
+      indirectBufferRid,
+      indirectOffset,
+      count,
+    );
+  }
+
+  /**
+   * @param {GPUBuffer} indirectBuffer
+   * @param {number} indirectOffset
+   * @param {number} count
+   */
+  multiDrawIndexedIndirect(indirectBuffer, indirectOffset, count) {
+    webidl.assertBranded(this, GPURenderPassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'multiDrawIndexedIndirect' on 'GPURenderPassEncoder'";
+    webidl.requiredArguments(arguments.length, 3, { prefix });
+    indirectBuffer = webidl.converters.GPUBuffer(indirectBuffer, {
+      prefix,
+      context: "Argument 1",
+    });
+    indirectOffset = webidl.converters.GPUSize64(indirectOffset, {
+      prefix,
+      context: "Argument 2",
+    });
+    count = webidl.converters.GPUSize32(count, {
+      prefix,
+      context: "Argument 3",
+    });
+    const device = assertDevice(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    assertResource(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    const renderPassRid = assertResource(this, { prefix, context: "this" });
+    const indirectBufferRid = assertResource(indirectBuffer, {
+      prefix,
+      context: "Argument 1",
+    });
+    assertDeviceMatch(device, indirectBuffer, {
+      prefix,
+      resourceContext: "Argument 1",
+      selfContext: "this",
+    });
+    ops.op_webgpu_render_pass_multi_draw_indexed_indirect(
+      renderPassRid,
+      indirectBufferRid,
+      indirectOffset,
+      count,
+    );
+  }
+
+  /**
+   * @param {GPUBuffer} indirectBuffer
+   * @param {number} indirectOffset
+   * @param {GPUBuffer} countBuffer
+   * @param {number} countBufferOffset
+   * @param {number} maxCount
+   */
+  multiDrawIndirectCount(
+    indirectBuffer,
+    indirectOffset,
+    countBuffer,
+    countBufferOffset,
+    maxCount,
+  ) {
+    webidl.assertBranded(this, GPURenderPassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'multiDrawIndirectCount' on 'GPURenderPassEncoder'";
+    webidl.requiredArguments(arguments.length, 5, { prefix });
+    indirectBuffer = webidl.converters.GPUBuffer(indirectBuffer, {
+      prefix,
+      context: "Argument 1",
+    });
+    indirectOffset = webidl.converters.GPUSize64(indirectOffset, {
+      prefix,
+      context: "Argument 2",
+    });
+    countBuffer = webidl.converters.GPUBuffer(countBuffer, {
+      prefix,
+      context: "Argument 3",
+    });
+    countBufferOffset = webidl.converters.GPUSize64(countBufferOffset, {
+      prefix,
+      context: "Argument 4",
+    });
+    maxCount = webidl.converters.GPUSize32(maxCount, {
+      prefix,
+      context: "Argument 5",
+    });
+    const device = assertDevice(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    assertResource(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    const renderPassRid = assertResource(this, { prefix, context: "this" });
+    const indirectBufferRid = assertResource(indirectBuffer, {
+      prefix,
+      context: "Argument 1",
+    });
+    assertDeviceMatch(device, indirectBuffer, {
+      prefix,
+      resourceContext: "Argument 1",
+      selfContext: "this",
+    });
+    const countBufferRid = assertResource(countBuffer, {
+      prefix,
+      context: "Argument 3",
+    });
+    assertDeviceMatch(device, countBuffer, {
+      prefix,
+      resourceContext: "Argument 3",
+      selfContext: "this",
+    });
+    ops.op_webgpu_render_pass_multi_draw_indirect_count(
+      renderPassRid,
+      indirectBufferRid,
+      indirectOffset,
+      countBufferRid,
+      countBufferOffset,
+      maxCount,
+    );
+  }
+
+  /**
+   * @param {GPUBuffer} indirectBuffer
+   * @param {number} indirectOffset
+   * @param {GPUBuffer} countBuffer
+   * @param {number} countBufferOffset
+   * @param {number} maxCount
+   */
+  multiDrawIndexedIndirectCount(
+    indirectBuffer,
+    indirectOffset,
+    countBuffer,
+    countBufferOffset,
+    maxCount,
+  ) {
+    webidl.assertBranded(this, GPURenderPassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'multiDrawIndexedIndirectCount' on 'GPURenderPassEncoder'";
+    webidl.requiredArguments(arguments.length, 5, { prefix });
+    indirectBuffer = webidl.converters.GPUBuffer(indirectBuffer, {
+      prefix,
+      context: "Argument 1",
+    });
+    indirectOffset = webidl.converters.GPUSize64(indirectOffset, {
+      prefix,
+      context: "Argument 2",
+    });
+    countBuffer = webidl.converters.GPUBuffer(countBuffer, {
+      prefix,
+      context: "Argument 3",
+    });
+    countBufferOffset = webidl.converters.GPUSize64(countBufferOffset, {
+      prefix,
+      context: "Argument 4",
+    });
+    maxCount = webidl.converters.GPUSize32(maxCount, {
+      prefix,
+      context: "Argument 5",
+    });
+    const device = assertDevice(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    assertResource(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    const renderPassRid = assertResource(this, { prefix, context: "this" });
+    const indirectBufferRid = assertResource(indirectBuffer, {
+      prefix,
+      context: "Argument 1",
+    });
+    assertDeviceMatch(device, indirectBuffer, {
+      prefix,
+      resourceContext: "Argument 1",
+      selfContext: "this",
+    });
+    const countBufferRid = assertResource(countBuffer, {
+      prefix,
+      context: "Argument 3",
+    });
+    assertDeviceMatch(device, countBuffer, {
+      prefix,
+      resourceContext: "Argument 3",
+      selfContext: "this",
+    });
+    ops.op_webgpu_render_pass_multi_draw_indexed_indirect_count(
+      renderPassRid,
+      indirectBufferRid,
+      indirectOffset,
+      countBufferRid,
+      countBufferOffset,
+      maxCount,
+    );
+  }
+
+  [SymbolFor("Deno.privateCustomInspect")](inspect) {
+    return `${this.constructor.name} ${
+      inspect({
+        label: this.label,
+      })
+    }`;
+  }
+}
+GPUObjectBaseMixin("GPURenderPassEncoder", GPURenderPassEncoder);
+const GPURenderPassEncoderPrototype = GPURenderPassEncoder.prototype;
+
+/**
+ * @param {string | null} label
+ * @param {GPUCommandEncoder} encoder
+ * @param {number} rid
+ * @returns {GPUComputePassEncoder}
+ */
+function createGPUComputePassEncoder(label, encoder, rid) {
+  /** @type {GPUComputePassEncoder} */
+  const computePassEncoder = webidl.createBranded(GPUComputePassEncoder);
+  computePassEncoder[_label] = label;
+  computePassEncoder[_encoder] = encoder;
+  computePassEncoder[_rid] = rid;
+  return computePassEncoder;
+}
+
+class GPUComputePassEncoder {
+  /** @type {GPUCommandEncoder} */
+  [_encoder];
+
+  /** @type {number | undefined} */
+  [_rid];
+
+  [_cleanup]() {
+    const rid = this[_rid];
+    if (rid !== undefined) {
+      core.close(rid);
+      /** @type {number | undefined} */
+      this[_rid] = undefined;
+    }
+  }
+
+  constructor() {
+    webidl.illegalConstructor();
+  }
+
+  /**
+   * @param {GPUComputePipeline} pipeline
+   */
+  setPipeline(pipeline) {
+    webidl.assertBranded(this, GPUComputePassEncoderPrototype);
+    const prefix = "Failed to execute 'setPipeline' on 'GPUComputePassEncoder'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    pipeline = webidl.converters.GPUComputePipeline(pipeline, {
+      prefix,
+      context: "Argument 1",
+    });
+    const device = assertDevice(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    assertResource(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    const computePassRid = assertResource(this, { prefix, context: "this" });
+    const pipelineRid = assertResource(pipeline, {
+      prefix,
+      context: "Argument 1",
+    });
+    assertDeviceMatch(device, pipeline, {
+      prefix,
+      resourceContext: "Argument 1",
+      selfContext: "this",
+    });
+    ops.op_webgpu_compute_pass_set_pipeline(computePassRid, pipelineRid);
   }
 
   /**
@@ -4531,11 +5105,44 @@ class GPUComputePassEncoder {
     }
     ops.op_webgpu_compute_pass_set_bind_group(
       computePassRid,
-      index,
-      bindGroupRid,
-      dynamicOffsetsData,
-      dynamicOffsetsDataStart,
-      dynamicOffsetsDataLength,
+      index,
+      bindGroupRid,
+      dynamicOffsetsData,
+      dynamicOffsetsDataStart,
+      dynamicOffsetsDataLength,
+    );
+  }
+
+  /**
+   * @param {number} offset
+   * @param {BufferSource} data
+   */
+  setPushConstants(offset, data) {
+    webidl.assertBranded(this, GPUComputePassEncoderPrototype);
+    const prefix =
+      "Failed to execute 'setPushConstants' on 'GPUComputePassEncoder'";
+    webidl.requiredArguments(arguments.length, 2, { prefix });
+    offset = webidl.converters.GPUSize32(offset, {
+      prefix,
+      context: "Argument 1",
+    });
+    data = webidl.converters.BufferSource(data, {
+      prefix,
+      context: "Argument 2",
+    });
+    assertDevice(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    assertResource(this[_encoder], {
+      prefix,
+      context: "encoder referenced by this",
+    });
+    const computePassRid = assertResource(this, { prefix, context: "this" });
+    ops.op_webgpu_compute_pass_set_push_constants(
+      computePassRid,
+      offset,
+      bufferSourceBytes(data),
     );
   }
 
@@ -4771,6 +5378,41 @@ class GPURenderBundleEncoder {
     );
   }
 
+  /**
+   * @param {number} stages
+   * @param {number} offset
+   * @param {BufferSource} data
+   */
+  setPushConstants(stages, offset, data) {
+    webidl.assertBranded(this, GPURenderBundleEncoderPrototype);
+    const prefix =
+      "Failed to execute 'setPushConstants' on 'GPURenderBundleEncoder'";
+    webidl.requiredArguments(arguments.length, 3, { prefix });
+    stages = webidl.converters.GPUShaderStageFlags(stages, {
+      prefix,
+      context: "Argument 1",
+    });
+    offset = webidl.converters.GPUSize32(offset, {
+      prefix,
+      context: "Argument 2",
+    });
+    data = webidl.converters.BufferSource(data, {
+      prefix,
+      context: "Argument 3",
+    });
+    assertDevice(this, { prefix, context: "this" });
+    const renderBundleEncoderRid = assertResource(this, {
+      prefix,
+      context: "this",
+    });
+    ops.op_webgpu_render_bundle_encoder_set_push_constants(
+      renderBundleEncoderRid,
+      stages,
+      offset,
+      bufferSourceBytes(data),
+    );
+  }
+
   /**
    * @param {string} groupLabel
    */
@@ -5224,11 +5866,37 @@ const GPUQuerySetPrototype = GPUQuerySet.prototype;
 
 /**
  * @param {number} rid
+ * @param {(() => void) | undefined} onReconfigured called after the surface
+ *   was reconfigured because it became outdated or lost
+ * @param {(() => void) | undefined} onDeviceLost called when the device the
+ *   surface is configured with is lost
  * @returns {GPUSurface}
  */
-function createGPUSurface(rid) {
+function createGPUSurface(rid, onReconfigured, onDeviceLost) {
   const surface = webidl.createBranded(GPUSurface);
   surface[_rid] = rid;
+  surface[_offscreen] = false;
+  surface[_onReconfigured] = onReconfigured;
+  surface[_onDeviceLost] = onDeviceLost;
+  surface[_presentStatistics] = null;
+  return surface;
+}
+
+/**
+ * A surface that isn't backed by a window. Its textures are ordinary offscreen
+ * textures and presenting them is a no-op.
+ * @param {(() => [number, number]) | undefined} getSize if given, the size of
+ *   each texture instead of the configured size
+ * @param {(() => void) | undefined} onDeviceLost called when the device the
+ *   surface is configured with is lost
+ * @returns {GPUSurface}
+ */
+function createOffscreenGPUSurface(getSize, onDeviceLost) {
+  const surface = webidl.createBranded(GPUSurface);
+  surface[_offscreen] = true;
+  surface[_getOffscreenSize] = getSize;
+  surface[_onDeviceLost] = onDeviceLost;
+  surface[_presentStatistics] = null;
   return surface;
 }
 
@@ -5239,7 +5907,11 @@ function destroyGPUSurface(surface) {
   surface[_currentTexture]?.destroy();
   surface[_configuration] = undefined;
   surface[_device] = undefined;
-  core.close(surface[_rid]);
+  if (surface[_offscreen]) {
+    surface[_offscreen] = false;
+  } else {
+    core.close(surface[_rid]);
+  }
   surface[_rid] = undefined;
 }
 
@@ -5256,6 +5928,21 @@ class GPUSurface {
   /** @type {GPUSurfaceTexture | undefined} */
   [_currentTexture];
 
+  /** @type {boolean} */
+  [_offscreen];
+
+  /** @type {(() => void) | undefined} */
+  [_onReconfigured];
+
+  /** @type {(() => void) | undefined} */
+  [_onDeviceLost];
+
+  /** @type {(() => [number, number]) | undefined} */
+  [_getOffscreenSize];
+
+  /** @type {GPUSurfacePresentStatistics | null} */
+  [_presentStatistics];
+
   constructor() {
     webidl.illegalConstructor();
   }
@@ -5268,7 +5955,6 @@ class GPUSurface {
     webidl.assertBranded(this, GPUSurfacePrototype);
 
     const prefix = "Failed to execute 'getCapabilities' on 'GPUSurface'";
-    const rid = assertResource(this, { prefix, context: "this" });
     webidl.requiredArguments(arguments.length, 1, { prefix });
 
     adapter = webidl.converters.GPUAdapter(adapter, {
@@ -5276,12 +5962,57 @@ class GPUSurface {
       context: "Argument 1",
     });
 
+    if (this[_offscreen]) {
+      return {
+        formats: [
+          "bgra8unorm",
+          "bgra8unorm-srgb",
+          "rgba8unorm",
+          "rgba8unorm-srgb",
+        ],
+        presentModes: ["fifo"],
+        alphaModes: ["opaque"],
+      };
+    }
+    const rid = assertResource(this, { prefix, context: "this" });
+
     return ops.op_webgpu_surface_get_capabilities(
       rid,
       adapter[_adapter].rid,
     );
   }
 
+  /**
+   * @param {GPUAdapter} adapter
+   * @returns {GPUSurfacePreferredConfiguration}
+   */
+  getPreferredConfiguration(adapter) {
+    webidl.assertBranded(this, GPUSurfacePrototype);
+
+    const prefix =
+      "Failed to execute 'getPreferredConfiguration' on 'GPUSurface'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+
+    adapter = webidl.converters.GPUAdapter(adapter, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    if (this[_offscreen]) {
+      return {
+        format: "bgra8unorm",
+        presentMode: "fifo",
+        alphaMode: "opaque",
+      };
+    }
+    const rid = assertResource(this, { prefix, context: "this" });
+
+    return ops.op_webgpu_surface_get_preferred_configuration(
+      rid,
+      adapter[_adapter].rid,
+    );
+  }
+
   /**
    * @param {GPUDevice} device
    * @param {GPUSurfaceConfiguration} config
@@ -5290,7 +6021,6 @@ class GPUSurface {
     webidl.assertBranded(this, GPUSurfacePrototype);
 
     const prefix = "Failed to execute 'configure' on 'GPUSurface'";
-    const rid = assertResource(this, { prefix, context: "this" });
     webidl.requiredArguments(arguments.length, 2, { prefix });
 
     device = webidl.converters.GPUDevice(device, {
@@ -5307,9 +6037,24 @@ class GPUSurface {
       context: "Argument 2",
     });
     config.size = normalizeGPUExtent3D(config.size);
+    if (device.isLost) {
+      throw new DOMException("Device has been lost.", "OperationError");
+    }
 
-    ops.op_webgpu_surface_configure(rid, device.rid, config);
+    if (!this[_offscreen]) {
+      const rid = assertResource(this, { prefix, context: "this" });
+      ops.op_webgpu_surface_configure(rid, device.rid, config);
+    }
 
+    if (this[_device] !== device) {
+      // After a loss, the surface can be configured with a new device.
+      PromisePrototypeThen(device.lost, () => {
+        if (this[_device] === device) {
+          this[_currentTexture] = undefined;
+          this[_onDeviceLost]?.();
+        }
+      });
+    }
     this[_device] = device;
     this[_configuration] = config;
   }
@@ -5325,11 +6070,43 @@ class GPUSurface {
     }
 
     const prefix = "Failed to execute 'getCurrentTexture' on 'GPUSurface'";
-    const rid = assertResource(this, { prefix, context: "this" });
     const device = assertDevice(this, { prefix, context: "this" });
 
-    const [textureRid, isSuboptimal] = ops
-      .op_webgpu_surface_get_current_texture(rid, device.rid);
+    let textureRid, isSuboptimal;
+    if (this[_offscreen]) {
+      if (this[_getOffscreenSize]) {
+        const [width, height] = this[_getOffscreenSize]();
+        this[_configuration].size = { width, height, depthOrArrayLayers: 1 };
+      }
+      const { rid, err } = ops.op_webgpu_create_texture({
+        deviceRid: device.rid,
+        size: this[_configuration].size,
+        mipLevelCount: 1,
+        sampleCount: 1,
+        dimension: "2d",
+        format: this[_configuration].format,
+        usage: this[_configuration].usage,
+        viewFormats: this[_configuration].viewFormats,
+      });
+      device.pushError(err);
+      textureRid = rid;
+      isSuboptimal = false;
+    } else {
+      const rid = assertResource(this, { prefix, context: "this" });
+      const output = ops.op_webgpu_surface_get_current_texture(rid, device.rid);
+      if (output === null) {
+        device.pushError({ type: "lost", value: null });
+        throw new DOMException("Device has been lost.", "OperationError");
+      }
+      const { reconfigured, width, height } = output;
+      textureRid = output.textureRid;
+      isSuboptimal = output.suboptimal;
+      // The size changes if the surface resizes with its window.
+      this[_configuration].size = { width, height, depthOrArrayLayers: 1 };
+      if (reconfigured) {
+        this[_onReconfigured]?.();
+      }
+    }
 
     this[_currentTexture] = createGPUSurfaceTexture(
       {
@@ -5349,6 +6126,51 @@ class GPUSurface {
     device.trackResource(this[_currentTexture]);
     return this[_currentTexture];
   }
+
+  /**
+   * @returns {GPUSurfacePresentStatistics | null}
+   */
+  getPresentStatistics() {
+    webidl.assertBranded(this, GPUSurfacePrototype);
+    return this[_presentStatistics];
+  }
+
+  /**
+   * @param {GPUSurfaceFrameCaptureOptions} options
+   */
+  startFrameCapture(options) {
+    webidl.assertBranded(this, GPUSurfacePrototype);
+
+    const prefix = "Failed to execute 'startFrameCapture' on 'GPUSurface'";
+    if (this[_offscreen]) {
+      throw new DOMException(
+        `${prefix}: Frame capture is not supported for offscreen surfaces.`,
+        "NotSupportedError",
+      );
+    }
+    const rid = assertResource(this, { prefix, context: "this" });
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+
+    options = webidl.converters.GPUSurfaceFrameCaptureOptions(options, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    ops.op_webgpu_surface_start_frame_capture(
+      rid,
+      options.directory,
+      options.interval,
+    );
+  }
+
+  stopFrameCapture() {
+    webidl.assertBranded(this, GPUSurfacePrototype);
+
+    const prefix = "Failed to execute 'stopFrameCapture' on 'GPUSurface'";
+    const rid = assertResource(this, { prefix, context: "this" });
+
+    ops.op_webgpu_surface_stop_frame_capture(rid);
+  }
 }
 const GPUSurfacePrototype = GPUSurface.prototype;
 
@@ -5387,10 +6209,12 @@ class GPUSurfaceTexture extends GPUTexture {
 
   [_cleanup]() {
     if (this[_rid] !== undefined) {
-      ops.op_webgpu_surface_texture_discard(
-        this[_surface][_rid],
-        this[_device].rid,
-      );
+      if (!this[_surface][_offscreen]) {
+        ops.op_webgpu_surface_texture_discard(
+          this[_surface][_rid],
+          this[_device].rid,
+        );
+      }
       this[_surface][_currentTexture] = undefined;
     }
     super[_cleanup]();
@@ -5404,14 +6228,34 @@ class GPUSurfaceTexture extends GPUTexture {
     return this[_isSuboptimal];
   }
 
-  present() {
+  /**
+   * @param {GPUSurfacePresentOptions} options
+   */
+  present(options = {}) {
     webidl.assertBranded(this, GPUSurfaceTexturePrototype);
 
     const prefix = "Failed to execute 'present' on 'GPUSurfaceTexture'";
+    options = webidl.converters.GPUSurfacePresentOptions(options, {
+      prefix,
+      context: "Argument 1",
+    });
     const device = assertDevice(this, { prefix, context: "this" });
-    assertResource(this, { prefix, context: "this" });
+    const rid = assertResource(this, { prefix, context: "this" });
 
-    ops.op_webgpu_surface_texture_present(this[_surface][_rid], device.rid);
+    if (!this[_surface][_offscreen]) {
+      const { statistics, deviceLost } = ops.op_webgpu_surface_texture_present(
+        this[_surface][_rid],
+        device.rid,
+        rid,
+        options.damage,
+      );
+      if (statistics !== null) {
+        this[_surface][_presentStatistics] = statistics;
+      }
+      if (deviceLost) {
+        device.pushError({ type: "lost", value: null });
+      }
+    }
 
     this[_surface][_currentTexture] = undefined;
     super[_cleanup]();
@@ -5419,12 +6263,268 @@ class GPUSurfaceTexture extends GPUTexture {
 }
 const GPUSurfaceTexturePrototype = GPUSurfaceTexture.prototype;
 
+/**
+ * @param {GPUDevice} device
+ * @param {number} maxScopes
+ * @returns {GPUProfiler}
+ */
+function createGPUProfiler(device, maxScopes) {
+  const profiler = webidl.createBranded(GPUProfiler);
+  profiler[_profiledDevice] = device;
+  profiler[_maxScopes] = maxScopes;
+  profiler[_scopes] = [];
+  profiler[_openScopes] = [];
+  profiler[_resolved] = false;
+  if (
+    SetPrototypeHas(device.features[webidl.setlikeInner], "timestamp-query")
+  ) {
+    const size = maxScopes * 2 * 8;
+    profiler[_querySet] = device.createQuerySet({
+      label: "GPUProfiler",
+      type: "timestamp",
+      count: maxScopes * 2,
+    });
+    profiler[_resolveBuffer] = device.createBuffer({
+      label: "GPUProfiler resolve",
+      size,
+      usage: GPUBufferUsage.QUERY_RESOLVE | GPUBufferUsage.COPY_SRC,
+    });
+    profiler[_readBuffer] = device.createBuffer({
+      label: "GPUProfiler read",
+      size,
+      usage: GPUBufferUsage.MAP_READ | GPUBufferUsage.COPY_DST,
+    });
+  }
+  return profiler;
+}
+
+/**
+ * Measures how long labeled scopes of GPU work take using timestamp queries.
+ * Without the "timestamp-query" feature, scopes aren't measured and no results
+ * are reported.
+ */
+class GPUProfiler {
+  /** @type {GPUDevice} */
+  [_profiledDevice];
+
+  /** @type {GPUQuerySet | undefined} */
+  [_querySet];
+
+  /** @type {GPUBuffer | undefined} */
+  [_resolveBuffer];
+
+  /** @type {GPUBuffer | undefined} */
+  [_readBuffer];
+
+  /** @type {number} */
+  [_maxScopes];
+
+  /** @type {{label: string, depth: number}[]} */
+  [_scopes];
+
+  /** @type {number[]} */
+  [_openScopes];
+
+  /** @type {boolean} */
+  [_resolved];
+
+  constructor() {
+    webidl.illegalConstructor();
+  }
+
+  /**
+   * The features to request with requestDevice() for profiling to work, if
+   * the adapter supports them.
+   * @param {GPUAdapter} adapter
+   * @returns {GPUFeatureName[]}
+   */
+  static requiredFeatures(adapter) {
+    const prefix = "Failed to execute 'requiredFeatures' on 'GPUProfiler'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    adapter = webidl.converters.GPUAdapter(adapter, {
+      prefix,
+      context: "Argument 1",
+    });
+    return adapter.features.has("timestamp-query") ? ["timestamp-query"] : [];
+  }
+
+  /** @returns {boolean} */
+  get enabled() {
+    webidl.assertBranded(this, GPUProfilerPrototype);
+    return this[_querySet] !== undefined;
+  }
+
+  /**
+   * @param {GPUCommandEncoder} encoder
+   * @param {string} label
+   */
+  beginScope(encoder, label) {
+    webidl.assertBranded(this, GPUProfilerPrototype);
+    const prefix = "Failed to execute 'beginScope' on 'GPUProfiler'";
+    webidl.requiredArguments(arguments.length, 2, { prefix });
+    encoder = webidl.converters.GPUCommandEncoder(encoder, {
+      prefix,
+      context: "Argument 1",
+    });
+    label = webidl.converters.USVString(label, {
+      prefix,
+      context: "Argument 2",
+    });
+
+    if (this[_querySet] === undefined) {
+      return;
+    }
+    if (this[_resolved]) {
+      throw new DOMException(
+        `${prefix}: Scopes were resolved but their results haven't been read with getResults().`,
+        "OperationError",
+      );
+    }
+    const index = this[_scopes].length;
+    if (index === this[_maxScopes]) {
+      throw new DOMException(
+        `${prefix}: More than ${this[_maxScopes]} scopes.`,
+        "OperationError",
+      );
+    }
+    ArrayPrototypePush(this[_scopes], {
+      label,
+      depth: this[_openScopes].length,
+    });
+    ArrayPrototypePush(this[_openScopes], index);
+    encoder.writeTimestamp(this[_querySet], index * 2);
+  }
+
+  /**
+   * @param {GPUCommandEncoder} encoder
+   */
+  endScope(encoder) {
+    webidl.assertBranded(this, GPUProfilerPrototype);
+    const prefix = "Failed to execute 'endScope' on 'GPUProfiler'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    encoder = webidl.converters.GPUCommandEncoder(encoder, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    if (this[_querySet] === undefined) {
+      return;
+    }
+    if (this[_openScopes].length === 0) {
+      throw new DOMException(
+        `${prefix}: There is no open scope.`,
+        "OperationError",
+      );
+    }
+    const index = ArrayPrototypePop(this[_openScopes]);
+    encoder.writeTimestamp(this[_querySet], index * 2 + 1);
+  }
+
+  /**
+   * Records commands that copy the timestamps of all scopes so far to where
+   * getResults() can read them. Call it once per frame after the last scope
+   * ends.
+   * @param {GPUCommandEncoder} encoder
+   */
+  resolve(encoder) {
+    webidl.assertBranded(this, GPUProfilerPrototype);
+    const prefix = "Failed to execute 'resolve' on 'GPUProfiler'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    encoder = webidl.converters.GPUCommandEncoder(encoder, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    if (this[_querySet] === undefined || this[_scopes].length === 0) {
+      return;
+    }
+    if (this[_openScopes].length !== 0) {
+      throw new DOMException(
+        `${prefix}: ${this[_openScopes].length} scopes haven't ended.`,
+        "OperationError",
+      );
+    }
+    const count = this[_scopes].length * 2;
+    encoder.resolveQuerySet(
+      this[_querySet],
+      0,
+      count,
+      this[_resolveBuffer],
+      0,
+    );
+    encoder.copyBufferToBuffer(
+      this[_resolveBuffer],
+      0,
+      this[_readBuffer],
+      0,
+      count * 8,
+    );
+    this[_resolved] = true;
+  }
+
+  /**
+   * Waits for the GPU to finish the resolved scopes and reports how long each
+   * took. New scopes can begin afterwards.
+   * @returns {Promise<GPUProfilerScopeResult[]>}
+   */
+  async getResults() {
+    webidl.assertBranded(this, GPUProfilerPrototype);
+    const prefix = "Failed to execute 'getResults' on 'GPUProfiler'";
+
+    const scopes = this[_scopes];
+    if (!this[_resolved]) {
+      if (this[_openScopes].length !== 0) {
+        throw new DOMException(
+          `${prefix}: ${this[_openScopes].length} scopes haven't ended.`,
+          "OperationError",
+        );
+      }
+      this[_scopes] = [];
+      return [];
+    }
+
+    const device = this[_profiledDevice];
+    const size = scopes.length * 2 * 8;
+    await this[_readBuffer].mapAsync(GPUMapMode.READ, 0, size);
+    const timestamps = new BigUint64Array(
+      this[_readBuffer].getMappedRange(0, size),
+    );
+    const period = ops.op_webgpu_queue_get_timestamp_period(
+      device[_device].rid,
+    );
+    const results = ArrayPrototypeMap(scopes, ({ label, depth }, i) => {
+      const begin = timestamps[i * 2];
+      const end = timestamps[i * 2 + 1];
+      const ticks = end > begin ? Number(end - begin) : 0;
+      return { label, depth, milliseconds: ticks * period / 1e6 };
+    });
+    this[_readBuffer].unmap();
+
+    this[_scopes] = [];
+    this[_resolved] = false;
+    return results;
+  }
+
+  destroy() {
+    webidl.assertBranded(this, GPUProfilerPrototype);
+    this[_querySet]?.destroy();
+    this[_resolveBuffer]?.destroy();
+    this[_readBuffer]?.destroy();
+    this[_querySet] = undefined;
+    this[_resolveBuffer] = undefined;
+    this[_readBuffer] = undefined;
+  }
+}
+const GPUProfilerPrototype = GPUProfiler.prototype;
+
 const gpu = webidl.createBranded(GPU);
 export {
   _device,
   assertDevice,
+  createGPUShaderModule,
   createGPUTexture,
   createGPUSurface,
+  createOffscreenGPUSurface,
   destroyGPUSurface,
   GPU,
   gpu,
@@ -5445,6 +6545,7 @@ export {
   GPUMapMode,
   GPUOutOfMemoryError,
   GPUPipelineLayout,
+  GPUProfiler,
   GPUQuerySet,
   GPUQueue,
   GPURenderBundle,
@@ -132,6 +132,7 @@ webidl.converters["GPUFeatureName"] = webidl.createEnumConverter(
     "sampled-texture-and-storage-buffer-array-non-uniform-indexing",
     "uniform-buffer-and-storage-buffer-texture-non-uniform-indexing",
     "unsized-binding-array",
+    "partially-bound-binding-array",
     "multi-draw-indirect",
     "multi-draw-indirect-count",
     "push-constants",
@@ -787,6 +788,7 @@ const dictMembersGPUBindGroupLayoutEntry = [
     key: "storageTexture",
     converter: webidl.converters["GPUStorageTextureBindingLayout"],
   },
+  { key: "count", converter: webidl.converters["GPUSize32"] },
 ];
 webidl.converters["GPUBindGroupLayoutEntry"] = webidl
   .createDictionaryConverter(
@@ -892,6 +894,21 @@ webidl.converters.GPUPipelineLayout = webidl.createInterfaceConverter(
   GPUPipelineLayout.prototype,
 );
 
+// DICTIONARY: GPUPushConstantRange
+const dictMembersGPUPushConstantRange = [
+  {
+    key: "stages",
+    converter: webidl.converters["GPUShaderStageFlags"],
+    required: true,
+  },
+  { key: "start", converter: webidl.converters["GPUSize32"], required: true },
+  { key: "end", converter: webidl.converters["GPUSize32"], required: true },
+];
+webidl.converters["GPUPushConstantRange"] = webidl.createDictionaryConverter(
+  "GPUPushConstantRange",
+  dictMembersGPUPushConstantRange,
+);
+
 // DICTIONARY: GPUPipelineLayoutDescriptor
 const dictMembersGPUPipelineLayoutDescriptor = [
   {
@@ -901,6 +918,12 @@ const dictMembersGPUPipelineLayoutDescriptor = [
     ),
     required: true,
   },
+  {
+    key: "pushConstantRanges",
+    converter: webidl.createSequenceConverter(
+      webidl.converters["GPUPushConstantRange"],
+    ),
+  },
 ];
 webidl.converters["GPUPipelineLayoutDescriptor"] = webidl
   .createDictionaryConverter(
@@ -2114,3 +2137,114 @@ webidl.converters["GPUSurfaceConfiguration"] = webidl
     "GPUSurfaceConfiguration",
     dictMembersGPUSurfaceConfiguration,
   );
+
+// DICTIONARY: GPUSurfaceFrameCaptureOptions
+const dictMembersGPUSurfaceFrameCaptureOptions = [
+  {
+    key: "directory",
+    converter: webidl.converters["DOMString"],
+    required: true,
+  },
+  {
+    key: "interval",
+    converter: webidl.converters["GPUSize32"],
+    defaultValue: 1,
+  },
+];
+webidl.converters["GPUSurfaceFrameCaptureOptions"] = webidl
+  .createDictionaryConverter(
+    "GPUSurfaceFrameCaptureOptions",
+    dictMembersGPUSurfaceFrameCaptureOptions,
+  );
+
+// DICTIONARY: GPUSurfaceDamageRect
+const dictMembersGPUSurfaceDamageRect = [
+  {
+    key: "x",
+    converter: webidl.converters["GPUIntegerCoordinate"],
+    required: true,
+  },
+  {
+    key: "y",
+    converter: webidl.converters["GPUIntegerCoordinate"],
+    required: true,
+  },
+  {
+    key: "width",
+    converter: webidl.converters["GPUIntegerCoordinate"],
+    required: true,
+  },
+  {
+    key: "height",
+    converter: webidl.converters["GPUIntegerCoordinate"],
+    required: true,
+  },
+];
+webidl.converters["GPUSurfaceDamageRect"] = webidl.createDictionaryConverter(
+  "GPUSurfaceDamageRect",
+  dictMembersGPUSurfaceDamageRect,
+);
+
+// DICTIONARY: GPUSurfacePresentOptions
+const dictMembersGPUSurfacePresentOptions = [
+  {
+    key: "damage",
+    converter: webidl.createSequenceConverter(
+      webidl.converters["GPUSurfaceDamageRect"],
+    ),
+  },
+];
+webidl.converters["GPUSurfacePresentOptions"] = webidl
+  .createDictionaryConverter(
+    "GPUSurfacePresentOptions",
+    dictMembersGPUSurfacePresentOptions,
+  );
+
+// ENUM: GPURawWindowSystem
+webidl.converters["GPURawWindowSystem"] = webidl.createEnumConverter(
+  "GPURawWindowSystem",
+  [
+    "win32",
+    "xlib",
+    "xcb",
+    "wayland",
+    "appkit",
+  ],
+);
+
+// DICTIONARY: GPURawSurfaceHandles
+const dictMembersGPURawSurfaceHandles = [
+  {
+    key: "system",
+    converter: webidl.converters["GPURawWindowSystem"],
+    required: true,
+  },
+  {
+    key: "window",
+    converter: webidl.converters.any,
+    required: true,
+  },
+  {
+    key: "display",
+    converter: webidl.converters.any,
+    defaultValue: null,
+  },
+];
+webidl.converters["GPURawSurfaceHandles"] = webidl.createDictionaryConverter(
+  "GPURawSurfaceHandles",
+  dictMembersGPURawSurfaceHandles,
+);
+
+// DICTIONARY: GPUProfilerDescriptor
+const dictMembersGPUProfilerDescriptor = [
+  {
+    key: "maxScopes",
+    converter: webidl.converters["GPUSize32"],
+    defaultValue: 256,
+  },
+];
+webidl.converters["GPUProfilerDescriptor"] = webidl
+  .createDictionaryConverter(
+    "GPUProfilerDescriptor",
+    dictMembersGPUProfilerDescriptor,
+  );
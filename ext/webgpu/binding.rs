@@ -1,5 +1,6 @@
 // Copyright 2018-2023 the Deno authors. All rights reserved. MIT license.
 
+use deno_core::error::type_error;
 use deno_core::error::AnyError;
 use deno_core::op;
 use deno_core::OpState;
@@ -125,6 +126,8 @@ impl From<GpuStorageTextureAccess> for wgpu_types::StorageTextureAccess {
 pub struct GpuBindGroupLayoutEntry {
   binding: u32,
   visibility: u32,
+  // native-only, requires one of the binding array features
+  count: Option<u32>,
   #[serde(flatten)]
   binding_type: GpuBindingType,
 }
@@ -180,14 +183,12 @@ pub fn op_webgpu_create_bind_group_layout(
 
   let entries = entries
     .into_iter()
-    .map(|entry| {
-      wgpu_types::BindGroupLayoutEntry {
-        binding: entry.binding,
-        visibility: wgpu_types::ShaderStages::from_bits(entry.visibility)
-          .unwrap(),
-        ty: entry.binding_type.into(),
-        count: None, // native-only
-      }
+    .map(|entry| wgpu_types::BindGroupLayoutEntry {
+      binding: entry.binding,
+      visibility: wgpu_types::ShaderStages::from_bits(entry.visibility)
+        .unwrap(),
+      ty: entry.binding_type.into(),
+      count: entry.count.and_then(std::num::NonZeroU32::new),
     })
     .collect::<Vec<_>>();
 
@@ -203,12 +204,45 @@ pub fn op_webgpu_create_bind_group_layout(
   ) => state, WebGpuBindGroupLayout)
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuPushConstantRange {
+  stages: u32,
+  start: u32,
+  end: u32,
+}
+
+impl From<GpuPushConstantRange> for wgpu_types::PushConstantRange {
+  fn from(range: GpuPushConstantRange) -> Self {
+    Self {
+      stages: wgpu_types::ShaderStages::from_bits_truncate(range.stages),
+      range: range.start..range.end,
+    }
+  }
+}
+
+// wgpu-core panics on push constant offsets and sizes that aren't multiples of
+// 4 bytes, so they're checked before recording. Returns the size in bytes.
+pub(crate) fn push_constants_size(
+  offset: u32,
+  data: &[u8],
+) -> Result<u32, AnyError> {
+  let alignment = wgpu_types::PUSH_CONSTANT_ALIGNMENT;
+  if offset % alignment != 0 || data.len() as u32 % alignment != 0 {
+    return Err(type_error(format!(
+      "Push constant offset and size must be multiples of {alignment}"
+    )));
+  }
+  Ok(data.len() as u32)
+}
+
 #[op]
 pub fn op_webgpu_create_pipeline_layout(
   state: &mut OpState,
   device_rid: ResourceId,
   label: Option<String>,
   bind_group_layouts: Vec<u32>,
+  push_constant_ranges: Vec<GpuPushConstantRange>,
 ) -> Result<WebGpuResult, AnyError> {
   let instance = state.borrow::<super::Instance>();
   let device_resource = state
@@ -228,7 +262,10 @@ pub fn op_webgpu_create_pipeline_layout(
   let descriptor = wgpu_core::binding_model::PipelineLayoutDescriptor {
     label: label.map(Cow::from),
     bind_group_layouts: Cow::from(bind_group_layouts),
-    push_constant_ranges: Default::default(),
+    push_constant_ranges: push_constant_ranges
+      .into_iter()
+      .map(Into::into)
+      .collect(),
   };
 
   gfx_put!(device => instance.device_create_pipeline_layout(
@@ -243,6 +280,19 @@ pub fn op_webgpu_create_pipeline_layout(
 pub struct GpuBindGroupEntry {
   binding: u32,
   kind: String,
+  #[serde(default)]
+  resource: ResourceId,
+  offset: Option<u64>,
+  size: Option<u64>,
+  // The elements of a binding array (native-only), for the kinds ending in
+  // "Array".
+  #[serde(default)]
+  elements: Vec<GpuBindGroupArrayElement>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuBindGroupArrayElement {
   resource: ResourceId,
   offset: Option<u64>,
   size: Option<u64>,
@@ -299,6 +349,59 @@ pub fn op_webgpu_create_bind_group(
               },
             )
           }
+          "GPUSamplerArray" => {
+            let samplers = entry
+              .elements
+              .iter()
+              .map(|element| {
+                let sampler_resource =
+                  state
+                    .resource_table
+                    .get::<super::sampler::WebGpuSampler>(element.resource)?;
+                Ok(sampler_resource.0)
+              })
+              .collect::<Result<Vec<_>, AnyError>>()?;
+            wgpu_core::binding_model::BindingResource::SamplerArray(Cow::from(
+              samplers,
+            ))
+          }
+          "GPUTextureViewArray" => {
+            let texture_views = entry
+              .elements
+              .iter()
+              .map(|element| {
+                let texture_view_resource = state
+                  .resource_table
+                  .get::<super::texture::WebGpuTextureView>(
+                  element.resource,
+                )?;
+                Ok(texture_view_resource.0)
+              })
+              .collect::<Result<Vec<_>, AnyError>>()?;
+            wgpu_core::binding_model::BindingResource::TextureViewArray(
+              Cow::from(texture_views),
+            )
+          }
+          "GPUBufferBindingArray" => {
+            let buffers = entry
+              .elements
+              .iter()
+              .map(|element| {
+                let buffer_resource =
+                  state
+                    .resource_table
+                    .get::<super::buffer::WebGpuBuffer>(element.resource)?;
+                Ok(wgpu_core::binding_model::BufferBinding {
+                  buffer_id: buffer_resource.0,
+                  offset: element.offset.unwrap_or(0),
+                  size: std::num::NonZeroU64::new(element.size.unwrap_or(0)),
+                })
+              })
+              .collect::<Result<Vec<_>, AnyError>>()?;
+            wgpu_core::binding_model::BindingResource::BufferArray(Cow::from(
+              buffers,
+            ))
+          }
           _ => unreachable!(),
         },
       })
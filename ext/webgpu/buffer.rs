@@ -4,13 +4,16 @@ use deno_core::error::type_error;
 use deno_core::error::AnyError;
 use deno_core::futures::channel::oneshot;
 use deno_core::op;
+use deno_core::serde_v8;
+use deno_core::v8;
 use deno_core::OpState;
 use deno_core::Resource;
 use deno_core::ResourceId;
-use deno_core::ZeroCopyBuf;
+use serde::Serialize;
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::convert::TryFrom;
+use std::ffi::c_void;
 use std::rc::Rc;
 use std::time::Duration;
 use wgpu_core::resource::BufferAccessResult;
@@ -136,14 +139,53 @@ pub async fn op_webgpu_buffer_get_map_async(
   Ok(WebGpuResult::empty())
 }
 
-#[op]
-pub fn op_webgpu_buffer_get_mapped_range(
+/// Mapped memory is handed to JS as an `ArrayBuffer` that points directly at
+/// it. wgpu owns the memory, so the backing store must not free it.
+unsafe extern "C" fn noop_deleter_callback(
+  _data: *mut c_void,
+  _byte_length: usize,
+  _deleter_data: *mut c_void,
+) {
+}
+
+/// Only code that has the detach key can detach an `ArrayBuffer` that has one,
+/// and JS never does. So a mapped range can't be transferred, e.g. to a worker
+/// that could keep using the mapped memory after unmap.
+fn mapped_range_detach_key<'a>(
+  scope: &mut v8::HandleScope<'a>,
+) -> v8::Local<'a, v8::Value> {
+  v8::String::new(scope, "GPUBuffer mapped range")
+    .unwrap()
+    .into()
+}
+
+/// Detaches an `ArrayBuffer` returned by `op_webgpu_buffer_get_mapped_range`,
+/// so that JS can't touch the mapped memory once wgpu has released it.
+fn detach_mapped_range(
+  scope: &mut v8::HandleScope,
+  buffer: serde_v8::Value,
+) -> Result<(), AnyError> {
+  let buffer = v8::Local::<v8::ArrayBuffer>::try_from(buffer.v8_value)
+    .map_err(|_| type_error("expected an ArrayBuffer"))?;
+  let key = mapped_range_detach_key(scope);
+  buffer.detach(Some(key));
+  Ok(())
+}
+
+#[derive(Serialize)]
+pub struct GpuMappedRange<'a> {
+  rid: ResourceId,
+  buffer: serde_v8::Value<'a>,
+}
+
+#[op(v8)]
+pub fn op_webgpu_buffer_get_mapped_range<'a>(
+  scope: &mut v8::HandleScope<'a>,
   state: &mut OpState,
   buffer_rid: ResourceId,
   offset: u64,
   size: Option<u64>,
-  mut buf: ZeroCopyBuf,
-) -> Result<WebGpuResult, AnyError> {
+) -> Result<GpuMappedRange<'a>, AnyError> {
   let instance = state.borrow::<super::Instance>();
   let buffer_resource = state.resource_table.get::<WebGpuBuffer>(buffer_rid)?;
   let buffer = buffer_resource.0;
@@ -155,43 +197,67 @@ pub fn op_webgpu_buffer_get_mapped_range(
       size
     ))
     .map_err(|e| DomExceptionOperationError::new(&e.to_string()))?;
+  let range_size = range_size as usize;
 
-  // TODO(crowlKats):
-  #[allow(clippy::undocumented_unsafe_blocks)]
-  let slice = unsafe {
-    std::slice::from_raw_parts_mut(slice_pointer, range_size as usize)
+  let array_buffer = if range_size == 0 {
+    v8::ArrayBuffer::new(scope, 0)
+  } else {
+    // SAFETY: wgpu guarantees that the mapped range is valid for
+    // `range_size` bytes until the buffer is unmapped or destroyed, and the
+    // returned ArrayBuffer is detached before either happens.
+    let backing_store = unsafe {
+      v8::ArrayBuffer::new_backing_store_from_ptr(
+        slice_pointer as *mut c_void,
+        range_size,
+        noop_deleter_callback,
+        std::ptr::null_mut(),
+      )
+    }
+    .make_shared();
+    v8::ArrayBuffer::with_backing_store(scope, &backing_store)
   };
-  buf.copy_from_slice(slice);
+  let key = mapped_range_detach_key(scope);
+  array_buffer.set_detach_key(key);
 
   let rid = state
     .resource_table
-    .add(WebGpuBufferMapped(slice_pointer, range_size as usize));
+    .add(WebGpuBufferMapped(slice_pointer, range_size));
 
-  Ok(WebGpuResult::rid(rid))
+  Ok(GpuMappedRange {
+    rid,
+    buffer: v8::Local::<v8::Value>::from(array_buffer).into(),
+  })
 }
 
-#[op]
+#[op(v8)]
 pub fn op_webgpu_buffer_unmap(
+  scope: &mut v8::HandleScope,
   state: &mut OpState,
   buffer_rid: ResourceId,
   mapped_rid: ResourceId,
-  buf: Option<ZeroCopyBuf>,
+  mapped_buffer: serde_v8::Value,
 ) -> Result<WebGpuResult, AnyError> {
-  let mapped_resource = state
+  detach_mapped_range(scope, mapped_buffer)?;
+  state
     .resource_table
     .take::<WebGpuBufferMapped>(mapped_rid)?;
   let instance = state.borrow::<super::Instance>();
   let buffer_resource = state.resource_table.get::<WebGpuBuffer>(buffer_rid)?;
   let buffer = buffer_resource.0;
 
-  if let Some(buf) = buf {
-    // TODO(crowlKats):
-    #[allow(clippy::undocumented_unsafe_blocks)]
-    let slice = unsafe {
-      std::slice::from_raw_parts_mut(mapped_resource.0, mapped_resource.1)
-    };
-    slice.copy_from_slice(&buf);
-  }
-
   gfx_ok!(buffer => instance.buffer_unmap(buffer))
 }
+
+#[op(v8)]
+pub fn op_webgpu_buffer_detach_mapped_range(
+  scope: &mut v8::HandleScope,
+  state: &mut OpState,
+  mapped_rid: ResourceId,
+  mapped_buffer: serde_v8::Value,
+) -> Result<(), AnyError> {
+  detach_mapped_range(scope, mapped_buffer)?;
+  state
+    .resource_table
+    .take::<WebGpuBufferMapped>(mapped_rid)?;
+  Ok(())
+}
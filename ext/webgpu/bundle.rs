@@ -165,6 +165,35 @@ pub fn op_webgpu_render_bundle_encoder_set_bind_group(
   Ok(WebGpuResult::empty())
 }
 
+#[op]
+pub fn op_webgpu_render_bundle_encoder_set_push_constants(
+  state: &mut OpState,
+  render_bundle_encoder_rid: ResourceId,
+  stages: u32,
+  offset: u32,
+  data: ZeroCopyBuf,
+) -> Result<WebGpuResult, AnyError> {
+  let render_bundle_encoder_resource =
+    state
+      .resource_table
+      .get::<WebGpuRenderBundleEncoder>(render_bundle_encoder_rid)?;
+  let size_bytes = super::binding::push_constants_size(offset, &data)?;
+
+  // SAFETY: the raw pointer and length are of the same slice, and that slice
+  // lives longer than the below function invocation.
+  unsafe {
+    wgpu_core::command::bundle_ffi::wgpu_render_bundle_set_push_constants(
+      &mut render_bundle_encoder_resource.0.borrow_mut(),
+      wgpu_types::ShaderStages::from_bits_truncate(stages),
+      offset,
+      size_bytes,
+      data.as_ptr(),
+    );
+  }
+
+  Ok(WebGpuResult::empty())
+}
+
 #[op]
 pub fn op_webgpu_render_bundle_encoder_push_debug_group(
   state: &mut OpState,
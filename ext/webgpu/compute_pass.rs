@@ -224,6 +224,32 @@ pub fn op_webgpu_compute_pass_set_bind_group(
   Ok(WebGpuResult::empty())
 }
 
+#[op]
+pub fn op_webgpu_compute_pass_set_push_constants(
+  state: &mut OpState,
+  compute_pass_rid: ResourceId,
+  offset: u32,
+  data: ZeroCopyBuf,
+) -> Result<WebGpuResult, AnyError> {
+  let compute_pass_resource = state
+    .resource_table
+    .get::<WebGpuComputePass>(compute_pass_rid)?;
+  let size_bytes = super::binding::push_constants_size(offset, &data)?;
+
+  // SAFETY: the raw pointer and length are of the same slice, and that slice
+  // lives longer than the below function invocation.
+  unsafe {
+    wgpu_core::command::compute_ffi::wgpu_compute_pass_set_push_constant(
+      &mut compute_pass_resource.0.borrow_mut(),
+      offset,
+      size_bytes,
+      data.as_ptr(),
+    );
+  }
+
+  Ok(WebGpuResult::empty())
+}
+
 #[op]
 pub fn op_webgpu_compute_pass_push_debug_group(
   state: &mut OpState,
@@ -17,6 +17,8 @@ use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::path::Path;
+use std::path::PathBuf;
 use std::rc::Rc;
 pub use wgpu_core;
 pub use wgpu_types;
@@ -73,16 +75,30 @@ pub mod bundle;
 pub mod command_encoder;
 pub mod compute_pass;
 pub mod error;
+mod overlay;
 pub mod pipeline;
 pub mod queue;
+mod readback;
 pub mod render_pass;
+mod renderdoc;
 pub mod sampler;
 pub mod shader;
+pub mod shader_tools;
 pub mod surface;
 pub mod texture;
 
 pub struct Unstable(pub bool);
 
+struct Backends(wgpu_types::Backends);
+
+pub trait WebGpuPermissions {
+  fn check_write(
+    &mut self,
+    path: &Path,
+    api_name: &str,
+  ) -> Result<(), AnyError>;
+}
+
 fn check_unstable(state: &OpState, api_name: &str) {
   let unstable = state.borrow::<Unstable>();
   if !unstable.0 {
@@ -96,14 +112,18 @@ fn check_unstable(state: &OpState, api_name: &str) {
 pub type Instance =
   wgpu_core::hub::Global<wgpu_core::hub::IdentityManagerFactory>;
 
-fn choose_backends() -> wgpu_types::Backends {
-  std::env::var("DENO_WEBGPU_BACKEND")
+// An explicit preference, e.g. from --gpu-backend, takes precedence over the
+// DENO_WEBGPU_BACKEND environment variable.
+fn choose_backends(preference: Option<&str>) -> wgpu_types::Backends {
+  preference
+    .map(String::from)
+    .or_else(|| std::env::var("DENO_WEBGPU_BACKEND").ok())
     .map(|s| wgpu_core::instance::parse_backends_from_comma_list(&s))
-    .unwrap_or_else(|_| wgpu_types::Backends::all())
+    .unwrap_or_else(wgpu_types::Backends::all)
 }
 
-pub fn create_instance() -> Instance {
-  create_instance_internal(choose_backends())
+pub fn create_instance(state: &OpState) -> Instance {
+  create_instance_internal(state.borrow::<Backends>().0)
 }
 
 fn create_instance_internal(backends: wgpu_types::Backends) -> Instance {
@@ -138,28 +158,95 @@ impl Resource for WebGpuQuerySet {
   }
 }
 
+// The instance of a runtime that's going away and the devices it requested,
+// kept for a later runtime on the same thread, e.g. across hot reloads. GPU
+// surfaces created with the instance stay valid as well.
+pub struct PreservedGpu {
+  instance: Instance,
+  devices: Vec<wgpu_core::id::DeviceId>,
+}
+
+// Devices of an earlier runtime that `op_webgpu_request_device` hands out
+// before it requests new ones.
+struct PreservedDevices(Vec<wgpu_core::id::DeviceId>);
+
+// Take the instance and devices out of a runtime, if it has an instance. The
+// runtime can't use WebGPU afterwards.
+pub fn preserve_gpu(state: &mut OpState) -> Option<PreservedGpu> {
+  let instance = state.try_take::<Instance>()?;
+  let mut devices = state
+    .try_take::<PreservedDevices>()
+    .map(|devices| devices.0)
+    .unwrap_or_default();
+  let rids: Vec<_> = state.resource_table.names().map(|(rid, _)| rid).collect();
+  for rid in rids {
+    if let Ok(device) = state.resource_table.take::<WebGpuDevice>(rid) {
+      devices.push(device.0);
+    }
+  }
+  Some(PreservedGpu { instance, devices })
+}
+
+// Give a new runtime the instance and devices of an earlier one.
+pub fn restore_gpu(state: &mut OpState, gpu: PreservedGpu) {
+  state.put(gpu.instance);
+  state.put(PreservedDevices(gpu.devices));
+}
+
+// Take a preserved device of the adapter's backend that has the features and
+// limits that `descriptor` asks for, if there is one.
+fn take_preserved_device(
+  state: &mut OpState,
+  adapter: wgpu_core::id::AdapterId,
+  descriptor: &wgpu_types::DeviceDescriptor<Option<Cow<str>>>,
+) -> Option<wgpu_core::id::DeviceId> {
+  let devices = &state.try_borrow::<PreservedDevices>()?.0;
+  let instance = state.borrow::<Instance>();
+  let index = devices.iter().position(|&device| {
+    let features = gfx_select!(device => instance.device_features(device));
+    let limits = gfx_select!(device => instance.device_limits(device));
+    device.backend() == adapter.backend()
+      && features
+        .map_or(false, |features| features.contains(descriptor.features))
+      && limits.map_or(false, |limits| descriptor.limits.check_limits(&limits))
+  })?;
+  Some(state.borrow_mut::<PreservedDevices>().0.remove(index))
+}
+
 fn ext() -> ExtensionBuilder {
   Extension::builder_with_deps("deno_webgpu", &["deno_webidl", "deno_web"])
 }
 
-fn ops(ext: &mut ExtensionBuilder, unstable: bool) -> &mut ExtensionBuilder {
-  ext.ops(declare_webgpu_ops()).state(move |state| {
+fn ops<P: WebGpuPermissions + 'static>(
+  ext: &mut ExtensionBuilder,
+  unstable: bool,
+  backend: Option<String>,
+) -> &mut ExtensionBuilder {
+  let backends = choose_backends(backend.as_deref());
+  ext.ops(declare_webgpu_ops::<P>()).state(move |state| {
     // TODO: check & possibly streamline this
     // Unstable might be able to be OpMiddleware
     // let unstable_checker = state.borrow::<super::UnstableChecker>();
     // let unstable = unstable_checker.unstable;
     state.put(Unstable(unstable));
+    state.put(Backends(backends));
   })
 }
 
-pub fn init_ops_and_esm(unstable: bool) -> Extension {
-  ops(&mut ext(), unstable)
+pub fn init_ops_and_esm<P: WebGpuPermissions + 'static>(
+  unstable: bool,
+  backend: Option<String>,
+) -> Extension {
+  ops::<P>(&mut ext(), unstable, backend)
     .esm(include_js_files!("01_webgpu.js", "02_idl_types.js",))
     .build()
 }
 
-pub fn init_ops(unstable: bool) -> Extension {
-  ops(&mut ext(), unstable).build()
+pub fn init_ops<P: WebGpuPermissions + 'static>(
+  unstable: bool,
+  backend: Option<String>,
+) -> Extension {
+  ops::<P>(&mut ext(), unstable, backend).build()
 }
 
 fn deserialize_features(features: &wgpu_types::Features) -> Vec<&'static str> {
@@ -240,7 +327,16 @@ fn deserialize_features(features: &wgpu_types::Features) -> Vec<&'static str> {
     return_features.push("shader-primitive-index");
   }
   if features.contains(wgpu_types::Features::PARTIALLY_BOUND_BINDING_ARRAY) {
-    return_features.push("shader-primitive-index");
+    return_features.push("partially-bound-binding-array");
+  }
+  if features.contains(wgpu_types::Features::MULTI_DRAW_INDIRECT) {
+    return_features.push("multi-draw-indirect");
+  }
+  if features.contains(wgpu_types::Features::MULTI_DRAW_INDIRECT_COUNT) {
+    return_features.push("multi-draw-indirect-count");
+  }
+  if features.contains(wgpu_types::Features::PUSH_CONSTANTS) {
+    return_features.push("push-constants");
   }
 
   return_features
@@ -271,7 +367,7 @@ pub async fn op_webgpu_request_adapter(
 ) -> Result<GpuAdapterDeviceOrErr, AnyError> {
   let mut state = state.borrow_mut();
   check_unstable(&state, "navigator.gpu.requestAdapter");
-  let backends = choose_backends();
+  let backends = state.borrow::<Backends>().0;
   let instance = if let Some(instance) = state.try_borrow::<Instance>() {
     instance
   } else {
@@ -284,7 +380,7 @@ pub async fn op_webgpu_request_adapter(
     Some(rid) => Some(state.resource_table.get::<surface::WebGpuSurface>(rid)?),
   };
   let compatible_surface =
-    compatible_surface_resource.map(|resource| resource.0);
+    compatible_surface_resource.map(|resource| resource.id);
 
   let descriptor = wgpu_core::instance::RequestAdapterOptions {
     power_preference: power_preference.unwrap_or_default(),
@@ -430,6 +526,18 @@ impl From<GpuRequiredFeatures> for wgpu_types::Features {
         .0
         .contains("partially-bound-binding-array"),
     );
+    features.set(
+      wgpu_types::Features::MULTI_DRAW_INDIRECT,
+      required_features.0.contains("multi-draw-indirect"),
+    );
+    features.set(
+      wgpu_types::Features::MULTI_DRAW_INDIRECT_COUNT,
+      required_features.0.contains("multi-draw-indirect-count"),
+    );
+    features.set(
+      wgpu_types::Features::PUSH_CONSTANTS,
+      required_features.0.contains("push-constants"),
+    );
 
     features
   }
@@ -447,7 +555,6 @@ pub async fn op_webgpu_request_device(
   let adapter_resource =
     state.resource_table.get::<WebGpuAdapter>(adapter_rid)?;
   let adapter = adapter_resource.0;
-  let instance = state.borrow::<Instance>();
 
   let descriptor = wgpu_types::DeviceDescriptor {
     label: label.map(Cow::from),
@@ -455,16 +562,29 @@ pub async fn op_webgpu_request_device(
     limits: required_limits.unwrap_or_default(),
   };
 
-  let (device, maybe_err) = gfx_select!(adapter => instance.adapter_request_device(
-    adapter,
-    &descriptor,
-    std::env::var("DENO_WEBGPU_TRACE").ok().as_ref().map(std::path::Path::new),
-    ()
-  ));
-  if let Some(err) = maybe_err {
-    return Err(DomExceptionOperationError::new(&err.to_string()).into());
-  }
+  let device = match take_preserved_device(&mut state, adapter, &descriptor) {
+    Some(device) => device,
+    None => {
+      let trace_path = state
+        .try_borrow::<TraceDirectory>()
+        .map(|directory| directory.0.clone())
+        .or_else(|| std::env::var_os("DENO_WEBGPU_TRACE").map(PathBuf::from));
+
+      let instance = state.borrow::<Instance>();
+      let (device, maybe_err) = gfx_select!(adapter => instance.adapter_request_device(
+        adapter,
+        &descriptor,
+        trace_path.as_deref(),
+        ()
+      ));
+      if let Some(err) = maybe_err {
+        return Err(DomExceptionOperationError::new(&err.to_string()).into());
+      }
+      device
+    }
+  };
 
+  let instance = state.borrow::<Instance>();
   let device_features =
     gfx_select!(device => instance.device_features(device))?;
   let features = deserialize_features(&device_features);
@@ -481,6 +601,32 @@ pub async fn op_webgpu_request_device(
   })
 }
 
+// Where devices requested after GPU.startTrace() record their API calls, for
+// replaying with wgpu's player.
+struct TraceDirectory(PathBuf);
+
+#[op]
+pub fn op_webgpu_start_trace<P>(
+  state: &mut OpState,
+  directory: String,
+) -> Result<(), AnyError>
+where
+  P: WebGpuPermissions + 'static,
+{
+  let directory = PathBuf::from(directory);
+  state
+    .borrow_mut::<P>()
+    .check_write(&directory, "GPU.startTrace")?;
+  std::fs::create_dir_all(&directory)?;
+  state.put(TraceDirectory(directory));
+  Ok(())
+}
+
+#[op]
+pub fn op_webgpu_stop_trace(state: &mut OpState) {
+  state.try_take::<TraceDirectory>();
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GPUAdapterInfo {
@@ -589,18 +735,27 @@ pub fn op_webgpu_create_query_set(
   ) => state, WebGpuQuerySet)
 }
 
-fn declare_webgpu_ops() -> Vec<deno_core::OpDecl> {
+fn declare_webgpu_ops<P: WebGpuPermissions + 'static>() -> Vec<deno_core::OpDecl>
+{
   vec![
     // Request device/adapter
     op_webgpu_request_adapter::decl(),
     op_webgpu_request_device::decl(),
     op_webgpu_request_adapter_info::decl(),
+    op_webgpu_start_trace::decl::<P>(),
+    op_webgpu_stop_trace::decl(),
+    // RenderDoc
+    renderdoc::op_webgpu_renderdoc_is_attached::decl(),
+    renderdoc::op_webgpu_renderdoc_trigger_capture::decl(),
+    renderdoc::op_webgpu_renderdoc_start_capture::decl(),
+    renderdoc::op_webgpu_renderdoc_end_capture::decl(),
     // Query Set
     op_webgpu_create_query_set::decl(),
     // buffer
     buffer::op_webgpu_create_buffer::decl(),
     buffer::op_webgpu_buffer_get_mapped_range::decl(),
     buffer::op_webgpu_buffer_unmap::decl(),
+    buffer::op_webgpu_buffer_detach_mapped_range::decl(),
     // buffer async
     buffer::op_webgpu_buffer_get_map_async::decl(),
     // remaining sync ops
@@ -639,6 +794,7 @@ fn declare_webgpu_ops() -> Vec<deno_core::OpDecl> {
     render_pass::op_webgpu_render_pass_set_scissor_rect::decl(),
     render_pass::op_webgpu_render_pass_set_blend_constant::decl(),
     render_pass::op_webgpu_render_pass_set_stencil_reference::decl(),
+    render_pass::op_webgpu_render_pass_set_push_constants::decl(),
     render_pass::op_webgpu_render_pass_begin_pipeline_statistics_query::decl(),
     render_pass::op_webgpu_render_pass_end_pipeline_statistics_query::decl(),
     render_pass::op_webgpu_render_pass_write_timestamp::decl(),
@@ -655,6 +811,11 @@ fn declare_webgpu_ops() -> Vec<deno_core::OpDecl> {
     render_pass::op_webgpu_render_pass_draw_indexed::decl(),
     render_pass::op_webgpu_render_pass_draw_indirect::decl(),
     render_pass::op_webgpu_render_pass_draw_indexed_indirect::decl(),
+    render_pass::op_webgpu_render_pass_multi_draw_indirect::decl(),
+    render_pass::op_webgpu_render_pass_multi_draw_indexed_indirect::decl(),
+    render_pass::op_webgpu_render_pass_multi_draw_indirect_count::decl(),
+    render_pass::op_webgpu_render_pass_multi_draw_indexed_indirect_count::decl(
+    ),
     // compute_pass
     compute_pass::op_webgpu_compute_pass_set_pipeline::decl(),
     compute_pass::op_webgpu_compute_pass_dispatch_workgroups::decl(),
@@ -665,6 +826,7 @@ fn declare_webgpu_ops() -> Vec<deno_core::OpDecl> {
     compute_pass::op_webgpu_compute_pass_write_timestamp::decl(),
     compute_pass::op_webgpu_compute_pass_end::decl(),
     compute_pass::op_webgpu_compute_pass_set_bind_group::decl(),
+    compute_pass::op_webgpu_compute_pass_set_push_constants::decl(),
     compute_pass::op_webgpu_compute_pass_push_debug_group::decl(),
     compute_pass::op_webgpu_compute_pass_pop_debug_group::decl(),
     compute_pass::op_webgpu_compute_pass_insert_debug_marker::decl(),
@@ -672,6 +834,7 @@ fn declare_webgpu_ops() -> Vec<deno_core::OpDecl> {
     bundle::op_webgpu_create_render_bundle_encoder::decl(),
     bundle::op_webgpu_render_bundle_encoder_finish::decl(),
     bundle::op_webgpu_render_bundle_encoder_set_bind_group::decl(),
+    bundle::op_webgpu_render_bundle_encoder_set_push_constants::decl(),
     bundle::op_webgpu_render_bundle_encoder_push_debug_group::decl(),
     bundle::op_webgpu_render_bundle_encoder_pop_debug_group::decl(),
     bundle::op_webgpu_render_bundle_encoder_insert_debug_marker::decl(),
@@ -685,13 +848,23 @@ fn declare_webgpu_ops() -> Vec<deno_core::OpDecl> {
     queue::op_webgpu_queue_submit::decl(),
     queue::op_webgpu_write_buffer::decl(),
     queue::op_webgpu_write_texture::decl(),
+    queue::op_webgpu_queue_read_texture::decl(),
+    queue::op_webgpu_queue_get_timestamp_period::decl(),
     // shader
     shader::op_webgpu_create_shader_module::decl(),
+    shader_tools::op_webgpu_shader_validate_wgsl::decl(),
+    shader_tools::op_webgpu_shader_spirv_to_wgsl::decl(),
+    shader_tools::op_webgpu_shader_glsl_to_wgsl::decl(),
+    shader_tools::op_webgpu_shader_reflect_wgsl::decl(),
     // surface
+    surface::op_webgpu_create_surface_from_raw_handles::decl(),
     surface::op_webgpu_surface_get_capabilities::decl(),
+    surface::op_webgpu_surface_get_preferred_configuration::decl(),
     surface::op_webgpu_surface_configure::decl(),
     surface::op_webgpu_surface_get_current_texture::decl(),
     surface::op_webgpu_surface_texture_discard::decl(),
     surface::op_webgpu_surface_texture_present::decl(),
+    surface::op_webgpu_surface_start_frame_capture::decl::<P>(),
+    surface::op_webgpu_surface_stop_frame_capture::decl(),
   ]
 }
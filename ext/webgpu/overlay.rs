@@ -0,0 +1,291 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use deno_core::error::AnyError;
+use std::{collections::VecDeque, num::NonZeroU32};
+
+// Text is drawn with 3x5 glyphs scaled up, so it's readable without a font.
+const GLYPH_SCALE: usize = 2;
+const GLYPH_ADVANCE: usize = 4 * GLYPH_SCALE;
+const LINE_HEIGHT: usize = 7 * GLYPH_SCALE;
+const PADDING: usize = 6;
+const MARGIN: u32 = 8;
+const PANEL_WIDTH: usize = 220;
+const GRAPH_HEIGHT: usize = 40;
+// Frame times in the graph, one bar of two pixels each.
+const FRAME_HISTORY: usize = (PANEL_WIDTH - 2 * PADDING) / 2;
+// The frame time at the top of the graph, and the budget of a 60 Hz display,
+// in milliseconds.
+const GRAPH_MAX_FRAME_TIME: f64 = 50.0;
+const FRAME_BUDGET: f64 = 1000.0 / 60.0;
+
+const BACKGROUND: [u8; 4] = [16, 16, 16, 255];
+const TEXT: [u8; 4] = [255, 255, 255, 255];
+const BAR: [u8; 4] = [80, 200, 120, 255];
+const SLOW_BAR: [u8; 4] = [230, 90, 70, 255];
+const BUDGET_LINE: [u8; 4] = [90, 90, 90, 255];
+
+// Diagnostics drawn into the top left corner of each frame right before it's
+// presented, so they show even when the program's own rendering is broken.
+// The panel is written into the surface texture with a queue write, which
+// needs no pipeline but does need the surface to have COPY_DST usage and an
+// 8-bit RGBA or BGRA format. Other surfaces are left alone.
+pub(crate) struct DiagnosticsOverlay {
+  // More lines to show, e.g. from the window system.
+  lines: Box<dyn Fn() -> Vec<String>>,
+  // In milliseconds, oldest first.
+  frame_times: VecDeque<f64>,
+}
+
+impl DiagnosticsOverlay {
+  pub fn new(lines: Box<dyn Fn() -> Vec<String>>) -> Self {
+    Self {
+      lines,
+      frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+    }
+  }
+
+  // Draw the overlay into `texture`, which is about to be presented.
+  // `frame_time` is the time since the previous present, if any.
+  pub fn draw(
+    &mut self,
+    instance: &super::Instance,
+    device: wgpu_core::id::DeviceId,
+    texture: wgpu_core::id::TextureId,
+    config: &wgpu_types::SurfaceConfiguration<Vec<wgpu_types::TextureFormat>>,
+    frame_time: Option<f64>,
+  ) -> Result<(), AnyError> {
+    if let Some(frame_time) = frame_time {
+      if self.frame_times.len() == FRAME_HISTORY {
+        self.frame_times.pop_front();
+      }
+      self.frame_times.push_back(frame_time);
+    }
+
+    use wgpu_types::TextureFormat::*;
+    let bgra = match config.format {
+      Bgra8Unorm | Bgra8UnormSrgb => true,
+      Rgba8Unorm | Rgba8UnormSrgb => false,
+      _ => return Ok(()),
+    };
+    if !config.usage.contains(wgpu_types::TextureUsages::COPY_DST) {
+      return Ok(());
+    }
+
+    let mut lines = vec![self.frame_line(), gpu_line(instance, device)];
+    lines.extend((self.lines)());
+    let mut panel = Panel::new(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+      panel.text(PADDING, PADDING + i * LINE_HEIGHT, line);
+    }
+    panel.graph(PADDING + lines.len() * LINE_HEIGHT, &self.frame_times);
+
+    // Clip the panel to the frame.
+    let width = (panel.width as u32).min(config.width.saturating_sub(MARGIN));
+    let height =
+      (panel.height as u32).min(config.height.saturating_sub(MARGIN));
+    if width == 0 || height == 0 {
+      return Ok(());
+    }
+    let data = panel.to_bytes(width as usize, height as usize, bgra);
+
+    let destination = wgpu_core::command::ImageCopyTexture {
+      texture,
+      mip_level: 0,
+      origin: wgpu_types::Origin3d {
+        x: MARGIN,
+        y: MARGIN,
+        z: 0,
+      },
+      aspect: wgpu_types::TextureAspect::All,
+    };
+    let data_layout = wgpu_types::ImageDataLayout {
+      offset: 0,
+      bytes_per_row: NonZeroU32::new(width * 4),
+      rows_per_image: None,
+    };
+    let size = wgpu_types::Extent3d {
+      width,
+      height,
+      depth_or_array_layers: 1,
+    };
+    gfx_select!(device => instance.queue_write_texture(
+      device,
+      &destination,
+      &data,
+      &data_layout,
+      &size
+    ))?;
+    // Queue writes wait for the next submit, which would come after the
+    // present.
+    gfx_select!(device => instance.queue_submit(device, &[]))?;
+    Ok(())
+  }
+
+  fn frame_line(&self) -> String {
+    let count = self.frame_times.len();
+    if count == 0 {
+      return "FPS -".into();
+    }
+    let average = self.frame_times.iter().sum::<f64>() / count as f64;
+    let last = self.frame_times.back().unwrap();
+    format!("FPS {:.1}  FRAME {last:.1} MS", 1000.0 / average)
+  }
+}
+
+// How many of each GPU resource exist, since wgpu doesn't report how much
+// memory they use.
+fn gpu_line(
+  instance: &super::Instance,
+  device: wgpu_core::id::DeviceId,
+) -> String {
+  let report = instance.generate_report();
+  let hub = match device.backend() {
+    #[cfg(any(windows, all(unix, not(target_arch = "emscripten"))))]
+    wgpu_types::Backend::Vulkan => report.vulkan.as_ref(),
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    wgpu_types::Backend::Metal => report.metal.as_ref(),
+    #[cfg(windows)]
+    wgpu_types::Backend::Dx12 => report.dx12.as_ref(),
+    #[cfg(windows)]
+    wgpu_types::Backend::Dx11 => report.dx11.as_ref(),
+    wgpu_types::Backend::Gl => report.gl.as_ref(),
+    _ => None,
+  };
+  match hub {
+    Some(hub) => format!(
+      "BUFFERS {}  TEXTURES {}",
+      hub.buffers.num_occupied, hub.textures.num_occupied
+    ),
+    None => "BUFFERS -  TEXTURES -".into(),
+  }
+}
+
+struct Panel {
+  width: usize,
+  height: usize,
+  pixels: Vec<[u8; 4]>,
+}
+
+impl Panel {
+  fn new(lines: usize) -> Self {
+    let width = PANEL_WIDTH;
+    let height = 2 * PADDING + lines * LINE_HEIGHT + GRAPH_HEIGHT;
+    Self {
+      width,
+      height,
+      pixels: vec![BACKGROUND; width * height],
+    }
+  }
+
+  fn fill(&mut self, x: usize, y: usize, w: usize, h: usize, color: [u8; 4]) {
+    for y in y..(y + h).min(self.height) {
+      for x in x..(x + w).min(self.width) {
+        self.pixels[y * self.width + x] = color;
+      }
+    }
+  }
+
+  fn text(&mut self, x: usize, y: usize, text: &str) {
+    for (i, c) in text.chars().enumerate() {
+      let glyph = glyph(c.to_ascii_uppercase());
+      let x = x + i * GLYPH_ADVANCE;
+      for (row, bits) in glyph.iter().enumerate() {
+        for column in 0..3 {
+          if bits & (0b100 >> column) != 0 {
+            self.fill(
+              x + column * GLYPH_SCALE,
+              y + row * GLYPH_SCALE,
+              GLYPH_SCALE,
+              GLYPH_SCALE,
+              TEXT,
+            );
+          }
+        }
+      }
+    }
+  }
+
+  // Bars of frame times, newest on the right, with a line at the budget of a
+  // 60 Hz display.
+  fn graph(&mut self, y: usize, frame_times: &VecDeque<f64>) {
+    let bar_height = |frame_time: f64| {
+      let fraction = (frame_time / GRAPH_MAX_FRAME_TIME).min(1.0);
+      (fraction * (GRAPH_HEIGHT - PADDING) as f64).round() as usize
+    };
+    let bottom = y + GRAPH_HEIGHT - PADDING;
+    let budget = bar_height(FRAME_BUDGET);
+    self.fill(PADDING, bottom - budget, 2 * FRAME_HISTORY, 1, BUDGET_LINE);
+    let start = PADDING + 2 * (FRAME_HISTORY - frame_times.len());
+    for (i, &frame_time) in frame_times.iter().enumerate() {
+      let height = bar_height(frame_time);
+      let color = if frame_time > FRAME_BUDGET * 1.5 {
+        SLOW_BAR
+      } else {
+        BAR
+      };
+      self.fill(start + 2 * i, bottom - height, 2, height, color);
+    }
+  }
+
+  // The top left `width` by `height` pixels, tightly packed.
+  fn to_bytes(&self, width: usize, height: usize, bgra: bool) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(width * height * 4);
+    for row in self.pixels.chunks(self.width).take(height) {
+      for &[r, g, b, a] in &row[..width] {
+        if bgra {
+          bytes.extend_from_slice(&[b, g, r, a]);
+        } else {
+          bytes.extend_from_slice(&[r, g, b, a]);
+        }
+      }
+    }
+    bytes
+  }
+}
+
+// Rows of three pixels, top first, with the leftmost pixel in the high bit.
+fn glyph(c: char) -> [u8; 5] {
+  match c {
+    '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+    '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+    '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+    '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+    '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+    '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+    '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+    '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+    '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+    '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+    'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+    'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+    'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+    'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+    'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+    'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+    'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+    'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+    'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+    'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+    'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+    'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+    'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+    'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+    'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+    'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+    'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+    'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+    'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+    'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+    'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+    'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+    'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+    'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+    'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+    'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+    '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+    ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+    '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+    '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+    _ => [0; 5],
+  }
+}
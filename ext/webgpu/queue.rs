@@ -9,6 +9,7 @@ use deno_core::ResourceId;
 use deno_core::ZeroCopyBuf;
 use serde::Deserialize;
 
+use super::error::DomExceptionOperationError;
 use super::error::WebGpuResult;
 
 type WebGpuQueue = super::WebGpuDevice;
@@ -44,6 +45,19 @@ pub fn op_webgpu_queue_submit(
   Ok(WebGpuResult::maybe_err(maybe_err))
 }
 
+// Nanoseconds per tick of timestamp queries.
+#[op]
+pub fn op_webgpu_queue_get_timestamp_period(
+  state: &mut OpState,
+  queue_rid: ResourceId,
+) -> Result<f32, AnyError> {
+  let instance = state.borrow::<super::Instance>();
+  let queue_resource = state.resource_table.get::<WebGpuQueue>(queue_rid)?;
+  let queue = queue_resource.0;
+
+  Ok(gfx_select!(queue => instance.queue_get_timestamp_period(queue))?)
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GpuImageDataLayout {
@@ -70,7 +84,7 @@ pub fn op_webgpu_write_buffer(
   buffer_offset: u64,
   data_offset: usize,
   size: Option<usize>,
-  buf: ZeroCopyBuf,
+  buf: &[u8],
 ) -> Result<WebGpuResult, AnyError> {
   let instance = state.borrow::<super::Instance>();
   let buffer_resource = state
@@ -81,9 +95,14 @@ pub fn op_webgpu_write_buffer(
   let queue = queue_resource.0;
 
   let data = match size {
-    Some(size) => &buf[data_offset..(data_offset + size)],
-    None => &buf[data_offset..],
-  };
+    Some(size) => data_offset
+      .checked_add(size)
+      .and_then(|end| buf.get(data_offset..end)),
+    None => buf.get(data_offset..),
+  }
+  .ok_or_else(|| {
+    DomExceptionOperationError::new("data range is out of bounds")
+  })?;
   let maybe_err = gfx_select!(queue => instance.queue_write_buffer(
     queue,
     buffer,
@@ -102,7 +121,7 @@ pub fn op_webgpu_write_texture(
   destination: super::command_encoder::GpuImageCopyTexture,
   data_layout: GpuImageDataLayout,
   size: wgpu_types::Extent3d,
-  buf: ZeroCopyBuf,
+  buf: &[u8],
 ) -> Result<WebGpuResult, AnyError> {
   let instance = state.borrow::<super::Instance>();
   let texture_resource = state
@@ -122,8 +141,36 @@ pub fn op_webgpu_write_texture(
   gfx_ok!(queue => instance.queue_write_texture(
     queue,
     &destination,
-    &*buf,
+    buf,
     &data_layout,
     &size
   ))
 }
+
+#[op]
+pub fn op_webgpu_queue_read_texture(
+  state: &mut OpState,
+  queue_rid: ResourceId,
+  source: super::command_encoder::GpuImageCopyTexture,
+  format: wgpu_types::TextureFormat,
+  size: wgpu_types::Extent3d,
+) -> Result<ZeroCopyBuf, AnyError> {
+  let instance = state.borrow::<super::Instance>();
+  let texture_resource = state
+    .resource_table
+    .get::<super::texture::WebGpuTexture>(source.texture)?;
+  let queue_resource = state.resource_table.get::<WebGpuQueue>(queue_rid)?;
+  let queue = queue_resource.0;
+
+  let source = wgpu_core::command::ImageCopyTexture {
+    texture: texture_resource.0,
+    mip_level: source.mip_level,
+    origin: source.origin,
+    aspect: source.aspect,
+  };
+
+  let data =
+    super::readback::read_texture(instance, queue, &source, format, size)
+      .map_err(|e| DomExceptionOperationError::new(&e.to_string()))?;
+  Ok(data.into())
+}
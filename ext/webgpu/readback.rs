@@ -0,0 +1,279 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use deno_core::error::AnyError;
+use std::borrow::Cow;
+use std::io::Write;
+use std::num::NonZeroU32;
+use std::sync::mpsc;
+
+// Size in bytes of one texel block of `format` when copying `aspect` of it
+// into a buffer, or `None` if that aspect can't be copied out.
+fn copy_block_size(
+  format: wgpu_types::TextureFormat,
+  aspect: wgpu_types::TextureAspect,
+) -> Option<u32> {
+  use wgpu_types::TextureAspect;
+  use wgpu_types::TextureFormat::*;
+  match (format, aspect) {
+    (
+      Stencil8 | Depth24PlusStencil8 | Depth32FloatStencil8,
+      TextureAspect::StencilOnly,
+    )
+    | (Stencil8, TextureAspect::All) => Some(1),
+    (Depth16Unorm, TextureAspect::All | TextureAspect::DepthOnly) => Some(2),
+    (Depth32Float, TextureAspect::All | TextureAspect::DepthOnly)
+    | (Depth32FloatStencil8, TextureAspect::DepthOnly) => Some(4),
+    (
+      Depth24Plus | Depth24PlusStencil8 | Depth32FloatStencil8,
+      TextureAspect::All | TextureAspect::DepthOnly,
+    )
+    | (Depth16Unorm | Depth32Float, TextureAspect::StencilOnly) => None,
+    (_, TextureAspect::All) => Some(format.describe().block_size as u32),
+    (_, _) => None,
+  }
+}
+
+// Rows copied from a texture into a buffer must start at multiples of
+// `COPY_BYTES_PER_ROW_ALIGNMENT` bytes.
+fn padded_bytes_per_row(unpadded_bytes_per_row: u32) -> u32 {
+  let align = wgpu_types::COPY_BYTES_PER_ROW_ALIGNMENT;
+  (unpadded_bytes_per_row + align - 1) / align * align
+}
+
+// Drop the padding at the end of each row copied into a buffer.
+fn strip_row_padding(
+  padded: &[u8],
+  padded_bytes_per_row: u32,
+  unpadded_bytes_per_row: u32,
+) -> Vec<u8> {
+  let rows = padded.len() / padded_bytes_per_row as usize;
+  let mut data = Vec::with_capacity(unpadded_bytes_per_row as usize * rows);
+  for row in padded.chunks(padded_bytes_per_row as usize) {
+    data.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+  }
+  data
+}
+
+// Copy a region of a texture into CPU memory as tightly packed rows of texel
+// blocks, taking care of the row padding required by texture-to-buffer
+// copies. Layers of 2D array textures and slices of 3D textures follow each
+// other.
+pub(crate) fn read_texture(
+  instance: &super::Instance,
+  device: wgpu_core::id::DeviceId,
+  source: &wgpu_core::command::ImageCopyTexture,
+  format: wgpu_types::TextureFormat,
+  copy_size: wgpu_types::Extent3d,
+) -> Result<Vec<u8>, AnyError> {
+  let block_size = copy_block_size(format, source.aspect).ok_or_else(|| {
+    AnyError::msg(format!(
+      "Unable to read back aspect {:?} of texture with format {format:?}.",
+      source.aspect
+    ))
+  })?;
+  let (block_width, block_height) = format.describe().block_dimensions;
+  let blocks_per_row =
+    (copy_size.width + block_width as u32 - 1) / block_width as u32;
+  let rows_per_image =
+    (copy_size.height + block_height as u32 - 1) / block_height as u32;
+  let rows = rows_per_image as usize * copy_size.depth_or_array_layers as usize;
+
+  let unpadded_bytes_per_row = blocks_per_row * block_size;
+  let padded_bytes_per_row = padded_bytes_per_row(unpadded_bytes_per_row);
+  let buffer_size = padded_bytes_per_row as u64 * rows as u64;
+  if buffer_size == 0 {
+    return Ok(Vec::new());
+  }
+
+  let descriptor = wgpu_core::resource::BufferDescriptor {
+    label: Some(Cow::Borrowed("readback")),
+    size: buffer_size,
+    usage: wgpu_types::BufferUsages::COPY_DST
+      | wgpu_types::BufferUsages::MAP_READ,
+    mapped_at_creation: false,
+  };
+  let (buffer, maybe_err) = gfx_select!(device => instance.device_create_buffer(
+    device,
+    &descriptor,
+    ()
+  ));
+  if let Some(err) = maybe_err {
+    gfx_select!(buffer => instance.buffer_drop(buffer, false));
+    return Err(err.into());
+  }
+
+  let result = (|| -> Result<Vec<u8>, AnyError> {
+    let (encoder, maybe_err) = gfx_select!(device => instance.device_create_command_encoder(
+      device,
+      &wgpu_types::CommandEncoderDescriptor { label: None },
+      ()
+    ));
+    if let Some(err) = maybe_err {
+      return Err(err.into());
+    }
+
+    let destination = wgpu_core::command::ImageCopyBuffer {
+      buffer,
+      layout: wgpu_types::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+        rows_per_image: NonZeroU32::new(rows_per_image),
+      },
+    };
+    gfx_select!(encoder => instance.command_encoder_copy_texture_to_buffer(
+      encoder,
+      source,
+      &destination,
+      &copy_size
+    ))?;
+
+    let (command_buffer, maybe_err) = gfx_select!(encoder => instance.command_encoder_finish(
+      encoder,
+      &wgpu_types::CommandBufferDescriptor { label: None }
+    ));
+    if let Some(err) = maybe_err {
+      return Err(err.into());
+    }
+    gfx_select!(device => instance.queue_submit(device, &[command_buffer]))?;
+
+    let (sender, receiver) = mpsc::channel();
+    let callback =
+      Box::new(move |status: wgpu_core::resource::BufferAccessResult| {
+        let _ = sender.send(status);
+      });
+    gfx_select!(buffer => instance.buffer_map_async(
+      buffer,
+      0..buffer_size,
+      wgpu_core::resource::BufferMapOperation {
+        host: wgpu_core::device::HostMap::Read,
+        callback: wgpu_core::resource::BufferMapCallback::from_rust(callback),
+      }
+    ))?;
+    gfx_select!(device => instance.device_poll(device, wgpu_types::Maintain::Wait))?;
+    receiver.recv()??;
+
+    let (pointer, mapped_size) = gfx_select!(buffer => instance.buffer_get_mapped_range(
+      buffer,
+      0,
+      Some(buffer_size)
+    ))?;
+    let mapped = {
+      // SAFETY: The buffer stays mapped for reading until the unmap below.
+      unsafe { std::slice::from_raw_parts(pointer, mapped_size as usize) }
+    };
+    let data =
+      strip_row_padding(mapped, padded_bytes_per_row, unpadded_bytes_per_row);
+    gfx_select!(buffer => instance.buffer_unmap(buffer))?;
+    Ok(data)
+  })();
+
+  gfx_select!(buffer => instance.buffer_drop(buffer, false));
+  result
+}
+
+// Copy a 2D texture into CPU memory as tightly packed RGBA8 rows.
+pub(crate) fn read_texture_rgba8(
+  instance: &super::Instance,
+  device: wgpu_core::id::DeviceId,
+  texture: wgpu_core::id::TextureId,
+  format: wgpu_types::TextureFormat,
+  size: (u32, u32),
+) -> Result<Vec<u8>, AnyError> {
+  use wgpu_types::TextureFormat::*;
+  let swizzle = match format {
+    Rgba8Unorm | Rgba8UnormSrgb => false,
+    Bgra8Unorm | Bgra8UnormSrgb => true,
+    _ => {
+      return Err(AnyError::msg(format!(
+        "Unable to read back texture with format {format:?}."
+      )))
+    }
+  };
+
+  let (width, height) = size;
+  let source = wgpu_core::command::ImageCopyTexture {
+    texture,
+    mip_level: 0,
+    origin: wgpu_types::Origin3d::ZERO,
+    aspect: wgpu_types::TextureAspect::All,
+  };
+  let copy_size = wgpu_types::Extent3d {
+    width,
+    height,
+    depth_or_array_layers: 1,
+  };
+  let mut pixels = read_texture(instance, device, &source, format, copy_size)?;
+
+  if swizzle {
+    for pixel in pixels.chunks_exact_mut(4) {
+      pixel.swap(0, 2);
+    }
+  }
+  Ok(pixels)
+}
+
+// Encode tightly packed RGBA8 pixels as a PNG, with optional text metadata.
+pub(crate) fn encode_png<W: Write>(
+  writer: W,
+  size: (u32, u32),
+  pixels: &[u8],
+  text: &[(&str, String)],
+) -> Result<(), AnyError> {
+  let (width, height) = size;
+  let mut encoder = png::Encoder::new(writer, width, height);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  for (keyword, value) in text {
+    encoder.add_text_chunk(keyword.to_string(), value.clone())?;
+  }
+  let mut writer = encoder.write_header()?;
+  writer.write_image_data(pixels)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use wgpu_types::{TextureAspect, TextureFormat};
+
+  #[test]
+  fn row_padding() {
+    assert_eq!(padded_bytes_per_row(256), 256);
+    assert_eq!(padded_bytes_per_row(512), 512);
+    assert_eq!(padded_bytes_per_row(4), 256);
+    assert_eq!(padded_bytes_per_row(260), 512);
+
+    let rows: Vec<u8> = (0..512).map(|i| i as u8).collect();
+    assert_eq!(strip_row_padding(&rows, 256, 256), rows);
+    let mut expected = rows[..12].to_vec();
+    expected.extend_from_slice(&rows[256..268]);
+    assert_eq!(strip_row_padding(&rows, 256, 12), expected);
+  }
+
+  #[test]
+  fn copy_block_size_compressed() {
+    let block_size = |format| copy_block_size(format, TextureAspect::All);
+    assert_eq!(block_size(TextureFormat::Bc1RgbaUnorm), Some(8));
+    assert_eq!(block_size(TextureFormat::Bc7RgbaUnorm), Some(16));
+    assert_eq!(block_size(TextureFormat::Rgba8Unorm), Some(4));
+    assert_eq!(
+      copy_block_size(TextureFormat::Bc1RgbaUnorm, TextureAspect::DepthOnly),
+      None
+    );
+  }
+
+  #[test]
+  fn copy_block_size_depth_stencil() {
+    use TextureAspect::*;
+    use TextureFormat::*;
+    assert_eq!(copy_block_size(Depth16Unorm, All), Some(2));
+    assert_eq!(copy_block_size(Depth32Float, DepthOnly), Some(4));
+    assert_eq!(copy_block_size(Depth32Float, StencilOnly), None);
+    assert_eq!(copy_block_size(Depth24Plus, All), None);
+    assert_eq!(copy_block_size(Depth24PlusStencil8, DepthOnly), None);
+    assert_eq!(copy_block_size(Depth24PlusStencil8, StencilOnly), Some(1));
+    assert_eq!(copy_block_size(Depth32FloatStencil8, All), None);
+    assert_eq!(copy_block_size(Depth32FloatStencil8, DepthOnly), Some(4));
+    assert_eq!(copy_block_size(Stencil8, All), Some(1));
+  }
+}
@@ -284,6 +284,34 @@ pub fn op_webgpu_render_pass_set_bind_group(
   Ok(WebGpuResult::empty())
 }
 
+#[op]
+pub fn op_webgpu_render_pass_set_push_constants(
+  state: &mut OpState,
+  render_pass_rid: ResourceId,
+  stages: u32,
+  offset: u32,
+  data: ZeroCopyBuf,
+) -> Result<WebGpuResult, AnyError> {
+  let render_pass_resource = state
+    .resource_table
+    .get::<WebGpuRenderPass>(render_pass_rid)?;
+  let size_bytes = super::binding::push_constants_size(offset, &data)?;
+
+  // SAFETY: the raw pointer and length are of the same slice, and that slice
+  // lives longer than the below function invocation.
+  unsafe {
+    wgpu_core::command::render_ffi::wgpu_render_pass_set_push_constants(
+      &mut render_pass_resource.0.borrow_mut(),
+      wgpu_types::ShaderStages::from_bits_truncate(stages),
+      offset,
+      size_bytes,
+      data.as_ptr(),
+    );
+  }
+
+  Ok(WebGpuResult::empty())
+}
+
 #[op]
 pub fn op_webgpu_render_pass_push_debug_group(
   state: &mut OpState,
@@ -536,3 +564,119 @@ pub fn op_webgpu_render_pass_draw_indexed_indirect(
 
   Ok(WebGpuResult::empty())
 }
+
+#[op]
+pub fn op_webgpu_render_pass_multi_draw_indirect(
+  state: &mut OpState,
+  render_pass_rid: ResourceId,
+  indirect_buffer: ResourceId,
+  indirect_offset: u64,
+  count: u32,
+) -> Result<WebGpuResult, AnyError> {
+  let buffer_resource = state
+    .resource_table
+    .get::<super::buffer::WebGpuBuffer>(indirect_buffer)?;
+  let render_pass_resource = state
+    .resource_table
+    .get::<WebGpuRenderPass>(render_pass_rid)?;
+
+  wgpu_core::command::render_ffi::wgpu_render_pass_multi_draw_indirect(
+    &mut render_pass_resource.0.borrow_mut(),
+    buffer_resource.0,
+    indirect_offset,
+    count,
+  );
+
+  Ok(WebGpuResult::empty())
+}
+
+#[op]
+pub fn op_webgpu_render_pass_multi_draw_indexed_indirect(
+  state: &mut OpState,
+  render_pass_rid: ResourceId,
+  indirect_buffer: ResourceId,
+  indirect_offset: u64,
+  count: u32,
+) -> Result<WebGpuResult, AnyError> {
+  let buffer_resource = state
+    .resource_table
+    .get::<super::buffer::WebGpuBuffer>(indirect_buffer)?;
+  let render_pass_resource = state
+    .resource_table
+    .get::<WebGpuRenderPass>(render_pass_rid)?;
+
+  wgpu_core::command::render_ffi::wgpu_render_pass_multi_draw_indexed_indirect(
+    &mut render_pass_resource.0.borrow_mut(),
+    buffer_resource.0,
+    indirect_offset,
+    count,
+  );
+
+  Ok(WebGpuResult::empty())
+}
+
+#[op]
+pub fn op_webgpu_render_pass_multi_draw_indirect_count(
+  state: &mut OpState,
+  render_pass_rid: ResourceId,
+  indirect_buffer: ResourceId,
+  indirect_offset: u64,
+  count_buffer: ResourceId,
+  count_buffer_offset: u64,
+  max_count: u32,
+) -> Result<WebGpuResult, AnyError> {
+  let buffer_resource = state
+    .resource_table
+    .get::<super::buffer::WebGpuBuffer>(indirect_buffer)?;
+  let count_buffer_resource =
+    state
+      .resource_table
+      .get::<super::buffer::WebGpuBuffer>(count_buffer)?;
+  let render_pass_resource = state
+    .resource_table
+    .get::<WebGpuRenderPass>(render_pass_rid)?;
+
+  wgpu_core::command::render_ffi::wgpu_render_pass_multi_draw_indirect_count(
+    &mut render_pass_resource.0.borrow_mut(),
+    buffer_resource.0,
+    indirect_offset,
+    count_buffer_resource.0,
+    count_buffer_offset,
+    max_count,
+  );
+
+  Ok(WebGpuResult::empty())
+}
+
+#[op]
+pub fn op_webgpu_render_pass_multi_draw_indexed_indirect_count(
+  state: &mut OpState,
+  render_pass_rid: ResourceId,
+  indirect_buffer: ResourceId,
+  indirect_offset: u64,
+  count_buffer: ResourceId,
+  count_buffer_offset: u64,
+  max_count: u32,
+) -> Result<WebGpuResult, AnyError> {
+  let buffer_resource = state
+    .resource_table
+    .get::<super::buffer::WebGpuBuffer>(indirect_buffer)?;
+  let count_buffer_resource =
+    state
+      .resource_table
+      .get::<super::buffer::WebGpuBuffer>(count_buffer)?;
+  let render_pass_resource = state
+    .resource_table
+    .get::<WebGpuRenderPass>(render_pass_rid)?;
+
+  wgpu_core::command::render_ffi::wgpu_render_pass_multi_draw_indexed_indirect_count(
+    &mut render_pass_resource.0.borrow_mut(),
+    buffer_resource.0,
+    indirect_offset,
+    count_buffer_resource.0,
+    count_buffer_offset,
+    max_count,
+  );
+
+  Ok(WebGpuResult::empty())
+}
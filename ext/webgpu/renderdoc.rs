@@ -0,0 +1,121 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+//! Frame captures with RenderDoc (https://renderdoc.org/) when the program was
+//! launched from it. See the In-Application API documentation at
+//! https://renderdoc.org/docs/in_application_api.html.
+
+use deno_core::error::AnyError;
+use deno_core::op;
+use deno_core::OpState;
+use std::ffi::c_void;
+use std::ptr;
+
+struct RenderDoc {
+  api: renderdoc_sys::RENDERDOC_API_1_4_1,
+  // Keeps the function pointers in `api` valid.
+  _lib: libloading::Library,
+}
+
+impl RenderDoc {
+  // Returns `None` unless RenderDoc injected itself into this process. It's
+  // never loaded otherwise.
+  fn load() -> Option<Self> {
+    #[cfg(windows)]
+    let lib: libloading::Library =
+      libloading::os::windows::Library::open_already_loaded("renderdoc.dll")
+        .ok()?
+        .into();
+    #[cfg(unix)]
+    let lib: libloading::Library = {
+      // SAFETY: RTLD_NOLOAD doesn't run any initializers since it only
+      // succeeds if the library is already loaded.
+      unsafe {
+        libloading::os::unix::Library::open(
+          Some("librenderdoc.so"),
+          libloading::os::unix::RTLD_NOW | libc::RTLD_NOLOAD,
+        )
+      }
+      .ok()?
+      .into()
+    };
+
+    type GetApiFn =
+      unsafe extern "C" fn(version: u32, out: *mut *mut c_void) -> i32;
+    // SAFETY: RENDERDOC_GetAPI has this signature in every RenderDoc version.
+    let get_api = unsafe { lib.get::<GetApiFn>(b"RENDERDOC_GetAPI\0") }.ok()?;
+    let mut api = ptr::null_mut();
+    // SAFETY: On success, `api` points to a function table for the requested
+    // version that lives as long as the library.
+    unsafe {
+      if get_api(renderdoc_sys::eRENDERDOC_API_Version_1_4_1, &mut api) != 1 {
+        return None;
+      }
+      let api = *(api as *const renderdoc_sys::RENDERDOC_API_1_4_1);
+      Some(Self { api, _lib: lib })
+    }
+  }
+}
+
+// Loaded the first time a RenderDoc op is called.
+struct RenderDocState(Option<RenderDoc>);
+
+fn renderdoc(state: &mut OpState) -> Option<&RenderDoc> {
+  if !state.has::<RenderDocState>() {
+    state.put(RenderDocState(RenderDoc::load()));
+  }
+  state.borrow::<RenderDocState>().0.as_ref()
+}
+
+fn require_renderdoc<'a>(
+  state: &'a mut OpState,
+  api_name: &str,
+) -> Result<&'a RenderDoc, AnyError> {
+  renderdoc(state).ok_or_else(|| {
+    AnyError::msg(format!(
+      "{api_name} requires the program to be launched from RenderDoc."
+    ))
+  })
+}
+
+#[op]
+pub fn op_webgpu_renderdoc_is_attached(state: &mut OpState) -> bool {
+  renderdoc(state).is_some()
+}
+
+// Captures the next `frames` frames presented to the active window.
+#[op]
+pub fn op_webgpu_renderdoc_trigger_capture(
+  state: &mut OpState,
+  frames: u32,
+) -> Result<(), AnyError> {
+  let renderdoc = require_renderdoc(state, "GPU.triggerRenderDocCapture")?;
+  // SAFETY: The function table is valid while RenderDoc is loaded.
+  unsafe { renderdoc.api.TriggerMultiFrameCapture.unwrap()(frames.max(1)) };
+  Ok(())
+}
+
+// Null device and window handles let RenderDoc pick the device and window
+// that are currently active.
+#[op]
+pub fn op_webgpu_renderdoc_start_capture(
+  state: &mut OpState,
+) -> Result<(), AnyError> {
+  let renderdoc = require_renderdoc(state, "GPU.startRenderDocCapture")?;
+  // SAFETY: The function table is valid while RenderDoc is loaded.
+  unsafe {
+    renderdoc.api.StartFrameCapture.unwrap()(ptr::null_mut(), ptr::null_mut())
+  };
+  Ok(())
+}
+
+#[op]
+pub fn op_webgpu_renderdoc_end_capture(
+  state: &mut OpState,
+) -> Result<bool, AnyError> {
+  let renderdoc = require_renderdoc(state, "GPU.endRenderDocCapture")?;
+  // SAFETY: The function table is valid while RenderDoc is loaded.
+  let captured = unsafe {
+    renderdoc.api.EndFrameCapture.unwrap()(ptr::null_mut(), ptr::null_mut())
+  };
+  Ok(captured == 1)
+}
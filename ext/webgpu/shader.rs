@@ -22,6 +22,17 @@ pub fn op_webgpu_create_shader_module(
   device_rid: ResourceId,
   label: Option<String>,
   code: String,
+) -> Result<WebGpuResult, AnyError> {
+  create_shader_module(state, device_rid, label, code)
+}
+
+// Also used by other extensions that compile WGSL for a device, e.g. when a
+// watched shader file changes.
+pub fn create_shader_module(
+  state: &mut OpState,
+  device_rid: ResourceId,
+  label: Option<String>,
+  code: String,
 ) -> Result<WebGpuResult, AnyError> {
   let instance = state.borrow::<super::Instance>();
   let device_resource = state
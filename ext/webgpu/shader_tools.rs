@@ -0,0 +1,417 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+//! Shader tooling built on naga, independent of any adapter or device:
+//! validating WGSL, translating SPIR-V and GLSL to WGSL and reflecting the
+//! bind group layouts a WGSL module expects.
+
+use deno_core::error::type_error;
+use deno_core::error::AnyError;
+use deno_core::op;
+use deno_core::ZeroCopyBuf;
+use naga::valid::Capabilities;
+use naga::valid::ModuleInfo;
+use naga::valid::ValidationFlags;
+use naga::valid::Validator;
+use naga::Module;
+use naga::SourceLocation;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+// Shaped like GPUCompilationMessage. Offsets and lengths are in bytes of the
+// UTF-8 source; lines and positions start at 1, or are 0 when unknown.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuShaderMessage {
+  message: String,
+  r#type: &'static str,
+  line_num: u32,
+  line_pos: u32,
+  offset: u32,
+  length: u32,
+}
+
+impl GpuShaderMessage {
+  pub fn error(message: String, location: Option<SourceLocation>) -> Self {
+    let location = location.unwrap_or(SourceLocation {
+      line_number: 0,
+      line_position: 0,
+      offset: 0,
+      length: 0,
+    });
+    Self {
+      message,
+      r#type: "error",
+      line_num: location.line_number,
+      line_pos: location.line_position,
+      offset: location.offset,
+      length: location.length,
+    }
+  }
+}
+
+// Joins an error with its sources, which is where naga puts most details of
+// validation errors.
+fn error_chain(err: &dyn Error) -> String {
+  let mut message = err.to_string();
+  let mut source = err.source();
+  while let Some(err) = source {
+    message.push_str(": ");
+    message.push_str(&err.to_string());
+    source = err.source();
+  }
+  message
+}
+
+fn parse_wgsl(code: &str) -> Result<Module, Vec<GpuShaderMessage>> {
+  naga::front::wgsl::parse_str(code).map_err(|err| {
+    let mut message = err.message().to_string();
+    for (_, label) in err.labels().filter(|(_, label)| !label.is_empty()) {
+      message.push_str(": ");
+      message.push_str(label);
+    }
+    vec![GpuShaderMessage::error(message, err.location(code))]
+  })
+}
+
+// Every capability is allowed since there's no device to check them against.
+// Those are checked again when the module is created on a device.
+fn validate(
+  module: &Module,
+  source: Option<&str>,
+) -> Result<ModuleInfo, Vec<GpuShaderMessage>> {
+  Validator::new(ValidationFlags::all(), Capabilities::all())
+    .validate(module)
+    .map_err(|err| {
+      let location = source.and_then(|source| err.location(source));
+      vec![GpuShaderMessage::error(
+        error_chain(err.as_inner()),
+        location,
+      )]
+    })
+}
+
+#[op]
+pub fn op_webgpu_shader_validate_wgsl(code: String) -> Vec<GpuShaderMessage> {
+  validate_wgsl(&code)
+}
+
+pub fn validate_wgsl(code: &str) -> Vec<GpuShaderMessage> {
+  match parse_wgsl(code).and_then(|module| validate(&module, Some(code))) {
+    Ok(_) => Vec::new(),
+    Err(messages) => messages,
+  }
+}
+
+#[derive(Serialize)]
+pub struct GpuShaderTranslation {
+  code: Option<String>,
+  messages: Vec<GpuShaderMessage>,
+}
+
+impl From<Result<String, Vec<GpuShaderMessage>>> for GpuShaderTranslation {
+  fn from(result: Result<String, Vec<GpuShaderMessage>>) -> Self {
+    match result {
+      Ok(code) => Self {
+        code: Some(code),
+        messages: Vec::new(),
+      },
+      Err(messages) => Self {
+        code: None,
+        messages,
+      },
+    }
+  }
+}
+
+fn write_wgsl(module: &Module) -> Result<String, Vec<GpuShaderMessage>> {
+  let info = validate(module, None)?;
+  naga::back::wgsl::write_string(
+    module,
+    &info,
+    naga::back::wgsl::WriterFlags::empty(),
+  )
+  .map_err(|err| vec![GpuShaderMessage::error(error_chain(&err), None)])
+}
+
+#[op]
+pub fn op_webgpu_shader_spirv_to_wgsl(
+  spirv: ZeroCopyBuf,
+) -> GpuShaderTranslation {
+  let options = naga::front::spv::Options::default();
+  naga::front::spv::parse_u8_slice(&spirv, &options)
+    .map_err(|err| vec![GpuShaderMessage::error(error_chain(&err), None)])
+    .and_then(|module| write_wgsl(&module))
+    .into()
+}
+
+#[op]
+pub fn op_webgpu_shader_glsl_to_wgsl(
+  code: String,
+  stage: String,
+) -> Result<GpuShaderTranslation, AnyError> {
+  let stage = match stage.as_str() {
+    "vertex" => naga::ShaderStage::Vertex,
+    "fragment" => naga::ShaderStage::Fragment,
+    "compute" => naga::ShaderStage::Compute,
+    _ => return Err(type_error(format!("Invalid shader stage: {stage}"))),
+  };
+  let options = naga::front::glsl::Options::from(stage);
+  Ok(
+    naga::front::glsl::Parser::default()
+      .parse(&options, &code)
+      .map_err(|errors| {
+        errors
+          .into_iter()
+          .map(|err| {
+            let location =
+              err.meta.is_defined().then(|| err.meta.location(&code));
+            GpuShaderMessage::error(err.kind.to_string(), location)
+          })
+          .collect()
+      })
+      .and_then(|module| write_wgsl(&module))
+      .into(),
+  )
+}
+
+// The members below are named and valued like the GPUBindGroupLayoutEntry
+// members they correspond to, so entries can be passed to
+// GPUDevice.createBindGroupLayout as they are.
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuReflectedBufferLayout {
+  r#type: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuReflectedSamplerLayout {
+  r#type: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuReflectedTextureLayout {
+  sample_type: &'static str,
+  view_dimension: &'static str,
+  multisampled: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuReflectedStorageTextureLayout {
+  access: &'static str,
+  format: String,
+  view_dimension: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuReflectedBinding {
+  binding: u32,
+  name: Option<String>,
+  visibility: u32,
+  // The number of elements of a binding array, or `None` for a single
+  // resource or a runtime-sized binding array.
+  count: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  buffer: Option<GpuReflectedBufferLayout>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  sampler: Option<GpuReflectedSamplerLayout>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  texture: Option<GpuReflectedTextureLayout>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  storage_texture: Option<GpuReflectedStorageTextureLayout>,
+}
+
+#[derive(Serialize)]
+pub struct GpuReflectedBindGroup {
+  group: u32,
+  entries: Vec<GpuReflectedBinding>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuShaderReflection {
+  bind_groups: Vec<GpuReflectedBindGroup>,
+  messages: Vec<GpuShaderMessage>,
+}
+
+fn view_dimension(dim: naga::ImageDimension, arrayed: bool) -> &'static str {
+  match (dim, arrayed) {
+    (naga::ImageDimension::D1, _) => "1d",
+    (naga::ImageDimension::D2, false) => "2d",
+    (naga::ImageDimension::D2, true) => "2d-array",
+    (naga::ImageDimension::D3, _) => "3d",
+    (naga::ImageDimension::Cube, false) => "cube",
+    (naga::ImageDimension::Cube, true) => "cube-array",
+  }
+}
+
+// naga's storage formats are named like GPUTextureFormat, except for the one
+// format whose name WebGPU spells differently.
+fn storage_format_name(format: naga::StorageFormat) -> String {
+  match format {
+    naga::StorageFormat::Rg11b10Float => "rg11b10ufloat".to_string(),
+    format => format!("{format:?}").to_lowercase(),
+  }
+}
+
+fn reflect_binding(
+  module: &Module,
+  var: &naga::GlobalVariable,
+  binding: u32,
+  visibility: u32,
+) -> GpuReflectedBinding {
+  let mut reflected = GpuReflectedBinding {
+    binding,
+    name: var.name.clone(),
+    visibility,
+    count: None,
+    buffer: None,
+    sampler: None,
+    texture: None,
+    storage_texture: None,
+  };
+
+  let mut inner = &module.types[var.ty].inner;
+  if let naga::TypeInner::BindingArray { base, size } = *inner {
+    if let naga::ArraySize::Constant(size) = size {
+      reflected.count = match module.constants[size].inner {
+        naga::ConstantInner::Scalar {
+          value: naga::ScalarValue::Uint(size),
+          ..
+        } => u32::try_from(size).ok(),
+        naga::ConstantInner::Scalar {
+          value: naga::ScalarValue::Sint(size),
+          ..
+        } => u32::try_from(size).ok(),
+        _ => None,
+      };
+    }
+    inner = &module.types[base].inner;
+  }
+
+  match var.space {
+    naga::AddressSpace::Uniform => {
+      reflected.buffer = Some(GpuReflectedBufferLayout { r#type: "uniform" });
+    }
+    naga::AddressSpace::Storage { access } => {
+      let r#type = if access.contains(naga::StorageAccess::STORE) {
+        "storage"
+      } else {
+        "read-only-storage"
+      };
+      reflected.buffer = Some(GpuReflectedBufferLayout { r#type });
+    }
+    _ => match *inner {
+      naga::TypeInner::Sampler { comparison } => {
+        let r#type = if comparison {
+          "comparison"
+        } else {
+          "filtering"
+        };
+        reflected.sampler = Some(GpuReflectedSamplerLayout { r#type });
+      }
+      naga::TypeInner::Image {
+        dim,
+        arrayed,
+        class,
+      } => match class {
+        naga::ImageClass::Sampled { kind, multi } => {
+          let sample_type = match kind {
+            naga::ScalarKind::Sint => "sint",
+            naga::ScalarKind::Uint => "uint",
+            _ => "float",
+          };
+          reflected.texture = Some(GpuReflectedTextureLayout {
+            sample_type,
+            view_dimension: view_dimension(dim, arrayed),
+            multisampled: multi,
+          });
+        }
+        naga::ImageClass::Depth { multi } => {
+          reflected.texture = Some(GpuReflectedTextureLayout {
+            sample_type: "depth",
+            view_dimension: view_dimension(dim, arrayed),
+            multisampled: multi,
+          });
+        }
+        naga::ImageClass::Storage { format, access } => {
+          let access = if access.contains(naga::StorageAccess::LOAD) {
+            if access.contains(naga::StorageAccess::STORE) {
+              "read-write"
+            } else {
+              "read-only"
+            }
+          } else {
+            "write-only"
+          };
+          reflected.storage_texture = Some(GpuReflectedStorageTextureLayout {
+            access,
+            format: storage_format_name(format),
+            view_dimension: view_dimension(dim, arrayed),
+          });
+        }
+      },
+      _ => {}
+    },
+  }
+
+  reflected
+}
+
+fn reflect(module: &Module, info: &ModuleInfo) -> Vec<GpuReflectedBindGroup> {
+  let mut groups = BTreeMap::<u32, Vec<GpuReflectedBinding>>::new();
+  for (handle, var) in module.global_variables.iter() {
+    let Some(binding) = &var.binding else {
+      continue;
+    };
+    // Same bits as GPUShaderStage.
+    let visibility = module
+      .entry_points
+      .iter()
+      .enumerate()
+      .filter(|(i, _)| !info.get_entry_point(*i)[handle].is_empty())
+      .fold(0, |visibility, (_, entry_point)| {
+        visibility
+          | match entry_point.stage {
+            naga::ShaderStage::Vertex => 0x1,
+            naga::ShaderStage::Fragment => 0x2,
+            naga::ShaderStage::Compute => 0x4,
+          }
+      });
+    groups
+      .entry(binding.group)
+      .or_default()
+      .push(reflect_binding(module, var, binding.binding, visibility));
+  }
+
+  groups
+    .into_iter()
+    .map(|(group, mut entries)| {
+      entries.sort_by_key(|entry| entry.binding);
+      GpuReflectedBindGroup { group, entries }
+    })
+    .collect()
+}
+
+#[op]
+pub fn op_webgpu_shader_reflect_wgsl(code: String) -> GpuShaderReflection {
+  let result = parse_wgsl(&code).and_then(|module| {
+    let info = validate(&module, Some(&code))?;
+    Ok(reflect(&module, &info))
+  });
+  match result {
+    Ok(bind_groups) => GpuShaderReflection {
+      bind_groups,
+      messages: Vec::new(),
+    },
+    Err(messages) => GpuShaderReflection {
+      bind_groups: Vec::new(),
+      messages,
+    },
+  }
+}
@@ -1,12 +1,139 @@
 // Copyright 2018-2023 the Deno authors. All rights reserved. MIT license.
 // Copyright 2023 Jo Bates. All rights reserved. MIT license.
 
-use crate::{texture::WebGpuTexture, WebGpuAdapter, WebGpuDevice};
-use deno_core::{error::AnyError, op, OpState, Resource, ResourceId};
+use crate::{
+  overlay::DiagnosticsOverlay, readback, texture::WebGpuTexture, WebGpuAdapter,
+  WebGpuDevice, WebGpuPermissions,
+};
+use deno_core::{
+  error::type_error, error::AnyError, op, OpState, Resource, ResourceId,
+};
+use raw_window_handle::{
+  AppKitDisplayHandle, AppKitWindowHandle, RawDisplayHandle, RawWindowHandle,
+  WaylandDisplayHandle, WaylandWindowHandle, Win32WindowHandle,
+  WindowsDisplayHandle, XcbDisplayHandle, XcbWindowHandle, XlibDisplayHandle,
+  XlibWindowHandle,
+};
 use serde::{ser::SerializeSeq, Deserialize, Serialize, Serializer};
-use std::borrow::Cow;
+use std::{
+  borrow::Cow,
+  cell::Cell,
+  cell::RefCell,
+  ffi::{c_ulong, c_void},
+  fs::File,
+  io::BufWriter,
+  path::PathBuf,
+  time::Instant,
+};
+
+pub struct WebGpuSurface {
+  pub id: wgpu_core::id::SurfaceId,
+  config: RefCell<
+    Option<wgpu_types::SurfaceConfiguration<Vec<wgpu_types::TextureFormat>>>,
+  >,
+  frame_capture: RefCell<Option<FrameCapture>>,
+  auto_recover: Cell<bool>,
+  pending_size: Cell<Option<(u32, u32)>>,
+  present_listener: RefCell<Option<Box<dyn Fn()>>>,
+  diagnostics_overlay: RefCell<Option<DiagnosticsOverlay>>,
+  refresh_rate_millihertz: Cell<Option<u32>>,
+  present_count: Cell<u64>,
+  last_present: Cell<Option<Instant>>,
+}
+
+impl WebGpuSurface {
+  pub fn new(id: wgpu_core::id::SurfaceId) -> Self {
+    Self {
+      id,
+      config: RefCell::new(None),
+      frame_capture: RefCell::new(None),
+      auto_recover: Cell::new(false),
+      pending_size: Cell::new(None),
+      present_listener: RefCell::new(None),
+      diagnostics_overlay: RefCell::new(None),
+      refresh_rate_millihertz: Cell::new(None),
+      present_count: Cell::new(0),
+      last_present: Cell::new(None),
+    }
+  }
+
+  // Reconfigure the surface with its last configuration when it becomes
+  // outdated or lost instead of reporting an error.
+  pub fn set_auto_recover(&self, auto_recover: bool) {
+    self.auto_recover.set(auto_recover);
+  }
+
+  // Reconfigure the surface to the given size before the next frame is
+  // acquired. Zero sizes (e.g. minimized windows) are ignored.
+  pub fn resize(&self, width: u32, height: u32) {
+    if width > 0 && height > 0 {
+      self.pending_size.set(Some((width, height)));
+    }
+  }
+
+  // Call the given function after each successful present.
+  pub fn set_present_listener(&self, listener: impl Fn() + 'static) {
+    *self.present_listener.borrow_mut() = Some(Box::new(listener));
+  }
+
+  // Draw FPS, a graph of frame times and GPU resource counts over each frame
+  // before it's presented, followed by the lines that `lines` returns, or stop
+  // with `None`. Only surfaces with COPY_DST usage and an 8-bit RGBA or BGRA
+  // format show it.
+  pub fn set_diagnostics_overlay(
+    &self,
+    lines: Option<Box<dyn Fn() -> Vec<String>>>,
+  ) {
+    *self.diagnostics_overlay.borrow_mut() = lines.map(DiagnosticsOverlay::new);
+  }
+
+  pub fn has_diagnostics_overlay(&self) -> bool {
+    self.diagnostics_overlay.borrow().is_some()
+  }
+
+  // The refresh rate of the display the surface is shown on, used to count
+  // missed vertical blanks in present statistics.
+  pub fn set_refresh_rate_millihertz(&self, refresh_rate: Option<u32>) {
+    self.refresh_rate_millihertz.set(refresh_rate);
+  }
+
+  fn present_statistics(
+    &self,
+    started: Instant,
+    finished: Instant,
+  ) -> GpuSurfacePresentStatistics {
+    let present_count = self.present_count.get() + 1;
+    self.present_count.set(present_count);
+    let interval = self
+      .last_present
+      .replace(Some(finished))
+      .map(|last| finished.duration_since(last).as_secs_f64() * 1000.0);
+    let missed_vsyncs = interval.zip(self.refresh_rate_millihertz.get()).map(
+      |(interval, refresh_rate)| {
+        let vsync_interval = 1_000_000.0 / refresh_rate as f64;
+        ((interval / vsync_interval).round() as u32).saturating_sub(1)
+      },
+    );
+    GpuSurfacePresentStatistics {
+      present_count,
+      present_duration: finished.duration_since(started).as_secs_f64() * 1000.0,
+      interval,
+      missed_vsyncs,
+    }
+  }
+
+  fn recoverable_config(
+    &self,
+  ) -> Option<wgpu_types::SurfaceConfiguration<Vec<wgpu_types::TextureFormat>>>
+  {
+    if self.auto_recover.get() {
+      self.config.borrow().clone()
+    } else {
+      None
+    }
+  }
+}
 
-pub struct WebGpuSurface(pub wgpu_core::id::SurfaceId);
 impl Resource for WebGpuSurface {
   fn name(&self) -> Cow<str> {
     "webGPUSurface".into()
@@ -33,6 +160,22 @@ impl From<wgpu_types::SurfaceCapabilities> for GpuSurfaceCapabilities {
   }
 }
 
+// Timing of a present, for detecting stutter. Durations are in milliseconds.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GpuSurfacePresentStatistics {
+  // Presents so far, including this one.
+  present_count: u64,
+  // Time spent presenting, which includes waiting for the compositor when the
+  // swap chain is full.
+  present_duration: f64,
+  // Time since the previous present finished.
+  interval: Option<f64>,
+  // Vertical blanks since the previous present that didn't show a new frame.
+  // Only known if the display's refresh rate is.
+  missed_vsyncs: Option<u32>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case", remote = "wgpu_types::PresentMode")]
 enum GpuSurfacePresentMode {
@@ -50,19 +193,23 @@ fn serialize_present_modes<S: Serializer>(
 ) -> Result<S::Ok, S::Error> {
   let mut s = s.serialize_seq(Some(modes.len()))?;
   for mode in modes {
-    use wgpu_types::PresentMode::*;
-    s.serialize_element(match mode {
-      AutoVsync => "auto-vsync",
-      AutoNoVsync => "auto-no-vsync",
-      Fifo => "fifo",
-      FifoRelaxed => "fifo-relaxed",
-      Immediate => "immediate",
-      Mailbox => "mailbox",
-    })?
+    s.serialize_element(present_mode_name(*mode))?
   }
   s.end()
 }
 
+fn present_mode_name(mode: wgpu_types::PresentMode) -> &'static str {
+  use wgpu_types::PresentMode::*;
+  match mode {
+    AutoVsync => "auto-vsync",
+    AutoNoVsync => "auto-no-vsync",
+    Fifo => "fifo",
+    FifoRelaxed => "fifo-relaxed",
+    Immediate => "immediate",
+    Mailbox => "mailbox",
+  }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case", remote = "wgpu_types::CompositeAlphaMode")]
 enum GpuSurfaceAlphaMode {
@@ -79,18 +226,60 @@ fn serialize_alpha_modes<S: Serializer>(
 ) -> Result<S::Ok, S::Error> {
   let mut s = s.serialize_seq(Some(modes.len()))?;
   for mode in modes {
-    use wgpu_types::CompositeAlphaMode::*;
-    s.serialize_element(match mode {
-      Auto => "auto",
-      Opaque => "opaque",
-      PreMultiplied => "pre-multiplied",
-      PostMultiplied => "post-multiplied",
-      Inherit => "inherit",
-    })?
+    s.serialize_element(alpha_mode_name(*mode))?
   }
   s.end()
 }
 
+fn alpha_mode_name(mode: wgpu_types::CompositeAlphaMode) -> &'static str {
+  use wgpu_types::CompositeAlphaMode::*;
+  match mode {
+    Auto => "auto",
+    Opaque => "opaque",
+    PreMultiplied => "pre-multiplied",
+    PostMultiplied => "post-multiplied",
+    Inherit => "inherit",
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GpuSurfacePreferredConfiguration {
+  format: wgpu_types::TextureFormat,
+  present_mode: &'static str,
+  alpha_mode: &'static str,
+}
+
+impl From<wgpu_types::SurfaceCapabilities>
+  for GpuSurfacePreferredConfiguration
+{
+  // Mirrors `navigator.gpu.getPreferredCanvasFormat()`: an 8-bit non-sRGB
+  // format, opaque compositing and vsync, falling back to whatever the surface
+  // lists first.
+  fn from(caps: wgpu_types::SurfaceCapabilities) -> Self {
+    use wgpu_types::{CompositeAlphaMode, PresentMode, TextureFormat};
+    let format = [TextureFormat::Bgra8Unorm, TextureFormat::Rgba8Unorm]
+      .into_iter()
+      .find(|format| caps.formats.contains(format))
+      .unwrap_or(caps.formats[0]);
+    let present_mode = if caps.present_modes.contains(&PresentMode::Fifo) {
+      PresentMode::Fifo
+    } else {
+      caps.present_modes[0]
+    };
+    let alpha_mode = if caps.alpha_modes.contains(&CompositeAlphaMode::Opaque) {
+      CompositeAlphaMode::Opaque
+    } else {
+      caps.alpha_modes[0]
+    };
+    Self {
+      format,
+      present_mode: present_mode_name(present_mode),
+      alpha_mode: alpha_mode_name(alpha_mode),
+    }
+  }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct GpuSurfaceConfiguration {
@@ -118,6 +307,100 @@ fn check_suboptimal(
   Err(AnyError::msg(msg))
 }
 
+// Raw handles of a window created by another toolkit, e.g. passed in through
+// FFI by a plugin host. X11 windows are identified by an id instead of a
+// pointer.
+fn foreign_window_handles(
+  system: &str,
+  window: *mut c_void,
+  window_id: u32,
+  display: *mut c_void,
+) -> Result<(RawDisplayHandle, RawWindowHandle), AnyError> {
+  let require = |pointer: *mut c_void, name: &str| {
+    if pointer.is_null() {
+      Err(type_error(format!(
+        "{name} handle of {system} window is null."
+      )))
+    } else {
+      Ok(pointer)
+    }
+  };
+  Ok(match system {
+    "win32" => {
+      let mut window_handle = Win32WindowHandle::empty();
+      window_handle.hwnd = require(window, "window")?;
+      window_handle.hinstance = display;
+      (
+        RawDisplayHandle::Windows(WindowsDisplayHandle::empty()),
+        RawWindowHandle::Win32(window_handle),
+      )
+    }
+    "xlib" => {
+      let mut display_handle = XlibDisplayHandle::empty();
+      display_handle.display = require(display, "display")?;
+      let mut window_handle = XlibWindowHandle::empty();
+      window_handle.window = window_id as c_ulong;
+      (
+        RawDisplayHandle::Xlib(display_handle),
+        RawWindowHandle::Xlib(window_handle),
+      )
+    }
+    "xcb" => {
+      let mut display_handle = XcbDisplayHandle::empty();
+      display_handle.connection = require(display, "display")?;
+      let mut window_handle = XcbWindowHandle::empty();
+      window_handle.window = window_id;
+      (
+        RawDisplayHandle::Xcb(display_handle),
+        RawWindowHandle::Xcb(window_handle),
+      )
+    }
+    "wayland" => {
+      let mut display_handle = WaylandDisplayHandle::empty();
+      display_handle.display = require(display, "display")?;
+      let mut window_handle = WaylandWindowHandle::empty();
+      window_handle.surface = require(window, "window")?;
+      (
+        RawDisplayHandle::Wayland(display_handle),
+        RawWindowHandle::Wayland(window_handle),
+      )
+    }
+    "appkit" => {
+      let mut window_handle = AppKitWindowHandle::empty();
+      window_handle.ns_view = require(window, "window")?;
+      window_handle.ns_window = display;
+      (
+        RawDisplayHandle::AppKit(AppKitDisplayHandle::empty()),
+        RawWindowHandle::AppKit(window_handle),
+      )
+    }
+    _ => return Err(type_error(format!("Unknown window system {system}."))),
+  })
+}
+
+#[op]
+pub(crate) fn op_webgpu_create_surface_from_raw_handles(
+  state: &mut OpState,
+  system: String,
+  window: *mut c_void,
+  window_id: u32,
+  display: *mut c_void,
+) -> Result<ResourceId, AnyError> {
+  super::check_unstable(state, "navigator.gpu.createSurfaceFromRawHandles");
+  let (display_handle, window_handle) =
+    foreign_window_handles(&system, window, window_id, display)?;
+
+  if state.try_borrow::<super::Instance>().is_none() {
+    let backends = state.borrow::<super::Backends>().0;
+    state.put(super::create_instance_internal(backends));
+  }
+  let instance = state.borrow::<super::Instance>();
+  let surface_id =
+    instance.instance_create_surface(display_handle, window_handle, ());
+
+  Ok(state.resource_table.add(WebGpuSurface::new(surface_id)))
+}
+
 #[op]
 pub(crate) fn op_webgpu_surface_get_capabilities(
   state: &mut OpState,
@@ -128,7 +411,7 @@ pub(crate) fn op_webgpu_surface_get_capabilities(
 
   let surface_resource =
     state.resource_table.get::<WebGpuSurface>(surface_rid)?;
-  let surface = surface_resource.0;
+  let surface = surface_resource.id;
 
   let adapter_resource =
     state.resource_table.get::<WebGpuAdapter>(adapter_rid)?;
@@ -142,6 +425,33 @@ pub(crate) fn op_webgpu_surface_get_capabilities(
   }
 }
 
+#[op]
+pub(crate) fn op_webgpu_surface_get_preferred_configuration(
+  state: &mut OpState,
+  surface_rid: ResourceId,
+  adapter_rid: ResourceId,
+) -> Result<GpuSurfacePreferredConfiguration, AnyError> {
+  let instance = state.borrow::<super::Instance>();
+
+  let surface_resource =
+    state.resource_table.get::<WebGpuSurface>(surface_rid)?;
+  let surface = surface_resource.id;
+
+  let adapter_resource =
+    state.resource_table.get::<WebGpuAdapter>(adapter_rid)?;
+  let adapter = adapter_resource.0;
+
+  match gfx_select!(adapter =>
+   instance.surface_get_capabilities(surface, adapter)
+  ) {
+    Ok(caps) if caps.formats.is_empty() => Err(AnyError::msg(
+      "The surface is not compatible with the adapter.",
+    )),
+    Ok(caps) => Ok(caps.into()),
+    Err(err) => Err(err.into()),
+  }
+}
+
 #[op]
 pub(crate) fn op_webgpu_surface_configure(
   state: &mut OpState,
@@ -153,7 +463,7 @@ pub(crate) fn op_webgpu_surface_configure(
 
   let surface_resource =
     state.resource_table.get::<WebGpuSurface>(surface_rid)?;
-  let surface = surface_resource.0;
+  let surface = surface_resource.id;
 
   let device_resource = state.resource_table.get::<WebGpuDevice>(device_rid)?;
   let device = device_resource.0;
@@ -168,38 +478,132 @@ pub(crate) fn op_webgpu_surface_configure(
     view_formats: config.view_formats,
   };
 
+  if surface_resource.frame_capture.borrow().is_some() {
+    check_frame_capture_usage(config.usage)?;
+  }
+
   match gfx_select!(device =>
     instance.surface_configure(surface, device, &config)
   ) {
-    None => Ok(()),
+    None => {
+      surface_resource.config.replace(Some(config));
+      Ok(())
+    }
     Some(err) => Err(err.into()),
   }
 }
 
+// Device loss is reported to JS through GPUDevice.lost instead of being thrown,
+// so that the surface can be configured with a new device.
+fn is_device_lost(err: &wgpu_core::present::SurfaceError) -> bool {
+  matches!(
+    err,
+    wgpu_core::present::SurfaceError::Device(
+      wgpu_core::device::DeviceError::Lost
+    )
+  )
+}
+
+fn is_configure_device_lost(
+  err: &wgpu_core::present::ConfigureSurfaceError,
+) -> bool {
+  matches!(
+    err,
+    wgpu_core::present::ConfigureSurfaceError::Device(
+      wgpu_core::device::DeviceError::Lost
+    )
+  )
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GpuSurfaceCurrentTexture {
+  texture_rid: ResourceId,
+  suboptimal: bool,
+  // Whether the surface was reconfigured because it was outdated or lost.
+  reconfigured: bool,
+  width: u32,
+  height: u32,
+}
+
+// Returns `None` if the device was lost.
 #[op]
 pub(crate) fn op_webgpu_surface_get_current_texture(
   state: &mut OpState,
   surface_rid: ResourceId,
   device_rid: ResourceId,
-) -> Result<(ResourceId, bool), AnyError> {
+) -> Result<Option<GpuSurfaceCurrentTexture>, AnyError> {
   let instance = state.borrow::<super::Instance>();
 
   let surface_resource =
     state.resource_table.get::<WebGpuSurface>(surface_rid)?;
-  let surface = surface_resource.0;
+  let surface = surface_resource.id;
 
   let device_resource = state.resource_table.get::<WebGpuDevice>(device_rid)?;
   let device = device_resource.0;
 
-  match gfx_select!(device =>
+  let (width, height) = {
+    let mut config = surface_resource.config.borrow_mut();
+    let config = config.as_mut().unwrap();
+    if let Some((width, height)) = surface_resource.pending_size.take() {
+      if (width, height) != (config.width, config.height) {
+        config.width = width;
+        config.height = height;
+        if let Some(err) = gfx_select!(device =>
+          instance.surface_configure(surface, device, config)
+        ) {
+          return if is_configure_device_lost(&err) {
+            Ok(None)
+          } else {
+            Err(err.into())
+          };
+        }
+      }
+    }
+    (config.width, config.height)
+  };
+
+  let mut reconfigured = false;
+  let mut result = gfx_select!(device =>
     instance.surface_get_current_texture(surface, ())
-  ) {
+  );
+  if let Ok(wgpu_core::present::SurfaceOutput {
+    status:
+      wgpu_types::SurfaceStatus::Outdated | wgpu_types::SurfaceStatus::Lost,
+    ..
+  }) = result
+  {
+    if let Some(config) = surface_resource.recoverable_config() {
+      if let Some(err) = gfx_select!(device =>
+        instance.surface_configure(surface, device, &config)
+      ) {
+        return if is_configure_device_lost(&err) {
+          Ok(None)
+        } else {
+          Err(err.into())
+        };
+      }
+      reconfigured = true;
+      result = gfx_select!(device =>
+        instance.surface_get_current_texture(surface, ())
+      );
+    }
+  }
+
+  match result {
     Ok(output) => {
       let suboptimal = check_suboptimal(output.status)?;
       let texture_resource = WebGpuTexture(output.texture_id.unwrap());
       let texture_rid = state.resource_table.add(texture_resource);
-      Ok((texture_rid, suboptimal))
+      Ok(Some(GpuSurfaceCurrentTexture {
+        texture_rid,
+        suboptimal,
+        reconfigured,
+        width,
+        height,
+      }))
     }
+    Err(err) if is_device_lost(&err) => Ok(None),
     Err(err) => Err(err.into()),
   }
 }
@@ -214,7 +618,7 @@ pub(crate) fn op_webgpu_surface_texture_discard(
 
   let surface_resource =
     state.resource_table.get::<WebGpuSurface>(surface_rid)?;
-  let surface = surface_resource.0;
+  let surface = surface_resource.id;
 
   let device_resource = state.resource_table.get::<WebGpuDevice>(device_rid)?;
   let device = device_resource.0;
@@ -227,28 +631,226 @@ pub(crate) fn op_webgpu_surface_texture_discard(
   }
 }
 
+#[derive(Deserialize)]
+pub struct GpuSurfaceDamageRect {
+  x: u32,
+  y: u32,
+  width: u32,
+  height: u32,
+}
+
+impl GpuSurfaceDamageRect {
+  fn is_visible(&self, width: u32, height: u32) -> bool {
+    self.width > 0 && self.height > 0 && self.x < width && self.y < height
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuSurfacePresentOutput {
+  // `None` when nothing was presented.
+  statistics: Option<GpuSurfacePresentStatistics>,
+  device_lost: bool,
+}
+
+// Presents the texture, unless `damage` lists no visible change, in which case
+// the texture is discarded and the window keeps showing the previous frame.
+// wgpu 0.15 can't forward damage regions to the backend yet, so any other
+// damage presents the whole texture.
 #[op]
 pub(crate) fn op_webgpu_surface_texture_present(
   state: &mut OpState,
   surface_rid: ResourceId,
   device_rid: ResourceId,
-) -> Result<(), AnyError> {
+  texture_rid: ResourceId,
+  damage: Option<Vec<GpuSurfaceDamageRect>>,
+) -> Result<GpuSurfacePresentOutput, AnyError> {
   let instance = state.borrow::<super::Instance>();
 
   let surface_resource =
     state.resource_table.get::<WebGpuSurface>(surface_rid)?;
-  let surface = surface_resource.0;
+  let surface = surface_resource.id;
 
   let device_resource = state.resource_table.get::<WebGpuDevice>(device_rid)?;
   let device = device_resource.0;
 
+  let texture_resource =
+    state.resource_table.get::<WebGpuTexture>(texture_rid)?;
+  let texture = texture_resource.0;
+
+  if let Some(damage) = damage {
+    let undamaged = {
+      let config = surface_resource.config.borrow();
+      let config = config.as_ref().unwrap();
+      !damage
+        .iter()
+        .any(|rect| rect.is_visible(config.width, config.height))
+    };
+    if undamaged {
+      return match gfx_select!(device =>
+        instance.surface_texture_discard(surface)
+      ) {
+        Ok(()) => Ok(GpuSurfacePresentOutput {
+          statistics: None,
+          device_lost: false,
+        }),
+        Err(err) if is_device_lost(&err) => Ok(GpuSurfacePresentOutput {
+          statistics: None,
+          device_lost: true,
+        }),
+        Err(err) => Err(err.into()),
+      };
+    }
+  }
+
+  if let Some(frame_capture) = &mut *surface_resource.frame_capture.borrow_mut()
+  {
+    let config = surface_resource.config.borrow();
+    let config = config.as_ref().unwrap();
+    frame_capture.capture(instance, device, texture, config)?;
+  }
+
+  // Frame captures don't include the overlay, and it never fails a present.
+  if let Some(overlay) = &mut *surface_resource.diagnostics_overlay.borrow_mut()
+  {
+    let config = surface_resource.config.borrow();
+    let config = config.as_ref().unwrap();
+    let frame_time = surface_resource
+      .last_present
+      .get()
+      .map(|last| last.elapsed().as_secs_f64() * 1000.0);
+    let _ = overlay.draw(instance, device, texture, config, frame_time);
+  }
+
+  let started = Instant::now();
   match gfx_select!(device =>
     instance.surface_present(surface)
   ) {
     Ok(status) => {
+      let statistics =
+        surface_resource.present_statistics(started, Instant::now());
+      if let Some(listener) = &*surface_resource.present_listener.borrow() {
+        listener();
+      }
       check_suboptimal(status)?;
-      Ok(())
+      Ok(GpuSurfacePresentOutput {
+        statistics: Some(statistics),
+        device_lost: false,
+      })
     }
+    Err(err) if is_device_lost(&err) => Ok(GpuSurfacePresentOutput {
+      statistics: None,
+      device_lost: true,
+    }),
     Err(err) => Err(err.into()),
   }
 }
+
+struct FrameCapture {
+  directory: PathBuf,
+  interval: u32,
+  frame: u64,
+  next_index: u64,
+  started: Instant,
+}
+
+impl FrameCapture {
+  fn capture(
+    &mut self,
+    instance: &super::Instance,
+    device: wgpu_core::id::DeviceId,
+    texture: wgpu_core::id::TextureId,
+    config: &wgpu_types::SurfaceConfiguration<Vec<wgpu_types::TextureFormat>>,
+  ) -> Result<(), AnyError> {
+    let frame = self.frame;
+    self.frame += 1;
+    if frame % self.interval as u64 != 0 {
+      return Ok(());
+    }
+
+    check_frame_capture_usage(config.usage)?;
+    let size = (config.width, config.height);
+    let pixels = readback::read_texture_rgba8(
+      instance,
+      device,
+      texture,
+      config.format,
+      size,
+    )?;
+
+    let path = self
+      .directory
+      .join(format!("frame-{:06}.png", self.next_index));
+    self.next_index += 1;
+    let text = [
+      ("Frame", frame.to_string()),
+      (
+        "Time",
+        format!("{:.6}", self.started.elapsed().as_secs_f64()),
+      ),
+      ("Size", format!("{}x{}", config.width, config.height)),
+      ("Format", format!("{:?}", config.format)),
+    ];
+    let writer = BufWriter::new(File::create(path)?);
+    readback::encode_png(writer, size, &pixels, &text)
+  }
+}
+
+fn check_frame_capture_usage(
+  usage: wgpu_types::TextureUsages,
+) -> Result<(), AnyError> {
+  if usage.contains(wgpu_types::TextureUsages::COPY_SRC) {
+    Ok(())
+  } else {
+    Err(AnyError::msg(
+      "Frame capture requires the surface to be configured with COPY_SRC usage.",
+    ))
+  }
+}
+
+#[op]
+pub(crate) fn op_webgpu_surface_start_frame_capture<P>(
+  state: &mut OpState,
+  surface_rid: ResourceId,
+  directory: String,
+  interval: u32,
+) -> Result<(), AnyError>
+where
+  P: WebGpuPermissions + 'static,
+{
+  let directory = PathBuf::from(directory);
+  state
+    .borrow_mut::<P>()
+    .check_write(&directory, "GPUSurface.startFrameCapture")?;
+
+  if interval == 0 {
+    return Err(AnyError::msg("Frame capture interval must be at least 1."));
+  }
+
+  let surface_resource =
+    state.resource_table.get::<WebGpuSurface>(surface_rid)?;
+  if let Some(config) = &*surface_resource.config.borrow() {
+    check_frame_capture_usage(config.usage)?;
+  }
+
+  std::fs::create_dir_all(&directory)?;
+  surface_resource.frame_capture.replace(Some(FrameCapture {
+    directory,
+    interval,
+    frame: 0,
+    next_index: 0,
+    started: Instant::now(),
+  }));
+  Ok(())
+}
+
+#[op]
+pub(crate) fn op_webgpu_surface_stop_frame_capture(
+  state: &mut OpState,
+  surface_rid: ResourceId,
+) -> Result<(), AnyError> {
+  let surface_resource =
+    state.resource_table.get::<WebGpuSurface>(surface_rid)?;
+  surface_resource.frame_capture.replace(None);
+  Ok(())
+}
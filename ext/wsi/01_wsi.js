@@ -2,13 +2,44 @@
 
 const core = globalThis.Deno.core;
 const ops = core.ops;
+import { setTimeout } from "ext:deno_web/02_timers.js";
+import * as abortSignal from "ext:deno_web/03_abort_signal.js";
 import * as webgpu from "ext:deno_webgpu/01_webgpu.js";
 import * as webidl from "ext:deno_webidl/00_webidl.js";
 
 const _wid = Symbol("wid");
 const _gpuSurface = Symbol("gpuSurface");
+const _isOccluded = Symbol("isOccluded");
+const _parent = Symbol("parent");
+const _children = Symbol("children");
+const _cursorPosition = Symbol("cursorPosition");
+const _coordinateSpace = Symbol("coordinateSpace");
+const _watch = Symbol("watch");
+const _device = Symbol("device");
+const _label = Symbol("label");
+const _module = Symbol("module");
+const _messages = Symbol("messages");
 
 const windows = new Map();
+const shaderWatches = new Map();
+const pendingEvents = [];
+const beforeQuitCallbacks = [];
+let options;
+let sessionChecked = false;
+// The window the cursor is over, as of the last cursor event.
+let cursorWindow = null;
+// The modifiers of the last "modifiers-changed" event, for platforms where
+// they can't be queried.
+let lastModifiers = 0;
+
+function getOptions() {
+  return options ??= ops.op_wsi_get_options();
+}
+
+// With --wsi-headless, window ops are served by virtual windows instead.
+function wsiOps() {
+  return getOptions().headless ? headlessOps : ops;
+}
 
 function convertPosition(prefix, args) {
   if (args.length >= 2) {
@@ -32,6 +63,29 @@ function convertPosition(prefix, args) {
   }
 }
 
+function convertPoint(prefix, position) {
+  position = webidl.converters["sequence<double>"](position, {
+    prefix,
+    context: "Argument 1",
+  });
+  checkPosition(prefix, position);
+  return position;
+}
+
+// Image data as bytes, or `null`.
+function convertImage(prefix, image) {
+  if (image === null) {
+    return null;
+  }
+  image = webidl.converters["BufferSource"](image, {
+    prefix,
+    context: "Argument 1",
+  });
+  return ArrayBuffer.isView(image)
+    ? new Uint8Array(image.buffer, image.byteOffset, image.byteLength)
+    : new Uint8Array(image);
+}
+
 function checkPosition(prefix, position) {
   if (position.length != 2) {
     throw new DOMException(
@@ -76,6 +130,61 @@ function checkSize(prefix, size) {
   }
 }
 
+// A rectangle as `[x, y, width, height]`, or `null`.
+function convertRect(prefix, rect) {
+  if (rect === null) {
+    return null;
+  }
+  rect = webidl.converters["WSIRect"](rect, {
+    prefix,
+    context: "Argument 1",
+  });
+  if (rect.length != 4) {
+    throw new DOMException(
+      `${prefix}: rect.length must equal 4.`,
+      "OperationError",
+    );
+  }
+  if (rect[2] < 0 || rect[3] < 0) {
+    throw new DOMException(
+      `${prefix}: rect must not have a negative width or height.`,
+      "OperationError",
+    );
+  }
+  return rect;
+}
+
+// Colors are `[r, g, b]`.
+function checkTitleBarStyle(prefix, style) {
+  for (const key of ["captionColor", "borderColor", "textColor"]) {
+    if (style[key] !== undefined && style[key].length != 3) {
+      throw new DOMException(
+        `${prefix}: ${key}.length must equal 3.`,
+        "OperationError",
+      );
+    }
+  }
+}
+
+// Aborting the signal rejects with its reason, and timing out resolves with
+// null. The event that the call was waiting for then goes to the next call.
+async function nextNativeEvent(signal, timeoutMs = null) {
+  if (signal === undefined) {
+    return await core.opAsync("op_wsi_next_event", null, timeoutMs);
+  }
+  const cancelRid = ops.op_cancel_handle();
+  const abortHandler = () => core.tryClose(cancelRid);
+  signal[abortSignal.add](abortHandler);
+  try {
+    return await core.opAsync("op_wsi_next_event", cancelRid, timeoutMs);
+  } catch (error) {
+    signal.throwIfAborted();
+    throw error;
+  } finally {
+    signal[abortSignal.remove](abortHandler);
+  }
+}
+
 class WSI {
   [webidl.brand] = webidl.brand;
 
@@ -83,13 +192,70 @@ class WSI {
     webidl.illegalConstructor();
   }
 
-  async nextEvent() {
+  async nextEvent(options = {}) {
     webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'nextEvent' on 'WSI'";
+
+    options = webidl.converters["WSINextEventOptions"](options, {
+      prefix,
+      context: "Argument 1",
+    });
+    options.signal?.throwIfAborted();
+
+    // The layout saved by a previous run is offered before any other event.
+    if (!sessionChecked) {
+      sessionChecked = true;
+      const session = ops.op_wsi_get_saved_session();
+      if (session != null) {
+        pendingEvents.push({
+          type: "session-restore-available",
+          windows: session.windows,
+        });
+      }
+    }
+
+    if (pendingEvents.length > 0) {
+      return takePendingEvent();
+    }
+    if (getOptions().headless) {
+      const reload = ops.op_wsi_take_shader_reload();
+      if (reload != null) {
+        return shaderReloaded(reload) ?? await this.nextEvent(options);
+      }
+      return await nextHeadlessEvent(options.signal, options.timeoutMs);
+    }
 
-    const event = await core.opAsync("op_wsi_next_event");
+    const event = await nextNativeEvent(options.signal, options.timeoutMs);
+    if (event === null) {
+      return null;
+    }
+    // Quit requests are answered by the `onBeforeQuit` callbacks instead.
+    if (event.type === "app-quit-requested") {
+      await replyToQuitRequest();
+      return await this.nextEvent(options);
+    }
+    if (event.type === "shader-reloaded") {
+      return shaderReloaded(event) ?? await this.nextEvent(options);
+    }
     if (event.window != null) {
       event.window = windows.get(event.window);
     }
+    if (event.type === "window-occlusion" && event.window != null) {
+      event.window[_isOccluded] = event.isOccluded;
+    }
+    if (event.type === "modifiers-changed") {
+      lastModifiers = event.modifiers;
+    }
+    if (event.type === "cursor-moved" && event.window != null) {
+      event.window[_cursorPosition] = event.position;
+      cursorWindow = event.window;
+    }
+    if (event.type === "cursor-left" && event.window != null) {
+      event.window[_cursorPosition] = null;
+      if (cursorWindow === event.window) {
+        cursorWindow = null;
+      }
+    }
     return event;
   }
 
@@ -103,13 +269,226 @@ class WSI {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_set_device_event_filter(filter);
+    return wsiOps().op_wsi_set_device_event_filter(filter);
+  }
+
+  setDeviceMotionInterval(milliseconds) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'setDeviceMotionInterval' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    milliseconds = webidl.converters["double"](milliseconds, {
+      prefix,
+      context: "Argument 1",
+    });
+    if (milliseconds < 0) {
+      throw new DOMException(
+        `${prefix}: milliseconds must not be negative.`,
+        "OperationError",
+      );
+    }
+
+    return wsiOps().op_wsi_set_device_motion_interval(milliseconds);
+  }
+
+  getDeviceInfo(deviceId) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'getDeviceInfo' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    deviceId = webidl.converters["unsigned long"](deviceId, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_get_device_info(deviceId);
+  }
+
+  setGlobalInputEnabled(enabled = true) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'setGlobalInputEnabled' on 'WSI'";
+
+    enabled = webidl.converters["boolean"](enabled, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_set_global_input_enabled(enabled);
+  }
+
+  setClickOptions(options = {}) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'setClickOptions' on 'WSI'";
+
+    options = webidl.converters["WSIClickOptions"](options, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_set_click_options(options);
+  }
+
+  capabilities() {
+    webidl.assertBranded(this, WSIPrototype);
+    return wsiOps().op_wsi_get_capabilities();
+  }
+
+  getMonitors() {
+    webidl.assertBranded(this, WSIPrototype);
+    return wsiOps().op_wsi_get_monitors();
+  }
+
+  getSystemTheme() {
+    webidl.assertBranded(this, WSIPrototype);
+    return wsiOps().op_wsi_get_system_theme();
+  }
+
+  getCursorPosition() {
+    webidl.assertBranded(this, WSIPrototype);
+    const position = cursorWindow?.getCursorPosition();
+    if (position == null) {
+      return null;
+    }
+    return cursorWindow.clientToDesktop(position);
+  }
+
+  getKeyLabel(scanCode) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'getKeyLabel' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    scanCode = webidl.converters["unsigned long"](scanCode, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_get_key_label(scanCode);
+  }
+
+  getKeyScanCode(label) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'getKeyScanCode' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    label = webidl.converters["DOMString"](label, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_get_key_scan_code(label);
+  }
+
+  getModifiers() {
+    webidl.assertBranded(this, WSIPrototype);
+    return wsiOps().op_wsi_get_modifiers() ?? lastModifiers;
+  }
+
+  setAppMenu(items) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'setAppMenu' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    items = webidl.converters["sequence<WSIAppMenuItem?>"](items, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_set_app_menu(items);
+  }
+
+  setDockBadge(label) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'setDockBadge' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    if (label !== null) {
+      label = webidl.converters["DOMString"](label, {
+        prefix,
+        context: "Argument 1",
+      });
+    }
+
+    return wsiOps().op_wsi_set_dock_badge(label);
+  }
+
+  setDockIcon(image) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'setDockIcon' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    image = convertImage(prefix, image);
+
+    return wsiOps().op_wsi_set_dock_icon(image);
+  }
+
+  requestDockAttention(type = "informational") {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'requestDockAttention' on 'WSI'";
+
+    type = webidl.converters["WSIUserAttentionType"](type, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_request_dock_attention(type);
+  }
+
+  registerUrlScheme(scheme) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'registerUrlScheme' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    scheme = webidl.converters["DOMString"](scheme, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    // A process started to open a URL gets it as the first "open-url" event.
+    const url = wsiOps().op_wsi_register_url_scheme(scheme);
+    if (url != null) {
+      pendingEvents.push({ type: "open-url", url });
+    }
+  }
+
+  onBeforeQuit(callback) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'onBeforeQuit' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    callback = webidl.converters["Function"](callback, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    beforeQuitCallbacks.push(callback);
+  }
+
+  setCrashMetadata(metadata) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'setCrashMetadata' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    metadata = webidl.converters["record<USVString, USVString>"](metadata, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_set_crash_metadata(metadata);
   }
 
   createWindow(options) {
     webidl.assertBranded(this, WSIPrototype);
     const prefix = "Failed to execute 'createWindow' on 'WSI'";
 
+    // Defaults from the "wsi.window" section of the config file, except that
+    // --wsi-app-id takes precedence over its "appId".
+    const { windowDefaults, appId } = getOptions();
+    if (appId != null) {
+      options = { ...windowDefaults, appId, ...options };
+    } else if (windowDefaults != null) {
+      options = { ...windowDefaults, ...options };
+    }
+
     if (options !== undefined) {
       options = webidl.converters["WSICreateWindowOptions"](options, {
         prefix,
@@ -130,17 +509,221 @@ class WSI {
       if (options.resizeIncrements != null) {
         checkSize(prefix, options.resizeIncrements);
       }
+      if (options.titleBarStyle !== undefined) {
+        checkTitleBarStyle(prefix, options.titleBarStyle);
+      }
     }
 
-    const wid = ops.op_wsi_create_window(options);
+    const parent = options?.parent;
+    if (parent != null) {
+      options.parent = assertWindow(parent, { prefix, context: "parent" });
+    }
+
+    const wid = wsiOps().op_wsi_create_window(options);
     const window = webidl.createBranded(WSIWindow);
     windows.set(wid, window);
     window[_wid] = wid;
+    window[_coordinateSpace] = options?.coordinateSpace ?? "physical";
+    window[_children] = new Set();
+    if (parent != null) {
+      window[_parent] = parent;
+      parent[_children].add(window);
+    }
     return window;
   }
+
+  windows() {
+    webidl.assertBranded(this, WSIPrototype);
+    const infos = new Map();
+    for (const info of wsiOps().op_wsi_windows()) {
+      infos.set(info.window, info);
+    }
+    // In the order the windows were created.
+    const result = [];
+    for (const [wid, window] of windows) {
+      const info = infos.get(wid);
+      if (info != null) {
+        result.push({ ...info, window });
+      }
+    }
+    return result;
+  }
+
+  clearSavedSession() {
+    webidl.assertBranded(this, WSIPrototype);
+    ops.op_wsi_clear_saved_session();
+  }
+
+  metrics() {
+    webidl.assertBranded(this, WSIPrototype);
+    return ops.op_wsi_metrics();
+  }
+
+  setTraceEnabled(enabled = true) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'setTraceEnabled' on 'WSI'";
+
+    enabled = webidl.converters["boolean"](enabled, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_set_trace_enabled(enabled);
+  }
+
+  async requestFrame(window) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'requestFrame' on 'WSI'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    const wid = assertWindow(window, { prefix, context: "Argument 1" });
+
+    // Assume 60 Hz if the window's monitor doesn't report its refresh rate.
+    const refreshRate = wsiOps().op_wsi_window_get_refresh_rate(wid) ?? 60000;
+    await core.opAsync("op_wsi_wait_for_frame", refreshRate);
+  }
+
+  get assets() {
+    webidl.assertBranded(this, WSIPrototype);
+    return assets;
+  }
+
+  watchShader(device, path, options = {}) {
+    webidl.assertBranded(this, WSIPrototype);
+    const prefix = "Failed to execute 'watchShader' on 'WSI'";
+
+    webidl.requiredArguments(arguments.length, 2, { prefix });
+    const inner = webgpu.assertDevice(device, {
+      prefix,
+      context: "Argument 1",
+    });
+    path = webidl.converters["DOMString"](path, {
+      prefix,
+      context: "Argument 2",
+    });
+    const label = options.label == null
+      ? null
+      : webidl.converters["USVString"](options.label, {
+        prefix,
+        context: "Argument 3",
+      });
+
+    const compilation = ops.op_wsi_watch_shader(inner.rid, path, label);
+    const watch = webidl.createBranded(WSIShaderWatch);
+    watch[_watch] = compilation.watch;
+    watch[_device] = inner;
+    watch[_label] = label;
+    watch[_module] = null;
+    shaderWatches.set(compilation.watch, watch);
+    shaderCompiled(watch, compilation);
+    return watch;
+  }
 }
 const WSIPrototype = WSI.prototype;
 
+class WSIShaderWatch {
+  [_watch];
+  [_device];
+  [_label];
+  [_module];
+  [_messages];
+
+  constructor() {
+    webidl.illegalConstructor();
+  }
+
+  // The module of the last compilation without errors.
+  get module() {
+    webidl.assertBranded(this, WSIShaderWatchPrototype);
+    return this[_module];
+  }
+
+  // The messages of the last compilation.
+  get messages() {
+    webidl.assertBranded(this, WSIShaderWatchPrototype);
+    return this[_messages];
+  }
+
+  close() {
+    webidl.assertBranded(this, WSIShaderWatchPrototype);
+    if (shaderWatches.delete(this[_watch])) {
+      ops.op_wsi_unwatch_shader(this[_watch]);
+    }
+  }
+}
+const WSIShaderWatchPrototype = WSIShaderWatch.prototype;
+
+// Returns the new module, if the code compiled without errors.
+function shaderCompiled(watch, { module: rid, code, messages }) {
+  watch[_messages] = messages;
+  if (rid == null) {
+    return null;
+  }
+  const module = webgpu.createGPUShaderModule(
+    watch[_label],
+    watch[_device],
+    rid,
+    code,
+  );
+  watch[_device].trackResource(module);
+  watch[_module] = module;
+  return module;
+}
+
+// Turns a "shader-reloaded" event from the ops into the one JS sees, or
+// returns null if the watch was closed in the meantime.
+function shaderReloaded(event) {
+  const watch = shaderWatches.get(event.watch);
+  if (watch == null) {
+    if (event.module != null) {
+      core.close(event.module);
+    }
+    return null;
+  }
+  const module = shaderCompiled(watch, event);
+  return {
+    type: "shader-reloaded",
+    watch,
+    module,
+    messages: event.messages,
+  };
+}
+
+class WSIAssets {
+  [webidl.brand] = webidl.brand;
+
+  constructor() {
+    webidl.illegalConstructor();
+  }
+
+  list() {
+    webidl.assertBranded(this, WSIAssetsPrototype);
+    return ops.op_wsi_assets_list();
+  }
+
+  async readFile(path) {
+    webidl.assertBranded(this, WSIAssetsPrototype);
+    const prefix = "Failed to execute 'readFile' on 'WSIAssets'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    path = webidl.converters["DOMString"](path, {
+      prefix,
+      context: "Argument 1",
+    });
+    return await core.opAsync("op_wsi_assets_read", path);
+  }
+
+  async readTextFile(path) {
+    webidl.assertBranded(this, WSIAssetsPrototype);
+    const prefix = "Failed to execute 'readTextFile' on 'WSIAssets'";
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    path = webidl.converters["DOMString"](path, {
+      prefix,
+      context: "Argument 1",
+    });
+    return core.decode(await core.opAsync("op_wsi_assets_read", path));
+  }
+}
+const WSIAssetsPrototype = WSIAssets.prototype;
+
 class WSIModifierKey {
   constructor() {
     webidl.illegalConstructor();
@@ -174,11 +757,69 @@ function assertWindow(window, { prefix, context }) {
 class WSIWindow {
   [_wid];
   [_gpuSurface];
+  [_isOccluded] = false;
+  [_parent];
+  [_children];
+  [_cursorPosition] = null;
+  [_coordinateSpace];
 
   constructor() {
     webidl.illegalConstructor();
   }
 
+  getCoordinateSpace() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getCoordinateSpace' on 'WSIWindow'";
+    assertWindow(this, { prefix, context: "this" });
+
+    return this[_coordinateSpace];
+  }
+
+  setCoordinateSpace(space) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setCoordinateSpace' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    space = webidl.converters["WSICoordinateSpace"](space, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    wsiOps().op_wsi_window_set_coordinate_space(wid, space);
+    this[_coordinateSpace] = space;
+  }
+
+  getSupportedBackdrops() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getSupportedBackdrops' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_get_supported_backdrops(wid);
+  }
+
+  setBackdrop(backdrop) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setBackdrop' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    backdrop = webidl.converters["WSIWindowBackdrop"](backdrop, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_set_backdrop(wid, backdrop);
+  }
+
+  isContentProtected() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'isContentProtected' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_is_content_protected(wid);
+  }
+
   setContentProtected(contentProtected = true) {
     webidl.assertBranded(this, WSIWindowPrototype);
     const prefix = "Failed to execute 'setContentProtected' on 'WSIWindow'";
@@ -189,7 +830,48 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_content_protected(wid, contentProtected);
+    return wsiOps().op_wsi_window_set_content_protected(wid, contentProtected);
+  }
+
+  setCornerPreference(corners) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setCornerPreference' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    corners = webidl.converters["WSIWindowCorners"](corners, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_set_corner_preference(wid, corners);
+  }
+
+  setTitleBarStyle(style) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setTitleBarStyle' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    style = webidl.converters["WSITitleBarStyle"](style, {
+      prefix,
+      context: "Argument 1",
+    });
+    checkTitleBarStyle(prefix, style);
+
+    return wsiOps().op_wsi_window_set_title_bar_style(wid, style);
+  }
+
+  setCursorConfinementRect(rect) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix =
+      "Failed to execute 'setCursorConfinementRect' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    rect = convertRect(prefix, rect);
+
+    return wsiOps().op_wsi_window_set_cursor_confinement_rect(wid, rect);
   }
 
   setCursorGrabMode(mode) {
@@ -203,7 +885,7 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_cursor_grab_mode(wid, mode);
+    return wsiOps().op_wsi_window_set_cursor_grab_mode(wid, mode);
   }
 
   setCursorHitTestEnabled(enabled = true) {
@@ -216,7 +898,24 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_cursor_hit_test_enabled(wid, enabled);
+    return wsiOps().op_wsi_window_set_cursor_hit_test_enabled(wid, enabled);
+  }
+
+  setCursorHitTestRegion(rects) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setCursorHitTestRegion' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    if (rects !== null) {
+      rects = webidl.converters["sequence<WSIRect>"](rects, {
+        prefix,
+        context: "Argument 1",
+      });
+      rects = rects.map((rect) => convertRect(prefix, rect));
+    }
+
+    return wsiOps().op_wsi_window_set_cursor_hit_test_region(wid, rects);
   }
 
   setCursorIcon(icon) {
@@ -230,7 +929,7 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_cursor_icon(wid, icon);
+    return wsiOps().op_wsi_window_set_cursor_icon(wid, icon);
   }
 
   setCursorPosition() {
@@ -240,7 +939,48 @@ class WSIWindow {
 
     const position = convertPosition(prefix, arguments);
 
-    return ops.op_wsi_window_set_cursor_position(wid, position);
+    return wsiOps().op_wsi_window_set_cursor_position(wid, position);
+  }
+
+  getCursorPosition() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getCursorPosition' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    // Cursor events always report physical pixels.
+    const position = this[_cursorPosition];
+    if (position == null) {
+      return null;
+    }
+    if (this[_coordinateSpace] === "logical") {
+      const scaleFactor = wsiOps().op_wsi_window_get_scale_factor(wid);
+      return [position[0] / scaleFactor, position[1] / scaleFactor];
+    }
+    return [position[0], position[1]];
+  }
+
+  clientToDesktop(position) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'clientToDesktop' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    const [x, y] = convertPoint(prefix, position);
+
+    const [left, top] = wsiOps().op_wsi_window_get_inner_position(wid);
+    return [left + x, top + y];
+  }
+
+  desktopToClient(position) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'desktopToClient' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    const [x, y] = convertPoint(prefix, position);
+
+    const [left, top] = wsiOps().op_wsi_window_get_inner_position(wid);
+    return [x - left, y - top];
   }
 
   setCursorVisible(visible = true) {
@@ -253,7 +993,7 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_cursor_visible(wid, visible);
+    return wsiOps().op_wsi_window_set_cursor_visible(wid, visible);
   }
 
   isDecorated() {
@@ -261,7 +1001,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'isDecorated' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_is_decorated(wid);
+    return wsiOps().op_wsi_window_is_decorated(wid);
   }
 
   setDecorated(decorated = true) {
@@ -274,7 +1014,41 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_decorated(wid, decorated);
+    return wsiOps().op_wsi_window_set_decorated(wid, decorated);
+  }
+
+  setDiagnosticsOverlay(enabled = true) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setDiagnosticsOverlay' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    enabled = webidl.converters["boolean"](enabled, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_set_diagnostics_overlay(wid, enabled);
+  }
+
+  isEnabled() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'isEnabled' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_is_enabled(wid);
+  }
+
+  setEnabled(enabled = true) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setEnabled' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    enabled = webidl.converters["boolean"](enabled, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_set_enabled(wid, enabled);
   }
 
   getEnabledButtons() {
@@ -282,7 +1056,20 @@ class WSIWindow {
     const prefix = "Failed to execute 'getEnabledButtons' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_get_enabled_buttons(wid);
+    return wsiOps().op_wsi_window_get_enabled_buttons(wid);
+  }
+
+  getEnabledButtonsDict() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getEnabledButtonsDict' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    const buttons = wsiOps().op_wsi_window_get_enabled_buttons(wid);
+    return {
+      close: (buttons & WSIWindowButton.CLOSE) !== 0,
+      minimize: (buttons & WSIWindowButton.MINIMIZE) !== 0,
+      maximize: (buttons & WSIWindowButton.MAXIMIZE) !== 0,
+    };
   }
 
   setEnabledButtons(buttons) {
@@ -291,12 +1078,12 @@ class WSIWindow {
     const wid = assertWindow(this, { prefix, context: "this" });
 
     webidl.requiredArguments(arguments.length, 1, { prefix });
-    buttons = webidl.converters["unsigned long"](buttons, {
+    buttons = webidl.converters["WSIWindowButtons"](buttons, {
       prefix,
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_enabled_buttons(wid, buttons);
+    return wsiOps().op_wsi_window_set_enabled_buttons(wid, buttons);
   }
 
   hasFocus() {
@@ -304,7 +1091,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'hasFocus' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_has_focus(wid);
+    return wsiOps().op_wsi_window_has_focus(wid);
   }
 
   takeFocus() {
@@ -312,7 +1099,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'takeFocus' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_take_focus(wid);
+    return wsiOps().op_wsi_window_take_focus(wid);
   }
 
   isFullscreen() {
@@ -320,7 +1107,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'isFullscreen' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_is_fullscreen(wid);
+    return wsiOps().op_wsi_window_is_fullscreen(wid);
   }
 
   setFullscreen(fullscreen = true) {
@@ -333,57 +1120,124 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_fullscreen(wid, fullscreen);
+    return wsiOps().op_wsi_window_set_fullscreen(wid, fullscreen);
   }
 
-  getGPUSurface() {
+  getGPUSurface(options = {}) {
     webidl.assertBranded(this, WSIWindowPrototype);
     const prefix = "Failed to execute 'getGPUSurface' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
+    options = webidl.converters["WSIGPUSurfaceOptions"](options, {
+      prefix,
+      context: "Argument 1",
+    });
 
+    const onDeviceLost = () => {
+      pendingEvents.push({ type: "surface-device-lost", window: this });
+    };
     if (this[_gpuSurface] != null) {
       return this[_gpuSurface];
+    } else if (getOptions().headless) {
+      return this[_gpuSurface] = webgpu.createOffscreenGPUSurface(
+        options.autoResize ? () => headlessWindow(wid).innerSize : undefined,
+        onDeviceLost,
+      );
     } else {
-      const rid = ops.op_wsi_window_create_gpu_surface(wid);
-      return this[_gpuSurface] = webgpu.createGPUSurface(rid);
+      const rid = ops.op_wsi_window_create_gpu_surface(
+        wid,
+        options.autoReconfigure,
+        options.autoResize,
+      );
+      return this[_gpuSurface] = webgpu.createGPUSurface(rid, () => {
+        pendingEvents.push({ type: "surface-outdated", window: this });
+      }, onDeviceLost);
     }
   }
 
-  setIMEAllowed(allowed = true) {
+  setIMEAllowed(allowed = true) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setIMEAllowed' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    allowed = webidl.converters["boolean"](allowed, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_set_ime_allowed(wid, allowed);
+  }
+
+  setIMEPosition() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setIMEPosition' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    const position = convertPosition(prefix, arguments);
+
+    return wsiOps().op_wsi_window_set_ime_position(wid, position);
+  }
+
+  setIMEPurpose(purpose) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setIMEPurpose' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    purpose = webidl.converters["WSIIMEPurpose"](purpose, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_set_ime_purpose(wid, purpose);
+  }
+
+  resetDeadKeys() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'resetDeadKeys' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_reset_dead_keys(wid);
+  }
+
+  showSoftKeyboard() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'showSoftKeyboard' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_show_soft_keyboard(wid);
+  }
+
+  hideSoftKeyboard() {
     webidl.assertBranded(this, WSIWindowPrototype);
-    const prefix = "Failed to execute 'setIMEAllowed' on 'WSIWindow'";
+    const prefix = "Failed to execute 'hideSoftKeyboard' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    allowed = webidl.converters["boolean"](allowed, {
-      prefix,
-      context: "Argument 1",
-    });
-
-    return ops.op_wsi_window_set_ime_allowed(wid, allowed);
+    return wsiOps().op_wsi_window_hide_soft_keyboard(wid);
   }
 
-  setIMEPosition() {
+  getSoftKeyboardRect() {
     webidl.assertBranded(this, WSIWindowPrototype);
-    const prefix = "Failed to execute 'setIMEPosition' on 'WSIWindow'";
+    const prefix = "Failed to execute 'getSoftKeyboardRect' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    const position = convertPosition(prefix, arguments);
-
-    return ops.op_wsi_window_set_ime_position(wid, position);
+    return wsiOps().op_wsi_window_get_soft_keyboard_rect(wid);
   }
 
-  setIMEPurpose(purpose) {
+  setSystemShortcutsInhibited(inhibited = true) {
     webidl.assertBranded(this, WSIWindowPrototype);
-    const prefix = "Failed to execute 'setIMEPurpose' on 'WSIWindow'";
+    const prefix =
+      "Failed to execute 'setSystemShortcutsInhibited' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    webidl.requiredArguments(arguments.length, 1, { prefix });
-    purpose = webidl.converters["WSIIMEPurpose"](purpose, {
+    inhibited = webidl.converters["boolean"](inhibited, {
       prefix,
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_ime_purpose(wid, purpose);
+    return wsiOps().op_wsi_window_set_system_shortcuts_inhibited(
+      wid,
+      inhibited,
+    );
   }
 
   getInnerPosition() {
@@ -391,7 +1245,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'getInnerPosition' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_get_inner_position(wid);
+    return wsiOps().op_wsi_window_get_inner_position(wid);
   }
 
   getOuterPosition() {
@@ -399,7 +1253,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'getOuterPosition' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_get_outer_position(wid);
+    return wsiOps().op_wsi_window_get_outer_position(wid);
   }
 
   setOuterPosition() {
@@ -409,7 +1263,15 @@ class WSIWindow {
 
     const position = convertPosition(prefix, arguments);
 
-    return ops.op_wsi_window_set_outer_position(wid, position);
+    return wsiOps().op_wsi_window_set_outer_position(wid, position);
+  }
+
+  center() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'center' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_center(wid);
   }
 
   getInnerSize() {
@@ -417,7 +1279,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'getInnerSize' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_get_inner_size(wid);
+    return wsiOps().op_wsi_window_get_inner_size(wid);
   }
 
   getOuterSize() {
@@ -425,7 +1287,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'getOuterSize' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_get_outer_size(wid);
+    return wsiOps().op_wsi_window_get_outer_size(wid);
   }
 
   setInnerSize() {
@@ -435,7 +1297,7 @@ class WSIWindow {
 
     const size = convertSize(prefix, arguments);
 
-    return ops.op_wsi_window_set_inner_size(wid, size);
+    return wsiOps().op_wsi_window_set_inner_size(wid, size);
   }
 
   setMinInnerSize() {
@@ -446,7 +1308,7 @@ class WSIWindow {
     const nullable = true;
     const size = convertSize(prefix, arguments, nullable);
 
-    return ops.op_wsi_window_set_min_inner_size(wid, size);
+    return wsiOps().op_wsi_window_set_min_inner_size(wid, size);
   }
 
   setMaxInnerSize() {
@@ -457,7 +1319,7 @@ class WSIWindow {
     const nullable = true;
     const size = convertSize(prefix, arguments, nullable);
 
-    return ops.op_wsi_window_set_max_inner_size(wid, size);
+    return wsiOps().op_wsi_window_set_max_inner_size(wid, size);
   }
 
   setLevel(level) {
@@ -471,7 +1333,7 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_level(wid, level);
+    return wsiOps().op_wsi_window_set_level(wid, level);
   }
 
   isMinimized() {
@@ -479,7 +1341,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'isMinimized' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_is_minimized(wid);
+    return wsiOps().op_wsi_window_is_minimized(wid);
   }
 
   setMinimized(minimized = true) {
@@ -492,7 +1354,7 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_minimized(wid, minimized);
+    return wsiOps().op_wsi_window_set_minimized(wid, minimized);
   }
 
   isMaximized() {
@@ -500,7 +1362,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'isMaximized' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_is_maximized(wid);
+    return wsiOps().op_wsi_window_is_maximized(wid);
   }
 
   setMaximized(maximized = true) {
@@ -513,7 +1375,7 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_maximized(wid, maximized);
+    return wsiOps().op_wsi_window_set_maximized(wid, maximized);
   }
 
   isResizable() {
@@ -521,7 +1383,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'isResizable' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_is_resizable(wid);
+    return wsiOps().op_wsi_window_is_resizable(wid);
   }
 
   setResizable(resizable = true) {
@@ -534,7 +1396,40 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_resizable(wid, resizable);
+    return wsiOps().op_wsi_window_set_resizable(wid, resizable);
+  }
+
+  setShadow(shadow = true) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setShadow' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    shadow = webidl.converters["boolean"](shadow, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_set_shadow(wid, shadow);
+  }
+
+  setShape(shape) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setShape' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    if (shape !== null) {
+      shape = webidl.converters["WSIWindowShape"](shape, {
+        prefix,
+        context: "Argument 1",
+      });
+      shape = {
+        rects: shape.rects.map((rect) => convertRect(prefix, rect)),
+        cornerRadius: shape.cornerRadius,
+      };
+    }
+
+    return wsiOps().op_wsi_window_set_shape(wid, shape);
   }
 
   getResizeIncrements() {
@@ -542,7 +1437,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'getResizeIncrements' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_get_resize_increments(wid);
+    return wsiOps().op_wsi_window_get_resize_increments(wid);
   }
 
   setResizeIncrements() {
@@ -553,7 +1448,34 @@ class WSIWindow {
     const nullable = true;
     const size = convertSize(prefix, arguments, nullable);
 
-    return ops.op_wsi_window_set_resize_increments(wid, size);
+    return wsiOps().op_wsi_window_set_resize_increments(wid, size);
+  }
+
+  setAspectRatio() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setAspectRatio' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    const nullable = true;
+    const ratio = convertSize(prefix, arguments, nullable);
+    if (ratio != null && (ratio[0] == 0 || ratio[1] == 0)) {
+      throw new DOMException(
+        `${prefix}: Aspect ratio must not be zero.`,
+        "OperationError",
+      );
+    }
+
+    return wsiOps().op_wsi_window_set_aspect_ratio(wid, ratio);
+  }
+
+  fitInnerSize() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'fitInnerSize' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    const size = convertSize(prefix, arguments);
+
+    return wsiOps().op_wsi_window_fit_inner_size(wid, size);
   }
 
   getScaleFactor() {
@@ -561,7 +1483,153 @@ class WSIWindow {
     const prefix = "Failed to execute 'getScaleFactor' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_get_scale_factor(wid);
+    return wsiOps().op_wsi_window_get_scale_factor(wid);
+  }
+
+  getRefreshRate() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getRefreshRate' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_get_refresh_rate(wid);
+  }
+
+  getColorProfile() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getColorProfile' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_get_color_profile(wid);
+  }
+
+  getSupportedDisplayControls() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix =
+      "Failed to execute 'getSupportedDisplayControls' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_get_supported_display_controls(wid);
+  }
+
+  getBrightness() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getBrightness' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_get_brightness(wid);
+  }
+
+  setBrightness(brightness) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setBrightness' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    brightness = webidl.converters["double"](brightness, {
+      prefix,
+      context: "Argument 1",
+    });
+    if (brightness < 0 || brightness > 1) {
+      throw new DOMException(
+        `${prefix}: Brightness must be between 0 and 1.`,
+        "OperationError",
+      );
+    }
+
+    return wsiOps().op_wsi_window_set_brightness(wid, brightness);
+  }
+
+  setGamma(gamma) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setGamma' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    gamma = webidl.converters["double"](gamma, {
+      prefix,
+      context: "Argument 1",
+    });
+    if (gamma <= 0) {
+      throw new DOMException(
+        `${prefix}: Gamma must be greater than 0.`,
+        "OperationError",
+      );
+    }
+
+    return wsiOps().op_wsi_window_set_gamma(wid, gamma);
+  }
+
+  setSkipTaskbar(skip = true) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setSkipTaskbar' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    skip = webidl.converters["boolean"](skip, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_set_skip_taskbar(wid, skip);
+  }
+
+  getTag() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getTag' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_get_tag(wid);
+  }
+
+  setTaskbarProgress(state, value = undefined) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setTaskbarProgress' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    state = webidl.converters["WSITaskbarProgressState"](state, {
+      prefix,
+      context: "Argument 1",
+    });
+    if (value !== undefined) {
+      value = webidl.converters["double"](value, {
+        prefix,
+        context: "Argument 2",
+      });
+    }
+
+    return wsiOps().op_wsi_window_set_taskbar_progress(wid, state, value);
+  }
+
+  setTaskbarOverlay(icon, description = "") {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setTaskbarOverlay' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    icon = convertImage(prefix, icon);
+    description = webidl.converters["DOMString"](description, {
+      prefix,
+      context: "Argument 2",
+    });
+
+    return wsiOps().op_wsi_window_set_taskbar_overlay(wid, icon, description);
+  }
+
+  setBadge(count) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setBadge' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    if (count !== null) {
+      count = webidl.converters["unsigned long"](count, {
+        prefix,
+        context: "Argument 1",
+        enforceRange: true,
+      });
+    }
+
+    return wsiOps().op_wsi_window_set_badge(wid, count);
   }
 
   getTheme() {
@@ -569,7 +1637,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'getTheme' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_get_theme(wid);
+    return wsiOps().op_wsi_window_get_theme(wid);
   }
 
   setTheme(theme) {
@@ -585,7 +1653,7 @@ class WSIWindow {
       });
     }
 
-    return ops.op_wsi_window_set_theme(wid, theme);
+    return wsiOps().op_wsi_window_set_theme(wid, theme);
   }
 
   getTitle() {
@@ -593,7 +1661,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'getTitle' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_get_title(wid);
+    return wsiOps().op_wsi_window_get_title(wid);
   }
 
   setTitle(title) {
@@ -607,7 +1675,7 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_title(wid, title);
+    return wsiOps().op_wsi_window_set_title(wid, title);
   }
 
   setTransparent(transparent = true) {
@@ -620,7 +1688,7 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_transparent(wid, transparent);
+    return wsiOps().op_wsi_window_set_transparent(wid, transparent);
   }
 
   isVisible() {
@@ -628,7 +1696,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'isVisible' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_is_visible(wid);
+    return wsiOps().op_wsi_window_is_visible(wid);
   }
 
   setVisible(visible = true) {
@@ -641,7 +1709,67 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_set_visible(wid, visible);
+    return wsiOps().op_wsi_window_set_visible(wid, visible);
+  }
+
+  getVisibleRect() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getVisibleRect' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    if (this[_isOccluded]) {
+      return [0, 0, 0, 0];
+    }
+    return wsiOps().op_wsi_window_get_visible_rect(wid);
+  }
+
+  getVisibleFraction() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getVisibleFraction' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    if (this[_isOccluded]) {
+      return 0;
+    }
+    return wsiOps().op_wsi_window_get_visible_fraction(wid);
+  }
+
+  getSafeAreaInsets() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getSafeAreaInsets' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_get_safe_area_insets(wid);
+  }
+
+  getOrientation() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'getOrientation' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_get_orientation(wid);
+  }
+
+  lockOrientation(lock) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'lockOrientation' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    lock = webidl.converters["WSIOrientationLockType"](lock, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_lock_orientation(wid, lock);
+  }
+
+  unlockOrientation() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'unlockOrientation' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    wsiOps().op_wsi_window_lock_orientation(wid, "any");
   }
 
   beginDragMove() {
@@ -649,7 +1777,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'beginDragMove' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_begin_drag_move(wid);
+    return wsiOps().op_wsi_window_begin_drag_move(wid);
   }
 
   beginDragResize(direction) {
@@ -663,7 +1791,42 @@ class WSIWindow {
       context: "Argument 1",
     });
 
-    return ops.op_wsi_window_begin_drag_resize(wid, direction);
+    return wsiOps().op_wsi_window_begin_drag_resize(wid, direction);
+  }
+
+  setDropTypes(types) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'setDropTypes' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    types = webidl.converters["sequence<WSIDropType>"](types, {
+      prefix,
+      context: "Argument 1",
+    });
+
+    return wsiOps().op_wsi_window_set_drop_types(wid, types);
+  }
+
+  startDrag(data, image = null) {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'startDrag' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    webidl.requiredArguments(arguments.length, 1, { prefix });
+    data = webidl.converters["WSIDragData"](data, {
+      prefix,
+      context: "Argument 1",
+    });
+    if (!data.paths?.length && data.text === undefined) {
+      throw new DOMException(
+        `${prefix}: data must have paths or text.`,
+        "OperationError",
+      );
+    }
+    image = convertImage(prefix, image);
+
+    return wsiOps().op_wsi_window_start_drag(wid, data, image);
   }
 
   requestRedraw() {
@@ -671,7 +1834,7 @@ class WSIWindow {
     const prefix = "Failed to execute 'requestRedraw' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
-    return ops.op_wsi_window_request_redraw(wid);
+    return wsiOps().op_wsi_window_request_redraw(wid);
   }
 
   requestUserAttention(type) {
@@ -687,7 +1850,15 @@ class WSIWindow {
       });
     }
 
-    return ops.op_wsi_window_request_user_attention(wid, type);
+    return wsiOps().op_wsi_window_request_user_attention(wid, type);
+  }
+
+  captureContent() {
+    webidl.assertBranded(this, WSIWindowPrototype);
+    const prefix = "Failed to execute 'captureContent' on 'WSIWindow'";
+    const wid = assertWindow(this, { prefix, context: "this" });
+
+    return wsiOps().op_wsi_window_capture_content(wid);
   }
 
   destroy() {
@@ -695,18 +1866,96 @@ class WSIWindow {
     const prefix = "Failed to execute 'destroy' on 'WSIWindow'";
     const wid = assertWindow(this, { prefix, context: "this" });
 
+    // Child windows must not outlive their parent.
+    for (const child of this[_children]) {
+      child.destroy();
+    }
+    if (this[_parent] != null) {
+      this[_parent][_children].delete(this);
+      this[_parent] = undefined;
+    }
+
     if (this[_gpuSurface] != null) {
       webgpu.destroyGPUSurface(this[_gpuSurface]);
       this[_gpuSurface] = undefined;
     }
 
-    ops.op_wsi_window_destroy(wid);
+    if (cursorWindow === this) {
+      cursorWindow = null;
+    }
+
+    wsiOps().op_wsi_window_destroy(wid);
     windows.delete(wid);
     this[_wid] = undefined;
   }
 }
 const WSIWindowPrototype = WSIWindow.prototype;
 
+// What window commands that report whether they worked return in resilient
+// mode when they fail. The others return nothing.
+const resilientFallbacks = {
+  __proto__: null,
+  captureContent: null,
+  hideSoftKeyboard: false,
+  lockOrientation: false,
+  setBackdrop: false,
+  setBadge: false,
+  setBrightness: false,
+  setGamma: false,
+  setShape: false,
+  setSystemShortcutsInhibited: false,
+  showSoftKeyboard: false,
+  startDrag: false,
+};
+
+// Window methods that answer a question, for which there's no sensible value to
+// return in place of the answer.
+function isWindowQuery(name) {
+  return /^(get|is|has)[A-Z]/.test(name) || name === "clientToDesktop" ||
+    name === "desktopToClient";
+}
+
+// In resilient mode, report failed window commands as "recoverable-error"
+// events instead of throwing. Queries, and argument and state validation
+// errors, still throw.
+for (
+  const [name, descriptor] of Object.entries(
+    Object.getOwnPropertyDescriptors(WSIWindowPrototype),
+  )
+) {
+  const method = descriptor.value;
+  if (
+    name === "constructor" || typeof method !== "function" ||
+    isWindowQuery(name)
+  ) {
+    continue;
+  }
+  Object.defineProperty(WSIWindowPrototype, name, {
+    ...descriptor,
+    value: {
+      [name](...args) {
+        try {
+          return method.apply(this, args);
+        } catch (err) {
+          if (
+            !getOptions().resilient || err instanceof TypeError ||
+            err instanceof DOMException
+          ) {
+            throw err;
+          }
+          pendingEvents.push({
+            type: "recoverable-error",
+            window: this,
+            operation: name,
+            message: err.message,
+          });
+          return resilientFallbacks[name];
+        }
+      },
+    }[name],
+  });
+}
+
 class WSIWindowButton {
   constructor() {
     webidl.illegalConstructor();
@@ -723,5 +1972,341 @@ class WSIWindowButton {
   }
 }
 
+// Virtual windows for --wsi-headless. They keep just enough state to answer
+// queries consistently and synthesize the events a real window would produce.
+const headlessWindows = new Map();
+let nextHeadlessWid = 1;
+
+function headlessWindow(wid) {
+  return headlessWindows.get(wid);
+}
+
+function headlessWindowButtons(buttons) {
+  if (typeof buttons === "number") {
+    return buttons;
+  }
+  return (buttons.close ? WSIWindowButton.CLOSE : 0) |
+    (buttons.minimize ? WSIWindowButton.MINIMIZE : 0) |
+    (buttons.maximize ? WSIWindowButton.MAXIMIZE : 0);
+}
+
+function pushHeadlessEvent(event) {
+  pendingEvents.push(event);
+}
+
+async function nextHeadlessEvent(signal, timeoutMs) {
+  let redraw = false;
+  for (const state of headlessWindows.values()) {
+    redraw ||= state.redrawRequested;
+  }
+  if (!redraw) {
+    const timedOut = timeoutMs !== undefined && timeoutMs < 16;
+    await new Promise((resolve) =>
+      setTimeout(resolve, timedOut ? timeoutMs : 16)
+    );
+    signal?.throwIfAborted();
+    if (timedOut) {
+      return null;
+    }
+  }
+  pushHeadlessEvent({ type: "new-events" });
+  pushHeadlessEvent({ type: "main-events-cleared" });
+  for (const [wid, state] of headlessWindows) {
+    if (state.redrawRequested) {
+      state.redrawRequested = false;
+      pushHeadlessEvent({ type: "redraw-requested", window: wid });
+    }
+  }
+  pushHeadlessEvent({ type: "redraw-events-cleared" });
+  return takePendingEvent();
+}
+
+function noop() {}
+
+// Quitting is vetoed if a callback returns or resolves to false, or throws.
+async function replyToQuitRequest() {
+  let allow = false;
+  try {
+    for (const callback of beforeQuitCallbacks) {
+      if ((await callback()) === false) {
+        return;
+      }
+    }
+    allow = true;
+  } finally {
+    ops.op_wsi_reply_to_quit_request(allow);
+  }
+}
+
+const headlessOps = {
+  op_wsi_set_device_event_filter: noop,
+  op_wsi_set_device_motion_interval: noop,
+  op_wsi_get_device_info: () => null,
+  op_wsi_set_global_input_enabled: (enabled) => !enabled,
+  op_wsi_set_click_options: noop,
+  // Virtual windows accept everything and ignore what they can't show.
+  op_wsi_get_capabilities: () => ({
+    backend: "headless",
+    cursorGrabModes: ["none", "confined", "locked", "locked-or-confined"],
+    transparency: true,
+    dragResize: true,
+    ime: true,
+    contentProtection: true,
+    captureDetection: false,
+  }),
+  op_wsi_get_monitors: () => [{
+    name: "headless",
+    primary: true,
+    position: [0, 0],
+    size: [1920, 1080],
+    workArea: [0, 0, 1920, 1080],
+    scaleFactor: 1,
+    refreshRate: 60000,
+  }],
+  op_wsi_get_system_theme: () => null,
+  // The trace writer doesn't need the event loop.
+  op_wsi_set_trace_enabled: (enabled) => ops.op_wsi_set_trace_enabled(enabled),
+  op_wsi_get_key_label: () => null,
+  op_wsi_get_key_scan_code: () => null,
+  op_wsi_get_modifiers: () => null,
+  op_wsi_set_app_menu: noop,
+  op_wsi_set_dock_badge: noop,
+  op_wsi_set_dock_icon: noop,
+  op_wsi_request_dock_attention: noop,
+  op_wsi_register_url_scheme() {
+    ops.op_wsi_check_permission("Deno.wsi.registerUrlScheme");
+    return null;
+  },
+  op_wsi_set_crash_metadata: () => false,
+  op_wsi_create_window(options) {
+    ops.op_wsi_check_permission("Deno.wsi.createWindow");
+    const wid = nextHeadlessWid++;
+    headlessWindows.set(wid, {
+      contentProtected: options?.contentProtected ?? false,
+      decorated: options?.decorated ?? true,
+      enabled: true,
+      enabledButtons: headlessWindowButtons(options?.enabledButtons ?? 0b111),
+      fullscreen: options?.fullscreen ?? false,
+      position: options?.position ?? [0, 0],
+      innerSize: options?.innerSize ?? [800, 600],
+      minimized: false,
+      maximized: options?.maximized ?? false,
+      resizable: options?.resizable ?? true,
+      resizeIncrements: options?.resizeIncrements ?? null,
+      // Kept as JSON, so each read returns a copy, as with a real window.
+      tag: JSON.stringify(options?.tag ?? null),
+      theme: options?.theme ?? null,
+      title: options?.title ?? "Denog",
+      visible: options?.visible ?? true,
+      redrawRequested: true,
+    });
+    pushHeadlessEvent({
+      type: "window-resized",
+      window: wid,
+      innerSize: headlessWindow(wid).innerSize,
+    });
+    return wid;
+  },
+  op_wsi_windows() {
+    const monitor = headlessOps.op_wsi_get_monitors()[0];
+    return [...headlessWindows].map(([wid, state]) => ({
+      window: wid,
+      title: state.title,
+      focused: true,
+      visible: state.visible,
+      monitor,
+      tag: JSON.parse(state.tag),
+    }));
+  },
+  op_wsi_window_set_coordinate_space: noop,
+  op_wsi_window_get_supported_backdrops: () => ["none"],
+  op_wsi_window_set_backdrop: (_wid, backdrop) => backdrop === "none",
+  op_wsi_window_is_content_protected: (wid) =>
+    headlessWindow(wid).contentProtected,
+  op_wsi_window_set_content_protected(wid, contentProtected) {
+    headlessWindow(wid).contentProtected = contentProtected;
+  },
+  op_wsi_window_set_corner_preference: noop,
+  op_wsi_window_set_title_bar_style: noop,
+  op_wsi_window_set_cursor_confinement_rect: noop,
+  op_wsi_window_set_cursor_grab_mode: noop,
+  op_wsi_window_set_cursor_hit_test_enabled: noop,
+  op_wsi_window_set_cursor_hit_test_region: noop,
+  op_wsi_window_set_cursor_icon: noop,
+  op_wsi_window_set_cursor_position: noop,
+  op_wsi_window_set_cursor_visible: noop,
+  op_wsi_window_is_decorated: (wid) => headlessWindow(wid).decorated,
+  op_wsi_window_set_decorated(wid, decorated) {
+    headlessWindow(wid).decorated = decorated;
+  },
+  op_wsi_window_set_diagnostics_overlay: noop,
+  op_wsi_window_is_enabled: (wid) => headlessWindow(wid).enabled,
+  op_wsi_window_set_enabled(wid, enabled) {
+    headlessWindow(wid).enabled = enabled;
+  },
+  op_wsi_window_get_enabled_buttons: (wid) =>
+    headlessWindow(wid).enabledButtons,
+  op_wsi_window_set_enabled_buttons(wid, buttons) {
+    headlessWindow(wid).enabledButtons = headlessWindowButtons(buttons);
+  },
+  op_wsi_window_has_focus: () => true,
+  op_wsi_window_take_focus: noop,
+  op_wsi_window_is_fullscreen: (wid) => headlessWindow(wid).fullscreen,
+  op_wsi_window_set_fullscreen(wid, fullscreen) {
+    headlessWindow(wid).fullscreen = fullscreen;
+  },
+  op_wsi_window_set_ime_allowed: noop,
+  op_wsi_window_set_ime_position: noop,
+  op_wsi_window_set_ime_purpose: noop,
+  op_wsi_window_reset_dead_keys: noop,
+  op_wsi_window_show_soft_keyboard: () => false,
+  op_wsi_window_hide_soft_keyboard: () => false,
+  op_wsi_window_get_soft_keyboard_rect: () => null,
+  op_wsi_window_set_system_shortcuts_inhibited: () => false,
+  op_wsi_window_get_inner_position: (wid) => headlessWindow(wid).position,
+  op_wsi_window_get_outer_position: (wid) => headlessWindow(wid).position,
+  op_wsi_window_set_outer_position(wid, position) {
+    headlessWindow(wid).position = position;
+    pushHeadlessEvent({ type: "window-moved", window: wid, position });
+  },
+  op_wsi_window_center: noop,
+  op_wsi_window_get_inner_size: (wid) => headlessWindow(wid).innerSize,
+  op_wsi_window_get_outer_size: (wid) => headlessWindow(wid).innerSize,
+  op_wsi_window_set_inner_size(wid, innerSize) {
+    const state = headlessWindow(wid);
+    state.innerSize = innerSize;
+    state.redrawRequested = true;
+    pushHeadlessEvent({ type: "window-resized", window: wid, innerSize });
+  },
+  op_wsi_window_set_min_inner_size: noop,
+  op_wsi_window_set_max_inner_size: noop,
+  op_wsi_window_set_level: noop,
+  op_wsi_window_is_minimized: (wid) => headlessWindow(wid).minimized,
+  op_wsi_window_set_minimized(wid, minimized) {
+    headlessWindow(wid).minimized = minimized;
+  },
+  op_wsi_window_is_maximized: (wid) => headlessWindow(wid).maximized,
+  op_wsi_window_set_maximized(wid, maximized) {
+    headlessWindow(wid).maximized = maximized;
+  },
+  op_wsi_window_is_resizable: (wid) => headlessWindow(wid).resizable,
+  op_wsi_window_set_resizable(wid, resizable) {
+    headlessWindow(wid).resizable = resizable;
+  },
+  op_wsi_window_set_shadow: noop,
+  op_wsi_window_set_shape: () => true,
+  op_wsi_window_get_resize_increments: (wid) =>
+    headlessWindow(wid).resizeIncrements,
+  op_wsi_window_set_resize_increments(wid, increments) {
+    headlessWindow(wid).resizeIncrements = increments;
+  },
+  op_wsi_window_set_aspect_ratio(wid, ratio) {
+    headlessWindow(wid).aspectRatio = ratio;
+  },
+  op_wsi_window_fit_inner_size(wid, [width, height]) {
+    const state = headlessWindow(wid);
+    const [ratioWidth, ratioHeight] = state.aspectRatio ?? state.innerSize;
+    const innerSize = width * ratioHeight <= height * ratioWidth
+      ? [width, Math.floor(width * ratioHeight / ratioWidth)]
+      : [Math.floor(height * ratioWidth / ratioHeight), height];
+    headlessOps.op_wsi_window_set_inner_size(wid, innerSize);
+  },
+  op_wsi_window_get_scale_factor: () => 1,
+  op_wsi_window_get_refresh_rate: () => 60000,
+  op_wsi_window_get_color_profile: () => null,
+  op_wsi_window_get_supported_display_controls: () => [],
+  op_wsi_window_get_brightness: () => null,
+  op_wsi_window_set_brightness: () => false,
+  op_wsi_window_set_gamma: () => false,
+  op_wsi_window_set_skip_taskbar: noop,
+  op_wsi_window_get_tag: (wid) => JSON.parse(headlessWindow(wid).tag),
+  op_wsi_window_set_taskbar_progress: noop,
+  op_wsi_window_set_taskbar_overlay: noop,
+  op_wsi_window_set_badge: () => false,
+  op_wsi_window_get_theme: (wid) => headlessWindow(wid).theme,
+  op_wsi_window_set_theme(wid, theme) {
+    headlessWindow(wid).theme = theme;
+  },
+  op_wsi_window_get_title: (wid) => headlessWindow(wid).title,
+  op_wsi_window_set_title(wid, title) {
+    headlessWindow(wid).title = title;
+  },
+  op_wsi_window_set_transparent: noop,
+  op_wsi_window_is_visible: (wid) => headlessWindow(wid).visible,
+  op_wsi_window_set_visible(wid, visible) {
+    headlessWindow(wid).visible = visible;
+  },
+  op_wsi_window_get_visible_rect(wid) {
+    const { innerSize, minimized, visible } = headlessWindow(wid);
+    return visible && !minimized
+      ? [0, 0, innerSize[0], innerSize[1]]
+      : [0, 0, 0, 0];
+  },
+  op_wsi_window_get_visible_fraction(wid) {
+    const { minimized, visible } = headlessWindow(wid);
+    return visible && !minimized ? 1 : 0;
+  },
+  op_wsi_window_get_safe_area_insets: () => [0, 0, 0, 0],
+  op_wsi_window_get_orientation: () => null,
+  op_wsi_window_lock_orientation: (_wid, lock) => lock === "any",
+  op_wsi_window_begin_drag_move: noop,
+  op_wsi_window_begin_drag_resize: noop,
+  op_wsi_window_set_drop_types: noop,
+  op_wsi_window_start_drag() {
+    return false;
+  },
+  op_wsi_window_request_redraw(wid) {
+    headlessWindow(wid).redrawRequested = true;
+  },
+  op_wsi_window_request_user_attention: noop,
+  op_wsi_window_capture_content: () => null,
+  op_wsi_window_destroy(wid) {
+    headlessWindows.delete(wid);
+  },
+};
+
+function takePendingEvent() {
+  const event = pendingEvents.shift();
+  if (getOptions().headless) {
+    ops.op_wsi_trace_headless_event(
+      event.window instanceof WSIWindow
+        ? { ...event, window: event.window[_wid] }
+        : event,
+    );
+  }
+  if (event.window != null && !(event.window instanceof WSIWindow)) {
+    event.window = windows.get(event.window);
+  }
+  return event;
+}
+
+// The windows that are open right now.
+function openWindows() {
+  return new Set(windows.values());
+}
+
+// Destroy the windows that weren't open yet when `openWindows()` returned
+// `before`. The test runner calls this after each test, so that the windows a
+// test forgot to destroy don't leak into the next one.
+function destroyWindowsOpenedSince(before) {
+  for (const window of [...windows.values()]) {
+    // Destroying a parent destroys its children too.
+    if (window[_wid] !== undefined && !before.has(window)) {
+      window.destroy();
+    }
+  }
+}
+
 const wsi = webidl.createBranded(WSI);
-export { WSI, wsi, WSIModifierKey, WSIWindow, WSIWindowButton };
+const assets = webidl.createBranded(WSIAssets);
+export {
+  destroyWindowsOpenedSince,
+  openWindows,
+  WSI,
+  wsi,
+  WSIAssets,
+  WSIModifierKey,
+  WSIShaderWatch,
+  WSIWindow,
+  WSIWindowButton,
+};
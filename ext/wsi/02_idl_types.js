@@ -2,6 +2,15 @@
 
 import * as webidl from "ext:deno_webidl/00_webidl.js";
 
+// ENUM: WSICoordinateSpace
+webidl.converters["WSICoordinateSpace"] = webidl.createEnumConverter(
+  "WSICoordinateSpace",
+  [
+    "physical",
+    "logical",
+  ],
+);
+
 // ENUM: WSICursorGrabMode
 webidl.converters["WSICursorGrabMode"] = webidl.createEnumConverter(
   "WSICursorGrabMode",
@@ -9,6 +18,7 @@ webidl.converters["WSICursorGrabMode"] = webidl.createEnumConverter(
     "none",
     "confined",
     "locked",
+    "locked-or-confined",
   ],
 );
 
@@ -64,6 +74,18 @@ webidl.converters["WSIDeviceEventFilter"] = webidl.createEnumConverter(
   ],
 );
 
+// ENUM: WSIDropType
+webidl.converters["WSIDropType"] = webidl.createEnumConverter(
+  "WSIDropType",
+  [
+    "image/png",
+    "text/plain",
+    "text/uri-list",
+  ],
+);
+webidl.converters["sequence<WSIDropType>"] = webidl
+  .createSequenceConverter(webidl.converters["WSIDropType"]);
+
 // ENUM: WSIIMEPurpose
 webidl.converters["WSIIMEPurpose"] = webidl.createEnumConverter(
   "WSIIMEPurpose",
@@ -74,11 +96,38 @@ webidl.converters["WSIIMEPurpose"] = webidl.createEnumConverter(
   ],
 );
 
+// ENUM: WSIOrientationLockType
+webidl.converters["WSIOrientationLockType"] = webidl.createEnumConverter(
+  "WSIOrientationLockType",
+  [
+    "any",
+    "landscape",
+    "landscape-primary",
+    "landscape-secondary",
+    "portrait",
+    "portrait-primary",
+    "portrait-secondary",
+  ],
+);
+
 // TYPEDEF: WSIPosition
 webidl.converters["WSIPosition"] = webidl.createSequenceConverter(
   webidl.converters["long"],
 );
 
+// TYPEDEF: WSIColor
+webidl.converters["WSIColor"] = webidl.createSequenceConverter(
+  webidl.converters["octet"],
+);
+
+// TYPEDEF: WSIRect
+webidl.converters["WSIRect"] = webidl.createSequenceConverter(
+  webidl.converters["long"],
+);
+webidl.converters["sequence<WSIRect>"] = webidl.createSequenceConverter(
+  webidl.converters["WSIRect"],
+);
+
 // ENUM: WSIResizeDirection
 webidl.converters["WSIResizeDirection"] = webidl.createEnumConverter(
   "WSIResizeDirection",
@@ -99,6 +148,18 @@ webidl.converters["WSISize"] = webidl.createSequenceConverter(
   webidl.converters["unsigned long"],
 );
 
+// ENUM: WSITaskbarProgressState
+webidl.converters["WSITaskbarProgressState"] = webidl.createEnumConverter(
+  "WSITaskbarProgressState",
+  [
+    "none",
+    "indeterminate",
+    "normal",
+    "error",
+    "paused",
+  ],
+);
+
 // ENUM: WSIUserAttentionType
 webidl.converters["WSIUserAttentionType"] = webidl.createEnumConverter(
   "WSIUserAttentionType",
@@ -108,6 +169,28 @@ webidl.converters["WSIUserAttentionType"] = webidl.createEnumConverter(
   ],
 );
 
+// ENUM: WSIWindowBackdrop
+webidl.converters["WSIWindowBackdrop"] = webidl.createEnumConverter(
+  "WSIWindowBackdrop",
+  [
+    "none",
+    "blur",
+    "acrylic",
+    "mica",
+  ],
+);
+
+// ENUM: WSIWindowCorners
+webidl.converters["WSIWindowCorners"] = webidl.createEnumConverter(
+  "WSIWindowCorners",
+  [
+    "default",
+    "round",
+    "round-small",
+    "square",
+  ],
+);
+
 // ENUM: WSIWindowLevel
 webidl.converters["WSIWindowLevel"] = webidl.createEnumConverter(
   "WSIWindowLevel",
@@ -115,6 +198,7 @@ webidl.converters["WSIWindowLevel"] = webidl.createEnumConverter(
     "always-on-bottom",
     "normal",
     "always-on-top",
+    "always-on-top-of-fullscreen",
   ],
 );
 
@@ -127,23 +211,175 @@ webidl.converters["WSIWindowTheme"] = webidl.createEnumConverter(
   ],
 );
 
+// DICTIONARY: WSIAppMenuItem
+const dictMembersWSIAppMenuItem = [
+  {
+    key: "id",
+    converter: webidl.converters["DOMString"],
+    required: true,
+  },
+  {
+    key: "key",
+    converter: webidl.converters["DOMString"],
+  },
+  {
+    key: "title",
+    converter: webidl.converters["DOMString"],
+    required: true,
+  },
+];
+webidl.converters["WSIAppMenuItem"] = webidl
+  .createDictionaryConverter(
+    "WSIAppMenuItem",
+    dictMembersWSIAppMenuItem,
+  );
+webidl.converters["WSIAppMenuItem?"] = webidl.createNullableConverter(
+  webidl.converters["WSIAppMenuItem"],
+);
+webidl.converters["sequence<WSIAppMenuItem?>"] = webidl
+  .createSequenceConverter(webidl.converters["WSIAppMenuItem?"]);
+
+// DICTIONARY: WSIClickOptions
+const dictMembersWSIClickOptions = [
+  {
+    key: "distance",
+    converter: webidl.converters["double"],
+  },
+  {
+    key: "interval",
+    converter: webidl.converters["unsigned long"],
+  },
+];
+webidl.converters["WSIClickOptions"] = webidl
+  .createDictionaryConverter(
+    "WSIClickOptions",
+    dictMembersWSIClickOptions,
+  );
+
+// DICTIONARY: WSINextEventOptions
+const dictMembersWSINextEventOptions = [
+  {
+    key: "signal",
+    converter: webidl.converters["AbortSignal"],
+  },
+  {
+    key: "timeoutMs",
+    converter: webidl.converters["double"],
+  },
+];
+webidl.converters["WSINextEventOptions"] = webidl
+  .createDictionaryConverter(
+    "WSINextEventOptions",
+    dictMembersWSINextEventOptions,
+  );
+
+// DICTIONARY: WSIWindowButtonsDict
+const dictMembersWSIWindowButtonsDict = [
+  {
+    key: "close",
+    converter: webidl.converters["boolean"],
+    defaultValue: true,
+  },
+  {
+    key: "minimize",
+    converter: webidl.converters["boolean"],
+    defaultValue: true,
+  },
+  {
+    key: "maximize",
+    converter: webidl.converters["boolean"],
+    defaultValue: true,
+  },
+];
+webidl.converters["WSIWindowButtonsDict"] = webidl
+  .createDictionaryConverter(
+    "WSIWindowButtonsDict",
+    dictMembersWSIWindowButtonsDict,
+  );
+
+// TYPEDEF: WSIWindowButtons
+webidl.converters["WSIWindowButtons"] = (V, opts) => {
+  // Union for (WSIWindowButtonsDict or unsigned long). The bitfield of
+  // WSIWindowButton constants is kept for compatibility.
+  if (typeof V === "number") {
+    return webidl.converters["unsigned long"](V, opts);
+  }
+  return webidl.converters["WSIWindowButtonsDict"](V, opts);
+};
+
+// DICTIONARY: WSIWindowShape
+const dictMembersWSIWindowShape = [
+  {
+    key: "cornerRadius",
+    converter: webidl.converters["unsigned long"],
+    defaultValue: 0,
+  },
+  {
+    key: "rects",
+    converter: webidl.converters["sequence<WSIRect>"],
+    required: true,
+  },
+];
+webidl.converters["WSIWindowShape"] = webidl
+  .createDictionaryConverter(
+    "WSIWindowShape",
+    dictMembersWSIWindowShape,
+  );
+
+// DICTIONARY: WSITitleBarStyle
+const dictMembersWSITitleBarStyle = [
+  {
+    key: "borderColor",
+    converter: webidl.converters["WSIColor"],
+  },
+  {
+    key: "captionColor",
+    converter: webidl.converters["WSIColor"],
+  },
+  {
+    key: "darkMode",
+    converter: webidl.converters["boolean"],
+  },
+  {
+    key: "textColor",
+    converter: webidl.converters["WSIColor"],
+  },
+];
+webidl.converters["WSITitleBarStyle"] = webidl
+  .createDictionaryConverter(
+    "WSITitleBarStyle",
+    dictMembersWSITitleBarStyle,
+  );
+
 // DICTIONARY: WSICreateWindowOptions
 const dictMembersWSICreateWindowOptions = [
   {
     key: "active",
     converter: webidl.converters["boolean"],
   },
+  {
+    key: "appId",
+    converter: webidl.converters["DOMString"],
+  },
   {
     key: "contentProtected",
     converter: webidl.converters["boolean"],
   },
+  {
+    key: "coordinateSpace",
+    converter: webidl.converters["WSICoordinateSpace"],
+  },
+  {
+    key: "corners",
+    converter: webidl.converters["WSIWindowCorners"],
+  },
   {
     key: "decorated",
     converter: webidl.converters["boolean"],
   },
   {
     key: "enabledButtons",
-    converter: webidl.converters["unsigned long"],
+    converter: webidl.converters["WSIWindowButtons"],
   },
   {
     key: "fullscreen",
@@ -173,6 +409,14 @@ const dictMembersWSICreateWindowOptions = [
     key: "maximized",
     converter: webidl.converters["boolean"],
   },
+  {
+    key: "name",
+    converter: webidl.converters["DOMString"],
+  },
+  {
+    key: "parent",
+    converter: webidl.converters.any,
+  },
   {
     key: "resizable",
     converter: webidl.converters["boolean"],
@@ -181,6 +425,18 @@ const dictMembersWSICreateWindowOptions = [
     key: "resizeIncrements",
     converter: webidl.converters["WSISize"],
   },
+  {
+    key: "shadow",
+    converter: webidl.converters["boolean"],
+  },
+  {
+    key: "skipTaskbar",
+    converter: webidl.converters["boolean"],
+  },
+  {
+    key: "tag",
+    converter: webidl.converters.any,
+  },
   {
     key: "theme",
     converter: webidl.converters["WSIWindowTheme"],
@@ -189,6 +445,10 @@ const dictMembersWSICreateWindowOptions = [
     key: "title",
     converter: webidl.converters["DOMString"],
   },
+  {
+    key: "titleBarStyle",
+    converter: webidl.converters["WSITitleBarStyle"],
+  },
   {
     key: "transparent",
     converter: webidl.converters["boolean"],
@@ -203,3 +463,39 @@ webidl.converters["WSICreateWindowOptions"] = webidl
     "WSICreateWindowOptions",
     dictMembersWSICreateWindowOptions,
   );
+
+// DICTIONARY: WSIDragData
+const dictMembersWSIDragData = [
+  {
+    key: "paths",
+    converter: webidl.converters["sequence<DOMString>"],
+  },
+  {
+    key: "text",
+    converter: webidl.converters["DOMString"],
+  },
+];
+webidl.converters["WSIDragData"] = webidl
+  .createDictionaryConverter(
+    "WSIDragData",
+    dictMembersWSIDragData,
+  );
+
+// DICTIONARY: WSIGPUSurfaceOptions
+const dictMembersWSIGPUSurfaceOptions = [
+  {
+    key: "autoReconfigure",
+    converter: webidl.converters["boolean"],
+    defaultValue: false,
+  },
+  {
+    key: "autoResize",
+    converter: webidl.converters["boolean"],
+    defaultValue: false,
+  },
+];
+webidl.converters["WSIGPUSurfaceOptions"] = webidl
+  .createDictionaryConverter(
+    "WSIGPUSurfaceOptions",
+    dictMembersWSIGPUSurfaceOptions,
+  );
@@ -0,0 +1,88 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+};
+use winit::dpi::PhysicalSize;
+
+// Windows whose aspect ratio is locked, shared between the proxy thread, which
+// locks them, and the real event loop, which enforces the ratio while the user
+// resizes them.
+#[derive(Clone, Default)]
+pub(crate) struct WsiAspectRatios(Arc<Mutex<HashMap<u64, AspectRatioLock>>>);
+
+struct AspectRatioLock {
+  ratio: (u32, u32),
+  // The last inner size the ratio was enforced for.
+  size: PhysicalSize<u32>,
+}
+
+impl WsiAspectRatios {
+  pub(crate) fn get(&self, wid: u64) -> Option<(u32, u32)> {
+    let locks = self.0.lock().unwrap();
+    locks.get(&wid).map(|lock| lock.ratio)
+  }
+
+  pub(crate) fn lock(
+    &self,
+    wid: u64,
+    ratio: (u32, u32),
+    size: PhysicalSize<u32>,
+  ) {
+    let mut locks = self.0.lock().unwrap();
+    locks.insert(wid, AspectRatioLock { ratio, size });
+  }
+
+  pub(crate) fn unlock(&self, wid: u64) {
+    self.0.lock().unwrap().remove(&wid);
+  }
+
+  // Adjust a window's new inner size to its locked ratio. Returns `None` if the
+  // window's ratio isn't locked.
+  pub(crate) fn constrain(
+    &self,
+    wid: u64,
+    size: PhysicalSize<u32>,
+  ) -> Option<PhysicalSize<u32>> {
+    let mut locks = self.0.lock().unwrap();
+    let lock = locks.get_mut(&wid)?;
+    lock.size = constrain(lock.size, size, lock.ratio);
+    Some(lock.size)
+  }
+}
+
+// Keep whichever dimension the user changed more and derive the other one from
+// the ratio, so that dragging any edge or corner resizes the window.
+pub(crate) fn constrain(
+  previous: PhysicalSize<u32>,
+  size: PhysicalSize<u32>,
+  (ratio_width, ratio_height): (u32, u32),
+) -> PhysicalSize<u32> {
+  let (width, height) = (size.width as u64, size.height as u64);
+  let (ratio_width, ratio_height) = (ratio_width as u64, ratio_height as u64);
+  let width_change = width.abs_diff(previous.width as u64) * ratio_height;
+  let height_change = height.abs_diff(previous.height as u64) * ratio_width;
+  if width_change >= height_change {
+    PhysicalSize::new(size.width, (width * ratio_height / ratio_width) as u32)
+  } else {
+    PhysicalSize::new((height * ratio_width / ratio_height) as u32, size.height)
+  }
+}
+
+// The largest size with the given ratio that fits within `bounds`.
+pub(crate) fn fit(
+  bounds: PhysicalSize<u32>,
+  (ratio_width, ratio_height): (u32, u32),
+) -> PhysicalSize<u32> {
+  let (width, height) = (bounds.width as u64, bounds.height as u64);
+  let (ratio_width, ratio_height) = (ratio_width as u64, ratio_height as u64);
+  if width * ratio_height <= height * ratio_width {
+    PhysicalSize::new(bounds.width, (width * ratio_height / ratio_width) as u32)
+  } else {
+    PhysicalSize::new(
+      (height * ratio_width / ratio_height) as u32,
+      bounds.height,
+    )
+  }
+}
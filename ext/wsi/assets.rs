@@ -0,0 +1,101 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use deno_core::{anyhow, error::custom_error};
+use std::{
+  collections::BTreeMap,
+  fs::{self, File},
+  io::{self, Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+// Where Deno.wsi.assets reads from.
+#[derive(Clone, Debug)]
+pub enum WsiAssets {
+  // A directory on disk, when running from source.
+  Directory(PathBuf),
+  // Offsets and lengths of assets embedded in a file, e.g. by `denog compile`.
+  Embedded {
+    file: PathBuf,
+    entries: Arc<BTreeMap<String, (u64, u64)>>,
+  },
+}
+
+impl WsiAssets {
+  // Returns the paths of all assets, sorted.
+  pub fn list(&self) -> Result<Vec<String>, anyhow::Error> {
+    match self {
+      Self::Directory(dir) => {
+        let mut paths = Vec::new();
+        list_directory(dir, "", &mut paths)?;
+        paths.sort();
+        Ok(paths)
+      }
+      Self::Embedded { entries, .. } => Ok(entries.keys().cloned().collect()),
+    }
+  }
+
+  pub fn read(&self, path: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let path = normalize_path(path).ok_or_else(|| not_found(path))?;
+    match self {
+      Self::Directory(dir) => fs::read(dir.join(&path)).map_err(|err| {
+        if err.kind() == io::ErrorKind::NotFound {
+          not_found(&path)
+        } else {
+          err.into()
+        }
+      }),
+      Self::Embedded { file, entries } => {
+        let (offset, len) =
+          *entries.get(&path).ok_or_else(|| not_found(&path))?;
+        let mut file = File::open(file)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut data = vec![0; len as usize];
+        file.read_exact(&mut data)?;
+        Ok(data)
+      }
+    }
+  }
+}
+
+// Asset paths are relative and separated by forward slashes, e.g.
+// "textures/grass.png". Returns `None` for paths that could escape the asset
+// directory.
+fn normalize_path(path: &str) -> Option<String> {
+  let mut components = Vec::new();
+  for component in path.split(['/', '\\']) {
+    match component {
+      "" | "." => {}
+      ".." => return None,
+      component if component.contains(':') => return None,
+      component => components.push(component),
+    }
+  }
+  Some(components.join("/"))
+}
+
+fn list_directory(
+  dir: &Path,
+  prefix: &str,
+  paths: &mut Vec<String>,
+) -> Result<(), io::Error> {
+  for entry in fs::read_dir(dir)? {
+    let entry = entry?;
+    let name = entry.file_name().to_string_lossy().to_string();
+    let path = if prefix.is_empty() {
+      name
+    } else {
+      format!("{prefix}/{name}")
+    };
+    if entry.file_type()?.is_dir() {
+      list_directory(&entry.path(), &path, paths)?;
+    } else {
+      paths.push(path);
+    }
+  }
+  Ok(())
+}
+
+fn not_found(path: &str) -> anyhow::Error {
+  custom_error("NotFound", format!("Asset not found: {path}"))
+}
@@ -0,0 +1,207 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use serde::{Deserialize, Serialize};
+use winit::window::Window;
+
+// What the window system draws behind the transparent parts of a window.
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiWindowBackdrop {
+  None,
+  // Blurred desktop (KWin on X11).
+  Blur,
+  // Blurred desktop with noise and tint (Windows 11).
+  Acrylic,
+  // Desktop wallpaper tinted by the theme (Windows 11).
+  Mica,
+}
+
+pub fn supported_backdrops(window: &Window) -> Vec<WsiWindowBackdrop> {
+  let mut backdrops = vec![WsiWindowBackdrop::None];
+  if dwm::is_supported(window) {
+    backdrops.push(WsiWindowBackdrop::Acrylic);
+    backdrops.push(WsiWindowBackdrop::Mica);
+  }
+  if kwin::is_supported(window) {
+    backdrops.push(WsiWindowBackdrop::Blur);
+  }
+  backdrops
+}
+
+// Returns `false` if the backdrop isn't supported for the window.
+pub fn set_backdrop(window: &Window, backdrop: WsiWindowBackdrop) -> bool {
+  match backdrop {
+    WsiWindowBackdrop::None => {
+      if dwm::is_supported(window) {
+        dwm::set_backdrop(window, dwm::DWMSBT_NONE);
+      }
+      if kwin::is_supported(window) {
+        kwin::set_blur(window, false);
+      }
+      true
+    }
+    WsiWindowBackdrop::Blur => {
+      kwin::is_supported(window) && kwin::set_blur(window, true)
+    }
+    WsiWindowBackdrop::Acrylic => {
+      dwm::set_backdrop(window, dwm::DWMSBT_TRANSIENTWINDOW)
+    }
+    WsiWindowBackdrop::Mica => {
+      dwm::set_backdrop(window, dwm::DWMSBT_MAINWINDOW)
+    }
+  }
+}
+
+// System backdrops, available since Windows 11 22H2.
+#[cfg(windows)]
+mod dwm {
+  use winapi::um::dwmapi::{DwmGetWindowAttribute, DwmSetWindowAttribute};
+  use winit::{platform::windows::WindowExtWindows, window::Window};
+
+  // Not defined by winapi, which predates Windows 11.
+  const DWMWA_SYSTEMBACKDROP_TYPE: u32 = 38;
+  pub const DWMSBT_NONE: u32 = 1;
+  pub const DWMSBT_MAINWINDOW: u32 = 2;
+  pub const DWMSBT_TRANSIENTWINDOW: u32 = 3;
+
+  // Older versions of Windows reject the attribute.
+  pub fn is_supported(window: &Window) -> bool {
+    let mut value = 0u32;
+    // SAFETY: The window handle is valid and the attribute is a DWORD.
+    let result = unsafe {
+      DwmGetWindowAttribute(
+        window.hwnd() as _,
+        DWMWA_SYSTEMBACKDROP_TYPE,
+        &mut value as *mut u32 as _,
+        std::mem::size_of::<u32>() as u32,
+      )
+    };
+    result >= 0
+  }
+
+  pub fn set_backdrop(window: &Window, value: u32) -> bool {
+    // SAFETY: The window handle is valid and the attribute is a DWORD.
+    let result = unsafe {
+      DwmSetWindowAttribute(
+        window.hwnd() as _,
+        DWMWA_SYSTEMBACKDROP_TYPE,
+        &value as *const u32 as _,
+        std::mem::size_of::<u32>() as u32,
+      )
+    };
+    result >= 0
+  }
+}
+
+#[cfg(not(windows))]
+mod dwm {
+  use winit::window::Window;
+
+  pub const DWMSBT_NONE: u32 = 1;
+  pub const DWMSBT_MAINWINDOW: u32 = 2;
+  pub const DWMSBT_TRANSIENTWINDOW: u32 = 3;
+
+  pub fn is_supported(_window: &Window) -> bool {
+    false
+  }
+
+  pub fn set_backdrop(_window: &Window, _value: u32) -> bool {
+    false
+  }
+}
+
+// KWin blurs behind X11 windows that set a property, and creates the property's
+// atom when it starts, so the atom's existence tells whether it's running.
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod kwin {
+  use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+  };
+  use std::ptr;
+  use winit::window::Window;
+  use x11_dl::xlib::{self, Xlib};
+
+  const BLUR_BEHIND_REGION: &[u8] = b"_KDE_NET_WM_BLUR_BEHIND_REGION\0";
+
+  fn xlib_window(
+    window: &Window,
+  ) -> Option<(*mut xlib::Display, xlib::Window)> {
+    match (window.raw_display_handle(), window.raw_window_handle()) {
+      (RawDisplayHandle::Xlib(display), RawWindowHandle::Xlib(handle))
+        if !display.display.is_null() =>
+      {
+        Some((display.display.cast(), handle.window))
+      }
+      _ => None,
+    }
+  }
+
+  pub fn is_supported(window: &Window) -> bool {
+    let (Some((display, _)), Ok(xlib)) = (xlib_window(window), Xlib::open())
+    else {
+      return false;
+    };
+    // SAFETY: The display comes from winit and stays open while the window
+    // exists.
+    let atom = unsafe {
+      (xlib.XInternAtom)(
+        display,
+        BLUR_BEHIND_REGION.as_ptr().cast(),
+        xlib::True,
+      )
+    };
+    atom != 0
+  }
+
+  pub fn set_blur(window: &Window, blur: bool) -> bool {
+    let (Some((display, xwindow)), Ok(xlib)) =
+      (xlib_window(window), Xlib::open())
+    else {
+      return false;
+    };
+    // SAFETY: The display and window come from winit and are valid while the
+    // window exists. An empty region blurs behind the whole window.
+    unsafe {
+      let atom = (xlib.XInternAtom)(
+        display,
+        BLUR_BEHIND_REGION.as_ptr().cast(),
+        xlib::False,
+      );
+      if blur {
+        (xlib.XChangeProperty)(
+          display,
+          xwindow,
+          atom,
+          xlib::XA_CARDINAL,
+          32,
+          xlib::PropModeReplace,
+          ptr::null(),
+          0,
+        );
+      } else {
+        (xlib.XDeleteProperty)(display, xwindow, atom);
+      }
+      (xlib.XFlush)(display);
+    }
+    true
+  }
+}
+
+#[cfg(not(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+)))]
+mod kwin {
+  use winit::window::Window;
+
+  pub fn is_supported(_window: &Window) -> bool {
+    false
+  }
+
+  pub fn set_blur(_window: &Window, _blur: bool) -> bool {
+    false
+  }
+}
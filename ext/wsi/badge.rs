@@ -0,0 +1,189 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use winit::window::Window;
+
+// Show a count, e.g. of unread messages, on the button that the taskbar or
+// dock shows for the window, or remove it with `None` or 0. Returns whether the
+// platform supports it. macOS badges the dock icon, which is shared by all of
+// the program's windows, and Windows draws the count as the window's taskbar
+// overlay icon. On Linux, docks that follow Unity's launcher API badge the
+// program's .desktop file, which is found by its app ID.
+pub fn set_badge(
+  window: &Window,
+  count: Option<u32>,
+  app_id: Option<&str>,
+) -> bool {
+  platform::set_badge(window, count.filter(|&count| count > 0), app_id)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+  use crate::macos;
+  use winit::window::Window;
+
+  pub fn set_badge(
+    _window: &Window,
+    count: Option<u32>,
+    _app_id: Option<&str>,
+  ) -> bool {
+    macos::set_dock_badge(count.map(|count| count.to_string()));
+    true
+  }
+}
+
+#[cfg(windows)]
+mod platform {
+  use crate::{
+    image::WsiRgbaImage,
+    taskbar::{set_overlay, WsiTaskbarOverlay},
+  };
+  use winit::window::Window;
+
+  // Overlay icons are drawn at 16x16 pixels at 100% scaling, so this leaves
+  // room for up to 200%.
+  const SIZE: u32 = 32;
+  // The red of Windows' notification badges.
+  const BACKGROUND: [u8; 4] = [0xc4, 0x2b, 0x1c, u8::MAX];
+  const FOREGROUND: [u8; 4] = [u8::MAX; 4];
+
+  // A 3x5 pixel font for the digits and "+", with a row in the lowest three
+  // bits of each byte.
+  const GLYPHS: [[u8; 5]; 11] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+    [0b000, 0b010, 0b111, 0b010, 0b000],
+  ];
+
+  pub fn set_badge(
+    window: &Window,
+    count: Option<u32>,
+    _app_id: Option<&str>,
+  ) -> bool {
+    let overlay = count.map(|count| {
+      WsiTaskbarOverlay::new(badge_image(count), count.to_string())
+    });
+    set_overlay(window, overlay);
+    true
+  }
+
+  // A red circle with the count in it, or "99+" for larger counts.
+  fn badge_image(count: u32) -> WsiRgbaImage {
+    let text = if count > 99 {
+      "99+".to_string()
+    } else {
+      count.to_string()
+    };
+    let glyphs: Vec<&[u8; 5]> = text
+      .bytes()
+      .map(|c| match c {
+        b'0'..=b'9' => &GLYPHS[(c - b'0') as usize],
+        _ => &GLYPHS[10],
+      })
+      .collect();
+    // Shorter counts are drawn larger. Glyphs are a pixel apart.
+    let scale = match glyphs.len() {
+      1 => 4,
+      2 => 3,
+      _ => 2,
+    };
+    let width = glyphs.len() as u32 * 4 * scale - scale;
+    let height = 5 * scale;
+    let (left, top) = ((SIZE - width) / 2, (SIZE - height) / 2);
+    let is_text = |x: u32, y: u32| {
+      if x < left || y < top {
+        return false;
+      }
+      let (column, row) = ((x - left) / scale, (y - top) / scale);
+      let (glyph, column) = ((column / 4) as usize, column % 4);
+      glyph < glyphs.len()
+        && column < 3
+        && row < 5
+        && (glyphs[glyph][row as usize] >> (2 - column)) & 1 != 0
+    };
+    let radius = SIZE as f32 / 2.0;
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+      for x in 0..SIZE {
+        let dx = x as f32 + 0.5 - radius;
+        let dy = y as f32 + 0.5 - radius;
+        let pixel = if dx * dx + dy * dy > radius * radius {
+          [0; 4]
+        } else if is_text(x, y) {
+          FOREGROUND
+        } else {
+          BACKGROUND
+        };
+        rgba.extend(pixel);
+      }
+    }
+    WsiRgbaImage {
+      width: SIZE,
+      height: SIZE,
+      rgba,
+    }
+  }
+}
+
+// Unity's launcher API is a D-Bus signal, which gdbus can send without a D-Bus
+// library. Docks without the API, like GNOME's own, ignore it.
+// https://wiki.ubuntu.com/Unity/LauncherAPI
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use std::process::{Command, Stdio};
+  use winit::window::Window;
+
+  pub fn set_badge(
+    _window: &Window,
+    count: Option<u32>,
+    app_id: Option<&str>,
+  ) -> bool {
+    let Some(app_id) = app_id else {
+      return false;
+    };
+    let properties = format!(
+      "{{'count': <int64 {}>, 'count-visible': <{}>}}",
+      count.unwrap_or(0),
+      count.is_some()
+    );
+    Command::new("gdbus")
+      .args(["emit", "--session", "--object-path", "/"])
+      .args(["--signal", "com.canonical.Unity.LauncherEntry.Update"])
+      .arg(format!("application://{app_id}.desktop"))
+      .arg(properties)
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .status()
+      .map_or(false, |status| status.success())
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use winit::window::Window;
+
+  pub fn set_badge(
+    _window: &Window,
+    _count: Option<u32>,
+    _app_id: Option<&str>,
+  ) -> bool {
+    false
+  }
+}
@@ -0,0 +1,95 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{
+  content_protection::can_detect_capture, cursor::WsiCursorGrabMode,
+};
+use serde::Serialize;
+use winit::event_loop::EventLoopWindowTarget;
+
+// The window system that the event loop is connected to.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiBackend {
+  #[cfg_attr(not(windows), allow(dead_code))]
+  Win32,
+  #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+  Cocoa,
+  #[cfg_attr(any(windows, target_os = "macos"), allow(dead_code))]
+  X11,
+  #[cfg_attr(any(windows, target_os = "macos"), allow(dead_code))]
+  Wayland,
+}
+
+// Which optional features the window system supports, so that programs can
+// branch on them instead of trying them and catching the errors.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiCapabilities {
+  backend: WsiBackend,
+  // The modes that `WSIWindow.setCursorGrabMode()` accepts.
+  cursor_grab_modes: Vec<WsiCursorGrabMode>,
+  transparency: bool,
+  drag_resize: bool,
+  ime: bool,
+  // Whether `WSIWindow.setContentProtected()` has an effect. Elsewhere it
+  // does nothing.
+  content_protection: bool,
+  // Whether protected windows get `window-capture-detected` events.
+  capture_detection: bool,
+}
+
+pub fn capabilities(
+  window_target: &EventLoopWindowTarget<()>,
+) -> WsiCapabilities {
+  use WsiCursorGrabMode as Mode;
+  let backend = backend(window_target);
+  // Winit can confine the cursor everywhere but on macOS and lock it on
+  // macOS and Wayland. Locked-or-confined works everywhere.
+  let cursor_grab_modes = match backend {
+    WsiBackend::Win32 | WsiBackend::X11 => {
+      vec![Mode::None, Mode::Confined, Mode::LockedOrConfined]
+    }
+    WsiBackend::Cocoa => vec![Mode::None, Mode::Locked, Mode::LockedOrConfined],
+    WsiBackend::Wayland => vec![
+      Mode::None,
+      Mode::Confined,
+      Mode::Locked,
+      Mode::LockedOrConfined,
+    ],
+  };
+  WsiCapabilities {
+    backend,
+    cursor_grab_modes,
+    transparency: true,
+    drag_resize: backend != WsiBackend::Cocoa,
+    ime: true,
+    content_protection: matches!(
+      backend,
+      WsiBackend::Win32 | WsiBackend::Cocoa
+    ),
+    capture_detection: backend == WsiBackend::Cocoa && can_detect_capture(),
+  }
+}
+
+#[cfg(windows)]
+fn backend(_window_target: &EventLoopWindowTarget<()>) -> WsiBackend {
+  WsiBackend::Win32
+}
+
+#[cfg(target_os = "macos")]
+fn backend(_window_target: &EventLoopWindowTarget<()>) -> WsiBackend {
+  WsiBackend::Cocoa
+}
+
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+fn backend(window_target: &EventLoopWindowTarget<()>) -> WsiBackend {
+  use winit::platform::wayland::EventLoopWindowTargetExtWayland;
+  if window_target.is_wayland() {
+    WsiBackend::Wayland
+  } else {
+    WsiBackend::X11
+  }
+}
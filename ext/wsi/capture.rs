@@ -0,0 +1,336 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::image::WsiRgbaImage;
+use winit::window::Window;
+
+// What a window's client area shows, as composited by the window system, so
+// that it includes content drawn with the GPU. Returns `None` where windows
+// can't be captured, which includes Wayland, where clients can't read back any
+// window, and minimized windows.
+pub fn capture_window(window: &Window) -> Option<WsiRgbaImage> {
+  let size = window.inner_size();
+  if size.width == 0 || size.height == 0 {
+    return None;
+  }
+  platform::capture_window(window, (size.width, size.height))
+}
+
+// PrintWindow asks the window to draw itself into a bitmap. Without
+// PW_RENDERFULLCONTENT, DirectX and DirectComposition content comes out black.
+// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-printwindow
+#[cfg(windows)]
+mod platform {
+  use crate::image::WsiRgbaImage;
+  use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+  use std::{mem, ptr, slice};
+  use winapi::{
+    shared::windef::HWND,
+    um::{
+      wingdi::{
+        CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject,
+        SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+      },
+      winuser::{
+        GetDC, PrintWindow, ReleaseDC, PW_CLIENTONLY, PW_RENDERFULLCONTENT,
+      },
+    },
+  };
+  use winit::window::Window;
+
+  pub fn capture_window(
+    window: &Window,
+    (width, height): (u32, u32),
+  ) -> Option<WsiRgbaImage> {
+    let RawWindowHandle::Win32(handle) = window.raw_window_handle() else {
+      return None;
+    };
+    let hwnd: HWND = handle.hwnd.cast();
+    // SAFETY: The window comes from winit and is valid while it exists. The
+    // bitmap has room for the whole client area, and the GDI objects are
+    // released after use.
+    let mut pixels = unsafe {
+      let window_dc = GetDC(hwnd);
+      let dc = CreateCompatibleDC(window_dc);
+      let mut info: BITMAPINFO = mem::zeroed();
+      info.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as u32;
+      info.bmiHeader.biWidth = width as i32;
+      // Negative heights make the rows go from top to bottom.
+      info.bmiHeader.biHeight = -(height as i32);
+      info.bmiHeader.biPlanes = 1;
+      info.bmiHeader.biBitCount = 32;
+      info.bmiHeader.biCompression = BI_RGB;
+      let mut bits = ptr::null_mut();
+      let bitmap = CreateDIBSection(
+        dc,
+        &info,
+        DIB_RGB_COLORS,
+        &mut bits,
+        ptr::null_mut(),
+        0,
+      );
+      let mut pixels = None;
+      if !bitmap.is_null() {
+        let previous = SelectObject(dc, bitmap.cast());
+        let flags = PW_CLIENTONLY | PW_RENDERFULLCONTENT;
+        if PrintWindow(hwnd, dc, flags) != 0 {
+          let len = width as usize * height as usize * 4;
+          pixels = Some(slice::from_raw_parts(bits.cast::<u8>(), len).to_vec());
+        }
+        SelectObject(dc, previous);
+        DeleteObject(bitmap.cast());
+      }
+      DeleteDC(dc);
+      ReleaseDC(hwnd, window_dc);
+      pixels?
+    };
+    // The pixels are BGR, and the fourth byte isn't alpha.
+    for pixel in pixels.chunks_exact_mut(4) {
+      pixel.swap(0, 2);
+      pixel[3] = u8::MAX;
+    }
+    Some(WsiRgbaImage {
+      width,
+      height,
+      rgba: pixels,
+    })
+  }
+}
+
+// The window server keeps the image of each window. The content view's rect
+// is captured, so the title bar is left out.
+// https://developer.apple.com/documentation/coregraphics/1454852-cgwindowlistcreateimage
+#[cfg(target_os = "macos")]
+mod platform {
+  use crate::{image::WsiRgbaImage, macos};
+  use std::ffi::c_void;
+  use winit::window::Window;
+
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct CGPoint {
+    x: f64,
+    y: f64,
+  }
+
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct CGSize {
+    width: f64,
+    height: f64,
+  }
+
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct CGRect {
+    origin: CGPoint,
+    size: CGSize,
+  }
+
+  const K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW: u32 = 1 << 3;
+  const K_CG_WINDOW_IMAGE_SHOULD_BE_OPAQUE: u32 = 1 << 1;
+  const K_CG_WINDOW_IMAGE_BEST_RESOLUTION: u32 = 1 << 3;
+  const K_CG_IMAGE_ALPHA_NONE_SKIP_LAST: u32 = 5;
+
+  #[link(name = "CoreGraphics", kind = "framework")]
+  extern "C" {
+    fn CGWindowListCreateImage(
+      bounds: CGRect,
+      list_option: u32,
+      window_id: u32,
+      image_option: u32,
+    ) -> *mut c_void;
+    fn CGImageGetWidth(image: *mut c_void) -> usize;
+    fn CGImageGetHeight(image: *mut c_void) -> usize;
+    fn CGImageRelease(image: *mut c_void);
+    fn CGColorSpaceCreateDeviceRGB() -> *mut c_void;
+    fn CGColorSpaceRelease(space: *mut c_void);
+    fn CGBitmapContextCreate(
+      data: *mut c_void,
+      width: usize,
+      height: usize,
+      bits_per_component: usize,
+      bytes_per_row: usize,
+      space: *mut c_void,
+      bitmap_info: u32,
+    ) -> *mut c_void;
+    fn CGContextDrawImage(
+      context: *mut c_void,
+      rect: CGRect,
+      image: *mut c_void,
+    );
+    fn CGContextRelease(context: *mut c_void);
+  }
+
+  pub fn capture_window(
+    window: &Window,
+    (width, height): (u32, u32),
+  ) -> Option<WsiRgbaImage> {
+    let window_number = macos::window_number(window)?;
+    // Window server coordinates are in points from the top left of the main
+    // display, like winit's logical positions.
+    let scale_factor = window.scale_factor();
+    let position = window
+      .inner_position()
+      .ok()?
+      .to_logical::<f64>(scale_factor);
+    let bounds = CGRect {
+      origin: CGPoint {
+        x: position.x,
+        y: position.y,
+      },
+      size: CGSize {
+        width: width as f64 / scale_factor,
+        height: height as f64 / scale_factor,
+      },
+    };
+    // SAFETY: The image, color space and context are released after use, and
+    // the pixels have room for the whole image.
+    unsafe {
+      let image = CGWindowListCreateImage(
+        bounds,
+        K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW,
+        window_number,
+        K_CG_WINDOW_IMAGE_SHOULD_BE_OPAQUE | K_CG_WINDOW_IMAGE_BEST_RESOLUTION,
+      );
+      if image.is_null() {
+        return None;
+      }
+      let (width, height) = (CGImageGetWidth(image), CGImageGetHeight(image));
+      let mut rgba = vec![0u8; width * height * 4];
+      let space = CGColorSpaceCreateDeviceRGB();
+      let context = CGBitmapContextCreate(
+        rgba.as_mut_ptr().cast(),
+        width,
+        height,
+        8,
+        width * 4,
+        space,
+        K_CG_IMAGE_ALPHA_NONE_SKIP_LAST,
+      );
+      CGColorSpaceRelease(space);
+      if !context.is_null() {
+        let rect = CGRect {
+          origin: CGPoint { x: 0.0, y: 0.0 },
+          size: CGSize {
+            width: width as f64,
+            height: height as f64,
+          },
+        };
+        CGContextDrawImage(context, rect, image);
+        CGContextRelease(context);
+      }
+      CGImageRelease(image);
+      if context.is_null() || width == 0 || height == 0 {
+        return None;
+      }
+      for pixel in rgba.chunks_exact_mut(4) {
+        pixel[3] = u8::MAX;
+      }
+      Some(WsiRgbaImage {
+        width: width as u32,
+        height: height as u32,
+        rgba,
+      })
+    }
+  }
+}
+
+// XGetImage reads a window's contents from the X server. With a compositing
+// manager each window has its own offscreen image; without one, the parts of
+// the window covered by other windows are undefined.
+// https://www.x.org/releases/current/doc/man/man3/XGetImage.3.xhtml
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use crate::image::WsiRgbaImage;
+  use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+  };
+  use std::os::raw::c_ulong;
+  use winit::window::Window;
+  use x11_dl::xlib::{self, Xlib};
+
+  pub fn capture_window(
+    window: &Window,
+    (width, height): (u32, u32),
+  ) -> Option<WsiRgbaImage> {
+    let (RawDisplayHandle::Xlib(display), RawWindowHandle::Xlib(handle)) =
+      (window.raw_display_handle(), window.raw_window_handle())
+    else {
+      return None;
+    };
+    if display.display.is_null() {
+      return None;
+    }
+    let xlib = Xlib::open().ok()?;
+    // SAFETY: The display and window come from winit and are valid while the
+    // window exists. The image is destroyed after use.
+    unsafe {
+      let image = (xlib.XGetImage)(
+        display.display.cast(),
+        handle.window,
+        0,
+        0,
+        width,
+        height,
+        !0,
+        xlib::ZPixmap,
+      );
+      if image.is_null() {
+        return None;
+      }
+      let (red, green, blue) =
+        ((*image).red_mask, (*image).green_mask, (*image).blue_mask);
+      let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+      for y in 0..height as i32 {
+        for x in 0..width as i32 {
+          let pixel = (xlib.XGetPixel)(image, x, y);
+          rgba.extend([
+            channel(pixel, red),
+            channel(pixel, green),
+            channel(pixel, blue),
+            u8::MAX,
+          ]);
+        }
+      }
+      (xlib.XDestroyImage)(image);
+      Some(WsiRgbaImage {
+        width,
+        height,
+        rgba,
+      })
+    }
+  }
+
+  // Scales a channel of a TrueColor pixel to 8 bits, whatever its depth.
+  fn channel(pixel: c_ulong, mask: c_ulong) -> u8 {
+    if mask == 0 {
+      return 0;
+    }
+    let max = mask >> mask.trailing_zeros();
+    let value = (pixel & mask) >> mask.trailing_zeros();
+    (value * u8::MAX as c_ulong / max) as u8
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use crate::image::WsiRgbaImage;
+  use winit::window::Window;
+
+  pub fn capture_window(
+    _window: &Window,
+    _size: (u32, u32),
+  ) -> Option<WsiRgbaImage> {
+    None
+  }
+}
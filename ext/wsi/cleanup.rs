@@ -0,0 +1,145 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{
+  cursor::{forget_cursor_grab, set_cursor_confinement},
+  display::restore_gamma,
+  request::{ExecuteRequest, Request, RequestSender},
+  shortcuts::set_system_shortcuts_inhibited,
+};
+use std::{
+  collections::HashMap,
+  panic,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc as std_mpsc, Mutex, Once,
+  },
+  thread::{self, ThreadId},
+  time::{Duration, Instant},
+};
+use winit::{
+  event_loop::EventLoopProxy,
+  window::{CursorGrabMode, Window},
+};
+
+// How long a dying thread waits for the event loop to restore the desktop.
+const RESTORE_TIMEOUT: Duration = Duration::from_millis(500);
+
+static EVENT_LOOP: Mutex<Option<EventLoopHandle>> = Mutex::new(None);
+static RESTORED: AtomicBool = AtomicBool::new(false);
+static INSTALL_PANIC_HOOK: Once = Once::new();
+
+// How another thread reaches the event loop thread while the process dies.
+struct EventLoopHandle {
+  thread: ThreadId,
+  // The thread the runtime runs on, once it's connected.
+  runtime_thread: Option<ThreadId>,
+  request_tx: RequestSender,
+  event_loop_proxy: EventLoopProxy<()>,
+}
+
+// A program that dies without destroying its windows can leave the desktop
+// with a grabbed or hidden cursor, a clipped cursor on Windows, inhibited
+// shortcuts or changed gamma, which some platforms keep after the process is
+// gone. Have a panic on the runtime thread, which takes the program down,
+// restore them first. Panics on other threads can be caught, so they leave the
+// desktop alone. Call this on the event loop thread, where the windows are.
+pub(crate) fn install_restore_hook(
+  request_tx: RequestSender,
+  event_loop_proxy: EventLoopProxy<()>,
+) {
+  *EVENT_LOOP.lock().unwrap() = Some(EventLoopHandle {
+    thread: thread::current().id(),
+    runtime_thread: None,
+    request_tx,
+    event_loop_proxy,
+  });
+  INSTALL_PANIC_HOOK.call_once(|| {
+    let panic_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+      if is_runtime_thread() {
+        restore_desktop();
+      }
+      panic_hook(panic_info);
+    }));
+  });
+}
+
+// Record the calling thread as the runtime's, whose panics restore the
+// desktop.
+pub(crate) fn set_runtime_thread() {
+  if let Some(event_loop) = &mut *EVENT_LOOP.lock().unwrap() {
+    event_loop.runtime_thread = Some(thread::current().id());
+  }
+}
+
+fn is_runtime_thread() -> bool {
+  let Ok(event_loop) = EVENT_LOOP.lock() else {
+    return false;
+  };
+  event_loop
+    .as_ref()
+    .and_then(|event_loop| event_loop.runtime_thread)
+    == Some(thread::current().id())
+}
+
+// Release cursor grabs, show the cursor, leave fullscreen and put back what
+// else windows changed, before the process exits. Call this on the way out
+// of any thread but the event loop's, e.g. when the runtime exits with an
+// uncaught exception. It only has an effect once, and gives up after
+// `RESTORE_TIMEOUT` if the event loop is stuck.
+pub fn restore_desktop() {
+  let (request_tx, event_loop_proxy) = {
+    let Ok(event_loop) = EVENT_LOOP.lock() else {
+      return;
+    };
+    let Some(event_loop) = &*event_loop else {
+      return;
+    };
+    // The driver has the windows borrowed, so a panic on the event loop
+    // thread leaves them to the window system.
+    if event_loop.thread == thread::current().id() {
+      return;
+    }
+    (
+      event_loop.request_tx.clone(),
+      event_loop.event_loop_proxy.clone(),
+    )
+  };
+  if RESTORED.swap(true, Ordering::SeqCst) {
+    return;
+  }
+  let deadline = Instant::now() + RESTORE_TIMEOUT;
+  let (done_tx, done_rx) = std_mpsc::channel();
+  let request =
+    Request::Execute(ExecuteRequest::new(Box::new(move |_, windows| {
+      restore_windows(windows);
+      let _ = done_tx.send(());
+    })));
+  // The runtime may have left a request for the event loop to take first.
+  if request_tx.send_timeout(request, RESTORE_TIMEOUT).is_err() {
+    // Nothing was restored, so let a later call try again.
+    RESTORED.store(false, Ordering::SeqCst);
+    return;
+  }
+  // The event loop might be waiting for an event before it takes requests.
+  let _ = event_loop_proxy.send_event(());
+  let _ =
+    done_rx.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+}
+
+// Restore the desktop on the event loop thread, e.g. when the driver exits the
+// process itself.
+pub(crate) fn restore_windows(windows: &HashMap<u64, Window>) {
+  RESTORED.store(true, Ordering::SeqCst);
+  for (&wid, window) in windows {
+    forget_cursor_grab(wid);
+    let _ = set_cursor_confinement(wid, window, None);
+    let _ = window.set_cursor_grab(CursorGrabMode::None);
+    window.set_cursor_visible(true);
+    if window.fullscreen().is_some() {
+      window.set_fullscreen(None);
+    }
+    set_system_shortcuts_inhibited(wid, window, false);
+    restore_gamma(wid, window);
+  }
+}
@@ -0,0 +1,183 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{
+  event::WsiEvent,
+  input::{WsiButtonState, WsiMouseButton},
+};
+use serde::Deserialize;
+use std::{
+  cell::Cell,
+  collections::HashMap,
+  time::{Duration, Instant},
+};
+
+// How far apart in time and space presses can be to count as one double or
+// triple click, in milliseconds and physical pixels. Missing values follow the
+// OS settings.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiClickOptions {
+  pub interval: Option<u64>,
+  pub distance: Option<f64>,
+}
+
+thread_local! {
+  // The options set by `set_click_options`, on the event loop thread.
+  static CLICK_OPTIONS: Cell<WsiClickOptions> =
+    Cell::new(WsiClickOptions::default());
+}
+
+pub fn set_click_options(options: WsiClickOptions) {
+  CLICK_OPTIONS.with(|click_options| click_options.set(options));
+}
+
+// What winit doesn't report with mouse button events: how many times in a row
+// the button was clicked, counting presses of the same button in the same
+// window that are close enough in time and space.
+#[derive(Default)]
+pub struct ClickCounter {
+  cursor_positions: HashMap<u64, (f64, f64)>,
+  last_press: Option<Press>,
+}
+
+struct Press {
+  window: u64,
+  button: WsiMouseButton,
+  time: Instant,
+  position: Option<(f64, f64)>,
+  count: u32,
+}
+
+impl ClickCounter {
+  // Fill in the click count of mouse button events. Releases have the count
+  // of the press they end.
+  pub fn update(&mut self, event: &mut WsiEvent) {
+    match event {
+      WsiEvent::CursorMoved {
+        window, position, ..
+      } => {
+        self.cursor_positions.insert(*window, *position);
+      }
+      WsiEvent::CursorLeft { window, .. } => {
+        self.cursor_positions.remove(window);
+      }
+      WsiEvent::MouseButton {
+        window,
+        button,
+        state: WsiButtonState::Pressed,
+        click_count,
+        ..
+      } => {
+        let time = Instant::now();
+        let position = self.cursor_positions.get(window).copied();
+        let count = match &self.last_press {
+          Some(last)
+            if last.window == *window
+              && last.button == *button
+              && is_repeat(last, time, position) =>
+          {
+            last.count + 1
+          }
+          _ => 1,
+        };
+        self.last_press = Some(Press {
+          window: *window,
+          button: *button,
+          time,
+          position,
+          count,
+        });
+        *click_count = count;
+      }
+      WsiEvent::MouseButton {
+        window,
+        button,
+        state: WsiButtonState::Released,
+        click_count,
+        ..
+      } => {
+        *click_count = match &self.last_press {
+          Some(last) if last.window == *window && last.button == *button => {
+            last.count
+          }
+          _ => 1,
+        };
+      }
+      _ => {}
+    }
+  }
+}
+
+// Whether a press continues the clicks that ended with the last press.
+fn is_repeat(
+  last: &Press,
+  time: Instant,
+  position: Option<(f64, f64)>,
+) -> bool {
+  let options = CLICK_OPTIONS.with(Cell::get);
+  let interval = options
+    .interval
+    .map(Duration::from_millis)
+    .unwrap_or_else(platform::double_click_interval);
+  let distance = options
+    .distance
+    .unwrap_or_else(platform::double_click_distance);
+  let near = match (last.position, position) {
+    (Some((last_x, last_y)), Some((x, y))) => {
+      (x - last_x).abs() <= distance && (y - last_y).abs() <= distance
+    }
+    _ => true,
+  };
+  time.duration_since(last.time) <= interval && near
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getdoubleclicktime
+#[cfg(windows)]
+mod platform {
+  use std::time::Duration;
+  use winapi::um::winuser::{
+    GetDoubleClickTime, GetSystemMetrics, SM_CXDOUBLECLK, SM_CYDOUBLECLK,
+  };
+
+  pub fn double_click_interval() -> Duration {
+    // SAFETY: GetDoubleClickTime has no preconditions.
+    Duration::from_millis(unsafe { GetDoubleClickTime() } as u64)
+  }
+
+  // The metrics are the size of the rectangle centered on the first click
+  // that the second one must be in.
+  pub fn double_click_distance() -> f64 {
+    // SAFETY: GetSystemMetrics has no preconditions.
+    let (width, height) = unsafe {
+      (
+        GetSystemMetrics(SM_CXDOUBLECLK),
+        GetSystemMetrics(SM_CYDOUBLECLK),
+      )
+    };
+    width.max(height) as f64 / 2.0
+  }
+}
+
+// Windows' default distance, since macOS has no setting for it.
+#[cfg(target_os = "macos")]
+mod platform {
+  pub use crate::macos::double_click_interval;
+
+  pub fn double_click_distance() -> f64 {
+    2.0
+  }
+}
+
+// Where there's no OS setting to read, Windows' default settings.
+#[cfg(not(any(windows, target_os = "macos")))]
+mod platform {
+  use std::time::Duration;
+
+  pub fn double_click_interval() -> Duration {
+    Duration::from_millis(500)
+  }
+
+  pub fn double_click_distance() -> f64 {
+    2.0
+  }
+}
@@ -0,0 +1,249 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use winit::{monitor::MonitorHandle, window::Window};
+
+// The bytes of the ICC profile that the system uses for a monitor, or `None` if
+// it has none or the platform doesn't tell. The window is only needed to reach
+// the X server. Wayland has no stable color management protocol yet, so there
+// are no profiles there.
+pub fn color_profile(
+  window: &Window,
+  monitor: &MonitorHandle,
+) -> Option<Vec<u8>> {
+  platform::color_profile(window, monitor).filter(|profile| !profile.is_empty())
+}
+
+// GDI names the profile file associated with a display device.
+// https://learn.microsoft.com/en-us/windows/win32/api/wingdi/nf-wingdi-geticmprofilew
+#[cfg(windows)]
+mod platform {
+  use std::{
+    ffi::OsString,
+    fs, iter,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    ptr,
+  };
+  use winapi::{
+    shared::minwindef::{DWORD, MAX_PATH},
+    um::wingdi::{CreateDCW, DeleteDC, GetICMProfileW},
+  };
+  use winit::{
+    monitor::MonitorHandle, platform::windows::MonitorHandleExtWindows,
+    window::Window,
+  };
+
+  pub fn color_profile(
+    _window: &Window,
+    monitor: &MonitorHandle,
+  ) -> Option<Vec<u8>> {
+    // The monitor's native ID is its display device name, like
+    // `\\.\DISPLAY1`.
+    let device: Vec<u16> = OsString::from(monitor.native_id())
+      .encode_wide()
+      .chain(iter::once(0))
+      .collect();
+    let mut path = [0u16; MAX_PATH];
+    let mut len = path.len() as DWORD;
+    // SAFETY: The device name is null-terminated, `len` is the length of
+    // `path`, and the DC is deleted after use.
+    let found = unsafe {
+      let dc =
+        CreateDCW(device.as_ptr(), ptr::null(), ptr::null(), ptr::null());
+      if dc.is_null() {
+        return None;
+      }
+      let found = GetICMProfileW(dc, &mut len, path.as_mut_ptr());
+      DeleteDC(dc);
+      found != 0
+    };
+    if !found {
+      return None;
+    }
+    let len = path.iter().position(|&c| c == 0).unwrap_or(path.len());
+    fs::read(OsString::from_wide(&path[..len])).ok()
+  }
+}
+
+// Every display has a color space, whose ICC data is the display's profile.
+// https://developer.apple.com/documentation/coregraphics/1454645-cgdisplaycopycolorspace
+#[cfg(target_os = "macos")]
+mod platform {
+  use std::{ffi::c_void, slice};
+  use winit::{
+    monitor::MonitorHandle, platform::macos::MonitorHandleExtMacOS,
+    window::Window,
+  };
+
+  #[link(name = "CoreGraphics", kind = "framework")]
+  extern "C" {
+    fn CGDisplayCopyColorSpace(display: u32) -> *mut c_void;
+    fn CGColorSpaceCopyICCData(space: *mut c_void) -> *const c_void;
+    fn CGColorSpaceRelease(space: *mut c_void);
+  }
+
+  #[link(name = "CoreFoundation", kind = "framework")]
+  extern "C" {
+    fn CFDataGetLength(data: *const c_void) -> isize;
+    fn CFDataGetBytePtr(data: *const c_void) -> *const u8;
+    fn CFRelease(object: *const c_void);
+  }
+
+  pub fn color_profile(
+    _window: &Window,
+    monitor: &MonitorHandle,
+  ) -> Option<Vec<u8>> {
+    // SAFETY: The monitor's native ID is its display ID, and the color space
+    // and data are released after use.
+    unsafe {
+      let space = CGDisplayCopyColorSpace(monitor.native_id());
+      if space.is_null() {
+        return None;
+      }
+      let data = CGColorSpaceCopyICCData(space);
+      CGColorSpaceRelease(space);
+      if data.is_null() {
+        return None;
+      }
+      let len = CFDataGetLength(data) as usize;
+      let profile = slice::from_raw_parts(CFDataGetBytePtr(data), len).to_vec();
+      CFRelease(data);
+      Some(profile)
+    }
+  }
+}
+
+// Color managers like colord set each RandR output's `_ICC_PROFILE` property,
+// and the root window's for the first monitor.
+// https://www.freedesktop.org/wiki/Specifications/icc_profiles_in_x_spec/
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use crate::display::crtc_output;
+  use raw_window_handle::{HasRawDisplayHandle, RawDisplayHandle};
+  use std::{
+    os::raw::{c_int, c_long, c_uchar, c_ulong},
+    ptr, slice,
+  };
+  use winit::{
+    monitor::MonitorHandle, platform::x11::MonitorHandleExtX11, window::Window,
+  };
+  use x11_dl::{
+    xlib::{self, Xlib},
+    xrandr::Xrandr,
+  };
+
+  pub fn color_profile(
+    window: &Window,
+    monitor: &MonitorHandle,
+  ) -> Option<Vec<u8>> {
+    let RawDisplayHandle::Xlib(display) = window.raw_display_handle() else {
+      return None;
+    };
+    if display.display.is_null() {
+      return None;
+    }
+    let display: *mut xlib::Display = display.display.cast();
+    let xlib = Xlib::open().ok()?;
+    let xrandr = Xrandr::open().ok()?;
+    let is_first = window.available_monitors().next().as_ref() == Some(monitor);
+    // SAFETY: The display comes from winit and is valid while the event loop
+    // exists.
+    unsafe {
+      let atom = (xlib.XInternAtom)(
+        display,
+        b"_ICC_PROFILE\0".as_ptr().cast(),
+        xlib::True,
+      );
+      // The atom only exists once a profile has been set.
+      if atom == 0 {
+        return None;
+      }
+      let crtc = monitor.native_id() as c_ulong;
+      let output = crtc_output(&xlib, &xrandr, display, crtc)?;
+      let profile =
+        read_property(&xlib, |kind, format, items, after, value| {
+          (xrandr.XRRGetOutputProperty)(
+            display,
+            output,
+            atom,
+            0,
+            c_long::from(i32::MAX),
+            xlib::False,
+            xlib::False,
+            xlib::AnyPropertyType as c_ulong,
+            kind,
+            format,
+            items,
+            after,
+            value,
+          )
+        });
+      if profile.is_some() || !is_first {
+        return profile;
+      }
+      let root = (xlib.XDefaultRootWindow)(display);
+      read_property(&xlib, |kind, format, items, after, value| {
+        (xlib.XGetWindowProperty)(
+          display,
+          root,
+          atom,
+          0,
+          c_long::from(i32::MAX),
+          xlib::False,
+          xlib::AnyPropertyType as c_ulong,
+          kind,
+          format,
+          items,
+          after,
+          value,
+        )
+      })
+    }
+  }
+
+  // Calls XGetWindowProperty or XRRGetOutputProperty, which return values the
+  // same way, and copies out a value of bytes.
+  unsafe fn read_property(
+    xlib: &Xlib,
+    get: impl FnOnce(
+      *mut c_ulong,
+      *mut c_int,
+      *mut c_ulong,
+      *mut c_ulong,
+      *mut *mut c_uchar,
+    ) -> c_int,
+  ) -> Option<Vec<u8>> {
+    let (mut kind, mut format, mut items, mut after) = (0, 0, 0, 0);
+    let mut value = ptr::null_mut();
+    let status =
+      get(&mut kind, &mut format, &mut items, &mut after, &mut value);
+    if value.is_null() {
+      return None;
+    }
+    let bytes = (status == xlib::Success as c_int && format == 8)
+      .then(|| slice::from_raw_parts(value, items as usize).to_vec());
+    (xlib.XFree)(value.cast());
+    bytes
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use winit::{monitor::MonitorHandle, window::Window};
+
+  pub fn color_profile(
+    _window: &Window,
+    _monitor: &MonitorHandle,
+  ) -> Option<Vec<u8>> {
+    None
+  }
+}
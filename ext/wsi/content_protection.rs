@@ -0,0 +1,144 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::event::WsiEvent;
+use std::{
+  collections::{HashMap, HashSet},
+  time::{Duration, Instant},
+};
+use winit::window::Window;
+
+// How often screen capture is checked for, at most.
+const CAPTURE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Whether the window system keeps the window out of screenshots and screen
+// recordings. Returns `None` where winit's `set_content_protected` does
+// nothing, i.e. on X11 and Wayland.
+pub fn is_content_protected(window: &Window) -> Option<bool> {
+  platform::is_content_protected(window)
+}
+
+// Whether this system tells when the screen is being captured.
+pub fn can_detect_capture() -> bool {
+  platform::screen_captured().is_some()
+}
+
+// Winit doesn't report screen capture, so the driver checks for it on events
+// at most once per `CAPTURE_POLL_INTERVAL`. Each content protected window is
+// told once per capture, including windows that become protected while it
+// lasts. Only macOS reports capture, so elsewhere there are no events.
+#[derive(Default)]
+pub struct CaptureWatcher {
+  // The windows that were told about the current capture, if any.
+  notified: HashSet<u64>,
+  last_poll: Option<Instant>,
+}
+
+impl CaptureWatcher {
+  pub fn update(&mut self, windows: &HashMap<u64, Window>) -> Vec<WsiEvent> {
+    let due = self.last_poll.map_or(true, |last_poll| {
+      last_poll.elapsed() >= CAPTURE_POLL_INTERVAL
+    });
+    if !due || windows.is_empty() {
+      return vec![];
+    }
+    self.last_poll = Some(Instant::now());
+    if platform::screen_captured() != Some(true) {
+      self.notified.clear();
+      return vec![];
+    }
+    let mut events = Vec::new();
+    for (&wid, window) in windows {
+      if is_content_protected(window) == Some(true) && self.notified.insert(wid)
+      {
+        events.push(WsiEvent::WindowCaptureDetected { window: wid });
+      }
+    }
+    events
+  }
+}
+
+// Protected windows have a sharing type of `NSWindowSharingNone`. Whether the
+// screen is being recorded or shared comes from `CGSIsScreenWatcherPresent`,
+// which is undocumented, but is what screen sharing apps themselves are
+// detected by. It's looked up when it's needed rather than linked, so that
+// the binary still loads on a release of macOS without it.
+// https://developer.apple.com/documentation/appkit/nswindow/1419729-sharingtype
+#[cfg(target_os = "macos")]
+mod platform {
+  use objc::{msg_send, runtime::Object, sel, sel_impl};
+  use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+  use std::mem;
+  use winit::window::Window;
+
+  const NS_WINDOW_SHARING_NONE: usize = 0;
+
+  type ScreenWatcherPresentFn = unsafe extern "C" fn() -> bool;
+
+  pub fn is_content_protected(window: &Window) -> Option<bool> {
+    let RawWindowHandle::AppKit(handle) = window.raw_window_handle() else {
+      return None;
+    };
+    let ns_window: *mut Object = handle.ns_window.cast();
+    // SAFETY: The window comes from winit and is valid while it exists.
+    let sharing_type: usize = unsafe { msg_send![ns_window, sharingType] };
+    Some(sharing_type == NS_WINDOW_SHARING_NONE)
+  }
+
+  pub fn screen_captured() -> Option<bool> {
+    // SAFETY: The name is NUL-terminated. CoreGraphics is loaded along with
+    // AppKit.
+    let symbol = unsafe {
+      libc::dlsym(
+        libc::RTLD_DEFAULT,
+        b"CGSIsScreenWatcherPresent\0".as_ptr().cast(),
+      )
+    };
+    if symbol.is_null() {
+      return None;
+    }
+    // SAFETY: The function has taken no arguments and returned a bool in
+    // every release of macOS that has it, and only reads session state.
+    unsafe {
+      let screen_watcher_present: ScreenWatcherPresentFn =
+        mem::transmute(symbol);
+      Some(screen_watcher_present())
+    }
+  }
+}
+
+// Protected windows have a display affinity other than `WDA_NONE`. Windows
+// doesn't tell apps when they're being captured.
+// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getwindowdisplayaffinity
+#[cfg(windows)]
+mod platform {
+  use winapi::{
+    shared::{minwindef::DWORD, windef::HWND},
+    um::winuser::{GetWindowDisplayAffinity, WDA_NONE},
+  };
+  use winit::{platform::windows::WindowExtWindows, window::Window};
+
+  pub fn is_content_protected(window: &Window) -> Option<bool> {
+    let mut affinity: DWORD = WDA_NONE;
+    // SAFETY: The window handle is valid and `affinity` is a DWORD.
+    let ok =
+      unsafe { GetWindowDisplayAffinity(window.hwnd() as HWND, &mut affinity) };
+    (ok != 0).then_some(affinity != WDA_NONE)
+  }
+
+  pub fn screen_captured() -> Option<bool> {
+    None
+  }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod platform {
+  use winit::window::Window;
+
+  pub fn is_content_protected(_window: &Window) -> Option<bool> {
+    None
+  }
+
+  pub fn screen_captured() -> Option<bool> {
+    None
+  }
+}
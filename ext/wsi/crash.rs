@@ -0,0 +1,167 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crash_handler::{
+  make_crash_event, CrashContext, CrashEventResult, CrashHandler,
+};
+use deno_core::{anyhow, serde_json};
+use minidumper::{Client, LoopAction, MinidumpBinary, Server, ServerHandler};
+use std::{
+  collections::HashMap,
+  env,
+  fs::{self, File},
+  io,
+  path::PathBuf,
+  process::{Child, Command},
+  sync::{atomic::AtomicBool, Arc, Mutex},
+  thread,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Tell a copy of the executable to run the crash server instead, with the name
+// of its socket and the directory that minidumps are written to.
+const CRASH_SERVER_SOCKET_ENV: &str = "DENOG_WSI_CRASH_SERVER_SOCKET";
+const CRASH_SERVER_DIR_ENV: &str = "DENOG_WSI_CRASH_SERVER_DIR";
+
+// The kind of message that carries the app's metadata, as JSON.
+const METADATA_MESSAGE: u32 = 1;
+
+// How long to wait for the crash server to start listening.
+const CONNECT_ATTEMPTS: u32 = 50;
+const CONNECT_INTERVAL: Duration = Duration::from_millis(10);
+
+static CRASH_REPORTER: Mutex<Option<CrashReporter>> = Mutex::new(None);
+
+// A crashed process can't be trusted to write its own minidump, so a copy of
+// the executable does it. The crash handler catches signals and exceptions on
+// every thread, which covers the event loop, the runtime and the GPU's render
+// threads, and blocks the crashed thread until the dump is written.
+struct CrashReporter {
+  client: Arc<Client>,
+  _handler: CrashHandler,
+  _server: Child,
+}
+
+// Run the crash server and exit if this process was spawned to be one. Call
+// this first thing in `main`, before any other threads are started.
+pub fn run_crash_server_if_requested() {
+  let (Some(socket), Some(dir)) = (
+    env::var_os(CRASH_SERVER_SOCKET_ENV),
+    env::var_os(CRASH_SERVER_DIR_ENV),
+  ) else {
+    return;
+  };
+  let handler = CrashServer {
+    dir: PathBuf::from(dir),
+    metadata: Mutex::new(b"{}".to_vec()),
+  };
+  let exit_code = match Server::with_name(socket.to_string_lossy().as_ref())
+    .and_then(|mut server| {
+      server.run(Box::new(handler), &AtomicBool::new(false), None)
+    }) {
+    Ok(()) => 0,
+    Err(_) => 1,
+  };
+  std::process::exit(exit_code);
+}
+
+// Write minidumps of native crashes to `dir`, each with a JSON file of the
+// app's metadata next to it. Only the first call has an effect.
+pub fn install_crash_handler(dir: PathBuf) -> Result<(), anyhow::Error> {
+  let mut reporter = CRASH_REPORTER.lock().unwrap();
+  if reporter.is_some() {
+    return Ok(());
+  }
+
+  let socket = format!("denog-crash-{}", std::process::id());
+  let server = Command::new(env::current_exe()?)
+    .env(CRASH_SERVER_SOCKET_ENV, &socket)
+    .env(CRASH_SERVER_DIR_ENV, &dir)
+    .spawn()?;
+  let client = connect(&socket)?;
+
+  let crash_client = client.clone();
+  // SAFETY: The closure only sends the crash context to the crash server,
+  // which is as little as a crashed process can safely do.
+  let handler = CrashHandler::attach(unsafe {
+    make_crash_event(move |context: &CrashContext| {
+      CrashEventResult::Handled(crash_client.request_dump(context).is_ok())
+    })
+  })?;
+  // The crash server reads the crashed process's memory with ptrace, which
+  // Yama only allows for descendants unless told otherwise.
+  #[cfg(any(target_os = "linux", target_os = "android"))]
+  handler.set_ptracer(Some(server.id()));
+
+  *reporter = Some(CrashReporter {
+    client,
+    _handler: handler,
+    _server: server,
+  });
+  Ok(())
+}
+
+// Attach the app's metadata, e.g. its version and build, to minidumps written
+// from now on. Returns `false` if crash reporting isn't enabled.
+pub fn set_crash_metadata(
+  metadata: HashMap<String, String>,
+) -> Result<bool, anyhow::Error> {
+  let reporter = CRASH_REPORTER.lock().unwrap();
+  let Some(reporter) = &*reporter else {
+    return Ok(false);
+  };
+  let metadata = serde_json::to_vec_pretty(&metadata)?;
+  reporter.client.send_message(METADATA_MESSAGE, metadata)?;
+  Ok(true)
+}
+
+fn connect(socket: &str) -> Result<Arc<Client>, anyhow::Error> {
+  let mut attempts = 0;
+  loop {
+    match Client::with_name(socket) {
+      Ok(client) => return Ok(Arc::new(client)),
+      Err(_) if attempts < CONNECT_ATTEMPTS => {
+        attempts += 1;
+        thread::sleep(CONNECT_INTERVAL);
+      }
+      Err(err) => return Err(err.into()),
+    }
+  }
+}
+
+struct CrashServer {
+  dir: PathBuf,
+  metadata: Mutex<Vec<u8>>,
+}
+
+impl ServerHandler for CrashServer {
+  fn create_minidump_file(&self) -> Result<(File, PathBuf), io::Error> {
+    fs::create_dir_all(&self.dir)?;
+    let millis = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |since| since.as_millis());
+    let path = self.dir.join(format!("{millis}.dmp"));
+    Ok((File::create(&path)?, path))
+  }
+
+  fn on_minidump_created(
+    &self,
+    result: Result<MinidumpBinary, minidumper::Error>,
+  ) -> LoopAction {
+    if let Ok(binary) = result {
+      let metadata = self.metadata.lock().unwrap();
+      let _ = fs::write(binary.path.with_extension("json"), &*metadata);
+    }
+    // The crashed process won't send anything else.
+    LoopAction::Exit
+  }
+
+  fn on_message(&self, kind: u32, buffer: Vec<u8>) {
+    if kind == METADATA_MESSAGE {
+      *self.metadata.lock().unwrap() = buffer;
+    }
+  }
+
+  fn on_client_disconnected(&self, _num_clients: usize) -> LoopAction {
+    LoopAction::Exit
+  }
+}
@@ -1,17 +1,193 @@
-use serde::Deserialize;
-use winit::window::{CursorGrabMode, CursorIcon};
+use crate::event::WsiEvent;
+use serde::{Deserialize, Serialize};
+use std::{
+  cell::RefCell,
+  collections::{HashMap, HashSet},
+};
+use winit::{
+  dpi::PhysicalPosition,
+  error::ExternalError,
+  window::{CursorGrabMode, CursorIcon, Window},
+};
 
-#[derive(Deserialize)]
-pub struct WsiCursorGrabMode(
-  #[serde(with = "WsiCursorGrabModeDef")] pub CursorGrabMode,
-);
-
-#[derive(Deserialize)]
-#[serde(rename_all = "kebab-case", remote = "CursorGrabMode")]
-enum WsiCursorGrabModeDef {
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiCursorGrabMode {
   None,
   Confined,
   Locked,
+  // Locked where the platform can lock the cursor, and elsewhere confined and
+  // moved back to the middle of the window whenever it moves.
+  LockedOrConfined,
+}
+
+thread_local! {
+  // The windows whose cursor is recentered in place of being locked, on the
+  // event loop thread.
+  static RECENTERED_WINDOWS: RefCell<HashSet<u64>> =
+    RefCell::new(HashSet::new());
+  // The rectangles that windows confine their cursor to, in physical pixels
+  // relative to their client area, on the event loop thread.
+  static CONFINEMENT_RECTS: RefCell<HashMap<u64, (i32, i32, u32, u32)>> =
+    RefCell::new(HashMap::new());
+}
+
+// Winit only supports locking the cursor on some platforms and confining it
+// on others, so emulate a locked cursor with a confined one where it can't be
+// locked.
+pub fn set_cursor_grab(
+  wid: u64,
+  window: &Window,
+  mode: WsiCursorGrabMode,
+) -> Result<(), ExternalError> {
+  let recenter = match mode {
+    WsiCursorGrabMode::None => {
+      window.set_cursor_grab(CursorGrabMode::None).map(|_| false)
+    }
+    WsiCursorGrabMode::Confined => window
+      .set_cursor_grab(CursorGrabMode::Confined)
+      .map(|_| false),
+    WsiCursorGrabMode::Locked => window
+      .set_cursor_grab(CursorGrabMode::Locked)
+      .map(|_| false),
+    WsiCursorGrabMode::LockedOrConfined => window
+      .set_cursor_grab(CursorGrabMode::Locked)
+      .map(|_| false)
+      .or_else(|_| {
+        window
+          .set_cursor_grab(CursorGrabMode::Confined)
+          .map(|_| true)
+      }),
+  }?;
+  RECENTERED_WINDOWS.with(|recentered| {
+    let mut recentered = recentered.borrow_mut();
+    if recenter {
+      recentered.insert(wid);
+    } else {
+      recentered.remove(&wid);
+    }
+  });
+  if recenter {
+    let _ = window.set_cursor_position(window_center(window));
+  }
+  Ok(())
+}
+
+// Stop recentering or confining the cursor of a window that's being
+// destroyed.
+pub fn forget_cursor_grab(wid: u64) {
+  RECENTERED_WINDOWS.with(|recentered| recentered.borrow_mut().remove(&wid));
+  CONFINEMENT_RECTS.with(|rects| rects.borrow_mut().remove(&wid));
+}
+
+// Confine the cursor to a rectangle of a window's client area while the
+// window has focus, or stop confining it with `None`. Windows clips the cursor
+// to the rectangle. Elsewhere, the cursor is confined to the window where
+// winit can do that, and moved back into the rectangle whenever it leaves, so
+// it can be seen outside it for a moment.
+pub fn set_cursor_confinement(
+  wid: u64,
+  window: &Window,
+  rect: Option<(i32, i32, u32, u32)>,
+) -> Result<(), ExternalError> {
+  platform::set_cursor_confinement(window, rect)?;
+  CONFINEMENT_RECTS.with(|rects| {
+    let mut rects = rects.borrow_mut();
+    match rect {
+      Some(rect) => rects.insert(wid, rect),
+      None => rects.remove(&wid),
+    }
+  });
+  Ok(())
+}
+
+// Keep a confined cursor in its rectangle, moving cursor moved events that
+// are outside it to where the cursor is moved back to. A clipped cursor is
+// released when the window loses focus, and winit releases it whenever it
+// updates the cursor, so it's clipped again after the events that follow.
+pub fn confine_cursor(event: &mut WsiEvent, windows: &HashMap<u64, Window>) {
+  let (WsiEvent::CursorMoved { window: wid, .. }
+  | WsiEvent::CursorEntered { window: wid, .. }
+  | WsiEvent::WindowFocus { window: wid, .. }
+  | WsiEvent::WindowMoved { window: wid, .. }
+  | WsiEvent::WindowResized { window: wid, .. }) = *event
+  else {
+    return;
+  };
+  let rect = CONFINEMENT_RECTS.with(|rects| rects.borrow().get(&wid).copied());
+  let Some(rect) = rect else {
+    return;
+  };
+  let Some(window) = windows.get(&wid) else {
+    return;
+  };
+  match event {
+    WsiEvent::CursorMoved { position, .. } if window.has_focus() => {
+      let inside = clamp_to_rect(*position, rect);
+      if inside != *position {
+        let _ =
+          window.set_cursor_position(PhysicalPosition::<f64>::from(inside));
+        platform::clip_cursor(window, Some(rect));
+        *position = inside;
+      }
+    }
+    WsiEvent::WindowFocus { has_focus, .. } => {
+      platform::clip_cursor(window, has_focus.then_some(rect));
+    }
+    WsiEvent::CursorEntered { .. }
+    | WsiEvent::WindowMoved { .. }
+    | WsiEvent::WindowResized { .. }
+      if window.has_focus() =>
+    {
+      platform::clip_cursor(window, Some(rect));
+    }
+    _ => {}
+  }
+}
+
+// The nearest position to a position that's inside a rectangle, whose right
+// and bottom edges are outside it.
+fn clamp_to_rect(
+  (x, y): (f64, f64),
+  (left, top, width, height): (i32, i32, u32, u32),
+) -> (f64, f64) {
+  let right = left as f64 + width.saturating_sub(1) as f64;
+  let bottom = top as f64 + height.saturating_sub(1) as f64;
+  (x.clamp(left as f64, right), y.clamp(top as f64, bottom))
+}
+
+// Move a recentered cursor back to the middle of its window while the window
+// has focus. Returns whether the event is a cursor moved event of such a
+// window, which should be dropped, since a locked cursor doesn't move. Mouse
+// motion device events still report how the mouse moved.
+pub fn recenter_cursor(
+  event: &WsiEvent,
+  windows: &HashMap<u64, Window>,
+) -> bool {
+  let WsiEvent::CursorMoved {
+    window, position, ..
+  } = event
+  else {
+    return false;
+  };
+  if !RECENTERED_WINDOWS.with(|recentered| recentered.borrow().contains(window))
+  {
+    return false;
+  }
+  let Some(window) = windows.get(window) else {
+    return false;
+  };
+  let center = window_center(window);
+  // Recentering moves the cursor too, so skip the event it causes.
+  if window.has_focus() && *position != (center.x, center.y) {
+    let _ = window.set_cursor_position(center);
+  }
+  true
+}
+
+fn window_center(window: &Window) -> PhysicalPosition<f64> {
+  let size = window.inner_size();
+  PhysicalPosition::new((size.width / 2) as f64, (size.height / 2) as f64)
 }
 
 #[derive(Deserialize)]
@@ -56,3 +232,82 @@ enum WsiCursorIconDef {
   ColResize,
   RowResize,
 }
+
+// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-clipcursor
+#[cfg(windows)]
+mod platform {
+  use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+  use std::ptr;
+  use winapi::{
+    shared::windef::{HWND, POINT, RECT},
+    um::winuser::{ClientToScreen, ClipCursor, GetForegroundWindow},
+  };
+  use winit::{error::ExternalError, window::Window};
+
+  pub fn set_cursor_confinement(
+    window: &Window,
+    rect: Option<(i32, i32, u32, u32)>,
+  ) -> Result<(), ExternalError> {
+    if window.has_focus() {
+      clip_cursor(window, rect);
+    }
+    Ok(())
+  }
+
+  // Clip the cursor to a rectangle of a window's client area if the window is
+  // in the foreground, or release it. The clip is in screen coordinates, so it
+  // doesn't follow the window.
+  pub fn clip_cursor(window: &Window, rect: Option<(i32, i32, u32, u32)>) {
+    let RawWindowHandle::Win32(handle) = window.raw_window_handle() else {
+      return;
+    };
+    let hwnd = handle.hwnd as HWND;
+    // SAFETY: The window comes from winit and is valid while it exists.
+    unsafe {
+      let Some((x, y, width, height)) = rect else {
+        ClipCursor(ptr::null());
+        return;
+      };
+      if GetForegroundWindow() != hwnd {
+        return;
+      }
+      let mut origin = POINT { x: 0, y: 0 };
+      ClientToScreen(hwnd, &mut origin);
+      let left = origin.x + x;
+      let top = origin.y + y;
+      let clip = RECT {
+        left,
+        top,
+        right: left + width as i32,
+        bottom: top + height as i32,
+      };
+      ClipCursor(&clip);
+    }
+  }
+}
+
+// Winit can confine the cursor to the whole window everywhere but macOS,
+// where warping the cursor back has to do.
+#[cfg(not(windows))]
+mod platform {
+  use winit::{
+    error::ExternalError,
+    window::{CursorGrabMode, Window},
+  };
+
+  pub fn set_cursor_confinement(
+    window: &Window,
+    rect: Option<(i32, i32, u32, u32)>,
+  ) -> Result<(), ExternalError> {
+    let mode = match rect {
+      Some(_) => CursorGrabMode::Confined,
+      None => CursorGrabMode::None,
+    };
+    match window.set_cursor_grab(mode) {
+      Err(ExternalError::NotSupported(_)) => Ok(()),
+      result => result,
+    }
+  }
+
+  pub fn clip_cursor(_window: &Window, _rect: Option<(i32, i32, u32, u32)>) {}
+}
@@ -0,0 +1,67 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::event_loop::WsiEventLoopPause;
+use deno_core::{
+  futures::{channel::mpsc, executor},
+  serde_json::{self, json, Value},
+  InspectorMsg, InspectorMsgKind, InspectorSessionProxy, JsRuntimeInspector,
+};
+use std::thread;
+
+// Pause the event loop whenever a debugger pauses the runtime, so windows
+// keep responding to the window system while the program sits at a
+// breakpoint. This connects an inspector session of its own, which counts as
+// a connected debugger, so call it once the runtime is done waiting for one.
+pub fn follow_debugger_pauses(
+  inspector: &JsRuntimeInspector,
+  pause: WsiEventLoopPause,
+) {
+  let (inbound_tx, inbound_rx) = mpsc::unbounded::<InspectorMsg>();
+  let (outbound_tx, outbound_rx) = mpsc::unbounded::<String>();
+  let session = InspectorSessionProxy {
+    tx: inbound_tx,
+    rx: outbound_rx,
+  };
+  if inspector
+    .get_session_sender()
+    .unbounded_send(session)
+    .is_err()
+  {
+    return;
+  }
+
+  // Pauses are only reported to sessions with the debugger enabled. The
+  // session deactivates its breakpoints so that `debugger` statements don't
+  // stop the program unless a real debugger is attached.
+  let messages = [
+    json!({ "id": 1, "method": "Debugger.enable" }),
+    json!({
+      "id": 2,
+      "method": "Debugger.setBreakpointsActive",
+      "params": { "active": false },
+    }),
+  ];
+  for message in messages {
+    let _ = outbound_tx.unbounded_send(message.to_string());
+  }
+
+  thread::spawn(move || {
+    // The session ends when this is dropped.
+    let _outbound_tx = outbound_tx;
+    for msg in executor::block_on_stream(inbound_rx) {
+      let InspectorMsgKind::Notification = msg.kind else {
+        continue;
+      };
+      let Ok(notification) = serde_json::from_str::<Value>(&msg.content) else {
+        continue;
+      };
+      match notification["method"].as_str() {
+        Some("Debugger.paused") => pause.pause(),
+        Some("Debugger.resumed") => pause.resume(),
+        _ => {}
+      }
+    }
+    // The inspector is gone.
+    pause.resume();
+  });
+}
@@ -1,8 +1,20 @@
 // Copyright 2023 Jo Bates. All rights reserved. MIT license.
 
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap};
 use winit::event::DeviceId;
 
+thread_local! {
+  // The devices numbered so far by their number, on the event loop thread,
+  // so ops can look them up.
+  static DEVICES: RefCell<HashMap<u32, DeviceId>> =
+    RefCell::new(HashMap::new());
+}
+
+// The device with a number from `DeviceIds::get`.
+pub fn winit_device_id(u: u32) -> Option<DeviceId> {
+  DEVICES.with(|devices| devices.borrow().get(&u).copied())
+}
+
 pub struct DeviceIds {
   map: HashMap<DeviceId, u32>,
   next: u32,
@@ -23,6 +35,7 @@ impl DeviceIds {
         let u = self.next;
         self.next += 1;
         self.map.insert(device_id, u);
+        DEVICES.with(|devices| devices.borrow_mut().insert(u, device_id));
         u
       }
     }
@@ -0,0 +1,217 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use serde::Serialize;
+use winit::event::DeviceId;
+
+// What the OS tells about an input device, for per-device settings.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct WsiDeviceInfo {
+  name: Option<String>,
+  #[serde(rename = "type")]
+  device_type: WsiDeviceType,
+  vendor_id: Option<u16>,
+  product_id: Option<u16>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum WsiDeviceType {
+  Mouse,
+  Keyboard,
+  Touchpad,
+  Other,
+}
+
+// Winit only identifies devices on Windows, as raw input device handles. On
+// macOS and Wayland all input comes from one device ID, and winit keeps X11's
+// device IDs to itself.
+pub fn device_info(device_id: DeviceId) -> Option<WsiDeviceInfo> {
+  platform::device_info(device_id)
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getrawinputdeviceinfow
+#[cfg(windows)]
+mod platform {
+  use super::{WsiDeviceInfo, WsiDeviceType};
+  use std::{ffi::OsStr, mem, os::windows::ffi::OsStrExt, ptr};
+  use winapi::{
+    shared::{hidsdi::HidD_GetProductString, minwindef::UINT},
+    um::{
+      fileapi::{CreateFileW, OPEN_EXISTING},
+      handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+      winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, HANDLE},
+      winuser::{
+        GetRawInputDeviceInfoW, GetRawInputDeviceList, RAWINPUTDEVICELIST,
+        RIDI_DEVICEINFO, RIDI_DEVICENAME, RID_DEVICE_INFO, RIM_TYPEHID,
+        RIM_TYPEKEYBOARD, RIM_TYPEMOUSE,
+      },
+    },
+  };
+  use winit::{event::DeviceId, platform::windows::DeviceIdExtWindows};
+
+  // The HID usage of precision touchpads.
+  // https://learn.microsoft.com/en-us/windows-hardware/design/component-guidelines/touchpad-windows-precision-touchpad-collection
+  const HID_USAGE_PAGE_DIGITIZER: u16 = 0x0d;
+  const HID_USAGE_DIGITIZER_TOUCH_PAD: u16 = 0x05;
+
+  // Product strings are at most 126 characters, plus the null.
+  const MAX_PRODUCT_STRING_LEN: usize = 127;
+
+  pub fn device_info(device_id: DeviceId) -> Option<WsiDeviceInfo> {
+    // Winit's device IDs are raw input device handles, which it only exposes
+    // through their names.
+    let path = device_id.persistent_identifier()?;
+    let path = path.trim_end_matches('\0');
+    let device = raw_input_devices()
+      .into_iter()
+      .find(|&device| device_name(device).as_deref() == Some(path))?;
+    let info = raw_input_device_info(device)?;
+    let (device_type, ids) = match info.dwType {
+      RIM_TYPEMOUSE => (WsiDeviceType::Mouse, None),
+      RIM_TYPEKEYBOARD => (WsiDeviceType::Keyboard, None),
+      RIM_TYPEHID => {
+        // SAFETY: HID devices have HID info.
+        let hid = unsafe { info.u.hid() };
+        let device_type = if hid.usUsagePage == HID_USAGE_PAGE_DIGITIZER
+          && hid.usUsage == HID_USAGE_DIGITIZER_TOUCH_PAD
+        {
+          WsiDeviceType::Touchpad
+        } else {
+          WsiDeviceType::Other
+        };
+        let ids = (hid.dwVendorId as u16, hid.dwProductId as u16);
+        (device_type, Some(ids))
+      }
+      _ => (WsiDeviceType::Other, None),
+    };
+    let ids = ids.or_else(|| path_ids(path));
+    Some(WsiDeviceInfo {
+      name: product_string(path),
+      device_type,
+      vendor_id: ids.map(|(vendor_id, _)| vendor_id),
+      product_id: ids.map(|(_, product_id)| product_id),
+    })
+  }
+
+  fn raw_input_devices() -> Vec<HANDLE> {
+    let size = mem::size_of::<RAWINPUTDEVICELIST>() as UINT;
+    let mut count = 0;
+    // SAFETY: The list has room for `count` devices.
+    unsafe {
+      if GetRawInputDeviceList(ptr::null_mut(), &mut count, size) == UINT::MAX {
+        return Vec::new();
+      }
+      let mut list: Vec<RAWINPUTDEVICELIST> =
+        vec![mem::zeroed(); count as usize];
+      let count = GetRawInputDeviceList(list.as_mut_ptr(), &mut count, size);
+      if count == UINT::MAX {
+        return Vec::new();
+      }
+      list.truncate(count as usize);
+      list.into_iter().map(|device| device.hDevice).collect()
+    }
+  }
+
+  fn device_name(device: HANDLE) -> Option<String> {
+    let mut len = 0;
+    // SAFETY: The name has room for `len` characters.
+    unsafe {
+      GetRawInputDeviceInfoW(
+        device,
+        RIDI_DEVICENAME,
+        ptr::null_mut(),
+        &mut len,
+      );
+      let mut name = vec![0u16; len as usize];
+      let status = GetRawInputDeviceInfoW(
+        device,
+        RIDI_DEVICENAME,
+        name.as_mut_ptr().cast(),
+        &mut len,
+      );
+      if status == UINT::MAX || status == 0 {
+        return None;
+      }
+      let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+      Some(String::from_utf16_lossy(&name[..len]))
+    }
+  }
+
+  fn raw_input_device_info(device: HANDLE) -> Option<RID_DEVICE_INFO> {
+    // SAFETY: The info is the size it's said to be.
+    unsafe {
+      let mut info: RID_DEVICE_INFO = mem::zeroed();
+      info.cbSize = mem::size_of::<RID_DEVICE_INFO>() as UINT;
+      let mut size = info.cbSize;
+      let status = GetRawInputDeviceInfoW(
+        device,
+        RIDI_DEVICEINFO,
+        (&mut info as *mut RID_DEVICE_INFO).cast(),
+        &mut size,
+      );
+      (status != UINT::MAX && status != 0).then_some(info)
+    }
+  }
+
+  // The product name that a HID device reports. Opening it with no access
+  // rights is enough to read it, even for mice and keyboards that Windows
+  // keeps to itself.
+  // https://learn.microsoft.com/en-us/windows-hardware/drivers/hid/opening-hid-collections
+  fn product_string(path: &str) -> Option<String> {
+    let path: Vec<u16> =
+      OsStr::new(path).encode_wide().chain(Some(0)).collect();
+    // SAFETY: The path is null-terminated, the buffer is the size it's said
+    // to be, and the file is closed after use.
+    unsafe {
+      let file = CreateFileW(
+        path.as_ptr(),
+        0,
+        FILE_SHARE_READ | FILE_SHARE_WRITE,
+        ptr::null_mut(),
+        OPEN_EXISTING,
+        0,
+        ptr::null_mut(),
+      );
+      if file == INVALID_HANDLE_VALUE {
+        return None;
+      }
+      let mut name = [0u16; MAX_PRODUCT_STRING_LEN];
+      let ok = HidD_GetProductString(
+        file,
+        name.as_mut_ptr().cast(),
+        mem::size_of_val(&name) as u32,
+      );
+      CloseHandle(file);
+      if ok == 0 {
+        return None;
+      }
+      let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+      Some(String::from_utf16_lossy(&name[..len]))
+        .filter(|name| !name.is_empty())
+    }
+  }
+
+  // The vendor and product IDs in the path of a USB device, like
+  // `\\?\HID#VID_046D&PID_C52B&MI_01#...`.
+  fn path_ids(path: &str) -> Option<(u16, u16)> {
+    let path = path.to_ascii_uppercase();
+    let id = |prefix: &str| {
+      let start = path.find(prefix)? + prefix.len();
+      u16::from_str_radix(path.get(start..start + 4)?, 16).ok()
+    };
+    Some((id("VID_")?, id("PID_")?))
+  }
+}
+
+#[cfg(not(windows))]
+mod platform {
+  use super::WsiDeviceInfo;
+  use winit::event::DeviceId;
+
+  pub fn device_info(_device_id: DeviceId) -> Option<WsiDeviceInfo> {
+    None
+  }
+}
@@ -0,0 +1,122 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::event::WsiEvent;
+use std::{
+  cell::Cell,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  thread,
+  time::{Duration, Instant},
+};
+use winit::event_loop::EventLoopProxy;
+
+thread_local! {
+  // The interval set by `set_device_motion_interval`, on the event loop
+  // thread. Zero delivers every event.
+  static DEVICE_MOTION_INTERVAL: Cell<Duration> = Cell::new(Duration::ZERO);
+}
+
+pub fn set_device_motion_interval(interval: Duration) {
+  DEVICE_MOTION_INTERVAL.with(|cell| cell.set(interval));
+}
+
+// Mice that report at up to 8 kHz send that many `MouseMotion` and
+// `DeviceAxis` events, each of which waits for the runtime to take it, which
+// leaves little time for its requests. So the driver delivers them at most
+// once per interval: mouse motion adds up per device, and each device axis
+// keeps its latest value. Motion that's held back is delivered when the
+// interval ends, waking the event loop if nothing else does.
+pub struct DeviceMotionLimiter {
+  wake: EventLoopProxy<()>,
+  // At most one event per device, or per device and axis, in the order they
+  // were first seen.
+  held: Vec<WsiEvent>,
+  last_delivery: Option<Instant>,
+  wake_scheduled: Arc<AtomicBool>,
+}
+
+impl DeviceMotionLimiter {
+  pub fn new(wake: EventLoopProxy<()>) -> Self {
+    Self {
+      wake,
+      held: Vec::new(),
+      last_delivery: None,
+      wake_scheduled: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  // Hold back a motion event, or return any other event.
+  pub fn hold(&mut self, event: WsiEvent) -> Option<WsiEvent> {
+    let interval = DEVICE_MOTION_INTERVAL.with(Cell::get);
+    if interval.is_zero() && self.held.is_empty() {
+      return Some(event);
+    }
+    match event {
+      WsiEvent::MouseMotion { device_id, delta } => {
+        let held = self.held.iter_mut().find_map(|held| match held {
+          WsiEvent::MouseMotion {
+            device_id: held_id,
+            delta,
+          } if *held_id == device_id => Some(delta),
+          _ => None,
+        });
+        match held {
+          Some(held) => *held += delta,
+          None => self.held.push(WsiEvent::MouseMotion { device_id, delta }),
+        }
+      }
+      WsiEvent::DeviceAxis {
+        device_id,
+        axis_id,
+        value,
+      } => {
+        let held = self.held.iter_mut().find_map(|held| match held {
+          WsiEvent::DeviceAxis {
+            device_id: held_id,
+            axis_id: held_axis,
+            value,
+          } if *held_id == device_id && *held_axis == axis_id => Some(value),
+          _ => None,
+        });
+        match held {
+          Some(held) => *held = value,
+          None => self.held.push(WsiEvent::DeviceAxis {
+            device_id,
+            axis_id,
+            value,
+          }),
+        }
+      }
+      event => return Some(event),
+    }
+    None
+  }
+
+  // The motion held back, once the interval since the last delivery is over.
+  // Call this after every event.
+  pub fn take_due(&mut self) -> Vec<WsiEvent> {
+    if self.held.is_empty() {
+      return vec![];
+    }
+    let interval = DEVICE_MOTION_INTERVAL.with(Cell::get);
+    let elapsed = self
+      .last_delivery
+      .map_or(interval, |last_delivery| last_delivery.elapsed());
+    if elapsed >= interval {
+      self.last_delivery = Some(Instant::now());
+      return std::mem::take(&mut self.held);
+    }
+    if !self.wake_scheduled.swap(true, Ordering::Relaxed) {
+      let wake = self.wake.clone();
+      let wake_scheduled = self.wake_scheduled.clone();
+      thread::spawn(move || {
+        thread::sleep(interval - elapsed);
+        wake_scheduled.store(false, Ordering::Relaxed);
+        let _ = wake.send_event(());
+      });
+    }
+    vec![]
+  }
+}
@@ -0,0 +1,55 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{event::WsiEvent, input::WsiButtonState};
+use std::{cell::RefCell, collections::VecDeque, time::Duration};
+use winit::event::ModifiersState;
+
+// Execute requests whose latency is averaged for the diagnostics overlay.
+const LATENCY_HISTORY: usize = 120;
+
+// How long execute requests wait before the event loop thread runs them, which
+// is what every op that touches a window pays on top of its own work.
+#[derive(Default)]
+pub struct ExecuteLatency {
+  recent: RefCell<VecDeque<Duration>>,
+}
+
+impl ExecuteLatency {
+  pub fn record(&self, latency: Duration) {
+    let mut recent = self.recent.borrow_mut();
+    if recent.len() == LATENCY_HISTORY {
+      recent.pop_front();
+    }
+    recent.push_back(latency);
+  }
+
+  // The line shown in the diagnostics overlay, in milliseconds.
+  pub fn overlay_line(&self) -> String {
+    let recent = self.recent.borrow();
+    if recent.is_empty() {
+      return "EVENT LOOP -".into();
+    }
+    let millis = |latency: &Duration| latency.as_secs_f64() * 1000.0;
+    let average = recent.iter().map(millis).sum::<f64>() / recent.len() as f64;
+    let max = recent.iter().map(millis).fold(0.0, f64::max);
+    format!("EVENT LOOP {average:.2} MS  MAX {max:.2}")
+  }
+}
+
+// Ctrl+Shift+F12 toggles the diagnostics overlay of the focused window. The
+// key event is still delivered.
+pub fn overlay_hotkey_window(event: &WsiEvent) -> Option<u64> {
+  let WsiEvent::InputKey {
+    window,
+    code: Some("F12"),
+    state: WsiButtonState::Pressed,
+    modifiers,
+    repeat: false,
+    ..
+  } = *event
+  else {
+    return None;
+  };
+  let modifiers = ModifiersState::from_bits_truncate(modifiers);
+  (modifiers.ctrl() && modifiers.shift()).then_some(window)
+}
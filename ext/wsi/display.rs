@@ -0,0 +1,698 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// The brightness and gamma of the monitor that a window is on, for kiosks and
+// full-screen programs. Brightness is set over DDC/CI on Windows, which most
+// external monitors support and laptop panels don't, and through the RandR
+// `Backlight` output property on X11, which only some drivers have. macOS only
+// changes brightness through private APIs, so it isn't available there. Gamma
+// ramps can be changed on Windows, macOS and X11. Wayland leaves both to the
+// compositor.
+
+use serde::Serialize;
+use std::cell::RefCell;
+use winit::{monitor::MonitorHandle, window::Window};
+
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+pub use platform::crtc_output;
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiDisplayControl {
+  Brightness,
+  Gamma,
+}
+
+// The red, green and blue lookup tables that the display applies to pixels on
+// their way to the monitor, with values from 0 to 1.
+#[derive(Clone)]
+pub struct GammaRamps {
+  red: Vec<f32>,
+  green: Vec<f32>,
+  blue: Vec<f32>,
+}
+
+impl GammaRamps {
+  // The ramps with each input raised to `1 / gamma` first, so gammas above 1
+  // brighten the midtones, like `xrandr --gamma`.
+  fn with_gamma(&self, gamma: f64) -> Self {
+    let adjust = |ramp: &[f32]| {
+      let last = ramp.len().saturating_sub(1) as f64;
+      (0..ramp.len())
+        .map(|i| {
+          let input = if last > 0.0 { i as f64 / last } else { 1.0 };
+          ramp[(input.powf(1.0 / gamma) * last).round() as usize]
+        })
+        .collect()
+    };
+    Self {
+      red: adjust(&self.red),
+      green: adjust(&self.green),
+      blue: adjust(&self.blue),
+    }
+  }
+}
+
+// A monitor whose gamma was changed, with the ramps to put back when the
+// window that last changed it is destroyed.
+struct AdjustedGamma {
+  monitor: MonitorHandle,
+  window: u64,
+  original: GammaRamps,
+}
+
+thread_local! {
+  // On the event loop thread. The system keeps changed ramps after the
+  // program exits, except on macOS.
+  static ADJUSTED_GAMMA: RefCell<Vec<AdjustedGamma>> = RefCell::new(Vec::new());
+}
+
+// What can be changed about the window's monitor.
+pub fn supported_display_controls(window: &Window) -> Vec<WsiDisplayControl> {
+  let Some(monitor) = window.current_monitor() else {
+    return Vec::new();
+  };
+  let mut controls = Vec::new();
+  if platform::brightness(window, &monitor).is_some() {
+    controls.push(WsiDisplayControl::Brightness);
+  }
+  if platform::gamma_ramps(window, &monitor).is_some() {
+    controls.push(WsiDisplayControl::Gamma);
+  }
+  controls
+}
+
+// The brightness of the window's monitor from 0 to 1, or `None` if it can't
+// be read.
+pub fn brightness(window: &Window) -> Option<f64> {
+  platform::brightness(window, &window.current_monitor()?)
+}
+
+// Returns whether the brightness was set. Unlike gamma, it stays set after the
+// window is destroyed, like a change made with the monitor's own buttons.
+pub fn set_brightness(window: &Window, brightness: f64) -> bool {
+  let Some(monitor) = window.current_monitor() else {
+    return false;
+  };
+  platform::set_brightness(window, &monitor, brightness.clamp(0.0, 1.0))
+}
+
+// Applies `gamma` to the ramps that the window's monitor had before any
+// window changed them, and returns whether it could. A gamma of 1 puts the
+// original ramps back.
+pub fn set_gamma(wid: u64, window: &Window, gamma: f64) -> bool {
+  let Some(monitor) = window.current_monitor() else {
+    return false;
+  };
+  ADJUSTED_GAMMA.with(|adjusted| {
+    let mut adjusted = adjusted.borrow_mut();
+    let index = adjusted.iter().position(|other| other.monitor == monitor);
+    let original = match index {
+      Some(index) => adjusted[index].original.clone(),
+      None => match platform::gamma_ramps(window, &monitor) {
+        Some(ramps) => ramps,
+        None => return false,
+      },
+    };
+    let reset = gamma == 1.0;
+    let ramps = if reset {
+      original.clone()
+    } else {
+      original.with_gamma(gamma)
+    };
+    if !platform::set_gamma_ramps(window, &monitor, &ramps) {
+      return false;
+    }
+    match index {
+      Some(index) if reset => {
+        adjusted.remove(index);
+      }
+      Some(index) => adjusted[index].window = wid,
+      None if reset => {}
+      None => adjusted.push(AdjustedGamma {
+        monitor,
+        window: wid,
+        original,
+      }),
+    }
+    true
+  })
+}
+
+// Puts back the ramps of the monitors whose gamma the window changed last.
+pub fn restore_gamma(wid: u64, window: &Window) {
+  ADJUSTED_GAMMA.with(|adjusted| {
+    adjusted.borrow_mut().retain(|adjusted| {
+      if adjusted.window != wid {
+        return true;
+      }
+      platform::set_gamma_ramps(window, &adjusted.monitor, &adjusted.original);
+      false
+    })
+  })
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/monitor/using-the-high-level-monitor-configuration-functions
+// https://learn.microsoft.com/en-us/windows/win32/api/wingdi/nf-wingdi-setdevicegammaramp
+#[cfg(windows)]
+mod platform {
+  use super::GammaRamps;
+  use std::{ffi::OsString, iter, mem, os::windows::ffi::OsStrExt, ptr};
+  use winapi::{
+    shared::{minwindef::DWORD, windef::HDC},
+    um::{
+      highlevelmonitorconfigurationapi::{
+        GetMonitorBrightness, SetMonitorBrightness,
+      },
+      physicalmonitorenumerationapi::{
+        DestroyPhysicalMonitors, GetNumberOfPhysicalMonitorsFromHMONITOR,
+        GetPhysicalMonitorsFromHMONITOR, PHYSICAL_MONITOR,
+      },
+      wingdi::{CreateDCW, DeleteDC, GetDeviceGammaRamp, SetDeviceGammaRamp},
+      winnt::HANDLE,
+    },
+  };
+  use winit::{
+    monitor::MonitorHandle, platform::windows::MonitorHandleExtWindows,
+    window::Window,
+  };
+
+  pub fn brightness(_window: &Window, monitor: &MonitorHandle) -> Option<f64> {
+    with_physical_monitors(monitor, |handles| {
+      let (mut min, mut current, mut max) = (0, 0, 0);
+      // SAFETY: The handle is valid until the physical monitors are
+      // destroyed.
+      let read = unsafe {
+        GetMonitorBrightness(
+          *handles.first()?,
+          &mut min,
+          &mut current,
+          &mut max,
+        )
+      };
+      (read != 0 && max > min)
+        .then(|| current.saturating_sub(min) as f64 / (max - min) as f64)
+    })
+  }
+
+  // Monitors that are mirrored show the same content, so they're all set.
+  pub fn set_brightness(
+    _window: &Window,
+    monitor: &MonitorHandle,
+    brightness: f64,
+  ) -> bool {
+    with_physical_monitors(monitor, |handles| {
+      let mut set = false;
+      for &handle in handles {
+        let (mut min, mut current, mut max) = (0, 0, 0);
+        // SAFETY: The handle is valid until the physical monitors are
+        // destroyed.
+        unsafe {
+          if GetMonitorBrightness(handle, &mut min, &mut current, &mut max) != 0
+          {
+            let value =
+              min + ((max - min) as f64 * brightness).round() as DWORD;
+            set |= SetMonitorBrightness(handle, value) != 0;
+          }
+        }
+      }
+      Some(set)
+    })
+    .unwrap_or(false)
+  }
+
+  pub fn gamma_ramps(
+    _window: &Window,
+    monitor: &MonitorHandle,
+  ) -> Option<GammaRamps> {
+    let mut ramp = [[0u16; 256]; 3];
+    // SAFETY: The ramp is the size that GetDeviceGammaRamp expects.
+    let read = with_dc(monitor, |dc| unsafe {
+      GetDeviceGammaRamp(dc, ramp.as_mut_ptr().cast()) != 0
+    });
+    let channel =
+      |values: &[u16]| values.iter().map(|&v| v as f32 / 65535.0).collect();
+    read.then(|| GammaRamps {
+      red: channel(&ramp[0]),
+      green: channel(&ramp[1]),
+      blue: channel(&ramp[2]),
+    })
+  }
+
+  // Windows refuses ramps that stray too far from the identity.
+  pub fn set_gamma_ramps(
+    _window: &Window,
+    monitor: &MonitorHandle,
+    ramps: &GammaRamps,
+  ) -> bool {
+    let mut ramp = [[0u16; 256]; 3];
+    let channels = [&ramps.red, &ramps.green, &ramps.blue];
+    for (values, channel) in ramp.iter_mut().zip(channels) {
+      if channel.len() != values.len() {
+        return false;
+      }
+      for (value, level) in values.iter_mut().zip(channel) {
+        *value = (level * 65535.0).round() as u16;
+      }
+    }
+    // SAFETY: The ramp is the size that SetDeviceGammaRamp expects.
+    with_dc(monitor, |dc| unsafe {
+      SetDeviceGammaRamp(dc, ramp.as_mut_ptr().cast()) != 0
+    })
+  }
+
+  // Calls `f` with a DC for the monitor's display device, whose name is the
+  // monitor's native ID.
+  fn with_dc(monitor: &MonitorHandle, f: impl FnOnce(HDC) -> bool) -> bool {
+    let device: Vec<u16> = OsString::from(monitor.native_id())
+      .encode_wide()
+      .chain(iter::once(0))
+      .collect();
+    // SAFETY: The device name is null-terminated, and the DC is deleted after
+    // use.
+    unsafe {
+      let dc =
+        CreateDCW(device.as_ptr(), ptr::null(), ptr::null(), ptr::null());
+      if dc.is_null() {
+        return false;
+      }
+      let result = f(dc);
+      DeleteDC(dc);
+      result
+    }
+  }
+
+  // Calls `f` with the handles of the physical monitors that make up the
+  // monitor.
+  fn with_physical_monitors<T>(
+    monitor: &MonitorHandle,
+    f: impl FnOnce(&[HANDLE]) -> Option<T>,
+  ) -> Option<T> {
+    let hmonitor = monitor.hmonitor() as _;
+    let mut count = 0;
+    // SAFETY: The monitor comes from winit, and the physical monitors are
+    // destroyed after use.
+    unsafe {
+      if GetNumberOfPhysicalMonitorsFromHMONITOR(hmonitor, &mut count) == 0
+        || count == 0
+      {
+        return None;
+      }
+      let mut monitors: Vec<PHYSICAL_MONITOR> =
+        vec![mem::zeroed(); count as usize];
+      if GetPhysicalMonitorsFromHMONITOR(hmonitor, count, monitors.as_mut_ptr())
+        == 0
+      {
+        return None;
+      }
+      let handles: Vec<_> = monitors
+        .iter()
+        .map(|monitor| monitor.hPhysicalMonitor)
+        .collect();
+      let result = f(&handles);
+      DestroyPhysicalMonitors(count, monitors.as_mut_ptr());
+      result
+    }
+  }
+}
+
+// https://developer.apple.com/documentation/coregraphics/1454768-cgsetdisplaytransferbytable
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::GammaRamps;
+  use winit::{
+    monitor::MonitorHandle, platform::macos::MonitorHandleExtMacOS,
+    window::Window,
+  };
+
+  #[link(name = "CoreGraphics", kind = "framework")]
+  extern "C" {
+    fn CGDisplayGammaTableCapacity(display: u32) -> u32;
+    fn CGGetDisplayTransferByTable(
+      display: u32,
+      capacity: u32,
+      red: *mut f32,
+      green: *mut f32,
+      blue: *mut f32,
+      sample_count: *mut u32,
+    ) -> i32;
+    fn CGSetDisplayTransferByTable(
+      display: u32,
+      table_size: u32,
+      red: *const f32,
+      green: *const f32,
+      blue: *const f32,
+    ) -> i32;
+  }
+
+  pub fn brightness(_window: &Window, _monitor: &MonitorHandle) -> Option<f64> {
+    None
+  }
+
+  pub fn set_brightness(
+    _window: &Window,
+    _monitor: &MonitorHandle,
+    _brightness: f64,
+  ) -> bool {
+    false
+  }
+
+  pub fn gamma_ramps(
+    _window: &Window,
+    monitor: &MonitorHandle,
+  ) -> Option<GammaRamps> {
+    let display = monitor.native_id();
+    // SAFETY: The tables have room for `capacity` samples each.
+    unsafe {
+      let capacity = CGDisplayGammaTableCapacity(display);
+      let mut red = vec![0.0; capacity as usize];
+      let mut green = vec![0.0; capacity as usize];
+      let mut blue = vec![0.0; capacity as usize];
+      let mut count = 0;
+      let error = CGGetDisplayTransferByTable(
+        display,
+        capacity,
+        red.as_mut_ptr(),
+        green.as_mut_ptr(),
+        blue.as_mut_ptr(),
+        &mut count,
+      );
+      if error != 0 || count == 0 {
+        return None;
+      }
+      for table in [&mut red, &mut green, &mut blue] {
+        table.truncate(count as usize);
+      }
+      Some(GammaRamps { red, green, blue })
+    }
+  }
+
+  // The system restores the ramps when the program exits.
+  pub fn set_gamma_ramps(
+    _window: &Window,
+    monitor: &MonitorHandle,
+    ramps: &GammaRamps,
+  ) -> bool {
+    let len = ramps.red.len();
+    if ramps.green.len() != len || ramps.blue.len() != len {
+      return false;
+    }
+    // SAFETY: The tables each have `len` samples.
+    let error = unsafe {
+      CGSetDisplayTransferByTable(
+        monitor.native_id(),
+        len as u32,
+        ramps.red.as_ptr(),
+        ramps.green.as_ptr(),
+        ramps.blue.as_ptr(),
+      )
+    };
+    error == 0
+  }
+}
+
+// https://www.x.org/releases/current/doc/randrproto/randrproto.txt
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use super::GammaRamps;
+  use raw_window_handle::{HasRawDisplayHandle, RawDisplayHandle};
+  use std::{
+    os::raw::{c_int, c_long, c_uchar, c_ulong},
+    ptr, slice,
+  };
+  use winit::{
+    monitor::MonitorHandle, platform::x11::MonitorHandleExtX11, window::Window,
+  };
+  use x11_dl::{
+    xlib::{self, Xlib},
+    xrandr::Xrandr,
+  };
+
+  // The X connection of winit's windows, with Xlib and RandR.
+  struct Connection {
+    xlib: Xlib,
+    xrandr: Xrandr,
+    display: *mut xlib::Display,
+  }
+
+  impl Connection {
+    fn new(window: &Window) -> Option<Self> {
+      let RawDisplayHandle::Xlib(display) = window.raw_display_handle() else {
+        return None;
+      };
+      if display.display.is_null() {
+        return None;
+      }
+      Some(Self {
+        xlib: Xlib::open().ok()?,
+        xrandr: Xrandr::open().ok()?,
+        display: display.display.cast(),
+      })
+    }
+
+    // The `Backlight` property of the monitor's output, which older drivers
+    // call `BACKLIGHT`, and its range.
+    unsafe fn backlight(
+      &self,
+      monitor: &MonitorHandle,
+    ) -> Option<(c_ulong, xlib::Atom, c_long, c_long)> {
+      let output = crtc_output(
+        &self.xlib,
+        &self.xrandr,
+        self.display,
+        monitor.native_id() as c_ulong,
+      )?;
+      for name in [&b"Backlight\0"[..], &b"BACKLIGHT\0"[..]] {
+        let atom = (self.xlib.XInternAtom)(
+          self.display,
+          name.as_ptr().cast(),
+          xlib::True,
+        );
+        if atom == 0 {
+          continue;
+        }
+        let info =
+          (self.xrandr.XRRQueryOutputProperty)(self.display, output, atom);
+        if info.is_null() {
+          continue;
+        }
+        let range = ((*info).range != 0 && (*info).num_values == 2)
+          .then(|| (*(*info).values, *(*info).values.add(1)));
+        (self.xlib.XFree)(info.cast());
+        if let Some((min, max)) = range.filter(|(min, max)| max > min) {
+          return Some((output, atom, min, max));
+        }
+      }
+      None
+    }
+  }
+
+  pub fn brightness(window: &Window, monitor: &MonitorHandle) -> Option<f64> {
+    let connection = Connection::new(window)?;
+    // SAFETY: The display comes from winit and is valid while the event loop
+    // exists. The property value is freed after use.
+    unsafe {
+      let (output, atom, min, max) = connection.backlight(monitor)?;
+      let (mut kind, mut format, mut items, mut after) = (0, 0, 0, 0);
+      let mut value: *mut c_uchar = ptr::null_mut();
+      let status = (connection.xrandr.XRRGetOutputProperty)(
+        connection.display,
+        output,
+        atom,
+        0,
+        1,
+        xlib::False,
+        xlib::False,
+        xlib::XA_INTEGER,
+        &mut kind,
+        &mut format,
+        &mut items,
+        &mut after,
+        &mut value,
+      );
+      if value.is_null() {
+        return None;
+      }
+      // Format 32 values are longs on the client side.
+      let current = (status == xlib::Success as c_int
+        && kind == xlib::XA_INTEGER
+        && format == 32
+        && items == 1)
+        .then(|| *value.cast::<c_long>());
+      (connection.xlib.XFree)(value.cast());
+      Some((current? - min) as f64 / (max - min) as f64)
+    }
+  }
+
+  pub fn set_brightness(
+    window: &Window,
+    monitor: &MonitorHandle,
+    brightness: f64,
+  ) -> bool {
+    let Some(connection) = Connection::new(window) else {
+      return false;
+    };
+    // SAFETY: The display comes from winit and is valid while the event loop
+    // exists.
+    unsafe {
+      let Some((output, atom, min, max)) = connection.backlight(monitor) else {
+        return false;
+      };
+      let value = min + ((max - min) as f64 * brightness).round() as c_long;
+      (connection.xrandr.XRRChangeOutputProperty)(
+        connection.display,
+        output,
+        atom,
+        xlib::XA_INTEGER,
+        32,
+        xlib::PropModeReplace,
+        (&value as *const c_long).cast(),
+        1,
+      );
+      (connection.xlib.XFlush)(connection.display);
+    }
+    true
+  }
+
+  pub fn gamma_ramps(
+    window: &Window,
+    monitor: &MonitorHandle,
+  ) -> Option<GammaRamps> {
+    let connection = Connection::new(window)?;
+    let crtc = monitor.native_id() as c_ulong;
+    // SAFETY: The display comes from winit and is valid while the event loop
+    // exists. The gamma is freed after use.
+    unsafe {
+      let gamma = (connection.xrandr.XRRGetCrtcGamma)(connection.display, crtc);
+      if gamma.is_null() {
+        return None;
+      }
+      let len = (*gamma).size.max(0) as usize;
+      let channel = |values: *mut u16| {
+        slice::from_raw_parts(values, len)
+          .iter()
+          .map(|&v| v as f32 / 65535.0)
+          .collect()
+      };
+      let ramps = (len > 0).then(|| GammaRamps {
+        red: channel((*gamma).red),
+        green: channel((*gamma).green),
+        blue: channel((*gamma).blue),
+      });
+      (connection.xrandr.XRRFreeGamma)(gamma);
+      ramps
+    }
+  }
+
+  pub fn set_gamma_ramps(
+    window: &Window,
+    monitor: &MonitorHandle,
+    ramps: &GammaRamps,
+  ) -> bool {
+    let Some(connection) = Connection::new(window) else {
+      return false;
+    };
+    let crtc = monitor.native_id() as c_ulong;
+    let len = ramps.red.len();
+    if ramps.green.len() != len || ramps.blue.len() != len {
+      return false;
+    }
+    // SAFETY: The display comes from winit and is valid while the event loop
+    // exists. The gamma has room for `len` values per channel and is freed
+    // after use.
+    unsafe {
+      if (connection.xrandr.XRRGetCrtcGammaSize)(connection.display, crtc)
+        != len as c_int
+      {
+        return false;
+      }
+      let gamma = (connection.xrandr.XRRAllocGamma)(len as c_int);
+      if gamma.is_null() {
+        return false;
+      }
+      let channels = [
+        ((*gamma).red, &ramps.red),
+        ((*gamma).green, &ramps.green),
+        ((*gamma).blue, &ramps.blue),
+      ];
+      for (values, channel) in channels {
+        let values = slice::from_raw_parts_mut(values, len);
+        for (value, level) in values.iter_mut().zip(channel) {
+          *value = (level * 65535.0).round() as u16;
+        }
+      }
+      (connection.xrandr.XRRSetCrtcGamma)(connection.display, crtc, gamma);
+      (connection.xrandr.XRRFreeGamma)(gamma);
+      (connection.xlib.XFlush)(connection.display);
+    }
+    true
+  }
+
+  // Winit's monitors are RandR CRTCs, while properties belong to the outputs
+  // that show them. Mirrored outputs share a CRTC, and the first one is used.
+  pub unsafe fn crtc_output(
+    xlib: &Xlib,
+    xrandr: &Xrandr,
+    display: *mut xlib::Display,
+    crtc: c_ulong,
+  ) -> Option<c_ulong> {
+    let root = (xlib.XDefaultRootWindow)(display);
+    let resources = (xrandr.XRRGetScreenResourcesCurrent)(display, root);
+    if resources.is_null() {
+      return None;
+    }
+    let info = (xrandr.XRRGetCrtcInfo)(display, resources, crtc);
+    let output =
+      (!info.is_null() && (*info).noutput > 0).then(|| *(*info).outputs);
+    if !info.is_null() {
+      (xrandr.XRRFreeCrtcInfo)(info);
+    }
+    (xrandr.XRRFreeScreenResources)(resources);
+    output
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use super::GammaRamps;
+  use winit::{monitor::MonitorHandle, window::Window};
+
+  pub fn brightness(_window: &Window, _monitor: &MonitorHandle) -> Option<f64> {
+    None
+  }
+
+  pub fn set_brightness(
+    _window: &Window,
+    _monitor: &MonitorHandle,
+    _brightness: f64,
+  ) -> bool {
+    false
+  }
+
+  pub fn gamma_ramps(
+    _window: &Window,
+    _monitor: &MonitorHandle,
+  ) -> Option<GammaRamps> {
+    None
+  }
+
+  pub fn set_gamma_ramps(
+    _window: &Window,
+    _monitor: &MonitorHandle,
+    _ramps: &GammaRamps,
+  ) -> bool {
+    false
+  }
+}
@@ -0,0 +1,941 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// Drag and drop beyond what winit covers. Winit reports dropped files without
+// a position, so the position is the cursor's when the event is delivered.
+// Windows also accepts text, URL and image drops, by replacing winit's drop
+// target with one that negotiates a format with the drag source. Drop events
+// from it are queued and delivered by the event loop driver. Windows and macOS
+// can also start drags out of a window.
+
+use crate::image::WsiRgbaImage;
+use deno_core::{
+  anyhow::{self, bail},
+  ZeroCopyBuf,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use winit::{event_loop::EventLoopProxy, window::Window};
+
+// The MIME types of data that a window accepts besides files.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum WsiDropType {
+  #[serde(rename = "image/png")]
+  Png,
+  #[serde(rename = "text/plain")]
+  Text,
+  #[serde(rename = "text/uri-list")]
+  UriList,
+}
+
+// Text types are delivered as strings, images as bytes. Only Windows drops
+// data other than files.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum WsiDropData {
+  Text(String),
+  Bytes(ZeroCopyBuf),
+}
+
+// What to drag out of a window.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiDragData {
+  #[serde(default)]
+  pub paths: Vec<PathBuf>,
+  pub text: Option<String>,
+}
+
+// The image under the cursor while dragging, decoded from a PNG image.
+pub struct WsiDragImage {
+  #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+  png: Vec<u8>,
+  #[cfg_attr(not(windows), allow(dead_code))]
+  rgba: WsiRgbaImage,
+}
+
+impl WsiDragImage {
+  pub fn decode(png: Vec<u8>) -> Result<Self, anyhow::Error> {
+    let Some(rgba) = WsiRgbaImage::decode_png(&png) else {
+      bail!("The drag image is not a PNG image");
+    };
+    Ok(Self { png, rgba })
+  }
+}
+
+// Where the cursor is within a window's client area, in physical pixels.
+pub fn cursor_position(window: &Window) -> Option<(f64, f64)> {
+  platform::cursor_position(window)
+}
+
+// Take over drops onto a new window. `event_loop_proxy` wakes the event loop
+// when there are drop events to deliver.
+pub fn register_drop_target(
+  window: &Window,
+  event_loop_proxy: EventLoopProxy<()>,
+) {
+  platform::register_drop_target(window, event_loop_proxy)
+}
+
+// Accept data of these types, in order of preference, when no files are
+// dragged. Only Windows supports this; elsewhere it does nothing.
+pub fn set_drop_types(window: &Window, types: Vec<WsiDropType>) {
+  platform::set_drop_types(window, types)
+}
+
+// Start dragging files and/or text out of a window, e.g. into a file manager,
+// while handling the mouse button press that starts it. Windows returns when
+// the drag ends and macOS when it starts. Returns `false` if it couldn't
+// start, which is always the case elsewhere.
+pub fn start_drag(
+  window: &Window,
+  data: WsiDragData,
+  image: Option<WsiDragImage>,
+) -> bool {
+  platform::start_drag(window, data, image)
+}
+
+pub use platform::take_drop_events;
+
+// https://learn.microsoft.com/en-us/windows/win32/com/drag-and-drop
+#[cfg(windows)]
+mod platform {
+  use super::{WsiDragData, WsiDragImage, WsiDropData, WsiDropType};
+  use crate::event::WsiEvent;
+  use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{c_void, OsStr, OsString},
+    mem,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::PathBuf,
+    ptr, slice,
+    sync::atomic::{AtomicU32, Ordering},
+  };
+  use winapi::{
+    shared::{
+      guiddef::{IsEqualIID, GUID, REFIID},
+      minwindef::{BOOL, DWORD, HGLOBAL, TRUE, UINT, ULONG},
+      ntdef::HRESULT,
+      windef::{COLORREF, HBITMAP, HWND, POINT, POINTL, SIZE},
+      winerror::{
+        DRAGDROP_S_CANCEL, DRAGDROP_S_DROP, DRAGDROP_S_USEDEFAULTCURSORS,
+        E_NOINTERFACE, SUCCEEDED, S_OK,
+      },
+      wtypes::{CLIPFORMAT, DVASPECT_CONTENT},
+      wtypesbase::CLSCTX_INPROC_SERVER,
+    },
+    um::{
+      combaseapi::CoCreateInstance,
+      objidl::{IDataObject, FORMATETC, STGMEDIUM, TYMED_HGLOBAL},
+      ole2::{RegisterDragDrop, RevokeDragDrop},
+      oleidl::{
+        IDropTarget, IDropTargetVtbl, DROPEFFECT_COPY, DROPEFFECT_NONE,
+      },
+      shellapi::{DragQueryFileW, HDROP},
+      unknwnbase::{IUnknown, IUnknownVtbl},
+      winbase::{
+        GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock,
+        GMEM_MOVEABLE,
+      },
+      wingdi::{
+        CreateDIBSection, DeleteObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+        DIB_RGB_COLORS,
+      },
+      winuser::{
+        GetCursorPos, RegisterClipboardFormatW, ScreenToClient, CF_HDROP,
+        CF_UNICODETEXT, MK_LBUTTON,
+      },
+    },
+    Interface,
+  };
+  use winit::{
+    event_loop::EventLoopProxy, platform::windows::WindowExtWindows,
+    window::Window,
+  };
+
+  #[link(name = "ole32")]
+  extern "system" {
+    fn DoDragDrop(
+      data: *mut IDataObject,
+      source: *mut c_void,
+      ok_effects: DWORD,
+      effect: *mut DWORD,
+    ) -> HRESULT;
+    fn ReleaseStgMedium(medium: *mut STGMEDIUM);
+  }
+
+  #[link(name = "shell32")]
+  extern "system" {
+    fn SHCreateDataObject(
+      folder: *const c_void,
+      count: UINT,
+      items: *const c_void,
+      inner: *mut IDataObject,
+      riid: REFIID,
+      object: *mut *mut c_void,
+    ) -> HRESULT;
+  }
+
+  thread_local! {
+    // Drop events that haven't been delivered yet.
+    static DROP_EVENTS: RefCell<Vec<WsiEvent>> = RefCell::new(Vec::new());
+    // The types each window accepts, set by `set_drop_types`.
+    static DROP_TYPES: RefCell<HashMap<u64, Vec<WsiDropType>>> =
+      RefCell::new(HashMap::new());
+  }
+
+  pub fn take_drop_events() -> Vec<WsiEvent> {
+    DROP_EVENTS.with(|events| mem::take(&mut *events.borrow_mut()))
+  }
+
+  pub fn cursor_position(window: &Window) -> Option<(f64, f64)> {
+    let mut point = POINT { x: 0, y: 0 };
+    // SAFETY: The window handle is valid.
+    unsafe {
+      if GetCursorPos(&mut point) == 0
+        || ScreenToClient(window.hwnd() as _, &mut point) == 0
+      {
+        return None;
+      }
+    }
+    Some((point.x as f64, point.y as f64))
+  }
+
+  pub fn set_drop_types(window: &Window, types: Vec<WsiDropType>) {
+    let wid = window.id().into();
+    DROP_TYPES.with(|drop_types| drop_types.borrow_mut().insert(wid, types));
+  }
+
+  pub fn register_drop_target(
+    window: &Window,
+    event_loop_proxy: EventLoopProxy<()>,
+  ) {
+    let hwnd = window.hwnd() as HWND;
+    let target = Box::into_raw(Box::new(DropTarget {
+      interface: IDropTarget {
+        lpVtbl: &DROP_TARGET_VTBL,
+      },
+      refs: AtomicU32::new(1),
+      hwnd,
+      wid: window.id().into(),
+      event_loop_proxy,
+      hovered: None,
+    }));
+    // SAFETY: The window handle is valid and OLE was initialized by winit,
+    // whose drop target this replaces. The window holds its own reference to
+    // the target until the window is destroyed.
+    unsafe {
+      RevokeDragDrop(hwnd);
+      RegisterDragDrop(hwnd, target.cast());
+      release(target.cast());
+    }
+  }
+
+  // What a drag over the window would drop, decided when it enters.
+  #[derive(Clone, Copy)]
+  enum Hovered {
+    Files,
+    Data(WsiDropType),
+  }
+
+  // An IDropTarget, whose interface must come first.
+  #[repr(C)]
+  struct DropTarget {
+    interface: IDropTarget,
+    refs: AtomicU32,
+    hwnd: HWND,
+    wid: u64,
+    event_loop_proxy: EventLoopProxy<()>,
+    hovered: Option<Hovered>,
+  }
+
+  impl DropTarget {
+    fn send(&self, events: impl IntoIterator<Item = WsiEvent>) {
+      DROP_EVENTS.with(|queue| queue.borrow_mut().extend(events));
+      // Fails only after the event loop has exited.
+      let _ = self.event_loop_proxy.send_event(());
+    }
+
+    // The point in client coordinates.
+    fn position(&self, pt: *const POINTL) -> (f64, f64) {
+      // SAFETY: OLE passes a valid point and the window handle is valid.
+      let mut point = unsafe {
+        POINT {
+          x: (*pt).x,
+          y: (*pt).y,
+        }
+      };
+      unsafe { ScreenToClient(self.hwnd, &mut point) };
+      (point.x as f64, point.y as f64)
+    }
+
+    // Choose files if there are any, otherwise the first type the window
+    // accepts that the data is available as.
+    unsafe fn negotiate(&self, data: *const IDataObject) -> Option<Hovered> {
+      if has_format(data, CF_HDROP as CLIPFORMAT) {
+        return Some(Hovered::Files);
+      }
+      let types = DROP_TYPES
+        .with(|types| types.borrow().get(&self.wid).cloned())
+        .unwrap_or_default();
+      types
+        .into_iter()
+        .find(|&ty| has_format(data, clipboard_format(ty)))
+        .map(Hovered::Data)
+    }
+  }
+
+  fn clipboard_format(ty: WsiDropType) -> CLIPFORMAT {
+    let name = match ty {
+      WsiDropType::Text => return CF_UNICODETEXT as CLIPFORMAT,
+      WsiDropType::UriList => "UniformResourceLocatorW",
+      WsiDropType::Png => "PNG",
+    };
+    let name: Vec<u16> =
+      OsStr::new(name).encode_wide().chain(Some(0)).collect();
+    // SAFETY: The name is NUL-terminated.
+    unsafe { RegisterClipboardFormatW(name.as_ptr()) as CLIPFORMAT }
+  }
+
+  fn format_etc(format: CLIPFORMAT) -> FORMATETC {
+    FORMATETC {
+      cfFormat: format,
+      ptd: ptr::null(),
+      dwAspect: DVASPECT_CONTENT,
+      lindex: -1,
+      tymed: TYMED_HGLOBAL,
+    }
+  }
+
+  unsafe fn has_format(data: *const IDataObject, format: CLIPFORMAT) -> bool {
+    (*data).QueryGetData(&format_etc(format)) == S_OK
+  }
+
+  // Call `f` with the data in the given format, locked in memory.
+  unsafe fn with_data<R>(
+    data: *const IDataObject,
+    format: CLIPFORMAT,
+    f: impl FnOnce(HGLOBAL, &[u8]) -> R,
+  ) -> Option<R> {
+    let mut medium: STGMEDIUM = mem::zeroed();
+    if (*data).GetData(&format_etc(format), &mut medium) != S_OK {
+      return None;
+    }
+    // The union holds the HGLOBAL requested by `format_etc`.
+    let hglobal = medium.u as HGLOBAL;
+    let bytes = GlobalLock(hglobal) as *const u8;
+    let result = (!bytes.is_null()).then(|| {
+      let result =
+        f(hglobal, slice::from_raw_parts(bytes, GlobalSize(hglobal)));
+      GlobalUnlock(hglobal);
+      result
+    });
+    ReleaseStgMedium(&mut medium);
+    result
+  }
+
+  unsafe fn read_files(data: *const IDataObject) -> Vec<PathBuf> {
+    with_data(data, CF_HDROP as CLIPFORMAT, |hglobal, _| {
+      let hdrop = hglobal as HDROP;
+      let count = DragQueryFileW(hdrop, u32::MAX, ptr::null_mut(), 0);
+      (0..count)
+        .map(|i| {
+          let len = DragQueryFileW(hdrop, i, ptr::null_mut(), 0) as usize;
+          let mut path = vec![0; len + 1];
+          DragQueryFileW(hdrop, i, path.as_mut_ptr(), path.len() as u32);
+          OsString::from_wide(&path[..len]).into()
+        })
+        .collect()
+    })
+    .unwrap_or_default()
+  }
+
+  unsafe fn read_data(
+    data: *const IDataObject,
+    ty: WsiDropType,
+  ) -> Option<WsiDropData> {
+    with_data(data, clipboard_format(ty), |_, bytes| match ty {
+      WsiDropType::Png => WsiDropData::Bytes(bytes.to_vec().into()),
+      WsiDropType::Text | WsiDropType::UriList => {
+        let text: Vec<u16> = bytes
+          .chunks_exact(2)
+          .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+          .take_while(|&c| c != 0)
+          .collect();
+        WsiDropData::Text(String::from_utf16_lossy(&text))
+      }
+    })
+  }
+
+  static DROP_TARGET_VTBL: IDropTargetVtbl = IDropTargetVtbl {
+    parent: IUnknownVtbl {
+      QueryInterface: query_interface,
+      AddRef: add_ref,
+      Release: release,
+    },
+    DragEnter: drag_enter,
+    DragOver: drag_over,
+    DragLeave: drag_leave,
+    Drop: perform_drop,
+  };
+
+  unsafe fn target<'a, T>(this: *mut T) -> &'a mut DropTarget {
+    &mut *this.cast()
+  }
+
+  unsafe extern "system" fn query_interface(
+    this: *mut IUnknown,
+    riid: REFIID,
+    object: *mut *mut c_void,
+  ) -> HRESULT {
+    if IsEqualIID(&*riid, &IUnknown::uuidof())
+      || IsEqualIID(&*riid, &IDropTarget::uuidof())
+    {
+      add_ref(this);
+      *object = this.cast();
+      S_OK
+    } else {
+      *object = ptr::null_mut();
+      E_NOINTERFACE
+    }
+  }
+
+  unsafe extern "system" fn add_ref(this: *mut IUnknown) -> ULONG {
+    target(this).refs.fetch_add(1, Ordering::Relaxed) + 1
+  }
+
+  unsafe extern "system" fn release(this: *mut IUnknown) -> ULONG {
+    let refs = target(this).refs.fetch_sub(1, Ordering::AcqRel) - 1;
+    if refs == 0 {
+      drop(Box::from_raw(this.cast::<DropTarget>()));
+    }
+    refs
+  }
+
+  unsafe extern "system" fn drag_enter(
+    this: *mut IDropTarget,
+    data: *const IDataObject,
+    _key_state: DWORD,
+    pt: *const POINTL,
+    effect: *mut DWORD,
+  ) -> HRESULT {
+    let target = target(this);
+    let window = target.wid;
+    let position = Some(target.position(pt));
+    target.hovered = target.negotiate(data);
+    match target.hovered {
+      Some(Hovered::Files) => {
+        target.send(read_files(data).into_iter().map(|path| {
+          WsiEvent::FileHovered {
+            window,
+            path,
+            position,
+          }
+        }))
+      }
+      Some(Hovered::Data(mime_type)) => target.send([WsiEvent::DataHovered {
+        window,
+        mime_type,
+        position,
+      }]),
+      None => {}
+    }
+    drag_over(this, 0, pt, effect)
+  }
+
+  unsafe extern "system" fn drag_over(
+    this: *mut IDropTarget,
+    _key_state: DWORD,
+    _pt: *const POINTL,
+    effect: *mut DWORD,
+  ) -> HRESULT {
+    *effect = match target(this).hovered {
+      Some(_) => DROPEFFECT_COPY,
+      None => DROPEFFECT_NONE,
+    };
+    S_OK
+  }
+
+  unsafe extern "system" fn drag_leave(this: *mut IDropTarget) -> HRESULT {
+    let target = target(this);
+    let window = target.wid;
+    match target.hovered.take() {
+      Some(Hovered::Files) => target.send([WsiEvent::FileLeft { window }]),
+      Some(Hovered::Data(_)) => target.send([WsiEvent::DataLeft { window }]),
+      None => {}
+    }
+    S_OK
+  }
+
+  unsafe extern "system" fn perform_drop(
+    this: *mut IDropTarget,
+    data: *const IDataObject,
+    _key_state: DWORD,
+    pt: *const POINTL,
+    effect: *mut DWORD,
+  ) -> HRESULT {
+    let target = target(this);
+    let window = target.wid;
+    let position = Some(target.position(pt));
+    *effect = DROPEFFECT_NONE;
+    match target.hovered.take() {
+      Some(Hovered::Files) => {
+        *effect = DROPEFFECT_COPY;
+        target.send(read_files(data).into_iter().map(|path| {
+          WsiEvent::FileDropped {
+            window,
+            path,
+            position,
+          }
+        }))
+      }
+      Some(Hovered::Data(mime_type)) => match read_data(data, mime_type) {
+        Some(data) => {
+          *effect = DROPEFFECT_COPY;
+          target.send([WsiEvent::DataDropped {
+            window,
+            mime_type,
+            data,
+            position,
+          }])
+        }
+        None => target.send([WsiEvent::DataLeft { window }]),
+      },
+      None => {}
+    }
+    S_OK
+  }
+
+  pub fn start_drag(
+    _window: &Window,
+    data: WsiDragData,
+    image: Option<WsiDragImage>,
+  ) -> bool {
+    let mut object: *mut IDataObject = ptr::null_mut();
+    // SAFETY: The data object is created empty and released after the drag.
+    // DoDragDrop runs a modal loop until the drag ends, so the drop source on
+    // the stack outlives it.
+    unsafe {
+      let result = SHCreateDataObject(
+        ptr::null(),
+        0,
+        ptr::null(),
+        ptr::null_mut(),
+        &IDataObject::uuidof(),
+        &mut object as *mut _ as *mut *mut c_void,
+      );
+      if !SUCCEEDED(result) {
+        return false;
+      }
+      let mut ok = true;
+      if !data.paths.is_empty() {
+        ok &= set_data(object, CF_HDROP as CLIPFORMAT, &drop_files(&data));
+      }
+      if let Some(text) = &data.text {
+        let text: Vec<u8> = OsStr::new(text)
+          .encode_wide()
+          .chain(Some(0))
+          .flat_map(u16::to_ne_bytes)
+          .collect();
+        ok &= set_data(object, CF_UNICODETEXT as CLIPFORMAT, &text);
+      }
+      if let (true, Some(image)) = (ok, image) {
+        // Without an image, the shell shows its default one.
+        set_drag_image(object, image);
+      }
+      let mut effect = DROPEFFECT_NONE;
+      let result = ok.then(|| {
+        let source = DropSource {
+          vtbl: &DROP_SOURCE_VTBL,
+        };
+        DoDragDrop(
+          object,
+          &source as *const DropSource as *mut c_void,
+          DROPEFFECT_COPY,
+          &mut effect,
+        )
+      });
+      (*object).Release();
+      result.map_or(false, SUCCEEDED)
+    }
+  }
+
+  // A DROPFILES structure followed by the paths, each terminated by a NUL and
+  // the list by another.
+  // https://learn.microsoft.com/en-us/windows/win32/shell/clipboard#cf_hdrop
+  fn drop_files(data: &WsiDragData) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    // pFiles, the offset of the paths.
+    bytes.extend(20u32.to_ne_bytes());
+    // pt and fNC.
+    bytes.extend([0; 12]);
+    // fWide, since the paths are UTF-16.
+    bytes.extend(1u32.to_ne_bytes());
+    for path in &data.paths {
+      let path = path.as_os_str().encode_wide().chain(Some(0));
+      bytes.extend(path.flat_map(u16::to_ne_bytes));
+    }
+    bytes.extend(0u16.to_ne_bytes());
+    bytes
+  }
+
+  // Give the data object a copy of the bytes in the given format.
+  unsafe fn set_data(
+    object: *mut IDataObject,
+    format: CLIPFORMAT,
+    bytes: &[u8],
+  ) -> bool {
+    let hglobal = GlobalAlloc(GMEM_MOVEABLE, bytes.len());
+    let memory = GlobalLock(hglobal) as *mut u8;
+    if memory.is_null() {
+      GlobalFree(hglobal);
+      return false;
+    }
+    ptr::copy_nonoverlapping(bytes.as_ptr(), memory, bytes.len());
+    GlobalUnlock(hglobal);
+    let mut medium: STGMEDIUM = mem::zeroed();
+    medium.tymed = TYMED_HGLOBAL;
+    medium.u = hglobal as _;
+    // winapi declares the medium as a FORMATETC. The data object owns the
+    // memory if it succeeds.
+    let result = (*object).SetData(
+      &format_etc(format),
+      &mut medium as *mut STGMEDIUM as *const FORMATETC,
+      TRUE,
+    );
+    if result != S_OK {
+      GlobalFree(hglobal);
+    }
+    result == S_OK
+  }
+
+  // The shell's drag image helper, with the cursor at the image's center.
+  // https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nn-shobjidl_core-idragsourcehelper
+  unsafe fn set_drag_image(object: *mut IDataObject, image: WsiDragImage) {
+    let image = image.rgba;
+    let mut helper: *mut DragSourceHelper = ptr::null_mut();
+    let result = CoCreateInstance(
+      &CLSID_DRAG_DROP_HELPER,
+      ptr::null_mut(),
+      CLSCTX_INPROC_SERVER,
+      &IID_IDRAG_SOURCE_HELPER,
+      &mut helper as *mut _ as *mut *mut c_void,
+    );
+    if !SUCCEEDED(result) {
+      return;
+    }
+
+    // A top-down DIB with premultiplied BGRA pixels.
+    let mut info: BITMAPINFO = mem::zeroed();
+    info.bmiHeader = BITMAPINFOHEADER {
+      biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+      biWidth: image.width as i32,
+      biHeight: -(image.height as i32),
+      biPlanes: 1,
+      biBitCount: 32,
+      biCompression: BI_RGB,
+      ..mem::zeroed()
+    };
+    let mut bits: *mut c_void = ptr::null_mut();
+    let bitmap = CreateDIBSection(
+      ptr::null_mut(),
+      &info,
+      DIB_RGB_COLORS,
+      &mut bits,
+      ptr::null_mut(),
+      0,
+    );
+    if !bitmap.is_null() && !bits.is_null() {
+      let pixels = slice::from_raw_parts_mut(bits.cast(), image.rgba.len());
+      for (dst, src) in pixels.chunks_exact_mut(4).zip(image.rgba.chunks(4)) {
+        let alpha = src[3] as u32;
+        let premultiply = |c: u8| (c as u32 * alpha / 255) as u8;
+        dst.copy_from_slice(&[
+          premultiply(src[2]),
+          premultiply(src[1]),
+          premultiply(src[0]),
+          src[3],
+        ]);
+      }
+      let drag_image = ShDragImage {
+        size: SIZE {
+          cx: image.width as i32,
+          cy: image.height as i32,
+        },
+        offset: POINT {
+          x: image.width as i32 / 2,
+          y: image.height as i32 / 2,
+        },
+        bitmap,
+        color_key: 0,
+      };
+      // The helper owns the bitmap if it succeeds.
+      let result =
+        ((*(*helper).vtbl).initialize_from_bitmap)(helper, &drag_image, object);
+      if !SUCCEEDED(result) {
+        DeleteObject(bitmap.cast());
+      }
+    }
+    ((*(*helper).vtbl).parent.Release)(helper.cast());
+  }
+
+  const CLSID_DRAG_DROP_HELPER: GUID = GUID {
+    Data1: 0x4657278a,
+    Data2: 0x411b,
+    Data3: 0x11d2,
+    Data4: [0x83, 0x9a, 0x00, 0xc0, 0x4f, 0xd9, 0x18, 0xd0],
+  };
+
+  const IID_IDRAG_SOURCE_HELPER: GUID = GUID {
+    Data1: 0xde5bf786,
+    Data2: 0x477a,
+    Data3: 0x11d2,
+    Data4: [0x83, 0x9d, 0x00, 0xc0, 0x4f, 0xd9, 0x18, 0xd0],
+  };
+
+  const IID_IDROP_SOURCE: GUID = GUID {
+    Data1: 0x00000121,
+    Data2: 0x0000,
+    Data3: 0x0000,
+    Data4: [0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46],
+  };
+
+  // SHDRAGIMAGE
+  #[repr(C)]
+  struct ShDragImage {
+    size: SIZE,
+    offset: POINT,
+    bitmap: HBITMAP,
+    color_key: COLORREF,
+  }
+
+  // IDragSourceHelper, which winapi doesn't declare.
+  #[repr(C)]
+  struct DragSourceHelper {
+    vtbl: *const DragSourceHelperVtbl,
+  }
+
+  #[repr(C)]
+  struct DragSourceHelperVtbl {
+    parent: IUnknownVtbl,
+    initialize_from_bitmap: unsafe extern "system" fn(
+      this: *mut DragSourceHelper,
+      image: *const ShDragImage,
+      object: *mut IDataObject,
+    ) -> HRESULT,
+    initialize_from_window: unsafe extern "system" fn(
+      this: *mut DragSourceHelper,
+      hwnd: HWND,
+      point: *mut POINT,
+      object: *mut IDataObject,
+    ) -> HRESULT,
+  }
+
+  // An IDropSource, which winapi doesn't declare. It lives on the stack for
+  // the duration of DoDragDrop, so it isn't reference counted.
+  #[repr(C)]
+  struct DropSource {
+    vtbl: *const DropSourceVtbl,
+  }
+
+  #[repr(C)]
+  struct DropSourceVtbl {
+    parent: IUnknownVtbl,
+    query_continue_drag: unsafe extern "system" fn(
+      this: *mut DropSource,
+      escape_pressed: BOOL,
+      key_state: DWORD,
+    ) -> HRESULT,
+    give_feedback: unsafe extern "system" fn(
+      this: *mut DropSource,
+      effect: DWORD,
+    ) -> HRESULT,
+  }
+
+  static DROP_SOURCE_VTBL: DropSourceVtbl = DropSourceVtbl {
+    parent: IUnknownVtbl {
+      QueryInterface: source_query_interface,
+      AddRef: source_add_ref_release,
+      Release: source_add_ref_release,
+    },
+    query_continue_drag,
+    give_feedback,
+  };
+
+  unsafe extern "system" fn source_query_interface(
+    this: *mut IUnknown,
+    riid: REFIID,
+    object: *mut *mut c_void,
+  ) -> HRESULT {
+    if IsEqualIID(&*riid, &IUnknown::uuidof())
+      || IsEqualIID(&*riid, &IID_IDROP_SOURCE)
+    {
+      *object = this.cast();
+      S_OK
+    } else {
+      *object = ptr::null_mut();
+      E_NOINTERFACE
+    }
+  }
+
+  unsafe extern "system" fn source_add_ref_release(
+    _this: *mut IUnknown,
+  ) -> ULONG {
+    1
+  }
+
+  // Drop when the left mouse button is released and cancel on escape.
+  unsafe extern "system" fn query_continue_drag(
+    _this: *mut DropSource,
+    escape_pressed: BOOL,
+    key_state: DWORD,
+  ) -> HRESULT {
+    if escape_pressed != 0 {
+      DRAGDROP_S_CANCEL
+    } else if key_state & MK_LBUTTON as DWORD == 0 {
+      DRAGDROP_S_DROP
+    } else {
+      S_OK
+    }
+  }
+
+  unsafe extern "system" fn give_feedback(
+    _this: *mut DropSource,
+    _effect: DWORD,
+  ) -> HRESULT {
+    DRAGDROP_S_USEDEFAULTCURSORS
+  }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::{WsiDragData, WsiDragImage, WsiDropType};
+  use crate::{event::WsiEvent, macos};
+  use winit::{event_loop::EventLoopProxy, window::Window};
+
+  pub use macos::cursor_position;
+
+  pub fn take_drop_events() -> Vec<WsiEvent> {
+    Vec::new()
+  }
+
+  pub fn register_drop_target(
+    _window: &Window,
+    _event_loop_proxy: EventLoopProxy<()>,
+  ) {
+  }
+
+  pub fn set_drop_types(_window: &Window, _types: Vec<WsiDropType>) {}
+
+  pub fn start_drag(
+    window: &Window,
+    data: WsiDragData,
+    image: Option<WsiDragImage>,
+  ) -> bool {
+    macos::start_drag(window, data.paths, data.text, image.map(|i| i.png))
+  }
+}
+
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use super::{WsiDragData, WsiDragImage, WsiDropType};
+  use crate::event::WsiEvent;
+  use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+  };
+  use winit::{event_loop::EventLoopProxy, window::Window};
+  use x11_dl::xlib::Xlib;
+
+  pub fn take_drop_events() -> Vec<WsiEvent> {
+    Vec::new()
+  }
+
+  // Winit only supports dropping files on X11.
+  pub fn cursor_position(window: &Window) -> Option<(f64, f64)> {
+    let (RawDisplayHandle::Xlib(display), RawWindowHandle::Xlib(handle)) =
+      (window.raw_display_handle(), window.raw_window_handle())
+    else {
+      return None;
+    };
+    if display.display.is_null() {
+      return None;
+    }
+    let xlib = Xlib::open().ok()?;
+    let (mut root, mut child) = (0, 0);
+    let (mut root_x, mut root_y, mut x, mut y, mut mask) = (0, 0, 0, 0, 0);
+    // SAFETY: The display and window come from winit and are valid while the
+    // window exists.
+    let same_screen = unsafe {
+      (xlib.XQueryPointer)(
+        display.display.cast(),
+        handle.window,
+        &mut root,
+        &mut child,
+        &mut root_x,
+        &mut root_y,
+        &mut x,
+        &mut y,
+        &mut mask,
+      )
+    };
+    (same_screen != 0).then_some((x as f64, y as f64))
+  }
+
+  pub fn register_drop_target(
+    _window: &Window,
+    _event_loop_proxy: EventLoopProxy<()>,
+  ) {
+  }
+
+  pub fn set_drop_types(_window: &Window, _types: Vec<WsiDropType>) {}
+
+  pub fn start_drag(
+    _window: &Window,
+    _data: WsiDragData,
+    _image: Option<WsiDragImage>,
+  ) -> bool {
+    false
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use super::{WsiDragData, WsiDragImage, WsiDropType};
+  use crate::event::WsiEvent;
+  use winit::{event_loop::EventLoopProxy, window::Window};
+
+  pub fn take_drop_events() -> Vec<WsiEvent> {
+    Vec::new()
+  }
+
+  pub fn cursor_position(_window: &Window) -> Option<(f64, f64)> {
+    None
+  }
+
+  pub fn register_drop_target(
+    _window: &Window,
+    _event_loop_proxy: EventLoopProxy<()>,
+  ) {
+  }
+
+  pub fn set_drop_types(_window: &Window, _types: Vec<WsiDropType>) {}
+
+  pub fn start_drag(
+    _window: &Window,
+    _data: WsiDragData,
+    _image: Option<WsiDragImage>,
+  ) -> bool {
+    false
+  }
+}
@@ -0,0 +1,65 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::event::WsiEvent;
+use std::{cell::RefCell, collections::HashSet};
+use winit::window::Window;
+
+thread_local! {
+  // The windows that don't take input, e.g. the parent of a modal dialog
+  // window, on the event loop thread.
+  static DISABLED_WINDOWS: RefCell<HashSet<u64>> =
+    RefCell::new(HashSet::new());
+}
+
+// Enable or disable a window's input. Windows disables the window itself, so
+// that it can't be focused, moved or closed either. Elsewhere there's no such
+// thing, so its input and close requests are dropped before the runtime sees
+// them.
+pub fn set_enabled(wid: u64, window: &Window, enabled: bool) {
+  DISABLED_WINDOWS.with(|disabled| {
+    if enabled {
+      disabled.borrow_mut().remove(&wid);
+    } else {
+      disabled.borrow_mut().insert(wid);
+    }
+  });
+  platform::set_enabled(window, enabled);
+}
+
+pub fn is_enabled(wid: u64) -> bool {
+  DISABLED_WINDOWS.with(|disabled| !disabled.borrow().contains(&wid))
+}
+
+// Whether an event is input or a close request for a disabled window.
+pub fn is_blocked(event: &WsiEvent) -> bool {
+  let wid = match *event {
+    WsiEvent::CloseRequested { window } => Some(window),
+    _ => event.input_window(),
+  };
+  wid.map_or(false, |wid| !is_enabled(wid))
+}
+
+pub fn forget_enabled(wid: u64) {
+  DISABLED_WINDOWS.with(|disabled| disabled.borrow_mut().remove(&wid));
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-enablewindow
+#[cfg(windows)]
+mod platform {
+  use winapi::{shared::windef::HWND, um::winuser::EnableWindow};
+  use winit::{platform::windows::WindowExtWindows, window::Window};
+
+  pub fn set_enabled(window: &Window, enabled: bool) {
+    // SAFETY: The window handle is valid.
+    unsafe {
+      EnableWindow(window.hwnd() as HWND, enabled as _);
+    }
+  }
+}
+
+#[cfg(not(windows))]
+mod platform {
+  use winit::window::Window;
+
+  pub fn set_enabled(_window: &Window, _enabled: bool) {}
+}
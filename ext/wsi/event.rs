@@ -2,12 +2,18 @@
 
 use crate::{
   device_ids::DeviceIds,
+  drag_drop::{WsiDropData, WsiDropType},
   input::{
     WsiButtonState, WsiKeyCode, WsiMouseButton, WsiMouseDelta, WsiScrollDelta,
     WsiTouchForce, WsiTouchPhase,
   },
+  monitor::WsiMonitor,
+  orientation::WsiOrientation,
+  pen::WsiPenState,
+  shader_watch::WsiShaderCompilation,
   window::WsiWindowTheme,
 };
+use deno_core::serde_json;
 use serde::Serialize;
 use std::path::PathBuf;
 use winit::event::{DeviceEvent, Event, Ime, WindowEvent};
@@ -16,8 +22,23 @@ use winit::event::{DeviceEvent, Event, Ime, WindowEvent};
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum WsiEvent {
   Internal,
+  // Made by the driver when none of the app's windows can be seen anymore, or
+  // one can be again.
+  AppDidEnterBackground,
+  AppDidEnterForeground,
+  // Made by the driver when the system becomes low on memory.
+  AppMemoryWarning,
+  #[serde(rename_all = "camelCase")]
+  AppMenuItem {
+    id: String,
+  },
+  // Made by the driver when the platform asks whether the app can quit. JS
+  // answers it with the `onBeforeQuit` callbacks.
+  AppQuitRequested,
   AppResumed,
   AppSuspended,
+  // Made when the app is about to exit. It's the runtime's last event.
+  AppWillTerminate,
   #[serde(rename_all = "camelCase")]
   CloseRequested {
     window: u64,
@@ -39,6 +60,26 @@ pub enum WsiEvent {
     position: (f64, f64),
   },
   #[serde(rename_all = "camelCase")]
+  #[cfg_attr(not(windows), allow(dead_code))]
+  DataDropped {
+    window: u64,
+    mime_type: WsiDropType,
+    data: WsiDropData,
+    position: Option<(f64, f64)>,
+  },
+  #[serde(rename_all = "camelCase")]
+  #[cfg_attr(not(windows), allow(dead_code))]
+  DataHovered {
+    window: u64,
+    mime_type: WsiDropType,
+    position: Option<(f64, f64)>,
+  },
+  #[serde(rename_all = "camelCase")]
+  #[cfg_attr(not(windows), allow(dead_code))]
+  DataLeft {
+    window: u64,
+  },
+  #[serde(rename_all = "camelCase")]
   DeviceAdded {
     device_id: u32,
   },
@@ -79,17 +120,48 @@ pub enum WsiEvent {
   FileDropped {
     window: u64,
     path: PathBuf,
+    position: Option<(f64, f64)>,
   },
   #[serde(rename_all = "camelCase")]
   FileHovered {
     window: u64,
     path: PathBuf,
+    position: Option<(f64, f64)>,
   },
   #[serde(rename_all = "camelCase")]
   FileLeft {
     window: u64,
   },
   #[serde(rename_all = "camelCase")]
+  FilesDropped {
+    window: u64,
+    paths: Vec<PathBuf>,
+    position: Option<(f64, f64)>,
+  },
+  // Input from anywhere on the desktop, from `global_input`. Positions are
+  // relative to the desktop.
+  #[serde(rename_all = "camelCase")]
+  GlobalCursorMoved {
+    position: (f64, f64),
+  },
+  #[serde(rename_all = "camelCase")]
+  GlobalKey {
+    scan_code: u32,
+    code: Option<&'static str>,
+    state: WsiButtonState,
+  },
+  #[serde(rename_all = "camelCase")]
+  GlobalMouseButton {
+    button: WsiMouseButton,
+    state: WsiButtonState,
+    position: (f64, f64),
+  },
+  #[serde(rename_all = "camelCase")]
+  GlobalMouseScroll {
+    delta: WsiScrollDelta,
+    position: (f64, f64),
+  },
+  #[serde(rename_all = "camelCase")]
   ImeCommit {
     window: u64,
     string: String,
@@ -126,7 +198,12 @@ pub enum WsiEvent {
     device_id: u32,
     scan_code: u32,
     key_code: Option<WsiKeyCode>,
+    code: Option<&'static str>,
+    key: String,
+    text: Option<String>,
     state: WsiButtonState,
+    modifiers: u32,
+    repeat: bool,
     is_synthetic: bool,
   },
   #[serde(rename_all = "camelCase")]
@@ -139,6 +216,20 @@ pub enum WsiEvent {
     finger_id: u64,
   },
   MainEventsCleared,
+  // Made by the driver, which compares the monitors with the ones it last
+  // saw.
+  #[serde(rename_all = "camelCase")]
+  MonitorChanged {
+    monitor: WsiMonitor,
+  },
+  #[serde(rename_all = "camelCase")]
+  MonitorConnected {
+    monitor: WsiMonitor,
+  },
+  #[serde(rename_all = "camelCase")]
+  MonitorDisconnected {
+    monitor: WsiMonitor,
+  },
   #[serde(rename_all = "camelCase")]
   ModifiersChanged {
     window: u64,
@@ -150,6 +241,7 @@ pub enum WsiEvent {
     device_id: u32,
     button: WsiMouseButton,
     state: WsiButtonState,
+    click_count: u32,
   },
   #[serde(rename_all = "camelCase")]
   MouseMotion {
@@ -164,6 +256,35 @@ pub enum WsiEvent {
     touch_phase: WsiTouchPhase,
   },
   NewEvents,
+  #[serde(rename_all = "camelCase")]
+  OpenUrl {
+    url: String,
+  },
+  // Made by the driver from events that winit reported for pens.
+  #[serde(rename_all = "camelCase")]
+  PenDown {
+    window: u64,
+    device_id: u32,
+    position: (f64, f64),
+    #[serde(flatten)]
+    pen: WsiPenState,
+  },
+  #[serde(rename_all = "camelCase")]
+  PenMoved {
+    window: u64,
+    device_id: u32,
+    position: (f64, f64),
+    #[serde(flatten)]
+    pen: WsiPenState,
+  },
+  #[serde(rename_all = "camelCase")]
+  PenUp {
+    window: u64,
+    device_id: u32,
+    position: (f64, f64),
+    #[serde(flatten)]
+    pen: WsiPenState,
+  },
   RedrawEventsCleared,
   #[serde(rename_all = "camelCase")]
   RedrawRequested {
@@ -174,11 +295,27 @@ pub enum WsiEvent {
     window: u64,
     scale_factor: f64,
   },
+  // Made by op_wsi_next_event when a file watched with Deno.wsi.watchShader()
+  // changes.
+  ShaderReloaded(WsiShaderCompilation),
   #[serde(rename_all = "camelCase")]
   SmartMagnify {
     window: u64,
     device_id: u32,
   },
+  // Where the soft keyboard covers the window's client area, as it starts to
+  // show or hide. Only Windows reports it.
+  #[serde(rename_all = "camelCase")]
+  #[cfg_attr(not(windows), allow(dead_code))]
+  SoftKeyboardChanged {
+    window: u64,
+    rect: Option<(i32, i32, u32, u32)>,
+  },
+  // Made by the driver when the system theme changes, once the runtime has
+  // asked for it.
+  SystemThemeChanged {
+    theme: WsiWindowTheme,
+  },
   #[serde(rename_all = "camelCase")]
   TouchpadMagnify {
     window: u64,
@@ -200,11 +337,32 @@ pub enum WsiEvent {
     delta: f32,
     touch_phase: WsiTouchPhase,
   },
+  // Sent by another extension through a `WsiUserEventSender`.
+  UserEvent {
+    kind: String,
+    payload: serde_json::Value,
+  },
+  // Made by the driver when the screen is being captured while the window's
+  // content is protected.
+  WindowCaptureDetected {
+    window: u64,
+  },
+  // Made by the driver when the color profile of the window's monitor
+  // changes.
+  WindowColorProfileChanged {
+    window: u64,
+  },
   #[serde(rename_all = "camelCase")]
   WindowFocus {
     window: u64,
     has_focus: bool,
   },
+  // Made by the driver when the monitor that the window is on changes.
+  #[serde(rename_all = "camelCase")]
+  WindowMonitorChanged {
+    window: u64,
+    monitor: Option<WsiMonitor>,
+  },
   #[serde(rename_all = "camelCase")]
   WindowMoved {
     window: u64,
@@ -215,11 +373,30 @@ pub enum WsiEvent {
     window: u64,
     is_occluded: bool,
   },
+  // Made by the driver when the orientation of the window's monitor changes,
+  // or the window moves to a monitor with another one.
+  WindowOrientationChanged {
+    window: u64,
+    orientation: WsiOrientation,
+  },
+  // Made by the driver when the refresh rate of the window's monitor changes,
+  // in millihertz, including when the window moves to another monitor.
+  #[serde(rename_all = "camelCase")]
+  WindowRefreshRateChanged {
+    window: u64,
+    refresh_rate: Option<u32>,
+  },
   #[serde(rename_all = "camelCase")]
   WindowResized {
     window: u64,
     inner_size: (u32, u32),
   },
+  // Made by the driver when the window's safe area insets change, as (top,
+  // right, bottom, left).
+  WindowSafeAreaChanged {
+    window: u64,
+    insets: (u32, u32, u32, u32),
+  },
   #[serde(rename_all = "camelCase")]
   WindowThemeChanged {
     window: u64,
@@ -244,8 +421,17 @@ impl WsiEvent {
           },
           WindowEvent::CloseRequested => Self::CloseRequested { window },
           WindowEvent::Destroyed => Self::Internal,
-          WindowEvent::DroppedFile(path) => Self::FileDropped { window, path },
-          WindowEvent::HoveredFile(path) => Self::FileHovered { window, path },
+          // The driver fills in the positions.
+          WindowEvent::DroppedFile(path) => Self::FileDropped {
+            window,
+            path,
+            position: None,
+          },
+          WindowEvent::HoveredFile(path) => Self::FileHovered {
+            window,
+            path,
+            position: None,
+          },
           WindowEvent::HoveredFileCancelled => Self::FileLeft { window },
           WindowEvent::ReceivedCharacter(c) => Self::InputChar {
             window,
@@ -254,6 +440,8 @@ impl WsiEvent {
           WindowEvent::Focused(has_focus) => {
             Self::WindowFocus { window, has_focus }
           }
+          // The driver fills in what the key means in the keyboard layout, the
+          // modifiers and whether it's a repeat.
           WindowEvent::KeyboardInput {
             device_id,
             input,
@@ -263,7 +451,12 @@ impl WsiEvent {
             device_id: device_ids.get(device_id),
             scan_code: input.scancode,
             key_code: input.virtual_keycode.map(WsiKeyCode),
+            code: None,
+            key: String::new(),
+            text: None,
             state: input.state.into(),
+            modifiers: 0,
+            repeat: false,
             is_synthetic,
           },
           WindowEvent::ModifiersChanged(modifiers) => Self::ModifiersChanged {
@@ -313,6 +506,7 @@ impl WsiEvent {
             touch_phase: phase.into(),
           },
           #[allow(deprecated)]
+          // The driver fills in the click count.
           WindowEvent::MouseInput {
             device_id,
             state,
@@ -323,6 +517,7 @@ impl WsiEvent {
             device_id: device_ids.get(device_id),
             button: button.into(),
             state: state.into(),
+            click_count: 0,
           },
           WindowEvent::TouchpadMagnify {
             device_id,
@@ -436,7 +631,54 @@ impl WsiEvent {
         window: window_id.into(),
       },
       Event::RedrawEventsCleared => Self::RedrawEventsCleared,
-      Event::LoopDestroyed => Self::Internal,
+      Event::LoopDestroyed => Self::AppWillTerminate,
+    }
+  }
+
+  // Whether this is raw input from a device, which isn't tied to a window.
+  pub fn is_device_input(&self) -> bool {
+    matches!(
+      self,
+      Self::DeviceAxis { .. }
+        | Self::DeviceButton { .. }
+        | Self::DeviceChar { .. }
+        | Self::DeviceKey { .. }
+        | Self::DeviceScroll { .. }
+        | Self::MouseMotion { .. }
+    )
+  }
+
+  // Whether this is input from the whole desktop, from `global_input`.
+  pub fn is_global_input(&self) -> bool {
+    matches!(
+      self,
+      Self::GlobalCursorMoved { .. }
+        | Self::GlobalKey { .. }
+        | Self::GlobalMouseButton { .. }
+        | Self::GlobalMouseScroll { .. }
+    )
+  }
+
+  // The window that receives this event if it's user input.
+  pub fn input_window(&self) -> Option<u64> {
+    match *self {
+      Self::CursorMoved { window, .. }
+      | Self::ImeCommit { window, .. }
+      | Self::ImePreedit { window, .. }
+      | Self::InputAxis { window, .. }
+      | Self::InputChar { window, .. }
+      | Self::InputKey { window, .. }
+      | Self::InputTouch { window, .. }
+      | Self::MouseButton { window, .. }
+      | Self::MouseScroll { window, .. }
+      | Self::PenDown { window, .. }
+      | Self::PenMoved { window, .. }
+      | Self::PenUp { window, .. }
+      | Self::SmartMagnify { window, .. }
+      | Self::TouchpadMagnify { window, .. }
+      | Self::TouchpadPressure { window, .. }
+      | Self::TouchpadRotate { window, .. } => Some(window),
+      _ => None,
     }
   }
 }
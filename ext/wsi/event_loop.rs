@@ -1,140 +1,805 @@
 // Copyright 2023 Jo Bates. All rights reserved. MIT license.
 
 use crate::{
+  aspect_ratio::WsiAspectRatios,
+  cleanup,
+  click::ClickCounter,
+  content_protection::CaptureWatcher,
+  cursor,
   device_ids::DeviceIds,
+  device_motion::DeviceMotionLimiter,
+  diagnostics::ExecuteLatency,
+  drag_drop, enabled,
   event::WsiEvent,
-  request::{ExecuteRequestFn, Request},
+  gestures::{self, TouchGestures},
+  global_input,
+  hit_test::{self, HitTestWakeFilter},
+  hot_reload::WsiHotReload,
+  keyboard::KeyboardState,
+  lifecycle::{self, LifecycleWatcher},
+  macos,
+  monitor::MonitorWatcher,
+  pen,
+  request::{
+    self, ExecuteRequest, ExecuteRequestCanceller, ExecuteRequestFn, Request,
+    RequestReceiver, RequestSender, SendTimeoutError,
+  },
+  soft_keyboard,
+  theme::SystemThemeWatcher,
+  trace,
+  user_event::{WsiUserEventQueue, WsiUserEventSender},
 };
 use deno_core::anyhow;
+use serde::Deserialize;
 use std::{
-  cell::Cell, collections::HashMap, rc::Rc, sync::mpsc as std_mpsc, thread,
+  cell::{Cell, RefCell},
+  collections::{HashMap, HashSet, VecDeque},
+  panic::Location,
+  rc::Rc,
+  str::FromStr,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc as std_mpsc, Arc,
+  },
+  thread,
+  time::{Duration, Instant},
 };
-use tokio::sync::mpsc as tokio_mpsc;
+use tokio::sync::{mpsc as tokio_mpsc, Mutex as AsyncMutex};
 use winit::{
-  event_loop::{EventLoop, EventLoopProxy, EventLoopWindowTarget},
+  dpi::PhysicalSize,
+  event::{Event, WindowEvent},
+  event_loop::{
+    ControlFlow, EventLoop, EventLoopBuilder, EventLoopProxy,
+    EventLoopWindowTarget,
+  },
   window::Window,
 };
 
+// How long the runtime waits on the event loop before giving up on a request.
+// Requests usually take microseconds, so this only trips when the event loop
+// thread is stuck.
+const STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+// How the real event loop waits between iterations.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiControlFlow {
+  // Sleep until the next event arrives.
+  #[default]
+  Wait,
+  // Start the next iteration immediately, even without new events.
+  Poll,
+}
+
+impl From<WsiControlFlow> for ControlFlow {
+  fn from(control_flow: WsiControlFlow) -> Self {
+    match control_flow {
+      WsiControlFlow::Wait => Self::Wait,
+      WsiControlFlow::Poll => Self::Poll,
+    }
+  }
+}
+
+// The window system that the real event loop connects to on Linux and the
+// BSDs, where both X11 and Wayland may be available. By default, winit picks
+// Wayland if a compositor is running.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WsiUnixBackend {
+  X11,
+  Wayland,
+}
+
+impl FromStr for WsiUnixBackend {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "x11" => Ok(Self::X11),
+      "wayland" => Ok(Self::Wayland),
+      _ => Err(anyhow::anyhow!(
+        "Unknown window system backend '{s}', expected 'wayland' or 'x11'"
+      )),
+    }
+  }
+}
+
 // Spawn a proxy thread and hijack the calling thread for the real event loop.
 // On some platforms (e.g. macOS), this needs to be called from the main thread.
-pub fn hijack_main_and_spawn_proxy<F>(f: F) -> !
+// The backend is ignored on platforms with a single window system.
+pub fn hijack_main_and_spawn_proxy<F>(
+  control_flow: WsiControlFlow,
+  backend: Option<WsiUnixBackend>,
+  f: F,
+) -> !
 where
   F: FnOnce(Rc<WsiEventLoopProxy>) + Send + 'static,
 {
   // Initialize.
-  let event_loop = EventLoop::new();
-  let event_loop_proxy = event_loop.create_proxy();
-  let (event_tx, event_rx) = tokio_mpsc::channel(1);
-  let (request_tx, mut request_rx) = std_mpsc::sync_channel(1);
+  let event_loop = build_event_loop(backend);
+  let (mut driver, connection) = connect(&event_loop);
 
   // Spawn the proxy thread.
   thread::spawn(move || {
-    let wsi_event_loop_proxy = Rc::new(WsiEventLoopProxy {
-      event_loop_proxy,
-      waiting_for_event: Cell::new(false),
-      event_rx: Cell::new(Some(event_rx)),
-      request_tx,
-    });
+    let wsi_event_loop_proxy = connection.into_proxy();
     let _retain = wsi_event_loop_proxy.clone();
     f(wsi_event_loop_proxy);
   });
 
-  // Handle requests until the proxy thread is ready for the first event.
-  let mut windows = HashMap::new();
-  handle_requests(&mut request_rx, &event_loop, &mut windows);
-
   // Run the real event loop.
-  let mut device_ids = DeviceIds::new();
-  event_loop.run(move |event, window_target, control_flow| {
-    let event = WsiEvent::from(event, &mut device_ids);
-    event_tx.blocking_send(event).unwrap();
-    handle_requests(&mut request_rx, window_target, &mut windows);
-    control_flow.set_wait();
+  driver.start(&event_loop);
+  event_loop.run(move |event, window_target, flow| {
+    *flow = control_flow.into();
+    if !driver.handle_event(event, window_target) && driver.is_exiting() {
+      flow.set_exit();
+    }
   });
+}
+
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+fn build_event_loop(backend: Option<WsiUnixBackend>) -> EventLoop<()> {
+  use winit::platform::{
+    wayland::EventLoopBuilderExtWayland, x11::EventLoopBuilderExtX11,
+  };
+  let mut builder = EventLoopBuilder::new();
+  match backend {
+    Some(WsiUnixBackend::X11) => {
+      builder.with_x11();
+    }
+    Some(WsiUnixBackend::Wayland) => {
+      builder.with_wayland();
+    }
+    None => {}
+  }
+  builder.build()
+}
+
+#[cfg(not(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+)))]
+fn build_event_loop(_backend: Option<WsiUnixBackend>) -> EventLoop<()> {
+  EventLoopBuilder::new().build()
+}
+
+// Connect an event loop owned by the caller to a JS runtime on another
+// thread. This is how a host application embeds the runtime while driving its
+// own winit event loop: the driver stays with the event loop and the
+// connection is moved to the runtime thread, where it becomes the
+// `WsiEventLoopProxy` passed to the worker.
+pub fn connect(
+  event_loop: &EventLoop<()>,
+) -> (WsiEventLoopDriver, WsiEventLoopConnection) {
+  let (event_tx, event_rx) = tokio_mpsc::channel(1);
+  let (request_tx, request_rx) = request::request_channel();
+  lifecycle::install_quit_handler(event_loop.create_proxy());
+  cleanup::install_restore_hook(request_tx.clone(), event_loop.create_proxy());
+  let aspect_ratios = WsiAspectRatios::default();
+  let paused = Arc::new(AtomicBool::new(false));
+  let driver = WsiEventLoopDriver {
+    event_tx,
+    request_rx,
+    paused: paused.clone(),
+    pending_events: VecDeque::new(),
+    awaiting_runtime: true,
+    event_sent: None,
+    windows: HashMap::new(),
+    device_ids: DeviceIds::new(),
+    device_motion: DeviceMotionLimiter::new(event_loop.create_proxy()),
+    keyboard: KeyboardState::default(),
+    clicks: ClickCounter::default(),
+    gestures: TouchGestures::default(),
+    aspect_ratios: aspect_ratios.clone(),
+    monitors: MonitorWatcher::default(),
+    lifecycle: LifecycleWatcher::default(),
+    capture: CaptureWatcher::default(),
+    system_theme: SystemThemeWatcher::default(),
+    hit_test_wakes: HitTestWakeFilter::default(),
+    dropped_files: None,
+  };
+  let connection = WsiEventLoopConnection {
+    event_loop_proxy: event_loop.create_proxy(),
+    event_rx,
+    request_tx,
+    aspect_ratios,
+    paused,
+  };
+  (driver, connection)
+}
+
+// The event loop side of a connection to a JS runtime. It owns the windows
+// the runtime creates, so it must live on the event loop thread.
+pub struct WsiEventLoopDriver {
+  event_tx: tokio_mpsc::Sender<(WsiEvent, Instant)>,
+  request_rx: RequestReceiver,
+  paused: Arc<AtomicBool>,
+  // Events that the runtime hasn't been sent yet, because it hasn't asked for
+  // the next event or the event loop is paused.
+  pending_events: VecDeque<(WsiEvent, Instant)>,
+  // Whether the runtime has yet to ask for the next event, and when it was
+  // sent the last one.
+  awaiting_runtime: bool,
+  event_sent: Option<Instant>,
+  windows: HashMap<u64, Window>,
+  device_ids: DeviceIds,
+  device_motion: DeviceMotionLimiter,
+  keyboard: KeyboardState,
+  clicks: ClickCounter,
+  gestures: TouchGestures,
+  aspect_ratios: WsiAspectRatios,
+  monitors: MonitorWatcher,
+  lifecycle: LifecycleWatcher,
+  capture: CaptureWatcher,
+  system_theme: SystemThemeWatcher,
+  hit_test_wakes: HitTestWakeFilter,
+  // Files dropped together so far, as a `FilesDropped` event.
+  dropped_files: Option<WsiEvent>,
+}
+
+impl WsiEventLoopDriver {
+  // Handle requests until the runtime is ready for the first event. Call this
+  // once before running the event loop. Returns `false` if the runtime has
+  // shut down.
+  pub fn start(&mut self, window_target: &EventLoopWindowTarget<()>) -> bool {
+    macos::install_url_handler();
+    self.send_pending_events(window_target)
+  }
+
+  // Deliver an event from the event loop to the runtime, then handle its
+  // requests until it's ready for the next event. Call this for every event,
+  // including `Event::UserEvent(())`, which the runtime sends to wake the
+  // event loop when it has requests. Returns `false` if the runtime has shut
+  // down or the app is exiting, after which the driver ignores further events.
+  pub fn handle_event(
+    &mut self,
+    mut event: Event<()>,
+    window_target: &EventLoopWindowTarget<()>,
+  ) -> bool {
+    let delivered = Instant::now();
+    self.handle_waiting_requests(window_target);
+    if let Event::WindowEvent {
+      window_id,
+      event: WindowEvent::Resized(size),
+    } = &mut event
+    {
+      self.constrain_resize(u64::from(*window_id), size);
+    }
+    hit_test::update_hit_tests(&self.windows);
+    self.hit_test_wakes.update(&event);
+    let mut event = WsiEvent::from(event, &mut self.device_ids);
+    self.locate_drop(&mut event);
+    cursor::confine_cursor(&mut event, &self.windows);
+    self.keyboard.update(&mut event, &self.windows);
+    self.clicks.update(&mut event);
+    let monitors = self.monitors.update(&event, window_target, &self.windows);
+    let lifecycle = self.lifecycle.update(&event, &self.windows);
+    let capture = self.capture.update(&self.windows);
+    let system_theme = self.system_theme.update(&event);
+    // Pen events replace the touch events that winit reports pens as on
+    // Windows, and follow the mouse events it reports them as on macOS, which
+    // move the cursor too. Recentered cursors don't move as far as the
+    // runtime knows, and disabled windows get no input.
+    let events = match pen::pen_event(&event, &self.windows) {
+      _ if cursor::recenter_cursor(&event, &self.windows) => vec![],
+      _ if enabled::is_blocked(&event) => vec![],
+      Some(pen_event) if matches!(event, WsiEvent::InputTouch { .. }) => {
+        vec![pen_event]
+      }
+      Some(pen_event) => vec![event, pen_event],
+      None => vec![event],
+    };
+    // Two fingers on a touch screen also pinch and rotate.
+    let events: Vec<_> = events
+      .into_iter()
+      .flat_map(|event| {
+        let gesture_events = self.gestures.update(&event);
+        Some(event).into_iter().chain(gesture_events)
+      })
+      .collect();
+    // Device motion is delivered at most once per interval, see
+    // `DeviceMotionLimiter`.
+    let events: Vec<_> = events
+      .into_iter()
+      .filter_map(|event| self.device_motion.hold(event))
+      .collect();
+    for event in events {
+      if !self.deliver(event, delivered, window_target) {
+        return false;
+      }
+    }
+
+    // AppKit calls menu item actions and Apple Event handlers, OLE calls drop
+    // targets, the shell calls input pane handlers and Wayland gestures are
+    // read while winit dispatches events, so deliver their events after the
+    // event that was being dispatched. Global input hooks wake the event loop
+    // to have theirs delivered. Device motion that was held back follows once
+    // its interval is over. Monitor, lifecycle, capture and system theme
+    // changes follow the event that they were noticed on.
+    let menu_items = macos::take_selected_menu_items()
+      .into_iter()
+      .map(|id| WsiEvent::AppMenuItem { id });
+    let urls = macos::take_opened_urls()
+      .into_iter()
+      .map(|url| WsiEvent::OpenUrl { url });
+    let drops = drag_drop::take_drop_events();
+    let soft_keyboard = soft_keyboard::take_soft_keyboard_events();
+    let gestures =
+      gestures::take_gesture_events(&self.windows, &mut self.device_ids);
+    let global_input = global_input::take_global_input_events();
+    let device_motion = self.device_motion.take_due();
+    for event in menu_items
+      .chain(urls)
+      .chain(drops)
+      .chain(soft_keyboard)
+      .chain(gestures)
+      .chain(global_input)
+      .chain(device_motion)
+      .chain(monitors)
+      .chain(lifecycle)
+      .chain(capture)
+      .chain(system_theme)
+    {
+      if !self.deliver(event, Instant::now(), window_target) {
+        return false;
+      }
+    }
+    if self.is_exiting() {
+      cleanup::restore_windows(&self.windows);
+      return false;
+    }
+    true
+  }
+
+  // Whether the app agreed to quit and has been told it will terminate, in
+  // which case the caller should exit the event loop, where the platform
+  // doesn't end the process itself. The runtime has handled `AppWillTerminate`
+  // by then, unless the event loop is paused.
+  pub fn is_exiting(&self) -> bool {
+    self.lifecycle.is_exiting() && !self.awaiting_runtime
+  }
+
+  fn deliver(
+    &mut self,
+    event: WsiEvent,
+    delivered: Instant,
+    window_target: &EventLoopWindowTarget<()>,
+  ) -> bool {
+    for event in self.hit_test_wakes.filter(event) {
+      let dropped_files = self.batch_dropped_files(&event);
+      for event in dropped_files.into_iter().chain(Some(event)) {
+        self.pending_events.push_back((event, delivered));
+      }
+    }
+    self.send_pending_events(window_target)
+  }
+
+  // Send pending events to the runtime one at a time, handling its requests
+  // until it's ready for the next one. While the event loop is paused, stop
+  // waiting on the runtime and leave the rest pending.
+  fn send_pending_events(
+    &mut self,
+    window_target: &EventLoopWindowTarget<()>,
+  ) -> bool {
+    loop {
+      if self.awaiting_runtime {
+        match self.handle_requests(window_target) {
+          RuntimeState::Ready => self.awaiting_runtime = false,
+          RuntimeState::Paused => return true,
+          RuntimeState::Gone => return false,
+        }
+        if let Some(sent) = self.event_sent.take() {
+          trace::trace_event_handled(sent, Instant::now());
+        }
+      }
+      let Some((event, delivered)) = self.pending_events.pop_front() else {
+        return true;
+      };
+      let sent = Instant::now();
+      trace::trace_event(&event, delivered, sent);
+      if self.event_tx.blocking_send((event, delivered)).is_err() {
+        return false;
+      }
+      self.awaiting_runtime = true;
+      self.event_sent = Some(sent);
+    }
+  }
+
+  // Files dropped together arrive one `FileDropped` event after another, so
+  // collect them into a `FilesDropped` event. It's delivered before the next
+  // event that isn't one of them, which winit sends right after a drop.
+  fn batch_dropped_files(&mut self, event: &WsiEvent) -> Option<WsiEvent> {
+    let WsiEvent::FileDropped {
+      window,
+      path,
+      position,
+    } = event
+    else {
+      return self.dropped_files.take();
+    };
+    if let Some(WsiEvent::FilesDropped {
+      window: batch_window,
+      paths,
+      ..
+    }) = &mut self.dropped_files
+    {
+      if batch_window == window {
+        paths.push(path.clone());
+        return None;
+      }
+    }
+    self.dropped_files.replace(WsiEvent::FilesDropped {
+      window: *window,
+      paths: vec![path.clone()],
+      position: *position,
+    })
+  }
+
+  // Winit doesn't say where files are dragged, so use the cursor position.
+  fn locate_drop(&self, event: &mut WsiEvent) {
+    if let WsiEvent::FileDropped {
+      window, position, ..
+    }
+    | WsiEvent::FileHovered {
+      window, position, ..
+    } = event
+    {
+      if let Some(window) = self.windows.get(window) {
+        *position = drag_drop::cursor_position(window);
+      }
+    }
+  }
 
-  // Handle requests until the proxy thread is ready for the next event.
+  // Keep a window with a locked aspect ratio at that ratio while the user
+  // resizes it. The runtime already sees the adjusted size, so it never
+  // renders a frame at the wrong ratio.
+  fn constrain_resize(&mut self, wid: u64, size: &mut PhysicalSize<u32>) {
+    let Some(constrained) = self.aspect_ratios.constrain(wid, *size) else {
+      return;
+    };
+    if constrained != *size {
+      if let Some(window) = self.windows.get(&wid) {
+        window.set_inner_size(constrained);
+      }
+      *size = constrained;
+    }
+  }
+
+  // Handle requests until the runtime is ready for the next event. While the
+  // event loop is paused, only handle the requests that are already waiting.
   fn handle_requests(
-    request_rx: &mut std_mpsc::Receiver<Request>,
+    &mut self,
     window_target: &EventLoopWindowTarget<()>,
-    windows: &mut HashMap<u64, Window>,
-  ) {
+  ) -> RuntimeState {
     loop {
-      match request_rx.recv().unwrap() {
-        Request::NextEvent => break,
-        Request::Execute(f) => f(window_target, windows),
+      let request = if self.paused.load(Ordering::SeqCst) {
+        match self.request_rx.try_recv() {
+          Ok(request) => Ok(request),
+          Err(std_mpsc::TryRecvError::Empty) => return RuntimeState::Paused,
+          Err(std_mpsc::TryRecvError::Disconnected) => Err(()),
+        }
+      } else {
+        self.request_rx.recv().map_err(|_| ())
+      };
+      match request {
+        Ok(Request::NextEvent) => return RuntimeState::Ready,
+        Ok(Request::Execute(request)) => self.execute(request, window_target),
+        // Sent to stop waiting on the runtime.
+        Ok(Request::Pause) => {}
+        Err(()) => return RuntimeState::Gone,
       }
     }
   }
+
+  // Handle the execute requests that the runtime made while it waits for an
+  // event, e.g. from a timer, before the event loop's next event. Otherwise
+  // they'd wait for the next event that reaches the runtime, which during a
+  // burst of input that's held back or dropped can take a while, since the
+  // event that wakes the event loop for them is queued behind the burst.
+  fn handle_waiting_requests(
+    &mut self,
+    window_target: &EventLoopWindowTarget<()>,
+  ) {
+    if self.awaiting_runtime {
+      return;
+    }
+    while let Ok(request) = self.request_rx.try_recv() {
+      match request {
+        Request::Execute(request) => self.execute(request, window_target),
+        // The runtime already asked for the next event, and a pause is
+        // noticed when the event is sent.
+        Request::NextEvent | Request::Pause => {}
+      }
+    }
+  }
+
+  fn execute(
+    &mut self,
+    request: ExecuteRequest,
+    window_target: &EventLoopWindowTarget<()>,
+  ) {
+    // The runtime gave up on it.
+    if !request.claim() {
+      return;
+    }
+    let started = Instant::now();
+    (request.f)(window_target, &mut self.windows);
+    trace::trace_request(request.caller, request.sent, started, Instant::now());
+  }
+}
+
+enum RuntimeState {
+  // The runtime asked for the next event.
+  Ready,
+  // The event loop was paused before it did.
+  Paused,
+  // The runtime shut down.
+  Gone,
+}
+
+// Pauses and resumes the event loop from any thread. While it's paused, the
+// event loop doesn't wait on the runtime, so the window system keeps getting
+// answers even though the runtime is stopped, e.g. at a breakpoint. Events are
+// held until it resumes.
+#[derive(Clone)]
+pub struct WsiEventLoopPause {
+  paused: Arc<AtomicBool>,
+  request_tx: RequestSender,
+  event_loop_proxy: EventLoopProxy<()>,
+}
+
+impl WsiEventLoopPause {
+  pub fn pause(&self) {
+    if !self.paused.swap(true, Ordering::SeqCst) {
+      // If a request is already waiting, the event loop notices the pause
+      // once it has handled it.
+      let _ = self.request_tx.try_send(Request::Pause);
+    }
+  }
+
+  pub fn resume(&self) {
+    if self.paused.swap(false, Ordering::SeqCst) {
+      // Have the event loop send the held events.
+      let _ = self.event_loop_proxy.send_event(());
+    }
+  }
+}
+
+// The runtime side of a connection to an event loop, before it's turned into
+// a `WsiEventLoopProxy` on the runtime thread.
+pub struct WsiEventLoopConnection {
+  event_loop_proxy: EventLoopProxy<()>,
+  event_rx: tokio_mpsc::Receiver<(WsiEvent, Instant)>,
+  request_tx: RequestSender,
+  aspect_ratios: WsiAspectRatios,
+  paused: Arc<AtomicBool>,
+}
+
+impl WsiEventLoopConnection {
+  pub fn into_proxy(self) -> Rc<WsiEventLoopProxy> {
+    cleanup::set_runtime_thread();
+    let user_events =
+      Arc::new(WsiUserEventQueue::new(self.event_loop_proxy.clone()));
+    Rc::new(WsiEventLoopProxy {
+      event_loop_proxy: self.event_loop_proxy,
+      waiting_for_event: Cell::new(false),
+      event_requested: Cell::new(false),
+      event_rx: AsyncMutex::new(self.event_rx),
+      request_tx: self.request_tx,
+      user_events,
+      user_event_kinds: RefCell::new(HashSet::new()),
+      aspect_ratios: self.aspect_ratios,
+      execute_latency: ExecuteLatency::default(),
+      paused: self.paused,
+      hot_reload: WsiHotReload::default(),
+    })
+  }
 }
 
 // Event loop proxy.
 pub struct WsiEventLoopProxy {
   event_loop_proxy: EventLoopProxy<()>,
   waiting_for_event: Cell<bool>,
-  event_rx: Cell<Option<tokio_mpsc::Receiver<WsiEvent>>>,
-  request_tx: std_mpsc::SyncSender<Request>,
+  // Whether the event loop was asked for an event that hasn't been received
+  // yet, because the call that asked for it was cancelled.
+  event_requested: Cell<bool>,
+  event_rx: AsyncMutex<tokio_mpsc::Receiver<(WsiEvent, Instant)>>,
+  request_tx: RequestSender,
+  user_events: Arc<WsiUserEventQueue>,
+  user_event_kinds: RefCell<HashSet<Arc<str>>>,
+  aspect_ratios: WsiAspectRatios,
+  execute_latency: ExecuteLatency,
+  paused: Arc<AtomicBool>,
+  hot_reload: WsiHotReload,
 }
 
 impl WsiEventLoopProxy {
-  // Get the next event from the real event loop.
-  // Don't call this multiple times concurrently.
-  pub(crate) async fn next_event(&self) -> Result<WsiEvent, anyhow::Error> {
-    // Take the receiver for exclusive use.
-    let Some(mut event_rx) = self.event_rx.take() else {
-      return Err(anyhow::Error::msg("Receiver already in use"));
-    };
+  // Register a kind of user event that another extension can send into the
+  // WSI event stream, e.g. from the `Rc<WsiEventLoopProxy>` in its `OpState`.
+  // Each kind can only be registered once, so that two extensions can't
+  // mistake each other's events for their own.
+  pub fn register_user_event<T>(
+    &self,
+    kind: &str,
+  ) -> Result<WsiUserEventSender<T>, anyhow::Error> {
+    let kind: Arc<str> = kind.into();
+    if !self.user_event_kinds.borrow_mut().insert(kind.clone()) {
+      return Err(anyhow::anyhow!(
+        "User event kind '{kind}' is already registered"
+      ));
+    }
+    Ok(WsiUserEventSender::new(kind, self.user_events.clone()))
+  }
 
-    // Send the request.
-    self.request_tx.send(Request::NextEvent).unwrap();
+  // Get the next event from the real event loop, along with the time it was
+  // delivered there. User events that are already queued come first.
+  // Concurrent calls take turns, each getting an event of its own. The future
+  // can be dropped at any await, e.g. when JS aborts the call or --watch
+  // restarts the runtime, in which case the event it asked for goes to the
+  // next call.
+  pub(crate) async fn next_event(
+    &self,
+  ) -> Result<(WsiEvent, Instant), anyhow::Error> {
+    if let Some((kind, payload)) = self.user_events.pop() {
+      let kind = kind.to_string();
+      return Ok((WsiEvent::UserEvent { kind, payload }, Instant::now()));
+    }
 
-    // Async wait for the event.
-    self.waiting_for_event.set(true);
-    let event = event_rx.recv().await.unwrap();
-    self.waiting_for_event.set(false);
+    // Wait for the receiver to be free.
+    let mut event_rx = self.event_rx.lock().await;
+
+    // Send the request, unless a cancelled call already did. The event loop
+    // only sends an event per request, so asking again would leave it one
+    // event ahead of the runtime from then on.
+    if !self.event_requested.get() {
+      self.send_request(Request::NextEvent)?;
+      self.event_requested.set(true);
+      self.waiting_for_event.set(true);
+    }
 
-    // Save the receiver for re-use.
-    self.event_rx.set(Some(event_rx));
+    // Async wait for the event. Until it arrives, execute requests have to
+    // wake the event loop, so `waiting_for_event` stays set if this future is
+    // dropped.
+    let event = event_rx
+      .recv()
+      .await
+      .ok_or_else(|| anyhow::anyhow!("The event loop is gone"))?;
+    self.event_requested.set(false);
+    self.waiting_for_event.set(false);
 
     // Return the event.
     Ok(event)
   }
 
+  pub(crate) fn aspect_ratios(&self) -> &WsiAspectRatios {
+    &self.aspect_ratios
+  }
+
+  pub(crate) fn execute_latency(&self) -> &ExecuteLatency {
+    &self.execute_latency
+  }
+
+  pub(crate) fn hot_reload(&self) -> &WsiHotReload {
+    &self.hot_reload
+  }
+
+  // A handle that pauses the real event loop, e.g. while a debugger has the
+  // runtime stopped.
+  pub fn pause_handle(&self) -> WsiEventLoopPause {
+    WsiEventLoopPause {
+      paused: self.paused.clone(),
+      request_tx: self.request_tx.clone(),
+      event_loop_proxy: self.event_loop_proxy.clone(),
+    }
+  }
+
+  // A proxy that wakes the real event loop, for use on its thread.
+  pub(crate) fn event_loop_proxy(&self) -> EventLoopProxy<()> {
+    self.event_loop_proxy.clone()
+  }
+
+  // Send a request to the real event loop, which takes one at a time. Give up
+  // after `STALL_TIMEOUT` if it doesn't take the last one, rather than block
+  // the runtime forever.
+  #[track_caller]
+  fn send_request(&self, request: Request) -> Result<(), anyhow::Error> {
+    let caller = Location::caller();
+    match self.request_tx.send_timeout(request, STALL_TIMEOUT) {
+      Ok(()) => Ok(()),
+      Err(SendTimeoutError::Timeout) => Err(stalled_error(caller)),
+      Err(SendTimeoutError::Disconnected) => {
+        Err(anyhow::anyhow!("The event loop is gone"))
+      }
+    }
+  }
+
   // Send an execute request from the proxy thread to the real event loop.
-  fn send_execute_request(&self, f: Box<ExecuteRequestFn>) {
-    self.request_tx.send(Request::Execute(f)).unwrap();
+  #[track_caller]
+  fn send_execute_request(
+    &self,
+    f: Box<ExecuteRequestFn>,
+  ) -> Result<ExecuteRequestCanceller, anyhow::Error> {
+    let request = ExecuteRequest::new(f);
+    let canceller = request.canceller();
+    self.send_request(Request::Execute(request))?;
 
     // Send an event to the real event loop if the proxy thread is currently
     // waiting to receive an event. The real event loop might be waiting on an
-    // event too and won't process this request until it receives one.
-    if self.waiting_for_event.get() {
-      self.event_loop_proxy.send_event(()).unwrap();
+    // event too and won't process this request until it receives one. It
+    // waits on events while it's paused as well.
+    if self.waiting_for_event.get() || self.paused.load(Ordering::SeqCst) {
+      self
+        .event_loop_proxy
+        .send_event(())
+        .map_err(|_| anyhow::anyhow!("The event loop is gone"))?;
 
       // We don't need to do this again until we request the next event.
       self.waiting_for_event.set(false);
     }
+    Ok(canceller)
   }
 
-  // Execute the given function in the real event loop thread.
-  pub(crate) fn execute<F, R>(&self, f: F) -> R
+  // Execute the given function in the real event loop thread. Fails if the
+  // event loop doesn't get to it within `STALL_TIMEOUT`, e.g. because a
+  // platform call on its thread never returned, in which case the function
+  // doesn't run later, unless it had already started.
+  #[track_caller]
+  pub(crate) fn execute<F, R>(&self, f: F) -> Result<R, anyhow::Error>
   where
     F: FnOnce(&EventLoopWindowTarget<()>, &mut HashMap<u64, Window>) -> R,
     F: Send + 'static,
     R: Send + 'static,
   {
+    let caller = Location::caller();
     let (result_tx, result_rx) = std_mpsc::sync_channel(0);
-    self.send_execute_request(Box::new(move |window_target, windows| {
-      result_tx.send(f(window_target, windows)).unwrap();
-    }));
-    result_rx.recv().unwrap()
+    let sent = Instant::now();
+    let canceller =
+      self.send_execute_request(Box::new(move |window_target, windows| {
+        let latency = sent.elapsed();
+        // The runtime stops waiting if the event loop stalled.
+        let _ = result_tx.send((latency, f(window_target, windows)));
+      }))?;
+    let timeout = STALL_TIMEOUT.saturating_sub(sent.elapsed());
+    let (latency, result) = match result_rx.recv_timeout(timeout) {
+      Ok(result) => result,
+      Err(std_mpsc::RecvTimeoutError::Timeout) => {
+        // E.g. `build_window` mustn't create a window that nobody owns.
+        canceller.cancel();
+        return Err(stalled_error(caller));
+      }
+      Err(std_mpsc::RecvTimeoutError::Disconnected) => {
+        return Err(anyhow::anyhow!("The event loop is gone"));
+      }
+    };
+    self.execute_latency.record(latency);
+    Ok(result)
   }
 
   // Execute the given function in the real event loop with the given window.
-  pub(crate) fn execute_with_window<F, R>(&self, wid: u64, f: F) -> R
+  // Fails if the window was destroyed.
+  #[track_caller]
+  pub(crate) fn execute_with_window<F, R>(
+    &self,
+    wid: u64,
+    f: F,
+  ) -> Result<R, anyhow::Error>
   where
     F: FnOnce(&Window) -> R,
     F: Send + 'static,
     R: Send + 'static,
   {
-    self.execute(move |_, windows| f(windows.get(&wid).unwrap()))
+    self
+      .execute(move |_, windows| windows.get(&wid).map(f))?
+      .ok_or_else(|| anyhow::anyhow!("The window was destroyed"))
   }
 }
+
+// The caller is in the op that made the request, so the error says which.
+fn stalled_error(caller: &Location) -> anyhow::Error {
+  anyhow::anyhow!(
+    "The event loop didn't handle a request from {caller} within {} seconds. \
+    Something on the event loop thread is stuck.",
+    STALL_TIMEOUT.as_secs()
+  )
+}
@@ -0,0 +1,387 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// Pinch and rotation gestures, which winit only reports for touchpads on
+// macOS. Elsewhere, two fingers moving on a touch screen are recognized from
+// touch events, and Wayland compositors report touchpad pinches through the
+// pointer gestures protocol. Those events are queued and delivered by the
+// event loop driver. Windows turns touchpad pinches into Ctrl+scrolling, and
+// X11 reports touchpad gestures only to winit's own connection, so there are
+// no touchpad gestures there yet.
+
+use crate::{device_ids::DeviceIds, event::WsiEvent, input::WsiTouchPhase};
+use std::collections::HashMap;
+use winit::window::Window;
+
+// Start receiving touchpad gestures when the first window is created.
+pub fn register_gesture_handler(window: &Window) {
+  platform::register_gesture_handler(window)
+}
+
+// The touchpad gestures received since the last call.
+pub fn take_gesture_events(
+  windows: &HashMap<u64, Window>,
+  device_ids: &mut DeviceIds,
+) -> Vec<WsiEvent> {
+  platform::take_gesture_events(windows, device_ids)
+}
+
+// Recognizes pinches and rotations from the first two fingers that touch a
+// window, reported the way winit reports touchpad gestures on macOS.
+#[derive(Default)]
+pub struct TouchGestures {
+  touches: HashMap<(u64, u64), (f64, f64)>,
+  pinch: Option<Pinch>,
+}
+
+struct Pinch {
+  window: u64,
+  device_id: u32,
+  fingers: (u64, u64),
+  start_distance: f64,
+  distance: f64,
+  angle: f64,
+}
+
+impl TouchGestures {
+  // The gesture events that follow a touch event.
+  pub fn update(&mut self, event: &WsiEvent) -> Vec<WsiEvent> {
+    let WsiEvent::InputTouch {
+      window,
+      device_id,
+      location,
+      touch_phase,
+      finger_id,
+      ..
+    } = event
+    else {
+      return Vec::new();
+    };
+    match touch_phase {
+      WsiTouchPhase::Started => {
+        self.touches.insert((*window, *finger_id), *location);
+        if self.pinch.is_some() {
+          return Vec::new();
+        }
+        let mut fingers = self
+          .touches
+          .keys()
+          .filter(|(w, _)| w == window)
+          .map(|&(_, finger)| finger);
+        let (Some(first), Some(second), None) =
+          (fingers.next(), fingers.next(), fingers.next())
+        else {
+          return Vec::new();
+        };
+        let (distance, angle) = self.measure(*window, (first, second));
+        let pinch = Pinch {
+          window: *window,
+          device_id: *device_id,
+          fingers: (first, second),
+          start_distance: distance,
+          distance,
+          angle,
+        };
+        let events = pinch_events(
+          pinch.window,
+          pinch.device_id,
+          0.0,
+          0.0,
+          WsiTouchPhase::Started,
+        );
+        self.pinch = Some(pinch);
+        events
+      }
+      WsiTouchPhase::Moved => {
+        self.touches.insert((*window, *finger_id), *location);
+        let Some(pinch) = &self.pinch else {
+          return Vec::new();
+        };
+        if pinch.window != *window
+          || (pinch.fingers.0 != *finger_id && pinch.fingers.1 != *finger_id)
+        {
+          return Vec::new();
+        }
+        let (distance, angle) = self.measure(*window, pinch.fingers);
+        let pinch = self.pinch.as_mut().unwrap();
+        // Magnification adds up to the scale since the start, less 1.
+        let magnify = if pinch.start_distance > 0.0 {
+          (distance - pinch.distance) / pinch.start_distance
+        } else {
+          0.0
+        };
+        // Window coordinates go down, so angles grow clockwise, while
+        // rotations are counterclockwise.
+        let mut rotate = pinch.angle - angle;
+        if rotate > 180.0 {
+          rotate -= 360.0;
+        } else if rotate < -180.0 {
+          rotate += 360.0;
+        }
+        pinch.distance = distance;
+        pinch.angle = angle;
+        pinch_events(
+          pinch.window,
+          pinch.device_id,
+          magnify,
+          rotate as f32,
+          WsiTouchPhase::Moved,
+        )
+      }
+      WsiTouchPhase::Ended | WsiTouchPhase::Cancelled => {
+        self.touches.remove(&(*window, *finger_id));
+        match self.pinch.take() {
+          Some(pinch)
+            if pinch.window == *window
+              && (pinch.fingers.0 == *finger_id
+                || pinch.fingers.1 == *finger_id) =>
+          {
+            pinch_events(pinch.window, pinch.device_id, 0.0, 0.0, *touch_phase)
+          }
+          pinch => {
+            self.pinch = pinch;
+            Vec::new()
+          }
+        }
+      }
+    }
+  }
+
+  // The distance between two fingers, and the angle from the first to the
+  // second in degrees.
+  fn measure(&self, window: u64, fingers: (u64, u64)) -> (f64, f64) {
+    let (x0, y0) = self.touches[&(window, fingers.0)];
+    let (x1, y1) = self.touches[&(window, fingers.1)];
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    (dx.hypot(dy), dy.atan2(dx).to_degrees())
+  }
+}
+
+fn pinch_events(
+  window: u64,
+  device_id: u32,
+  magnify: f64,
+  rotate: f32,
+  phase: WsiTouchPhase,
+) -> Vec<WsiEvent> {
+  vec![
+    WsiEvent::TouchpadMagnify {
+      window,
+      device_id,
+      delta: magnify,
+      touch_phase: phase,
+    },
+    WsiEvent::TouchpadRotate {
+      window,
+      device_id,
+      delta: rotate,
+      touch_phase: phase,
+    },
+  ]
+}
+
+// https://wayland.app/protocols/pointer-gestures-unstable-v1
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use super::pinch_events;
+  use crate::{device_ids::DeviceIds, event::WsiEvent, input::WsiTouchPhase};
+  use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+  };
+  use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    mem,
+    rc::Rc,
+  };
+  use wayland_client::{
+    protocol::{
+      wl_pointer::WlPointer,
+      wl_seat::{self, WlSeat},
+    },
+    Display, EventQueue, GlobalManager, Main,
+  };
+  use wayland_protocols::unstable::pointer_gestures::v1::client::{
+    zwp_pointer_gesture_pinch_v1::{self, ZwpPointerGesturePinchV1},
+    zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+  };
+  use winit::{event::DeviceId, window::Window};
+
+  // The pinch gesture of the first seat's pointer, bound again on a separate
+  // event queue, so winit's own dispatching isn't disturbed. Fields are
+  // dropped in order, so the queue outlives the objects whose events go to
+  // it.
+  struct Gestures {
+    _pinch: Main<ZwpPointerGesturePinchV1>,
+    _pointer: Main<WlPointer>,
+    _seat: Main<WlSeat>,
+    queue: EventQueue,
+  }
+
+  thread_local! {
+    static GESTURES: RefCell<Option<Gestures>> = RefCell::new(None);
+    // Pinch events as the compositor sent them, until they're delivered.
+    static PINCH_EVENTS: RefCell<Vec<zwp_pointer_gesture_pinch_v1::Event>> =
+      RefCell::new(Vec::new());
+    // The window being pinched and the scale so far.
+    static PINCH: Cell<Option<(u64, f64)>> = Cell::new(None);
+  }
+
+  pub fn register_gesture_handler(window: &Window) {
+    let RawDisplayHandle::Wayland(display) = window.raw_display_handle() else {
+      return;
+    };
+    GESTURES.with(|gestures| {
+      let mut gestures = gestures.borrow_mut();
+      if gestures.is_none() {
+        // SAFETY: The display comes from winit and is valid while the event
+        // loop exists.
+        *gestures = unsafe { Gestures::new(display.display.cast()) };
+      }
+    });
+  }
+
+  pub fn take_gesture_events(
+    windows: &HashMap<u64, Window>,
+    device_ids: &mut DeviceIds,
+  ) -> Vec<WsiEvent> {
+    // Winit reads the events into the queue while it waits for its own.
+    GESTURES.with(|gestures| {
+      if let Some(gestures) = gestures.borrow_mut().as_mut() {
+        let _ = gestures.queue.dispatch_pending(&mut (), |_, _, _| {});
+      }
+    });
+    let pinch_events =
+      PINCH_EVENTS.with(|events| mem::take(&mut *events.borrow_mut()));
+    if pinch_events.is_empty() {
+      return Vec::new();
+    }
+    // SAFETY: Winit reports all Wayland input with the dummy device ID.
+    let device_id = device_ids.get(unsafe { DeviceId::dummy() });
+    pinch_events
+      .into_iter()
+      .flat_map(|event| pinch_event(event, windows, device_id))
+      .collect()
+  }
+
+  fn pinch_event(
+    event: zwp_pointer_gesture_pinch_v1::Event,
+    windows: &HashMap<u64, Window>,
+    device_id: u32,
+  ) -> Vec<WsiEvent> {
+    use zwp_pointer_gesture_pinch_v1::Event;
+    match event {
+      Event::Begin { surface, .. } => {
+        let surface = surface.as_ref().c_ptr().cast();
+        let window = windows.iter().find_map(|(&wid, window)| {
+          match window.raw_window_handle() {
+            RawWindowHandle::Wayland(handle) if handle.surface == surface => {
+              Some(wid)
+            }
+            _ => None,
+          }
+        });
+        PINCH.with(|pinch| pinch.set(window.map(|window| (window, 1.0))));
+        match window {
+          Some(window) => {
+            pinch_events(window, device_id, 0.0, 0.0, WsiTouchPhase::Started)
+          }
+          None => Vec::new(),
+        }
+      }
+      Event::Update {
+        scale, rotation, ..
+      } => {
+        let Some((window, last_scale)) = PINCH.with(Cell::get) else {
+          return Vec::new();
+        };
+        PINCH.with(|pinch| pinch.set(Some((window, scale))));
+        // Rotations are reported clockwise.
+        pinch_events(
+          window,
+          device_id,
+          scale - last_scale,
+          -rotation as f32,
+          WsiTouchPhase::Moved,
+        )
+      }
+      Event::End { cancelled, .. } => {
+        let Some((window, _)) = PINCH.with(|pinch| pinch.take()) else {
+          return Vec::new();
+        };
+        let phase = if cancelled != 0 {
+          WsiTouchPhase::Cancelled
+        } else {
+          WsiTouchPhase::Ended
+        };
+        pinch_events(window, device_id, 0.0, 0.0, phase)
+      }
+      _ => Vec::new(),
+    }
+  }
+
+  impl Gestures {
+    unsafe fn new(
+      display: *mut wayland_client::sys::client::wl_display,
+    ) -> Option<Self> {
+      let display = Display::from_external_display(display);
+      let mut queue = display.create_event_queue();
+      let attached = display.attach(queue.token());
+      let globals = GlobalManager::new(&attached);
+      queue.sync_roundtrip(&mut (), |_, _, _| {}).ok()?;
+      // Compositors without the protocol don't report gestures.
+      let manager =
+        globals.instantiate_exact::<ZwpPointerGesturesV1>(1).ok()?;
+      let seat = globals.instantiate_exact::<WlSeat>(1).ok()?;
+      // Asking a seat without a pointer for one is a protocol error, which
+      // would end winit's connection too.
+      let has_pointer = Rc::new(Cell::new(false));
+      seat.quick_assign({
+        let has_pointer = has_pointer.clone();
+        move |_, event, _| {
+          if let wl_seat::Event::Capabilities { capabilities } = event {
+            has_pointer
+              .set(capabilities.contains(wl_seat::Capability::Pointer));
+          }
+        }
+      });
+      queue.sync_roundtrip(&mut (), |_, _, _| {}).ok()?;
+      if !has_pointer.get() {
+        return None;
+      }
+      let pointer = seat.get_pointer();
+      let pinch = manager.get_pinch_gesture(&pointer);
+      pinch.quick_assign(|_, event, _| {
+        PINCH_EVENTS.with(|events| events.borrow_mut().push(event));
+      });
+      let _ = display.flush();
+      Some(Self {
+        _pinch: pinch,
+        _pointer: pointer,
+        _seat: seat,
+        queue,
+      })
+    }
+  }
+}
+
+#[cfg(not(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+)))]
+mod platform {
+  use crate::{device_ids::DeviceIds, event::WsiEvent};
+  use std::collections::HashMap;
+  use winit::window::Window;
+
+  pub fn register_gesture_handler(_window: &Window) {}
+
+  pub fn take_gesture_events(
+    _windows: &HashMap<u64, Window>,
+    _device_ids: &mut DeviceIds,
+  ) -> Vec<WsiEvent> {
+    Vec::new()
+  }
+}
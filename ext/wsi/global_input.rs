@@ -0,0 +1,729 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// Keyboard and mouse input from the whole desktop, whichever application has
+// focus and whether or not there are any windows, for overlay tools, macro
+// recorders and accessibility utilities. The input is only listened to, never
+// held back from where it's going. Windows has low-level hooks, macOS has
+// event taps, which need the Input Monitoring privilege, and X11 sends raw
+// XInput 2 events to any client that selects them. Wayland doesn't let clients
+// see each other's input. The hooks may run on other threads, so their events
+// are queued and delivered by the event loop driver.
+
+use crate::event::WsiEvent;
+use std::{
+  mem,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+  },
+};
+use winit::event_loop::{EventLoopProxy, EventLoopWindowTarget};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static GLOBAL_INPUT_EVENTS: Mutex<Vec<WsiEvent>> = Mutex::new(Vec::new());
+static EVENT_LOOP_PROXY: Mutex<Option<EventLoopProxy<()>>> = Mutex::new(None);
+
+// Start or stop reporting global input. `event_loop_proxy` wakes the event
+// loop when there are events to deliver. Returns `false` if it couldn't be
+// started, which is always the case on Wayland.
+pub fn set_global_input_enabled(
+  window_target: &EventLoopWindowTarget<()>,
+  enabled: bool,
+  event_loop_proxy: EventLoopProxy<()>,
+) -> bool {
+  *EVENT_LOOP_PROXY.lock().unwrap() = Some(event_loop_proxy);
+  if !enabled {
+    ENABLED.store(false, Ordering::Relaxed);
+    GLOBAL_INPUT_EVENTS.lock().unwrap().clear();
+  }
+  let hooked = platform::set_hooked(window_target, enabled);
+  if enabled {
+    ENABLED.store(hooked, Ordering::Relaxed);
+  }
+  hooked
+}
+
+pub fn take_global_input_events() -> Vec<WsiEvent> {
+  mem::take(&mut *GLOBAL_INPUT_EVENTS.lock().unwrap())
+}
+
+// Queue an event from a hook, waking the event loop if it's the first one
+// since the driver last took them.
+#[cfg_attr(
+  not(any(
+    windows,
+    target_os = "macos",
+    all(
+      unix,
+      not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+    )
+  )),
+  allow(dead_code)
+)]
+fn push_event(event: WsiEvent) {
+  if !ENABLED.load(Ordering::Relaxed) {
+    return;
+  }
+  let mut events = GLOBAL_INPUT_EVENTS.lock().unwrap();
+  events.push(event);
+  if events.len() == 1 {
+    if let Some(proxy) = &*EVENT_LOOP_PROXY.lock().unwrap() {
+      let _ = proxy.send_event(());
+    }
+  }
+}
+
+// Low-level hooks are called on the thread that installed them, while it waits
+// for messages, before the input reaches any window.
+// https://learn.microsoft.com/en-us/windows/win32/winmsg/lowlevelmouseproc
+#[cfg(windows)]
+mod platform {
+  use super::push_event;
+  use crate::{
+    event::WsiEvent,
+    input::{WsiButtonState, WsiMouseButton, WsiScrollDelta},
+    scan_codes,
+  };
+  use std::{cell::Cell, ptr};
+  use winapi::{
+    ctypes::c_int,
+    shared::{
+      minwindef::{HIWORD, LPARAM, LRESULT, UINT, WPARAM},
+      windef::HHOOK,
+    },
+    um::{
+      libloaderapi::GetModuleHandleW,
+      winuser::{
+        CallNextHookEx, SetWindowsHookExW, UnhookWindowsHookEx, HC_ACTION,
+        KBDLLHOOKSTRUCT, LLKHF_EXTENDED, LLKHF_UP, MSLLHOOKSTRUCT, WHEEL_DELTA,
+        WH_KEYBOARD_LL, WH_MOUSE_LL, WM_LBUTTONDOWN, WM_LBUTTONUP,
+        WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEHWHEEL, WM_MOUSEMOVE,
+        WM_MOUSEWHEEL, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_XBUTTONDOWN,
+        WM_XBUTTONUP,
+      },
+    },
+  };
+  use winit::event_loop::EventLoopWindowTarget;
+
+  thread_local! {
+    // The keyboard and mouse hooks while they're installed, on the event loop
+    // thread.
+    static HOOKS: Cell<Option<(HHOOK, HHOOK)>> = Cell::new(None);
+  }
+
+  pub fn set_hooked(
+    _window_target: &EventLoopWindowTarget<()>,
+    hooked: bool,
+  ) -> bool {
+    // SAFETY: The hook procedures are valid for the life of the program, and
+    // the hooks are only removed once.
+    unsafe {
+      match (HOOKS.with(Cell::get), hooked) {
+        (None, true) => {
+          let module = GetModuleHandleW(ptr::null());
+          let keyboard =
+            SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_proc), module, 0);
+          let mouse =
+            SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), module, 0);
+          if keyboard.is_null() || mouse.is_null() {
+            for hook in [keyboard, mouse] {
+              if !hook.is_null() {
+                UnhookWindowsHookEx(hook);
+              }
+            }
+            return false;
+          }
+          HOOKS.with(|hooks| hooks.set(Some((keyboard, mouse))));
+        }
+        (Some((keyboard, mouse)), false) => {
+          UnhookWindowsHookEx(keyboard);
+          UnhookWindowsHookEx(mouse);
+          HOOKS.with(|hooks| hooks.set(None));
+        }
+        _ => {}
+      }
+    }
+    true
+  }
+
+  unsafe extern "system" fn keyboard_proc(
+    code: c_int,
+    wparam: WPARAM,
+    lparam: LPARAM,
+  ) -> LRESULT {
+    if code == HC_ACTION {
+      let info = &*(lparam as *const KBDLLHOOKSTRUCT);
+      // Extended keys have 0xe0 in the second byte, as winit reports them.
+      let mut scan_code = info.scanCode;
+      if info.flags & LLKHF_EXTENDED != 0 {
+        scan_code |= 0xe000;
+      }
+      let state = if info.flags & LLKHF_UP != 0 {
+        WsiButtonState::Released
+      } else {
+        WsiButtonState::Pressed
+      };
+      push_event(WsiEvent::GlobalKey {
+        scan_code,
+        code: scan_codes::code(scan_code),
+        state,
+      });
+    }
+    CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+  }
+
+  unsafe extern "system" fn mouse_proc(
+    code: c_int,
+    wparam: WPARAM,
+    lparam: LPARAM,
+  ) -> LRESULT {
+    if code == HC_ACTION {
+      let info = &*(lparam as *const MSLLHOOKSTRUCT);
+      if let Some(event) = mouse_event(wparam as UINT, info) {
+        push_event(event);
+      }
+    }
+    CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+  }
+
+  fn mouse_event(message: UINT, info: &MSLLHOOKSTRUCT) -> Option<WsiEvent> {
+    use WsiButtonState::{Pressed, Released};
+    let position = (info.pt.x as f64, info.pt.y as f64);
+    let button = |button, state| WsiEvent::GlobalMouseButton {
+      button,
+      state,
+      position,
+    };
+    // The high word of the data is the wheel's movement, or which X button
+    // it is.
+    let data = HIWORD(info.mouseData);
+    let wheel = data as i16 as f32 / WHEEL_DELTA as f32;
+    let scroll = |x, y| WsiEvent::GlobalMouseScroll {
+      delta: WsiScrollDelta::LineDelta { x, y },
+      position,
+    };
+    Some(match message {
+      WM_MOUSEMOVE => WsiEvent::GlobalCursorMoved { position },
+      WM_LBUTTONDOWN => button(WsiMouseButton::Left, Pressed),
+      WM_LBUTTONUP => button(WsiMouseButton::Left, Released),
+      WM_RBUTTONDOWN => button(WsiMouseButton::Right, Pressed),
+      WM_RBUTTONUP => button(WsiMouseButton::Right, Released),
+      WM_MBUTTONDOWN => button(WsiMouseButton::Middle, Pressed),
+      WM_MBUTTONUP => button(WsiMouseButton::Middle, Released),
+      WM_XBUTTONDOWN => button(WsiMouseButton::Other(data), Pressed),
+      WM_XBUTTONUP => button(WsiMouseButton::Other(data), Released),
+      WM_MOUSEWHEEL => scroll(0.0, wheel),
+      // Inverted, as winit does.
+      WM_MOUSEHWHEEL => scroll(-wheel, 0.0),
+      _ => return None,
+    })
+  }
+}
+
+// A listen-only event tap on the main run loop, which AppKit runs for winit.
+// Creating it fails without the Input Monitoring privilege, which macOS asks
+// the user for the first time.
+// https://developer.apple.com/documentation/coregraphics/1454426-cgeventtapcreate
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::push_event;
+  use crate::{
+    event::WsiEvent,
+    input::{WsiButtonState, WsiMouseButton, WsiScrollDelta},
+    scan_codes,
+  };
+  use std::{cell::Cell, ffi::c_void, ptr};
+  use winit::event_loop::EventLoopWindowTarget;
+
+  const K_CG_SESSION_EVENT_TAP: u32 = 1;
+  const K_CG_HEAD_INSERT_EVENT_TAP: u32 = 0;
+  const K_CG_EVENT_TAP_OPTION_LISTEN_ONLY: u32 = 1;
+
+  const K_CG_EVENT_LEFT_MOUSE_DOWN: u32 = 1;
+  const K_CG_EVENT_LEFT_MOUSE_UP: u32 = 2;
+  const K_CG_EVENT_RIGHT_MOUSE_DOWN: u32 = 3;
+  const K_CG_EVENT_RIGHT_MOUSE_UP: u32 = 4;
+  const K_CG_EVENT_MOUSE_MOVED: u32 = 5;
+  const K_CG_EVENT_LEFT_MOUSE_DRAGGED: u32 = 6;
+  const K_CG_EVENT_RIGHT_MOUSE_DRAGGED: u32 = 7;
+  const K_CG_EVENT_KEY_DOWN: u32 = 10;
+  const K_CG_EVENT_KEY_UP: u32 = 11;
+  const K_CG_EVENT_FLAGS_CHANGED: u32 = 12;
+  const K_CG_EVENT_SCROLL_WHEEL: u32 = 22;
+  const K_CG_EVENT_OTHER_MOUSE_DOWN: u32 = 25;
+  const K_CG_EVENT_OTHER_MOUSE_UP: u32 = 26;
+  const K_CG_EVENT_OTHER_MOUSE_DRAGGED: u32 = 27;
+  const K_CG_EVENT_TAP_DISABLED_BY_TIMEOUT: u32 = 0xfffffffe;
+  const EVENT_TYPES: [u32; 14] = [
+    K_CG_EVENT_LEFT_MOUSE_DOWN,
+    K_CG_EVENT_LEFT_MOUSE_UP,
+    K_CG_EVENT_RIGHT_MOUSE_DOWN,
+    K_CG_EVENT_RIGHT_MOUSE_UP,
+    K_CG_EVENT_MOUSE_MOVED,
+    K_CG_EVENT_LEFT_MOUSE_DRAGGED,
+    K_CG_EVENT_RIGHT_MOUSE_DRAGGED,
+    K_CG_EVENT_KEY_DOWN,
+    K_CG_EVENT_KEY_UP,
+    K_CG_EVENT_FLAGS_CHANGED,
+    K_CG_EVENT_SCROLL_WHEEL,
+    K_CG_EVENT_OTHER_MOUSE_DOWN,
+    K_CG_EVENT_OTHER_MOUSE_UP,
+    K_CG_EVENT_OTHER_MOUSE_DRAGGED,
+  ];
+
+  const K_CG_MOUSE_EVENT_BUTTON_NUMBER: u32 = 3;
+  const K_CG_KEYBOARD_EVENT_KEYCODE: u32 = 9;
+  const K_CG_SCROLL_WHEEL_EVENT_DELTA_AXIS_1: u32 = 11;
+  const K_CG_SCROLL_WHEEL_EVENT_DELTA_AXIS_2: u32 = 12;
+  const K_CG_SCROLL_WHEEL_EVENT_IS_CONTINUOUS: u32 = 88;
+  const K_CG_SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_1: u32 = 96;
+  const K_CG_SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_2: u32 = 97;
+
+  #[repr(C)]
+  struct CGPoint {
+    x: f64,
+    y: f64,
+  }
+
+  type CGEventTapCallBack =
+    extern "C" fn(*mut c_void, u32, *mut c_void, *mut c_void) -> *mut c_void;
+
+  #[link(name = "CoreGraphics", kind = "framework")]
+  extern "C" {
+    fn CGEventTapCreate(
+      tap: u32,
+      place: u32,
+      options: u32,
+      events_of_interest: u64,
+      callback: CGEventTapCallBack,
+      user_info: *mut c_void,
+    ) -> *mut c_void;
+    fn CGEventTapEnable(tap: *mut c_void, enable: bool);
+    fn CGEventGetIntegerValueField(event: *mut c_void, field: u32) -> i64;
+    fn CGEventGetDoubleValueField(event: *mut c_void, field: u32) -> f64;
+    fn CGEventGetLocation(event: *mut c_void) -> CGPoint;
+    fn CGEventGetFlags(event: *mut c_void) -> u64;
+  }
+
+  #[link(name = "CoreFoundation", kind = "framework")]
+  extern "C" {
+    static kCFRunLoopCommonModes: *const c_void;
+    fn CFMachPortCreateRunLoopSource(
+      allocator: *const c_void,
+      port: *mut c_void,
+      order: isize,
+    ) -> *mut c_void;
+    fn CFMachPortInvalidate(port: *mut c_void);
+    fn CFRunLoopGetMain() -> *mut c_void;
+    fn CFRunLoopAddSource(
+      run_loop: *mut c_void,
+      source: *mut c_void,
+      mode: *const c_void,
+    );
+    fn CFRunLoopRemoveSource(
+      run_loop: *mut c_void,
+      source: *mut c_void,
+      mode: *const c_void,
+    );
+    fn CFRelease(cf: *const c_void);
+  }
+
+  thread_local! {
+    // The event tap and its run loop source while they're installed, on the
+    // main thread.
+    static TAP: Cell<Option<(*mut c_void, *mut c_void)>> = Cell::new(None);
+  }
+
+  pub fn set_hooked(
+    _window_target: &EventLoopWindowTarget<()>,
+    hooked: bool,
+  ) -> bool {
+    // SAFETY: The tap and source are released once, after they're removed
+    // from the run loop.
+    unsafe {
+      match (TAP.with(Cell::get), hooked) {
+        (None, true) => {
+          let events = EVENT_TYPES.iter().fold(0, |mask, &t| mask | 1 << t);
+          let tap = CGEventTapCreate(
+            K_CG_SESSION_EVENT_TAP,
+            K_CG_HEAD_INSERT_EVENT_TAP,
+            K_CG_EVENT_TAP_OPTION_LISTEN_ONLY,
+            events,
+            tap_callback,
+            ptr::null_mut(),
+          );
+          if tap.is_null() {
+            return false;
+          }
+          let source = CFMachPortCreateRunLoopSource(ptr::null(), tap, 0);
+          CFRunLoopAddSource(CFRunLoopGetMain(), source, kCFRunLoopCommonModes);
+          CGEventTapEnable(tap, true);
+          TAP.with(|cell| cell.set(Some((tap, source))));
+        }
+        (Some((tap, source)), false) => {
+          CGEventTapEnable(tap, false);
+          CFRunLoopRemoveSource(
+            CFRunLoopGetMain(),
+            source,
+            kCFRunLoopCommonModes,
+          );
+          CFMachPortInvalidate(tap);
+          CFRelease(source);
+          CFRelease(tap);
+          TAP.with(|cell| cell.set(None));
+        }
+        _ => {}
+      }
+    }
+    true
+  }
+
+  extern "C" fn tap_callback(
+    _proxy: *mut c_void,
+    event_type: u32,
+    event: *mut c_void,
+    _user_info: *mut c_void,
+  ) -> *mut c_void {
+    // SAFETY: The event is valid during the callback, and the tap is valid
+    // while it's installed.
+    unsafe {
+      // Taps that take too long are turned off until they're turned back on.
+      if event_type == K_CG_EVENT_TAP_DISABLED_BY_TIMEOUT {
+        if let Some((tap, _)) = TAP.with(Cell::get) {
+          CGEventTapEnable(tap, true);
+        }
+      } else if let Some(event) = global_event(event_type, event) {
+        push_event(event);
+      }
+    }
+    event
+  }
+
+  // SAFETY: The event must be valid.
+  unsafe fn global_event(
+    event_type: u32,
+    event: *mut c_void,
+  ) -> Option<WsiEvent> {
+    use WsiButtonState::{Pressed, Released};
+    let field = |field| CGEventGetIntegerValueField(event, field);
+    let location = CGEventGetLocation(event);
+    let position = (location.x, location.y);
+    let button = |state| {
+      // Button numbers are the same as AppKit's, which winit goes by.
+      let button = match field(K_CG_MOUSE_EVENT_BUTTON_NUMBER) {
+        0 => WsiMouseButton::Left,
+        1 => WsiMouseButton::Right,
+        2 => WsiMouseButton::Middle,
+        n => WsiMouseButton::Other(n as u16),
+      };
+      WsiEvent::GlobalMouseButton {
+        button,
+        state,
+        position,
+      }
+    };
+    let key = |state| {
+      let scan_code = field(K_CG_KEYBOARD_EVENT_KEYCODE) as u32;
+      WsiEvent::GlobalKey {
+        scan_code,
+        code: scan_codes::code(scan_code),
+        state,
+      }
+    };
+    Some(match event_type {
+      K_CG_EVENT_MOUSE_MOVED
+      | K_CG_EVENT_LEFT_MOUSE_DRAGGED
+      | K_CG_EVENT_RIGHT_MOUSE_DRAGGED
+      | K_CG_EVENT_OTHER_MOUSE_DRAGGED => {
+        WsiEvent::GlobalCursorMoved { position }
+      }
+      K_CG_EVENT_LEFT_MOUSE_DOWN
+      | K_CG_EVENT_RIGHT_MOUSE_DOWN
+      | K_CG_EVENT_OTHER_MOUSE_DOWN => button(Pressed),
+      K_CG_EVENT_LEFT_MOUSE_UP
+      | K_CG_EVENT_RIGHT_MOUSE_UP
+      | K_CG_EVENT_OTHER_MOUSE_UP => button(Released),
+      K_CG_EVENT_KEY_DOWN => key(Pressed),
+      K_CG_EVENT_KEY_UP => key(Released),
+      // Modifier keys only say that the modifiers changed, so whether the
+      // key was pressed is whether its modifier is now down.
+      K_CG_EVENT_FLAGS_CHANGED => {
+        let scan_code = field(K_CG_KEYBOARD_EVENT_KEYCODE) as u32;
+        let mask = modifier_mask(scan_code)?;
+        let down = CGEventGetFlags(event) & mask != 0;
+        key(if down { Pressed } else { Released })
+      }
+      K_CG_EVENT_SCROLL_WHEEL => {
+        let delta = if field(K_CG_SCROLL_WHEEL_EVENT_IS_CONTINUOUS) != 0 {
+          WsiScrollDelta::PixelDelta {
+            x: CGEventGetDoubleValueField(
+              event,
+              K_CG_SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_2,
+            ),
+            y: CGEventGetDoubleValueField(
+              event,
+              K_CG_SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_1,
+            ),
+          }
+        } else {
+          WsiScrollDelta::LineDelta {
+            x: field(K_CG_SCROLL_WHEEL_EVENT_DELTA_AXIS_2) as f32,
+            y: field(K_CG_SCROLL_WHEEL_EVENT_DELTA_AXIS_1) as f32,
+          }
+        };
+        WsiEvent::GlobalMouseScroll { delta, position }
+      }
+      _ => return None,
+    })
+  }
+
+  // The event flag of each modifier key, by virtual key code.
+  // https://developer.apple.com/documentation/coregraphics/cgeventflags
+  fn modifier_mask(key_code: u32) -> Option<u64> {
+    Some(match key_code {
+      0x37 | 0x36 => 1 << 20,
+      0x38 | 0x3c => 1 << 17,
+      0x39 => 1 << 16,
+      0x3a | 0x3d => 1 << 19,
+      0x3b | 0x3e => 1 << 18,
+      0x3f => 1 << 23,
+      _ => return None,
+    })
+  }
+}
+
+// Raw events are sent to the root window no matter which window has focus or
+// grabbed the input, but without the pointer's position, which is queried.
+// They're read from a connection of its own on a thread of its own, which
+// keeps running once it's started and drops the events while global input is
+// disabled.
+// https://www.x.org/releases/current/doc/inputproto/XI2proto.txt
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use super::push_event;
+  use crate::{
+    event::WsiEvent,
+    input::{WsiButtonState, WsiMouseButton, WsiScrollDelta},
+    scan_codes,
+  };
+  use raw_window_handle::{HasRawDisplayHandle, RawDisplayHandle};
+  use std::{cell::Cell, mem, ptr, sync::mpsc, thread};
+  use winit::event_loop::EventLoopWindowTarget;
+  use x11_dl::{
+    xinput2::{self, XInput2},
+    xlib::{self, Xlib},
+  };
+
+  // Linux key codes are X11 key codes less 8.
+  const KEYCODE_OFFSET: u32 = 8;
+
+  thread_local! {
+    // Whether the thread that reads raw events started, once it's been
+    // tried, on the event loop thread.
+    static LISTENING: Cell<Option<bool>> = Cell::new(None);
+  }
+
+  pub fn set_hooked(
+    window_target: &EventLoopWindowTarget<()>,
+    hooked: bool,
+  ) -> bool {
+    if !hooked {
+      return true;
+    }
+    if !matches!(
+      window_target.raw_display_handle(),
+      RawDisplayHandle::Xlib(_)
+    ) {
+      return false;
+    }
+    LISTENING.with(|listening| {
+      let started = listening.get().unwrap_or_else(listen);
+      listening.set(Some(started));
+      started
+    })
+  }
+
+  // Start reading raw events, returning whether it could.
+  fn listen() -> bool {
+    let (ready_tx, ready_rx) = mpsc::channel();
+    thread::spawn(move || {
+      // SAFETY: The display is only used on this thread, and is never
+      // closed after it's selected raw events.
+      unsafe {
+        let Some((xlib, display, opcode)) = open() else {
+          let _ = ready_tx.send(false);
+          return;
+        };
+        let _ = ready_tx.send(true);
+        read_events(&xlib, display, opcode);
+      }
+    });
+    ready_rx.recv().unwrap_or(false)
+  }
+
+  // Connect to the X server and select raw events, returning the connection
+  // and XInput's major opcode, which its events have as their extension.
+  unsafe fn open() -> Option<(Xlib, *mut xlib::Display, i32)> {
+    let xlib = Xlib::open().ok()?;
+    let xinput2 = XInput2::open().ok()?;
+    let display = (xlib.XOpenDisplay)(ptr::null());
+    if display.is_null() {
+      return None;
+    }
+    let (mut opcode, mut first_event, mut first_error) = (0, 0, 0);
+    let (mut major, mut minor) = (2, 0);
+    if (xlib.XQueryExtension)(
+      display,
+      b"XInputExtension\0".as_ptr().cast(),
+      &mut opcode,
+      &mut first_event,
+      &mut first_error,
+    ) == 0
+      || (xinput2.XIQueryVersion)(display, &mut major, &mut minor)
+        != xlib::Success as i32
+    {
+      (xlib.XCloseDisplay)(display);
+      return None;
+    }
+    let mut mask = [0; 4];
+    for event in [
+      xinput2::XI_RawKeyPress,
+      xinput2::XI_RawKeyRelease,
+      xinput2::XI_RawButtonPress,
+      xinput2::XI_RawButtonRelease,
+      xinput2::XI_RawMotion,
+    ] {
+      xinput2::XISetMask(&mut mask, event);
+    }
+    let mut event_mask = xinput2::XIEventMask {
+      deviceid: xinput2::XIAllMasterDevices,
+      mask_len: mask.len() as i32,
+      mask: mask.as_mut_ptr(),
+    };
+    let root = (xlib.XDefaultRootWindow)(display);
+    (xinput2.XISelectEvents)(display, root, &mut event_mask, 1);
+    (xlib.XFlush)(display);
+    Some((xlib, display, opcode))
+  }
+
+  // SAFETY: The display must be valid and only used on this thread.
+  unsafe fn read_events(
+    xlib: &Xlib,
+    display: *mut xlib::Display,
+    opcode: i32,
+  ) -> ! {
+    loop {
+      let mut event: xlib::XEvent = mem::zeroed();
+      (xlib.XNextEvent)(display, &mut event);
+      let mut cookie = xlib::XGenericEventCookie::from(event);
+      if cookie.type_ != xlib::GenericEvent
+        || cookie.extension != opcode
+        || (xlib.XGetEventData)(display, &mut cookie) == 0
+      {
+        continue;
+      }
+      let raw = &*(cookie.data as *const xinput2::XIRawEvent);
+      let event = global_event(xlib, display, cookie.evtype, raw.detail);
+      (xlib.XFreeEventData)(display, &mut cookie);
+      if let Some(event) = event {
+        push_event(event);
+      }
+    }
+  }
+
+  // SAFETY: The display must be valid.
+  unsafe fn global_event(
+    xlib: &Xlib,
+    display: *mut xlib::Display,
+    event_type: i32,
+    detail: i32,
+  ) -> Option<WsiEvent> {
+    use WsiButtonState::{Pressed, Released};
+    let key = |state| {
+      let scan_code = detail as u32 - KEYCODE_OFFSET;
+      WsiEvent::GlobalKey {
+        scan_code,
+        code: scan_codes::code(scan_code),
+        state,
+      }
+    };
+    let state = match event_type {
+      xinput2::XI_RawKeyPress => return Some(key(Pressed)),
+      xinput2::XI_RawKeyRelease => return Some(key(Released)),
+      xinput2::XI_RawButtonPress => Pressed,
+      xinput2::XI_RawButtonRelease => Released,
+      xinput2::XI_RawMotion => {
+        let position = pointer_position(xlib, display)?;
+        return Some(WsiEvent::GlobalCursorMoved { position });
+      }
+      _ => return None,
+    };
+    let position = pointer_position(xlib, display)?;
+    // Buttons 4 to 7 are the wheel, as winit treats them, and are only
+    // pressed.
+    let scroll = |x, y| {
+      let delta = WsiScrollDelta::LineDelta { x, y };
+      matches!(state, Pressed)
+        .then_some(WsiEvent::GlobalMouseScroll { delta, position })
+    };
+    let button = match detail {
+      1 => WsiMouseButton::Left,
+      2 => WsiMouseButton::Middle,
+      3 => WsiMouseButton::Right,
+      4 => return scroll(0.0, 1.0),
+      5 => return scroll(0.0, -1.0),
+      6 => return scroll(1.0, 0.0),
+      7 => return scroll(-1.0, 0.0),
+      n => WsiMouseButton::Other(n as u16),
+    };
+    Some(WsiEvent::GlobalMouseButton {
+      button,
+      state,
+      position,
+    })
+  }
+
+  // SAFETY: The display must be valid.
+  unsafe fn pointer_position(
+    xlib: &Xlib,
+    display: *mut xlib::Display,
+  ) -> Option<(f64, f64)> {
+    let root = (xlib.XDefaultRootWindow)(display);
+    let (mut root_return, mut child) = (0, 0);
+    let (mut x, mut y, mut win_x, mut win_y, mut mask) = (0, 0, 0, 0, 0);
+    let on_screen = (xlib.XQueryPointer)(
+      display,
+      root,
+      &mut root_return,
+      &mut child,
+      &mut x,
+      &mut y,
+      &mut win_x,
+      &mut win_y,
+      &mut mask,
+    );
+    (on_screen != 0).then_some((x as f64, y as f64))
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use winit::event_loop::EventLoopWindowTarget;
+
+  pub fn set_hooked(
+    _window_target: &EventLoopWindowTarget<()>,
+    hooked: bool,
+  ) -> bool {
+    !hooked
+  }
+}
@@ -0,0 +1,290 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{drag_drop::cursor_position, event::WsiEvent};
+use std::{
+  cell::RefCell,
+  collections::HashMap,
+  sync::atomic::{AtomicBool, Ordering},
+  thread,
+  time::Duration,
+};
+use winit::{
+  event::{Event, StartCause},
+  event_loop::EventLoopProxy,
+  window::Window,
+};
+
+// How often the cursor is checked against hit test regions.
+const HIT_TEST_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+// Whether the thread that wakes the event loop to check the cursor runs.
+static POLLING: AtomicBool = AtomicBool::new(false);
+
+// Whether that thread woke the event loop since it last started waiting.
+static POLL_WOKE: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+  // The windows with a hit test region, on the event loop thread.
+  static HIT_TEST_REGIONS: RefCell<HashMap<u64, HitTestRegion>> =
+    RefCell::new(HashMap::new());
+}
+
+struct HitTestRegion {
+  // In physical pixels relative to the window's client area.
+  rects: Vec<(i32, i32, u32, u32)>,
+  // Whether the window takes clicks right now.
+  hit_test: bool,
+}
+
+// Let clicks only hit a window within the given rectangles and pass through
+// it elsewhere, or everywhere again with `None`. Wayland compositors take an
+// input region for the window. Elsewhere, a window can only take clicks or
+// not, so while there are regions the event loop is woken to check whether
+// the cursor is in one, even when the window doesn't get cursor events
+// because it passes them through.
+pub fn set_hit_test_region(
+  wid: u64,
+  window: &Window,
+  rects: Option<Vec<(i32, i32, u32, u32)>>,
+  wake: EventLoopProxy<()>,
+) {
+  if platform::set_input_region(window, rects.as_deref()) {
+    return;
+  }
+  let polling = HIT_TEST_REGIONS.with(|regions| {
+    let mut regions = regions.borrow_mut();
+    match rects {
+      Some(rects) => {
+        regions.insert(
+          wid,
+          HitTestRegion {
+            rects,
+            hit_test: true,
+          },
+        );
+      }
+      None => {
+        regions.remove(&wid);
+      }
+    }
+    !regions.is_empty()
+  });
+  let _ = window.set_cursor_hittest(true);
+  if polling && !POLLING.swap(true, Ordering::Relaxed) {
+    thread::spawn(move || {
+      while POLLING.load(Ordering::Relaxed) {
+        thread::sleep(HIT_TEST_POLL_INTERVAL);
+        POLL_WOKE.store(true, Ordering::Relaxed);
+        if wake.send_event(()).is_err() {
+          break;
+        }
+      }
+    });
+  } else if !polling {
+    POLLING.store(false, Ordering::Relaxed);
+  }
+}
+
+// Let a window take clicks while the cursor is in its hit test region. Call
+// this for every event.
+pub fn update_hit_tests(windows: &HashMap<u64, Window>) {
+  HIT_TEST_REGIONS.with(|regions| {
+    for (wid, region) in regions.borrow_mut().iter_mut() {
+      let Some(window) = windows.get(wid) else {
+        continue;
+      };
+      let Some((x, y)) = cursor_position(window) else {
+        continue;
+      };
+      let inside = region.rects.iter().any(|&(left, top, width, height)| {
+        x >= left as f64
+          && y >= top as f64
+          && x < left as f64 + width as f64
+          && y < top as f64 + height as f64
+      });
+      if inside != region.hit_test && window.set_cursor_hittest(inside).is_ok()
+      {
+        region.hit_test = inside;
+      }
+    }
+  });
+}
+
+// The event loop is woken about 60 times a second to check the cursor, but
+// the runtime only hears of a wake if something comes of it. So while the
+// event loop runs a pass that the polling thread started, the driver holds back
+// the events that every pass has, and drops them at the end of the pass unless
+// another event was delivered in it.
+#[derive(Default)]
+pub struct HitTestWakeFilter {
+  held: Option<Vec<WsiEvent>>,
+}
+
+impl HitTestWakeFilter {
+  // Call this for every event, before it's converted.
+  pub fn update(&mut self, event: &Event<()>) {
+    // A wake only interrupts `ControlFlow::Wait` and `WaitUntil`, where no
+    // pass goes unnoticed otherwise.
+    if let Event::NewEvents(StartCause::WaitCancelled { .. }) = event {
+      if POLL_WOKE.swap(false, Ordering::Relaxed) {
+        self.held = Some(Vec::new());
+      }
+    }
+  }
+
+  // The events to deliver in place of `event`.
+  pub fn filter(&mut self, event: WsiEvent) -> Vec<WsiEvent> {
+    let Some(held) = &mut self.held else {
+      return vec![event];
+    };
+    match event {
+      WsiEvent::NewEvents
+      | WsiEvent::MainEventsCleared
+      | WsiEvent::Internal => {
+        held.push(event);
+        vec![]
+      }
+      WsiEvent::RedrawEventsCleared => {
+        self.held = None;
+        vec![]
+      }
+      event => {
+        let mut events = self.held.take().unwrap_or_default();
+        events.push(event);
+        events
+      }
+    }
+  }
+}
+
+pub fn forget_hit_test_region(wid: u64) {
+  let polling = HIT_TEST_REGIONS.with(|regions| {
+    let mut regions = regions.borrow_mut();
+    regions.remove(&wid);
+    !regions.is_empty()
+  });
+  if !polling {
+    POLLING.store(false, Ordering::Relaxed);
+  }
+}
+
+// Wayland surfaces have an input region, outside of which pointer and touch
+// events go to whatever is below. It's in surface coordinates, i.e. logical
+// pixels, and takes effect when the surface is next committed. That's left to
+// the window's next frame, which is requested, since winit commits the
+// surface itself.
+// https://wayland.app/protocols/wayland#wl_surface:request:set_input_region
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+  };
+  use std::{cell::RefCell, collections::HashMap, ffi::c_void};
+  use wayland_client::{
+    protocol::{wl_compositor::WlCompositor, wl_surface::WlSurface},
+    Display, EventQueue, GlobalManager, Main, Proxy,
+  };
+  use winit::window::Window;
+
+  // The compositor global, bound on an event queue of its own, so winit's
+  // own dispatching isn't disturbed.
+  struct Compositor {
+    display: Display,
+    _queue: EventQueue,
+    compositor: Main<WlCompositor>,
+  }
+
+  thread_local! {
+    // The compositor of each display, bound the first time it's needed, on
+    // the event loop thread. `None` if it couldn't be bound.
+    static COMPOSITORS: RefCell<HashMap<usize, Option<Compositor>>> =
+      RefCell::new(HashMap::new());
+  }
+
+  // SAFETY: `display` must be a valid `wl_display` that outlives the event
+  // loop thread, which winit's is.
+  unsafe fn bind_compositor(display: *mut c_void) -> Option<Compositor> {
+    let display = Display::from_external_display(display.cast());
+    let mut queue = display.create_event_queue();
+    let attached = display.attach(queue.token());
+    let globals = GlobalManager::new(&attached);
+    queue.sync_roundtrip(&mut (), |_, _, _| {}).ok()?;
+    let compositor = globals.instantiate_range::<WlCompositor>(1, 4).ok()?;
+    Some(Compositor {
+      display,
+      _queue: queue,
+      compositor,
+    })
+  }
+
+  // Returns `false` on X11.
+  pub fn set_input_region(
+    window: &Window,
+    rects: Option<&[(i32, i32, u32, u32)]>,
+  ) -> bool {
+    let (RawDisplayHandle::Wayland(display), RawWindowHandle::Wayland(handle)) =
+      (window.raw_display_handle(), window.raw_window_handle())
+    else {
+      return false;
+    };
+    let scale_factor = window.scale_factor();
+    COMPOSITORS.with(|compositors| {
+      let mut compositors = compositors.borrow_mut();
+      let compositor = compositors
+        .entry(display.display as usize)
+        // SAFETY: The display comes from winit.
+        .or_insert_with(|| unsafe { bind_compositor(display.display) });
+      let Some(Compositor {
+        display,
+        compositor,
+        ..
+      }) = compositor
+      else {
+        return;
+      };
+      let surface: WlSurface = {
+        // SAFETY: The surface comes from winit and is valid while the window
+        // exists.
+        unsafe { Proxy::<WlSurface>::from_c_ptr(handle.surface.cast()) }
+      }
+      .into();
+      match rects {
+        Some(rects) => {
+          let region = compositor.create_region();
+          for &(x, y, width, height) in rects {
+            region.add(
+              (x as f64 / scale_factor).round() as i32,
+              (y as f64 / scale_factor).round() as i32,
+              (width as f64 / scale_factor).round() as i32,
+              (height as f64 / scale_factor).round() as i32,
+            );
+          }
+          surface.set_input_region(Some(&region));
+          region.destroy();
+        }
+        None => surface.set_input_region(None),
+      }
+      let _ = display.flush();
+    });
+    window.request_redraw();
+    true
+  }
+}
+
+#[cfg(not(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+)))]
+mod platform {
+  use winit::window::Window;
+
+  pub fn set_input_region(
+    _window: &Window,
+    _rects: Option<&[(i32, i32, u32, u32)]>,
+  ) -> bool {
+    false
+  }
+}
@@ -0,0 +1,160 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{
+  event_loop::WsiEventLoopProxy, remove_window, WsiWindowNames,
+  WsiWindowSurfaces,
+};
+use deno_core::OpState;
+use deno_webgpu::{surface::WebGpuSurface, PreservedGpu};
+use std::{
+  cell::RefCell,
+  collections::{HashMap, HashSet},
+  rc::Rc,
+};
+
+// Windows, GPU surfaces and devices that a runtime left open when --watch
+// restarted it, until the next runtime starts. It lives in the event loop
+// proxy, which outlasts the runtimes.
+#[derive(Default)]
+pub(crate) struct WsiHotReload(RefCell<Preserved>);
+
+#[derive(Default)]
+struct Preserved {
+  windows: Vec<PreservedWindow>,
+  gpu: Option<PreservedGpu>,
+}
+
+struct PreservedWindow {
+  wid: u64,
+  name: Option<String>,
+  surfaces: Vec<Rc<WebGpuSurface>>,
+}
+
+// The top-level windows of the current runtime in the order it created or
+// claimed them, its child windows, and the preserved windows it hasn't
+// claimed yet.
+struct WsiHotReloadWindows {
+  current: Vec<u64>,
+  children: Vec<u64>,
+  unclaimed: Vec<PreservedWindow>,
+  claimed_surfaces: HashMap<u64, Vec<Rc<WebGpuSurface>>>,
+}
+
+// Give a new runtime what the previous one left behind.
+pub(crate) fn restore(state: &mut OpState, proxy: &WsiEventLoopProxy) {
+  let preserved = proxy.hot_reload().0.take();
+  if let Some(gpu) = preserved.gpu {
+    deno_webgpu::restore_gpu(state, gpu);
+  }
+  state.put(WsiHotReloadWindows {
+    current: Vec::new(),
+    children: Vec::new(),
+    unclaimed: preserved.windows,
+    claimed_surfaces: HashMap::new(),
+  });
+}
+
+// Keep the top-level windows, GPU surfaces and devices of a runtime that's
+// about to be dropped for the next one, and destroy its child windows and the
+// windows it left unclaimed. Child windows aren't kept, since the next runtime
+// creates them again under their parents. Does nothing unless the runtime was
+// started with `WsiOptions::hot_reload`, so it's safe to call on any runtime.
+pub fn preserve(state: &mut OpState) {
+  let Some(windows) = state.try_take::<WsiHotReloadWindows>() else {
+    return;
+  };
+  let proxy = state.borrow::<Rc<WsiEventLoopProxy>>().clone();
+  let destroyed: Vec<_> = windows
+    .children
+    .iter()
+    .copied()
+    .chain(windows.unclaimed.iter().map(|window| window.wid))
+    .collect();
+  for &wid in &destroyed {
+    proxy.aspect_ratios().unlock(wid);
+  }
+  let open = proxy
+    .execute(move |_, windows| {
+      for wid in destroyed {
+        remove_window(windows, wid);
+      }
+      windows.keys().copied().collect::<HashSet<_>>()
+    })
+    .unwrap_or_default();
+
+  let names = &state.borrow::<WsiWindowNames>().0;
+  let surfaces = &state.borrow::<WsiWindowSurfaces>().0;
+  let windows = windows
+    .current
+    .into_iter()
+    .filter(|wid| open.contains(wid))
+    .map(|wid| PreservedWindow {
+      wid,
+      name: names.get(&wid).cloned(),
+      surfaces: surfaces
+        .get(&wid)
+        .into_iter()
+        .flatten()
+        .filter_map(|surface| surface.upgrade())
+        .collect(),
+    })
+    .collect();
+  let gpu = deno_webgpu::preserve_gpu(state);
+  *proxy.hot_reload().0.borrow_mut() = Preserved { windows, gpu };
+}
+
+// Hand a preserved window to Deno.wsi.createWindow(), preferring one with
+// the same name and otherwise taking the first unnamed one.
+pub(crate) fn claim_window(
+  state: &mut OpState,
+  name: Option<&str>,
+) -> Option<u64> {
+  let windows = state.try_borrow_mut::<WsiHotReloadWindows>()?;
+  let index = name
+    .and_then(|name| {
+      windows
+        .unclaimed
+        .iter()
+        .position(|window| window.name.as_deref() == Some(name))
+    })
+    .or_else(|| {
+      windows
+        .unclaimed
+        .iter()
+        .position(|window| window.name.is_none())
+    })?;
+  let window = windows.unclaimed.remove(index);
+  windows.claimed_surfaces.insert(window.wid, window.surfaces);
+  Some(window.wid)
+}
+
+// Count a window as one of the current runtime's, to preserve it later if it
+// has no parent, or destroy it if it does.
+pub(crate) fn add_window(state: &mut OpState, wid: u64, child: bool) {
+  if let Some(windows) = state.try_borrow_mut::<WsiHotReloadWindows>() {
+    if child {
+      windows.children.push(wid);
+    } else {
+      windows.current.push(wid);
+    }
+  }
+}
+
+pub(crate) fn forget_window(state: &mut OpState, wid: u64) {
+  if let Some(windows) = state.try_borrow_mut::<WsiHotReloadWindows>() {
+    windows.current.retain(|&current| current != wid);
+    windows.children.retain(|&child| child != wid);
+    windows.claimed_surfaces.remove(&wid);
+  }
+}
+
+// Hand a preserved GPU surface of a claimed window to
+// WSIWindow.getGPUSurface(), in the order the previous runtime created them.
+pub(crate) fn take_surface(
+  state: &mut OpState,
+  wid: u64,
+) -> Option<Rc<WebGpuSurface>> {
+  let windows = state.try_borrow_mut::<WsiHotReloadWindows>()?;
+  let surfaces = windows.claimed_surfaces.get_mut(&wid)?;
+  (!surfaces.is_empty()).then(|| surfaces.remove(0))
+}
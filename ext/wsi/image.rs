@@ -0,0 +1,52 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// An image as 8-bit RGBA pixels in rows from top to bottom, e.g. one decoded
+// from a PNG file or captured from a window.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct WsiRgbaImage {
+  pub width: u32,
+  pub height: u32,
+  pub rgba: Vec<u8>,
+}
+
+impl WsiRgbaImage {
+  // Returns `None` if the data isn't a PNG image.
+  pub fn decode_png(png: &[u8]) -> Option<Self> {
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).ok()?;
+    pixels.truncate(info.buffer_size());
+    let rgba = match info.color_type {
+      png::ColorType::Rgba => pixels,
+      png::ColorType::Rgb => pixels
+        .chunks_exact(3)
+        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+        .collect(),
+      png::ColorType::GrayscaleAlpha => pixels
+        .chunks_exact(2)
+        .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+        .collect(),
+      png::ColorType::Grayscale => {
+        pixels.iter().flat_map(|&g| [g, g, g, u8::MAX]).collect()
+      }
+      // Expanded to RGB(A) by the transformations.
+      png::ColorType::Indexed => return None,
+    };
+    Some(Self {
+      width: info.width,
+      height: info.height,
+      rgba,
+    })
+  }
+
+  pub fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&self.rgba)?;
+    Ok(png)
+  }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize, Serializer};
+use std::ops::AddAssign;
 use winit::{
   event::{
     ElementState, Force, MouseButton, MouseScrollDelta, TouchPhase,
@@ -243,7 +244,7 @@ enum WsiKeyCodeDef {
   Cut,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum WsiMouseButton {
   Left,
   Right,
@@ -286,6 +287,13 @@ impl From<(f64, f64)> for WsiMouseDelta {
   }
 }
 
+impl AddAssign for WsiMouseDelta {
+  fn add_assign(&mut self, other: Self) {
+    self.x += other.x;
+    self.y += other.y;
+  }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum WsiScrollDelta {
@@ -334,7 +342,7 @@ impl From<Force> for WsiTouchForce {
   }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Copy, Debug, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum WsiTouchPhase {
   Started,
@@ -0,0 +1,565 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{event::WsiEvent, input::WsiButtonState, scan_codes};
+use std::collections::{HashMap, HashSet};
+use winit::{event::ModifiersState, window::Window};
+
+// What winit doesn't report with key events: the modifier keys held in each
+// window, which keys are down, to tell auto-repeated presses from new ones,
+// and what the keys mean in the keyboard layout.
+#[derive(Default)]
+pub struct KeyboardState {
+  modifiers: HashMap<u64, u32>,
+  pressed: HashSet<(u64, u32)>,
+}
+
+impl KeyboardState {
+  // Fill in the layout dependent fields, modifiers and repeat flag of key
+  // events.
+  pub fn update(
+    &mut self,
+    event: &mut WsiEvent,
+    windows: &HashMap<u64, Window>,
+  ) {
+    match event {
+      WsiEvent::ModifiersChanged { window, modifiers } => {
+        self.modifiers.insert(*window, *modifiers);
+      }
+      // Windows that lose focus don't see their keys released.
+      WsiEvent::WindowFocus {
+        window,
+        has_focus: false,
+      } => {
+        self.pressed.retain(|&(other, _)| other != *window);
+      }
+      WsiEvent::InputKey {
+        window,
+        scan_code,
+        code,
+        key,
+        text,
+        state,
+        modifiers,
+        repeat,
+        ..
+      } => {
+        *modifiers = self.modifiers.get(window).copied().unwrap_or(0);
+        let key_id = (*window, *scan_code);
+        let pressed = match state {
+          WsiButtonState::Pressed => {
+            *repeat = !self.pressed.insert(key_id);
+            true
+          }
+          WsiButtonState::Released => {
+            self.pressed.remove(&key_id);
+            false
+          }
+        };
+
+        *code = scan_codes::code(*scan_code);
+        let named = code.and_then(named_key);
+        let typed = match (named, windows.get(window)) {
+          (None, Some(window)) => key_text(window, *scan_code, true),
+          _ => None,
+        };
+        *key = match (named, &typed) {
+          (Some(name), _) => name.to_string(),
+          (None, Some(KeyText::Text(text))) => text.clone(),
+          (None, Some(KeyText::Dead)) => "Dead".to_string(),
+          (None, None) => "Unidentified".to_string(),
+        };
+        let modifiers = ModifiersState::from_bits_truncate(*modifiers);
+        *text = match typed {
+          Some(KeyText::Text(typed)) if pressed && !is_shortcut(modifiers) => {
+            Some(typed)
+          }
+          _ => None,
+        };
+      }
+      _ => {}
+    }
+  }
+}
+
+// What a key types in the current keyboard layout.
+pub enum KeyText {
+  Text(String),
+  // Combines with the next key instead, like the "´" in "é".
+  Dead,
+}
+
+// What a key types in the current keyboard layout, with the Shift, Caps Lock
+// and AltGr or Option keys held now if `modifiers` is set but no other
+// modifiers. Returns `None` for keys that type control characters or nothing,
+// and where the layout can't be read, which is the case on Wayland.
+fn key_text(
+  window: &Window,
+  scan_code: u32,
+  modifiers: bool,
+) -> Option<KeyText> {
+  match platform::key_text(window, scan_code, modifiers)? {
+    KeyText::Text(text) if text.chars().any(char::is_control) => None,
+    key_text => Some(key_text),
+  }
+}
+
+// A label for a key in the current keyboard layout, for showing key bindings:
+// what it types without modifiers, in upper case, or else its name. Without a
+// window to read the layout through, or where the layout can't be read, keys
+// that type text are labeled with their `code`.
+pub fn key_label(window: Option<&Window>, scan_code: u32) -> Option<String> {
+  let code = scan_codes::code(scan_code);
+  if let Some(name) = code.and_then(named_key) {
+    return Some(name.to_string());
+  }
+  match window.and_then(|window| key_text(window, scan_code, false)) {
+    Some(KeyText::Text(text)) if text == " " => Some("Space".to_string()),
+    Some(KeyText::Text(text)) => Some(text.to_uppercase()),
+    _ => code.map(str::to_string),
+  }
+}
+
+// The scan code of the key with a label from `key_label`, ignoring case, or
+// with a `code`.
+pub fn key_scan_code(window: Option<&Window>, label: &str) -> Option<u32> {
+  let label = label.to_lowercase();
+  scan_codes::scan_codes()
+    .find(|&scan_code| {
+      scan_codes::code(scan_code)
+        .map_or(false, |code| code.to_lowercase() == label)
+    })
+    .or_else(|| {
+      scan_codes::scan_codes().find(|&scan_code| {
+        key_label(window, scan_code)
+          .map_or(false, |key_label| key_label.to_lowercase() == label)
+      })
+    })
+}
+
+// The W3C `KeyboardEvent.key` of keys that don't type anything, which is the
+// same in every layout.
+// https://www.w3.org/TR/uievents-key/
+fn named_key(code: &'static str) -> Option<&'static str> {
+  let is_function_key =
+    code.starts_with('F') && code[1..].parse::<u8>().is_ok();
+  Some(match code {
+    "AltLeft" | "AltRight" => "Alt",
+    "ControlLeft" | "ControlRight" => "Control",
+    "MetaLeft" | "MetaRight" => "Meta",
+    "ShiftLeft" | "ShiftRight" => "Shift",
+    "NumpadEnter" => "Enter",
+    "Backspace" | "CapsLock" | "ContextMenu" | "Convert" | "Delete" | "End"
+    | "Enter" | "Escape" | "Fn" | "Home" | "Insert" | "KanaMode"
+    | "NonConvert" | "NumLock" | "PageDown" | "PageUp" | "Pause"
+    | "PrintScreen" | "ScrollLock" | "Tab" => code,
+    _ if is_function_key
+      || code.starts_with("Arrow")
+      || code.starts_with("AudioVolume") =>
+    {
+      code
+    }
+    _ => return None,
+  })
+}
+
+// Whether the modifiers make a key press a shortcut rather than typing. Option
+// types text on macOS, and Ctrl+Alt is AltGr on Windows.
+fn is_shortcut(modifiers: ModifiersState) -> bool {
+  if cfg!(target_os = "macos") {
+    modifiers.ctrl() || modifiers.logo()
+  } else {
+    modifiers.logo() || modifiers.ctrl() != modifiers.alt()
+  }
+}
+
+// The modifier keys held now, as `ModifiersState` bits, for when no
+// ModifiersChanged event says so yet. X11 needs a window to reach the display;
+// elsewhere than Windows, macOS and X11 this returns `None`.
+pub fn modifiers(window: Option<&Window>) -> Option<u32> {
+  platform::modifiers(window).map(|modifiers| modifiers.bits())
+}
+
+// Forget a dead key that was pressed but hasn't composed a character yet, so
+// the next key press isn't combined with it. Only Windows and macOS keep dead
+// key state this can reach; elsewhere this does nothing.
+pub fn reset_dead_keys(window: &Window) {
+  platform::reset_dead_keys(window)
+}
+
+// Keys are translated with the thread's keyboard layout and state. A pending
+// dead key is part of that state, which translating any key consumes unless
+// told not to.
+// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-tounicodeex
+#[cfg(windows)]
+mod platform {
+  use super::KeyText;
+  use winapi::um::winuser::{
+    GetAsyncKeyState, GetKeyboardLayout, GetKeyboardState, MapVirtualKeyExW,
+    MapVirtualKeyW, ToUnicode, ToUnicodeEx, MAPVK_VK_TO_VSC,
+    MAPVK_VSC_TO_VK_EX, VK_CONTROL, VK_LCONTROL, VK_LMENU, VK_LWIN, VK_MENU,
+    VK_RCONTROL, VK_RMENU, VK_RWIN, VK_SHIFT, VK_SPACE,
+  };
+  use winit::{event::ModifiersState, window::Window};
+
+  // Translating with flag 4 leaves the keyboard state, including a pending
+  // dead key, as it is.
+  pub fn key_text(
+    _window: &Window,
+    scan_code: u32,
+    modifiers: bool,
+  ) -> Option<KeyText> {
+    let mut key_state = [0; 256];
+    let mut chars = [0; 8];
+    // SAFETY: The buffers are as long as the functions expect.
+    let len = unsafe {
+      let layout = GetKeyboardLayout(0);
+      let vk = MapVirtualKeyExW(scan_code, MAPVK_VSC_TO_VK_EX, layout);
+      if vk == 0 || modifiers && GetKeyboardState(key_state.as_mut_ptr()) == 0 {
+        return None;
+      }
+      // Ctrl and Alt don't change what keys type, unless they're held
+      // together as AltGr.
+      let held = |vk: i32| key_state[vk as usize] & 0x80 != 0;
+      if !(held(VK_CONTROL) && held(VK_MENU)) {
+        for vk in [
+          VK_CONTROL,
+          VK_LCONTROL,
+          VK_RCONTROL,
+          VK_MENU,
+          VK_LMENU,
+          VK_RMENU,
+        ] {
+          key_state[vk as usize] = 0;
+        }
+      }
+      // The high bit of the scan code would mean the key is up, so leave out
+      // the extended prefix.
+      ToUnicodeEx(
+        vk,
+        scan_code & 0xff,
+        key_state.as_ptr(),
+        chars.as_mut_ptr(),
+        chars.len() as i32,
+        4,
+        layout,
+      )
+    };
+    match len {
+      0 => None,
+      len if len < 0 => Some(KeyText::Dead),
+      len => Some(KeyText::Text(String::from_utf16_lossy(
+        &chars[..len as usize],
+      ))),
+    }
+  }
+
+  // The keys' physical state, since the thread's key state only changes as
+  // it reads keyboard messages.
+  pub fn modifiers(_window: Option<&Window>) -> Option<ModifiersState> {
+    // SAFETY: GetAsyncKeyState has no preconditions.
+    let held = |vk: i32| unsafe { GetAsyncKeyState(vk) } as u16 & 0x8000 != 0;
+    let mut modifiers = ModifiersState::empty();
+    modifiers.set(ModifiersState::SHIFT, held(VK_SHIFT));
+    modifiers.set(ModifiersState::CTRL, held(VK_CONTROL));
+    modifiers.set(ModifiersState::ALT, held(VK_MENU));
+    modifiers.set(ModifiersState::LOGO, held(VK_LWIN) || held(VK_RWIN));
+    Some(modifiers)
+  }
+
+  pub fn reset_dead_keys(_window: &Window) {
+    let key_state = [0; 256];
+    let mut chars = [0; 8];
+    // SAFETY: The buffers are as long as ToUnicode is told they are.
+    unsafe {
+      let scan_code = MapVirtualKeyW(VK_SPACE as u32, MAPVK_VK_TO_VSC);
+      ToUnicode(
+        VK_SPACE as u32,
+        scan_code,
+        key_state.as_ptr(),
+        chars.as_mut_ptr(),
+        chars.len() as i32,
+        0,
+      );
+    }
+  }
+}
+
+// Keys are translated with the layout's 'uchr' data. AppKit composes dead keys
+// as marked text in the view.
+// https://developer.apple.com/documentation/coreservices/1390584-uckeytranslate
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::KeyText;
+  use std::ffi::c_void;
+  use winit::{event::ModifiersState, window::Window};
+
+  pub use crate::macos::reset_dead_keys;
+
+  const K_CG_EVENT_SOURCE_STATE_COMBINED_SESSION_STATE: i32 = 0;
+  const K_CG_EVENT_FLAG_MASK_ALPHA_SHIFT: u64 = 1 << 16;
+  const K_CG_EVENT_FLAG_MASK_SHIFT: u64 = 1 << 17;
+  const K_CG_EVENT_FLAG_MASK_CONTROL: u64 = 1 << 18;
+  const K_CG_EVENT_FLAG_MASK_ALTERNATE: u64 = 1 << 19;
+  const K_CG_EVENT_FLAG_MASK_COMMAND: u64 = 1 << 20;
+  // shiftKey, alphaLock and optionKey from HIToolbox/Events.h, shifted right
+  // by 8 bits as UCKeyTranslate expects.
+  const SHIFT_KEY: u32 = 1 << 1;
+  const ALPHA_LOCK: u32 = 1 << 2;
+  const OPTION_KEY: u32 = 1 << 3;
+  const K_UC_KEY_ACTION_DOWN: u16 = 0;
+
+  #[link(name = "Carbon", kind = "framework")]
+  extern "C" {
+    static kTISPropertyUnicodeKeyLayoutData: *const c_void;
+    fn TISCopyCurrentKeyboardLayoutInputSource() -> *mut c_void;
+    fn TISGetInputSourceProperty(
+      source: *mut c_void,
+      key: *const c_void,
+    ) -> *const c_void;
+    fn LMGetKbdType() -> u8;
+    fn UCKeyTranslate(
+      layout: *const c_void,
+      key_code: u16,
+      key_action: u16,
+      modifier_key_state: u32,
+      keyboard_type: u32,
+      options: u32,
+      dead_key_state: *mut u32,
+      max_len: usize,
+      len: *mut usize,
+      chars: *mut u16,
+    ) -> i32;
+  }
+
+  #[link(name = "CoreFoundation", kind = "framework")]
+  extern "C" {
+    fn CFDataGetBytePtr(data: *const c_void) -> *const u8;
+    fn CFRelease(cf: *const c_void);
+  }
+
+  #[link(name = "CoreGraphics", kind = "framework")]
+  extern "C" {
+    fn CGEventSourceFlagsState(state_id: i32) -> u64;
+  }
+
+  pub fn key_text(
+    _window: &Window,
+    scan_code: u32,
+    modifiers: bool,
+  ) -> Option<KeyText> {
+    // SAFETY: The input source is released after use, and the layout data it
+    // owns isn't used after that.
+    unsafe {
+      let flags = if modifiers {
+        CGEventSourceFlagsState(K_CG_EVENT_SOURCE_STATE_COMBINED_SESSION_STATE)
+      } else {
+        0
+      };
+      let mut modifiers = 0;
+      for (flag, modifier) in [
+        (K_CG_EVENT_FLAG_MASK_SHIFT, SHIFT_KEY),
+        (K_CG_EVENT_FLAG_MASK_ALPHA_SHIFT, ALPHA_LOCK),
+        (K_CG_EVENT_FLAG_MASK_ALTERNATE, OPTION_KEY),
+      ] {
+        if flags & flag != 0 {
+          modifiers |= modifier;
+        }
+      }
+
+      let source = TISCopyCurrentKeyboardLayoutInputSource();
+      if source.is_null() {
+        return None;
+      }
+      let data =
+        TISGetInputSourceProperty(source, kTISPropertyUnicodeKeyLayoutData);
+      let mut dead_key_state = 0;
+      let mut chars = [0; 8];
+      let mut len = 0;
+      let status = if data.is_null() {
+        -1
+      } else {
+        UCKeyTranslate(
+          CFDataGetBytePtr(data).cast(),
+          scan_code as u16,
+          K_UC_KEY_ACTION_DOWN,
+          modifiers,
+          LMGetKbdType() as u32,
+          0,
+          &mut dead_key_state,
+          chars.len(),
+          &mut len,
+          chars.as_mut_ptr(),
+        )
+      };
+      CFRelease(source);
+      match (status, len) {
+        (0, 0) if dead_key_state != 0 => Some(KeyText::Dead),
+        (0, len) if len > 0 => {
+          Some(KeyText::Text(String::from_utf16_lossy(&chars[..len])))
+        }
+        _ => None,
+      }
+    }
+  }
+
+  pub fn modifiers(_window: Option<&Window>) -> Option<ModifiersState> {
+    // SAFETY: CGEventSourceFlagsState has no preconditions.
+    let flags = unsafe {
+      CGEventSourceFlagsState(K_CG_EVENT_SOURCE_STATE_COMBINED_SESSION_STATE)
+    };
+    let mut modifiers = ModifiersState::empty();
+    for (flag, modifier) in [
+      (K_CG_EVENT_FLAG_MASK_SHIFT, ModifiersState::SHIFT),
+      (K_CG_EVENT_FLAG_MASK_CONTROL, ModifiersState::CTRL),
+      (K_CG_EVENT_FLAG_MASK_ALTERNATE, ModifiersState::ALT),
+      (K_CG_EVENT_FLAG_MASK_COMMAND, ModifiersState::LOGO),
+    ] {
+      modifiers.set(modifier, flags & flag != 0);
+    }
+    Some(modifiers)
+  }
+}
+
+// X11 keys are translated with the keymap's keysyms for the current group and
+// Shift, Caps Lock and AltGr state. Wayland keymaps aren't reachable through
+// winit.
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use super::KeyText;
+  use raw_window_handle::{HasRawDisplayHandle, RawDisplayHandle};
+  use std::mem;
+  use winit::{event::ModifiersState, window::Window};
+  use x11_dl::xlib::{self, Xlib};
+
+  const XKB_USE_CORE_KBD: u32 = 0x0100;
+  // Linux key codes are X11 key codes less 8.
+  const KEYCODE_OFFSET: u32 = 8;
+
+  thread_local! {
+    static XLIB: Option<Xlib> = Xlib::open().ok();
+  }
+
+  pub fn key_text(
+    window: &Window,
+    scan_code: u32,
+    modifiers: bool,
+  ) -> Option<KeyText> {
+    let display = display(window)?;
+    let keysym = XLIB.with(|xlib| {
+      let xlib = xlib.as_ref()?;
+      // SAFETY: The display comes from winit and is valid while the window
+      // exists.
+      unsafe {
+        let state = xkb_state(xlib, display);
+        // The group is the layout, which applies without modifiers too.
+        let mods = if modifiers { state.mods as u32 } else { 0 };
+        let shift = mods & xlib::ShiftMask != 0;
+        let level3 = mods & xlib::Mod5Mask != 0;
+        let keycode = (scan_code + KEYCODE_OFFSET) as u8;
+        let group = state.group as i32;
+        let level = shift as i32 + if level3 { 2 } else { 0 };
+        // Keys without a shifted level type the same with Shift.
+        let keysym = [level, level & 2, 0]
+          .into_iter()
+          .map(|level| {
+            (xlib.XkbKeycodeToKeysym)(display, keycode, group, level)
+          })
+          .find(|&keysym| keysym != 0)?;
+        // Caps Lock swaps the case of letters.
+        if mods & xlib::LockMask == 0 {
+          return Some(keysym);
+        }
+        let (mut lower, mut upper) = (0, 0);
+        (xlib.XConvertCase)(keysym, &mut lower, &mut upper);
+        Some(if shift { lower } else { upper })
+      }
+    })?;
+    keysym_text(keysym)
+  }
+
+  // Alt and Super are Mod1 and Mod4 in the usual modifier mapping.
+  pub fn modifiers(window: Option<&Window>) -> Option<ModifiersState> {
+    let display = display(window?)?;
+    let mods = XLIB.with(|xlib| {
+      let xlib = xlib.as_ref()?;
+      // SAFETY: The display comes from winit and is valid while the window
+      // exists.
+      Some(unsafe { xkb_state(xlib, display) }.mods as u32)
+    })?;
+    let mut modifiers = ModifiersState::empty();
+    for (mask, modifier) in [
+      (xlib::ShiftMask, ModifiersState::SHIFT),
+      (xlib::ControlMask, ModifiersState::CTRL),
+      (xlib::Mod1Mask, ModifiersState::ALT),
+      (xlib::Mod4Mask, ModifiersState::LOGO),
+    ] {
+      modifiers.set(modifier, mods & mask != 0);
+    }
+    Some(modifiers)
+  }
+
+  fn display(window: &Window) -> Option<*mut xlib::Display> {
+    match window.raw_display_handle() {
+      RawDisplayHandle::Xlib(display) if !display.display.is_null() => {
+        Some(display.display.cast())
+      }
+      _ => None,
+    }
+  }
+
+  // SAFETY: The display must be valid.
+  unsafe fn xkb_state(
+    xlib: &Xlib,
+    display: *mut xlib::Display,
+  ) -> xlib::XkbStateRec {
+    let mut state: xlib::XkbStateRec = mem::zeroed();
+    (xlib.XkbGetState)(display, XKB_USE_CORE_KBD, &mut state);
+    state
+  }
+
+  // Latin-1 keysyms are their code points, and Unicode keysyms are their code
+  // points plus 0x1000000. Other legacy keysyms aren't translated.
+  // https://www.x.org/releases/current/doc/xproto/x11protocol.html#keysym_encoding
+  fn keysym_text(keysym: u64) -> Option<KeyText> {
+    let code_point = match keysym {
+      0xfe50..=0xfe8f => return Some(KeyText::Dead),
+      0x20..=0x7e | 0xa0..=0xff => keysym,
+      0x100_0000..=0x110_ffff => keysym - 0x100_0000,
+      _ => return None,
+    };
+    let c = char::from_u32(code_point as u32)?;
+    Some(KeyText::Text(c.to_string()))
+  }
+
+  pub fn reset_dead_keys(_window: &Window) {}
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use super::KeyText;
+  use winit::{event::ModifiersState, window::Window};
+
+  pub fn key_text(
+    _window: &Window,
+    _scan_code: u32,
+    _modifiers: bool,
+  ) -> Option<KeyText> {
+    None
+  }
+
+  pub fn modifiers(_window: Option<&Window>) -> Option<ModifiersState> {
+    None
+  }
+
+  pub fn reset_dead_keys(_window: &Window) {}
+}
@@ -0,0 +1,111 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use serde::Serialize;
+use std::{
+  cell::RefCell,
+  collections::{HashMap, VecDeque},
+  time::Instant,
+};
+
+// Only the most recent samples are kept so percentiles track current behavior.
+const MAX_SAMPLES: usize = 4096;
+
+// Input events waiting on a present are only kept up to this many per window,
+// the most recent ones. A window that's never presented to, e.g. because
+// nothing renders to it, would collect them forever otherwise.
+const MAX_UNPRESENTED: usize = 256;
+
+// Measures how long input events take to reach JS and to affect the screen.
+#[derive(Default)]
+pub struct WsiLatencyTracker {
+  // Delivery times of input events not yet followed by a present, per window.
+  unpresented: RefCell<HashMap<u64, VecDeque<Instant>>>,
+  to_receipt: RefCell<Samples>,
+  to_present: RefCell<Samples>,
+}
+
+impl WsiLatencyTracker {
+  // Record that JS received an input event for the given window.
+  pub fn received(&self, window: u64, delivered: Instant) {
+    self
+      .to_receipt
+      .borrow_mut()
+      .push(delivered.elapsed().as_secs_f64());
+    let mut unpresented = self.unpresented.borrow_mut();
+    let unpresented = unpresented.entry(window).or_default();
+    if unpresented.len() == MAX_UNPRESENTED {
+      unpresented.pop_front();
+    }
+    unpresented.push_back(delivered);
+  }
+
+  // Record that the given window presented a frame.
+  pub fn presented(&self, window: u64) {
+    let Some(delivered) = self.unpresented.borrow_mut().remove(&window) else {
+      return;
+    };
+    let mut to_present = self.to_present.borrow_mut();
+    for delivered in delivered {
+      to_present.push(delivered.elapsed().as_secs_f64());
+    }
+  }
+
+  pub fn forget(&self, window: u64) {
+    self.unpresented.borrow_mut().remove(&window);
+  }
+
+  pub fn metrics(&self) -> WsiLatencyMetrics {
+    WsiLatencyMetrics {
+      to_receipt: self.to_receipt.borrow().percentiles(),
+      to_present: self.to_present.borrow().percentiles(),
+    }
+  }
+}
+
+#[derive(Default)]
+struct Samples(VecDeque<f64>);
+
+impl Samples {
+  fn push(&mut self, seconds: f64) {
+    if self.0.len() == MAX_SAMPLES {
+      self.0.pop_front();
+    }
+    self.0.push_back(seconds * 1000.0);
+  }
+
+  fn percentiles(&self) -> WsiLatencyPercentiles {
+    let mut sorted = Vec::from_iter(self.0.iter().copied());
+    sorted.sort_by(f64::total_cmp);
+    let percentile = |p: f64| {
+      if sorted.is_empty() {
+        0.0
+      } else {
+        sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+      }
+    };
+    WsiLatencyPercentiles {
+      count: sorted.len(),
+      p50: percentile(0.5),
+      p90: percentile(0.9),
+      p99: percentile(0.99),
+      max: percentile(1.0),
+    }
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiLatencyMetrics {
+  to_receipt: WsiLatencyPercentiles,
+  to_present: WsiLatencyPercentiles,
+}
+
+// Latencies in milliseconds.
+#[derive(Serialize)]
+pub struct WsiLatencyPercentiles {
+  count: usize,
+  p50: f64,
+  p90: f64,
+  p99: f64,
+  max: f64,
+}
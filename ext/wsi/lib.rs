@@ -1,56 +1,341 @@
 // Copyright 2023 Jo Bates. All rights reserved. MIT license.
 
+mod aspect_ratio;
+pub mod assets;
+mod backdrop;
+mod badge;
+mod capabilities;
+mod capture;
+pub mod cleanup;
+mod click;
+mod color_profile;
+mod content_protection;
+pub mod crash;
 mod cursor;
+pub mod debugger;
 mod device_ids;
+mod device_info;
+mod device_motion;
+mod diagnostics;
+mod display;
+mod drag_drop;
+mod enabled;
 mod event;
 pub mod event_loop;
+mod gestures;
+mod global_input;
+mod hit_test;
+pub mod hot_reload;
+mod image;
 mod input;
+mod keyboard;
+mod latency;
+mod lifecycle;
+mod macos;
+mod monitor;
+mod orientation;
+mod pen;
 mod request;
+mod safe_area;
+mod scan_codes;
+mod session;
+mod shader_watch;
+mod shape;
+mod shortcuts;
+mod soft_keyboard;
+mod startup;
+mod taskbar;
+pub mod testing;
+mod theme;
+mod trace;
+mod url_scheme;
+pub mod user_event;
 mod window;
 
+pub use winit;
+
 use crate::{
-  cursor::{WsiCursorGrabMode, WsiCursorIcon},
+  aspect_ratio::fit,
+  assets::WsiAssets,
+  backdrop::{set_backdrop, supported_backdrops, WsiWindowBackdrop},
+  badge::set_badge,
+  capabilities::{capabilities, WsiCapabilities},
+  capture::capture_window,
+  click::{set_click_options, WsiClickOptions},
+  color_profile::color_profile,
+  content_protection::is_content_protected,
+  crash::set_crash_metadata,
+  cursor::{
+    forget_cursor_grab, set_cursor_confinement, set_cursor_grab,
+    WsiCursorGrabMode, WsiCursorIcon,
+  },
+  device_ids::winit_device_id,
+  device_info::{device_info, WsiDeviceInfo},
+  device_motion::set_device_motion_interval,
+  diagnostics::overlay_hotkey_window,
+  display::{
+    brightness, restore_gamma, set_brightness, set_gamma,
+    supported_display_controls, WsiDisplayControl,
+  },
+  drag_drop::{
+    register_drop_target, set_drop_types, start_drag, WsiDragData,
+    WsiDragImage, WsiDropType,
+  },
+  enabled::{forget_enabled, is_enabled, set_enabled},
   event::WsiEvent,
   event_loop::WsiEventLoopProxy,
+  gestures::register_gesture_handler,
+  global_input::set_global_input_enabled,
+  hit_test::{forget_hit_test_region, set_hit_test_region},
   input::WsiDeviceEventFilter,
+  keyboard::{key_label, key_scan_code, modifiers, reset_dead_keys},
+  latency::{WsiLatencyMetrics, WsiLatencyTracker},
+  lifecycle::{register_quit_handler, reply_to_quit_request},
+  macos::WsiAppMenuItem,
+  monitor::WsiMonitor,
+  orientation::{
+    lock_orientation, orientation, WsiOrientation, WsiOrientationLock,
+  },
+  safe_area::safe_area_insets,
+  session::{WsiSavedWindow, WsiSession},
+  shader_watch::{WsiShaderCompilation, WsiShaderWatches},
+  shape::{set_shape, WsiWindowShape},
+  shortcuts::set_system_shortcuts_inhibited,
+  soft_keyboard::{
+    hide_soft_keyboard, register_soft_keyboard_handler, show_soft_keyboard,
+    soft_keyboard_rect, unregister_soft_keyboard_handler,
+  },
+  startup::complete_startup,
+  taskbar::{
+    set_overlay, set_progress, WsiTaskbarOverlay, WsiTaskbarProgressState,
+  },
+  theme::{system_theme, watch_system_theme},
+  trace::{set_trace_enabled, trace_headless_event},
   window::{
+    center, set_corner_preference, set_skip_taskbar, set_title_bar_style,
+    set_undecorated_shadow, set_window_level, visible_rect, WsiCoordinateSpace,
     WsiCreateWindowOptions, WsiImePurpose, WsiResizeDirection,
-    WsiUserAttentionType, WsiWindowLevel, WsiWindowTheme,
+    WsiTitleBarStyle, WsiUserAttentionType, WsiWindowButtons, WsiWindowCorners,
+    WsiWindowInfo, WsiWindowLevel, WsiWindowTheme,
   },
 };
 use deno_core::{
-  anyhow, include_js_files, op, Extension, ExtensionBuilder, OpState,
-  ResourceId,
+  anyhow, include_js_files, op, serde_json, CancelHandle, CancelTryFuture,
+  Extension, ExtensionBuilder, OpState, ResourceId, ZeroCopyBuf,
 };
 use deno_webgpu::surface::WebGpuSurface;
 use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
-use std::{cell::RefCell, rc::Rc};
-use winit::{
-  dpi::{PhysicalPosition, PhysicalSize},
-  window::{Fullscreen, WindowBuilder, WindowButtons},
+use serde::Serialize;
+use std::{
+  cell::RefCell,
+  collections::{HashMap, HashSet},
+  path::{Path, PathBuf},
+  rc::{Rc, Weak},
+  time::{Duration, Instant},
 };
+use winit::window::{Fullscreen, Window, WindowBuilder};
 
 fn ext() -> ExtensionBuilder {
-  Extension::builder_with_deps("deno_wsi", &["deno_webgpu", "deno_webidl"])
+  Extension::builder_with_deps(
+    "deno_wsi",
+    &["deno_web", "deno_webgpu", "deno_webidl"],
+  )
+}
+
+pub trait WsiPermissions {
+  fn check_read(
+    &mut self,
+    path: &Path,
+    api_name: &str,
+  ) -> Result<(), anyhow::Error>;
+  fn check_write_all(&mut self, api_name: &str) -> Result<(), anyhow::Error>;
+  fn check_run_all(&mut self, api_name: &str) -> Result<(), anyhow::Error>;
+  fn check_wsi(&mut self, api_name: &str) -> Result<(), anyhow::Error>;
+  fn check_wsi_devices(&mut self, api_name: &str) -> Result<(), anyhow::Error>;
+  fn allows_wsi_devices(&self) -> bool;
+  fn check_wsi_global_input(
+    &mut self,
+    api_name: &str,
+  ) -> Result<(), anyhow::Error>;
+  fn allows_wsi_global_input(&self) -> bool;
+}
+
+// Options that apply to the whole WSI session.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiOptions {
+  // Use virtual windows instead of real ones.
+  pub headless: bool,
+  // Measure input latency for Deno.wsi.metrics().
+  pub measure_latency: bool,
+  // Report recoverable errors as events and try to recover instead of failing.
+  pub resilient: bool,
+  // Log events and requests on the event loop to stderr.
+  pub trace: bool,
+  // Keep windows, GPU surfaces and devices for the next runtime when --watch
+  // restarts this one.
+  #[serde(skip)]
+  pub hot_reload: bool,
+  // Default for WSICreateWindowOptions.appId, given on the command line.
+  pub app_id: Option<String>,
+  // Defaults for WSICreateWindowOptions from the configuration file.
+  pub window_defaults: Option<serde_json::Value>,
+  // Where the window layout of this program is saved between runs.
+  #[serde(skip)]
+  pub session_file: Option<PathBuf>,
+  // Where Deno.wsi.assets reads from.
+  #[serde(skip)]
+  pub assets: Option<WsiAssets>,
+}
+
+// Coordinate spaces of windows that don't use physical pixels, by window.
+#[derive(Default)]
+struct WsiCoordinateSpaces(HashMap<u64, WsiCoordinateSpace>);
+
+fn coordinate_space(state: &OpState, wid: u64) -> WsiCoordinateSpace {
+  let spaces = &state.borrow::<WsiCoordinateSpaces>().0;
+  spaces.get(&wid).copied().unwrap_or_default()
+}
+
+// The tags that windows were created with, by window, so events and
+// Deno.wsi.windows() can be routed without a map in JS.
+#[derive(Default)]
+struct WsiWindowTags(HashMap<u64, serde_json::Value>);
+
+// The names that windows save their geometry under, by window.
+#[derive(Default)]
+struct WsiWindowNames(HashMap<u64, String>);
+
+// Saves the geometry of a named window to the session file, if the program has
+// one. Losing it isn't worth failing the program over, so errors are ignored.
+fn save_window_geometry(state: &OpState, wid: u64) {
+  let name = state.borrow::<WsiWindowNames>().0.get(&wid).cloned();
+  let Some(name) = name else {
+    return;
+  };
+  let Some(session_file) = state.borrow::<WsiOptions>().session_file.clone()
+  else {
+    return;
+  };
+  let proxy = state.borrow::<Rc<WsiEventLoopProxy>>();
+  let saved = proxy.execute(move |_, windows| {
+    windows
+      .get(&wid)
+      .map(|window| WsiSavedWindow::new(name, window))
+  });
+  if let Ok(Some(saved)) = saved {
+    let _ = WsiSession::save_window(&session_file, saved);
+  }
+}
+
+// GPU surfaces that follow the inner size of their window, by window.
+#[derive(Default)]
+struct WsiAutoResizeSurfaces(HashMap<u64, Rc<WebGpuSurface>>);
+
+// All GPU surfaces of each window, which count missed vertical blanks at the
+// refresh rate of the window's monitor. They're weak, so closing a surface
+// still frees it.
+#[derive(Default)]
+struct WsiWindowSurfaces(HashMap<u64, Vec<Weak<WebGpuSurface>>>);
+
+// Windows whose GPU surfaces show the diagnostics overlay, including surfaces
+// created later.
+#[derive(Default)]
+struct WsiDiagnosticsOverlays(HashSet<u64>);
+
+fn set_diagnostics_overlay(state: &mut OpState, wid: u64, enabled: bool) {
+  let overlays = &mut state.borrow_mut::<WsiDiagnosticsOverlays>().0;
+  if enabled {
+    overlays.insert(wid);
+  } else {
+    overlays.remove(&wid);
+  }
+  let surfaces = state.borrow::<WsiWindowSurfaces>().0.get(&wid);
+  for surface in surfaces.into_iter().flatten() {
+    if let Some(surface) = surface.upgrade() {
+      show_diagnostics_overlay(state, &surface, enabled);
+    }
+  }
+}
+
+fn show_diagnostics_overlay(
+  state: &OpState,
+  surface: &WebGpuSurface,
+  enabled: bool,
+) {
+  let lines = enabled.then(|| {
+    let proxy = state.borrow::<Rc<WsiEventLoopProxy>>().clone();
+    Box::new(move || vec![proxy.execute_latency().overlay_line()])
+      as Box<dyn Fn() -> Vec<String>>
+  });
+  surface.set_diagnostics_overlay(lines);
 }
 
-fn ops(
+// Deno.wsi.requestFrame() resolves on a grid of vertical blanks that starts
+// here. Window systems don't report when vertical blanks actually happen, so
+// only the interval between frames matches the display.
+struct WsiFrameEpoch(Instant);
+
+fn ops<P: WsiPermissions + 'static>(
   ext: &mut ExtensionBuilder,
   event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
+  options: WsiOptions,
 ) -> &mut ExtensionBuilder {
   ext
     .ops(vec![
-      op_wsi_next_event::decl(),
-      op_wsi_set_device_event_filter::decl(),
-      op_wsi_create_window::decl(),
+      op_wsi_next_event::decl::<P>(),
+      op_wsi_get_options::decl(),
+      op_wsi_metrics::decl(),
+      op_wsi_set_trace_enabled::decl(),
+      op_wsi_trace_headless_event::decl(),
+      op_wsi_wait_for_frame::decl(),
+      op_wsi_get_saved_session::decl(),
+      op_wsi_clear_saved_session::decl(),
+      op_wsi_assets_list::decl(),
+      op_wsi_assets_read::decl(),
+      op_wsi_check_permission::decl::<P>(),
+      op_wsi_watch_shader::decl::<P>(),
+      op_wsi_unwatch_shader::decl(),
+      op_wsi_take_shader_reload::decl(),
+      op_wsi_set_device_event_filter::decl::<P>(),
+      op_wsi_set_device_motion_interval::decl(),
+      op_wsi_get_device_info::decl::<P>(),
+      op_wsi_set_global_input_enabled::decl::<P>(),
+      op_wsi_set_click_options::decl(),
+      op_wsi_get_capabilities::decl(),
+      op_wsi_get_monitors::decl(),
+      op_wsi_get_system_theme::decl(),
+      op_wsi_get_key_label::decl(),
+      op_wsi_get_key_scan_code::decl(),
+      op_wsi_get_modifiers::decl(),
+      op_wsi_set_app_menu::decl(),
+      op_wsi_set_dock_badge::decl(),
+      op_wsi_set_dock_icon::decl(),
+      op_wsi_request_dock_attention::decl(),
+      op_wsi_register_url_scheme::decl::<P>(),
+      op_wsi_reply_to_quit_request::decl(),
+      op_wsi_set_crash_metadata::decl(),
+      op_wsi_create_window::decl::<P>(),
+      op_wsi_windows::decl(),
+      op_wsi_window_set_coordinate_space::decl(),
+      op_wsi_window_get_supported_backdrops::decl(),
+      op_wsi_window_set_backdrop::decl(),
+      op_wsi_window_is_content_protected::decl(),
       op_wsi_window_set_content_protected::decl(),
+      op_wsi_window_set_corner_preference::decl(),
+      op_wsi_window_set_title_bar_style::decl(),
+      op_wsi_window_set_cursor_confinement_rect::decl(),
       op_wsi_window_set_cursor_grab_mode::decl(),
       op_wsi_window_set_cursor_hit_test_enabled::decl(),
+      op_wsi_window_set_cursor_hit_test_region::decl(),
       op_wsi_window_set_cursor_icon::decl(),
       op_wsi_window_set_cursor_position::decl(),
       op_wsi_window_set_cursor_visible::decl(),
       op_wsi_window_is_decorated::decl(),
       op_wsi_window_set_decorated::decl(),
+      op_wsi_window_set_diagnostics_overlay::decl(),
+      op_wsi_window_is_enabled::decl(),
+      op_wsi_window_set_enabled::decl(),
       op_wsi_window_get_enabled_buttons::decl(),
       op_wsi_window_set_enabled_buttons::decl(),
       op_wsi_window_has_focus::decl(),
@@ -61,9 +346,15 @@ fn ops(
       op_wsi_window_set_ime_allowed::decl(),
       op_wsi_window_set_ime_position::decl(),
       op_wsi_window_set_ime_purpose::decl(),
+      op_wsi_window_reset_dead_keys::decl(),
+      op_wsi_window_show_soft_keyboard::decl(),
+      op_wsi_window_hide_soft_keyboard::decl(),
+      op_wsi_window_get_soft_keyboard_rect::decl(),
+      op_wsi_window_set_system_shortcuts_inhibited::decl(),
       op_wsi_window_get_inner_position::decl(),
       op_wsi_window_get_outer_position::decl(),
       op_wsi_window_set_outer_position::decl(),
+      op_wsi_window_center::decl(),
       op_wsi_window_get_inner_size::decl(),
       op_wsi_window_get_outer_size::decl(),
       op_wsi_window_set_inner_size::decl(),
@@ -76,9 +367,24 @@ fn ops(
       op_wsi_window_set_maximized::decl(),
       op_wsi_window_is_resizable::decl(),
       op_wsi_window_set_resizable::decl(),
+      op_wsi_window_set_shadow::decl(),
+      op_wsi_window_set_shape::decl(),
       op_wsi_window_get_resize_increments::decl(),
       op_wsi_window_set_resize_increments::decl(),
+      op_wsi_window_set_aspect_ratio::decl(),
+      op_wsi_window_fit_inner_size::decl(),
       op_wsi_window_get_scale_factor::decl(),
+      op_wsi_window_get_refresh_rate::decl(),
+      op_wsi_window_get_color_profile::decl(),
+      op_wsi_window_get_supported_display_controls::decl(),
+      op_wsi_window_get_brightness::decl(),
+      op_wsi_window_set_brightness::decl(),
+      op_wsi_window_set_gamma::decl(),
+      op_wsi_window_set_skip_taskbar::decl(),
+      op_wsi_window_get_tag::decl(),
+      op_wsi_window_set_taskbar_progress::decl(),
+      op_wsi_window_set_taskbar_overlay::decl(),
+      op_wsi_window_set_badge::decl(),
       op_wsi_window_get_theme::decl(),
       op_wsi_window_set_theme::decl(),
       op_wsi_window_get_title::decl(),
@@ -86,29 +392,59 @@ fn ops(
       op_wsi_window_set_transparent::decl(),
       op_wsi_window_is_visible::decl(),
       op_wsi_window_set_visible::decl(),
+      op_wsi_window_get_visible_rect::decl(),
+      op_wsi_window_get_visible_fraction::decl(),
+      op_wsi_window_get_safe_area_insets::decl(),
+      op_wsi_window_get_orientation::decl(),
+      op_wsi_window_lock_orientation::decl(),
       op_wsi_window_begin_drag_move::decl(),
       op_wsi_window_begin_drag_resize::decl(),
+      op_wsi_window_set_drop_types::decl(),
+      op_wsi_window_start_drag::decl(),
       op_wsi_window_request_redraw::decl(),
       op_wsi_window_request_user_attention::decl(),
+      op_wsi_window_capture_content::decl(),
       op_wsi_window_destroy::decl(),
     ])
     .state(move |state| {
       if let Some(event_loop_proxy) = &event_loop_proxy {
         state.put(event_loop_proxy.clone());
+        if options.hot_reload {
+          hot_reload::restore(state, event_loop_proxy);
+        }
+      }
+      if options.measure_latency {
+        state.put(Rc::new(WsiLatencyTracker::default()));
       }
+      if options.trace {
+        set_trace_enabled(true);
+      }
+      state.put(WsiAutoResizeSurfaces::default());
+      state.put(WsiWindowSurfaces::default());
+      state.put(WsiDiagnosticsOverlays::default());
+      state.put(WsiCoordinateSpaces::default());
+      state.put(WsiWindowTags::default());
+      state.put(WsiWindowNames::default());
+      state.put(WsiShaderWatches::default());
+      state.put(WsiFrameEpoch(Instant::now()));
+      state.put(options.clone());
     })
 }
 
-pub fn init_ops_and_esm(
+pub fn init_ops_and_esm<P: WsiPermissions + 'static>(
   event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
+  options: WsiOptions,
 ) -> Extension {
-  ops(&mut ext(), event_loop_proxy)
+  ops::<P>(&mut ext(), event_loop_proxy, options)
     .esm(include_js_files!("01_wsi.js", "02_idl_types.js",))
     .build()
 }
 
-pub fn init_ops(event_loop_proxy: Option<Rc<WsiEventLoopProxy>>) -> Extension {
-  ops(&mut ext(), event_loop_proxy).build()
+pub fn init_ops<P: WsiPermissions + 'static>(
+  event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
+  options: WsiOptions,
+) -> Extension {
+  ops::<P>(&mut ext(), event_loop_proxy, options).build()
 }
 
 fn try_borrow_event_loop_proxy<'a>(
@@ -124,47 +460,670 @@ fn try_borrow_event_loop_proxy<'a>(
 }
 
 #[op]
-async fn op_wsi_next_event(
+async fn op_wsi_next_event<P>(
   state: Rc<RefCell<OpState>>,
-) -> Result<WsiEvent, anyhow::Error> {
+  cancel_rid: Option<ResourceId>,
+  timeout_ms: Option<f64>,
+) -> Result<Option<WsiEvent>, anyhow::Error>
+where
+  P: WsiPermissions + 'static,
+{
+  // A call that times out leaves its event to the next call, like a cancelled
+  // one.
+  let timeout = timeout_ms.map(|timeout_ms| {
+    Duration::try_from_secs_f64(timeout_ms.max(0.0) / 1000.0)
+      .unwrap_or(Duration::MAX)
+  });
+  let next_event = async {
+    let next_event = next_event::<P>(state.clone());
+    match timeout {
+      Some(timeout) => tokio::time::timeout(timeout, next_event)
+        .await
+        .ok()
+        .transpose(),
+      None => next_event.await.map(Some),
+    }
+  };
+  // JS aborts the call by closing the cancel handle.
+  let Some(cancel_rid) = cancel_rid else {
+    return next_event.await;
+  };
+  let cancel_handle = state
+    .borrow_mut()
+    .resource_table
+    .get::<CancelHandle>(cancel_rid)?;
+  let result = next_event.try_or_cancel(cancel_handle).await;
+  state.borrow_mut().resource_table.close(cancel_rid).ok();
+  result
+}
+
+async fn next_event<P>(
+  state: Rc<RefCell<OpState>>,
+) -> Result<WsiEvent, anyhow::Error>
+where
+  P: WsiPermissions + 'static,
+{
   let proxy =
     try_borrow_event_loop_proxy(&state.borrow(), "Deno.wsi.nextEvent").clone();
   loop {
+    if let Some(event) = shader_watch::take_reload(&mut state.borrow_mut()) {
+      return Ok(event);
+    }
     match proxy.next_event().await? {
-      WsiEvent::Internal => continue,
-      event => return Ok(event),
+      (WsiEvent::Internal, _) => continue,
+      (event, _)
+        if event.is_device_input()
+          && !state.borrow().borrow::<P>().allows_wsi_devices() =>
+      {
+        continue
+      }
+      (event, _)
+        if event.is_global_input()
+          && !state.borrow().borrow::<P>().allows_wsi_global_input() =>
+      {
+        continue
+      }
+      (event, delivered) => {
+        // Programs often exit on the first close request without destroying
+        // the window, so named windows are saved then too.
+        if let WsiEvent::CloseRequested { window } = &event {
+          save_window_geometry(&state.borrow(), *window);
+        }
+        // Resize before JS sees the event so that the next frame it acquires
+        // already has the new size.
+        if let WsiEvent::WindowResized {
+          window,
+          inner_size: (width, height),
+        } = &event
+        {
+          if let Some(surface) = state
+            .borrow()
+            .borrow::<WsiAutoResizeSurfaces>()
+            .0
+            .get(window)
+          {
+            surface.resize(*width, *height);
+          }
+        }
+        if let WsiEvent::WindowRefreshRateChanged {
+          window,
+          refresh_rate,
+        } = &event
+        {
+          let state = state.borrow();
+          let surfaces = state.borrow::<WsiWindowSurfaces>().0.get(window);
+          for surface in surfaces.into_iter().flatten() {
+            if let Some(surface) = surface.upgrade() {
+              surface.set_refresh_rate_millihertz(*refresh_rate);
+            }
+          }
+        }
+        if let Some(window) = overlay_hotkey_window(&event) {
+          let mut state = state.borrow_mut();
+          let enabled =
+            !state.borrow::<WsiDiagnosticsOverlays>().0.contains(&window);
+          set_diagnostics_overlay(&mut state, window, enabled);
+        }
+        if let (Some(window), Some(tracker)) = (
+          event.input_window(),
+          state.borrow().try_borrow::<Rc<WsiLatencyTracker>>(),
+        ) {
+          tracker.received(window, delivered);
+        }
+        return Ok(event);
+      }
     }
   }
 }
 
 #[op]
-fn op_wsi_set_device_event_filter(
+fn op_wsi_get_options(state: &mut OpState) -> WsiOptions {
+  state.borrow::<WsiOptions>().clone()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WsiMetrics {
+  input_latency: Option<WsiLatencyMetrics>,
+}
+
+#[op]
+fn op_wsi_metrics(state: &mut OpState) -> WsiMetrics {
+  WsiMetrics {
+    input_latency: state
+      .try_borrow::<Rc<WsiLatencyTracker>>()
+      .map(|tracker| tracker.metrics()),
+  }
+}
+
+// Headless runtimes trace the events they make up, without an event loop.
+#[op]
+fn op_wsi_set_trace_enabled(state: &mut OpState, enabled: bool) {
+  if !state.borrow::<WsiOptions>().headless {
+    try_borrow_event_loop_proxy(state, "Deno.wsi.setTraceEnabled");
+  }
+  set_trace_enabled(enabled);
+}
+
+#[op]
+fn op_wsi_trace_headless_event(event: serde_json::Value) {
+  trace_headless_event(event);
+}
+
+#[op]
+async fn op_wsi_wait_for_frame(
+  state: Rc<RefCell<OpState>>,
+  refresh_rate_millihertz: u32,
+) -> Result<(), anyhow::Error> {
+  let epoch = state.borrow().borrow::<WsiFrameEpoch>().0;
+  let interval =
+    Duration::from_secs_f64(1000.0 / refresh_rate_millihertz.max(1) as f64);
+  let frames = epoch.elapsed().as_secs_f64() / interval.as_secs_f64();
+  let next_frame = epoch + interval.mul_f64(frames.floor() + 1.0);
+  tokio::time::sleep_until(next_frame.into()).await;
+  Ok(())
+}
+
+#[op]
+fn op_wsi_get_saved_session(state: &mut OpState) -> Option<WsiSession> {
+  let session_file = state.borrow::<WsiOptions>().session_file.as_ref()?;
+  WsiSession::load(session_file)
+}
+
+#[op]
+fn op_wsi_clear_saved_session(
+  state: &mut OpState,
+) -> Result<(), anyhow::Error> {
+  match &state.borrow::<WsiOptions>().session_file {
+    Some(session_file) => WsiSession::clear(session_file),
+    None => Ok(()),
+  }
+}
+
+fn borrow_assets(state: &OpState) -> Result<&WsiAssets, anyhow::Error> {
+  state.borrow::<WsiOptions>().assets.as_ref().ok_or_else(|| {
+    anyhow::anyhow!(
+      "No assets are available. Pass --wsi-assets to 'denog run' or 'denog compile'."
+    )
+  })
+}
+
+#[op]
+fn op_wsi_assets_list(
+  state: &mut OpState,
+) -> Result<Vec<String>, anyhow::Error> {
+  borrow_assets(state)?.list()
+}
+
+#[op]
+async fn op_wsi_assets_read(
+  state: Rc<RefCell<OpState>>,
+  path: String,
+) -> Result<ZeroCopyBuf, anyhow::Error> {
+  let assets = borrow_assets(&state.borrow())?.clone();
+  let data = tokio::task::spawn_blocking(move || assets.read(&path)).await??;
+  Ok(data.into())
+}
+
+#[op]
+fn op_wsi_check_permission<P>(
+  state: &mut OpState,
+  api_name: String,
+) -> Result<(), anyhow::Error>
+where
+  P: WsiPermissions + 'static,
+{
+  state.borrow_mut::<P>().check_wsi(&api_name)
+}
+
+#[op]
+fn op_wsi_watch_shader<P>(
+  state: &mut OpState,
+  device_rid: ResourceId,
+  path: String,
+  label: Option<String>,
+) -> Result<WsiShaderCompilation, anyhow::Error>
+where
+  P: WsiPermissions + 'static,
+{
+  let path = PathBuf::from(path);
+  state
+    .borrow_mut::<P>()
+    .check_read(&path, "Deno.wsi.watchShader")?;
+  let wake = state
+    .try_borrow::<Rc<WsiEventLoopProxy>>()
+    .map(|proxy| proxy.event_loop_proxy());
+  shader_watch::watch(state, device_rid, path, label, wake)
+}
+
+#[op]
+fn op_wsi_unwatch_shader(state: &mut OpState, watch: u32) {
+  shader_watch::unwatch(state, watch);
+}
+
+// Headless sessions have no event loop to wake, so JS asks for reloads itself.
+#[op]
+fn op_wsi_take_shader_reload(state: &mut OpState) -> Option<WsiEvent> {
+  shader_watch::take_reload(state)
+}
+
+#[op]
+fn op_wsi_set_device_event_filter<P>(
   state: &mut OpState,
   filter: WsiDeviceEventFilter,
-) {
-  try_borrow_event_loop_proxy(state, "Deno.wsi.setDeviceEventFilter").execute(
-    |window_target, _| window_target.set_device_event_filter(filter.into()),
+) -> Result<(), anyhow::Error>
+where
+  P: WsiPermissions + 'static,
+{
+  let proxy =
+    try_borrow_event_loop_proxy(state, "Deno.wsi.setDeviceEventFilter").clone();
+  // Receiving device events while unfocused amounts to a global input hook.
+  if let WsiDeviceEventFilter::Never = filter {
+    state
+      .borrow_mut::<P>()
+      .check_wsi_devices("Deno.wsi.setDeviceEventFilter")?;
+  }
+  proxy.execute(|window_target, _| {
+    window_target.set_device_event_filter(filter.into())
+  })?;
+  Ok(())
+}
+
+#[op]
+fn op_wsi_set_device_motion_interval(
+  state: &mut OpState,
+  milliseconds: f64,
+) -> Result<(), anyhow::Error> {
+  let interval = Duration::try_from_secs_f64(milliseconds.max(0.0) / 1000.0)
+    .unwrap_or(Duration::MAX);
+  try_borrow_event_loop_proxy(state, "Deno.wsi.setDeviceMotionInterval")
+    .execute(move |_, _| set_device_motion_interval(interval))
+}
+
+#[op]
+fn op_wsi_get_device_info<P>(
+  state: &mut OpState,
+  device_id: u32,
+) -> Result<Option<WsiDeviceInfo>, anyhow::Error>
+where
+  P: WsiPermissions + 'static,
+{
+  let proxy =
+    try_borrow_event_loop_proxy(state, "Deno.wsi.getDeviceInfo").clone();
+  // Device IDs come from device events, which need the same permission.
+  state
+    .borrow_mut::<P>()
+    .check_wsi_devices("Deno.wsi.getDeviceInfo")?;
+  let info = proxy
+    .execute(move |_, _| winit_device_id(device_id).and_then(device_info))?;
+  Ok(info)
+}
+
+#[op]
+fn op_wsi_set_global_input_enabled<P>(
+  state: &mut OpState,
+  enabled: bool,
+) -> Result<bool, anyhow::Error>
+where
+  P: WsiPermissions + 'static,
+{
+  let proxy =
+    try_borrow_event_loop_proxy(state, "Deno.wsi.setGlobalInputEnabled")
+      .clone();
+  if enabled {
+    state
+      .borrow_mut::<P>()
+      .check_wsi_global_input("Deno.wsi.setGlobalInputEnabled")?;
+  }
+  let event_loop_proxy = proxy.event_loop_proxy();
+  let hooked = proxy.execute(move |window_target, _| {
+    set_global_input_enabled(window_target, enabled, event_loop_proxy)
+  })?;
+  Ok(hooked)
+}
+
+#[op]
+fn op_wsi_set_click_options(
+  state: &mut OpState,
+  options: WsiClickOptions,
+) -> Result<(), anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.setClickOptions")
+    .execute(move |_, _| set_click_options(options))
+}
+
+#[op]
+fn op_wsi_get_capabilities(
+  state: &mut OpState,
+) -> Result<WsiCapabilities, anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.capabilities")
+    .execute(|window_target, _| capabilities(window_target))
+}
+
+#[op]
+fn op_wsi_get_monitors(
+  state: &mut OpState,
+) -> Result<Vec<WsiMonitor>, anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.getMonitors").execute(
+    |window_target, _| {
+      let primary = window_target.primary_monitor();
+      window_target
+        .available_monitors()
+        .map(|monitor| {
+          let is_primary = primary.as_ref() == Some(&monitor);
+          WsiMonitor::new(&monitor, is_primary)
+        })
+        .collect()
+    },
   )
 }
 
 #[op]
-fn op_wsi_create_window(
+fn op_wsi_get_system_theme(
+  state: &mut OpState,
+) -> Result<Option<WsiWindowTheme>, anyhow::Error> {
+  let proxy = try_borrow_event_loop_proxy(state, "Deno.wsi.getSystemTheme");
+  // Read here rather than on the event loop thread, which it could stall.
+  let theme = system_theme();
+  watch_system_theme(proxy.event_loop_proxy(), theme);
+  Ok(theme)
+}
+
+// Layouts are read through a window on X11, so use any window.
+#[op]
+fn op_wsi_get_key_label(
+  state: &mut OpState,
+  scan_code: u32,
+) -> Result<Option<String>, anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.getKeyLabel")
+    .execute(move |_, windows| key_label(windows.values().next(), scan_code))
+}
+
+#[op]
+fn op_wsi_get_key_scan_code(
+  state: &mut OpState,
+  label: String,
+) -> Result<Option<u32>, anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.getKeyScanCode")
+    .execute(move |_, windows| key_scan_code(windows.values().next(), &label))
+}
+
+#[op]
+fn op_wsi_get_modifiers(
+  state: &mut OpState,
+) -> Result<Option<u32>, anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.getModifiers")
+    .execute(|_, windows| modifiers(windows.values().next()))
+}
+
+#[op]
+fn op_wsi_set_app_menu(
+  state: &mut OpState,
+  items: Vec<Option<WsiAppMenuItem>>,
+) -> Result<(), anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.setAppMenu")
+    .execute(move |_, _| macos::set_app_menu(items))
+}
+
+#[op]
+fn op_wsi_set_dock_badge(
+  state: &mut OpState,
+  label: Option<String>,
+) -> Result<(), anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.setDockBadge")
+    .execute(move |_, _| macos::set_dock_badge(label))
+}
+
+#[op]
+fn op_wsi_set_dock_icon(
+  state: &mut OpState,
+  image: Option<ZeroCopyBuf>,
+) -> Result<(), anyhow::Error> {
+  let image = image.map(|image| image.to_vec());
+  let decoded = try_borrow_event_loop_proxy(state, "Deno.wsi.setDockIcon")
+    .execute(move |_, _| macos::set_dock_icon(image))?;
+  if !decoded {
+    anyhow::bail!("The dock icon could not be decoded");
+  }
+  Ok(())
+}
+
+#[op]
+fn op_wsi_request_dock_attention(
   state: &mut OpState,
+  attention_type: WsiUserAttentionType,
+) -> Result<(), anyhow::Error> {
+  let critical = matches!(attention_type, WsiUserAttentionType::Critical);
+  try_borrow_event_loop_proxy(state, "Deno.wsi.requestDockAttention")
+    .execute(move |_, _| macos::request_dock_attention(critical))
+}
+
+// Returns the URL this process was started to open, if any. Registering
+// changes which program the OS starts for the scheme, for the whole user
+// account, by writing a registry key or a desktop entry and running xdg-mime,
+// so it needs write and run permission too.
+#[op]
+fn op_wsi_register_url_scheme<P>(
+  state: &mut OpState,
+  scheme: String,
+) -> Result<Option<String>, anyhow::Error>
+where
+  P: WsiPermissions + 'static,
+{
+  let api_name = "Deno.wsi.registerUrlScheme";
+  try_borrow_event_loop_proxy(state, api_name);
+  let permissions = state.borrow_mut::<P>();
+  permissions.check_wsi(api_name)?;
+  permissions.check_write_all(api_name)?;
+  permissions.check_run_all(api_name)?;
+  url_scheme::check_scheme(&scheme)?;
+  url_scheme::register(&scheme)?;
+  Ok(url_scheme::take_launch_url(&scheme))
+}
+
+// Called by JS after an `app-quit-requested` event, once the `onBeforeQuit`
+// callbacks have run.
+#[op]
+fn op_wsi_reply_to_quit_request(
+  state: &mut OpState,
+  allow: bool,
+) -> Result<(), anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.onBeforeQuit")
+    .execute(move |_, _| reply_to_quit_request(allow))
+}
+
+#[op]
+fn op_wsi_set_crash_metadata(
+  state: &mut OpState,
+  metadata: HashMap<String, String>,
+) -> Result<bool, anyhow::Error> {
+  try_borrow_event_loop_proxy(state, "Deno.wsi.setCrashMetadata");
+  set_crash_metadata(metadata)
+}
+
+#[op]
+fn op_wsi_create_window<P>(
+  state: &mut OpState,
+  mut options: Option<WsiCreateWindowOptions>,
+) -> Result<u64, anyhow::Error>
+where
+  P: WsiPermissions + 'static,
+{
+  let proxy =
+    try_borrow_event_loop_proxy(state, "Deno.wsi.createWindow").clone();
+  state.borrow_mut::<P>().check_wsi("Deno.wsi.createWindow")?;
+  let space = options
+    .as_ref()
+    .and_then(|options| options.coordinate_space);
+  let corners = options.as_ref().and_then(|options| options.corners);
+  let tag = options.as_mut().and_then(|options| options.tag.take());
+  let name = options.as_ref().and_then(|options| options.name.clone());
+  let saved = name.as_ref().and_then(|name| {
+    let session_file = state.borrow::<WsiOptions>().session_file.as_ref()?;
+    WsiSession::saved_window(session_file, name)
+  });
+  let visible = options
+    .as_ref()
+    .and_then(|options| options.visible)
+    .unwrap_or(true);
+  let parent = options.as_ref().and_then(|options| options.parent);
+  let claimed = match parent {
+    Some(_) => None,
+    None => hot_reload::claim_window(state, name.as_deref()),
+  };
+  let wid = match claimed {
+    // A window kept by --watch only takes the title and visibility from the
+    // new options, since the rest describes how a window starts out.
+    Some(wid) => {
+      let title = options
+        .and_then(|options| options.title)
+        .unwrap_or_else(|| "Denog".into());
+      proxy.execute_with_window(wid, move |window| {
+        window.set_title(&title);
+        window.set_visible(visible);
+      })?;
+      wid
+    }
+    None => build_window(&proxy, options, saved, corners, visible)?,
+  };
+  hot_reload::add_window(state, wid, parent.is_some());
+  if let Some(space) = space {
+    state
+      .borrow_mut::<WsiCoordinateSpaces>()
+      .0
+      .insert(wid, space);
+  }
+  if let Some(tag) = tag {
+    state.borrow_mut::<WsiWindowTags>().0.insert(wid, tag);
+  }
+  if let Some(name) = name {
+    state.borrow_mut::<WsiWindowNames>().0.insert(wid, name);
+  }
+  Ok(wid)
+}
+
+fn build_window(
+  proxy: &WsiEventLoopProxy,
   options: Option<WsiCreateWindowOptions>,
+  saved: Option<WsiSavedWindow>,
+  corners: Option<WsiWindowCorners>,
+  visible: bool,
 ) -> Result<u64, anyhow::Error> {
-  try_borrow_event_loop_proxy(state, "Deno.wsi.createWindow")
-    .execute(|window_target, windows| {
-      let mut builder = WindowBuilder::new().with_title("Denog");
-      if let Some(options) = options {
-        builder = options.into_window_builder(builder);
+  let event_loop_proxy = proxy.event_loop_proxy();
+  let level = options.as_ref().and_then(|options| options.level);
+  let title_bar_style =
+    options.as_ref().and_then(|options| options.title_bar_style);
+  proxy.execute(move |window_target, windows| {
+    let mut builder = WindowBuilder::new().with_title("Denog");
+    if let Some(options) = options {
+      let parent = options.parent;
+      if let Some(saved) = saved {
+        builder = saved.restore(builder, &options, window_target);
       }
-      builder.build(window_target).map(|window| {
-        let wid = window.id().into();
-        windows.insert(wid, window);
-        wid
-      })
-    })
-    .map_err(Into::into)
+      builder = options.into_window_builder(builder);
+      if let Some(parent) = parent {
+        let Some(parent) = windows.get(&parent) else {
+          return Err(anyhow::anyhow!("The parent window was destroyed"));
+        };
+        // SAFETY: The parent window outlives the child, since JS destroys
+        // a window's children before the window itself.
+        builder = unsafe {
+          builder.with_parent_window(Some(parent.raw_window_handle()))
+        };
+      }
+    }
+    let window = builder.build(window_target)?;
+    if let Some(corners) = corners {
+      set_corner_preference(&window, corners);
+    }
+    if let Some(level) = level {
+      set_window_level(&window, level);
+    }
+    if let Some(style) = title_bar_style {
+      set_title_bar_style(&window, style);
+    }
+    if visible {
+      complete_startup(&window);
+    }
+    register_drop_target(&window, event_loop_proxy.clone());
+    register_soft_keyboard_handler(&window, event_loop_proxy);
+    register_gesture_handler(&window);
+    register_quit_handler(&window);
+    let wid = window.id().into();
+    windows.insert(wid, window);
+    Ok(wid)
+  })?
+}
+
+#[op]
+fn op_wsi_windows(
+  state: &mut OpState,
+) -> Result<Vec<WsiWindowInfo>, anyhow::Error> {
+  let mut infos = try_borrow_event_loop_proxy(state, "Deno.wsi.windows")
+    .execute(|window_target, windows| {
+      let primary = window_target.primary_monitor();
+      windows
+        .iter()
+        .map(|(&wid, window)| WsiWindowInfo {
+          window: wid,
+          title: window.title(),
+          focused: window.has_focus(),
+          visible: window.is_visible(),
+          monitor: window.current_monitor().map(|monitor| {
+            let is_primary = primary.as_ref() == Some(&monitor);
+            WsiMonitor::new(&monitor, is_primary)
+          }),
+          tag: None,
+        })
+        .collect::<Vec<_>>()
+    })?;
+  let tags = &state.borrow::<WsiWindowTags>().0;
+  for info in &mut infos {
+    info.tag = tags.get(&info.window).cloned();
+  }
+  Ok(infos)
+}
+
+#[op]
+fn op_wsi_window_set_coordinate_space(
+  state: &mut OpState,
+  wid: u64,
+  space: WsiCoordinateSpace,
+) {
+  state
+    .borrow_mut::<WsiCoordinateSpaces>()
+    .0
+    .insert(wid, space);
+}
+
+#[op]
+fn op_wsi_window_get_supported_backdrops(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Vec<WsiWindowBackdrop>, anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, supported_backdrops)
+}
+
+#[op]
+fn op_wsi_window_set_backdrop(
+  state: &mut OpState,
+  wid: u64,
+  backdrop: WsiWindowBackdrop,
+) -> Result<bool, anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| set_backdrop(window, backdrop))
+}
+
+#[op]
+fn op_wsi_window_is_content_protected(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<bool>, anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, is_content_protected)
 }
 
 #[op]
@@ -172,7 +1131,7 @@ fn op_wsi_window_set_content_protected(
   state: &mut OpState,
   wid: u64,
   protected: bool,
-) {
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| {
@@ -180,6 +1139,54 @@ fn op_wsi_window_set_content_protected(
     })
 }
 
+#[op]
+fn op_wsi_window_set_corner_preference(
+  state: &mut OpState,
+  wid: u64,
+  corners: WsiWindowCorners,
+) -> Result<(), anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      set_corner_preference(window, corners)
+    })
+}
+
+#[op]
+fn op_wsi_window_set_title_bar_style(
+  state: &mut OpState,
+  wid: u64,
+  style: WsiTitleBarStyle,
+) -> Result<(), anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| set_title_bar_style(window, style))
+}
+
+#[op]
+fn op_wsi_window_set_cursor_confinement_rect(
+  state: &mut OpState,
+  wid: u64,
+  rect: Option<(i32, i32, u32, u32)>,
+) -> Result<(), anyhow::Error> {
+  let space = coordinate_space(state, wid);
+  let rect = rect.map(|(x, y, width, height)| {
+    (space.position((x, y)), space.size((width, height)))
+  });
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      let scale_factor = window.scale_factor();
+      let rect = rect.map(|(position, size)| {
+        let position = position.to_physical(scale_factor);
+        let size = size.to_physical(scale_factor);
+        (position.x, position.y, size.width, size.height)
+      });
+      set_cursor_confinement(wid, window, rect)
+    })?
+    .map_err(Into::into)
+}
+
 #[op]
 fn op_wsi_window_set_cursor_grab_mode(
   state: &mut OpState,
@@ -188,7 +1195,7 @@ fn op_wsi_window_set_cursor_grab_mode(
 ) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| window.set_cursor_grab(mode.0))
+    .execute_with_window(wid, move |window| set_cursor_grab(wid, window, mode))?
     .map_err(Into::into)
 }
 
@@ -200,16 +1207,44 @@ fn op_wsi_window_set_cursor_hit_test_enabled(
 ) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| window.set_cursor_hittest(enabled))
+    .execute_with_window(wid, move |window| {
+      forget_hit_test_region(wid);
+      window.set_cursor_hittest(enabled)
+    })?
     .map_err(Into::into)
 }
 
+#[op]
+fn op_wsi_window_set_cursor_hit_test_region(
+  state: &mut OpState,
+  wid: u64,
+  rects: Option<Vec<(i32, i32, u32, u32)>>,
+) -> Result<(), anyhow::Error> {
+  let space = coordinate_space(state, wid);
+  let proxy = state.borrow::<Rc<WsiEventLoopProxy>>();
+  let wake = proxy.event_loop_proxy();
+  proxy.execute_with_window(wid, move |window| {
+    let scale_factor = window.scale_factor();
+    let rects = rects.map(|rects| {
+      rects
+        .into_iter()
+        .map(|(x, y, width, height)| {
+          let position = space.position((x, y)).to_physical(scale_factor);
+          let size = space.size((width, height)).to_physical(scale_factor);
+          (position.x, position.y, size.width, size.height)
+        })
+        .collect()
+    });
+    set_hit_test_region(wid, window, rects, wake)
+  })
+}
+
 #[op]
 fn op_wsi_window_set_cursor_icon(
   state: &mut OpState,
   wid: u64,
   icon: WsiCursorIcon,
-) {
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| window.set_cursor_icon(icon.0))
@@ -219,13 +1254,14 @@ fn op_wsi_window_set_cursor_icon(
 fn op_wsi_window_set_cursor_position(
   state: &mut OpState,
   wid: u64,
-  (x, y): (i32, i32),
+  position: (i32, i32),
 ) -> Result<(), anyhow::Error> {
+  let position = coordinate_space(state, wid).position(position);
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| {
-      window.set_cursor_position(PhysicalPosition { x, y })
-    })
+      window.set_cursor_position(position)
+    })?
     .map_err(Into::into)
 }
 
@@ -234,28 +1270,68 @@ fn op_wsi_window_set_cursor_visible(
   state: &mut OpState,
   wid: u64,
   visible: bool,
-) {
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| window.set_cursor_visible(visible))
 }
 
 #[op]
-fn op_wsi_window_is_decorated(state: &mut OpState, wid: u64) -> bool {
+fn op_wsi_window_is_decorated(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<bool, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| window.is_decorated())
 }
 
 #[op]
-fn op_wsi_window_set_decorated(state: &mut OpState, wid: u64, decorated: bool) {
+fn op_wsi_window_set_decorated(
+  state: &mut OpState,
+  wid: u64,
+  decorated: bool,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| window.set_decorations(decorated))
 }
 
 #[op]
-fn op_wsi_window_get_enabled_buttons(state: &mut OpState, wid: u64) -> u32 {
+fn op_wsi_window_set_diagnostics_overlay(
+  state: &mut OpState,
+  wid: u64,
+  enabled: bool,
+) {
+  set_diagnostics_overlay(state, wid, enabled);
+}
+
+#[op]
+fn op_wsi_window_is_enabled(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<bool, anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |_| is_enabled(wid))
+}
+
+#[op]
+fn op_wsi_window_set_enabled(
+  state: &mut OpState,
+  wid: u64,
+  enabled: bool,
+) -> Result<(), anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| set_enabled(wid, window, enabled))
+}
+
+#[op]
+fn op_wsi_window_get_enabled_buttons(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<u32, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| window.enabled_buttons().bits())
@@ -265,32 +1341,40 @@ fn op_wsi_window_get_enabled_buttons(state: &mut OpState, wid: u64) -> u32 {
 fn op_wsi_window_set_enabled_buttons(
   state: &mut OpState,
   wid: u64,
-  buttons: u32,
-) {
-  state.borrow::<Rc<WsiEventLoopProxy>>().execute_with_window(
-    wid,
-    move |window| {
-      window.set_enabled_buttons(WindowButtons::from_bits_truncate(buttons))
-    },
-  )
+  buttons: WsiWindowButtons,
+) -> Result<(), anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      window.set_enabled_buttons(buttons.into())
+    })
 }
 
 #[op]
-fn op_wsi_window_has_focus(state: &mut OpState, wid: u64) -> bool {
+fn op_wsi_window_has_focus(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<bool, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| window.has_focus())
 }
 
 #[op]
-fn op_wsi_window_take_focus(state: &mut OpState, wid: u64) {
+fn op_wsi_window_take_focus(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| window.focus_window())
 }
 
 #[op]
-fn op_wsi_window_is_fullscreen(state: &mut OpState, wid: u64) -> bool {
+fn op_wsi_window_is_fullscreen(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<bool, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| window.fullscreen().is_some())
@@ -301,7 +1385,7 @@ fn op_wsi_window_set_fullscreen(
   state: &mut OpState,
   wid: u64,
   fullscreen: bool,
-) {
+) -> Result<(), anyhow::Error> {
   state.borrow::<Rc<WsiEventLoopProxy>>().execute_with_window(
     wid,
     move |window| {
@@ -317,12 +1401,48 @@ fn op_wsi_window_set_fullscreen(
 fn op_wsi_window_create_gpu_surface(
   state: &mut OpState,
   wid: u64,
-) -> ResourceId {
+  auto_reconfigure: bool,
+  auto_resize: bool,
+) -> Result<ResourceId, anyhow::Error> {
+  let surface = match hot_reload::take_surface(state, wid) {
+    Some(surface) => surface,
+    None => Rc::new(create_gpu_surface(state, wid)?),
+  };
+  let overlay = state.borrow::<WsiDiagnosticsOverlays>().0.contains(&wid);
+  if overlay || surface.has_diagnostics_overlay() {
+    show_diagnostics_overlay(state, &surface, overlay);
+  }
+  surface.set_auto_recover(
+    auto_reconfigure || state.borrow::<WsiOptions>().resilient,
+  );
+  if let Some(tracker) = state.try_borrow::<Rc<WsiLatencyTracker>>() {
+    let tracker = tracker.clone();
+    surface.set_present_listener(move || tracker.presented(wid));
+  }
+
+  let rid = state.resource_table.add_rc(surface.clone());
+  let surfaces = &mut state.borrow_mut::<WsiWindowSurfaces>().0;
+  let surfaces = surfaces.entry(wid).or_default();
+  surfaces.retain(|surface| surface.strong_count() > 0);
+  surfaces.push(Rc::downgrade(&surface));
+  if auto_resize {
+    state
+      .borrow_mut::<WsiAutoResizeSurfaces>()
+      .0
+      .insert(wid, surface);
+  }
+  Ok(rid)
+}
+
+fn create_gpu_surface(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<WebGpuSurface, anyhow::Error> {
   let webgpu_instance = state
     .try_take::<deno_webgpu::Instance>()
-    .unwrap_or_else(deno_webgpu::create_instance);
+    .unwrap_or_else(|| deno_webgpu::create_instance(state));
 
-  let (webgpu_instance, surface_id) = state
+  let (webgpu_instance, surface_id, refresh_rate) = state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| {
       let surface_id = webgpu_instance.instance_create_surface(
@@ -330,240 +1450,571 @@ fn op_wsi_window_create_gpu_surface(
         window.raw_window_handle(),
         (),
       );
-      (webgpu_instance, surface_id)
-    });
+      let refresh_rate = window
+        .current_monitor()
+        .and_then(|monitor| monitor.refresh_rate_millihertz());
+      (webgpu_instance, surface_id, refresh_rate)
+    })?;
+  state.put(webgpu_instance);
+
+  let surface = WebGpuSurface::new(surface_id);
+  surface.set_refresh_rate_millihertz(refresh_rate);
+  Ok(surface)
+}
+
+#[op]
+fn op_wsi_window_set_ime_allowed(
+  state: &mut OpState,
+  wid: u64,
+  allowed: bool,
+) -> Result<(), anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| window.set_ime_allowed(allowed))
+}
+
+#[op]
+fn op_wsi_window_set_ime_position(
+  state: &mut OpState,
+  wid: u64,
+  position: (i32, i32),
+) -> Result<(), anyhow::Error> {
+  let position = coordinate_space(state, wid).position(position);
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| window.set_ime_position(position))
+}
+
+#[op]
+fn op_wsi_window_set_ime_purpose(
+  state: &mut OpState,
+  wid: u64,
+  purpose: WsiImePurpose,
+) -> Result<(), anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      window.set_ime_purpose(purpose.into())
+    })
+}
+
+#[op]
+fn op_wsi_window_reset_dead_keys(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(), anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, reset_dead_keys)
+}
+
+#[op]
+fn op_wsi_window_show_soft_keyboard(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<bool, anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, show_soft_keyboard)
+}
+
+#[op]
+fn op_wsi_window_hide_soft_keyboard(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<bool, anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, hide_soft_keyboard)
+}
+
+#[op]
+fn op_wsi_window_get_soft_keyboard_rect(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<(f64, f64, f64, f64)>, anyhow::Error> {
+  let space = coordinate_space(state, wid);
+  state.borrow::<Rc<WsiEventLoopProxy>>().execute_with_window(
+    wid,
+    move |window| {
+      let rect = soft_keyboard_rect(window)?;
+      Some(space.to_rect(rect, window.scale_factor()))
+    },
+  )
+}
+
+#[op]
+fn op_wsi_window_set_system_shortcuts_inhibited(
+  state: &mut OpState,
+  wid: u64,
+  inhibited: bool,
+) -> Result<bool, anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      set_system_shortcuts_inhibited(wid, window, inhibited)
+    })
+}
+
+#[op]
+fn op_wsi_window_get_inner_position(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(f64, f64), anyhow::Error> {
+  let space = coordinate_space(state, wid);
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      let scale_factor = window.scale_factor();
+      window
+        .inner_position()
+        .map(|position| space.to_position(position, scale_factor))
+    })?
+    .map_err(Into::into)
+}
+
+#[op]
+fn op_wsi_window_get_outer_position(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(f64, f64), anyhow::Error> {
+  let space = coordinate_space(state, wid);
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      let scale_factor = window.scale_factor();
+      window
+        .outer_position()
+        .map(|position| space.to_position(position, scale_factor))
+    })?
+    .map_err(Into::into)
+}
+
+#[op]
+fn op_wsi_window_set_outer_position(
+  state: &mut OpState,
+  wid: u64,
+  position: (i32, i32),
+) -> Result<(), anyhow::Error> {
+  let position = coordinate_space(state, wid).position(position);
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| window.set_outer_position(position))
+}
+
+#[op]
+fn op_wsi_window_center(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(), anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, center)
+}
+
+#[op]
+fn op_wsi_window_get_inner_size(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(f64, f64), anyhow::Error> {
+  let space = coordinate_space(state, wid);
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      space.to_size(window.inner_size(), window.scale_factor())
+    })
+}
+
+#[op]
+fn op_wsi_window_get_outer_size(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(f64, f64), anyhow::Error> {
+  let space = coordinate_space(state, wid);
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      space.to_size(window.outer_size(), window.scale_factor())
+    })
+}
+
+#[op]
+fn op_wsi_window_set_inner_size(
+  state: &mut OpState,
+  wid: u64,
+  size: (u32, u32),
+) -> Result<(), anyhow::Error> {
+  let size = coordinate_space(state, wid).size(size);
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| window.set_inner_size(size))
+}
 
-  state.put(webgpu_instance);
-  state.resource_table.add(WebGpuSurface(surface_id))
+#[op]
+fn op_wsi_window_set_min_inner_size(
+  state: &mut OpState,
+  wid: u64,
+  size: Option<(u32, u32)>,
+) -> Result<(), anyhow::Error> {
+  let size = size.map(|size| coordinate_space(state, wid).size(size));
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| window.set_min_inner_size(size))
 }
 
 #[op]
-fn op_wsi_window_set_ime_allowed(state: &mut OpState, wid: u64, allowed: bool) {
+fn op_wsi_window_set_max_inner_size(
+  state: &mut OpState,
+  wid: u64,
+  size: Option<(u32, u32)>,
+) -> Result<(), anyhow::Error> {
+  let size = size.map(|size| coordinate_space(state, wid).size(size));
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| window.set_ime_allowed(allowed))
+    .execute_with_window(wid, move |window| window.set_max_inner_size(size))
 }
 
 #[op]
-fn op_wsi_window_set_ime_position(
+fn op_wsi_window_set_level(
   state: &mut OpState,
   wid: u64,
-  (x, y): (i32, i32),
-) {
+  level: WsiWindowLevel,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| {
-      window.set_ime_position(PhysicalPosition { x, y })
-    })
+    .execute_with_window(wid, move |window| set_window_level(window, level))
 }
 
 #[op]
-fn op_wsi_window_set_ime_purpose(
+fn op_wsi_window_is_minimized(
   state: &mut OpState,
   wid: u64,
-  purpose: WsiImePurpose,
-) {
+) -> Result<Option<bool>, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| {
-      window.set_ime_purpose(purpose.into())
-    })
+    .execute_with_window(wid, |window| window.is_minimized())
 }
 
 #[op]
-fn op_wsi_window_get_inner_position(
+fn op_wsi_window_set_minimized(
   state: &mut OpState,
   wid: u64,
-) -> Result<(i32, i32), anyhow::Error> {
+  minimized: bool,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.inner_position())
-    .map(|PhysicalPosition { x, y }| (x, y))
-    .map_err(Into::into)
+    .execute_with_window(wid, move |window| window.set_minimized(minimized))
 }
 
 #[op]
-fn op_wsi_window_get_outer_position(
+fn op_wsi_window_is_maximized(
   state: &mut OpState,
   wid: u64,
-) -> Result<(i32, i32), anyhow::Error> {
+) -> Result<bool, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.outer_position())
-    .map(|PhysicalPosition { x, y }| (x, y))
-    .map_err(Into::into)
+    .execute_with_window(wid, |window| window.is_maximized())
 }
 
 #[op]
-fn op_wsi_window_set_outer_position(
+fn op_wsi_window_set_maximized(
   state: &mut OpState,
   wid: u64,
-  (x, y): (i32, i32),
-) {
+  maximized: bool,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| {
-      window.set_outer_position(PhysicalPosition { x, y })
-    })
+    .execute_with_window(wid, move |window| window.set_maximized(maximized))
 }
 
 #[op]
-fn op_wsi_window_get_inner_size(state: &mut OpState, wid: u64) -> (u32, u32) {
+fn op_wsi_window_is_resizable(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<bool, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| {
-      let size = window.inner_size();
-      (size.width, size.height)
-    })
+    .execute_with_window(wid, |window| window.is_resizable())
 }
 
 #[op]
-fn op_wsi_window_get_outer_size(state: &mut OpState, wid: u64) -> (u32, u32) {
+fn op_wsi_window_set_resizable(
+  state: &mut OpState,
+  wid: u64,
+  resizable: bool,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| {
-      let size = window.outer_size();
-      (size.width, size.height)
-    })
+    .execute_with_window(wid, move |window| window.set_resizable(resizable))
 }
 
 #[op]
-fn op_wsi_window_set_inner_size(
+fn op_wsi_window_set_shadow(
   state: &mut OpState,
   wid: u64,
-  (width, height): (u32, u32),
-) {
+  shadow: bool,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| {
-      window.set_inner_size(PhysicalSize { width, height })
+      set_undecorated_shadow(window, shadow)
     })
 }
 
 #[op]
-fn op_wsi_window_set_min_inner_size(
+fn op_wsi_window_set_shape(
   state: &mut OpState,
   wid: u64,
-  size: Option<(u32, u32)>,
-) {
+  shape: Option<WsiWindowShape>,
+) -> Result<bool, anyhow::Error> {
+  let space = coordinate_space(state, wid);
   state.borrow::<Rc<WsiEventLoopProxy>>().execute_with_window(
     wid,
     move |window| {
-      window.set_min_inner_size(
-        size.map(|(width, height)| PhysicalSize { width, height }),
-      )
+      let scale_factor = window.scale_factor();
+      let shape = shape.map(|shape| WsiWindowShape {
+        rects: shape
+          .rects
+          .into_iter()
+          .map(|(x, y, width, height)| {
+            let position = space.position((x, y)).to_physical(scale_factor);
+            let size = space.size((width, height)).to_physical(scale_factor);
+            (position.x, position.y, size.width, size.height)
+          })
+          .collect(),
+        corner_radius: space
+          .size((shape.corner_radius, shape.corner_radius))
+          .to_physical::<u32>(scale_factor)
+          .width,
+      });
+      set_shape(window, shape.as_ref())
     },
   )
 }
 
 #[op]
-fn op_wsi_window_set_max_inner_size(
+fn op_wsi_window_get_resize_increments(
   state: &mut OpState,
   wid: u64,
-  size: Option<(u32, u32)>,
-) {
+) -> Result<Option<(f64, f64)>, anyhow::Error> {
+  let space = coordinate_space(state, wid);
   state.borrow::<Rc<WsiEventLoopProxy>>().execute_with_window(
     wid,
     move |window| {
-      window.set_max_inner_size(
-        size.map(|(width, height)| PhysicalSize { width, height }),
-      )
+      let increments = window.resize_increments()?;
+      Some(space.to_size(increments, window.scale_factor()))
     },
   )
 }
 
 #[op]
-fn op_wsi_window_set_level(
+fn op_wsi_window_set_resize_increments(
   state: &mut OpState,
   wid: u64,
-  level: WsiWindowLevel,
-) {
+  increments: Option<(u32, u32)>,
+) -> Result<(), anyhow::Error> {
+  let increments =
+    increments.map(|size| coordinate_space(state, wid).size(size));
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.set_window_level(level.into()))
+    .execute_with_window(wid, move |window| {
+      window.set_resize_increments(increments)
+    })
+}
+
+#[op]
+fn op_wsi_window_set_aspect_ratio(
+  state: &mut OpState,
+  wid: u64,
+  ratio: Option<(u32, u32)>,
+) -> Result<(), anyhow::Error> {
+  let proxy = state.borrow::<Rc<WsiEventLoopProxy>>();
+  let aspect_ratios = proxy.aspect_ratios().clone();
+  proxy.execute_with_window(wid, move |window| {
+    let Some(ratio) = ratio else {
+      aspect_ratios.unlock(wid);
+      return;
+    };
+    // Bring the current size to the new ratio, keeping its width.
+    let size = window.inner_size();
+    aspect_ratios.lock(wid, ratio, size);
+    let constrained = aspect_ratios.constrain(wid, size).unwrap();
+    if constrained != size {
+      window.set_inner_size(constrained);
+    }
+  })
+}
+
+#[op]
+fn op_wsi_window_fit_inner_size(
+  state: &mut OpState,
+  wid: u64,
+  bounds: (u32, u32),
+) -> Result<(), anyhow::Error> {
+  let bounds = coordinate_space(state, wid).size(bounds);
+  let proxy = state.borrow::<Rc<WsiEventLoopProxy>>();
+  let aspect_ratios = proxy.aspect_ratios().clone();
+  proxy.execute_with_window(wid, move |window| {
+    let size = window.inner_size();
+    let ratio = aspect_ratios
+      .get(wid)
+      .unwrap_or((size.width.max(1), size.height.max(1)));
+    let size = fit(bounds.to_physical(window.scale_factor()), ratio);
+    // Record the size first so the resulting resize event isn't adjusted.
+    aspect_ratios.constrain(wid, size);
+    window.set_inner_size(size);
+  })
 }
 
 #[op]
-fn op_wsi_window_is_minimized(state: &mut OpState, wid: u64) -> Option<bool> {
+fn op_wsi_window_get_scale_factor(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<f64, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.is_minimized())
+    .execute_with_window(wid, |window| window.scale_factor())
 }
 
 #[op]
-fn op_wsi_window_set_minimized(state: &mut OpState, wid: u64, minimized: bool) {
+fn op_wsi_window_get_refresh_rate(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<u32>, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| window.set_minimized(minimized))
+    .execute_with_window(wid, |window| {
+      window
+        .current_monitor()
+        .and_then(|monitor| monitor.refresh_rate_millihertz())
+    })
+}
+
+#[op]
+fn op_wsi_window_get_color_profile(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<ZeroCopyBuf>, anyhow::Error> {
+  let profile = state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, |window| {
+      let monitor = window.current_monitor()?;
+      color_profile(window, &monitor)
+    })?;
+  Ok(profile.map(Into::into))
 }
 
 #[op]
-fn op_wsi_window_is_maximized(state: &mut OpState, wid: u64) -> bool {
+fn op_wsi_window_get_supported_display_controls(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Vec<WsiDisplayControl>, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.is_maximized())
+    .execute_with_window(wid, supported_display_controls)
 }
 
 #[op]
-fn op_wsi_window_set_maximized(state: &mut OpState, wid: u64, maximized: bool) {
+fn op_wsi_window_get_brightness(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<f64>, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| window.set_maximized(maximized))
+    .execute_with_window(wid, brightness)
 }
 
 #[op]
-fn op_wsi_window_is_resizable(state: &mut OpState, wid: u64) -> bool {
+fn op_wsi_window_set_brightness(
+  state: &mut OpState,
+  wid: u64,
+  brightness: f64,
+) -> Result<bool, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.is_resizable())
+    .execute_with_window(wid, move |window| set_brightness(window, brightness))
 }
 
 #[op]
-fn op_wsi_window_set_resizable(state: &mut OpState, wid: u64, resizable: bool) {
+fn op_wsi_window_set_gamma(
+  state: &mut OpState,
+  wid: u64,
+  gamma: f64,
+) -> Result<bool, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| window.set_resizable(resizable))
+    .execute_with_window(wid, move |window| set_gamma(wid, window, gamma))
 }
 
 #[op]
-fn op_wsi_window_get_resize_increments(
+fn op_wsi_window_set_skip_taskbar(
   state: &mut OpState,
   wid: u64,
-) -> Option<(u32, u32)> {
+  skip: bool,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.resize_increments())
-    .map(|PhysicalSize { width, height }| (width, height))
+    .execute_with_window(wid, move |window| set_skip_taskbar(window, skip))
 }
 
 #[op]
-fn op_wsi_window_set_resize_increments(
+fn op_wsi_window_get_tag(
   state: &mut OpState,
   wid: u64,
-  increments: Option<(u32, u32)>,
-) {
-  state.borrow::<Rc<WsiEventLoopProxy>>().execute_with_window(
-    wid,
-    move |window| {
-      window.set_resize_increments(
-        increments.map(|(width, height)| PhysicalSize { width, height }),
-      )
-    },
-  )
+) -> Option<serde_json::Value> {
+  state.borrow::<WsiWindowTags>().0.get(&wid).cloned()
 }
 
 #[op]
-fn op_wsi_window_get_scale_factor(state: &mut OpState, wid: u64) -> f64 {
+fn op_wsi_window_set_taskbar_progress(
+  state: &mut OpState,
+  wid: u64,
+  progress_state: WsiTaskbarProgressState,
+  value: Option<f64>,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.scale_factor())
+    .execute_with_window(wid, move |window| {
+      set_progress(window, progress_state, value)
+    })
+}
+
+#[op]
+fn op_wsi_window_set_taskbar_overlay(
+  state: &mut OpState,
+  wid: u64,
+  icon: Option<ZeroCopyBuf>,
+  description: String,
+) -> Result<(), anyhow::Error> {
+  let overlay = match icon {
+    Some(icon) => Some(WsiTaskbarOverlay::decode(&icon, description)?),
+    None => None,
+  };
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| set_overlay(window, overlay))?;
+  Ok(())
+}
+
+#[op]
+fn op_wsi_window_set_badge(
+  state: &mut OpState,
+  wid: u64,
+  count: Option<u32>,
+) -> Result<bool, anyhow::Error> {
+  let app_id = state.borrow::<WsiOptions>().app_id.clone();
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| {
+      set_badge(window, count, app_id.as_deref())
+    })
 }
 
 #[op]
 fn op_wsi_window_get_theme(
   state: &mut OpState,
   wid: u64,
-) -> Option<WsiWindowTheme> {
+) -> Result<Option<WsiWindowTheme>, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| window.theme().map(Into::into))
@@ -574,21 +2025,30 @@ fn op_wsi_window_set_theme(
   state: &mut OpState,
   wid: u64,
   theme: Option<WsiWindowTheme>,
-) {
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.set_theme(theme.map(Into::into)))
+    .execute_with_window(wid, move |window| {
+      window.set_theme(theme.map(Into::into))
+    })
 }
 
 #[op]
-fn op_wsi_window_get_title(state: &mut OpState, wid: u64) -> String {
+fn op_wsi_window_get_title(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<String, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| window.title())
 }
 
 #[op]
-fn op_wsi_window_set_title(state: &mut OpState, wid: u64, title: String) {
+fn op_wsi_window_set_title(
+  state: &mut OpState,
+  wid: u64,
+  title: String,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| window.set_title(&title))
@@ -599,24 +2059,108 @@ fn op_wsi_window_set_transparent(
   state: &mut OpState,
   wid: u64,
   transparent: bool,
-) {
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| window.set_transparent(transparent))
 }
 
 #[op]
-fn op_wsi_window_is_visible(state: &mut OpState, wid: u64) -> Option<bool> {
+fn op_wsi_window_is_visible(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<bool>, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| window.is_visible())
 }
 
 #[op]
-fn op_wsi_window_set_visible(state: &mut OpState, wid: u64, visible: bool) {
+fn op_wsi_window_set_visible(
+  state: &mut OpState,
+  wid: u64,
+  visible: bool,
+) -> Result<(), anyhow::Error> {
+  state.borrow::<Rc<WsiEventLoopProxy>>().execute_with_window(
+    wid,
+    move |window| {
+      window.set_visible(visible);
+      if visible {
+        complete_startup(window);
+      }
+    },
+  )
+}
+
+#[op]
+fn op_wsi_window_get_visible_rect(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<(f64, f64, f64, f64)>, anyhow::Error> {
+  let space = coordinate_space(state, wid);
+  state.borrow::<Rc<WsiEventLoopProxy>>().execute_with_window(
+    wid,
+    move |window| {
+      let rect = visible_rect(window)?;
+      Some(space.to_rect(rect, window.scale_factor()))
+    },
+  )
+}
+
+#[op]
+fn op_wsi_window_get_visible_fraction(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<f64>, anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, |window| {
+      let (_, _, width, height) = visible_rect(window)?;
+      let size = window.inner_size();
+      let area = size.width as f64 * size.height as f64;
+      if area == 0.0 {
+        return Some(0.0);
+      }
+      Some(width as f64 * height as f64 / area)
+    })
+}
+
+#[op]
+fn op_wsi_window_get_safe_area_insets(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(f64, f64, f64, f64), anyhow::Error> {
+  let space = coordinate_space(state, wid);
+  state.borrow::<Rc<WsiEventLoopProxy>>().execute_with_window(
+    wid,
+    move |window| {
+      space.to_insets(safe_area_insets(window), window.scale_factor())
+    },
+  )
+}
+
+#[op]
+fn op_wsi_window_get_orientation(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<WsiOrientation>, anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, |window| {
+      let monitor = window.current_monitor()?;
+      Some(orientation(window, &monitor))
+    })
+}
+
+#[op]
+fn op_wsi_window_lock_orientation(
+  state: &mut OpState,
+  wid: u64,
+  lock: WsiOrientationLock,
+) -> Result<bool, anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, move |window| window.set_visible(visible))
+    .execute_with_window(wid, move |_| lock_orientation(lock))
 }
 
 #[op]
@@ -626,7 +2170,7 @@ fn op_wsi_window_begin_drag_move(
 ) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute_with_window(wid, |window| window.drag_window())
+    .execute_with_window(wid, |window| window.drag_window())?
     .map_err(Into::into)
 }
 
@@ -640,12 +2184,42 @@ fn op_wsi_window_begin_drag_resize(
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| {
       window.drag_resize_window(direction.into())
-    })
+    })?
     .map_err(Into::into)
 }
 
 #[op]
-fn op_wsi_window_request_redraw(state: &mut OpState, wid: u64) {
+fn op_wsi_window_set_drop_types(
+  state: &mut OpState,
+  wid: u64,
+  types: Vec<WsiDropType>,
+) -> Result<(), anyhow::Error> {
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| set_drop_types(window, types))
+}
+
+#[op]
+fn op_wsi_window_start_drag(
+  state: &mut OpState,
+  wid: u64,
+  data: WsiDragData,
+  image: Option<ZeroCopyBuf>,
+) -> Result<bool, anyhow::Error> {
+  let image = match image {
+    Some(image) => Some(WsiDragImage::decode(image.to_vec())?),
+    None => None,
+  };
+  state
+    .borrow::<Rc<WsiEventLoopProxy>>()
+    .execute_with_window(wid, move |window| start_drag(window, data, image))
+}
+
+#[op]
+fn op_wsi_window_request_redraw(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, |window| window.request_redraw())
@@ -656,7 +2230,7 @@ fn op_wsi_window_request_user_attention(
   state: &mut OpState,
   wid: u64,
   attention_type: Option<WsiUserAttentionType>,
-) {
+) -> Result<(), anyhow::Error> {
   state
     .borrow::<Rc<WsiEventLoopProxy>>()
     .execute_with_window(wid, move |window| {
@@ -664,11 +2238,51 @@ fn op_wsi_window_request_user_attention(
     })
 }
 
+// Returns the window's content as a PNG image.
 #[op]
-fn op_wsi_window_destroy(state: &mut OpState, wid: u64) {
-  state
+fn op_wsi_window_capture_content(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<Option<ZeroCopyBuf>, anyhow::Error> {
+  let image = state
     .borrow::<Rc<WsiEventLoopProxy>>()
-    .execute(move |_, windows| {
-      windows.remove(&wid);
-    })
+    .execute_with_window(wid, capture_window)?;
+  match image {
+    Some(image) => Ok(Some(image.encode_png()?.into())),
+    None => Ok(None),
+  }
+}
+
+#[op]
+fn op_wsi_window_destroy(
+  state: &mut OpState,
+  wid: u64,
+) -> Result<(), anyhow::Error> {
+  state.borrow_mut::<WsiAutoResizeSurfaces>().0.remove(&wid);
+  state.borrow_mut::<WsiWindowSurfaces>().0.remove(&wid);
+  state.borrow_mut::<WsiDiagnosticsOverlays>().0.remove(&wid);
+  state.borrow_mut::<WsiCoordinateSpaces>().0.remove(&wid);
+  save_window_geometry(state, wid);
+  state.borrow_mut::<WsiWindowTags>().0.remove(&wid);
+  state.borrow_mut::<WsiWindowNames>().0.remove(&wid);
+  if let Some(tracker) = state.try_borrow::<Rc<WsiLatencyTracker>>() {
+    tracker.forget(wid);
+  }
+  hot_reload::forget_window(state, wid);
+  let proxy = state.borrow::<Rc<WsiEventLoopProxy>>();
+  proxy.aspect_ratios().unlock(wid);
+  proxy.execute(move |_, windows| remove_window(windows, wid))
+}
+
+// Destroy a window on the event loop thread, after putting back what it
+// changed outside of itself.
+fn remove_window(windows: &mut HashMap<u64, Window>, wid: u64) {
+  unregister_soft_keyboard_handler(wid);
+  forget_cursor_grab(wid);
+  forget_enabled(wid);
+  forget_hit_test_region(wid);
+  if let Some(window) = windows.remove(&wid) {
+    set_system_shortcuts_inhibited(wid, &window, false);
+    restore_gamma(wid, &window);
+  }
 }
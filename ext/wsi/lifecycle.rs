@@ -0,0 +1,407 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::event::WsiEvent;
+use std::{
+  collections::{HashMap, HashSet},
+  time::{Duration, Instant},
+};
+use winit::{event_loop::EventLoopProxy, window::Window};
+
+// How often the memory pressure and the windows' visibility are checked, at
+// most.
+const LIFECYCLE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Winit only reports suspending and resuming, which desktop platforms don't
+// do, so the driver works out the rest of the app's lifecycle itself. The app
+// is in the background while none of its windows can be seen, because each is
+// minimized, hidden or, where winit reports it, occluded. That's checked when
+// a window is occluded, resized or focused, and on other events at most once
+// per `LIFECYCLE_POLL_INTERVAL`, along with the memory pressure. Quit requests
+// come from the platform, and are answered with `reply_to_quit_request`.
+#[derive(Default)]
+pub struct LifecycleWatcher {
+  occluded: HashSet<u64>,
+  // `None` until there's a window, which is first seen without an event.
+  background: Option<bool>,
+  memory_low: bool,
+  last_poll: Option<Instant>,
+  exiting: bool,
+}
+
+impl LifecycleWatcher {
+  // The events for what changed since the last poll, and any quit request.
+  pub fn update(
+    &mut self,
+    event: &WsiEvent,
+    windows: &HashMap<u64, Window>,
+  ) -> Vec<WsiEvent> {
+    let mut events = Vec::new();
+    if platform::take_quit_request() {
+      events.push(WsiEvent::AppQuitRequested);
+    }
+    if platform::take_termination() {
+      self.exiting = true;
+      events.push(WsiEvent::AppWillTerminate);
+    }
+
+    if let WsiEvent::WindowOcclusion {
+      window,
+      is_occluded,
+    } = *event
+    {
+      if is_occluded {
+        self.occluded.insert(window);
+      } else {
+        self.occluded.remove(&window);
+      }
+    }
+    let window_changed = matches!(
+      event,
+      WsiEvent::WindowOcclusion { .. }
+        | WsiEvent::WindowResized { .. }
+        | WsiEvent::WindowFocus { .. }
+    );
+    let due = self.last_poll.map_or(true, |last_poll| {
+      last_poll.elapsed() >= LIFECYCLE_POLL_INTERVAL
+    });
+    if !window_changed && !due {
+      return events;
+    }
+
+    self.occluded.retain(|wid| windows.contains_key(wid));
+    if !windows.is_empty() {
+      let background = windows.iter().all(|(wid, window)| {
+        self.occluded.contains(wid)
+          || window.is_minimized() == Some(true)
+          || window.is_visible() == Some(false)
+      });
+      match self.background.replace(background) {
+        Some(false) if background => {
+          events.push(WsiEvent::AppDidEnterBackground)
+        }
+        Some(true) if !background => {
+          events.push(WsiEvent::AppDidEnterForeground)
+        }
+        _ => {}
+      }
+    }
+
+    if due {
+      self.last_poll = Some(Instant::now());
+      let memory_low = platform::memory_low().unwrap_or(false);
+      if memory_low && !self.memory_low {
+        events.push(WsiEvent::AppMemoryWarning);
+      }
+      self.memory_low = memory_low;
+    }
+    events
+  }
+
+  // Whether the app agreed to quit and has been told it will terminate, after
+  // which the event loop exits, where the platform doesn't end the process.
+  pub fn is_exiting(&self) -> bool {
+    self.exiting
+  }
+}
+
+// Have the platform ask before the app quits. `event_loop_proxy` wakes the
+// event loop when there's a request to deliver. Call this once the event loop
+// has been created.
+pub fn install_quit_handler(event_loop_proxy: EventLoopProxy<()>) {
+  platform::install_quit_handler(event_loop_proxy)
+}
+
+// Ask a new window's platform messages about quitting.
+pub fn register_quit_handler(window: &Window) {
+  platform::register_quit_handler(window)
+}
+
+// Quit, or keep running, after a quit request.
+pub fn reply_to_quit_request(allow: bool) {
+  platform::reply_to_quit_request(allow)
+}
+
+// AppKit asks the application delegate before quitting from the Quit menu
+// item, the Dock or logging out, and exits the process itself once the app
+// agrees. macOS rates memory pressure as normal, warning or critical.
+// https://developer.apple.com/documentation/appkit/nsapplicationdelegate/1428642-applicationshouldterminate
+#[cfg(target_os = "macos")]
+mod platform {
+  use crate::macos;
+  use std::{
+    mem,
+    os::raw::{c_char, c_int, c_void},
+    ptr,
+  };
+  use winit::{event_loop::EventLoopProxy, window::Window};
+
+  // DISPATCH_MEMORYPRESSURE_WARN
+  const MEMORY_PRESSURE_WARN: c_int = 2;
+
+  extern "C" {
+    fn sysctlbyname(
+      name: *const c_char,
+      old: *mut c_void,
+      old_len: *mut usize,
+      new: *mut c_void,
+      new_len: usize,
+    ) -> c_int;
+  }
+
+  pub fn install_quit_handler(_event_loop_proxy: EventLoopProxy<()>) {
+    macos::install_quit_handler();
+  }
+
+  pub fn register_quit_handler(_window: &Window) {}
+
+  pub fn take_quit_request() -> bool {
+    macos::take_quit_request()
+  }
+
+  pub fn reply_to_quit_request(allow: bool) {
+    macos::reply_to_quit_request(allow);
+  }
+
+  pub fn take_termination() -> bool {
+    false
+  }
+
+  pub fn memory_low() -> Option<bool> {
+    let mut level: c_int = 0;
+    let mut len = mem::size_of::<c_int>();
+    // SAFETY: The name is null-terminated and `len` is the size of `level`.
+    let result = unsafe {
+      sysctlbyname(
+        b"kern.memorystatus_vm_pressure_level\0".as_ptr().cast(),
+        (&mut level as *mut c_int).cast(),
+        &mut len,
+        ptr::null_mut(),
+        0,
+      )
+    };
+    (result == 0).then_some(level >= MEMORY_PRESSURE_WARN)
+  }
+}
+
+// Windows asks each top-level window before the user logs off or shuts down.
+// A window that says no is listed with a reason while it blocks, and the
+// process exits once the app agrees. Critical shutdowns can't be blocked.
+// Low memory is signalled by a memory resource notification.
+// https://learn.microsoft.com/en-us/windows/win32/shutdown/shutdown-changes-for-windows-vista
+#[cfg(windows)]
+mod platform {
+  use std::{
+    cell::{Cell, RefCell},
+    ffi::OsStr,
+    iter,
+    os::windows::ffi::OsStrExt,
+    ptr,
+  };
+  use winapi::{
+    shared::{
+      basetsd::{DWORD_PTR, UINT_PTR},
+      minwindef::{FALSE, LPARAM, LRESULT, UINT, WPARAM},
+      windef::HWND,
+    },
+    um::{
+      commctrl::{DefSubclassProc, RemoveWindowSubclass, SetWindowSubclass},
+      memoryapi::{
+        CreateMemoryResourceNotification, LowMemoryResourceNotification,
+        QueryMemoryResourceNotification,
+      },
+      winnt::HANDLE,
+      winuser::{
+        ShutdownBlockReasonCreate, ShutdownBlockReasonDestroy,
+        ENDSESSION_CRITICAL, WM_NCDESTROY, WM_QUERYENDSESSION,
+      },
+    },
+  };
+  use winit::{
+    event_loop::EventLoopProxy, platform::windows::WindowExtWindows,
+    window::Window,
+  };
+
+  // What Windows shows for the app while it blocks shutting down.
+  const BLOCK_REASON: &str = "Saving before closing";
+
+  thread_local! {
+    static EVENT_LOOP_PROXY: RefCell<Option<EventLoopProxy<()>>> =
+      RefCell::new(None);
+    // Whether there's a quit request that hasn't been delivered yet.
+    static QUIT_REQUESTED: Cell<bool> = Cell::new(false);
+    // The window that's blocking shutdown until the app replies, if any.
+    static BLOCKING_WINDOW: Cell<HWND> = Cell::new(ptr::null_mut());
+    // Whether the app agreed to quit and hasn't been told it will terminate.
+    static TERMINATING: Cell<bool> = Cell::new(false);
+    // The low memory notification, or null if it couldn't be created. It's
+    // kept for the life of the event loop thread.
+    static LOW_MEMORY: HANDLE = create_low_memory_notification();
+  }
+
+  pub fn install_quit_handler(event_loop_proxy: EventLoopProxy<()>) {
+    EVENT_LOOP_PROXY.with(|proxy| *proxy.borrow_mut() = Some(event_loop_proxy));
+  }
+
+  pub fn register_quit_handler(window: &Window) {
+    // SAFETY: The window handle is valid, and the subclass is removed when the
+    // window is destroyed.
+    unsafe {
+      SetWindowSubclass(window.hwnd() as HWND, Some(subclass_proc), 0, 0);
+    }
+  }
+
+  pub fn take_quit_request() -> bool {
+    QUIT_REQUESTED.with(|requested| requested.replace(false))
+  }
+
+  pub fn reply_to_quit_request(allow: bool) {
+    let hwnd = BLOCKING_WINDOW.with(|hwnd| hwnd.replace(ptr::null_mut()));
+    if hwnd.is_null() {
+      return;
+    }
+    // SAFETY: The window blocked shutdown with a reason, and is still alive,
+    // since the subclass clears it when the window is destroyed.
+    unsafe { ShutdownBlockReasonDestroy(hwnd) };
+    if allow {
+      TERMINATING.with(|terminating| terminating.set(true));
+      wake_event_loop();
+    }
+  }
+
+  pub fn take_termination() -> bool {
+    TERMINATING.with(|terminating| terminating.replace(false))
+  }
+
+  pub fn memory_low() -> Option<bool> {
+    let notification = LOW_MEMORY.with(|notification| *notification);
+    if notification.is_null() {
+      return None;
+    }
+    let mut low = FALSE;
+    // SAFETY: The notification handle is valid.
+    let result =
+      unsafe { QueryMemoryResourceNotification(notification, &mut low) };
+    (result != 0).then_some(low != FALSE)
+  }
+
+  fn create_low_memory_notification() -> HANDLE {
+    // SAFETY: CreateMemoryResourceNotification has no preconditions.
+    unsafe { CreateMemoryResourceNotification(LowMemoryResourceNotification) }
+  }
+
+  fn wake_event_loop() {
+    EVENT_LOOP_PROXY.with(|proxy| {
+      if let Some(proxy) = &*proxy.borrow() {
+        let _ = proxy.send_event(());
+      }
+    });
+  }
+
+  unsafe extern "system" fn subclass_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    id: UINT_PTR,
+    _data: DWORD_PTR,
+  ) -> LRESULT {
+    match msg {
+      WM_QUERYENDSESSION if lparam as UINT & ENDSESSION_CRITICAL == 0 => {
+        if TERMINATING.with(|terminating| terminating.get()) {
+          return DefSubclassProc(hwnd, msg, wparam, lparam);
+        }
+        if BLOCKING_WINDOW.with(|blocking| blocking.get()).is_null() {
+          let reason: Vec<u16> = OsStr::new(BLOCK_REASON)
+            .encode_wide()
+            .chain(iter::once(0))
+            .collect();
+          ShutdownBlockReasonCreate(hwnd, reason.as_ptr());
+          BLOCKING_WINDOW.with(|blocking| blocking.set(hwnd));
+          QUIT_REQUESTED.with(|requested| requested.set(true));
+          wake_event_loop();
+        }
+        return FALSE as LRESULT;
+      }
+      WM_NCDESTROY => {
+        if BLOCKING_WINDOW.with(|blocking| blocking.get()) == hwnd {
+          ShutdownBlockReasonDestroy(hwnd);
+          BLOCKING_WINDOW.with(|blocking| blocking.set(ptr::null_mut()));
+        }
+        RemoveWindowSubclass(hwnd, Some(subclass_proc), id);
+      }
+      _ => {}
+    }
+    DefSubclassProc(hwnd, msg, wparam, lparam)
+  }
+}
+
+// Desktop sessions on Linux end by signalling their processes, which
+// `Deno.addSignalListener` can handle. Memory is low when less than a
+// twentieth of it is available, as the kernel estimates.
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use std::fs;
+  use winit::{event_loop::EventLoopProxy, window::Window};
+
+  pub fn install_quit_handler(_event_loop_proxy: EventLoopProxy<()>) {}
+
+  pub fn register_quit_handler(_window: &Window) {}
+
+  pub fn take_quit_request() -> bool {
+    false
+  }
+
+  pub fn reply_to_quit_request(_allow: bool) {}
+
+  pub fn take_termination() -> bool {
+    false
+  }
+
+  pub fn memory_low() -> Option<bool> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| {
+      meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))?
+        .trim()
+        .trim_end_matches(" kB")
+        .parse::<u64>()
+        .ok()
+    };
+    let (total, available) = (field("MemTotal")?, field("MemAvailable")?);
+    Some(available < total / 20)
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use winit::{event_loop::EventLoopProxy, window::Window};
+
+  pub fn install_quit_handler(_event_loop_proxy: EventLoopProxy<()>) {}
+
+  pub fn register_quit_handler(_window: &Window) {}
+
+  pub fn take_quit_request() -> bool {
+    false
+  }
+
+  pub fn reply_to_quit_request(_allow: bool) {}
+
+  pub fn take_termination() -> bool {
+    false
+  }
+
+  pub fn memory_low() -> Option<bool> {
+    None
+  }
+}
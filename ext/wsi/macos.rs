@@ -0,0 +1,632 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// Dock, application menu, URL, quit, drag and drop, dead key and tablet
+// handling.
+// Winit doesn't cover these, so they talk to AppKit directly. Everything here
+// must run on the main thread, i.e. in the real event loop. On other platforms
+// these functions do nothing.
+
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiAppMenuItem {
+  pub id: String,
+  pub title: String,
+  // The key that selects the item together with Command, e.g. "q".
+  pub key: Option<String>,
+}
+
+#[cfg(target_os = "macos")]
+mod appkit {
+  use super::WsiAppMenuItem;
+  use objc::{
+    class,
+    declare::ClassDecl,
+    msg_send,
+    rc::autoreleasepool,
+    runtime::{class_addMethod, Class, Imp, Object, Sel, BOOL, NO, YES},
+    sel, sel_impl, Message,
+  };
+  use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+  use std::{
+    cell::Cell,
+    ffi::{c_void, CStr},
+    mem,
+    os::raw::c_char,
+    path::PathBuf,
+    ptr,
+    sync::{
+      atomic::{AtomicBool, Ordering},
+      Mutex, Once,
+    },
+    time::Duration,
+  };
+  use winit::window::Window;
+
+  const NS_UTF8_STRING_ENCODING: usize = 4;
+  const NS_CRITICAL_REQUEST: isize = 0;
+  const NS_INFORMATIONAL_REQUEST: isize = 10;
+  const NS_DRAG_OPERATION_COPY: usize = 1;
+  const NS_EVENT_SUBTYPE_TABLET_POINT: i16 = 1;
+  // The left, right and other mouse down, up and dragged events, and mouse
+  // moved events.
+  const NS_MOUSE_EVENT_TYPES: [usize; 10] = [1, 2, 3, 4, 5, 6, 7, 25, 26, 27];
+  // The 'GURL' Apple Event and its '----' parameter.
+  const K_INTERNET_EVENT_CLASS: u32 = u32::from_be_bytes(*b"GURL");
+  const K_AE_GET_URL: u32 = u32::from_be_bytes(*b"GURL");
+  const KEY_DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+  const NS_TERMINATE_LATER: usize = 2;
+
+  // IDs of menu items selected since the event loop last asked.
+  static SELECTED_MENU_ITEMS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+  // URLs opened since the event loop last asked.
+  static OPENED_URLS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+  // Whether the app was asked to quit since the event loop last asked.
+  static QUIT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct NSPoint {
+    x: f64,
+    y: f64,
+  }
+
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct NSSize {
+    width: f64,
+    height: f64,
+  }
+
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  #[allow(dead_code)]
+  struct NSRect {
+    origin: NSPoint,
+    size: NSSize,
+  }
+
+  #[repr(C)]
+  #[derive(Clone, Copy, Default)]
+  struct NSEdgeInsets {
+    top: f64,
+    left: f64,
+    bottom: f64,
+    right: f64,
+  }
+
+  #[link(name = "CoreFoundation", kind = "framework")]
+  extern "C" {
+    fn CFRunLoopGetMain() -> *mut c_void;
+    fn CFRunLoopWakeUp(run_loop: *mut c_void);
+  }
+
+  thread_local! {
+    // Neither menu items nor the Apple Event manager retain their target, so
+    // keep it alive here.
+    static EVENT_TARGET: Cell<*mut Object> = Cell::new(ptr::null_mut());
+  }
+
+  unsafe fn ns_string(string: &str) -> *mut Object {
+    let ns_string: *mut Object = msg_send![class!(NSString), alloc];
+    let ns_string: *mut Object = msg_send![
+      ns_string,
+      initWithBytes: string.as_ptr()
+      length: string.len()
+      encoding: NS_UTF8_STRING_ENCODING
+    ];
+    msg_send![ns_string, autorelease]
+  }
+
+  unsafe fn shared_application() -> *mut Object {
+    msg_send![class!(NSApplication), sharedApplication]
+  }
+
+  unsafe fn rust_string(ns_string: *mut Object) -> String {
+    let utf8: *const c_char = msg_send![ns_string, UTF8String];
+    CStr::from_ptr(utf8).to_string_lossy().into_owned()
+  }
+
+  extern "C" fn select_menu_item(_this: &Object, _sel: Sel, item: *mut Object) {
+    // SAFETY: AppKit passes the selected NSMenuItem, whose represented object
+    // is the NSString ID set in `set_app_menu`.
+    let id = unsafe {
+      let id: *mut Object = msg_send![item, representedObject];
+      rust_string(id)
+    };
+    SELECTED_MENU_ITEMS.lock().unwrap().push(id);
+  }
+
+  extern "C" fn handle_get_url_event(
+    _this: &Object,
+    _sel: Sel,
+    event: *mut Object,
+    _reply: *mut Object,
+  ) {
+    // SAFETY: The Apple Event manager passes the NSAppleEventDescriptor of a
+    // 'GURL' event, whose direct parameter is the URL.
+    let url = unsafe {
+      let url: *mut Object =
+        msg_send![event, paramDescriptorForKeyword: KEY_DIRECT_OBJECT];
+      let url: *mut Object = msg_send![url, stringValue];
+      if url.is_null() {
+        return;
+      }
+      rust_string(url)
+    };
+    OPENED_URLS.lock().unwrap().push(url);
+  }
+
+  extern "C" fn should_terminate(
+    _this: &Object,
+    _sel: Sel,
+    _sender: *mut Object,
+  ) -> usize {
+    QUIT_REQUESTED.store(true, Ordering::Relaxed);
+    // AppKit runs the run loop until it gets a reply. Waking it has winit
+    // dispatch events, after which the driver delivers the request.
+    // SAFETY: The main run loop exists while the app runs.
+    unsafe { CFRunLoopWakeUp(CFRunLoopGetMain()) };
+    NS_TERMINATE_LATER
+  }
+
+  extern "C" fn source_operation_mask(
+    _this: &Object,
+    _sel: Sel,
+    _session: *mut Object,
+    _context: isize,
+  ) -> usize {
+    NS_DRAG_OPERATION_COPY
+  }
+
+  // An object that receives menu item actions and Apple Events, and is the
+  // source of drags out of windows.
+  unsafe fn event_target() -> *mut Object {
+    static REGISTER_CLASS: Once = Once::new();
+    REGISTER_CLASS.call_once(|| {
+      let mut decl =
+        ClassDecl::new("DenogEventTarget", class!(NSObject)).unwrap();
+      decl.add_method(
+        sel!(selectMenuItem:),
+        select_menu_item as extern "C" fn(&Object, Sel, *mut Object),
+      );
+      decl.add_method(
+        sel!(handleGetUrlEvent:withReplyEvent:),
+        handle_get_url_event
+          as extern "C" fn(&Object, Sel, *mut Object, *mut Object),
+      );
+      decl.add_method(
+        sel!(draggingSession:sourceOperationMaskForDraggingContext:),
+        source_operation_mask
+          as extern "C" fn(&Object, Sel, *mut Object, isize) -> usize,
+      );
+      decl.register();
+    });
+    EVENT_TARGET.with(|target| {
+      if target.get().is_null() {
+        target.set(msg_send![class!(DenogEventTarget), new]);
+      }
+      target.get()
+    })
+  }
+
+  pub fn set_app_menu(items: Vec<Option<WsiAppMenuItem>>) {
+    autoreleasepool(|| unsafe {
+      let target = event_target();
+      let app_menu: *mut Object = msg_send![class!(NSMenu), new];
+      let app_menu: *mut Object = msg_send![app_menu, autorelease];
+      for item in items {
+        let menu_item: *mut Object = match item {
+          Some(item) => {
+            let key = item.key.as_deref().unwrap_or("");
+            let menu_item: *mut Object = msg_send![class!(NSMenuItem), alloc];
+            let menu_item: *mut Object = msg_send![
+              menu_item,
+              initWithTitle: ns_string(&item.title)
+              action: sel!(selectMenuItem:)
+              keyEquivalent: ns_string(key)
+            ];
+            let _: () = msg_send![menu_item, setTarget: target];
+            let _: () =
+              msg_send![menu_item, setRepresentedObject: ns_string(&item.id)];
+            msg_send![menu_item, autorelease]
+          }
+          None => msg_send![class!(NSMenuItem), separatorItem],
+        };
+        let _: () = msg_send![app_menu, addItem: menu_item];
+      }
+
+      // The first item of the main menu holds the application menu.
+      let app_menu_item: *mut Object = msg_send![class!(NSMenuItem), new];
+      let app_menu_item: *mut Object = msg_send![app_menu_item, autorelease];
+      let _: () = msg_send![app_menu_item, setSubmenu: app_menu];
+      let main_menu: *mut Object = msg_send![class!(NSMenu), new];
+      let main_menu: *mut Object = msg_send![main_menu, autorelease];
+      let _: () = msg_send![main_menu, addItem: app_menu_item];
+      let _: () = msg_send![shared_application(), setMainMenu: main_menu];
+    })
+  }
+
+  pub fn take_selected_menu_items() -> Vec<String> {
+    std::mem::take(&mut *SELECTED_MENU_ITEMS.lock().unwrap())
+  }
+
+  // Receive the URLs that macOS opens with this app, i.e. those with a scheme
+  // declared in the app bundle's Info.plist. Install this before the event
+  // loop runs to receive the URL that launched the app.
+  pub fn install_url_handler() {
+    // SAFETY: Called on the main thread. The target lives as long as the
+    // thread.
+    unsafe {
+      let manager: *mut Object =
+        msg_send![class!(NSAppleEventManager), sharedAppleEventManager];
+      let _: () = msg_send![
+        manager,
+        setEventHandler: event_target()
+        andSelector: sel!(handleGetUrlEvent:withReplyEvent:)
+        forEventClass: K_INTERNET_EVENT_CLASS
+        andEventID: K_AE_GET_URL
+      ];
+    }
+  }
+
+  pub fn take_opened_urls() -> Vec<String> {
+    std::mem::take(&mut *OPENED_URLS.lock().unwrap())
+  }
+
+  // Have the app ask before it quits, e.g. from the Quit menu item or when the
+  // user logs out, instead of quitting right away. Winit's application
+  // delegate doesn't answer `applicationShouldTerminate:`, so the method is
+  // added to its class, which exists once the event loop has been created.
+  pub fn install_quit_handler() {
+    let Some(class) = Class::get("WinitApplicationDelegate") else {
+      return;
+    };
+    let should_terminate: extern "C" fn(&Object, Sel, *mut Object) -> usize =
+      should_terminate;
+    // SAFETY: The type encoding matches `should_terminate`, which returns an
+    // NSApplicationTerminateReply.
+    unsafe {
+      class_addMethod(
+        class as *const Class as *mut Class,
+        sel!(applicationShouldTerminate:),
+        mem::transmute::<_, Imp>(should_terminate),
+        b"Q@:@\0".as_ptr().cast(),
+      );
+    }
+  }
+
+  pub fn take_quit_request() -> bool {
+    QUIT_REQUESTED.swap(false, Ordering::Relaxed)
+  }
+
+  // Quit, or keep running, after a quit request. Quitting makes winit destroy
+  // the event loop, and AppKit exits the process after that.
+  pub fn reply_to_quit_request(allow: bool) {
+    let reply = if allow { YES } else { NO };
+    // SAFETY: Called on the main thread while AppKit waits for a reply.
+    unsafe {
+      let _: () = msg_send![
+        shared_application(),
+        replyToApplicationShouldTerminate: reply
+      ];
+    }
+  }
+
+  pub fn set_dock_badge(label: Option<String>) {
+    autoreleasepool(|| unsafe {
+      let label = match label {
+        Some(label) => ns_string(&label),
+        None => ptr::null_mut(),
+      };
+      let dock_tile: *mut Object = msg_send![shared_application(), dockTile];
+      let _: () = msg_send![dock_tile, setBadgeLabel: label];
+    })
+  }
+
+  // Returns `false` if the image couldn't be decoded.
+  pub fn set_dock_icon(image: Option<Vec<u8>>) -> bool {
+    autoreleasepool(|| unsafe {
+      let image = match image {
+        Some(image) => {
+          let data: *mut Object = msg_send![
+            class!(NSData),
+            dataWithBytes: image.as_ptr()
+            length: image.len()
+          ];
+          let ns_image: *mut Object = msg_send![class!(NSImage), alloc];
+          let ns_image: *mut Object = msg_send![ns_image, initWithData: data];
+          if ns_image.is_null() {
+            return false;
+          }
+          msg_send![ns_image, autorelease]
+        }
+        // Restores the bundle's icon.
+        None => ptr::null_mut(),
+      };
+      let _: () =
+        msg_send![shared_application(), setApplicationIconImage: image];
+      true
+    })
+  }
+
+  // The window server's number for the window, which CoreGraphics takes.
+  pub fn window_number(window: &Window) -> Option<u32> {
+    let RawWindowHandle::AppKit(handle) = window.raw_window_handle() else {
+      return None;
+    };
+    let ns_window: *mut Object = handle.ns_window.cast();
+    // SAFETY: The window comes from winit and is valid while it exists.
+    let number: isize = unsafe { msg_send![ns_window, windowNumber] };
+    // Windows that aren't on screen yet have no number.
+    u32::try_from(number).ok().filter(|&number| number > 0)
+  }
+
+  // Where the cursor is within the window's view, in physical pixels.
+  pub fn cursor_position(window: &Window) -> Option<(f64, f64)> {
+    let RawWindowHandle::AppKit(handle) = window.raw_window_handle() else {
+      return None;
+    };
+    let ns_window: *mut Object = handle.ns_window.cast();
+    let ns_view: *mut Object = handle.ns_view.cast();
+    // SAFETY: The window and view come from winit and are valid while the
+    // window exists. Views aren't flipped, so y counts up from the bottom.
+    let (point, frame) = unsafe {
+      let point: NSPoint =
+        msg_send![ns_window, mouseLocationOutsideOfEventStream];
+      let point: NSPoint = msg_send![
+        ns_view,
+        convertPoint: point
+        fromView: ptr::null_mut::<Object>()
+      ];
+      let frame: NSRect = msg_send![ns_view, frame];
+      (point, frame)
+    };
+    let scale_factor = window.scale_factor();
+    Some((
+      point.x * scale_factor,
+      (frame.size.height - point.y) * scale_factor,
+    ))
+  }
+
+  // How far in from each edge of the window's view content can be drawn
+  // without being covered by the title bar or cut off by the screen's notch,
+  // as (top, right, bottom, left) in points. Views and screens have safe areas
+  // since macOS 11 and 12.
+  pub fn safe_area_insets(window: &Window) -> Option<(f64, f64, f64, f64)> {
+    let RawWindowHandle::AppKit(handle) = window.raw_window_handle() else {
+      return None;
+    };
+    let ns_window: *mut Object = handle.ns_window.cast();
+    let ns_view: *mut Object = handle.ns_view.cast();
+    // SAFETY: The window and view come from winit and are valid while the
+    // window exists. Only objects that respond to safeAreaInsets are sent it.
+    unsafe {
+      let mut insets = NSEdgeInsets::default();
+      let responds: BOOL =
+        msg_send![ns_view, respondsToSelector: sel!(safeAreaInsets)];
+      if responds != NO {
+        insets = msg_send![ns_view, safeAreaInsets];
+      }
+      let screen: *mut Object = msg_send![ns_window, screen];
+      if screen.is_null() {
+        return Some((insets.top, insets.right, insets.bottom, insets.left));
+      }
+      let responds: BOOL =
+        msg_send![screen, respondsToSelector: sel!(safeAreaInsets)];
+      if responds != NO {
+        // Screen coordinates count up from the bottom of the main screen.
+        let screen_insets: NSEdgeInsets = msg_send![screen, safeAreaInsets];
+        let screen: NSRect = msg_send![screen, frame];
+        let frame: NSRect = msg_send![ns_window, frame];
+        let content: NSRect =
+          msg_send![ns_window, contentRectForFrameRect: frame];
+        let (screen_right, screen_top) = (
+          screen.origin.x + screen.size.width,
+          screen.origin.y + screen.size.height,
+        );
+        let (content_right, content_top) = (
+          content.origin.x + content.size.width,
+          content.origin.y + content.size.height,
+        );
+        insets.top = insets
+          .top
+          .max(content_top - (screen_top - screen_insets.top));
+        insets.right = insets
+          .right
+          .max(content_right - (screen_right - screen_insets.right));
+        insets.bottom = insets
+          .bottom
+          .max(screen.origin.y + screen_insets.bottom - content.origin.y);
+        insets.left = insets
+          .left
+          .max(screen.origin.x + screen_insets.left - content.origin.x);
+      }
+      Some((
+        insets.top.max(0.0),
+        insets.right.max(0.0),
+        insets.bottom.max(0.0),
+        insets.left.max(0.0),
+      ))
+    }
+  }
+
+  pub fn double_click_interval() -> Duration {
+    // SAFETY: doubleClickInterval has no preconditions.
+    let seconds: f64 =
+      unsafe { msg_send![class!(NSEvent), doubleClickInterval] };
+    Duration::from_secs_f64(seconds)
+  }
+
+  // The pressure, tilt and NSEventButtonMask of the mouse event being handled,
+  // if a tablet pen caused it.
+  pub fn tablet_point() -> Option<(f32, (f64, f64), usize)> {
+    // SAFETY: Only mouse events have a subtype, and tablet point events have
+    // the rest.
+    unsafe {
+      let event: *mut Object = msg_send![shared_application(), currentEvent];
+      if event.is_null() {
+        return None;
+      }
+      // msg_send! can't name `type`, which is a Rust keyword.
+      let event_type: usize =
+        (*event).send_message(Sel::register("type"), ()).ok()?;
+      if !NS_MOUSE_EVENT_TYPES.contains(&event_type) {
+        return None;
+      }
+      let subtype: i16 = msg_send![event, subtype];
+      if subtype != NS_EVENT_SUBTYPE_TABLET_POINT {
+        return None;
+      }
+      let pressure: f32 = msg_send![event, pressure];
+      let tilt: NSPoint = msg_send![event, tilt];
+      let buttons: usize = msg_send![event, buttonMask];
+      Some((pressure, (tilt.x, tilt.y), buttons))
+    }
+  }
+
+  // Start dragging files and text out of the window's view, under the cursor.
+  // Drags start from the mouse event being handled, so returns `false` if
+  // there is none.
+  pub fn start_drag(
+    window: &Window,
+    paths: Vec<PathBuf>,
+    text: Option<String>,
+    image: Option<Vec<u8>>,
+  ) -> bool {
+    let RawWindowHandle::AppKit(handle) = window.raw_window_handle() else {
+      return false;
+    };
+    autoreleasepool(|| unsafe {
+      let ns_view: *mut Object = handle.ns_view.cast();
+      let event: *mut Object = msg_send![shared_application(), currentEvent];
+      if event.is_null() {
+        return false;
+      }
+      let image: *mut Object = match image {
+        Some(image) => {
+          let data: *mut Object = msg_send![
+            class!(NSData),
+            dataWithBytes: image.as_ptr()
+            length: image.len()
+          ];
+          let ns_image: *mut Object = msg_send![class!(NSImage), alloc];
+          let ns_image: *mut Object = msg_send![ns_image, initWithData: data];
+          msg_send![ns_image, autorelease]
+        }
+        None => ptr::null_mut(),
+      };
+      let size = if image.is_null() {
+        NSSize {
+          width: 32.0,
+          height: 32.0,
+        }
+      } else {
+        msg_send![image, size]
+      };
+
+      // The image is centered on the cursor.
+      let location: NSPoint = msg_send![event, locationInWindow];
+      let location: NSPoint = msg_send![
+        ns_view,
+        convertPoint: location
+        fromView: ptr::null_mut::<Object>()
+      ];
+      let frame = NSRect {
+        origin: NSPoint {
+          x: location.x - size.width / 2.0,
+          y: location.y - size.height / 2.0,
+        },
+        size,
+      };
+
+      let urls = paths.iter().map(|path| {
+        let url: *mut Object = msg_send![
+          class!(NSURL),
+          fileURLWithPath: ns_string(&path.to_string_lossy())
+        ];
+        url
+      });
+      let items: Vec<*mut Object> = urls
+        .chain(text.map(|text| ns_string(&text)))
+        .map(|writer| {
+          let item: *mut Object = msg_send![class!(NSDraggingItem), alloc];
+          let item: *mut Object =
+            msg_send![item, initWithPasteboardWriter: writer];
+          let _: () = msg_send![item, setDraggingFrame: frame contents: image];
+          msg_send![item, autorelease]
+        })
+        .collect();
+      let items: *mut Object = msg_send![
+        class!(NSArray),
+        arrayWithObjects: items.as_ptr()
+        count: items.len()
+      ];
+      let session: *mut Object = msg_send![
+        ns_view,
+        beginDraggingSessionWithItems: items
+        event: event
+        source: event_target()
+      ];
+      !session.is_null()
+    })
+  }
+
+  // Drop the dead key being composed as marked text, both in winit's view and
+  // in the input context that would combine it with the next key.
+  pub fn reset_dead_keys(window: &Window) {
+    let RawWindowHandle::AppKit(handle) = window.raw_window_handle() else {
+      return;
+    };
+    let ns_view: *mut Object = handle.ns_view.cast();
+    // SAFETY: The view comes from winit and is valid while the window exists.
+    unsafe {
+      let _: () = msg_send![ns_view, unmarkText];
+      let input_context: *mut Object = msg_send![ns_view, inputContext];
+      if !input_context.is_null() {
+        let _: () = msg_send![input_context, discardMarkedText];
+      }
+    }
+  }
+
+  pub fn request_dock_attention(critical: bool) {
+    let request_type = if critical {
+      NS_CRITICAL_REQUEST
+    } else {
+      NS_INFORMATIONAL_REQUEST
+    };
+    // SAFETY: Called on the main thread.
+    unsafe {
+      let _: isize =
+        msg_send![shared_application(), requestUserAttention: request_type];
+    }
+  }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod appkit {
+  use super::WsiAppMenuItem;
+
+  pub fn set_app_menu(_items: Vec<Option<WsiAppMenuItem>>) {}
+
+  pub fn take_selected_menu_items() -> Vec<String> {
+    Vec::new()
+  }
+
+  pub fn install_url_handler() {}
+
+  pub fn take_opened_urls() -> Vec<String> {
+    Vec::new()
+  }
+
+  pub fn set_dock_badge(_label: Option<String>) {}
+
+  pub fn set_dock_icon(_image: Option<Vec<u8>>) -> bool {
+    true
+  }
+
+  pub fn request_dock_attention(_critical: bool) {}
+}
+
+pub use appkit::*;
@@ -0,0 +1,219 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{
+  color_profile::color_profile,
+  event::WsiEvent,
+  orientation::{orientation, WsiOrientation},
+  safe_area::safe_area_insets,
+};
+use serde::Serialize;
+use std::{
+  collections::HashMap,
+  time::{Duration, Instant},
+};
+use winit::{
+  event_loop::EventLoopWindowTarget, monitor::MonitorHandle, window::Window,
+};
+
+// How often the monitors are compared with the ones last seen, at most.
+const MONITOR_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiMonitor {
+  name: Option<String>,
+  primary: bool,
+  position: (i32, i32),
+  size: (u32, u32),
+  work_area: (i32, i32, u32, u32),
+  scale_factor: f64,
+  refresh_rate: Option<u32>,
+}
+
+impl WsiMonitor {
+  pub fn new(monitor: &MonitorHandle, primary: bool) -> Self {
+    let position = monitor.position();
+    let size = monitor.size();
+    Self {
+      name: monitor.name(),
+      primary,
+      position: (position.x, position.y),
+      size: (size.width, size.height),
+      work_area: work_area(monitor),
+      scale_factor: monitor.scale_factor(),
+      refresh_rate: monitor.refresh_rate_millihertz(),
+    }
+  }
+}
+
+// The part of the monitor not covered by taskbars, docks or menu bars, as
+// (x, y, width, height) in desktop coordinates. Winit doesn't expose this, so
+// it's only known on Windows; elsewhere it's the whole monitor.
+pub fn work_area(monitor: &MonitorHandle) -> (i32, i32, u32, u32) {
+  #[cfg(windows)]
+  {
+    use winapi::um::winuser::{GetMonitorInfoW, MONITORINFO};
+    use winit::platform::windows::MonitorHandleExtWindows;
+
+    let mut info: MONITORINFO = unsafe { std::mem::zeroed() };
+    info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+    // SAFETY: The handle comes from winit and `info` is a valid MONITORINFO.
+    if unsafe { GetMonitorInfoW(monitor.hmonitor() as _, &mut info) } != 0 {
+      let rect = info.rcWork;
+      return (
+        rect.left,
+        rect.top,
+        (rect.right - rect.left) as u32,
+        (rect.bottom - rect.top) as u32,
+      );
+    }
+  }
+
+  let position = monitor.position();
+  let size = monitor.size();
+  (position.x, position.y, size.width, size.height)
+}
+
+// Winit doesn't report when monitors are connected, disconnected or changed,
+// or when a window moves to another monitor, its monitor's refresh rate,
+// color profile or orientation changes or its safe area changes, so the
+// driver compares them with the ones it last saw. It does so when a window
+// moves, resizes or changes its scale factor, which changing the monitors
+// usually makes happen, and on other events at most once per
+// `MONITOR_POLL_INTERVAL`. Reading a color profile can mean reading a file, so
+// profiles are only compared on those timed polls and when a window moves to
+// another monitor.
+#[derive(Default)]
+pub struct MonitorWatcher {
+  // `None` until the first poll, which only records what's there.
+  monitors: Option<Vec<(MonitorHandle, WsiMonitor)>>,
+  window_monitors: HashMap<u64, WindowMonitor>,
+  last_poll: Option<Instant>,
+}
+
+// The monitor that a window is on, with its refresh rate, color profile and
+// orientation, and the window's safe area insets.
+struct WindowMonitor {
+  monitor: Option<MonitorHandle>,
+  refresh_rate: Option<u32>,
+  color_profile: Option<Vec<u8>>,
+  orientation: Option<WsiOrientation>,
+  safe_area_insets: (u32, u32, u32, u32),
+}
+
+impl MonitorWatcher {
+  // The events for what changed since the last poll, if it's time to poll.
+  pub fn update(
+    &mut self,
+    event: &WsiEvent,
+    window_target: &EventLoopWindowTarget<()>,
+    windows: &HashMap<u64, Window>,
+  ) -> Vec<WsiEvent> {
+    let window_changed = matches!(
+      event,
+      WsiEvent::WindowMoved { .. }
+        | WsiEvent::WindowResized { .. }
+        | WsiEvent::ScaleFactorChanged { .. }
+    );
+    let due = self.last_poll.map_or(true, |last_poll| {
+      last_poll.elapsed() >= MONITOR_POLL_INTERVAL
+    });
+    if !window_changed && !due {
+      return Vec::new();
+    }
+    self.last_poll = Some(Instant::now());
+    let primary = window_target.primary_monitor();
+    let describe = |monitor: &MonitorHandle| {
+      WsiMonitor::new(monitor, primary.as_ref() == Some(monitor))
+    };
+    let mut events = Vec::new();
+
+    let monitors: Vec<_> = window_target
+      .available_monitors()
+      .map(|monitor| {
+        let info = describe(&monitor);
+        (monitor, info)
+      })
+      .collect();
+    if let Some(previous) = self.monitors.replace(monitors.clone()) {
+      for (handle, monitor) in &previous {
+        if !monitors.iter().any(|(other, _)| other == handle) {
+          events.push(WsiEvent::MonitorDisconnected {
+            monitor: monitor.clone(),
+          });
+        }
+      }
+      for (handle, monitor) in monitors {
+        match previous.iter().find(|(other, _)| *other == handle) {
+          None => events.push(WsiEvent::MonitorConnected { monitor }),
+          Some((_, old)) if *old != monitor => {
+            events.push(WsiEvent::MonitorChanged { monitor })
+          }
+          Some(_) => {}
+        }
+      }
+    }
+
+    // Windows are first seen without an event.
+    self
+      .window_monitors
+      .retain(|wid, _| windows.contains_key(wid));
+    for (&wid, window) in windows {
+      let monitor = window.current_monitor();
+      let refresh_rate = monitor
+        .as_ref()
+        .and_then(MonitorHandle::refresh_rate_millihertz);
+      let previous = self.window_monitors.get(&wid);
+      let profile = match (previous, &monitor) {
+        (Some(previous), _) if !due && previous.monitor == monitor => {
+          previous.color_profile.clone()
+        }
+        (_, Some(monitor)) => color_profile(window, monitor),
+        (_, None) => None,
+      };
+      let orientation =
+        monitor.as_ref().map(|monitor| orientation(window, monitor));
+      let seen = WindowMonitor {
+        monitor,
+        refresh_rate,
+        color_profile: profile,
+        orientation,
+        safe_area_insets: safe_area_insets(window),
+      };
+      let Some(previous) = self.window_monitors.insert(wid, seen) else {
+        continue;
+      };
+      let seen = &self.window_monitors[&wid];
+      if previous.monitor != seen.monitor {
+        events.push(WsiEvent::WindowMonitorChanged {
+          window: wid,
+          monitor: seen.monitor.as_ref().map(describe),
+        });
+      }
+      if previous.refresh_rate != seen.refresh_rate {
+        events.push(WsiEvent::WindowRefreshRateChanged {
+          window: wid,
+          refresh_rate: seen.refresh_rate,
+        });
+      }
+      if previous.color_profile != seen.color_profile {
+        events.push(WsiEvent::WindowColorProfileChanged { window: wid });
+      }
+      if let Some(orientation) = seen.orientation {
+        if previous.orientation != seen.orientation {
+          events.push(WsiEvent::WindowOrientationChanged {
+            window: wid,
+            orientation,
+          });
+        }
+      }
+      if previous.safe_area_insets != seen.safe_area_insets {
+        events.push(WsiEvent::WindowSafeAreaChanged {
+          window: wid,
+          insets: seen.safe_area_insets,
+        });
+      }
+    }
+    events
+  }
+}
@@ -0,0 +1,242 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use serde::{Deserialize, Serialize};
+use winit::{monitor::MonitorHandle, window::Window};
+
+// The orientations of the Screen Orientation API. A monitor's primary
+// orientations are the ones it has unrotated and rotated by 90 degrees.
+// https://w3c.github.io/screen-orientation/#dom-orientationtype
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiOrientationType {
+  PortraitPrimary,
+  PortraitSecondary,
+  LandscapePrimary,
+  LandscapeSecondary,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct WsiOrientation {
+  #[serde(rename = "type")]
+  kind: WsiOrientationType,
+  // How far the monitor is rotated clockwise, in degrees.
+  angle: u32,
+}
+
+// https://w3c.github.io/screen-orientation/#dom-orientationlocktype
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiOrientationLock {
+  Any,
+  Landscape,
+  Portrait,
+  PortraitPrimary,
+  PortraitSecondary,
+  LandscapePrimary,
+  LandscapeSecondary,
+}
+
+// A monitor's orientation, from its size and rotation. Monitors are taken to
+// be unrotated where the platform doesn't tell, which includes Wayland.
+pub fn orientation(window: &Window, monitor: &MonitorHandle) -> WsiOrientation {
+  let angle = platform::rotation(window, monitor).unwrap_or(0);
+  let size = monitor.size();
+  let kind = match (size.width >= size.height, angle >= 180) {
+    (true, false) => WsiOrientationType::LandscapePrimary,
+    (true, true) => WsiOrientationType::LandscapeSecondary,
+    (false, false) => WsiOrientationType::PortraitPrimary,
+    (false, true) => WsiOrientationType::PortraitSecondary,
+  };
+  WsiOrientation { kind, angle }
+}
+
+// Keep tablets that rotate their screen with the device in the given
+// orientations, and return whether the platform supports it. Only Windows
+// does, and it applies the last lock to the whole program while one of its
+// windows is in the foreground. Unlocking always succeeds.
+pub fn lock_orientation(lock: WsiOrientationLock) -> bool {
+  platform::lock_orientation(lock) || matches!(lock, WsiOrientationLock::Any)
+}
+
+#[cfg(windows)]
+mod platform {
+  use super::WsiOrientationLock;
+  use std::{ffi::OsString, iter, mem, os::windows::ffi::OsStrExt};
+  use winapi::{
+    shared::minwindef::{BOOL, DWORD},
+    um::{
+      wingdi::DEVMODEW,
+      winuser::{EnumDisplaySettingsW, ENUM_CURRENT_SETTINGS},
+    },
+  };
+  use winit::{
+    monitor::MonitorHandle, platform::windows::MonitorHandleExtWindows,
+    window::Window,
+  };
+
+  // https://learn.microsoft.com/en-us/windows/win32/api/winuser/ne-winuser-orientation_preference
+  const ORIENTATION_PREFERENCE_NONE: DWORD = 0x0;
+  const ORIENTATION_PREFERENCE_LANDSCAPE: DWORD = 0x1;
+  const ORIENTATION_PREFERENCE_PORTRAIT: DWORD = 0x2;
+  const ORIENTATION_PREFERENCE_LANDSCAPE_FLIPPED: DWORD = 0x4;
+  const ORIENTATION_PREFERENCE_PORTRAIT_FLIPPED: DWORD = 0x8;
+
+  #[link(name = "user32")]
+  extern "system" {
+    fn SetDisplayAutoRotationPreferences(orientation: DWORD) -> BOOL;
+  }
+
+  // Display orientations count 90 degree steps clockwise.
+  // https://learn.microsoft.com/en-us/windows/win32/api/wingdi/ns-wingdi-devmodew
+  pub fn rotation(_window: &Window, monitor: &MonitorHandle) -> Option<u32> {
+    let device: Vec<u16> = OsString::from(monitor.native_id())
+      .encode_wide()
+      .chain(iter::once(0))
+      .collect();
+    // SAFETY: The device name is null-terminated, and the mode has its size
+    // set.
+    unsafe {
+      let mut mode: DEVMODEW = mem::zeroed();
+      mode.dmSize = mem::size_of::<DEVMODEW>() as u16;
+      if EnumDisplaySettingsW(device.as_ptr(), ENUM_CURRENT_SETTINGS, &mut mode)
+        == 0
+      {
+        return None;
+      }
+      Some(mode.u1.s2().dmDisplayOrientation * 90)
+    }
+  }
+
+  // Orientations are named for a landscape screen, like the Screen
+  // Orientation API's primary orientations.
+  pub fn lock_orientation(lock: WsiOrientationLock) -> bool {
+    let preference = match lock {
+      WsiOrientationLock::Any => ORIENTATION_PREFERENCE_NONE,
+      WsiOrientationLock::Landscape => {
+        ORIENTATION_PREFERENCE_LANDSCAPE
+          | ORIENTATION_PREFERENCE_LANDSCAPE_FLIPPED
+      }
+      WsiOrientationLock::Portrait => {
+        ORIENTATION_PREFERENCE_PORTRAIT
+          | ORIENTATION_PREFERENCE_PORTRAIT_FLIPPED
+      }
+      WsiOrientationLock::PortraitPrimary => ORIENTATION_PREFERENCE_PORTRAIT,
+      WsiOrientationLock::PortraitSecondary => {
+        ORIENTATION_PREFERENCE_PORTRAIT_FLIPPED
+      }
+      WsiOrientationLock::LandscapePrimary => ORIENTATION_PREFERENCE_LANDSCAPE,
+      WsiOrientationLock::LandscapeSecondary => {
+        ORIENTATION_PREFERENCE_LANDSCAPE_FLIPPED
+      }
+    };
+    // SAFETY: SetDisplayAutoRotationPreferences has no preconditions.
+    unsafe { SetDisplayAutoRotationPreferences(preference) != 0 }
+  }
+}
+
+// https://developer.apple.com/documentation/coregraphics/1454776-cgdisplayrotation
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::WsiOrientationLock;
+  use winit::{
+    monitor::MonitorHandle, platform::macos::MonitorHandleExtMacOS,
+    window::Window,
+  };
+
+  #[link(name = "CoreGraphics", kind = "framework")]
+  extern "C" {
+    fn CGDisplayRotation(display: u32) -> f64;
+  }
+
+  pub fn rotation(_window: &Window, monitor: &MonitorHandle) -> Option<u32> {
+    // SAFETY: CGDisplayRotation returns 0 for invalid displays.
+    let angle = unsafe { CGDisplayRotation(monitor.native_id()) };
+    Some(angle.round() as u32 % 360)
+  }
+
+  pub fn lock_orientation(_lock: WsiOrientationLock) -> bool {
+    false
+  }
+}
+
+// RandR rotations are counterclockwise.
+// https://www.x.org/releases/current/doc/randrproto/randrproto.txt
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use super::WsiOrientationLock;
+  use raw_window_handle::{HasRawDisplayHandle, RawDisplayHandle};
+  use std::os::raw::{c_int, c_ulong};
+  use winit::{
+    monitor::MonitorHandle, platform::x11::MonitorHandleExtX11, window::Window,
+  };
+  use x11_dl::{
+    xlib::{self, Xlib},
+    xrandr::{self, Xrandr},
+  };
+
+  pub fn rotation(window: &Window, monitor: &MonitorHandle) -> Option<u32> {
+    let RawDisplayHandle::Xlib(display) = window.raw_display_handle() else {
+      return None;
+    };
+    if display.display.is_null() {
+      return None;
+    }
+    let display: *mut xlib::Display = display.display.cast();
+    let xlib = Xlib::open().ok()?;
+    let xrandr = Xrandr::open().ok()?;
+    // SAFETY: The display comes from winit and is valid while the event loop
+    // exists. The monitor's native ID is its RandR CRTC, and the RandR
+    // resources are freed after use.
+    let rotation = unsafe {
+      let root = (xlib.XDefaultRootWindow)(display);
+      let resources = (xrandr.XRRGetScreenResourcesCurrent)(display, root);
+      if resources.is_null() {
+        return None;
+      }
+      let crtc = monitor.native_id() as c_ulong;
+      let info = (xrandr.XRRGetCrtcInfo)(display, resources, crtc);
+      let rotation = (!info.is_null()).then(|| (*info).rotation);
+      if !info.is_null() {
+        (xrandr.XRRFreeCrtcInfo)(info);
+      }
+      (xrandr.XRRFreeScreenResources)(resources);
+      rotation?
+    };
+    // The rotation shares its bits with reflections.
+    match c_int::from(rotation) & 0xf {
+      xrandr::RR_Rotate_0 => Some(0),
+      xrandr::RR_Rotate_90 => Some(270),
+      xrandr::RR_Rotate_180 => Some(180),
+      xrandr::RR_Rotate_270 => Some(90),
+      _ => None,
+    }
+  }
+
+  pub fn lock_orientation(_lock: WsiOrientationLock) -> bool {
+    false
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use super::WsiOrientationLock;
+  use winit::{monitor::MonitorHandle, window::Window};
+
+  pub fn rotation(_window: &Window, _monitor: &MonitorHandle) -> Option<u32> {
+    None
+  }
+
+  pub fn lock_orientation(_lock: WsiOrientationLock) -> bool {
+    false
+  }
+}
@@ -0,0 +1,224 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// Pen and stylus input. Winit reports pens as touches on Windows and as the
+// mouse on macOS, without their tilt, buttons or which end is used, so the
+// event loop driver reads those from the OS while it handles the event winit
+// reported. Elsewhere, pens are only reported as the mouse or touches.
+
+use crate::event::WsiEvent;
+use serde::Serialize;
+use std::collections::HashMap;
+use winit::window::Window;
+
+// What a pen is doing, like the pen fields of DOM pointer events.
+// https://www.w3.org/TR/pointerevents3/#pointerevent-interface
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(any(windows, target_os = "macos")), allow(dead_code))]
+pub struct WsiPenState {
+  // From 0 to 1.
+  pub pressure: f64,
+  // The angles between the pen and the surface's normal along the x and y
+  // axes, from -90 to 90 degrees.
+  pub tilt: (f64, f64),
+  pub in_contact: bool,
+  pub barrel_button: bool,
+  pub eraser: bool,
+}
+
+#[cfg_attr(not(any(windows, target_os = "macos")), allow(dead_code))]
+enum PenPhase {
+  Down,
+  Moved,
+  Up,
+}
+
+// The pen event for an event that winit reported for a pen.
+pub fn pen_event(
+  event: &WsiEvent,
+  windows: &HashMap<u64, Window>,
+) -> Option<WsiEvent> {
+  let (phase, window, device_id, position, pen) =
+    platform::pen_input(event, windows)?;
+  Some(match phase {
+    PenPhase::Down => WsiEvent::PenDown {
+      window,
+      device_id,
+      position,
+      pen,
+    },
+    PenPhase::Moved => WsiEvent::PenMoved {
+      window,
+      device_id,
+      position,
+      pen,
+    },
+    PenPhase::Up => WsiEvent::PenUp {
+      window,
+      device_id,
+      position,
+      pen,
+    },
+  })
+}
+
+type PenInput = (PenPhase, u64, u32, (f64, f64), WsiPenState);
+
+// Pens send pointer messages, which winit turns into touch events with the
+// pointer ID as the finger ID, and whose pen info stays available while they
+// are handled.
+// https://learn.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-pointer_pen_info
+#[cfg(windows)]
+mod platform {
+  use super::{PenInput, PenPhase, WsiPenState};
+  use crate::{event::WsiEvent, input::WsiTouchPhase};
+  use std::{collections::HashMap, mem};
+  use winapi::um::winuser::{
+    GetPointerPenInfo, GetPointerType, PEN_FLAG_BARREL, PEN_FLAG_ERASER,
+    PEN_FLAG_INVERTED, PEN_MASK_PRESSURE, PEN_MASK_TILT_X, PEN_MASK_TILT_Y,
+    POINTER_FLAG_INCONTACT, POINTER_PEN_INFO, PT_PEN,
+  };
+  use winit::window::Window;
+
+  // Pen pressure is reported from 0 to 1024.
+  const MAX_PRESSURE: f64 = 1024.0;
+
+  pub(super) fn pen_input(
+    event: &WsiEvent,
+    _windows: &HashMap<u64, Window>,
+  ) -> Option<PenInput> {
+    let WsiEvent::InputTouch {
+      window,
+      device_id,
+      location,
+      touch_phase,
+      finger_id,
+      ..
+    } = event
+    else {
+      return None;
+    };
+    let phase = match touch_phase {
+      WsiTouchPhase::Started => PenPhase::Down,
+      WsiTouchPhase::Moved => PenPhase::Moved,
+      WsiTouchPhase::Ended | WsiTouchPhase::Cancelled => PenPhase::Up,
+    };
+    let pen = pen_state(*finger_id as u32)?;
+    Some((phase, *window, *device_id, *location, pen))
+  }
+
+  fn pen_state(pointer_id: u32) -> Option<WsiPenState> {
+    let mut pointer_type = 0;
+    // SAFETY: The out pointers are valid.
+    let info = unsafe {
+      let mut info: POINTER_PEN_INFO = mem::zeroed();
+      if GetPointerType(pointer_id, &mut pointer_type) == 0
+        || pointer_type != PT_PEN
+        || GetPointerPenInfo(pointer_id, &mut info) == 0
+      {
+        return None;
+      }
+      info
+    };
+    let has = |mask| info.penMask & mask != 0;
+    let in_contact =
+      info.pointerInfo.pointerFlags & POINTER_FLAG_INCONTACT != 0;
+    // Pens without pressure are pressed halfway, as in DOM pointer events.
+    let pressure = if has(PEN_MASK_PRESSURE) {
+      info.pressure as f64 / MAX_PRESSURE
+    } else if in_contact {
+      0.5
+    } else {
+      0.0
+    };
+    let tilt_x = if has(PEN_MASK_TILT_X) { info.tiltX } else { 0 };
+    let tilt_y = if has(PEN_MASK_TILT_Y) { info.tiltY } else { 0 };
+    Some(WsiPenState {
+      pressure,
+      tilt: (tilt_x as f64, tilt_y as f64),
+      in_contact,
+      barrel_button: info.penFlags & PEN_FLAG_BARREL != 0,
+      // Inverted pens point their eraser end at the screen, while the eraser
+      // button makes the tip erase.
+      eraser: info.penFlags & (PEN_FLAG_INVERTED | PEN_FLAG_ERASER) != 0,
+    })
+  }
+}
+
+// Tablets send mouse events with the pen's state. Which end of the pen is
+// used is only sent in proximity events, which winit doesn't handle.
+// https://developer.apple.com/documentation/appkit/nsevent/1534969-tilt
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::{PenInput, PenPhase, WsiPenState};
+  use crate::{
+    event::WsiEvent,
+    input::{WsiButtonState, WsiMouseButton},
+    macos,
+  };
+  use std::collections::HashMap;
+  use winit::window::Window;
+
+  const NS_PEN_TIP_MASK: usize = 1;
+  const NS_PEN_LOWER_SIDE_MASK: usize = 2;
+  const NS_PEN_UPPER_SIDE_MASK: usize = 4;
+  // Tilt is reported from -1 to 1.
+  const MAX_TILT: f64 = 90.0;
+
+  pub(super) fn pen_input(
+    event: &WsiEvent,
+    windows: &HashMap<u64, Window>,
+  ) -> Option<PenInput> {
+    let (phase, window, device_id, position) = match event {
+      WsiEvent::CursorMoved {
+        window,
+        device_id,
+        position,
+      } => (PenPhase::Moved, *window, *device_id, Some(*position)),
+      WsiEvent::MouseButton {
+        window,
+        device_id,
+        button: WsiMouseButton::Left,
+        state,
+        ..
+      } => {
+        let phase = match state {
+          WsiButtonState::Pressed => PenPhase::Down,
+          WsiButtonState::Released => PenPhase::Up,
+        };
+        (phase, *window, *device_id, None)
+      }
+      _ => return None,
+    };
+    let (pressure, (tilt_x, tilt_y), buttons) = macos::tablet_point()?;
+    let position = match position {
+      Some(position) => position,
+      None => macos::cursor_position(windows.get(&window)?)?,
+    };
+    let pen = WsiPenState {
+      pressure: pressure as f64,
+      tilt: (tilt_x * MAX_TILT, tilt_y * MAX_TILT),
+      in_contact: buttons & NS_PEN_TIP_MASK != 0,
+      barrel_button: buttons
+        & (NS_PEN_LOWER_SIDE_MASK | NS_PEN_UPPER_SIDE_MASK)
+        != 0,
+      eraser: false,
+    };
+    Some((phase, window, device_id, position, pen))
+  }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod platform {
+  use super::PenInput;
+  use crate::event::WsiEvent;
+  use std::collections::HashMap;
+  use winit::window::Window;
+
+  pub(super) fn pen_input(
+    _event: &WsiEvent,
+    _windows: &HashMap<u64, Window>,
+  ) -> Option<PenInput> {
+    None
+  }
+}
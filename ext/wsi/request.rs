@@ -3,6 +3,12 @@
 use std::{
   collections::HashMap,
   fmt::{self, Debug, Formatter},
+  panic::Location,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc as std_mpsc, Arc, Condvar, Mutex,
+  },
+  time::{Duration, Instant},
 };
 use winit::{event_loop::EventLoopWindowTarget, window::Window};
 
@@ -11,14 +17,192 @@ pub type ExecuteRequestFn =
 
 pub enum Request {
   NextEvent,
-  Execute(Box<ExecuteRequestFn>),
+  Execute(ExecuteRequest),
+  // Wakes the event loop when it's paused.
+  Pause,
+}
+
+pub struct ExecuteRequest {
+  pub f: Box<ExecuteRequestFn>,
+  // Where the request was made, which names the op for tracing.
+  pub caller: &'static Location<'static>,
+  pub sent: Instant,
+  // Set by whichever gets to the request first: the event loop to run it, or
+  // the runtime to give up on it.
+  claimed: Arc<AtomicBool>,
+}
+
+impl ExecuteRequest {
+  #[track_caller]
+  pub fn new(f: Box<ExecuteRequestFn>) -> Self {
+    Self {
+      f,
+      caller: Location::caller(),
+      sent: Instant::now(),
+      claimed: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  // Claim the request to run it. Fails if the runtime gave up on it.
+  pub fn claim(&self) -> bool {
+    !self.claimed.swap(true, Ordering::SeqCst)
+  }
+
+  // A handle that gives up on the request.
+  pub fn canceller(&self) -> ExecuteRequestCanceller {
+    ExecuteRequestCanceller(self.claimed.clone())
+  }
+}
+
+pub struct ExecuteRequestCanceller(Arc<AtomicBool>);
+
+impl ExecuteRequestCanceller {
+  // Keep the event loop from running the request. Fails if it already started.
+  pub fn cancel(&self) -> bool {
+    !self.0.swap(true, Ordering::SeqCst)
+  }
 }
 
 impl Debug for Request {
   fn fmt(&self, f: &mut Formatter) -> fmt::Result {
     match self {
       Request::NextEvent => f.write_str("Request::NextEvent"),
-      Request::Execute(_) => f.write_str("Request::Execute"),
+      Request::Pause => f.write_str("Request::Pause"),
+      Request::Execute(request) => {
+        write!(f, "Request::Execute({})", request.caller)
+      }
     }
   }
 }
+
+// A channel for one request at a time, like `std_mpsc::sync_channel(1)`,
+// except that the sender can wait for the event loop to take the last request
+// with a timeout, instead of blocking forever. The receiver counts the
+// requests it takes and wakes waiting senders.
+pub fn request_channel() -> (RequestSender, RequestReceiver) {
+  let (tx, rx) = std_mpsc::sync_channel(1);
+  let taken = Arc::new((Mutex::new(0), Condvar::new()));
+  let sender = RequestSender {
+    tx,
+    taken: taken.clone(),
+  };
+  (sender, RequestReceiver { rx, taken })
+}
+
+pub enum SendTimeoutError {
+  Timeout,
+  Disconnected,
+}
+
+#[derive(Clone)]
+pub struct RequestSender {
+  tx: std_mpsc::SyncSender<Request>,
+  taken: Arc<(Mutex<u64>, Condvar)>,
+}
+
+impl RequestSender {
+  pub fn try_send(
+    &self,
+    request: Request,
+  ) -> Result<(), std_mpsc::TrySendError<Request>> {
+    self.tx.try_send(request)
+  }
+
+  pub fn send_timeout(
+    &self,
+    mut request: Request,
+    timeout: Duration,
+  ) -> Result<(), SendTimeoutError> {
+    let deadline = Instant::now() + timeout;
+    let (taken, condvar) = &*self.taken;
+    // The lock is held from the attempt until the wait, so the receiver can't
+    // take the request in between without the wait noticing.
+    let mut count = taken.lock().unwrap();
+    loop {
+      match self.tx.try_send(request) {
+        Ok(()) => return Ok(()),
+        Err(std_mpsc::TrySendError::Full(unsent)) => request = unsent,
+        Err(std_mpsc::TrySendError::Disconnected(_)) => {
+          return Err(SendTimeoutError::Disconnected);
+        }
+      }
+      let now = Instant::now();
+      if now >= deadline {
+        return Err(SendTimeoutError::Timeout);
+      }
+      let last = *count;
+      count = condvar
+        .wait_timeout_while(count, deadline - now, |count| *count == last)
+        .unwrap()
+        .0;
+    }
+  }
+}
+
+pub struct RequestReceiver {
+  rx: std_mpsc::Receiver<Request>,
+  taken: Arc<(Mutex<u64>, Condvar)>,
+}
+
+impl RequestReceiver {
+  pub fn recv(&self) -> Result<Request, std_mpsc::RecvError> {
+    let request = self.rx.recv()?;
+    self.notify_taken();
+    Ok(request)
+  }
+
+  pub fn try_recv(&self) -> Result<Request, std_mpsc::TryRecvError> {
+    let request = self.rx.try_recv()?;
+    self.notify_taken();
+    Ok(request)
+  }
+
+  fn notify_taken(&self) {
+    let (taken, condvar) = &*self.taken;
+    *taken.lock().unwrap() += 1;
+    condvar.notify_all();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::thread;
+
+  #[test]
+  fn send_timeout_times_out_while_the_slot_is_full() {
+    let (tx, _rx) = request_channel();
+    assert!(tx
+      .send_timeout(Request::NextEvent, Duration::from_millis(10))
+      .is_ok());
+    assert!(matches!(
+      tx.send_timeout(Request::NextEvent, Duration::from_millis(10)),
+      Err(SendTimeoutError::Timeout)
+    ));
+  }
+
+  #[test]
+  fn send_timeout_sends_once_the_receiver_takes_a_request() {
+    let (tx, rx) = request_channel();
+    tx.try_send(Request::NextEvent).unwrap();
+    let receiver = thread::spawn(move || {
+      thread::sleep(Duration::from_millis(50));
+      assert!(matches!(rx.recv(), Ok(Request::NextEvent)));
+      assert!(matches!(rx.recv(), Ok(Request::Pause)));
+    });
+    assert!(tx
+      .send_timeout(Request::Pause, Duration::from_secs(10))
+      .is_ok());
+    receiver.join().unwrap();
+  }
+
+  #[test]
+  fn send_timeout_fails_once_the_receiver_is_dropped() {
+    let (tx, rx) = request_channel();
+    drop(rx);
+    assert!(matches!(
+      tx.send_timeout(Request::NextEvent, Duration::from_millis(10)),
+      Err(SendTimeoutError::Disconnected)
+    ));
+  }
+}
@@ -0,0 +1,43 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use winit::window::Window;
+
+// How far in from each edge of a window's client area content can be drawn
+// without being hidden, as (top, right, bottom, left) in physical pixels. Only
+// macOS has display cutouts, the camera housing of MacBooks with a notch, and
+// there the title bar of a window whose content extends under it counts too.
+// Elsewhere the whole client area is safe.
+pub fn safe_area_insets(window: &Window) -> (u32, u32, u32, u32) {
+  platform::safe_area_insets(window)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+  use crate::macos;
+  use winit::window::Window;
+
+  pub fn safe_area_insets(window: &Window) -> (u32, u32, u32, u32) {
+    let Some((top, right, bottom, left)) = macos::safe_area_insets(window)
+    else {
+      return (0, 0, 0, 0);
+    };
+    // Round outward, so nothing is drawn in a partly covered pixel.
+    let scale_factor = window.scale_factor();
+    let physical = |points: f64| (points * scale_factor).ceil() as u32;
+    (
+      physical(top),
+      physical(right),
+      physical(bottom),
+      physical(left),
+    )
+  }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+  use winit::window::Window;
+
+  pub fn safe_area_insets(_window: &Window) -> (u32, u32, u32, u32) {
+    (0, 0, 0, 0)
+  }
+}
@@ -0,0 +1,342 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// The physical keys of the scan codes winit reports, named like the W3C
+// `KeyboardEvent.code` values, which don't depend on the keyboard layout.
+// https://www.w3.org/TR/uievents-code/
+pub fn code(scan_code: u32) -> Option<&'static str> {
+  platform::SCAN_CODES
+    .iter()
+    .chain(PC_SCAN_CODES)
+    .find(|&&(s, _)| s == scan_code)
+    .map(|&(_, code)| code)
+}
+
+// All the scan codes with a `code`.
+pub fn scan_codes() -> impl Iterator<Item = u32> {
+  platform::SCAN_CODES
+    .iter()
+    .chain(PC_SCAN_CODES)
+    .map(|&(scan_code, _)| scan_code)
+}
+
+// PC keyboard scan codes, which Windows reports and Linux key codes keep for
+// the main keys.
+#[cfg(not(target_os = "macos"))]
+const PC_SCAN_CODES: &[(u32, &str)] = &[
+  (0x01, "Escape"),
+  (0x02, "Digit1"),
+  (0x03, "Digit2"),
+  (0x04, "Digit3"),
+  (0x05, "Digit4"),
+  (0x06, "Digit5"),
+  (0x07, "Digit6"),
+  (0x08, "Digit7"),
+  (0x09, "Digit8"),
+  (0x0a, "Digit9"),
+  (0x0b, "Digit0"),
+  (0x0c, "Minus"),
+  (0x0d, "Equal"),
+  (0x0e, "Backspace"),
+  (0x0f, "Tab"),
+  (0x10, "KeyQ"),
+  (0x11, "KeyW"),
+  (0x12, "KeyE"),
+  (0x13, "KeyR"),
+  (0x14, "KeyT"),
+  (0x15, "KeyY"),
+  (0x16, "KeyU"),
+  (0x17, "KeyI"),
+  (0x18, "KeyO"),
+  (0x19, "KeyP"),
+  (0x1a, "BracketLeft"),
+  (0x1b, "BracketRight"),
+  (0x1c, "Enter"),
+  (0x1d, "ControlLeft"),
+  (0x1e, "KeyA"),
+  (0x1f, "KeyS"),
+  (0x20, "KeyD"),
+  (0x21, "KeyF"),
+  (0x22, "KeyG"),
+  (0x23, "KeyH"),
+  (0x24, "KeyJ"),
+  (0x25, "KeyK"),
+  (0x26, "KeyL"),
+  (0x27, "Semicolon"),
+  (0x28, "Quote"),
+  (0x29, "Backquote"),
+  (0x2a, "ShiftLeft"),
+  (0x2b, "Backslash"),
+  (0x2c, "KeyZ"),
+  (0x2d, "KeyX"),
+  (0x2e, "KeyC"),
+  (0x2f, "KeyV"),
+  (0x30, "KeyB"),
+  (0x31, "KeyN"),
+  (0x32, "KeyM"),
+  (0x33, "Comma"),
+  (0x34, "Period"),
+  (0x35, "Slash"),
+  (0x36, "ShiftRight"),
+  (0x37, "NumpadMultiply"),
+  (0x38, "AltLeft"),
+  (0x39, "Space"),
+  (0x3a, "CapsLock"),
+  (0x3b, "F1"),
+  (0x3c, "F2"),
+  (0x3d, "F3"),
+  (0x3e, "F4"),
+  (0x3f, "F5"),
+  (0x40, "F6"),
+  (0x41, "F7"),
+  (0x42, "F8"),
+  (0x43, "F9"),
+  (0x44, "F10"),
+  (0x46, "ScrollLock"),
+  (0x47, "Numpad7"),
+  (0x48, "Numpad8"),
+  (0x49, "Numpad9"),
+  (0x4a, "NumpadSubtract"),
+  (0x4b, "Numpad4"),
+  (0x4c, "Numpad5"),
+  (0x4d, "Numpad6"),
+  (0x4e, "NumpadAdd"),
+  (0x4f, "Numpad1"),
+  (0x50, "Numpad2"),
+  (0x51, "Numpad3"),
+  (0x52, "Numpad0"),
+  (0x53, "NumpadDecimal"),
+  (0x56, "IntlBackslash"),
+  (0x57, "F11"),
+  (0x58, "F12"),
+];
+
+#[cfg(target_os = "macos")]
+const PC_SCAN_CODES: &[(u32, &str)] = &[];
+
+// Keys beyond the main ones, with 0xe0 in the second byte when the scan code
+// is extended.
+// https://learn.microsoft.com/en-us/windows/win32/inputdev/about-keyboard-input#scan-codes
+#[cfg(windows)]
+mod platform {
+  pub const SCAN_CODES: &[(u32, &str)] = &[
+    (0x59, "NumpadEqual"),
+    (0x64, "F13"),
+    (0x65, "F14"),
+    (0x66, "F15"),
+    (0x67, "F16"),
+    (0x68, "F17"),
+    (0x69, "F18"),
+    (0x6a, "F19"),
+    (0x6b, "F20"),
+    (0x6c, "F21"),
+    (0x6d, "F22"),
+    (0x6e, "F23"),
+    (0x70, "KanaMode"),
+    (0x73, "IntlRo"),
+    (0x76, "F24"),
+    (0x79, "Convert"),
+    (0x7b, "NonConvert"),
+    (0x7d, "IntlYen"),
+    (0x7e, "NumpadComma"),
+    (0xe01c, "NumpadEnter"),
+    (0xe01d, "ControlRight"),
+    (0xe020, "AudioVolumeMute"),
+    (0xe02e, "AudioVolumeDown"),
+    (0xe030, "AudioVolumeUp"),
+    (0xe035, "NumpadDivide"),
+    (0xe037, "PrintScreen"),
+    (0xe038, "AltRight"),
+    (0xe045, "NumLock"),
+    (0xe047, "Home"),
+    (0xe048, "ArrowUp"),
+    (0xe049, "PageUp"),
+    (0xe04b, "ArrowLeft"),
+    (0xe04d, "ArrowRight"),
+    (0xe04f, "End"),
+    (0xe050, "ArrowDown"),
+    (0xe051, "PageDown"),
+    (0xe052, "Insert"),
+    (0xe053, "Delete"),
+    // Winit reports Pause with this scan code, whatever the modifiers.
+    (0xe059, "Pause"),
+    (0xe05b, "MetaLeft"),
+    (0xe05c, "MetaRight"),
+    (0xe05d, "ContextMenu"),
+  ];
+}
+
+// Virtual key codes, which are really scan codes, from HIToolbox/Events.h.
+#[cfg(target_os = "macos")]
+mod platform {
+  pub const SCAN_CODES: &[(u32, &str)] = &[
+    (0x00, "KeyA"),
+    (0x01, "KeyS"),
+    (0x02, "KeyD"),
+    (0x03, "KeyF"),
+    (0x04, "KeyH"),
+    (0x05, "KeyG"),
+    (0x06, "KeyZ"),
+    (0x07, "KeyX"),
+    (0x08, "KeyC"),
+    (0x09, "KeyV"),
+    (0x0a, "IntlBackslash"),
+    (0x0b, "KeyB"),
+    (0x0c, "KeyQ"),
+    (0x0d, "KeyW"),
+    (0x0e, "KeyE"),
+    (0x0f, "KeyR"),
+    (0x10, "KeyY"),
+    (0x11, "KeyT"),
+    (0x12, "Digit1"),
+    (0x13, "Digit2"),
+    (0x14, "Digit3"),
+    (0x15, "Digit4"),
+    (0x16, "Digit6"),
+    (0x17, "Digit5"),
+    (0x18, "Equal"),
+    (0x19, "Digit9"),
+    (0x1a, "Digit7"),
+    (0x1b, "Minus"),
+    (0x1c, "Digit8"),
+    (0x1d, "Digit0"),
+    (0x1e, "BracketRight"),
+    (0x1f, "KeyO"),
+    (0x20, "KeyU"),
+    (0x21, "BracketLeft"),
+    (0x22, "KeyI"),
+    (0x23, "KeyP"),
+    (0x24, "Enter"),
+    (0x25, "KeyL"),
+    (0x26, "KeyJ"),
+    (0x27, "Quote"),
+    (0x28, "KeyK"),
+    (0x29, "Semicolon"),
+    (0x2a, "Backslash"),
+    (0x2b, "Comma"),
+    (0x2c, "Slash"),
+    (0x2d, "KeyN"),
+    (0x2e, "KeyM"),
+    (0x2f, "Period"),
+    (0x30, "Tab"),
+    (0x31, "Space"),
+    (0x32, "Backquote"),
+    (0x33, "Backspace"),
+    (0x35, "Escape"),
+    (0x36, "MetaRight"),
+    (0x37, "MetaLeft"),
+    (0x38, "ShiftLeft"),
+    (0x39, "CapsLock"),
+    (0x3a, "AltLeft"),
+    (0x3b, "ControlLeft"),
+    (0x3c, "ShiftRight"),
+    (0x3d, "AltRight"),
+    (0x3e, "ControlRight"),
+    (0x3f, "Fn"),
+    (0x40, "F17"),
+    (0x41, "NumpadDecimal"),
+    (0x43, "NumpadMultiply"),
+    (0x45, "NumpadAdd"),
+    (0x47, "NumLock"),
+    (0x48, "AudioVolumeUp"),
+    (0x49, "AudioVolumeDown"),
+    (0x4a, "AudioVolumeMute"),
+    (0x4b, "NumpadDivide"),
+    (0x4c, "NumpadEnter"),
+    (0x4e, "NumpadSubtract"),
+    (0x4f, "F18"),
+    (0x50, "F19"),
+    (0x51, "NumpadEqual"),
+    (0x52, "Numpad0"),
+    (0x53, "Numpad1"),
+    (0x54, "Numpad2"),
+    (0x55, "Numpad3"),
+    (0x56, "Numpad4"),
+    (0x57, "Numpad5"),
+    (0x58, "Numpad6"),
+    (0x59, "Numpad7"),
+    (0x5a, "F20"),
+    (0x5b, "Numpad8"),
+    (0x5c, "Numpad9"),
+    (0x5d, "IntlYen"),
+    (0x5e, "IntlRo"),
+    (0x5f, "NumpadComma"),
+    (0x60, "F5"),
+    (0x61, "F6"),
+    (0x62, "F7"),
+    (0x63, "F3"),
+    (0x64, "F8"),
+    (0x65, "F9"),
+    (0x66, "Lang2"),
+    (0x67, "F11"),
+    (0x68, "Lang1"),
+    (0x69, "F13"),
+    (0x6a, "F16"),
+    (0x6b, "F14"),
+    (0x6d, "F10"),
+    (0x6e, "ContextMenu"),
+    (0x6f, "F12"),
+    (0x71, "F15"),
+    (0x72, "Insert"),
+    (0x73, "Home"),
+    (0x74, "PageUp"),
+    (0x75, "Delete"),
+    (0x76, "F4"),
+    (0x77, "End"),
+    (0x78, "F2"),
+    (0x79, "PageDown"),
+    (0x7a, "F1"),
+    (0x7b, "ArrowLeft"),
+    (0x7c, "ArrowRight"),
+    (0x7d, "ArrowDown"),
+    (0x7e, "ArrowUp"),
+  ];
+}
+
+// Linux key codes, from linux/input-event-codes.h.
+#[cfg(not(any(windows, target_os = "macos")))]
+mod platform {
+  pub const SCAN_CODES: &[(u32, &str)] = &[
+    (69, "NumLock"),
+    (89, "IntlRo"),
+    (92, "Convert"),
+    (93, "KanaMode"),
+    (94, "NonConvert"),
+    (96, "NumpadEnter"),
+    (97, "ControlRight"),
+    (98, "NumpadDivide"),
+    (99, "PrintScreen"),
+    (100, "AltRight"),
+    (102, "Home"),
+    (103, "ArrowUp"),
+    (104, "PageUp"),
+    (105, "ArrowLeft"),
+    (106, "ArrowRight"),
+    (107, "End"),
+    (108, "ArrowDown"),
+    (109, "PageDown"),
+    (110, "Insert"),
+    (111, "Delete"),
+    (113, "AudioVolumeMute"),
+    (114, "AudioVolumeDown"),
+    (115, "AudioVolumeUp"),
+    (117, "NumpadEqual"),
+    (119, "Pause"),
+    (121, "NumpadComma"),
+    (124, "IntlYen"),
+    (125, "MetaLeft"),
+    (126, "MetaRight"),
+    (127, "ContextMenu"),
+    (183, "F13"),
+    (184, "F14"),
+    (185, "F15"),
+    (186, "F16"),
+    (187, "F17"),
+    (188, "F18"),
+    (189, "F19"),
+    (190, "F20"),
+    (191, "F21"),
+    (192, "F22"),
+    (193, "F23"),
+    (194, "F24"),
+  ];
+}
@@ -0,0 +1,130 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::window::WsiCreateWindowOptions;
+use deno_core::{anyhow, serde_json};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+use winit::{
+  dpi::{PhysicalPosition, PhysicalSize},
+  event_loop::EventLoopWindowTarget,
+  window::{Window, WindowBuilder},
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiSavedWindow {
+  pub name: String,
+  pub position: Option<(i32, i32)>,
+  pub inner_size: (u32, u32),
+  pub maximized: bool,
+  pub fullscreen: bool,
+}
+
+impl WsiSavedWindow {
+  pub fn new(name: String, window: &Window) -> Self {
+    let size = window.inner_size();
+    Self {
+      name,
+      // Wayland doesn't tell windows where they are.
+      position: window.outer_position().ok().map(|p| (p.x, p.y)),
+      inner_size: (size.width, size.height),
+      maximized: window.is_maximized(),
+      fullscreen: window.fullscreen().is_some(),
+    }
+  }
+
+  // Starts a window where it was saved, except for what its options set. The
+  // position is dropped if it's off every monitor, e.g. because the monitor it
+  // was on has been disconnected.
+  pub fn restore(
+    &self,
+    mut builder: WindowBuilder,
+    options: &WsiCreateWindowOptions,
+    window_target: &EventLoopWindowTarget<()>,
+  ) -> WindowBuilder {
+    if let (None, Some((x, y))) = (options.position, self.position) {
+      let on_monitor = window_target.available_monitors().any(|monitor| {
+        let position = monitor.position();
+        let size = monitor.size();
+        (position.x..position.x + size.width as i32).contains(&x)
+          && (position.y..position.y + size.height as i32).contains(&y)
+      });
+      if on_monitor {
+        builder = builder.with_position(PhysicalPosition::new(x, y));
+      }
+    }
+    if options.inner_size.is_none() {
+      let (width, height) = self.inner_size;
+      builder = builder.with_inner_size(PhysicalSize::new(width, height));
+    }
+    if options.maximized.is_none() {
+      builder = builder.with_maximized(self.maximized);
+    }
+    builder
+  }
+}
+
+// The window layout saved by a previous run of the same program.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiSession {
+  pub windows: Vec<WsiSavedWindow>,
+}
+
+impl WsiSession {
+  // Returns `None` if there is no saved session. A file that can't be parsed
+  // (e.g. one written by an incompatible version) is treated as missing.
+  pub fn load(path: &Path) -> Option<Self> {
+    Self::read(path).filter(|session| !session.windows.is_empty())
+  }
+
+  fn read(path: &Path) -> Option<Self> {
+    let contents = fs::read(path).ok()?;
+    serde_json::from_slice::<Self>(&contents).ok()
+  }
+
+  pub fn saved_window(path: &Path, name: &str) -> Option<WsiSavedWindow> {
+    Self::read(path)?
+      .windows
+      .into_iter()
+      .find(|saved| saved.name == name)
+  }
+
+  // Replaces what was saved for the window's name. A maximized or full-screen
+  // window keeps the position and size it was saved with before, which winit
+  // can't report, so that it's unmaximized to where it was.
+  pub fn save_window(
+    path: &Path,
+    mut window: WsiSavedWindow,
+  ) -> Result<(), anyhow::Error> {
+    let mut session = Self::read(path).unwrap_or_default();
+    let previous = session
+      .windows
+      .iter()
+      .position(|saved| saved.name == window.name);
+    if let Some(index) = previous {
+      let previous = session.windows.remove(index);
+      if window.maximized || window.fullscreen {
+        window.position = previous.position;
+        window.inner_size = previous.inner_size;
+      }
+    }
+    session.windows.push(window);
+    // Write a temporary file and rename it, so that a crash can't leave a
+    // partial session behind.
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, serde_json::to_vec(&session)?)?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+  }
+
+  pub fn clear(path: &Path) -> Result<(), anyhow::Error> {
+    match fs::remove_file(path) {
+      Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+      _ => Ok(()),
+    }
+  }
+}
@@ -0,0 +1,171 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::event::WsiEvent;
+use deno_core::{anyhow, OpState, ResourceId};
+use deno_webgpu::{
+  error::WebGpuError, shader::create_shader_module,
+  shader_tools::validate_wgsl, shader_tools::GpuShaderMessage,
+};
+use serde::Serialize;
+use std::{
+  collections::{HashMap, VecDeque},
+  fs,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+  thread,
+  time::{Duration, SystemTime},
+};
+use winit::event_loop::EventLoopProxy;
+
+// How often watched files are checked for changes. Polling keeps working when
+// an editor saves by replacing the file.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// WGSL files that Deno.wsi.watchShader() compiles again when they change, by
+// watch id, and the watches whose file changed since it was last compiled.
+#[derive(Default)]
+pub(crate) struct WsiShaderWatches {
+  next_id: u32,
+  watches: HashMap<u32, ShaderWatch>,
+  changed: Arc<Mutex<VecDeque<u32>>>,
+}
+
+struct ShaderWatch {
+  device_rid: ResourceId,
+  label: Option<String>,
+  path: PathBuf,
+  stop: Arc<AtomicBool>,
+}
+
+impl Drop for ShaderWatch {
+  fn drop(&mut self) {
+    self.stop.store(true, Ordering::Relaxed);
+  }
+}
+
+// A watched file, compiled. The module is only created when the code has no
+// errors, so a typo doesn't replace a working module with an invalid one.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiShaderCompilation {
+  watch: u32,
+  module: Option<ResourceId>,
+  code: String,
+  messages: Vec<GpuShaderMessage>,
+}
+
+// Start watching a WGSL file for the device and compile it right away. Changes
+// wake the event loop, if there is one, so that they're noticed while
+// Deno.wsi.nextEvent() waits.
+pub(crate) fn watch(
+  state: &mut OpState,
+  device_rid: ResourceId,
+  path: PathBuf,
+  label: Option<String>,
+  wake: Option<EventLoopProxy<()>>,
+) -> Result<WsiShaderCompilation, anyhow::Error> {
+  let watches = state.borrow_mut::<WsiShaderWatches>();
+  let id = watches.next_id;
+  watches.next_id += 1;
+  let stop = Arc::new(AtomicBool::new(false));
+  let last_modified = modified(&path);
+  let compilation = compile(state, id, device_rid, label.clone(), &path)?;
+
+  let watches = state.borrow_mut::<WsiShaderWatches>();
+  let changed = watches.changed.clone();
+  watches.watches.insert(
+    id,
+    ShaderWatch {
+      device_rid,
+      label,
+      path: path.clone(),
+      stop: stop.clone(),
+    },
+  );
+  thread::spawn(move || {
+    let mut last_modified = last_modified;
+    while !stop.load(Ordering::Relaxed) {
+      thread::sleep(POLL_INTERVAL);
+      let modified = modified(&path);
+      if modified.is_none() || modified == last_modified {
+        continue;
+      }
+      last_modified = modified;
+      let mut changed = changed.lock().unwrap();
+      if !changed.contains(&id) {
+        changed.push_back(id);
+      }
+      if let Some(wake) = &wake {
+        let _ = wake.send_event(());
+      }
+    }
+  });
+  Ok(compilation)
+}
+
+pub(crate) fn unwatch(state: &mut OpState, id: u32) {
+  state.borrow_mut::<WsiShaderWatches>().watches.remove(&id);
+}
+
+// Compile the next watched file that changed, as a `shader-reloaded` event.
+// Files that can't be read right now, e.g. halfway through being replaced,
+// are skipped until they change again.
+pub(crate) fn take_reload(state: &mut OpState) -> Option<WsiEvent> {
+  loop {
+    let watches = state.borrow::<WsiShaderWatches>();
+    let id = watches.changed.lock().unwrap().pop_front()?;
+    let Some(watch) = watches.watches.get(&id) else {
+      continue;
+    };
+    let device_rid = watch.device_rid;
+    let label = watch.label.clone();
+    let path = watch.path.clone();
+    if let Ok(compilation) = compile(state, id, device_rid, label, &path) {
+      return Some(WsiEvent::ShaderReloaded(compilation));
+    }
+  }
+}
+
+fn compile(
+  state: &mut OpState,
+  watch: u32,
+  device_rid: ResourceId,
+  label: Option<String>,
+  path: &Path,
+) -> Result<WsiShaderCompilation, anyhow::Error> {
+  let code = fs::read_to_string(path)?;
+  let mut messages = validate_wgsl(&code);
+  let mut module = None;
+  if messages.is_empty() {
+    let result = create_shader_module(state, device_rid, label, code.clone())?;
+    match result.err {
+      None => module = result.rid,
+      // Only the device knows which features the code may use.
+      Some(err) => {
+        if let Some(rid) = result.rid {
+          let _ = state.resource_table.close(rid);
+        }
+        let message = match err {
+          WebGpuError::Lost => "The device was lost".into(),
+          WebGpuError::OutOfMemory => "Out of memory".into(),
+          WebGpuError::Validation(message) => message,
+        };
+        messages.push(GpuShaderMessage::error(message, None));
+      }
+    }
+  }
+  Ok(WsiShaderCompilation {
+    watch,
+    module,
+    code,
+    messages,
+  })
+}
+
+fn modified(path: &Path) -> Option<(SystemTime, u64)> {
+  let metadata = fs::metadata(path).ok()?;
+  Some((metadata.modified().ok()?, metadata.len()))
+}
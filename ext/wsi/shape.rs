@@ -0,0 +1,326 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use serde::Deserialize;
+use winit::window::Window;
+
+// The part of a window that's shown and takes input: a union of rectangles,
+// each with its corners rounded by the same radius. The rectangles are in
+// physical pixels relative to the client area once the op converts them.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiWindowShape {
+  pub rects: Vec<(i32, i32, u32, u32)>,
+  #[serde(default)]
+  pub corner_radius: u32,
+}
+
+// Give a window a non-rectangular shape, or its usual one back with `None`.
+// Returns `false` where windows can't be shaped, i.e. on Wayland, where
+// clients draw transparency and pass clicks through themselves.
+pub fn set_shape(window: &Window, shape: Option<&WsiWindowShape>) -> bool {
+  platform::set_shape(window, shape)
+}
+
+// The corner radius of a rectangle, which can be at most half its width or
+// height.
+#[allow(dead_code)]
+fn clamp_radius(
+  (_, _, width, height): (i32, i32, u32, u32),
+  radius: u32,
+) -> u32 {
+  radius.min(width / 2).min(height / 2)
+}
+
+// Windows shapes a window by a region of its whole frame, so the shape is
+// offset by where the client area is within it.
+// https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setwindowrgn
+#[cfg(windows)]
+mod platform {
+  use super::{clamp_radius, WsiWindowShape};
+  use std::ptr;
+  use winapi::{
+    shared::{
+      minwindef::TRUE,
+      windef::{HWND, POINT, RECT},
+    },
+    um::{
+      wingdi::{
+        CombineRgn, CreateRectRgn, CreateRoundRectRgn, DeleteObject, RGN_OR,
+      },
+      winuser::{ClientToScreen, GetWindowRect, SetWindowRgn},
+    },
+  };
+  use winit::{platform::windows::WindowExtWindows, window::Window};
+
+  pub fn set_shape(window: &Window, shape: Option<&WsiWindowShape>) -> bool {
+    let hwnd = window.hwnd() as HWND;
+    let Some(shape) = shape else {
+      // SAFETY: The window handle is valid.
+      unsafe { SetWindowRgn(hwnd, ptr::null_mut(), TRUE) };
+      return true;
+    };
+    let mut frame = RECT {
+      left: 0,
+      top: 0,
+      right: 0,
+      bottom: 0,
+    };
+    let mut client = POINT { x: 0, y: 0 };
+    // SAFETY: The window handle is valid. The window owns the region once it
+    // has been set, and the rectangles' regions are deleted once they've been
+    // combined.
+    unsafe {
+      if GetWindowRect(hwnd, &mut frame) == 0
+        || ClientToScreen(hwnd, &mut client) == 0
+      {
+        return false;
+      }
+      let (dx, dy) = (client.x - frame.left, client.y - frame.top);
+      let region = CreateRectRgn(0, 0, 0, 0);
+      for &rect in &shape.rects {
+        let (x, y, width, height) = rect;
+        let diameter = 2 * clamp_radius(rect, shape.corner_radius) as i32;
+        // The right and bottom edges are exclusive, and rounded ones one
+        // pixel more so.
+        let rect_region = CreateRoundRectRgn(
+          x + dx,
+          y + dy,
+          x + dx + width as i32 + 1,
+          y + dy + height as i32 + 1,
+          diameter,
+          diameter,
+        );
+        CombineRgn(region, region, rect_region, RGN_OR);
+        DeleteObject(rect_region.cast());
+      }
+      SetWindowRgn(hwnd, region, TRUE) != 0
+    }
+  }
+}
+
+// AppKit windows are shaped by masking the content view's layer, which also
+// masks the GPU surface's layer in it, and making the window transparent
+// around it. Layers count y up from the bottom and are in points.
+// https://developer.apple.com/documentation/quartzcore/calayer/1410861-mask
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::{clamp_radius, WsiWindowShape};
+  use objc::{
+    class, msg_send,
+    runtime::{Object, NO, YES},
+    sel, sel_impl,
+  };
+  use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+  use std::{ffi::c_void, ptr};
+  use winit::window::Window;
+
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct CGPoint {
+    x: f64,
+    y: f64,
+  }
+
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct CGSize {
+    width: f64,
+    height: f64,
+  }
+
+  #[repr(C)]
+  #[derive(Clone, Copy)]
+  struct CGRect {
+    origin: CGPoint,
+    size: CGSize,
+  }
+
+  #[link(name = "CoreGraphics", kind = "framework")]
+  extern "C" {
+    fn CGPathCreateMutable() -> *mut c_void;
+    fn CGPathAddRoundedRect(
+      path: *mut c_void,
+      transform: *const c_void,
+      rect: CGRect,
+      corner_width: f64,
+      corner_height: f64,
+    );
+    fn CGPathRelease(path: *mut c_void);
+  }
+
+  #[link(name = "QuartzCore", kind = "framework")]
+  extern "C" {}
+
+  pub fn set_shape(window: &Window, shape: Option<&WsiWindowShape>) -> bool {
+    let RawWindowHandle::AppKit(handle) = window.raw_window_handle() else {
+      return false;
+    };
+    let ns_window: *mut Object = handle.ns_window.cast();
+    let ns_view: *mut Object = handle.ns_view.cast();
+    let scale_factor = window.scale_factor();
+    let height = window.inner_size().height as f64 / scale_factor;
+    // SAFETY: The window and view come from winit and are valid while the
+    // window exists, and this runs on the main thread. The mask layer is
+    // autoreleased and retained by the view's layer, which copies the path.
+    unsafe {
+      let () = msg_send![ns_view, setWantsLayer: YES];
+      let layer: *mut Object = msg_send![ns_view, layer];
+      let Some(shape) = shape else {
+        let () = msg_send![layer, setMask: ptr::null_mut::<Object>()];
+        let () = msg_send![ns_window, invalidateShadow];
+        return true;
+      };
+      let path = CGPathCreateMutable();
+      for &rect in &shape.rects {
+        let (x, y, width, height_) = rect;
+        let radius = clamp_radius(rect, shape.corner_radius) as f64;
+        let bounds = CGRect {
+          origin: CGPoint {
+            x: x as f64 / scale_factor,
+            y: height - (y as f64 + height_ as f64) / scale_factor,
+          },
+          size: CGSize {
+            width: width as f64 / scale_factor,
+            height: height_ as f64 / scale_factor,
+          },
+        };
+        let radius = radius / scale_factor;
+        CGPathAddRoundedRect(path, ptr::null(), bounds, radius, radius);
+      }
+      let mask: *mut Object = msg_send![class!(CAShapeLayer), layer];
+      let () = msg_send![mask, setPath: path];
+      CGPathRelease(path);
+      let () = msg_send![layer, setMask: mask];
+      let clear: *mut Object = msg_send![class!(NSColor), clearColor];
+      let () = msg_send![ns_window, setOpaque: NO];
+      let () = msg_send![ns_window, setBackgroundColor: clear];
+      let () = msg_send![ns_window, invalidateShadow];
+    }
+    true
+  }
+}
+
+// X11 windows are shaped by the bounding region of the Shape extension, which
+// XFixes sets. Regions are lists of rectangles, so rounded corners are made of
+// a rectangle per row.
+// https://www.x.org/releases/current/doc/libXext/shapelib.html
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use super::{clamp_radius, WsiWindowShape};
+  use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+  };
+  use std::os::raw::c_int;
+  use winit::window::Window;
+  use x11_dl::{
+    xfixes,
+    xlib::{self, XRectangle},
+  };
+
+  const SHAPE_BOUNDING: c_int = 0;
+
+  pub fn set_shape(window: &Window, shape: Option<&WsiWindowShape>) -> bool {
+    let (RawDisplayHandle::Xlib(display), RawWindowHandle::Xlib(handle)) =
+      (window.raw_display_handle(), window.raw_window_handle())
+    else {
+      return false;
+    };
+    if display.display.is_null() {
+      return false;
+    }
+    let (Ok(xlib), Ok(xfixes)) = (xlib::Xlib::open(), xfixes::Xlib::open()) else {
+      return false;
+    };
+    let display: *mut xlib::Display = display.display.cast();
+    let mut rects: Vec<XRectangle> = shape
+      .map(|shape| {
+        shape
+          .rects
+          .iter()
+          .flat_map(|&rect| rows(rect, clamp_radius(rect, shape.corner_radius)))
+          .collect()
+      })
+      .unwrap_or_default();
+    // SAFETY: The display and window come from winit and are valid while the
+    // window exists. A region of 0 removes the shape.
+    unsafe {
+      let region = match shape {
+        Some(_) => (xfixes.XFixesCreateRegion)(
+          display,
+          rects.as_mut_ptr(),
+          rects.len() as c_int,
+        ),
+        None => 0,
+      };
+      (xfixes.XFixesSetWindowShapeRegion)(
+        display,
+        handle.window,
+        SHAPE_BOUNDING,
+        0,
+        0,
+        region,
+      );
+      if region != 0 {
+        (xfixes.XFixesDestroyRegion)(display, region);
+      }
+      (xlib.XFlush)(display);
+    }
+    true
+  }
+
+  // A rounded rectangle as rectangles: a row for each pixel of the rounded
+  // corners' height, and one for the straight part in between.
+  fn rows(
+    (x, y, width, height): (i32, i32, u32, u32),
+    radius: u32,
+  ) -> Vec<XRectangle> {
+    let rect = |x: i32, y: i32, width: u32, height: u32| XRectangle {
+      x: x as i16,
+      y: y as i16,
+      width: width as u16,
+      height: height as u16,
+    };
+    let mut rows = Vec::new();
+    let r = radius as f64;
+    for row in 0..radius {
+      let dy = r - row as f64 - 0.5;
+      let inset = (r - (r * r - dy * dy).max(0.0).sqrt()).round() as u32;
+      let row_width = width.saturating_sub(2 * inset);
+      let row = row as i32;
+      rows.push(rect(x + inset as i32, y + row, row_width, 1));
+      rows.push(rect(
+        x + inset as i32,
+        y + height as i32 - 1 - row,
+        row_width,
+        1,
+      ));
+    }
+    rows.push(rect(
+      x,
+      y + radius as i32,
+      width,
+      height.saturating_sub(2 * radius),
+    ));
+    rows
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use super::WsiWindowShape;
+  use winit::window::Window;
+
+  pub fn set_shape(_window: &Window, _shape: Option<&WsiWindowShape>) -> bool {
+    false
+  }
+}
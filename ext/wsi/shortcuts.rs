@@ -0,0 +1,279 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use winit::window::Window;
+
+// Let a window receive the keyboard shortcuts that the desktop would handle
+// itself while the window has focus, such as Alt+Tab and the Super key, for
+// full-screen games and remote desktop clients. Returns whether the platform
+// supports it. This works with Wayland compositors that support the keyboard
+// shortcuts inhibit protocol and on Windows, where the shell's shortcuts are
+// intercepted with a keyboard hook. macOS and X11 have no equivalent that
+// leaves other windows alone.
+pub fn set_system_shortcuts_inhibited(
+  wid: u64,
+  window: &Window,
+  inhibited: bool,
+) -> bool {
+  platform::set_system_shortcuts_inhibited(wid, window, inhibited)
+}
+
+// https://wayland.app/protocols/keyboard-shortcuts-inhibit-unstable-v1
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+  };
+  use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+  };
+  use wayland_client::{
+    protocol::{wl_seat::WlSeat, wl_surface::WlSurface},
+    Display, EventQueue, GlobalManager, Main, Proxy,
+  };
+  use wayland_protocols::unstable::keyboard_shortcuts_inhibit::v1::client::{
+    zwp_keyboard_shortcuts_inhibit_manager_v1::ZwpKeyboardShortcutsInhibitManagerV1,
+    zwp_keyboard_shortcuts_inhibitor_v1::ZwpKeyboardShortcutsInhibitorV1,
+  };
+  use winit::window::Window;
+
+  // The compositor keeps inhibiting shortcuts for as long as the inhibitors
+  // exist, so they're kept with the queue their events go to. Fields are
+  // dropped in order, so the queue outlives them.
+  struct Inhibitors {
+    inhibitors: HashMap<u64, Main<ZwpKeyboardShortcutsInhibitorV1>>,
+    manager: Main<ZwpKeyboardShortcutsInhibitManagerV1>,
+    seat: Main<WlSeat>,
+    display: Display,
+    _queue: EventQueue,
+  }
+
+  thread_local! {
+    static INHIBITORS: RefCell<Option<Inhibitors>> = RefCell::new(None);
+  }
+
+  pub fn set_system_shortcuts_inhibited(
+    wid: u64,
+    window: &Window,
+    inhibited: bool,
+  ) -> bool {
+    let (RawDisplayHandle::Wayland(display), RawWindowHandle::Wayland(handle)) =
+      (window.raw_display_handle(), window.raw_window_handle())
+    else {
+      return false;
+    };
+    INHIBITORS.with(|inhibitors| {
+      let mut inhibitors = inhibitors.borrow_mut();
+      if inhibitors.is_none() {
+        // SAFETY: The display comes from winit and is valid while the event
+        // loop exists.
+        *inhibitors = unsafe { Inhibitors::new(display.display.cast()) };
+      }
+      let Some(inhibitors) = inhibitors.as_mut() else {
+        return false;
+      };
+      if !inhibited {
+        if let Some(inhibitor) = inhibitors.inhibitors.remove(&wid) {
+          inhibitor.destroy();
+        }
+      } else if let Entry::Vacant(entry) = inhibitors.inhibitors.entry(wid) {
+        // SAFETY: The surface comes from winit and is valid while the window
+        // exists.
+        let surface: WlSurface = unsafe {
+          Proxy::<WlSurface>::from_c_ptr(handle.surface.cast()).into()
+        };
+        entry.insert(
+          inhibitors
+            .manager
+            .inhibit_shortcuts(&surface, &inhibitors.seat),
+        );
+      }
+      let _ = inhibitors.display.flush();
+      true
+    })
+  }
+
+  impl Inhibitors {
+    // Requests go through a separate event queue, so winit's own dispatching
+    // isn't disturbed. Inhibiting shortcuts for winit's seat would need its
+    // `wl_seat`, so the first seat is bound again.
+    unsafe fn new(
+      display: *mut wayland_client::sys::client::wl_display,
+    ) -> Option<Self> {
+      let display = Display::from_external_display(display);
+      let mut queue = display.create_event_queue();
+      let attached = display.attach(queue.token());
+      let globals = GlobalManager::new(&attached);
+      queue.sync_roundtrip(&mut (), |_, _, _| {}).ok()?;
+      // Compositors without the protocol always handle their shortcuts.
+      let manager = globals
+        .instantiate_exact::<ZwpKeyboardShortcutsInhibitManagerV1>(1)
+        .ok()?;
+      let seat = globals.instantiate_exact::<WlSeat>(1).ok()?;
+      Some(Self {
+        inhibitors: HashMap::new(),
+        manager,
+        seat,
+        display,
+        _queue: queue,
+      })
+    }
+  }
+}
+
+// The shell handles its shortcuts before they reach the focused window, so a
+// low-level keyboard hook takes them first and posts them to the window.
+// Ctrl+Alt+Del and Win+L can't be intercepted.
+// https://learn.microsoft.com/en-us/windows/win32/winmsg/lowlevelkeyboardproc
+#[cfg(windows)]
+mod platform {
+  use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+  use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    ptr,
+  };
+  use winapi::{
+    ctypes::c_int,
+    shared::{
+      minwindef::{LPARAM, LRESULT, UINT, WPARAM},
+      windef::HHOOK,
+    },
+    um::{
+      libloaderapi::GetModuleHandleW,
+      winuser::{
+        CallNextHookEx, GetAsyncKeyState, GetForegroundWindow, PostMessageW,
+        SetWindowsHookExW, UnhookWindowsHookEx, HC_ACTION, KBDLLHOOKSTRUCT,
+        LLKHF_ALTDOWN, LLKHF_EXTENDED, LLKHF_UP, VK_CONTROL, VK_ESCAPE,
+        VK_LWIN, VK_RWIN, VK_TAB, WH_KEYBOARD_LL,
+      },
+    },
+  };
+  use winit::window::Window;
+
+  thread_local! {
+    // The windows that receive the shell's shortcuts, and the hook that
+    // intercepts them while there are any, on the event loop thread.
+    static INHIBITED_WINDOWS: RefCell<HashSet<usize>> =
+      RefCell::new(HashSet::new());
+    static HOOK: Cell<HHOOK> = Cell::new(ptr::null_mut());
+  }
+
+  pub fn set_system_shortcuts_inhibited(
+    _wid: u64,
+    window: &Window,
+    inhibited: bool,
+  ) -> bool {
+    let RawWindowHandle::Win32(handle) = window.raw_window_handle() else {
+      return false;
+    };
+    let hwnd = handle.hwnd as usize;
+    let any_inhibited = INHIBITED_WINDOWS.with(|windows| {
+      let mut windows = windows.borrow_mut();
+      if inhibited {
+        windows.insert(hwnd);
+      } else {
+        windows.remove(&hwnd);
+      }
+      !windows.is_empty()
+    });
+    let hook = HOOK.with(Cell::get);
+    // SAFETY: The hook procedure is valid for the life of the program, and
+    // the hook is only removed once.
+    unsafe {
+      if any_inhibited && hook.is_null() {
+        let hook = SetWindowsHookExW(
+          WH_KEYBOARD_LL,
+          Some(keyboard_proc),
+          GetModuleHandleW(ptr::null()),
+          0,
+        );
+        HOOK.with(|cell| cell.set(hook));
+        return !hook.is_null();
+      }
+      if !any_inhibited && !hook.is_null() {
+        UnhookWindowsHookEx(hook);
+        HOOK.with(|cell| cell.set(ptr::null_mut()));
+      }
+    }
+    true
+  }
+
+  // Low-level keyboard hooks are called on the thread that installed them,
+  // while it waits for messages.
+  unsafe extern "system" fn keyboard_proc(
+    code: c_int,
+    wparam: WPARAM,
+    lparam: LPARAM,
+  ) -> LRESULT {
+    if code == HC_ACTION {
+      let info = &*(lparam as *const KBDLLHOOKSTRUCT);
+      let foreground = GetForegroundWindow();
+      let inhibited = INHIBITED_WINDOWS
+        .with(|windows| windows.borrow().contains(&(foreground as usize)));
+      if inhibited && is_shell_shortcut(info) {
+        let message = wparam as UINT;
+        PostMessageW(
+          foreground,
+          message,
+          info.vkCode as WPARAM,
+          key_lparam(info),
+        );
+        return 1;
+      }
+    }
+    CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+  }
+
+  // The Windows keys, which start all of the shell's Win+ shortcuts, Alt+Tab,
+  // Alt+Esc and Ctrl+Esc.
+  fn is_shell_shortcut(info: &KBDLLHOOKSTRUCT) -> bool {
+    let alt = info.flags & LLKHF_ALTDOWN != 0;
+    // SAFETY: GetAsyncKeyState has no preconditions.
+    let ctrl = unsafe { GetAsyncKeyState(VK_CONTROL) } < 0;
+    match info.vkCode as c_int {
+      VK_LWIN | VK_RWIN => true,
+      VK_TAB => alt,
+      VK_ESCAPE => alt || ctrl,
+      _ => false,
+    }
+  }
+
+  // The `lParam` of the key message that the window would have received.
+  // https://learn.microsoft.com/en-us/windows/win32/inputdev/about-keyboard-input#keystroke-message-flags
+  fn key_lparam(info: &KBDLLHOOKSTRUCT) -> LPARAM {
+    let mut lparam = 1 | (info.scanCode & 0xff) << 16;
+    if info.flags & LLKHF_EXTENDED != 0 {
+      lparam |= 1 << 24;
+    }
+    if info.flags & LLKHF_ALTDOWN != 0 {
+      lparam |= 1 << 29;
+    }
+    if info.flags & LLKHF_UP != 0 {
+      lparam |= 3 << 30;
+    }
+    lparam as LPARAM
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use winit::window::Window;
+
+  pub fn set_system_shortcuts_inhibited(
+    _wid: u64,
+    _window: &Window,
+    _inhibited: bool,
+  ) -> bool {
+    false
+  }
+}
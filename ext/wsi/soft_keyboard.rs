@@ -0,0 +1,497 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// The on-screen keyboard of touch devices. On Windows, the InputPane of a
+// window shows and hides it, and the shell tells handlers registered for each
+// window where it covers the screen as it starts to show or hide. Those events
+// are queued and delivered by the event loop driver. Other platforms have no
+// soft keyboard that can be controlled yet.
+
+use winit::{event_loop::EventLoopProxy, window::Window};
+
+// Show the soft keyboard for a window. Returns `false` if it couldn't be
+// shown, which is always the case outside Windows.
+pub fn show_soft_keyboard(window: &Window) -> bool {
+  platform::show_soft_keyboard(window)
+}
+
+// Hide the soft keyboard. Returns `false` if it couldn't be hidden.
+pub fn hide_soft_keyboard(window: &Window) -> bool {
+  platform::hide_soft_keyboard(window)
+}
+
+// The part of a window's client area the soft keyboard covers, in physical
+// pixels, or `None` if it doesn't cover the window.
+pub fn soft_keyboard_rect(window: &Window) -> Option<(i32, i32, u32, u32)> {
+  platform::soft_keyboard_rect(window)
+}
+
+// Report where the soft keyboard covers a new window. `event_loop_proxy` wakes
+// the event loop when there are soft keyboard events to deliver.
+pub fn register_soft_keyboard_handler(
+  window: &Window,
+  event_loop_proxy: EventLoopProxy<()>,
+) {
+  platform::register_soft_keyboard_handler(window, event_loop_proxy)
+}
+
+// Stop reporting for a window that's being destroyed.
+pub fn unregister_soft_keyboard_handler(wid: u64) {
+  platform::unregister_soft_keyboard_handler(wid)
+}
+
+pub use platform::take_soft_keyboard_events;
+
+// https://learn.microsoft.com/en-us/windows/win32/api/inputpaneinterop/nn-inputpaneinterop-iinputpaneinterop
+// https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nn-shobjidl_core-iframeworkinputpane
+#[cfg(windows)]
+mod platform {
+  use crate::event::WsiEvent;
+  use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{c_void, OsStr},
+    mem,
+    os::windows::ffi::OsStrExt,
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+  };
+  use winapi::{
+    shared::{
+      guiddef::{IsEqualIID, GUID, REFIID},
+      minwindef::{BOOL, DWORD, ULONG},
+      ntdef::HRESULT,
+      windef::{HWND, POINT, RECT},
+      winerror::{E_NOINTERFACE, SUCCEEDED, S_OK},
+      wtypesbase::CLSCTX_INPROC_SERVER,
+    },
+    um::{
+      combaseapi::CoCreateInstance,
+      unknwnbase::{IUnknown, IUnknownVtbl},
+      winuser::{GetClientRect, ScreenToClient},
+    },
+    winrt::{
+      hstring::HSTRING,
+      inspectable::IInspectableVtbl,
+      roapi::RoGetActivationFactory,
+      winstring::{WindowsCreateString, WindowsDeleteString},
+    },
+    Interface,
+  };
+  use winit::{
+    event_loop::EventLoopProxy, platform::windows::WindowExtWindows,
+    window::Window,
+  };
+
+  thread_local! {
+    // Soft keyboard events that haven't been delivered yet.
+    static SOFT_KEYBOARD_EVENTS: RefCell<Vec<WsiEvent>> =
+      RefCell::new(Vec::new());
+    // The shell's input pane, or null if there isn't one. It's kept for the
+    // life of the event loop thread.
+    static FRAMEWORK_INPUT_PANE: *mut FrameworkInputPane =
+      create_framework_input_pane();
+    // The cookies of the handlers registered for each window.
+    static COOKIES: RefCell<HashMap<u64, DWORD>> =
+      RefCell::new(HashMap::new());
+  }
+
+  pub fn take_soft_keyboard_events() -> Vec<WsiEvent> {
+    SOFT_KEYBOARD_EVENTS.with(|events| mem::take(&mut *events.borrow_mut()))
+  }
+
+  pub fn show_soft_keyboard(window: &Window) -> bool {
+    // SAFETY: The window handle is valid.
+    unsafe { try_show_or_hide(window.hwnd() as HWND, true) }
+  }
+
+  pub fn hide_soft_keyboard(window: &Window) -> bool {
+    // SAFETY: The window handle is valid.
+    unsafe { try_show_or_hide(window.hwnd() as HWND, false) }
+  }
+
+  pub fn soft_keyboard_rect(window: &Window) -> Option<(i32, i32, u32, u32)> {
+    let pane = FRAMEWORK_INPUT_PANE.with(|pane| *pane);
+    if pane.is_null() {
+      return None;
+    }
+    // SAFETY: The input pane is valid and the window handle is valid.
+    unsafe {
+      let mut rect: RECT = mem::zeroed();
+      if !SUCCEEDED(((*(*pane).vtbl).location)(pane, &mut rect)) {
+        return None;
+      }
+      client_rect(window.hwnd() as HWND, &rect)
+    }
+  }
+
+  pub fn register_soft_keyboard_handler(
+    window: &Window,
+    event_loop_proxy: EventLoopProxy<()>,
+  ) {
+    let pane = FRAMEWORK_INPUT_PANE.with(|pane| *pane);
+    if pane.is_null() {
+      return;
+    }
+    let hwnd = window.hwnd() as HWND;
+    let wid = window.id().into();
+    let handler = Box::into_raw(Box::new(Handler {
+      vtbl: &HANDLER_VTBL,
+      refs: AtomicU32::new(1),
+      hwnd,
+      wid,
+      event_loop_proxy,
+    }));
+    let mut cookie = 0;
+    // SAFETY: The input pane and window handle are valid. The input pane
+    // holds its own reference to the handler until it's unadvised.
+    let result = unsafe {
+      let result =
+        ((*(*pane).vtbl).advise_with_hwnd)(pane, hwnd, handler, &mut cookie);
+      release(handler.cast());
+      result
+    };
+    if SUCCEEDED(result) {
+      COOKIES.with(|cookies| cookies.borrow_mut().insert(wid, cookie));
+    }
+  }
+
+  pub fn unregister_soft_keyboard_handler(wid: u64) {
+    let Some(cookie) =
+      COOKIES.with(|cookies| cookies.borrow_mut().remove(&wid))
+    else {
+      return;
+    };
+    let pane = FRAMEWORK_INPUT_PANE.with(|pane| *pane);
+    // SAFETY: The cookie came from the input pane, which is valid.
+    unsafe { ((*(*pane).vtbl).unadvise)(pane, cookie) };
+  }
+
+  // COM is already initialized on the event loop thread by winit.
+  fn create_framework_input_pane() -> *mut FrameworkInputPane {
+    let mut pane: *mut FrameworkInputPane = ptr::null_mut();
+    // SAFETY: The class and interface IDs match the out pointer's type.
+    let result = unsafe {
+      CoCreateInstance(
+        &CLSID_FRAMEWORK_INPUT_PANE,
+        ptr::null_mut(),
+        CLSCTX_INPROC_SERVER,
+        &IID_IFRAMEWORK_INPUT_PANE,
+        &mut pane as *mut _ as *mut *mut c_void,
+      )
+    };
+    if SUCCEEDED(result) {
+      pane
+    } else {
+      ptr::null_mut()
+    }
+  }
+
+  // The window's Windows.UI.ViewManagement.InputPane, which shows and hides
+  // the keyboard on request since Windows 10.
+  unsafe fn try_show_or_hide(hwnd: HWND, show: bool) -> bool {
+    let name: Vec<u16> = OsStr::new("Windows.UI.ViewManagement.InputPane")
+      .encode_wide()
+      .collect();
+    let mut class: HSTRING = ptr::null_mut();
+    if !SUCCEEDED(WindowsCreateString(
+      name.as_ptr(),
+      name.len() as u32,
+      &mut class,
+    )) {
+      return false;
+    }
+    let mut interop: *mut InputPaneInterop = ptr::null_mut();
+    let result = RoGetActivationFactory(
+      class,
+      &IID_IINPUT_PANE_INTEROP,
+      &mut interop as *mut _ as *mut *mut c_void,
+    );
+    WindowsDeleteString(class);
+    if !SUCCEEDED(result) {
+      return false;
+    }
+    let mut pane: *mut InputPane2 = ptr::null_mut();
+    let result = ((*(*interop).vtbl).get_for_window)(
+      interop,
+      hwnd,
+      &IID_IINPUT_PANE2,
+      &mut pane as *mut _ as *mut *mut c_void,
+    );
+    ((*(*interop).vtbl).parent.parent.Release)(interop.cast());
+    if !SUCCEEDED(result) {
+      return false;
+    }
+    let mut done = 0;
+    let result = if show {
+      ((*(*pane).vtbl).try_show)(pane, &mut done)
+    } else {
+      ((*(*pane).vtbl).try_hide)(pane, &mut done)
+    };
+    ((*(*pane).vtbl).parent.parent.Release)(pane.cast());
+    SUCCEEDED(result) && done != 0
+  }
+
+  // The part of the client area that a rectangle in screen coordinates
+  // covers.
+  unsafe fn client_rect(
+    hwnd: HWND,
+    rect: &RECT,
+  ) -> Option<(i32, i32, u32, u32)> {
+    let mut client: RECT = mem::zeroed();
+    let mut top_left = POINT {
+      x: rect.left,
+      y: rect.top,
+    };
+    let mut bottom_right = POINT {
+      x: rect.right,
+      y: rect.bottom,
+    };
+    if GetClientRect(hwnd, &mut client) == 0
+      || ScreenToClient(hwnd, &mut top_left) == 0
+      || ScreenToClient(hwnd, &mut bottom_right) == 0
+    {
+      return None;
+    }
+    let left = top_left.x.max(client.left);
+    let top = top_left.y.max(client.top);
+    let right = bottom_right.x.min(client.right);
+    let bottom = bottom_right.y.min(client.bottom);
+    if right <= left || bottom <= top {
+      return None;
+    }
+    Some((left, top, (right - left) as u32, (bottom - top) as u32))
+  }
+
+  const CLSID_FRAMEWORK_INPUT_PANE: GUID = GUID {
+    Data1: 0xd5120aa3,
+    Data2: 0x46ba,
+    Data3: 0x44c5,
+    Data4: [0x82, 0x2d, 0xca, 0x80, 0x92, 0xc1, 0xfc, 0x72],
+  };
+
+  const IID_IFRAMEWORK_INPUT_PANE: GUID = GUID {
+    Data1: 0x5752238b,
+    Data2: 0x24f0,
+    Data3: 0x495a,
+    Data4: [0x82, 0xf1, 0x2f, 0xd5, 0x93, 0x05, 0x67, 0x96],
+  };
+
+  const IID_IFRAMEWORK_INPUT_PANE_HANDLER: GUID = GUID {
+    Data1: 0x226c537b,
+    Data2: 0x1e76,
+    Data3: 0x4d9e,
+    Data4: [0xa4, 0x21, 0x79, 0x6a, 0x2c, 0xd6, 0xa1, 0xf6],
+  };
+
+  const IID_IINPUT_PANE_INTEROP: GUID = GUID {
+    Data1: 0x75cf2c57,
+    Data2: 0x9195,
+    Data3: 0x4931,
+    Data4: [0x83, 0x32, 0xf0, 0xb4, 0x09, 0xe9, 0x16, 0xaf],
+  };
+
+  const IID_IINPUT_PANE2: GUID = GUID {
+    Data1: 0x8a6b3f26,
+    Data2: 0x7090,
+    Data3: 0x4793,
+    Data4: [0x94, 0x4c, 0xc3, 0xf2, 0xcd, 0xe2, 0x62, 0x76],
+  };
+
+  // IFrameworkInputPane, which winapi doesn't declare.
+  #[repr(C)]
+  struct FrameworkInputPane {
+    vtbl: *const FrameworkInputPaneVtbl,
+  }
+
+  #[repr(C)]
+  #[allow(dead_code)]
+  struct FrameworkInputPaneVtbl {
+    parent: IUnknownVtbl,
+    advise: unsafe extern "system" fn(
+      this: *mut FrameworkInputPane,
+      window: *mut IUnknown,
+      handler: *mut Handler,
+      cookie: *mut DWORD,
+    ) -> HRESULT,
+    advise_with_hwnd: unsafe extern "system" fn(
+      this: *mut FrameworkInputPane,
+      hwnd: HWND,
+      handler: *mut Handler,
+      cookie: *mut DWORD,
+    ) -> HRESULT,
+    unadvise: unsafe extern "system" fn(
+      this: *mut FrameworkInputPane,
+      cookie: DWORD,
+    ) -> HRESULT,
+    location: unsafe extern "system" fn(
+      this: *mut FrameworkInputPane,
+      rect: *mut RECT,
+    ) -> HRESULT,
+  }
+
+  // IInputPaneInterop, which winapi doesn't declare.
+  #[repr(C)]
+  struct InputPaneInterop {
+    vtbl: *const InputPaneInteropVtbl,
+  }
+
+  #[repr(C)]
+  struct InputPaneInteropVtbl {
+    parent: IInspectableVtbl,
+    get_for_window: unsafe extern "system" fn(
+      this: *mut InputPaneInterop,
+      hwnd: HWND,
+      riid: REFIID,
+      object: *mut *mut c_void,
+    ) -> HRESULT,
+  }
+
+  // Windows.UI.ViewManagement.IInputPane2, which winapi doesn't declare.
+  #[repr(C)]
+  struct InputPane2 {
+    vtbl: *const InputPane2Vtbl,
+  }
+
+  #[repr(C)]
+  struct InputPane2Vtbl {
+    parent: IInspectableVtbl,
+    try_show: unsafe extern "system" fn(
+      this: *mut InputPane2,
+      result: *mut u8,
+    ) -> HRESULT,
+    try_hide: unsafe extern "system" fn(
+      this: *mut InputPane2,
+      result: *mut u8,
+    ) -> HRESULT,
+  }
+
+  // An IFrameworkInputPaneHandler, whose interface must come first.
+  #[repr(C)]
+  #[allow(dead_code)]
+  struct Handler {
+    vtbl: *const HandlerVtbl,
+    refs: AtomicU32,
+    hwnd: HWND,
+    wid: u64,
+    event_loop_proxy: EventLoopProxy<()>,
+  }
+
+  #[repr(C)]
+  #[allow(dead_code)]
+  struct HandlerVtbl {
+    parent: IUnknownVtbl,
+    showing: unsafe extern "system" fn(
+      this: *mut Handler,
+      rect: *mut RECT,
+      ensure_focused_element_in_view: BOOL,
+    ) -> HRESULT,
+    hiding: unsafe extern "system" fn(
+      this: *mut Handler,
+      ensure_focused_element_in_view: BOOL,
+    ) -> HRESULT,
+  }
+
+  impl Handler {
+    fn send(&self, rect: Option<(i32, i32, u32, u32)>) {
+      let event = WsiEvent::SoftKeyboardChanged {
+        window: self.wid,
+        rect,
+      };
+      SOFT_KEYBOARD_EVENTS.with(|queue| queue.borrow_mut().push(event));
+      // Fails only after the event loop has exited.
+      let _ = self.event_loop_proxy.send_event(());
+    }
+  }
+
+  static HANDLER_VTBL: HandlerVtbl = HandlerVtbl {
+    parent: IUnknownVtbl {
+      QueryInterface: query_interface,
+      AddRef: add_ref,
+      Release: release,
+    },
+    showing,
+    hiding,
+  };
+
+  unsafe fn handler<'a, T>(this: *mut T) -> &'a Handler {
+    &*this.cast()
+  }
+
+  unsafe extern "system" fn query_interface(
+    this: *mut IUnknown,
+    riid: REFIID,
+    object: *mut *mut c_void,
+  ) -> HRESULT {
+    if IsEqualIID(&*riid, &IUnknown::uuidof())
+      || IsEqualIID(&*riid, &IID_IFRAMEWORK_INPUT_PANE_HANDLER)
+    {
+      add_ref(this);
+      *object = this.cast();
+      S_OK
+    } else {
+      *object = ptr::null_mut();
+      E_NOINTERFACE
+    }
+  }
+
+  unsafe extern "system" fn add_ref(this: *mut IUnknown) -> ULONG {
+    handler(this).refs.fetch_add(1, Ordering::Relaxed) + 1
+  }
+
+  unsafe extern "system" fn release(this: *mut IUnknown) -> ULONG {
+    let refs = handler(this).refs.fetch_sub(1, Ordering::AcqRel) - 1;
+    if refs == 0 {
+      drop(Box::from_raw(this.cast::<Handler>()));
+    }
+    refs
+  }
+
+  // The rectangle is where the keyboard will be once it has animated in.
+  unsafe extern "system" fn showing(
+    this: *mut Handler,
+    rect: *mut RECT,
+    _ensure_focused_element_in_view: BOOL,
+  ) -> HRESULT {
+    let handler = handler(this);
+    handler.send(client_rect(handler.hwnd, &*rect));
+    S_OK
+  }
+
+  unsafe extern "system" fn hiding(
+    this: *mut Handler,
+    _ensure_focused_element_in_view: BOOL,
+  ) -> HRESULT {
+    handler(this).send(None);
+    S_OK
+  }
+}
+
+#[cfg(not(windows))]
+mod platform {
+  use crate::event::WsiEvent;
+  use winit::{event_loop::EventLoopProxy, window::Window};
+
+  pub fn take_soft_keyboard_events() -> Vec<WsiEvent> {
+    Vec::new()
+  }
+
+  pub fn show_soft_keyboard(_window: &Window) -> bool {
+    false
+  }
+
+  pub fn hide_soft_keyboard(_window: &Window) -> bool {
+    false
+  }
+
+  pub fn soft_keyboard_rect(_window: &Window) -> Option<(i32, i32, u32, u32)> {
+    None
+  }
+
+  pub fn register_soft_keyboard_handler(
+    _window: &Window,
+    _event_loop_proxy: EventLoopProxy<()>,
+  ) {
+  }
+
+  pub fn unregister_soft_keyboard_handler(_wid: u64) {}
+}
@@ -0,0 +1,181 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use std::env;
+use winit::window::Window;
+
+// Tell the launcher that started this program that its first window is shown.
+// Launchers pass a token in the environment, XDG_ACTIVATION_TOKEN on Wayland
+// and DESKTOP_STARTUP_ID on X11. Presenting it lets the window take focus
+// despite focus stealing prevention and ends the launcher's "starting" cursor.
+// The variables are removed, so later windows and child processes don't reuse
+// the token.
+pub fn complete_startup(window: &Window) {
+  if let Some(token) = take_env("XDG_ACTIVATION_TOKEN") {
+    wayland::activate(window, token);
+  }
+  if let Some(startup_id) = take_env("DESKTOP_STARTUP_ID") {
+    x11::remove_startup_notification(window, &startup_id);
+  }
+}
+
+fn take_env(key: &str) -> Option<String> {
+  let value = env::var(key).ok()?;
+  env::remove_var(key);
+  Some(value).filter(|value| !value.is_empty())
+}
+
+// https://wayland.app/protocols/xdg-activation-v1
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod wayland {
+  use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+  };
+  use wayland_client::{
+    protocol::wl_surface::WlSurface, Display, GlobalManager, Proxy,
+  };
+  use wayland_protocols::staging::xdg_activation::v1::client::{
+    xdg_activation_v1::XdgActivationV1,
+  };
+  use winit::window::Window;
+
+  pub fn activate(window: &Window, token: String) {
+    let (RawDisplayHandle::Wayland(display), RawWindowHandle::Wayland(handle)) =
+      (window.raw_display_handle(), window.raw_window_handle())
+    else {
+      return;
+    };
+    // SAFETY: The display and surface come from winit and are valid while the
+    // window exists. Requests go through a separate event queue, so winit's
+    // own dispatching isn't disturbed.
+    unsafe {
+      let display = Display::from_external_display(display.display.cast());
+      let mut queue = display.create_event_queue();
+      let attached = display.attach(queue.token());
+      let globals = GlobalManager::new(&attached);
+      if queue.sync_roundtrip(&mut (), |_, _, _| {}).is_err() {
+        return;
+      }
+      // Compositors without the protocol don't use tokens.
+      let Ok(activation) = globals.instantiate_exact::<XdgActivationV1>(1)
+      else {
+        return;
+      };
+      let surface: WlSurface =
+        Proxy::<WlSurface>::from_c_ptr(handle.surface.cast()).into();
+      activation.activate(token, &surface);
+      activation.destroy();
+      let _ = display.flush();
+    }
+  }
+}
+
+#[cfg(not(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+)))]
+mod wayland {
+  use winit::window::Window;
+
+  pub fn activate(_window: &Window, _token: String) {}
+}
+
+// https://specifications.freedesktop.org/startup-notification-spec/startup-notification-latest.txt
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod x11 {
+  use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+  };
+  use winit::window::Window;
+  use x11_dl::xlib::{self, Xlib};
+
+  pub fn remove_startup_notification(window: &Window, startup_id: &str) {
+    let (RawDisplayHandle::Xlib(display), RawWindowHandle::Xlib(handle)) =
+      (window.raw_display_handle(), window.raw_window_handle())
+    else {
+      return;
+    };
+    let Ok(xlib) = Xlib::open() else {
+      return;
+    };
+    if display.display.is_null() {
+      return;
+    }
+    let display: *mut xlib::Display = display.display.cast();
+    let message = format!("remove: ID={}", quote(startup_id));
+
+    // SAFETY: The display and window come from winit and are valid while the
+    // window exists.
+    unsafe {
+      let atom = |name: &[u8]| {
+        (xlib.XInternAtom)(display, name.as_ptr().cast(), xlib::False)
+      };
+
+      // Lets the window manager match the window to the launch.
+      (xlib.XChangeProperty)(
+        display,
+        handle.window,
+        atom(b"_NET_STARTUP_ID\0"),
+        atom(b"UTF8_STRING\0"),
+        8,
+        xlib::PropModeReplace,
+        startup_id.as_ptr(),
+        startup_id.len() as i32,
+      );
+
+      // The message is broadcast to the root window in 20-byte pieces,
+      // including a terminating NUL, from a window of the sender's own.
+      let root = (xlib.XDefaultRootWindow)(display);
+      let sender =
+        (xlib.XCreateSimpleWindow)(display, root, -100, -100, 1, 1, 0, 0, 0);
+      let begin = atom(b"_NET_STARTUP_INFO_BEGIN\0");
+      let more = atom(b"_NET_STARTUP_INFO\0");
+      let bytes: Vec<u8> = message.bytes().chain(Some(0)).collect();
+      for (i, piece) in bytes.chunks(20).enumerate() {
+        let mut data = xlib::ClientMessageData::new();
+        for (j, &byte) in piece.iter().enumerate() {
+          data.set_byte(j, byte as _);
+        }
+        let mut event = xlib::XEvent::from(xlib::XClientMessageEvent {
+          type_: xlib::ClientMessage,
+          serial: 0,
+          send_event: xlib::True,
+          display,
+          window: sender,
+          message_type: if i == 0 { begin } else { more },
+          format: 8,
+          data,
+        });
+        (xlib.XSendEvent)(
+          display,
+          root,
+          xlib::False,
+          xlib::PropertyChangeMask,
+          &mut event,
+        );
+      }
+      (xlib.XDestroyWindow)(display, sender);
+      (xlib.XFlush)(display);
+    }
+  }
+
+  fn quote(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{escaped}\"")
+  }
+}
+
+#[cfg(not(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+)))]
+mod x11 {
+  use winit::window::Window;
+
+  pub fn remove_startup_notification(_window: &Window, _startup_id: &str) {}
+}
@@ -0,0 +1,189 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::image::WsiRgbaImage;
+use deno_core::anyhow::{self, bail};
+use serde::Deserialize;
+use winit::window::Window;
+
+// https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nf-shobjidl_core-itaskbarlist3-setprogressstate
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiTaskbarProgressState {
+  None,
+  Indeterminate,
+  Normal,
+  Error,
+  Paused,
+}
+
+// An overlay icon, e.g. decoded from a PNG image.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct WsiTaskbarOverlay {
+  image: WsiRgbaImage,
+  description: String,
+}
+
+impl WsiTaskbarOverlay {
+  #[cfg_attr(not(windows), allow(dead_code))]
+  pub fn new(image: WsiRgbaImage, description: String) -> Self {
+    Self { image, description }
+  }
+
+  pub fn decode(
+    png: &[u8],
+    description: String,
+  ) -> Result<Self, anyhow::Error> {
+    let Some(image) = WsiRgbaImage::decode_png(png) else {
+      bail!("The overlay icon is not a PNG image");
+    };
+    Ok(Self { image, description })
+  }
+}
+
+// Show the progress of a long operation on the window's taskbar button.
+// `value` is between 0 and 1 and only matters for the normal, error and paused
+// states. Only Windows supports this; elsewhere it does nothing.
+pub fn set_progress(
+  window: &Window,
+  state: WsiTaskbarProgressState,
+  value: Option<f64>,
+) {
+  itaskbarlist3::set_progress(window, state, value)
+}
+
+// Badge the window's taskbar button with a small icon, e.g. an unread count, or
+// remove the badge. Only Windows supports this; elsewhere it does nothing.
+pub fn set_overlay(window: &Window, overlay: Option<WsiTaskbarOverlay>) {
+  itaskbarlist3::set_overlay(window, overlay)
+}
+
+#[cfg(windows)]
+mod itaskbarlist3 {
+  use super::{WsiTaskbarOverlay, WsiTaskbarProgressState};
+  use std::{ffi::OsStr, os::windows::ffi::OsStrExt, ptr};
+  use winapi::{
+    shared::{winerror::SUCCEEDED, wtypesbase::CLSCTX_INPROC_SERVER},
+    um::{
+      combaseapi::CoCreateInstance,
+      shobjidl_core::{
+        CLSID_TaskbarList, ITaskbarList3, TBPF_ERROR, TBPF_INDETERMINATE,
+        TBPF_NOPROGRESS, TBPF_NORMAL, TBPF_PAUSED,
+      },
+      winuser::{CreateIcon, DestroyIcon},
+    },
+    Interface,
+  };
+  use winit::{platform::windows::WindowExtWindows, window::Window};
+
+  // Progress values are fractions of this total.
+  const PROGRESS_TOTAL: u64 = 10000;
+
+  // Call `f` with the shell's taskbar. COM is already initialized on the event
+  // loop thread by winit.
+  fn with_taskbar(f: impl FnOnce(&ITaskbarList3)) {
+    let mut taskbar: *mut ITaskbarList3 = ptr::null_mut();
+    // SAFETY: The class and interface IDs match the out pointer's type.
+    unsafe {
+      let result = CoCreateInstance(
+        &CLSID_TaskbarList,
+        ptr::null_mut(),
+        CLSCTX_INPROC_SERVER,
+        &ITaskbarList3::uuidof(),
+        &mut taskbar as *mut *mut ITaskbarList3 as _,
+      );
+      if !SUCCEEDED(result) || taskbar.is_null() {
+        return;
+      }
+      if SUCCEEDED((*taskbar).HrInit()) {
+        f(&*taskbar);
+      }
+      (*taskbar).Release();
+    }
+  }
+
+  pub fn set_progress(
+    window: &Window,
+    state: WsiTaskbarProgressState,
+    value: Option<f64>,
+  ) {
+    let flag = match state {
+      WsiTaskbarProgressState::None => TBPF_NOPROGRESS,
+      WsiTaskbarProgressState::Indeterminate => TBPF_INDETERMINATE,
+      WsiTaskbarProgressState::Normal => TBPF_NORMAL,
+      WsiTaskbarProgressState::Error => TBPF_ERROR,
+      WsiTaskbarProgressState::Paused => TBPF_PAUSED,
+    };
+    let hwnd = window.hwnd() as _;
+    with_taskbar(|taskbar| {
+      // SAFETY: The window handle is valid.
+      unsafe {
+        taskbar.SetProgressState(hwnd, flag);
+        if let Some(value) = value {
+          let completed =
+            (value.clamp(0.0, 1.0) * PROGRESS_TOTAL as f64) as u64;
+          taskbar.SetProgressValue(hwnd, completed, PROGRESS_TOTAL);
+        }
+      }
+    })
+  }
+
+  pub fn set_overlay(window: &Window, overlay: Option<WsiTaskbarOverlay>) {
+    let hwnd = window.hwnd() as _;
+    let Some(overlay) = overlay else {
+      // SAFETY: The window handle is valid and a null icon removes the badge.
+      with_taskbar(|taskbar| unsafe {
+        taskbar.SetOverlayIcon(hwnd, ptr::null_mut(), ptr::null());
+      });
+      return;
+    };
+
+    // Icons take BGRA pixels and an inverted alpha mask, like winit's.
+    let image = overlay.image;
+    let mut pixels = image.rgba;
+    let mut and_mask = Vec::with_capacity(pixels.len() / 4);
+    for pixel in pixels.chunks_exact_mut(4) {
+      pixel.swap(0, 2);
+      and_mask.push(pixel[3].wrapping_sub(u8::MAX));
+    }
+    let description: Vec<u16> = OsStr::new(&overlay.description)
+      .encode_wide()
+      .chain(Some(0))
+      .collect();
+    // SAFETY: The buffers hold `width * height` pixels of the declared depth.
+    let icon = unsafe {
+      CreateIcon(
+        ptr::null_mut(),
+        image.width as i32,
+        image.height as i32,
+        1,
+        32,
+        and_mask.as_ptr(),
+        pixels.as_ptr(),
+      )
+    };
+    if icon.is_null() {
+      return;
+    }
+    // SAFETY: The window handle and icon are valid. The taskbar keeps its own
+    // copy of the icon, so it can be destroyed afterwards.
+    with_taskbar(|taskbar| unsafe {
+      taskbar.SetOverlayIcon(hwnd, icon, description.as_ptr());
+    });
+    unsafe { DestroyIcon(icon) };
+  }
+}
+
+#[cfg(not(windows))]
+mod itaskbarlist3 {
+  use super::{WsiTaskbarOverlay, WsiTaskbarProgressState};
+  use winit::window::Window;
+
+  pub fn set_progress(
+    _window: &Window,
+    _state: WsiTaskbarProgressState,
+    _value: Option<f64>,
+  ) {
+  }
+
+  pub fn set_overlay(_window: &Window, _overlay: Option<WsiTaskbarOverlay>) {}
+}
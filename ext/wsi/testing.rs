@@ -0,0 +1,31 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{event_loop::WsiEventLoopProxy, remove_window};
+
+// Whether real windows can be created, so that `denog test --wsi` can fall
+// back to virtual windows where there's no display server, e.g. on CI.
+pub fn display_available() -> bool {
+  if cfg!(any(target_os = "windows", target_os = "macos")) {
+    return true;
+  }
+  ["WAYLAND_DISPLAY", "WAYLAND_SOCKET", "DISPLAY"]
+    .iter()
+    .any(|var| std::env::var_os(var).map_or(false, |value| !value.is_empty()))
+}
+
+// Destroy the windows that a test module left open. Test modules share the
+// event loop one after another, so this runs before the next one starts.
+pub fn destroy_windows(proxy: &WsiEventLoopProxy) {
+  let wids = proxy
+    .execute(|_, windows| {
+      let wids: Vec<_> = windows.keys().copied().collect();
+      for &wid in &wids {
+        remove_window(windows, wid);
+      }
+      wids
+    })
+    .unwrap_or_default();
+  for wid in wids {
+    proxy.aspect_ratios().unlock(wid);
+  }
+}
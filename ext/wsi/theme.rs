@@ -0,0 +1,221 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::{event::WsiEvent, window::WsiWindowTheme};
+use std::{
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, RecvTimeoutError, Sender},
+    Mutex,
+  },
+  thread,
+  time::Duration,
+};
+use winit::event_loop::EventLoopProxy;
+
+// How often the system theme is checked for changes.
+const THEME_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// Whether the runtime follows the system theme, which starts the first time it
+// asks for it.
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+// The theme that the watcher thread read last.
+static LATEST_THEME: Mutex<Option<WsiWindowTheme>> = Mutex::new(None);
+
+// Asks the watcher thread to read the theme without waiting for the interval.
+static POLL_NOW: Mutex<Option<Sender<()>>> = Mutex::new(None);
+
+// The theme that the user chose for the whole system, or `None` where there's
+// no such setting or it says that apps can choose. On Linux this starts a
+// process and waits for it, so it's never called on the event loop thread.
+pub fn system_theme() -> Option<WsiWindowTheme> {
+  platform::system_theme()
+}
+
+// Start delivering `SystemThemeChanged` events, from `theme` as it was just
+// read. The theme is read on a thread of its own, which wakes the event loop
+// when it changes, since the event loop may not get any other events while
+// there are no windows.
+pub fn watch_system_theme(
+  wake: EventLoopProxy<()>,
+  theme: Option<WsiWindowTheme>,
+) {
+  let mut poll_now = POLL_NOW.lock().unwrap();
+  if poll_now.is_some() {
+    return;
+  }
+  *LATEST_THEME.lock().unwrap() = theme;
+  let (poll_tx, poll_rx) = mpsc::channel();
+  *poll_now = Some(poll_tx);
+  WATCHING.store(true, Ordering::Release);
+  thread::spawn(move || loop {
+    if let Err(RecvTimeoutError::Disconnected) =
+      poll_rx.recv_timeout(THEME_POLL_INTERVAL)
+    {
+      break;
+    }
+    let theme = system_theme();
+    let previous = std::mem::replace(&mut *LATEST_THEME.lock().unwrap(), theme);
+    if previous != theme && wake.send_event(()).is_err() {
+      break;
+    }
+  });
+}
+
+// Winit only reports the theme per window, so the driver compares the theme
+// that the watcher thread read last on every event, and asks it to read the
+// theme at once when a window's theme changes. The theme is first seen without
+// an event.
+#[derive(Default)]
+pub struct SystemThemeWatcher {
+  theme: Option<Option<WsiWindowTheme>>,
+}
+
+impl SystemThemeWatcher {
+  pub fn update(&mut self, event: &WsiEvent) -> Vec<WsiEvent> {
+    if !WATCHING.load(Ordering::Acquire) {
+      return vec![];
+    }
+    if matches!(event, WsiEvent::WindowThemeChanged { .. }) {
+      if let Some(poll_now) = &*POLL_NOW.lock().unwrap() {
+        let _ = poll_now.send(());
+      }
+    }
+    let theme = *LATEST_THEME.lock().unwrap();
+    match self.theme.replace(theme) {
+      Some(previous) if previous != theme => match theme {
+        Some(theme) => vec![WsiEvent::SystemThemeChanged { theme }],
+        None => vec![],
+      },
+      _ => vec![],
+    }
+  }
+}
+
+// Apps follow the "AppsUseLightTheme" value that the Personalization settings
+// write.
+#[cfg(windows)]
+mod platform {
+  use crate::window::WsiWindowTheme;
+  use std::{ffi::OsStr, os::windows::ffi::OsStrExt, ptr};
+  use winapi::{
+    shared::{minwindef::DWORD, winerror::ERROR_SUCCESS},
+    um::winreg::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD},
+  };
+
+  pub fn system_theme() -> Option<WsiWindowTheme> {
+    let key = wide(OsStr::new(
+      "Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize",
+    ));
+    let name = wide(OsStr::new("AppsUseLightTheme"));
+    let mut value: DWORD = 0;
+    let mut size = std::mem::size_of::<DWORD>() as DWORD;
+    // SAFETY: The strings are null-terminated and `value` is a DWORD of the
+    // size given.
+    let result = unsafe {
+      RegGetValueW(
+        HKEY_CURRENT_USER,
+        key.as_ptr(),
+        name.as_ptr(),
+        RRF_RT_REG_DWORD,
+        ptr::null_mut(),
+        (&mut value as *mut DWORD).cast(),
+        &mut size,
+      )
+    };
+    if result != ERROR_SUCCESS as i32 {
+      return None;
+    }
+    Some(match value {
+      0 => WsiWindowTheme::Dark,
+      _ => WsiWindowTheme::Light,
+    })
+  }
+
+  fn wide(string: &OsStr) -> Vec<u16> {
+    string.encode_wide().chain(Some(0)).collect()
+  }
+}
+
+// "AppleInterfaceStyle" is "Dark" in dark mode, including while automatic
+// appearance has switched to it, and unset otherwise.
+#[cfg(target_os = "macos")]
+mod platform {
+  use crate::window::WsiWindowTheme;
+  use objc::{class, msg_send, runtime::Object, sel, sel_impl};
+  use std::ffi::{c_char, CStr};
+
+  pub fn system_theme() -> Option<WsiWindowTheme> {
+    // SAFETY: NSUserDefaults is thread-safe, and the strings are
+    // null-terminated and only read while the autoreleased objects live.
+    unsafe {
+      let defaults: *mut Object =
+        msg_send![class!(NSUserDefaults), standardUserDefaults];
+      let key: *mut Object = msg_send![
+        class!(NSString),
+        stringWithUTF8String: b"AppleInterfaceStyle\0".as_ptr()
+      ];
+      let style: *mut Object = msg_send![defaults, stringForKey: key];
+      if style.is_null() {
+        return Some(WsiWindowTheme::Light);
+      }
+      let utf8: *const c_char = msg_send![style, UTF8String];
+      Some(match CStr::from_ptr(utf8).to_bytes() {
+        b"Dark" => WsiWindowTheme::Dark,
+        _ => WsiWindowTheme::Light,
+      })
+    }
+  }
+}
+
+// Desktops publish the user's preference as the "color-scheme" setting of the
+// XDG desktop portal, which gdbus can read without a D-Bus library: 1 prefers
+// dark, 2 prefers light and 0 has no preference.
+// https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Settings.html
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use crate::window::WsiWindowTheme;
+  use std::process::{Command, Stdio};
+
+  pub fn system_theme() -> Option<WsiWindowTheme> {
+    let output = Command::new("gdbus")
+      .args(["call", "--session"])
+      .args(["--dest", "org.freedesktop.portal.Desktop"])
+      .args(["--object-path", "/org/freedesktop/portal/desktop"])
+      .args(["--method", "org.freedesktop.portal.Settings.Read"])
+      .args(["org.freedesktop.appearance", "color-scheme"])
+      .stderr(Stdio::null())
+      .output()
+      .ok()?;
+    if !output.status.success() {
+      return None;
+    }
+    // The reply looks like "(<<uint32 1>>,)".
+    let output = String::from_utf8_lossy(&output.stdout);
+    let (_, value) = output.split_once("uint32 ")?;
+    match value.chars().next()? {
+      '1' => Some(WsiWindowTheme::Dark),
+      '2' => Some(WsiWindowTheme::Light),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  target_os = "macos",
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use crate::window::WsiWindowTheme;
+
+  pub fn system_theme() -> Option<WsiWindowTheme> {
+    None
+  }
+}
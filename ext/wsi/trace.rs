@@ -0,0 +1,106 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use crate::event::WsiEvent;
+use deno_core::serde_json::{self, json, Value};
+use std::{
+  panic::Location,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+  },
+  time::Instant,
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+// When tracing was first enabled, which trace times are relative to.
+static EPOCH: Mutex<Option<Instant>> = Mutex::new(None);
+
+// Tracing writes a JSON object per line to stderr for every event the event
+// loop delivers and every request it executes, with how long each waited and
+// took, to find out which side of a stalled connection is stuck. Times are in
+// milliseconds.
+pub fn set_trace_enabled(enabled: bool) {
+  if enabled {
+    EPOCH.lock().unwrap().get_or_insert_with(Instant::now);
+  }
+  ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_trace_enabled() -> bool {
+  ENABLED.load(Ordering::Relaxed)
+}
+
+// An event that was delivered by the event loop at `delivered` and is being
+// sent to the runtime at `sent`.
+pub fn trace_event(event: &WsiEvent, delivered: Instant, sent: Instant) {
+  if !is_trace_enabled() {
+    return;
+  }
+  write(json!({
+    "trace": "event",
+    "at": millis_since_epoch(sent),
+    "event": event,
+    "queued": millis(delivered, sent),
+  }));
+}
+
+// The runtime asked for the next event at `handled`, after being sent the
+// last one at `sent`.
+pub fn trace_event_handled(sent: Instant, handled: Instant) {
+  if !is_trace_enabled() {
+    return;
+  }
+  write(json!({
+    "trace": "event-handled",
+    "at": millis_since_epoch(handled),
+    "handled": millis(sent, handled),
+  }));
+}
+
+// A request made by `caller` at `sent`, executed on the event loop from
+// `started` until `finished`.
+pub fn trace_request(
+  caller: &Location,
+  sent: Instant,
+  started: Instant,
+  finished: Instant,
+) {
+  if !is_trace_enabled() {
+    return;
+  }
+  write(json!({
+    "trace": "request",
+    "at": millis_since_epoch(sent),
+    "caller": caller.to_string(),
+    "queued": millis(sent, started),
+    "executed": millis(started, finished),
+  }));
+}
+
+// An event that a headless runtime made up, which never waits in a queue.
+pub fn trace_headless_event(event: Value) {
+  if !is_trace_enabled() {
+    return;
+  }
+  write(json!({
+    "trace": "event",
+    "at": millis_since_epoch(Instant::now()),
+    "event": event,
+    "queued": 0.0,
+  }));
+}
+
+fn write(trace: Value) {
+  if let Ok(trace) = serde_json::to_string(&trace) {
+    eprintln!("{trace}");
+  }
+}
+
+fn millis(from: Instant, to: Instant) -> f64 {
+  to.saturating_duration_since(from).as_secs_f64() * 1000.0
+}
+
+fn millis_since_epoch(instant: Instant) -> f64 {
+  let epoch = *EPOCH.lock().unwrap();
+  epoch.map_or(0.0, |epoch| millis(epoch, instant))
+}
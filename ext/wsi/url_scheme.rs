@@ -0,0 +1,392 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use deno_core::anyhow::{self, bail};
+use std::{
+  env,
+  ffi::OsString,
+  sync::atomic::{AtomicBool, Ordering},
+};
+
+static LAUNCH_URL_TAKEN: AtomicBool = AtomicBool::new(false);
+
+// Schemes that browsers, mail clients and the OS itself handle, which a
+// program mustn't take over for the whole user account.
+const RESERVED_SCHEMES: &[&str] = &[
+  "about",
+  "blob",
+  "callto",
+  "chrome",
+  "data",
+  "deno",
+  "denog",
+  "file",
+  "ftp",
+  "geo",
+  "git",
+  "http",
+  "https",
+  "im",
+  "irc",
+  "ircs",
+  "javascript",
+  "magnet",
+  "mailto",
+  "mms",
+  "news",
+  "nntp",
+  "sftp",
+  "sip",
+  "sips",
+  "skype",
+  "sms",
+  "smb",
+  "snews",
+  "ssh",
+  "tel",
+  "telnet",
+  "urn",
+  "view-source",
+  "webcal",
+  "ws",
+  "wss",
+  "xmpp",
+];
+
+// Windows reserves schemes starting with "ms-" for its own apps and settings.
+const RESERVED_PREFIXES: &[&str] = &["ms-"];
+
+// Schemes are a letter followed by letters, digits, "+", "-" or ".", and
+// compare case-insensitively.
+// https://www.rfc-editor.org/rfc/rfc3986#section-3.1
+pub fn check_scheme(scheme: &str) -> Result<(), anyhow::Error> {
+  let mut chars = scheme.chars();
+  let valid = chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+    && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+  if !valid {
+    bail!("'{scheme}' is not a valid URL scheme");
+  }
+  let lowercase = scheme.to_ascii_lowercase();
+  let reserved = RESERVED_SCHEMES.contains(&lowercase.as_str())
+    || RESERVED_PREFIXES
+      .iter()
+      .any(|prefix| lowercase.starts_with(prefix));
+  if reserved {
+    bail!("The '{scheme}' URL scheme is reserved and can't be registered");
+  }
+  Ok(())
+}
+
+// The URL this process was started to open, if the OS started it for a URL
+// with the scheme. Windows and Linux pass the URL as the last argument of the
+// command registered by `register`. Only the first call gets it, so it's
+// delivered once.
+pub fn take_launch_url(scheme: &str) -> Option<String> {
+  let url = launch_url(scheme)?;
+  (!LAUNCH_URL_TAKEN.swap(true, Ordering::Relaxed)).then_some(url)
+}
+
+fn launch_url(scheme: &str) -> Option<String> {
+  let url = env::args().last()?;
+  let prefix = url.get(..scheme.len() + 1)?;
+  prefix
+    .eq_ignore_ascii_case(&format!("{scheme}:"))
+    .then_some(url)
+}
+
+// The command that starts this program again, without the URL it may have been
+// started for.
+fn launch_command(scheme: &str) -> Vec<OsString> {
+  let mut args: Vec<OsString> = env::args_os().collect();
+  if args.len() > 1 && launch_url(scheme).is_some() {
+    args.pop();
+  }
+  if let Ok(exe) = env::current_exe() {
+    args[0] = exe.into();
+  }
+  args
+}
+
+// Make the OS open URLs with the scheme by starting this program with the URL
+// as its last argument. On macOS, URL schemes are declared by the app bundle
+// instead, so this does nothing there.
+pub fn register(scheme: &str) -> Result<(), anyhow::Error> {
+  platform::register(scheme, launch_command(scheme))
+}
+
+// HKEY_CURRENT_USER\Software\Classes\<scheme> makes the scheme a URL protocol
+// for the current user.
+// https://learn.microsoft.com/en-us/previous-versions/windows/internet-explorer/ie-developer/platform-apis/aa767914(v=vs.85)
+#[cfg(windows)]
+mod platform {
+  use deno_core::anyhow::{self, bail};
+  use std::{
+    ffi::{OsStr, OsString},
+    iter,
+    os::windows::ffi::OsStrExt,
+    ptr,
+  };
+  use winapi::{
+    shared::{minwindef::HKEY, winerror::ERROR_SUCCESS},
+    um::{
+      winnt::{KEY_WRITE, REG_SZ},
+      winreg::{
+        RegCloseKey, RegCreateKeyExW, RegSetValueExW, HKEY_CURRENT_USER,
+      },
+    },
+  };
+
+  pub fn register(
+    scheme: &str,
+    command: Vec<OsString>,
+  ) -> Result<(), anyhow::Error> {
+    let mut command_line = OsString::new();
+    for arg in command {
+      command_line.push(quote(&arg));
+      command_line.push(" ");
+    }
+    command_line.push("\"%1\"");
+
+    let key = format!(r"Software\Classes\{scheme}");
+    set_value(&key, None, OsStr::new(&format!("URL:{scheme}")))?;
+    set_value(&key, Some("URL Protocol"), OsStr::new(""))?;
+    set_value(&format!(r"{key}\shell\open\command"), None, &command_line)
+  }
+
+  fn set_value(
+    key: &str,
+    name: Option<&str>,
+    value: &OsStr,
+  ) -> Result<(), anyhow::Error> {
+    let key = wide(OsStr::new(key));
+    let name = name.map(|name| wide(OsStr::new(name)));
+    let value = wide(value);
+    let mut hkey: HKEY = ptr::null_mut();
+    // SAFETY: The strings are NUL-terminated and the key is closed after use.
+    unsafe {
+      let result = RegCreateKeyExW(
+        HKEY_CURRENT_USER,
+        key.as_ptr(),
+        0,
+        ptr::null_mut(),
+        0,
+        KEY_WRITE,
+        ptr::null_mut(),
+        &mut hkey,
+        ptr::null_mut(),
+      );
+      if result != ERROR_SUCCESS as i32 {
+        bail!("Failed to register the URL scheme (error {result})");
+      }
+      let result = RegSetValueExW(
+        hkey,
+        name.as_ref().map_or(ptr::null(), |name| name.as_ptr()),
+        0,
+        REG_SZ,
+        value.as_ptr().cast(),
+        (value.len() * 2) as u32,
+      );
+      RegCloseKey(hkey);
+      if result != ERROR_SUCCESS as i32 {
+        bail!("Failed to register the URL scheme (error {result})");
+      }
+    }
+    Ok(())
+  }
+
+  fn wide(string: &OsStr) -> Vec<u16> {
+    string.encode_wide().chain(Some(0)).collect()
+  }
+
+  // Quote an argument the way the C runtime splits command lines.
+  fn quote(arg: &OsStr) -> OsString {
+    let arg = arg.to_string_lossy();
+    let mut quoted = String::from("\"");
+    // Backslashes are only special before a quote.
+    let mut backslashes = 0;
+    for c in arg.chars() {
+      if c == '\\' {
+        backslashes += 1;
+        continue;
+      }
+      let escaped = if c == '"' {
+        backslashes * 2 + 1
+      } else {
+        backslashes
+      };
+      quoted.extend(iter::repeat('\\').take(escaped));
+      quoted.push(c);
+      backslashes = 0;
+    }
+    quoted.extend(iter::repeat('\\').take(backslashes * 2));
+    quoted.push('"');
+    quoted.into()
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_spaces_and_quotes() {
+      let quoted = |arg: &str| quote(OsStr::new(arg));
+      assert_eq!(
+        quoted(r"C:\Program Files\denog.exe"),
+        r#""C:\Program Files\denog.exe""#
+      );
+      assert_eq!(quoted(r#"say "hi""#), r#""say \"hi\"""#);
+      assert_eq!(quoted(r#"a\"b"#), r#""a\\\"b""#);
+      assert_eq!(quoted(r"dir\"), r#""dir\\""#);
+    }
+  }
+}
+
+// A desktop entry that handles x-scheme-handler/<scheme>, made the default
+// handler with xdg-mime.
+// https://specifications.freedesktop.org/desktop-entry-spec/desktop-entry-spec-latest.html
+#[cfg(all(
+  unix,
+  not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+))]
+mod platform {
+  use deno_core::anyhow::{self, bail, Context};
+  use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::PathBuf,
+    process::{Command, Stdio},
+  };
+
+  pub fn register(
+    scheme: &str,
+    command: Vec<OsString>,
+  ) -> Result<(), anyhow::Error> {
+    let mut exec: Vec<String> = command
+      .iter()
+      .map(|arg| quote(&arg.to_string_lossy()))
+      .collect();
+    exec.push("%u".to_string());
+    let desktop_file = format!("denog-url-{scheme}.desktop");
+    let entry = format!(
+      "[Desktop Entry]\n\
+       Type=Application\n\
+       Name={scheme} URL handler\n\
+       Exec={}\n\
+       MimeType=x-scheme-handler/{scheme};\n\
+       NoDisplay=true\n",
+      exec.join(" ")
+    );
+
+    let applications = data_home()?.join("applications");
+    fs::create_dir_all(&applications)
+      .context("Failed to register the URL scheme")?;
+    fs::write(applications.join(&desktop_file), entry)
+      .context("Failed to register the URL scheme")?;
+
+    let status = Command::new("xdg-mime")
+      .args(["default", &desktop_file])
+      .arg(format!("x-scheme-handler/{scheme}"))
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .status()
+      .context("Failed to run xdg-mime to register the URL scheme")?;
+    if !status.success() {
+      bail!("xdg-mime failed to register the URL scheme");
+    }
+    Ok(())
+  }
+
+  fn data_home() -> Result<PathBuf, anyhow::Error> {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+      return Ok(dir.into());
+    }
+    match env::var_os("HOME") {
+      Some(home) => Ok(PathBuf::from(home).join(".local/share")),
+      None => bail!("Failed to register the URL scheme: HOME is not set"),
+    }
+  }
+
+  // Quote an argument for the Exec key, where "%" starts a field code. Values
+  // are unescaped once before they're unquoted, so escaping backslashes are
+  // doubled.
+  fn quote(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+      match c {
+        '"' | '`' | '$' => {
+          quoted.push_str("\\\\");
+          quoted.push(c);
+        }
+        '\\' => quoted.push_str("\\\\\\\\"),
+        '%' => quoted.push_str("%%"),
+        _ => quoted.push(c),
+      }
+    }
+    quoted.push('"');
+    quoted
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_spaces_and_quotes() {
+      assert_eq!(quote("/opt/my app/denog"), r#""/opt/my app/denog""#);
+      assert_eq!(quote(r#"say "hi""#), r#""say \\"hi\\"""#);
+      assert_eq!(quote("$HOME`id`"), r#""\\$HOME\\`id\\`""#);
+      assert_eq!(quote(r"a\b"), r#""a\\\\b""#);
+      assert_eq!(quote("100%"), r#""100%%""#);
+    }
+  }
+}
+
+#[cfg(not(any(
+  windows,
+  all(
+    unix,
+    not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+  )
+)))]
+mod platform {
+  use deno_core::anyhow;
+  use std::ffi::OsString;
+
+  pub fn register(
+    _scheme: &str,
+    _command: Vec<OsString>,
+  ) -> Result<(), anyhow::Error> {
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn check_scheme_valid() {
+    assert!(check_scheme("denog-app").is_ok());
+    assert!(check_scheme("com.example.app+v2").is_ok());
+    assert!(check_scheme("MyApp").is_ok());
+  }
+
+  #[test]
+  fn check_scheme_invalid() {
+    assert!(check_scheme("").is_err());
+    assert!(check_scheme("1app").is_err());
+    assert!(check_scheme("my app").is_err());
+    assert!(check_scheme("my_app").is_err());
+    assert!(check_scheme("my:app").is_err());
+    assert!(check_scheme("appé").is_err());
+  }
+
+  #[test]
+  fn check_scheme_reserved() {
+    assert!(check_scheme("http").is_err());
+    assert!(check_scheme("HTTP").is_err());
+    assert!(check_scheme("MailTo").is_err());
+    assert!(check_scheme("ms-settings").is_err());
+    assert!(check_scheme("MS-Settings").is_err());
+  }
+}
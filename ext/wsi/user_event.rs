@@ -0,0 +1,78 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+use deno_core::{anyhow, serde_json};
+use serde::Serialize;
+use std::{
+  collections::VecDeque,
+  marker::PhantomData,
+  sync::{Arc, Mutex},
+};
+use winit::event_loop::EventLoopProxy;
+
+// User events that other extensions have sent but JS hasn't received yet,
+// shared between the proxy thread and any senders.
+pub(crate) struct WsiUserEventQueue {
+  events: Mutex<VecDeque<(Arc<str>, serde_json::Value)>>,
+  event_loop_proxy: Mutex<EventLoopProxy<()>>,
+}
+
+impl WsiUserEventQueue {
+  pub(crate) fn new(event_loop_proxy: EventLoopProxy<()>) -> Self {
+    Self {
+      events: Mutex::new(VecDeque::new()),
+      event_loop_proxy: Mutex::new(event_loop_proxy),
+    }
+  }
+
+  pub(crate) fn pop(&self) -> Option<(Arc<str>, serde_json::Value)> {
+    self.events.lock().unwrap().pop_front()
+  }
+
+  fn push(&self, kind: Arc<str>, payload: serde_json::Value) {
+    self.events.lock().unwrap().push_back((kind, payload));
+
+    // Wake the real event loop so that a pending Deno.wsi.nextEvent() call
+    // checks the queue again. It fails only after the event loop has exited.
+    let _ = self.event_loop_proxy.lock().unwrap().send_event(());
+  }
+}
+
+// Sends user events of one registered kind into the WSI event stream, where
+// JS receives them as `{ type: "user-event", kind, payload }`. It can be
+// cloned and used from any thread, e.g. by a native subsystem that runs
+// alongside the event loop.
+pub struct WsiUserEventSender<T> {
+  kind: Arc<str>,
+  queue: Arc<WsiUserEventQueue>,
+  payload_type: PhantomData<fn(&T)>,
+}
+
+impl<T> WsiUserEventSender<T> {
+  pub(crate) fn new(kind: Arc<str>, queue: Arc<WsiUserEventQueue>) -> Self {
+    Self {
+      kind,
+      queue,
+      payload_type: PhantomData,
+    }
+  }
+
+  pub fn kind(&self) -> &str {
+    &self.kind
+  }
+}
+
+impl<T: Serialize> WsiUserEventSender<T> {
+  // Queue an event with the given payload. The payload is serialized right
+  // away, so it doesn't need to outlive this call.
+  pub fn send(&self, payload: &T) -> Result<(), anyhow::Error> {
+    let payload = serde_json::to_value(payload)?;
+    self.queue.push(self.kind.clone(), payload);
+    Ok(())
+  }
+}
+
+impl<T> Clone for WsiUserEventSender<T> {
+  fn clone(&self) -> Self {
+    Self::new(self.kind.clone(), self.queue.clone())
+  }
+}
@@ -1,14 +1,89 @@
 // Copyright 2023 Jo Bates. All rights reserved. MIT license.
 
+use crate::monitor::{work_area, WsiMonitor};
+use deno_core::serde_json;
 use serde::{self, Deserialize, Serialize};
 use winit::{
-  dpi::{PhysicalPosition, PhysicalSize},
+  dpi::{
+    LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, Position,
+    Size,
+  },
   window::{
-    Fullscreen, ImePurpose, ResizeDirection, Theme, UserAttentionType,
+    Fullscreen, ImePurpose, ResizeDirection, Theme, UserAttentionType, Window,
     WindowBuilder, WindowButtons, WindowLevel,
   },
 };
 
+// Whether a window's geometry ops use physical pixels or logical pixels, which
+// are physical pixels divided by the window's scale factor.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiCoordinateSpace {
+  #[default]
+  Physical,
+  Logical,
+}
+
+impl WsiCoordinateSpace {
+  pub fn position(self, (x, y): (i32, i32)) -> Position {
+    match self {
+      Self::Physical => PhysicalPosition::new(x, y).into(),
+      Self::Logical => LogicalPosition::new(x, y).into(),
+    }
+  }
+
+  pub fn size(self, (width, height): (u32, u32)) -> Size {
+    match self {
+      Self::Physical => PhysicalSize::new(width, height).into(),
+      Self::Logical => LogicalSize::new(width, height).into(),
+    }
+  }
+
+  pub fn to_position(
+    self,
+    position: PhysicalPosition<i32>,
+    scale_factor: f64,
+  ) -> (f64, f64) {
+    match self {
+      Self::Physical => position.into(),
+      Self::Logical => position.to_logical::<f64>(scale_factor).into(),
+    }
+  }
+
+  pub fn to_size(
+    self,
+    size: PhysicalSize<u32>,
+    scale_factor: f64,
+  ) -> (f64, f64) {
+    match self {
+      Self::Physical => size.into(),
+      Self::Logical => size.to_logical::<f64>(scale_factor).into(),
+    }
+  }
+
+  pub fn to_rect(
+    self,
+    (x, y, width, height): (i32, i32, u32, u32),
+    scale_factor: f64,
+  ) -> (f64, f64, f64, f64) {
+    let (x, y) = self.to_position(PhysicalPosition { x, y }, scale_factor);
+    let (width, height) =
+      self.to_size(PhysicalSize { width, height }, scale_factor);
+    (x, y, width, height)
+  }
+
+  pub fn to_insets(
+    self,
+    (top, right, bottom, left): (u32, u32, u32, u32),
+    scale_factor: f64,
+  ) -> (f64, f64, f64, f64) {
+    let (left, top) = self.to_size(PhysicalSize::new(left, top), scale_factor);
+    let (right, bottom) =
+      self.to_size(PhysicalSize::new(right, bottom), scale_factor);
+    (top, right, bottom, left)
+  }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum WsiImePurpose {
@@ -71,12 +146,99 @@ impl From<WsiUserAttentionType> for UserAttentionType {
   }
 }
 
+// Which title bar buttons are enabled. JS passes them by name, where buttons
+// that are left out are enabled, or as a bitfield of `WSIWindowButton`
+// constants, which is still accepted for compatibility.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(from = "WsiWindowButtonsInit")]
+pub struct WsiWindowButtons {
+  pub close: bool,
+  pub minimize: bool,
+  pub maximize: bool,
+}
+
 #[derive(Deserialize)]
+#[serde(untagged)]
+enum WsiWindowButtonsInit {
+  Bits(u32),
+  Named {
+    close: Option<bool>,
+    minimize: Option<bool>,
+    maximize: Option<bool>,
+  },
+}
+
+impl From<WsiWindowButtonsInit> for WsiWindowButtons {
+  fn from(init: WsiWindowButtonsInit) -> Self {
+    match init {
+      WsiWindowButtonsInit::Bits(bits) => {
+        WindowButtons::from_bits_truncate(bits).into()
+      }
+      WsiWindowButtonsInit::Named {
+        close,
+        minimize,
+        maximize,
+      } => Self {
+        close: close.unwrap_or(true),
+        minimize: minimize.unwrap_or(true),
+        maximize: maximize.unwrap_or(true),
+      },
+    }
+  }
+}
+
+impl From<WsiWindowButtons> for WindowButtons {
+  fn from(buttons: WsiWindowButtons) -> Self {
+    let mut result = Self::empty();
+    result.set(Self::CLOSE, buttons.close);
+    result.set(Self::MINIMIZE, buttons.minimize);
+    result.set(Self::MAXIMIZE, buttons.maximize);
+    result
+  }
+}
+
+impl From<WindowButtons> for WsiWindowButtons {
+  fn from(buttons: WindowButtons) -> Self {
+    Self {
+      close: buttons.contains(WindowButtons::CLOSE),
+      minimize: buttons.contains(WindowButtons::MINIMIZE),
+      maximize: buttons.contains(WindowButtons::MAXIMIZE),
+    }
+  }
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/dwmapi/ne-dwmapi-dwm_window_corner_preference
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WsiWindowCorners {
+  Default,
+  Round,
+  RoundSmall,
+  Square,
+}
+
+// The title bar's colors as `[r, g, b]`, where `None` is the system's default.
+// `dark_mode` asks for the dark title bar without choosing its colors, and
+// `None` leaves that to the window's theme.
+// https://learn.microsoft.com/en-us/windows/win32/api/dwmapi/ne-dwmapi-dwmwindowattribute
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiTitleBarStyle {
+  pub dark_mode: Option<bool>,
+  pub caption_color: Option<(u8, u8, u8)>,
+  pub border_color: Option<(u8, u8, u8)>,
+  pub text_color: Option<(u8, u8, u8)>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum WsiWindowLevel {
   AlwaysOnBottom,
   Normal,
   AlwaysOnTop,
+  // Above other apps' fullscreen windows too, where the window system allows
+  // it. See `set_window_level`.
+  AlwaysOnTopOfFullscreen,
 }
 
 impl From<WsiWindowLevel> for WindowLevel {
@@ -85,11 +247,12 @@ impl From<WsiWindowLevel> for WindowLevel {
       WsiWindowLevel::AlwaysOnBottom => Self::AlwaysOnBottom,
       WsiWindowLevel::Normal => Self::Normal,
       WsiWindowLevel::AlwaysOnTop => Self::AlwaysOnTop,
+      WsiWindowLevel::AlwaysOnTopOfFullscreen => Self::AlwaysOnTop,
     }
   }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum WsiWindowTheme {
   Light,
@@ -114,13 +277,29 @@ impl From<Theme> for WsiWindowTheme {
   }
 }
 
+// The state of a live window that `Deno.wsi.windows()` reports.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WsiWindowInfo {
+  pub window: u64,
+  pub title: String,
+  pub focused: bool,
+  // Winit can't tell whether a window is visible on Wayland.
+  pub visible: Option<bool>,
+  pub monitor: Option<WsiMonitor>,
+  pub tag: Option<serde_json::Value>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WsiCreateWindowOptions {
   pub active: Option<bool>,
+  pub app_id: Option<String>,
   pub content_protected: Option<bool>,
+  pub coordinate_space: Option<WsiCoordinateSpace>,
+  pub corners: Option<WsiWindowCorners>,
   pub decorated: Option<bool>,
-  pub enabled_buttons: Option<u32>,
+  pub enabled_buttons: Option<WsiWindowButtons>,
   pub fullscreen: Option<bool>,
   pub position: Option<(i32, i32)>,
   pub inner_size: Option<(u32, u32)>,
@@ -128,10 +307,19 @@ pub struct WsiCreateWindowOptions {
   pub max_inner_size: Option<(u32, u32)>,
   pub level: Option<WsiWindowLevel>,
   pub maximized: Option<bool>,
+  // Windows with a name save their geometry when they're closed, and start
+  // where they were when they're created with the same name again.
+  pub name: Option<String>,
+  pub parent: Option<u64>,
   pub resizable: Option<bool>,
   pub resize_increments: Option<(u32, u32)>,
+  pub shadow: Option<bool>,
+  pub skip_taskbar: Option<bool>,
+  // Kept by the op state rather than the window, see `WsiWindowTags`.
+  pub tag: Option<serde_json::Value>,
   pub theme: Option<WsiWindowTheme>,
   pub title: Option<String>,
+  pub title_bar_style: Option<WsiTitleBarStyle>,
   pub transparent: Option<bool>,
   pub visible: Option<bool>,
 }
@@ -144,30 +332,42 @@ impl WsiCreateWindowOptions {
     if let Some(active) = self.active {
       builder = builder.with_active(active);
     }
+    // Wayland's app_id and X11's WM_CLASS match windows to the program's
+    // .desktop file. Other window systems identify the program by itself.
+    #[cfg(all(
+      unix,
+      not(any(target_os = "android", target_os = "ios", target_os = "macos"))
+    ))]
+    {
+      use winit::platform::x11::WindowBuilderExtX11;
+      if let Some(app_id) = &self.app_id {
+        builder = builder.with_name(app_id, app_id);
+      }
+    }
     if let Some(content_protected) = self.content_protected {
       builder = builder.with_content_protected(content_protected);
     }
     if let Some(decorated) = self.decorated {
       builder = builder.with_decorations(decorated);
     }
-    if let Some(bits) = self.enabled_buttons {
-      let buttons = WindowButtons::from_bits_truncate(bits);
-      builder = builder.with_enabled_buttons(buttons);
+    if let Some(buttons) = self.enabled_buttons {
+      builder = builder.with_enabled_buttons(buttons.into());
     }
     if let Some(true) = self.fullscreen {
       builder = builder.with_fullscreen(Some(Fullscreen::Borderless(None)));
     }
-    if let Some((x, y)) = self.position {
-      builder = builder.with_position(PhysicalPosition { x, y });
+    let space = self.coordinate_space.unwrap_or_default();
+    if let Some(position) = self.position {
+      builder = builder.with_position(space.position(position));
     }
-    if let Some((width, height)) = self.inner_size {
-      builder = builder.with_inner_size(PhysicalSize { width, height });
+    if let Some(size) = self.inner_size {
+      builder = builder.with_inner_size(space.size(size));
     }
-    if let Some((width, height)) = self.min_inner_size {
-      builder = builder.with_min_inner_size(PhysicalSize { width, height });
+    if let Some(size) = self.min_inner_size {
+      builder = builder.with_min_inner_size(space.size(size));
     }
-    if let Some((width, height)) = self.max_inner_size {
-      builder = builder.with_max_inner_size(PhysicalSize { width, height });
+    if let Some(size) = self.max_inner_size {
+      builder = builder.with_max_inner_size(space.size(size));
     }
     if let Some(level) = self.level {
       builder = builder.with_window_level(level.into());
@@ -178,8 +378,20 @@ impl WsiCreateWindowOptions {
     if let Some(resizable) = self.resizable {
       builder = builder.with_resizable(resizable);
     }
-    if let Some((width, height)) = self.resize_increments {
-      builder = builder.with_resize_increments(PhysicalSize { width, height });
+    if let Some(size) = self.resize_increments {
+      builder = builder.with_resize_increments(space.size(size));
+    }
+    // Only Windows draws shadows around undecorated windows on request and
+    // lets a single window opt out of the taskbar.
+    #[cfg(windows)]
+    {
+      use winit::platform::windows::WindowBuilderExtWindows;
+      if let Some(shadow) = self.shadow {
+        builder = builder.with_undecorated_shadow(shadow);
+      }
+      if let Some(skip_taskbar) = self.skip_taskbar {
+        builder = builder.with_skip_taskbar(skip_taskbar);
+      }
     }
     if let Some(theme) = self.theme {
       builder = builder.with_theme(Some(theme.into()));
@@ -196,3 +408,200 @@ impl WsiCreateWindowOptions {
     builder
   }
 }
+
+// Winit's always-on-top level already stays above borderless fullscreen
+// windows on Windows, X11 and Wayland, but not on macOS, where fullscreen apps
+// get a space of their own. There, windows above fullscreen ones join every
+// space as auxiliary windows at the screen saver level. Nothing can be drawn
+// over exclusive fullscreen games on Windows.
+// https://developer.apple.com/documentation/appkit/nswindow/collectionbehavior
+pub fn set_window_level(window: &Window, level: WsiWindowLevel) {
+  window.set_window_level(level.into());
+  #[cfg(target_os = "macos")]
+  {
+    use objc::{msg_send, runtime::Object, sel, sel_impl};
+    use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+
+    const NS_SCREEN_SAVER_WINDOW_LEVEL: isize = 1000;
+    const NS_WINDOW_COLLECTION_BEHAVIOR_CAN_JOIN_ALL_SPACES: usize = 1 << 0;
+    const NS_WINDOW_COLLECTION_BEHAVIOR_FULL_SCREEN_AUXILIARY: usize = 1 << 8;
+    let RawWindowHandle::AppKit(handle) = window.raw_window_handle() else {
+      return;
+    };
+    let ns_window: *mut Object = handle.ns_window.cast();
+    let over_fullscreen =
+      matches!(level, WsiWindowLevel::AlwaysOnTopOfFullscreen);
+    let behaviors = NS_WINDOW_COLLECTION_BEHAVIOR_CAN_JOIN_ALL_SPACES
+      | NS_WINDOW_COLLECTION_BEHAVIOR_FULL_SCREEN_AUXILIARY;
+    // SAFETY: The window comes from winit and is valid while it exists, and
+    // this runs on the main thread.
+    unsafe {
+      let mut behavior: usize = msg_send![ns_window, collectionBehavior];
+      if over_fullscreen {
+        behavior |= behaviors;
+      } else {
+        behavior &= !behaviors;
+      }
+      let () = msg_send![ns_window, setCollectionBehavior: behavior];
+      if over_fullscreen {
+        let () = msg_send![ns_window, setLevel: NS_SCREEN_SAVER_WINDOW_LEVEL];
+      }
+    }
+  }
+}
+
+// Only Windows draws a drop shadow around undecorated windows on request, so
+// this does nothing elsewhere.
+#[allow(unused_variables)]
+pub fn set_undecorated_shadow(window: &Window, shadow: bool) {
+  #[cfg(windows)]
+  {
+    use winit::platform::windows::WindowExtWindows;
+    window.set_undecorated_shadow(shadow);
+  }
+}
+
+// Only Windows 11 lets a window choose how its corners are rounded, so this
+// does nothing elsewhere.
+#[allow(unused_variables)]
+pub fn set_corner_preference(window: &Window, corners: WsiWindowCorners) {
+  #[cfg(windows)]
+  {
+    use winapi::um::dwmapi::DwmSetWindowAttribute;
+    use winit::platform::windows::WindowExtWindows;
+
+    // Not defined by winapi, which predates Windows 11.
+    const DWMWA_WINDOW_CORNER_PREFERENCE: u32 = 33;
+    let preference: u32 = match corners {
+      WsiWindowCorners::Default => 0,
+      WsiWindowCorners::Square => 1,
+      WsiWindowCorners::Round => 2,
+      WsiWindowCorners::RoundSmall => 3,
+    };
+    // SAFETY: The window handle is valid and the attribute is a DWORD. Older
+    // versions of Windows reject the attribute, which is fine to ignore.
+    unsafe {
+      DwmSetWindowAttribute(
+        window.hwnd() as _,
+        DWMWA_WINDOW_CORNER_PREFERENCE,
+        &preference as *const u32 as _,
+        std::mem::size_of::<u32>() as u32,
+      );
+    }
+  }
+}
+
+// Only Windows lets apps style the native title bar, so this does nothing
+// elsewhere. Dark mode is taken from Windows 10 20H1 on, under the attribute
+// it had before then if need be, and the colors from Windows 11 on.
+#[allow(unused_variables)]
+pub fn set_title_bar_style(window: &Window, style: WsiTitleBarStyle) {
+  #[cfg(windows)]
+  {
+    use winapi::{
+      shared::{minwindef::BOOL, winerror::S_OK},
+      um::dwmapi::DwmSetWindowAttribute,
+    };
+    use winit::platform::windows::WindowExtWindows;
+
+    // Not defined by winapi, which predates these attributes.
+    const DWMWA_USE_IMMERSIVE_DARK_MODE_BEFORE_20H1: u32 = 19;
+    const DWMWA_USE_IMMERSIVE_DARK_MODE: u32 = 20;
+    const DWMWA_BORDER_COLOR: u32 = 34;
+    const DWMWA_CAPTION_COLOR: u32 = 35;
+    const DWMWA_TEXT_COLOR: u32 = 36;
+    const DWMWA_COLOR_DEFAULT: u32 = 0xFFFFFFFF;
+
+    let hwnd = window.hwnd() as _;
+    // SAFETY: The window handle is valid and every attribute is a 32-bit BOOL
+    // or COLORREF. Older versions of Windows reject attributes they don't
+    // have, which is fine to ignore.
+    let set = |attribute: u32, value: *const u32| unsafe {
+      DwmSetWindowAttribute(
+        hwnd,
+        attribute,
+        value as _,
+        std::mem::size_of::<u32>() as u32,
+      )
+    };
+    if let Some(dark_mode) = style.dark_mode {
+      let dark_mode = dark_mode as BOOL;
+      let value = &dark_mode as *const BOOL as *const u32;
+      if set(DWMWA_USE_IMMERSIVE_DARK_MODE, value) != S_OK {
+        set(DWMWA_USE_IMMERSIVE_DARK_MODE_BEFORE_20H1, value);
+      }
+    }
+    // A COLORREF is 0x00BBGGRR.
+    let colorref = |color: Option<(u8, u8, u8)>| {
+      color.map_or(DWMWA_COLOR_DEFAULT, |(r, g, b)| {
+        r as u32 | (g as u32) << 8 | (b as u32) << 16
+      })
+    };
+    for (attribute, color) in [
+      (DWMWA_CAPTION_COLOR, style.caption_color),
+      (DWMWA_BORDER_COLOR, style.border_color),
+      (DWMWA_TEXT_COLOR, style.text_color),
+    ] {
+      set(attribute, &colorref(color));
+    }
+  }
+}
+
+// Only Windows lets a single window opt out of the taskbar, so this does nothing
+// elsewhere.
+#[allow(unused_variables)]
+pub fn set_skip_taskbar(window: &Window, skip: bool) {
+  #[cfg(windows)]
+  {
+    use winit::platform::windows::WindowExtWindows;
+    window.set_skip_taskbar(skip);
+  }
+}
+
+// Centers the window's frame in the work area of its current monitor, or the
+// primary monitor if the current one is unknown. Does nothing where neither can
+// be determined.
+pub fn center(window: &Window) {
+  let Some(monitor) = window
+    .current_monitor()
+    .or_else(|| window.primary_monitor())
+  else {
+    return;
+  };
+  let (left, top, width, height) = work_area(&monitor);
+  let size = window.outer_size();
+  let x = left + (width as i32 - size.width as i32) / 2;
+  let y = top + (height as i32 - size.height as i32) / 2;
+  window.set_outer_position(PhysicalPosition { x, y });
+}
+
+// Winit only reports a boolean occlusion state, so approximate the visible part
+// of the window's client area by clipping it against its current monitor. The
+// returned rect is relative to the client area. Returns `None` where the window
+// position or monitor can't be determined (e.g. on Wayland).
+pub fn visible_rect(window: &Window) -> Option<(i32, i32, u32, u32)> {
+  if window.is_minimized() == Some(true) || window.is_visible() == Some(false) {
+    return Some((0, 0, 0, 0));
+  }
+  let position = window.inner_position().ok()?;
+  let size = window.inner_size();
+  let monitor = window.current_monitor()?;
+  let monitor_position = monitor.position();
+  let monitor_size = monitor.size();
+
+  let left = position.x.max(monitor_position.x);
+  let top = position.y.max(monitor_position.y);
+  let right = (position.x + size.width as i32)
+    .min(monitor_position.x + monitor_size.width as i32);
+  let bottom = (position.y + size.height as i32)
+    .min(monitor_position.y + monitor_size.height as i32);
+  if right <= left || bottom <= top {
+    return Some((0, 0, 0, 0));
+  }
+  Some((
+    left - position.x,
+    top - position.y,
+    (right - left) as u32,
+    (bottom - top) as u32,
+  ))
+}
@@ -201,6 +201,57 @@ mod startup_snapshot {
     }
   }
 
+  impl deno_webgpu::WebGpuPermissions for Permissions {
+    fn check_write(
+      &mut self,
+      _path: &Path,
+      _api_name: &str,
+    ) -> Result<(), AnyError> {
+      unreachable!("snapshotting!")
+    }
+  }
+
+  impl deno_wsi::WsiPermissions for Permissions {
+    fn check_read(
+      &mut self,
+      _path: &Path,
+      _api_name: &str,
+    ) -> Result<(), AnyError> {
+      unreachable!("snapshotting!")
+    }
+
+    fn check_write_all(&mut self, _api_name: &str) -> Result<(), AnyError> {
+      unreachable!("snapshotting!")
+    }
+
+    fn check_run_all(&mut self, _api_name: &str) -> Result<(), AnyError> {
+      unreachable!("snapshotting!")
+    }
+
+    fn check_wsi(&mut self, _api_name: &str) -> Result<(), AnyError> {
+      unreachable!("snapshotting!")
+    }
+
+    fn check_wsi_devices(&mut self, _api_name: &str) -> Result<(), AnyError> {
+      unreachable!("snapshotting!")
+    }
+
+    fn allows_wsi_devices(&self) -> bool {
+      unreachable!("snapshotting!")
+    }
+
+    fn check_wsi_global_input(
+      &mut self,
+      _api_name: &str,
+    ) -> Result<(), AnyError> {
+      unreachable!("snapshotting!")
+    }
+
+    fn allows_wsi_global_input(&self) -> bool {
+      unreachable!("snapshotting!")
+    }
+  }
+
   fn create_runtime_snapshot(
     snapshot_path: PathBuf,
     maybe_additional_extension: Option<Extension>,
@@ -270,7 +321,7 @@ mod startup_snapshot {
       ),
       deno_webstorage::init_ops_and_esm(None),
       deno_crypto::init_ops_and_esm(None),
-      deno_webgpu::init_ops_and_esm(false),
+      deno_webgpu::init_ops_and_esm::<Permissions>(false, None),
       deno_broadcast_channel::init_ops_and_esm(
         deno_broadcast_channel::InMemoryBroadcastChannel::default(),
         false, // No --unstable.
@@ -285,7 +336,7 @@ mod startup_snapshot {
       deno_io::init_ops_and_esm(Default::default()),
       deno_fs::init_ops_and_esm::<Permissions>(false),
       deno_flash::init_ops_and_esm::<Permissions>(false), // No --unstable
-      deno_wsi::init_ops_and_esm(None),
+      deno_wsi::init_ops_and_esm::<Permissions>(None, Default::default()),
       runtime_extension,
       // FIXME(bartlomieju): these extensions are specified last, because they
       // depend on `runtime`, even though it should be other way around
@@ -0,0 +1,12 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// Runs inside a host application that owns the event loop.
+const window = Deno.wsi.createWindow();
+window.setTitle("Embedded in a host application");
+
+while (true) {
+  const event = await Deno.wsi.nextEvent();
+  if (event.type === "close-requested" && event.window === window) {
+    break;
+  }
+}
@@ -0,0 +1,130 @@
+// Copyright 2023 Jo Bates. All rights reserved. MIT license.
+
+// A host application that owns its winit event loop and runs a JS module
+// that creates windows through it.
+
+use deno_core::anyhow::Context;
+use deno_core::error::AnyError;
+use deno_core::FsModuleLoader;
+use denog_runtime::deno_broadcast_channel::InMemoryBroadcastChannel;
+use denog_runtime::deno_web::BlobStore;
+use denog_runtime::deno_wsi::event_loop;
+use denog_runtime::deno_wsi::event_loop::WsiEventLoopProxy;
+use denog_runtime::deno_wsi::winit::event_loop::EventLoop;
+use denog_runtime::permissions::PermissionsContainer;
+use denog_runtime::worker::MainWorker;
+use denog_runtime::worker::WorkerOptions;
+use denog_runtime::BootstrapOptions;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+
+fn get_error_class_name(e: &AnyError) -> &'static str {
+  denog_runtime::errors::get_error_class_name(e).unwrap_or("Error")
+}
+
+async fn run_js(
+  wsi_event_loop_proxy: Rc<WsiEventLoopProxy>,
+) -> Result<(), AnyError> {
+  let module_loader = Rc::new(FsModuleLoader);
+  let create_web_worker_cb = Arc::new(|_| {
+    todo!("Web workers are not supported in the example");
+  });
+  let web_worker_event_cb = Arc::new(|_| {
+    todo!("Web workers are not supported in the example");
+  });
+
+  let options = WorkerOptions {
+    bootstrap: BootstrapOptions {
+      args: vec![],
+      cpu_count: 1,
+      debug_flag: false,
+      enable_testing_features: false,
+      locale: deno_core::v8::icu::get_language_tag(),
+      location: None,
+      no_color: false,
+      is_tty: false,
+      runtime_version: "x".to_string(),
+      ts_version: "x".to_string(),
+      unstable: false,
+      user_agent: "embed_wsi".to_string(),
+      inspect: false,
+      gpu_backend: None,
+    },
+    extensions: vec![],
+    startup_snapshot: None,
+    unsafely_ignore_certificate_errors: None,
+    root_cert_store: None,
+    seed: None,
+    source_map_getter: None,
+    format_js_error_fn: None,
+    web_worker_preload_module_cb: web_worker_event_cb.clone(),
+    web_worker_pre_execute_module_cb: web_worker_event_cb,
+    create_web_worker_cb,
+    maybe_inspector_server: None,
+    should_break_on_first_statement: false,
+    should_wait_for_inspector_session: false,
+    module_loader,
+    npm_resolver: None,
+    get_error_class_fn: Some(&get_error_class_name),
+    cache_storage_dir: None,
+    origin_storage_dir: None,
+    blob_store: BlobStore::default(),
+    broadcast_channel: InMemoryBroadcastChannel::default(),
+    shared_array_buffer_store: None,
+    compiled_wasm_module_store: None,
+    stdio: Default::default(),
+    wsi_event_loop_proxy: Some(wsi_event_loop_proxy),
+    wsi_options: Default::default(),
+  };
+
+  let js_path =
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/embed_wsi.js");
+  let main_module = deno_core::resolve_path(
+    &js_path.to_string_lossy(),
+    &std::env::current_dir().context("Unable to get CWD")?,
+  )?;
+  let permissions = PermissionsContainer::allow_all();
+
+  let mut worker = MainWorker::bootstrap_from_options(
+    main_module.clone(),
+    permissions,
+    options,
+  );
+  worker.execute_main_module(&main_module).await?;
+  worker.run_event_loop(false).await?;
+  Ok(())
+}
+
+fn main() {
+  // The host creates the event loop on the main thread.
+  let event_loop = EventLoop::new();
+  let (mut driver, connection) = event_loop::connect(&event_loop);
+
+  // The runtime runs on its own thread.
+  thread::spawn(move || {
+    let rt = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    let local = tokio::task::LocalSet::new();
+    if let Err(err) = local.block_on(&rt, run_js(connection.into_proxy())) {
+      eprintln!("{err:?}");
+    }
+  });
+
+  // The host feeds every event to the runtime alongside its own handling, and
+  // exits once the runtime has shut down or the app agreed to quit.
+  let mut running = driver.start(&event_loop);
+  event_loop.run(move |event, window_target, control_flow| {
+    if running {
+      running = driver.handle_event(event, window_target);
+    }
+    if running {
+      control_flow.set_wait();
+    } else {
+      control_flow.set_exit();
+    }
+  });
+}
@@ -43,6 +43,7 @@ async fn main() -> Result<(), AnyError> {
       unstable: false,
       user_agent: "hello_runtime".to_string(),
       inspect: false,
+      gpu_backend: None,
     },
     extensions: vec![],
     startup_snapshot: None,
@@ -68,6 +69,7 @@ async fn main() -> Result<(), AnyError> {
     compiled_wasm_module_store: None,
     stdio: Default::default(),
     wsi_event_loop_proxy: None,
+    wsi_options: Default::default(),
   };
 
   let js_path =
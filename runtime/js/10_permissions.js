@@ -32,7 +32,21 @@ const illegalConstructorKey = Symbol("illegalConstructorKey");
  * @property {PermissionStatus} status
  */
 
-/** @type {ReadonlyArray<"read" | "write" | "net" | "env" | "sys" | "run" | "ffi" | "hrtime">} */
+/**
+ * @type {ReadonlyArray<
+ *   | "read"
+ *   | "write"
+ *   | "net"
+ *   | "env"
+ *   | "sys"
+ *   | "run"
+ *   | "ffi"
+ *   | "hrtime"
+ *   | "wsi"
+ *   | "wsi-devices"
+ *   | "wsi-global-input"
+ * >}
+ */
 const permissionNames = [
   "read",
   "write",
@@ -42,6 +56,9 @@ const permissionNames = [
   "run",
   "ffi",
   "hrtime",
+  "wsi",
+  "wsi-devices",
+  "wsi-global-input",
 ];
 
 /**
@@ -266,7 +283,15 @@ function serializePermissions(permissions) {
       }
     }
     for (
-      const key of new SafeArrayIterator(["env", "hrtime", "net", "sys"])
+      const key of new SafeArrayIterator([
+        "env",
+        "hrtime",
+        "net",
+        "sys",
+        "wsi",
+        "wsi-devices",
+        "wsi-global-input",
+      ])
     ) {
       if (ArrayIsArray(permissions[key])) {
         serializedPermissions[key] = ArrayPrototypeSlice(permissions[key]);
@@ -173,7 +173,9 @@ const denoNsUnstable = {
   upgradeHttpRaw: flash.upgradeHttpRaw,
   wsi: wsi.wsi,
   WSI: wsi.WSI,
+  WSIAssets: wsi.WSIAssets,
   WSIModifierKey: wsi.WSIModifierKey,
+  WSIShaderWatch: wsi.WSIShaderWatch,
   WSIWindow: wsi.WSIWindow,
   WSIWindowButton: wsi.WSIWindowButton,
 };
@@ -179,6 +179,7 @@ const unstableWindowOrWorkerGlobalScope = {
   GPUValidationError: util.nonEnumerable(webgpu.GPUValidationError),
   GPUSurface: util.nonEnumerable(webgpu.GPUSurface),
   GPUSurfaceTexture: util.nonEnumerable(webgpu.GPUSurfaceTexture),
+  GPUProfiler: util.nonEnumerable(webgpu.GPUProfiler),
 };
 
 class Navigator {
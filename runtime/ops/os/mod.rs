@@ -155,6 +155,7 @@ fn op_set_exit_code(state: &mut OpState, code: i32) {
 #[op]
 fn op_exit(state: &mut OpState) {
   let code = state.borrow::<ExitCode>().get();
+  deno_wsi::cleanup::restore_desktop();
   std::process::exit(code)
 }
 
@@ -56,6 +56,9 @@ pub fn op_query_permission(
     "run" => permissions.run.query(args.command.as_deref()),
     "ffi" => permissions.ffi.query(args.path.as_deref().map(Path::new)),
     "hrtime" => permissions.hrtime.query(),
+    "wsi" => permissions.wsi.query(),
+    "wsi-devices" => permissions.wsi_devices.query(),
+    "wsi-global-input" => permissions.wsi_global_input.query(),
     n => {
       return Err(custom_error(
         "ReferenceError",
@@ -90,6 +93,9 @@ pub fn op_revoke_permission(
     "run" => permissions.run.revoke(args.command.as_deref()),
     "ffi" => permissions.ffi.revoke(args.path.as_deref().map(Path::new)),
     "hrtime" => permissions.hrtime.revoke(),
+    "wsi" => permissions.wsi.revoke(),
+    "wsi-devices" => permissions.wsi_devices.revoke(),
+    "wsi-global-input" => permissions.wsi_global_input.revoke(),
     n => {
       return Err(custom_error(
         "ReferenceError",
@@ -124,6 +130,9 @@ pub fn op_request_permission(
     "run" => permissions.run.request(args.command.as_deref()),
     "ffi" => permissions.ffi.request(args.path.as_deref().map(Path::new)),
     "hrtime" => permissions.hrtime.request(),
+    "wsi" => permissions.wsi.request(),
+    "wsi-devices" => permissions.wsi_devices.request(),
+    "wsi-global-input" => permissions.wsi_global_input.request(),
     n => {
       return Err(custom_error(
         "ReferenceError",
@@ -1512,6 +1512,9 @@ pub struct Permissions {
   pub run: UnaryPermission<RunDescriptor>,
   pub ffi: UnaryPermission<FfiDescriptor>,
   pub hrtime: UnitPermission,
+  pub wsi: UnitPermission,
+  pub wsi_devices: UnitPermission,
+  pub wsi_global_input: UnitPermission,
 }
 
 impl Default for Permissions {
@@ -1525,6 +1528,9 @@ impl Default for Permissions {
       run: Permissions::new_run(&None, false).unwrap(),
       ffi: Permissions::new_ffi(&None, false).unwrap(),
       hrtime: Permissions::new_hrtime(false),
+      wsi: Permissions::new_wsi(false, false),
+      wsi_devices: Permissions::new_wsi_devices(false, false),
+      wsi_global_input: Permissions::new_wsi_global_input(false, false),
     }
   }
 }
@@ -1539,6 +1545,9 @@ pub struct PermissionsOptions {
   pub allow_run: Option<Vec<String>>,
   pub allow_sys: Option<Vec<String>>,
   pub allow_write: Option<Vec<PathBuf>>,
+  pub allow_wsi: bool,
+  pub allow_wsi_devices: bool,
+  pub allow_wsi_global_input: bool,
   pub prompt: bool,
 }
 
@@ -1682,6 +1691,28 @@ impl Permissions {
     )
   }
 
+  pub fn new_wsi(state: bool, prompt: bool) -> UnitPermission {
+    unit_permission_from_flag_bool(state, "wsi", "the window system", prompt)
+  }
+
+  pub fn new_wsi_devices(state: bool, prompt: bool) -> UnitPermission {
+    unit_permission_from_flag_bool(
+      state,
+      "wsi-devices",
+      "global input device events",
+      prompt,
+    )
+  }
+
+  pub fn new_wsi_global_input(state: bool, prompt: bool) -> UnitPermission {
+    unit_permission_from_flag_bool(
+      state,
+      "wsi-global-input",
+      "the desktop's keyboard and mouse input",
+      prompt,
+    )
+  }
+
   pub fn from_options(opts: &PermissionsOptions) -> Result<Self, AnyError> {
     Ok(Self {
       read: Permissions::new_read(&opts.allow_read, opts.prompt)?,
@@ -1692,6 +1723,15 @@ impl Permissions {
       run: Permissions::new_run(&opts.allow_run, opts.prompt)?,
       ffi: Permissions::new_ffi(&opts.allow_ffi, opts.prompt)?,
       hrtime: Permissions::new_hrtime(opts.allow_hrtime),
+      wsi: Permissions::new_wsi(opts.allow_wsi, opts.prompt),
+      wsi_devices: Permissions::new_wsi_devices(
+        opts.allow_wsi_devices,
+        opts.prompt,
+      ),
+      wsi_global_input: Permissions::new_wsi_global_input(
+        opts.allow_wsi_global_input,
+        opts.prompt,
+      ),
     })
   }
 
@@ -1705,6 +1745,9 @@ impl Permissions {
       run: Permissions::new_run(&Some(vec![]), false).unwrap(),
       ffi: Permissions::new_ffi(&Some(vec![]), false).unwrap(),
       hrtime: Permissions::new_hrtime(true),
+      wsi: Permissions::new_wsi(true, false),
+      wsi_devices: Permissions::new_wsi_devices(true, false),
+      wsi_global_input: Permissions::new_wsi_global_input(true, false),
     }
   }
 
@@ -1960,6 +2003,66 @@ impl deno_napi::NapiPermissions for PermissionsContainer {
   }
 }
 
+impl deno_webgpu::WebGpuPermissions for PermissionsContainer {
+  #[inline(always)]
+  fn check_write(
+    &mut self,
+    path: &Path,
+    api_name: &str,
+  ) -> Result<(), AnyError> {
+    self.0.lock().write.check(path, Some(api_name))
+  }
+}
+
+impl deno_wsi::WsiPermissions for PermissionsContainer {
+  #[inline(always)]
+  fn check_read(
+    &mut self,
+    path: &Path,
+    api_name: &str,
+  ) -> Result<(), AnyError> {
+    self.0.lock().read.check(path, Some(api_name))
+  }
+
+  #[inline(always)]
+  fn check_write_all(&mut self, api_name: &str) -> Result<(), AnyError> {
+    self.0.lock().write.check_all(Some(api_name))
+  }
+
+  #[inline(always)]
+  fn check_run_all(&mut self, api_name: &str) -> Result<(), AnyError> {
+    self.0.lock().run.check_all(Some(api_name))
+  }
+
+  #[inline(always)]
+  fn check_wsi(&mut self, _api_name: &str) -> Result<(), AnyError> {
+    self.0.lock().wsi.check()
+  }
+
+  #[inline(always)]
+  fn check_wsi_devices(&mut self, _api_name: &str) -> Result<(), AnyError> {
+    self.0.lock().wsi_devices.check()
+  }
+
+  #[inline(always)]
+  fn allows_wsi_devices(&self) -> bool {
+    self.0.lock().wsi_devices.query() == PermissionState::Granted
+  }
+
+  #[inline(always)]
+  fn check_wsi_global_input(
+    &mut self,
+    _api_name: &str,
+  ) -> Result<(), AnyError> {
+    self.0.lock().wsi_global_input.check()
+  }
+
+  #[inline(always)]
+  fn allows_wsi_global_input(&self) -> bool {
+    self.0.lock().wsi_global_input.query() == PermissionState::Granted
+  }
+}
+
 impl deno_ffi::FfiPermissions for PermissionsContainer {
   #[inline(always)]
   fn check(&mut self, path: Option<&Path>) -> Result<(), AnyError> {
@@ -2193,6 +2296,9 @@ pub struct ChildPermissionsArg {
   run: ChildUnaryPermissionArg,
   sys: ChildUnaryPermissionArg,
   write: ChildUnaryPermissionArg,
+  wsi: ChildUnitPermissionArg,
+  wsi_devices: ChildUnitPermissionArg,
+  wsi_global_input: ChildUnitPermissionArg,
 }
 
 impl ChildPermissionsArg {
@@ -2206,6 +2312,9 @@ impl ChildPermissionsArg {
       run: ChildUnaryPermissionArg::Inherit,
       sys: ChildUnaryPermissionArg::Inherit,
       write: ChildUnaryPermissionArg::Inherit,
+      wsi: ChildUnitPermissionArg::Inherit,
+      wsi_devices: ChildUnitPermissionArg::Inherit,
+      wsi_global_input: ChildUnitPermissionArg::Inherit,
     }
   }
 
@@ -2219,6 +2328,9 @@ impl ChildPermissionsArg {
       run: ChildUnaryPermissionArg::NotGranted,
       sys: ChildUnaryPermissionArg::NotGranted,
       write: ChildUnaryPermissionArg::NotGranted,
+      wsi: ChildUnitPermissionArg::NotGranted,
+      wsi_devices: ChildUnitPermissionArg::NotGranted,
+      wsi_global_input: ChildUnitPermissionArg::NotGranted,
     }
   }
 }
@@ -2304,6 +2416,23 @@ impl<'de> Deserialize<'de> for ChildPermissionsArg {
             child_permissions_arg.write = arg.map_err(|e| {
               de::Error::custom(format!("(deno.permissions.write) {e}"))
             })?;
+          } else if key == "wsi" {
+            let arg = serde_json::from_value::<ChildUnitPermissionArg>(value);
+            child_permissions_arg.wsi = arg.map_err(|e| {
+              de::Error::custom(format!("(deno.permissions.wsi) {e}"))
+            })?;
+          } else if key == "wsi-devices" {
+            let arg = serde_json::from_value::<ChildUnitPermissionArg>(value);
+            child_permissions_arg.wsi_devices = arg.map_err(|e| {
+              de::Error::custom(format!("(deno.permissions.wsi-devices) {e}"))
+            })?;
+          } else if key == "wsi-global-input" {
+            let arg = serde_json::from_value::<ChildUnitPermissionArg>(value);
+            child_permissions_arg.wsi_global_input = arg.map_err(|e| {
+              de::Error::custom(format!(
+                "(deno.permissions.wsi-global-input) {e}"
+              ))
+            })?;
           } else {
             return Err(de::Error::custom("unknown permission name"));
           }
@@ -2548,6 +2677,54 @@ pub fn create_child_permissions(
     worker_perms.write.global_state = PermissionState::Denied;
   }
   worker_perms.write.prompt = main_perms.write.prompt;
+  match child_permissions_arg.wsi {
+    ChildUnitPermissionArg::Inherit => {
+      worker_perms.wsi = main_perms.wsi.clone();
+    }
+    ChildUnitPermissionArg::Granted => {
+      if main_perms.wsi.check().is_err() {
+        return Err(escalation_error());
+      }
+      worker_perms.wsi.state = PermissionState::Granted;
+    }
+    ChildUnitPermissionArg::NotGranted => {}
+  }
+  if main_perms.wsi.state == PermissionState::Denied {
+    worker_perms.wsi.state = PermissionState::Denied;
+  }
+  worker_perms.wsi.prompt = main_perms.wsi.prompt;
+  match child_permissions_arg.wsi_devices {
+    ChildUnitPermissionArg::Inherit => {
+      worker_perms.wsi_devices = main_perms.wsi_devices.clone();
+    }
+    ChildUnitPermissionArg::Granted => {
+      if main_perms.wsi_devices.check().is_err() {
+        return Err(escalation_error());
+      }
+      worker_perms.wsi_devices.state = PermissionState::Granted;
+    }
+    ChildUnitPermissionArg::NotGranted => {}
+  }
+  if main_perms.wsi_devices.state == PermissionState::Denied {
+    worker_perms.wsi_devices.state = PermissionState::Denied;
+  }
+  worker_perms.wsi_devices.prompt = main_perms.wsi_devices.prompt;
+  match child_permissions_arg.wsi_global_input {
+    ChildUnitPermissionArg::Inherit => {
+      worker_perms.wsi_global_input = main_perms.wsi_global_input.clone();
+    }
+    ChildUnitPermissionArg::Granted => {
+      if main_perms.wsi_global_input.check().is_err() {
+        return Err(escalation_error());
+      }
+      worker_perms.wsi_global_input.state = PermissionState::Granted;
+    }
+    ChildUnitPermissionArg::NotGranted => {}
+  }
+  if main_perms.wsi_global_input.state == PermissionState::Denied {
+    worker_perms.wsi_global_input.state = PermissionState::Denied;
+  }
+  worker_perms.wsi_global_input.prompt = main_perms.wsi_global_input.prompt;
   Ok(worker_perms)
 }
 
@@ -2951,6 +3128,18 @@ mod tests {
         state: PermissionState::Prompt,
         ..Permissions::new_hrtime(false)
       },
+      wsi: UnitPermission {
+        state: PermissionState::Prompt,
+        ..Permissions::new_wsi(false, false)
+      },
+      wsi_devices: UnitPermission {
+        state: PermissionState::Prompt,
+        ..Permissions::new_wsi_devices(false, false)
+      },
+      wsi_global_input: UnitPermission {
+        state: PermissionState::Prompt,
+        ..Permissions::new_wsi_global_input(false, false)
+      },
     };
     #[rustfmt::skip]
     {
@@ -2987,6 +3176,12 @@ mod tests {
       assert_eq!(perms2.run.query(Some("deno")), PermissionState::Granted);
       assert_eq!(perms1.hrtime.query(), PermissionState::Granted);
       assert_eq!(perms2.hrtime.query(), PermissionState::Prompt);
+      assert_eq!(perms1.wsi.query(), PermissionState::Granted);
+      assert_eq!(perms2.wsi.query(), PermissionState::Prompt);
+      assert_eq!(perms1.wsi_devices.query(), PermissionState::Granted);
+      assert_eq!(perms2.wsi_devices.query(), PermissionState::Prompt);
+      assert_eq!(perms1.wsi_global_input.query(), PermissionState::Granted);
+      assert_eq!(perms2.wsi_global_input.query(), PermissionState::Prompt);
     };
   }
 
@@ -3035,6 +3230,18 @@ mod tests {
       assert_eq!(perms.hrtime.request(), PermissionState::Denied);
       prompt_value.set(true);
       assert_eq!(perms.hrtime.request(), PermissionState::Denied);
+      prompt_value.set(true);
+      assert_eq!(perms.wsi.request(), PermissionState::Granted);
+      prompt_value.set(false);
+      assert_eq!(perms.wsi.request(), PermissionState::Granted);
+      prompt_value.set(false);
+      assert_eq!(perms.wsi_devices.request(), PermissionState::Denied);
+      prompt_value.set(true);
+      assert_eq!(perms.wsi_devices.request(), PermissionState::Denied);
+      prompt_value.set(false);
+      assert_eq!(perms.wsi_global_input.request(), PermissionState::Denied);
+      prompt_value.set(true);
+      assert_eq!(perms.wsi_global_input.request(), PermissionState::Denied);
     };
   }
 
@@ -3090,6 +3297,18 @@ mod tests {
         state: PermissionState::Denied,
         ..Permissions::new_hrtime(false)
       },
+      wsi: UnitPermission {
+        state: PermissionState::Prompt,
+        ..Permissions::new_wsi(false, false)
+      },
+      wsi_devices: UnitPermission {
+        state: PermissionState::Prompt,
+        ..Permissions::new_wsi_devices(false, false)
+      },
+      wsi_global_input: UnitPermission {
+        state: PermissionState::Prompt,
+        ..Permissions::new_wsi_global_input(false, false)
+      },
     };
     #[rustfmt::skip]
     {
@@ -3109,6 +3328,9 @@ mod tests {
       assert_eq!(perms.env.revoke(Some("hostname")), PermissionState::Prompt);
       assert_eq!(perms.run.revoke(Some("deno")), PermissionState::Prompt);
       assert_eq!(perms.hrtime.revoke(), PermissionState::Denied);
+      assert_eq!(perms.wsi.revoke(), PermissionState::Prompt);
+      assert_eq!(perms.wsi_devices.revoke(), PermissionState::Prompt);
+      assert_eq!(perms.wsi_global_input.revoke(), PermissionState::Prompt);
     };
   }
 
@@ -3124,6 +3346,9 @@ mod tests {
       run: Permissions::new_run(&None, true).unwrap(),
       ffi: Permissions::new_ffi(&None, true).unwrap(),
       hrtime: Permissions::new_hrtime(false),
+      wsi: Permissions::new_wsi(false, true),
+      wsi_devices: Permissions::new_wsi_devices(false, true),
+      wsi_global_input: Permissions::new_wsi_global_input(false, true),
     };
 
     let prompt_value = PERMISSION_PROMPT_STUB_VALUE_SETTER.lock();
@@ -3174,6 +3399,21 @@ mod tests {
     assert!(perms.env.check("osRelease").is_err());
 
     assert!(perms.hrtime.check().is_err());
+
+    prompt_value.set(true);
+    assert!(perms.wsi.check().is_ok());
+    prompt_value.set(false);
+    assert!(perms.wsi.check().is_ok());
+
+    prompt_value.set(true);
+    assert!(perms.wsi_devices.check().is_ok());
+    prompt_value.set(false);
+    assert!(perms.wsi_devices.check().is_ok());
+
+    prompt_value.set(true);
+    assert!(perms.wsi_global_input.check().is_ok());
+    prompt_value.set(false);
+    assert!(perms.wsi_global_input.check().is_ok());
   }
 
   #[test]
@@ -3188,6 +3428,9 @@ mod tests {
       run: Permissions::new_run(&None, true).unwrap(),
       ffi: Permissions::new_ffi(&None, true).unwrap(),
       hrtime: Permissions::new_hrtime(false),
+      wsi: Permissions::new_wsi(false, true),
+      wsi_devices: Permissions::new_wsi_devices(false, true),
+      wsi_global_input: Permissions::new_wsi_global_input(false, true),
     };
 
     let prompt_value = PERMISSION_PROMPT_STUB_VALUE_SETTER.lock();
@@ -3254,6 +3497,21 @@ mod tests {
     assert!(perms.hrtime.check().is_err());
     prompt_value.set(true);
     assert!(perms.hrtime.check().is_err());
+
+    prompt_value.set(false);
+    assert!(perms.wsi.check().is_err());
+    prompt_value.set(true);
+    assert!(perms.wsi.check().is_err());
+
+    prompt_value.set(false);
+    assert!(perms.wsi_devices.check().is_err());
+    prompt_value.set(true);
+    assert!(perms.wsi_devices.check().is_err());
+
+    prompt_value.set(false);
+    assert!(perms.wsi_global_input.check().is_err());
+    prompt_value.set(true);
+    assert!(perms.wsi_global_input.check().is_err());
   }
 
   #[test]
@@ -3290,6 +3548,9 @@ mod tests {
         run: ChildUnaryPermissionArg::Inherit,
         sys: ChildUnaryPermissionArg::Inherit,
         write: ChildUnaryPermissionArg::Inherit,
+        wsi: ChildUnitPermissionArg::Inherit,
+        wsi_devices: ChildUnitPermissionArg::Inherit,
+        wsi_global_input: ChildUnitPermissionArg::Inherit,
       }
     );
     assert_eq!(
@@ -3303,6 +3564,9 @@ mod tests {
         run: ChildUnaryPermissionArg::NotGranted,
         sys: ChildUnaryPermissionArg::NotGranted,
         write: ChildUnaryPermissionArg::NotGranted,
+        wsi: ChildUnitPermissionArg::NotGranted,
+        wsi_devices: ChildUnitPermissionArg::NotGranted,
+        wsi_global_input: ChildUnitPermissionArg::NotGranted,
       }
     );
     assert_eq!(
@@ -3337,6 +3601,36 @@ mod tests {
         ..ChildPermissionsArg::none()
       }
     );
+    assert_eq!(
+      serde_json::from_value::<ChildPermissionsArg>(json!({
+        "wsi": true,
+      }))
+      .unwrap(),
+      ChildPermissionsArg {
+        wsi: ChildUnitPermissionArg::Granted,
+        ..ChildPermissionsArg::none()
+      }
+    );
+    assert_eq!(
+      serde_json::from_value::<ChildPermissionsArg>(json!({
+        "wsi-devices": true,
+      }))
+      .unwrap(),
+      ChildPermissionsArg {
+        wsi_devices: ChildUnitPermissionArg::Granted,
+        ..ChildPermissionsArg::none()
+      }
+    );
+    assert_eq!(
+      serde_json::from_value::<ChildPermissionsArg>(json!({
+        "wsi-global-input": true,
+      }))
+      .unwrap(),
+      ChildPermissionsArg {
+        wsi_global_input: ChildUnitPermissionArg::Granted,
+        ..ChildPermissionsArg::none()
+      }
+    );
     assert_eq!(
       serde_json::from_value::<ChildPermissionsArg>(json!({
         "hrtime": false,
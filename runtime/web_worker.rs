@@ -414,7 +414,10 @@ impl WebWorker {
         unstable,
       ),
       deno_crypto::init_ops(options.seed),
-      deno_webgpu::init_ops(unstable),
+      deno_webgpu::init_ops::<PermissionsContainer>(
+        unstable,
+        options.bootstrap.gpu_backend.clone(),
+      ),
       // ffi
       deno_ffi::init_ops::<PermissionsContainer>(unstable),
       // Runtime ops that are always initialized for WebWorkers
@@ -449,7 +452,7 @@ impl WebWorker {
       ops::http::init(),
       // Permissions ext (worker specific state)
       perm_ext,
-      deno_wsi::init_ops(None),
+      deno_wsi::init_ops::<PermissionsContainer>(None, Default::default()),
     ];
 
     // Append exts
@@ -33,7 +33,9 @@ use deno_io::Stdio;
 use deno_node::RequireNpmResolver;
 use deno_tls::rustls::RootCertStore;
 use deno_web::BlobStore;
+use deno_wsi::event_loop::WsiEventLoopPause;
 use deno_wsi::event_loop::WsiEventLoopProxy;
+use deno_wsi::WsiOptions;
 use log::debug;
 
 use crate::inspector_server::InspectorServer;
@@ -68,6 +70,9 @@ pub struct MainWorker {
   should_wait_for_inspector_session: bool,
   exit_code: ExitCode,
   bootstrap_fn_global: Option<v8::Global<v8::Function>>,
+  // Pauses the WSI event loop while a debugger has the runtime paused, until
+  // it's handed to the inspector.
+  wsi_event_loop_pause: Option<WsiEventLoopPause>,
 }
 
 pub struct WorkerOptions {
@@ -134,6 +139,7 @@ pub struct WorkerOptions {
   pub compiled_wasm_module_store: Option<CompiledWasmModuleStore>,
   pub stdio: Stdio,
   pub wsi_event_loop_proxy: Option<Rc<WsiEventLoopProxy>>,
+  pub wsi_options: WsiOptions,
 }
 
 impl Default for WorkerOptions {
@@ -170,6 +176,7 @@ impl Default for WorkerOptions {
       bootstrap: Default::default(),
       stdio: Default::default(),
       wsi_event_loop_proxy: None,
+      wsi_options: Default::default(),
     }
   }
 }
@@ -202,6 +209,11 @@ impl MainWorker {
       })
       .build();
     let exit_code = ExitCode(Arc::new(AtomicI32::new(0)));
+    let wsi_event_loop_pause = options
+      .wsi_event_loop_proxy
+      .as_ref()
+      .filter(|_| options.maybe_inspector_server.is_some())
+      .map(|proxy| proxy.pause_handle());
     let create_cache = options.cache_storage_dir.map(|storage_dir| {
       let create_cache_fn = move || SqliteBackedCache::new(storage_dir.clone());
       CreateCache(Arc::new(create_cache_fn))
@@ -237,7 +249,10 @@ impl MainWorker {
         unstable,
       ),
       deno_crypto::init_ops(options.seed),
-      deno_webgpu::init_ops(unstable),
+      deno_webgpu::init_ops::<PermissionsContainer>(
+        unstable,
+        options.bootstrap.gpu_backend.clone(),
+      ),
       // ffi
       deno_ffi::init_ops::<PermissionsContainer>(unstable),
       // Runtime ops
@@ -268,7 +283,10 @@ impl MainWorker {
       deno_http::init_ops(),
       deno_flash::init_ops::<PermissionsContainer>(unstable),
       ops::http::init(),
-      deno_wsi::init_ops(options.wsi_event_loop_proxy),
+      deno_wsi::init_ops::<PermissionsContainer>(
+        options.wsi_event_loop_proxy,
+        options.wsi_options,
+      ),
     ];
 
     extensions.push(perm_ext);
@@ -339,6 +357,7 @@ impl MainWorker {
         .should_wait_for_inspector_session,
       exit_code,
       bootstrap_fn_global: Some(bootstrap_fn_global),
+      wsi_event_loop_pause,
     }
   }
 
@@ -431,14 +450,31 @@ impl MainWorker {
   }
 
   fn wait_for_inspector_session(&mut self) {
+    // Windows keep responding while the runtime waits for a debugger. With
+    // `should_break_on_first_statement`, the event loop stays paused until
+    // the debugger resumes the runtime.
+    let wsi_event_loop_pause = self.wsi_event_loop_pause.take();
     if self.should_break_on_first_statement {
+      if let Some(pause) = &wsi_event_loop_pause {
+        pause.pause();
+      }
       self
         .js_runtime
         .inspector()
         .borrow_mut()
         .wait_for_session_and_break_on_next_statement();
     } else if self.should_wait_for_inspector_session {
+      if let Some(pause) = &wsi_event_loop_pause {
+        pause.pause();
+      }
       self.js_runtime.inspector().borrow_mut().wait_for_session();
+      if let Some(pause) = &wsi_event_loop_pause {
+        pause.resume();
+      }
+    }
+    if let Some(pause) = wsi_event_loop_pause {
+      let inspector = self.js_runtime.inspector();
+      deno_wsi::debugger::follow_debugger_pauses(&inspector.borrow(), pause);
     }
   }
 
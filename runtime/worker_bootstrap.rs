@@ -28,6 +28,8 @@ pub struct BootstrapOptions {
   pub unstable: bool,
   pub user_agent: String,
   pub inspect: bool,
+  /// Preferred WebGPU backend, e.g. "vulkan". Overrides DENO_WEBGPU_BACKEND.
+  pub gpu_backend: Option<String>,
 }
 
 impl Default for BootstrapOptions {
@@ -53,6 +55,7 @@ impl Default for BootstrapOptions {
       unstable: Default::default(),
       inspect: Default::default(),
       args: Default::default(),
+      gpu_backend: Default::default(),
     }
   }
 }